@@ -0,0 +1,97 @@
+//! Derive macros for Plonky2. They are re-exported by `plonky2`, which should be used instead of
+//! this crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Implements `plonky2::plonk::public_inputs::CircuitPublicInputs` for a struct whose fields are
+/// `PublicInputTarget`s, such as `Target`, `HashOutTarget` or `[Target; N]`. Each field is
+/// registered as a group of public inputs named after it.
+///
+/// Also defines a `<Struct>Values<F>` struct, with the same fields holding their values, which
+/// `CircuitPublicInputs::read` returns.
+#[proc_macro_derive(CircuitPublicInputs)]
+pub fn derive_circuit_public_inputs(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    circuit_public_inputs(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn circuit_public_inputs(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "CircuitPublicInputs can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "CircuitPublicInputs can only be derived for structs",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "CircuitPublicInputs can't be derived for generic structs",
+        ));
+    }
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let values_ident = format_ident!("{}Values", ident);
+    let names = fields
+        .iter()
+        .map(|field| field.ident.clone().expect("Named fields have identifiers"))
+        .collect::<Vec<_>>();
+    let name_strs = names
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<_>>();
+    let types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let field_vis = fields.iter().map(|field| &field.vis);
+    let values_doc = format!("The values of the public inputs of [`{}`].", ident);
+
+    let public_inputs = quote!(::plonky2::plonk::public_inputs);
+    Ok(quote! {
+        #[doc = #values_doc]
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        #vis struct #values_ident<F: ::plonky2::field::types::Field> {
+            #(#field_vis #names: <#types as #public_inputs::PublicInputTarget>::Value<F>,)*
+        }
+
+        impl #public_inputs::CircuitPublicInputs for #ident {
+            type Values<F: ::plonky2::field::types::Field> = #values_ident<F>;
+
+            fn register<
+                F: ::plonky2::hash::hash_types::RichField
+                    + ::plonky2::field::extension::Extendable<D>,
+                const D: usize,
+            >(
+                &self,
+                builder: &mut ::plonky2::plonk::circuit_builder::CircuitBuilder<F, D>,
+            ) {
+                #(#public_inputs::PublicInputTarget::register(&self.#names, builder, #name_strs);)*
+            }
+
+            fn read<F: ::plonky2::field::types::Field>(
+                public_inputs: &#public_inputs::PublicInputs<F>,
+            ) -> ::anyhow::Result<Self::Values<F>> {
+                ::core::result::Result::Ok(#values_ident {
+                    #(#names: <#types as #public_inputs::PublicInputTarget>::read(
+                        public_inputs,
+                        #name_strs,
+                    )?,)*
+                })
+            }
+        }
+    })
+}
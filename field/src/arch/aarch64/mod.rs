@@ -0,0 +1,2 @@
+#[cfg(all(feature = "neon", target_feature = "neon"))]
+pub mod neon_goldilocks_field;
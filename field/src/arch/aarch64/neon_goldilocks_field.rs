@@ -0,0 +1,497 @@
+use core::arch::aarch64::*;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use core::iter::{Product, Sum};
+use core::mem::transmute;
+use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::goldilocks_field::GoldilocksField;
+use crate::ops::Square;
+use crate::packed::PackedField;
+use crate::types::{Field, Field64};
+
+/// NEON Goldilocks Field
+///
+/// As with the x86 packings, we wrap `[GoldilocksField; 2]` rather than `uint64x2_t`, so that
+/// `NeonGoldilocksField` has the same alignment as `GoldilocksField`, and use the `new` and `get`
+/// methods to convert to and from `uint64x2_t`.
+#[derive(Copy, Clone)]
+#[repr(transparent)]
+pub struct NeonGoldilocksField(pub [GoldilocksField; 2]);
+
+impl NeonGoldilocksField {
+    #[inline]
+    fn new(x: uint64x2_t) -> Self {
+        unsafe { transmute(x) }
+    }
+    #[inline]
+    fn get(&self) -> uint64x2_t {
+        unsafe { transmute(*self) }
+    }
+}
+
+impl Add<Self> for NeonGoldilocksField {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        Self::new(unsafe { add(self.get(), rhs.get()) })
+    }
+}
+impl Add<GoldilocksField> for NeonGoldilocksField {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: GoldilocksField) -> Self {
+        self + Self::from(rhs)
+    }
+}
+impl Add<NeonGoldilocksField> for GoldilocksField {
+    type Output = NeonGoldilocksField;
+    #[inline]
+    fn add(self, rhs: Self::Output) -> Self::Output {
+        Self::Output::from(self) + rhs
+    }
+}
+impl AddAssign<Self> for NeonGoldilocksField {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+impl AddAssign<GoldilocksField> for NeonGoldilocksField {
+    #[inline]
+    fn add_assign(&mut self, rhs: GoldilocksField) {
+        *self = *self + rhs;
+    }
+}
+
+impl Debug for NeonGoldilocksField {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "({:?})", self.get())
+    }
+}
+
+impl Default for NeonGoldilocksField {
+    #[inline]
+    fn default() -> Self {
+        Self::ZEROS
+    }
+}
+
+impl Div<GoldilocksField> for NeonGoldilocksField {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    #[inline]
+    fn div(self, rhs: GoldilocksField) -> Self {
+        self * rhs.inverse()
+    }
+}
+impl DivAssign<GoldilocksField> for NeonGoldilocksField {
+    #[allow(clippy::suspicious_op_assign_impl)]
+    #[inline]
+    fn div_assign(&mut self, rhs: GoldilocksField) {
+        *self *= rhs.inverse();
+    }
+}
+
+impl From<GoldilocksField> for NeonGoldilocksField {
+    fn from(x: GoldilocksField) -> Self {
+        Self([x; 2])
+    }
+}
+
+impl Mul<Self> for NeonGoldilocksField {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(unsafe { mul(self.get(), rhs.get()) })
+    }
+}
+impl Mul<GoldilocksField> for NeonGoldilocksField {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: GoldilocksField) -> Self {
+        self * Self::from(rhs)
+    }
+}
+impl Mul<NeonGoldilocksField> for GoldilocksField {
+    type Output = NeonGoldilocksField;
+    #[inline]
+    fn mul(self, rhs: NeonGoldilocksField) -> Self::Output {
+        Self::Output::from(self) * rhs
+    }
+}
+impl MulAssign<Self> for NeonGoldilocksField {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+impl MulAssign<GoldilocksField> for NeonGoldilocksField {
+    #[inline]
+    fn mul_assign(&mut self, rhs: GoldilocksField) {
+        *self = *self * rhs;
+    }
+}
+
+impl Neg for NeonGoldilocksField {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self {
+        Self::new(unsafe { neg(self.get()) })
+    }
+}
+
+impl Product for NeonGoldilocksField {
+    #[inline]
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|x, y| x * y).unwrap_or(Self::ONES)
+    }
+}
+
+unsafe impl PackedField for NeonGoldilocksField {
+    const WIDTH: usize = 2;
+
+    type Scalar = GoldilocksField;
+
+    const ZEROS: Self = Self([GoldilocksField::ZERO; 2]);
+    const ONES: Self = Self([GoldilocksField::ONE; 2]);
+
+    #[inline]
+    fn from_slice(slice: &[Self::Scalar]) -> &Self {
+        assert_eq!(slice.len(), Self::WIDTH);
+        unsafe { &*slice.as_ptr().cast() }
+    }
+    #[inline]
+    fn from_slice_mut(slice: &mut [Self::Scalar]) -> &mut Self {
+        assert_eq!(slice.len(), Self::WIDTH);
+        unsafe { &mut *slice.as_mut_ptr().cast() }
+    }
+    #[inline]
+    fn as_slice(&self) -> &[Self::Scalar] {
+        &self.0[..]
+    }
+    #[inline]
+    fn as_slice_mut(&mut self) -> &mut [Self::Scalar] {
+        &mut self.0[..]
+    }
+
+    #[inline]
+    fn interleave(&self, other: Self, block_len: usize) -> (Self, Self) {
+        let (v0, v1) = (self.get(), other.get());
+        let (res0, res1) = match block_len {
+            1 => unsafe { interleave1(v0, v1) },
+            2 => (v0, v1),
+            _ => panic!("unsupported block_len"),
+        };
+        (Self::new(res0), Self::new(res1))
+    }
+}
+
+impl Square for NeonGoldilocksField {
+    #[inline]
+    fn square(&self) -> Self {
+        Self::new(unsafe { square(self.get()) })
+    }
+}
+
+impl Sub<Self> for NeonGoldilocksField {
+    type Output = Self;
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(unsafe { sub(self.get(), rhs.get()) })
+    }
+}
+impl Sub<GoldilocksField> for NeonGoldilocksField {
+    type Output = Self;
+    #[inline]
+    fn sub(self, rhs: GoldilocksField) -> Self {
+        self - Self::from(rhs)
+    }
+}
+impl Sub<NeonGoldilocksField> for GoldilocksField {
+    type Output = NeonGoldilocksField;
+    #[inline]
+    fn sub(self, rhs: NeonGoldilocksField) -> Self::Output {
+        Self::Output::from(self) - rhs
+    }
+}
+impl SubAssign<Self> for NeonGoldilocksField {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+impl SubAssign<GoldilocksField> for NeonGoldilocksField {
+    #[inline]
+    fn sub_assign(&mut self, rhs: GoldilocksField) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sum for NeonGoldilocksField {
+    #[inline]
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|x, y| x + y).unwrap_or(Self::ZEROS)
+    }
+}
+
+// Resources:
+// 1. Arm intrinsics reference: https://developer.arm.com/architectures/instruction-sets/intrinsics/
+// 2. Arm Neon programmer's guide for an introduction to the vector extension.
+
+// Preliminary knowledge:
+// 1. Unlike AVX2, NEON has unsigned 64-bit comparisons, so the shifting trick used by the x86
+//    packings is not needed. Comparisons return -1 (all bits 1) for true and 0 for false, which we
+//    use as masks to conditionally add or subtract EPSILON.
+//
+// 2. NEON has no 64-bit by 64-bit multiplication, not even a truncating one. We split each operand
+//    into 32-bit halves and use the widening 32-bit by 32-bit multiplications (vmull_u32 and the
+//    accumulating vmlal_u32) to compute the full 128-bit product.
+
+const FIELD_ORDER: uint64x2_t = unsafe { transmute([GoldilocksField::ORDER; 2]) };
+const EPSILON: uint64x2_t = unsafe { transmute([GoldilocksField::ORDER.wrapping_neg(); 2]) };
+
+/// Convert to canonical representation.
+#[inline]
+unsafe fn canonicalize(x: uint64x2_t) -> uint64x2_t {
+    // If x >= FIELD_ORDER then corresponding mask bits are all 1; otherwise all 0.
+    let mask = vcgeq_u64(x, FIELD_ORDER);
+    vsubq_u64(x, vandq_u64(mask, FIELD_ORDER))
+}
+
+/// Addition u64 + u64 -> u64. Assumes that x + y < 2^64 + FIELD_ORDER.
+#[inline]
+unsafe fn add_no_double_overflow_64_64(x: uint64x2_t, y: uint64x2_t) -> uint64x2_t {
+    let res_wrapped = vaddq_u64(x, y);
+    let mask = vcltq_u64(res_wrapped, y); // -1 if overflowed else 0.
+    vaddq_u64(res_wrapped, vandq_u64(mask, EPSILON))
+}
+
+/// Subtraction u64 - u64 -> u64. Assumes that y < FIELD_ORDER, so that a wrapped result is at
+/// least EPSILON.
+#[inline]
+unsafe fn sub_no_double_underflow_64_64(x: uint64x2_t, y: uint64x2_t) -> uint64x2_t {
+    let res_wrapped = vsubq_u64(x, y);
+    let mask = vcgtq_u64(y, x); // -1 if underflowed else 0.
+    vsubq_u64(res_wrapped, vandq_u64(mask, EPSILON))
+}
+
+#[inline]
+unsafe fn add(x: uint64x2_t, y: uint64x2_t) -> uint64x2_t {
+    add_no_double_overflow_64_64(x, canonicalize(y))
+}
+
+#[inline]
+unsafe fn sub(x: uint64x2_t, y: uint64x2_t) -> uint64x2_t {
+    sub_no_double_underflow_64_64(x, canonicalize(y))
+}
+
+#[inline]
+unsafe fn neg(y: uint64x2_t) -> uint64x2_t {
+    vsubq_u64(FIELD_ORDER, canonicalize(y))
+}
+
+/// Full 64-bit by 64-bit multiplication, returning the high and low 64 bits of the product.
+#[inline]
+unsafe fn mul64_64(x: uint64x2_t, y: uint64x2_t) -> (uint64x2_t, uint64x2_t) {
+    // Low and high 32 bits of each lane.
+    let x_lo = vmovn_u64(x);
+    let x_hi = vshrn_n_u64::<32>(x);
+    let y_lo = vmovn_u64(y);
+    let y_hi = vshrn_n_u64::<32>(y);
+
+    // Bignum multiplication. None of the additions below can overflow, since the product of two
+    // 32-bit numbers plus two more 32-bit numbers fits in 64 bits.
+    let mul_ll = vmull_u32(x_lo, y_lo);
+    let t0 = vmlal_u32(vshrq_n_u64::<32>(mul_ll), x_hi, y_lo);
+    let t1 = vmlal_u32(vandq_u64(t0, vdupq_n_u64(0xffffffff)), x_lo, y_hi);
+    let t2 = vmlal_u32(vshrq_n_u64::<32>(t0), x_hi, y_hi);
+    let res_hi = vsraq_n_u64::<32>(t2, t1);
+
+    // Combine the low 32 bits of mul_ll with the low 32 bits of t1, shifted into high position.
+    let res_lo = vsliq_n_u64::<32>(mul_ll, t1);
+
+    (res_hi, res_lo)
+}
+
+#[inline]
+unsafe fn reduce128(x: (uint64x2_t, uint64x2_t)) -> uint64x2_t {
+    let (hi0, lo0) = x;
+    let hi_hi0 = vshrq_n_u64::<32>(hi0);
+    let lo1 = sub_no_double_underflow_64_64(lo0, hi_hi0);
+    // hi_lo0 * EPSILON fits in 64 bits, since EPSILON = 2^32 - 1.
+    let t1 = vmull_u32(vmovn_u64(hi0), vdup_n_u32(0xffffffff));
+    add_no_double_overflow_64_64(lo1, t1)
+}
+
+/// Multiply two integers modulo FIELD_ORDER.
+#[inline]
+unsafe fn mul(x: uint64x2_t, y: uint64x2_t) -> uint64x2_t {
+    reduce128(mul64_64(x, y))
+}
+
+/// Square an integer modulo FIELD_ORDER.
+#[inline]
+unsafe fn square(x: uint64x2_t) -> uint64x2_t {
+    reduce128(mul64_64(x, x))
+}
+
+#[inline]
+unsafe fn interleave1(x: uint64x2_t, y: uint64x2_t) -> (uint64x2_t, uint64x2_t) {
+    (vzip1q_u64(x, y), vzip2q_u64(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arch::aarch64::neon_goldilocks_field::NeonGoldilocksField;
+    use crate::goldilocks_field::GoldilocksField;
+    use crate::ops::Square;
+    use crate::packed::PackedField;
+    use crate::types::Field;
+
+    fn test_vals_a() -> [GoldilocksField; 2] {
+        [
+            GoldilocksField::from_noncanonical_u64(14479013849828404771),
+            GoldilocksField::from_noncanonical_u64(18446744069414584322),
+        ]
+    }
+    fn test_vals_b() -> [GoldilocksField; 2] {
+        [
+            GoldilocksField::from_noncanonical_u64(17891926589593242302),
+            GoldilocksField::from_noncanonical_u64(11009798273260028228),
+        ]
+    }
+
+    #[test]
+    fn test_add() {
+        let a_arr = test_vals_a();
+        let b_arr = test_vals_b();
+
+        let packed_a = *NeonGoldilocksField::from_slice(&a_arr);
+        let packed_b = *NeonGoldilocksField::from_slice(&b_arr);
+        let packed_res = packed_a + packed_b;
+        let arr_res = packed_res.as_slice();
+
+        let expected = a_arr.iter().zip(b_arr).map(|(&a, b)| a + b);
+        for (exp, &res) in expected.zip(arr_res) {
+            assert_eq!(res, exp);
+        }
+    }
+
+    #[test]
+    fn test_mul() {
+        let a_arr = test_vals_a();
+        let b_arr = test_vals_b();
+
+        let packed_a = *NeonGoldilocksField::from_slice(&a_arr);
+        let packed_b = *NeonGoldilocksField::from_slice(&b_arr);
+        let packed_res = packed_a * packed_b;
+        let arr_res = packed_res.as_slice();
+
+        let expected = a_arr.iter().zip(b_arr).map(|(&a, b)| a * b);
+        for (exp, &res) in expected.zip(arr_res) {
+            assert_eq!(res, exp);
+        }
+    }
+
+    #[test]
+    fn test_square() {
+        let a_arr = test_vals_a();
+
+        let packed_a = *NeonGoldilocksField::from_slice(&a_arr);
+        let packed_res = packed_a.square();
+        let arr_res = packed_res.as_slice();
+
+        let expected = a_arr.iter().map(|&a| a.square());
+        for (exp, &res) in expected.zip(arr_res) {
+            assert_eq!(res, exp);
+        }
+    }
+
+    #[test]
+    fn test_neg() {
+        let a_arr = test_vals_a();
+
+        let packed_a = *NeonGoldilocksField::from_slice(&a_arr);
+        let packed_res = -packed_a;
+        let arr_res = packed_res.as_slice();
+
+        let expected = a_arr.iter().map(|&a| -a);
+        for (exp, &res) in expected.zip(arr_res) {
+            assert_eq!(res, exp);
+        }
+    }
+
+    #[test]
+    fn test_sub() {
+        let a_arr = test_vals_a();
+        let b_arr = test_vals_b();
+
+        let packed_a = *NeonGoldilocksField::from_slice(&a_arr);
+        let packed_b = *NeonGoldilocksField::from_slice(&b_arr);
+        let packed_res = packed_a - packed_b;
+        let arr_res = packed_res.as_slice();
+
+        let expected = a_arr.iter().zip(b_arr).map(|(&a, b)| a - b);
+        for (exp, &res) in expected.zip(arr_res) {
+            assert_eq!(res, exp);
+        }
+    }
+
+    #[test]
+    fn test_interleave_is_involution() {
+        let a_arr = test_vals_a();
+        let b_arr = test_vals_b();
+
+        let packed_a = *NeonGoldilocksField::from_slice(&a_arr);
+        let packed_b = *NeonGoldilocksField::from_slice(&b_arr);
+        {
+            // Interleave, then deinterleave.
+            let (x, y) = packed_a.interleave(packed_b, 1);
+            let (res_a, res_b) = x.interleave(y, 1);
+            assert_eq!(res_a.as_slice(), a_arr);
+            assert_eq!(res_b.as_slice(), b_arr);
+        }
+        {
+            let (x, y) = packed_a.interleave(packed_b, 2);
+            let (res_a, res_b) = x.interleave(y, 2);
+            assert_eq!(res_a.as_slice(), a_arr);
+            assert_eq!(res_b.as_slice(), b_arr);
+        }
+    }
+
+    #[allow(clippy::zero_prefixed_literal)]
+    #[test]
+    fn test_interleave() {
+        let in_a: [GoldilocksField; 2] = [
+            GoldilocksField::from_noncanonical_u64(00),
+            GoldilocksField::from_noncanonical_u64(01),
+        ];
+        let in_b: [GoldilocksField; 2] = [
+            GoldilocksField::from_noncanonical_u64(10),
+            GoldilocksField::from_noncanonical_u64(11),
+        ];
+        let int1_a: [GoldilocksField; 2] = [
+            GoldilocksField::from_noncanonical_u64(00),
+            GoldilocksField::from_noncanonical_u64(10),
+        ];
+        let int1_b: [GoldilocksField; 2] = [
+            GoldilocksField::from_noncanonical_u64(01),
+            GoldilocksField::from_noncanonical_u64(11),
+        ];
+
+        let packed_a = *NeonGoldilocksField::from_slice(&in_a);
+        let packed_b = *NeonGoldilocksField::from_slice(&in_b);
+        {
+            let (x1, y1) = packed_a.interleave(packed_b, 1);
+            assert_eq!(x1.as_slice(), int1_a);
+            assert_eq!(y1.as_slice(), int1_b);
+        }
+        {
+            let (x2, y2) = packed_a.interleave(packed_b, 2);
+            assert_eq!(x2.as_slice(), in_a);
+            assert_eq!(y2.as_slice(), in_b);
+        }
+    }
+}
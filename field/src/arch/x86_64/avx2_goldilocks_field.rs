@@ -17,16 +17,20 @@ use crate::types::{Field, Field64};
 /// `Avx2GoldilocksField`. We need to ensure that `Avx2GoldilocksField` has the same alignment as
 /// `GoldilocksField`. Thus we wrap `[GoldilocksField; 4]` and use the `new` and `get` methods to
 /// convert to and from `__m256i`.
+///
+/// Without the `avx2` target feature, this type is only used from functions compiled with AVX2
+/// enabled, after a runtime check (see `avx2_runtime`). Its operations are `#[inline(always)]` so
+/// that they get compiled with AVX2 there; out of line, every intrinsic would be a call.
 #[derive(Copy, Clone)]
 #[repr(transparent)]
 pub struct Avx2GoldilocksField(pub [GoldilocksField; 4]);
 
 impl Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn new(x: __m256i) -> Self {
         unsafe { transmute(x) }
     }
-    #[inline]
+    #[inline(always)]
     fn get(&self) -> __m256i {
         unsafe { transmute(*self) }
     }
@@ -34,33 +38,33 @@ impl Avx2GoldilocksField {
 
 impl Add<Self> for Avx2GoldilocksField {
     type Output = Self;
-    #[inline]
+    #[inline(always)]
     fn add(self, rhs: Self) -> Self {
         Self::new(unsafe { add(self.get(), rhs.get()) })
     }
 }
 impl Add<GoldilocksField> for Avx2GoldilocksField {
     type Output = Self;
-    #[inline]
+    #[inline(always)]
     fn add(self, rhs: GoldilocksField) -> Self {
         self + Self::from(rhs)
     }
 }
 impl Add<Avx2GoldilocksField> for GoldilocksField {
     type Output = Avx2GoldilocksField;
-    #[inline]
+    #[inline(always)]
     fn add(self, rhs: Self::Output) -> Self::Output {
         Self::Output::from(self) + rhs
     }
 }
 impl AddAssign<Self> for Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn add_assign(&mut self, rhs: Self) {
         *self = *self + rhs;
     }
 }
 impl AddAssign<GoldilocksField> for Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn add_assign(&mut self, rhs: GoldilocksField) {
         *self = *self + rhs;
     }
@@ -74,7 +78,7 @@ impl Debug for Avx2GoldilocksField {
 }
 
 impl Default for Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn default() -> Self {
         Self::ZEROS
     }
@@ -83,14 +87,14 @@ impl Default for Avx2GoldilocksField {
 impl Div<GoldilocksField> for Avx2GoldilocksField {
     type Output = Self;
     #[allow(clippy::suspicious_arithmetic_impl)]
-    #[inline]
+    #[inline(always)]
     fn div(self, rhs: GoldilocksField) -> Self {
         self * rhs.inverse()
     }
 }
 impl DivAssign<GoldilocksField> for Avx2GoldilocksField {
     #[allow(clippy::suspicious_op_assign_impl)]
-    #[inline]
+    #[inline(always)]
     fn div_assign(&mut self, rhs: GoldilocksField) {
         *self *= rhs.inverse();
     }
@@ -104,33 +108,33 @@ impl From<GoldilocksField> for Avx2GoldilocksField {
 
 impl Mul<Self> for Avx2GoldilocksField {
     type Output = Self;
-    #[inline]
+    #[inline(always)]
     fn mul(self, rhs: Self) -> Self {
         Self::new(unsafe { mul(self.get(), rhs.get()) })
     }
 }
 impl Mul<GoldilocksField> for Avx2GoldilocksField {
     type Output = Self;
-    #[inline]
+    #[inline(always)]
     fn mul(self, rhs: GoldilocksField) -> Self {
         self * Self::from(rhs)
     }
 }
 impl Mul<Avx2GoldilocksField> for GoldilocksField {
     type Output = Avx2GoldilocksField;
-    #[inline]
+    #[inline(always)]
     fn mul(self, rhs: Avx2GoldilocksField) -> Self::Output {
         Self::Output::from(self) * rhs
     }
 }
 impl MulAssign<Self> for Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn mul_assign(&mut self, rhs: Self) {
         *self = *self * rhs;
     }
 }
 impl MulAssign<GoldilocksField> for Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn mul_assign(&mut self, rhs: GoldilocksField) {
         *self = *self * rhs;
     }
@@ -138,7 +142,7 @@ impl MulAssign<GoldilocksField> for Avx2GoldilocksField {
 
 impl Neg for Avx2GoldilocksField {
     type Output = Self;
-    #[inline]
+    #[inline(always)]
     fn neg(self) -> Self {
         Self::new(unsafe { neg(self.get()) })
     }
@@ -159,26 +163,26 @@ unsafe impl PackedField for Avx2GoldilocksField {
     const ZEROS: Self = Self([GoldilocksField::ZERO; 4]);
     const ONES: Self = Self([GoldilocksField::ONE; 4]);
 
-    #[inline]
+    #[inline(always)]
     fn from_slice(slice: &[Self::Scalar]) -> &Self {
         assert_eq!(slice.len(), Self::WIDTH);
         unsafe { &*slice.as_ptr().cast() }
     }
-    #[inline]
+    #[inline(always)]
     fn from_slice_mut(slice: &mut [Self::Scalar]) -> &mut Self {
         assert_eq!(slice.len(), Self::WIDTH);
         unsafe { &mut *slice.as_mut_ptr().cast() }
     }
-    #[inline]
+    #[inline(always)]
     fn as_slice(&self) -> &[Self::Scalar] {
         &self.0[..]
     }
-    #[inline]
+    #[inline(always)]
     fn as_slice_mut(&mut self) -> &mut [Self::Scalar] {
         &mut self.0[..]
     }
 
-    #[inline]
+    #[inline(always)]
     fn interleave(&self, other: Self, block_len: usize) -> (Self, Self) {
         let (v0, v1) = (self.get(), other.get());
         let (res0, res1) = match block_len {
@@ -192,7 +196,7 @@ unsafe impl PackedField for Avx2GoldilocksField {
 }
 
 impl Square for Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn square(&self) -> Self {
         Self::new(unsafe { square(self.get()) })
     }
@@ -200,33 +204,33 @@ impl Square for Avx2GoldilocksField {
 
 impl Sub<Self> for Avx2GoldilocksField {
     type Output = Self;
-    #[inline]
+    #[inline(always)]
     fn sub(self, rhs: Self) -> Self {
         Self::new(unsafe { sub(self.get(), rhs.get()) })
     }
 }
 impl Sub<GoldilocksField> for Avx2GoldilocksField {
     type Output = Self;
-    #[inline]
+    #[inline(always)]
     fn sub(self, rhs: GoldilocksField) -> Self {
         self - Self::from(rhs)
     }
 }
 impl Sub<Avx2GoldilocksField> for GoldilocksField {
     type Output = Avx2GoldilocksField;
-    #[inline]
+    #[inline(always)]
     fn sub(self, rhs: Avx2GoldilocksField) -> Self::Output {
         Self::Output::from(self) - rhs
     }
 }
 impl SubAssign<Self> for Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn sub_assign(&mut self, rhs: Self) {
         *self = *self - rhs;
     }
 }
 impl SubAssign<GoldilocksField> for Avx2GoldilocksField {
-    #[inline]
+    #[inline(always)]
     fn sub_assign(&mut self, rhs: GoldilocksField) {
         *self = *self - rhs;
     }
@@ -295,7 +299,7 @@ const EPSILON: __m256i = unsafe { transmute([GoldilocksField::ORDER.wrapping_neg
 
 /// Add 2^63 with overflow. Needed to emulate unsigned comparisons (see point 3. in
 /// packed_prime_field.rs).
-#[inline]
+#[inline(always)]
 pub unsafe fn shift(x: __m256i) -> __m256i {
     _mm256_xor_si256(x, SIGN_BIT)
 }
@@ -304,7 +308,7 @@ pub unsafe fn shift(x: __m256i) -> __m256i {
 /// The argument is assumed to be shifted by 1 << 63 (i.e. x_s = x + 1<<63, where x is the field
 ///   value). The returned value is similarly shifted by 1 << 63 (i.e. we return y_s = y + (1<<63),
 ///   where 0 <= y < FIELD_ORDER).
-#[inline]
+#[inline(always)]
 unsafe fn canonicalize_s(x_s: __m256i) -> __m256i {
     // If x >= FIELD_ORDER then corresponding mask bits are all 0; otherwise all 1.
     let mask = _mm256_cmpgt_epi64(SHIFTED_FIELD_ORDER, x_s);
@@ -315,7 +319,7 @@ unsafe fn canonicalize_s(x_s: __m256i) -> __m256i {
 
 /// Addition u64 + u64 -> u64. Assumes that x + y < 2^64 + FIELD_ORDER. The second argument is
 /// pre-shifted by 1 << 63. The result is similarly shifted.
-#[inline]
+#[inline(always)]
 unsafe fn add_no_double_overflow_64_64s_s(x: __m256i, y_s: __m256i) -> __m256i {
     let res_wrapped_s = _mm256_add_epi64(x, y_s);
     let mask = _mm256_cmpgt_epi64(y_s, res_wrapped_s); // -1 if overflowed else 0.
@@ -323,14 +327,14 @@ unsafe fn add_no_double_overflow_64_64s_s(x: __m256i, y_s: __m256i) -> __m256i {
     _mm256_add_epi64(res_wrapped_s, wrapback_amt)
 }
 
-#[inline]
+#[inline(always)]
 unsafe fn add(x: __m256i, y: __m256i) -> __m256i {
     let y_s = shift(y);
     let res_s = add_no_double_overflow_64_64s_s(x, canonicalize_s(y_s));
     shift(res_s)
 }
 
-#[inline]
+#[inline(always)]
 unsafe fn sub(x: __m256i, y: __m256i) -> __m256i {
     let mut y_s = shift(y);
     y_s = canonicalize_s(y_s);
@@ -341,7 +345,7 @@ unsafe fn sub(x: __m256i, y: __m256i) -> __m256i {
     _mm256_sub_epi64(res_wrapped, wrapback_amt)
 }
 
-#[inline]
+#[inline(always)]
 unsafe fn neg(y: __m256i) -> __m256i {
     let y_s = shift(y);
     _mm256_sub_epi64(SHIFTED_FIELD_ORDER, canonicalize_s(y_s))
@@ -349,7 +353,7 @@ unsafe fn neg(y: __m256i) -> __m256i {
 
 /// Full 64-bit by 64-bit multiplication. This emulated multiplication is 1.33x slower than the
 /// scalar instruction, but may be worth it if we want our data to live in vector registers.
-#[inline]
+#[inline(always)]
 unsafe fn mul64_64(x: __m256i, y: __m256i) -> (__m256i, __m256i) {
     // We want to move the high 32 bits to the low position. The multiplication instruction ignores
     // the high 32 bits, so it's ok to just duplicate it into the low position. This duplication can
@@ -390,7 +394,7 @@ unsafe fn mul64_64(x: __m256i, y: __m256i) -> (__m256i, __m256i) {
 }
 
 /// Full 64-bit squaring. This routine is 1.2x faster than the scalar instruction.
-#[inline]
+#[inline(always)]
 unsafe fn square64(x: __m256i) -> (__m256i, __m256i) {
     // Get high 32 bits of x. See comment in mul64_64_s.
     let x_hi = _mm256_castps_si256(_mm256_movehdup_ps(_mm256_castsi256_ps(x)));
@@ -416,7 +420,7 @@ unsafe fn square64(x: __m256i) -> (__m256i, __m256i) {
 
 /// Goldilocks addition of a "small" number. `x_s` is pre-shifted by 2**63. `y` is assumed to be <=
 /// `0xffffffff00000000`. The result is shifted by 2**63.
-#[inline]
+#[inline(always)]
 unsafe fn add_small_64s_64_s(x_s: __m256i, y: __m256i) -> __m256i {
     let res_wrapped_s = _mm256_add_epi64(x_s, y);
     // 32-bit compare is faster than 64-bit. It's safe as long as x > res_wrapped iff x >> 32 >
@@ -432,7 +436,7 @@ unsafe fn add_small_64s_64_s(x_s: __m256i, y: __m256i) -> __m256i {
 
 /// Goldilocks subtraction of a "small" number. `x_s` is pre-shifted by 2**63. `y` is assumed to be
 /// <= `0xffffffff00000000`. The result is shifted by 2**63.
-#[inline]
+#[inline(always)]
 unsafe fn sub_small_64s_64_s(x_s: __m256i, y: __m256i) -> __m256i {
     let res_wrapped_s = _mm256_sub_epi64(x_s, y);
     // 32-bit compare is faster than 64-bit. It's safe as long as res_wrapped > x iff res_wrapped >>
@@ -446,7 +450,7 @@ unsafe fn sub_small_64s_64_s(x_s: __m256i, y: __m256i) -> __m256i {
     _mm256_sub_epi64(res_wrapped_s, wrapback_amt)
 }
 
-#[inline]
+#[inline(always)]
 unsafe fn reduce128(x: (__m256i, __m256i)) -> __m256i {
     let (hi0, lo0) = x;
     let lo0_s = shift(lo0);
@@ -458,25 +462,25 @@ unsafe fn reduce128(x: (__m256i, __m256i)) -> __m256i {
 }
 
 /// Multiply two integers modulo FIELD_ORDER.
-#[inline]
+#[inline(always)]
 unsafe fn mul(x: __m256i, y: __m256i) -> __m256i {
     reduce128(mul64_64(x, y))
 }
 
 /// Square an integer modulo FIELD_ORDER.
-#[inline]
+#[inline(always)]
 unsafe fn square(x: __m256i) -> __m256i {
     reduce128(square64(x))
 }
 
-#[inline]
+#[inline(always)]
 unsafe fn interleave1(x: __m256i, y: __m256i) -> (__m256i, __m256i) {
     let a = _mm256_unpacklo_epi64(x, y);
     let b = _mm256_unpackhi_epi64(x, y);
     (a, b)
 }
 
-#[inline]
+#[inline(always)]
 unsafe fn interleave2(x: __m256i, y: __m256i) -> (__m256i, __m256i) {
     let y_lo = _mm256_castsi256_si128(y); // This has 0 cost.
 
@@ -496,7 +500,7 @@ unsafe fn interleave2(x: __m256i, y: __m256i) -> (__m256i, __m256i) {
     (a, b)
 }
 
-#[cfg(test)]
+#[cfg(all(test, target_feature = "avx2"))]
 mod tests {
     use crate::arch::x86_64::avx2_goldilocks_field::Avx2GoldilocksField;
     use crate::goldilocks_field::GoldilocksField;
@@ -0,0 +1,190 @@
+//! Runtime dispatch to the AVX2 backend, for builds without the `avx2` target feature.
+//!
+//! Binaries are usually built for a baseline CPU, in which case `Packable` selects the scalar
+//! packing at compile time. The functions below check whether the running CPU supports AVX2, and if
+//! so, run the given operation on `GoldilocksField` elements with `Avx2GoldilocksField` from a
+//! function compiled with AVX2 enabled. They return `false` when the scalar path should be taken
+//! instead, i.e. for other fields or CPUs without AVX2.
+//!
+//! Generic code called from a `#[target_feature]` function is only compiled with that feature if
+//! it is inlined, so the packed loops run here are `#[inline(always)]`. Closures don't inherit
+//! target features either, so the parallel FFT calls back into AVX2 functions from its tasks.
+//! `benches/avx2_dispatch.rs` in `plonky2` compares this against the scalar path.
+
+use core::any::{Any, TypeId};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::arch::x86_64::avx2_goldilocks_field::Avx2GoldilocksField;
+use crate::batch_util::{batch_add_inplace_packed, batch_multiply_inplace_packed};
+use crate::fft::{fft_butterflies, fft_classic_layers_with, fft_classic_simd, FftRootTable};
+use crate::goldilocks_field::GoldilocksField;
+use crate::types::Field;
+
+static DISPATCH_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables the runtime dispatch to AVX2, which is enabled by default. Disabling it
+/// makes field operations take the scalar path even on CPUs with AVX2, e.g. to benchmark one
+/// against the other.
+pub fn set_avx2_dispatch(enabled: bool) {
+    DISPATCH_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn avx2_detected() -> bool {
+    // The result is cached by the standard library.
+    DISPATCH_ENABLED.load(Ordering::Relaxed) && std::is_x86_feature_detected!("avx2")
+}
+
+/// Reinterprets `slice` as a slice of `GoldilocksField`s if `F` is `GoldilocksField`.
+fn as_goldilocks<F: Field>(slice: &[F]) -> Option<&[GoldilocksField]> {
+    (TypeId::of::<F>() == TypeId::of::<GoldilocksField>()).then(|| {
+        // SAFETY: `F` and `GoldilocksField` are the same type.
+        unsafe { &*(slice as *const [F] as *const [GoldilocksField]) }
+    })
+}
+
+/// Like `as_goldilocks`, for mutable slices.
+fn as_goldilocks_mut<F: Field>(slice: &mut [F]) -> Option<&mut [GoldilocksField]> {
+    (TypeId::of::<F>() == TypeId::of::<GoldilocksField>()).then(|| {
+        // SAFETY: `F` and `GoldilocksField` are the same type.
+        unsafe { &mut *(slice as *mut [F] as *mut [GoldilocksField]) }
+    })
+}
+
+pub(crate) fn batch_multiply_inplace<F: Field>(out: &mut [F], a: &[F]) -> bool {
+    if !avx2_detected() {
+        return false;
+    }
+    let (Some(out), Some(a)) = (as_goldilocks_mut(out), as_goldilocks(a)) else {
+        return false;
+    };
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn run(out: &mut [GoldilocksField], a: &[GoldilocksField]) {
+        batch_multiply_inplace_packed::<Avx2GoldilocksField>(out, a);
+    }
+    // SAFETY: The CPU supports AVX2.
+    unsafe { run(out, a) };
+    true
+}
+
+pub(crate) fn batch_add_inplace<F: Field>(out: &mut [F], a: &[F]) -> bool {
+    if !avx2_detected() {
+        return false;
+    }
+    let (Some(out), Some(a)) = (as_goldilocks_mut(out), as_goldilocks(a)) else {
+        return false;
+    };
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn run(out: &mut [GoldilocksField], a: &[GoldilocksField]) {
+        batch_add_inplace_packed::<Avx2GoldilocksField>(out, a);
+    }
+    // SAFETY: The CPU supports AVX2.
+    unsafe { run(out, a) };
+    true
+}
+
+pub(crate) fn fft_classic_layers<F: Field>(
+    values: &mut [F],
+    r: usize,
+    lg_n: usize,
+    root_table: &FftRootTable<F>,
+) -> bool {
+    if !avx2_detected() {
+        return false;
+    }
+    let (Some(values), Some(root_table)) = (
+        as_goldilocks_mut(values),
+        (root_table as &dyn Any).downcast_ref::<FftRootTable<GoldilocksField>>(),
+    ) else {
+        return false;
+    };
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn simd(
+        values: &mut [GoldilocksField],
+        r: usize,
+        lg_n: usize,
+        root_table: &FftRootTable<GoldilocksField>,
+    ) {
+        fft_classic_simd::<Avx2GoldilocksField>(values, r, lg_n, root_table);
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn butterflies(
+        left: &mut [Avx2GoldilocksField],
+        right: &mut [Avx2GoldilocksField],
+        omegas: &[Avx2GoldilocksField],
+    ) {
+        fft_butterflies(left, right, omegas);
+    }
+
+    // SAFETY (both closures): The CPU supports AVX2.
+    fft_classic_layers_with::<Avx2GoldilocksField>(
+        values,
+        r,
+        lg_n,
+        root_table,
+        |values, r, lg_n| unsafe { simd(values, r, lg_n, root_table) },
+        |left, right, omegas| unsafe { butterflies(left, right, omegas) },
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::quadratic::QuadraticExtension;
+    use crate::fft::{fft_classic_layers, fft_root_table};
+    use crate::types::Sample;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_batch_ops_match_scalar() {
+        if !avx2_detected() {
+            return;
+        }
+        // Not a multiple of the packing width, so that the leftovers are covered too.
+        let a = F::rand_vec(1027);
+        let b = F::rand_vec(1027);
+
+        let mut product = a.clone();
+        assert!(batch_multiply_inplace(&mut product, &b));
+        let mut expected = a.clone();
+        batch_multiply_inplace_packed::<F>(&mut expected, &b);
+        assert_eq!(product, expected);
+
+        let mut sum = a.clone();
+        assert!(batch_add_inplace(&mut sum, &b));
+        let mut expected = a;
+        batch_add_inplace_packed::<F>(&mut expected, &b);
+        assert_eq!(sum, expected);
+
+        // Other fields take the scalar path.
+        let mut ext = vec![QuadraticExtension::<F>::ONE; 4];
+        assert!(!batch_add_inplace(
+            &mut ext,
+            &[QuadraticExtension::<F>::ONE; 4]
+        ));
+    }
+
+    #[test]
+    fn test_fft_matches_scalar() {
+        if !avx2_detected() {
+            return;
+        }
+        // 16 is large enough for the parallel FFT, when that is enabled.
+        for lg_n in [1, 2, 5, 10, 16] {
+            let root_table = fft_root_table::<F>(1 << lg_n);
+            for r in [0, 1] {
+                let values = F::rand_vec(1 << lg_n);
+                let mut packed = values.clone();
+                assert!(super::fft_classic_layers(&mut packed, r, lg_n, &root_table));
+                let mut expected = values;
+                fft_classic_layers::<F>(&mut expected, r, lg_n, &root_table);
+                assert_eq!(packed, expected);
+            }
+        }
+    }
+}
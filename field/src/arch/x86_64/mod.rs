@@ -1,5 +1,5 @@
 #[cfg(all(
-    target_feature = "avx2",
+    any(target_feature = "avx2", feature = "std"),
     not(all(
         target_feature = "avx512bw",
         target_feature = "avx512cd",
@@ -10,6 +10,9 @@
 ))]
 pub mod avx2_goldilocks_field;
 
+#[cfg(all(feature = "std", not(target_feature = "avx2")))]
+pub(crate) mod avx2_runtime;
+
 #[cfg(all(
     target_feature = "avx512bw",
     target_feature = "avx512cd",
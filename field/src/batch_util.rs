@@ -8,6 +8,7 @@ const fn pack_with_leftovers_split_point<P: PackedField>(slice: &[P::Scalar]) ->
     n - n_leftover
 }
 
+#[inline(always)]
 fn pack_slice_with_leftovers<P: PackedField>(slice: &[P::Scalar]) -> (&[P], &[P::Scalar]) {
     let split_point = pack_with_leftovers_split_point::<P>(slice);
     let (slice_packable, slice_leftovers) = slice.split_at(split_point);
@@ -15,6 +16,7 @@ fn pack_slice_with_leftovers<P: PackedField>(slice: &[P::Scalar]) -> (&[P], &[P:
     (slice_packed, slice_leftovers)
 }
 
+#[inline(always)]
 fn pack_slice_with_leftovers_mut<P: PackedField>(
     slice: &mut [P::Scalar],
 ) -> (&mut [P], &mut [P::Scalar]) {
@@ -30,10 +32,22 @@ pub fn batch_multiply_inplace<F: Field>(out: &mut [F], a: &[F]) {
     let n = out.len();
     assert_eq!(n, a.len(), "both arrays must have the same length");
 
+    #[cfg(all(target_arch = "x86_64", feature = "std", not(target_feature = "avx2")))]
+    if crate::arch::x86_64::avx2_runtime::batch_multiply_inplace(out, a) {
+        return;
+    }
+    batch_multiply_inplace_packed::<<F as Packable>::Packing>(out, a);
+}
+
+// Always inlined, so that the runtime AVX2 dispatch compiles the loop with AVX2 enabled.
+#[inline(always)]
+pub(crate) fn batch_multiply_inplace_packed<P: PackedField>(
+    out: &mut [P::Scalar],
+    a: &[P::Scalar],
+) {
     // Split out slice of vectors, leaving leftovers as scalars
-    let (out_packed, out_leftovers) =
-        pack_slice_with_leftovers_mut::<<F as Packable>::Packing>(out);
-    let (a_packed, a_leftovers) = pack_slice_with_leftovers::<<F as Packable>::Packing>(a);
+    let (out_packed, out_leftovers) = pack_slice_with_leftovers_mut::<P>(out);
+    let (a_packed, a_leftovers) = pack_slice_with_leftovers::<P>(a);
 
     // Multiply packed and the leftovers
     for (x_out, x_a) in out_packed.iter_mut().zip(a_packed) {
@@ -50,10 +64,19 @@ pub fn batch_add_inplace<F: Field>(out: &mut [F], a: &[F]) {
     let n = out.len();
     assert_eq!(n, a.len(), "both arrays must have the same length");
 
+    #[cfg(all(target_arch = "x86_64", feature = "std", not(target_feature = "avx2")))]
+    if crate::arch::x86_64::avx2_runtime::batch_add_inplace(out, a) {
+        return;
+    }
+    batch_add_inplace_packed::<<F as Packable>::Packing>(out, a);
+}
+
+// Always inlined, for the same reason as `batch_multiply_inplace_packed`.
+#[inline(always)]
+pub(crate) fn batch_add_inplace_packed<P: PackedField>(out: &mut [P::Scalar], a: &[P::Scalar]) {
     // Split out slice of vectors, leaving leftovers as scalars
-    let (out_packed, out_leftovers) =
-        pack_slice_with_leftovers_mut::<<F as Packable>::Packing>(out);
-    let (a_packed, a_leftovers) = pack_slice_with_leftovers::<<F as Packable>::Packing>(a);
+    let (out_packed, out_leftovers) = pack_slice_with_leftovers_mut::<P>(out);
+    let (a_packed, a_leftovers) = pack_slice_with_leftovers::<P>(a);
 
     // Add packed and the leftovers
     for (x_out, x_a) in out_packed.iter_mut().zip(a_packed) {
@@ -0,0 +1,218 @@
+//! A process-wide cache of the tables which only depend on a field and on the size of an evaluation
+//! domain, so that proving many circuits, or many proofs, in the same process computes each of
+//! them once.
+//!
+//! FFTs which are not given a root table fetch it from here. The cache holds at most
+//! [`DEFAULT_CAPACITY`] bytes of tables, or the capacity given to [`set_capacity`], and evicts the
+//! least recently used tables beyond that. Evicted tables stay alive for as long as their users
+//! hold on to them. [`clear`] drops all tables at once.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::any::{Any, TypeId};
+use core::mem::size_of;
+use std::sync::Mutex;
+
+use crate::fft::{self, FftRootTable};
+use crate::types::Field;
+
+/// The default capacity of the cache, in bytes. It fits the root table and the subgroup of a
+/// `2^23`-point domain over a 64-bit field.
+pub const DEFAULT_CAPACITY: usize = 256 << 20;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum Table {
+    FftRoots,
+    TwoAdicSubgroup,
+}
+
+/// Tables are keyed by the field they are over, their kind, and the log of the domain size.
+type Key = (TypeId, Table, usize);
+
+struct Entry {
+    table: Arc<dyn Any + Send + Sync>,
+    bytes: usize,
+    /// The value of `Tables::clock` when the table was last fetched.
+    last_used: u64,
+}
+
+struct Tables {
+    entries: BTreeMap<Key, Entry>,
+    capacity: usize,
+    bytes: usize,
+    clock: u64,
+}
+
+impl Tables {
+    /// Evicts the least recently used tables until `extra_bytes` more fit in the capacity.
+    fn make_room(&mut self, extra_bytes: usize) {
+        while self.bytes + extra_bytes > self.capacity {
+            let Some((&key, _)) = self.entries.iter().min_by_key(|(_, e)| e.last_used) else {
+                break;
+            };
+            self.bytes -= self.entries.remove(&key).unwrap().bytes;
+        }
+    }
+}
+
+struct DomainCache(Mutex<Tables>);
+
+impl DomainCache {
+    const fn new(capacity: usize) -> Self {
+        Self(Mutex::new(Tables {
+            entries: BTreeMap::new(),
+            capacity,
+            bytes: 0,
+            clock: 0,
+        }))
+    }
+
+    /// Returns the table under `key`, computing it if it isn't cached. `bytes` is the size of the
+    /// table.
+    fn get_or_compute<T: Any + Send + Sync>(
+        &self,
+        key: Key,
+        bytes: usize,
+        compute: impl FnOnce() -> T,
+    ) -> Arc<T> {
+        let downcast = |table: Arc<dyn Any + Send + Sync>| {
+            table.downcast().expect("Cached table has the wrong type")
+        };
+
+        {
+            let mut tables = self.0.lock().unwrap();
+            tables.clock += 1;
+            let clock = tables.clock;
+            if let Some(entry) = tables.entries.get_mut(&key) {
+                entry.last_used = clock;
+                return downcast(entry.table.clone());
+            }
+        }
+
+        // The table is computed without holding the lock, so that other tables can be fetched in
+        // the meantime. Threads racing to compute the same table all get the first one inserted.
+        let table: Arc<dyn Any + Send + Sync> = Arc::new(compute());
+        let mut tables = self.0.lock().unwrap();
+        if let Some(entry) = tables.entries.get(&key) {
+            return downcast(entry.table.clone());
+        }
+        if bytes <= tables.capacity {
+            tables.make_room(bytes);
+            tables.bytes += bytes;
+            let last_used = tables.clock;
+            tables.entries.insert(
+                key,
+                Entry {
+                    table: table.clone(),
+                    bytes,
+                    last_used,
+                },
+            );
+        }
+        downcast(table)
+    }
+
+    fn fft_root_table<F: Field>(&self, lg_n: usize) -> Arc<FftRootTable<F>> {
+        // The rows hold `2^lg_n - 1` elements, plus one in the first row.
+        let bytes = (1 << lg_n) * size_of::<F>();
+        self.get_or_compute((TypeId::of::<F>(), Table::FftRoots, lg_n), bytes, || {
+            fft::fft_root_table(1 << lg_n)
+        })
+    }
+
+    fn two_adic_subgroup<F: Field>(&self, lg_n: usize) -> Arc<Vec<F>> {
+        let bytes = (1 << lg_n) * size_of::<F>();
+        self.get_or_compute(
+            (TypeId::of::<F>(), Table::TwoAdicSubgroup, lg_n),
+            bytes,
+            || F::two_adic_subgroup(lg_n),
+        )
+    }
+
+    fn set_capacity(&self, capacity: usize) {
+        let mut tables = self.0.lock().unwrap();
+        tables.capacity = capacity;
+        tables.make_room(0);
+    }
+
+    fn clear(&self) {
+        let mut tables = self.0.lock().unwrap();
+        tables.entries.clear();
+        tables.bytes = 0;
+    }
+}
+
+static CACHE: DomainCache = DomainCache::new(DEFAULT_CAPACITY);
+
+/// Returns `fft::fft_root_table(1 << lg_n)`.
+pub fn fft_root_table<F: Field>(lg_n: usize) -> Arc<FftRootTable<F>> {
+    CACHE.fft_root_table(lg_n)
+}
+
+/// Returns `F::two_adic_subgroup(lg_n)`.
+pub fn two_adic_subgroup<F: Field>(lg_n: usize) -> Arc<Vec<F>> {
+    CACHE.two_adic_subgroup(lg_n)
+}
+
+/// Sets the number of bytes of tables the cache may hold, evicting the least recently used tables
+/// if it holds more. Tables larger than the capacity are computed on each use. A capacity of 0
+/// disables the cache.
+pub fn set_capacity(bytes: usize) {
+    CACHE.set_capacity(bytes);
+}
+
+/// Drops all cached tables, e.g. to free their memory once no more proofs are expected for these
+/// domain sizes.
+pub fn clear() {
+    CACHE.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use crate::domain_cache::{fft_root_table, two_adic_subgroup, DomainCache};
+    use crate::fft;
+    use crate::goldilocks_field::GoldilocksField;
+    use crate::types::Field;
+
+    #[test]
+    fn tables_are_shared() {
+        type F = GoldilocksField;
+        let roots = fft_root_table::<F>(5);
+        assert_eq!(*roots, fft::fft_root_table::<F>(1 << 5));
+        assert!(Arc::ptr_eq(&roots, &fft_root_table::<F>(5)));
+        assert_ne!(*fft_root_table::<F>(6), *roots);
+
+        let subgroup = two_adic_subgroup::<F>(5);
+        assert_eq!(*subgroup, F::two_adic_subgroup(5));
+        assert!(Arc::ptr_eq(&subgroup, &two_adic_subgroup::<F>(5)));
+    }
+
+    #[test]
+    fn least_recently_used_tables_are_evicted() {
+        type F = GoldilocksField;
+        // Room for two subgroups of 2^4 elements.
+        let cache = DomainCache::new(2 * 16 * 8);
+        let a = cache.two_adic_subgroup::<F>(4);
+        let roots = cache.fft_root_table::<F>(4);
+        assert!(Arc::ptr_eq(&a, &cache.two_adic_subgroup::<F>(4)));
+
+        // Evicts the root table, which was used less recently than the subgroup.
+        let b = cache.two_adic_subgroup::<F>(3);
+        assert!(Arc::ptr_eq(&a, &cache.two_adic_subgroup::<F>(4)));
+        assert!(!Arc::ptr_eq(&roots, &cache.fft_root_table::<F>(4)));
+        // That evicted `b`, the least recently used now.
+        assert!(!Arc::ptr_eq(&b, &cache.two_adic_subgroup::<F>(3)));
+
+        // Too large to be cached.
+        let c = cache.two_adic_subgroup::<F>(6);
+        assert_eq!(*c, F::two_adic_subgroup(6));
+        assert!(!Arc::ptr_eq(&c, &cache.two_adic_subgroup::<F>(6)));
+
+        cache.set_capacity(0);
+        assert_eq!(cache.0.lock().unwrap().bytes, 0);
+        assert!(cache.0.lock().unwrap().entries.is_empty());
+    }
+}
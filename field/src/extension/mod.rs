@@ -1,6 +1,8 @@
 use alloc::vec::Vec;
 
-use crate::types::Field;
+use anyhow::{ensure, Result};
+
+use crate::types::{Field, PrimeField64};
 
 pub mod algebra;
 pub mod quadratic;
@@ -144,3 +146,35 @@ where
         .map(|c| F::Extension::from_basefield_array(c.to_vec().try_into().unwrap()))
         .collect()
 }
+
+/// Concatenates the canonical little-endian encodings of the coefficients of `x`.
+pub(crate) fn to_bytes_le<F: PrimeField64, E: FieldExtension<D, BaseField = F>, const D: usize>(
+    x: &E,
+) -> Vec<u8> {
+    x.to_basefield_array()
+        .iter()
+        .flat_map(|c| c.to_bytes_le())
+        .collect()
+}
+
+/// Decodes an extension field element from the encoding produced by [`to_bytes_le`], checking that
+/// each coefficient is canonical.
+pub(crate) fn from_bytes_le<
+    F: PrimeField64,
+    E: FieldExtension<D, BaseField = F>,
+    const D: usize,
+>(
+    bytes: &[u8],
+) -> Result<E> {
+    ensure!(
+        bytes.len() == D * F::NUM_BYTES,
+        "expected {} bytes, got {}",
+        D * F::NUM_BYTES,
+        bytes.len()
+    );
+    let mut arr = [F::ZERO; D];
+    for (c, chunk) in arr.iter_mut().zip(bytes.chunks_exact(F::NUM_BYTES)) {
+        *c = F::from_bytes_le(chunk)?;
+    }
+    Ok(E::from_basefield_array(arr))
+}
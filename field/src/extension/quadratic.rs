@@ -1,18 +1,33 @@
+use alloc::vec::Vec;
 use core::fmt::{self, Debug, Display, Formatter};
 use core::iter::{Product, Sum};
 use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+use anyhow::Result;
 use num::bigint::BigUint;
 use serde::{Deserialize, Serialize};
 
-use crate::extension::{Extendable, FieldExtension, Frobenius, OEF};
+use crate::extension::{self, Extendable, FieldExtension, Frobenius, OEF};
 use crate::ops::Square;
-use crate::types::{Field, Sample};
+use crate::types::{Field, PrimeField64, Sample};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct QuadraticExtension<F: Extendable<2>>(pub [F; 2]);
 
+impl<F: Extendable<2> + PrimeField64> QuadraticExtension<F> {
+    /// Returns the concatenated canonical little-endian encodings of the coefficients.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        extension::to_bytes_le::<_, _, 2>(self)
+    }
+
+    /// Decodes an element from the encoding produced by [`Self::to_bytes_le`], failing if its
+    /// length is wrong or if any coefficient is not canonical.
+    pub fn from_bytes_le(bytes: &[u8]) -> Result<Self> {
+        extension::from_bytes_le::<_, _, 2>(bytes)
+    }
+}
+
 impl<F: Extendable<2>> Default for QuadraticExtension<F> {
     fn default() -> Self {
         Self::ZERO
@@ -246,5 +261,24 @@ mod tests {
                 crate::goldilocks_field::GoldilocksField,
             >
         );
+
+        #[test]
+        fn test_bytes_le_round_trip() {
+            use crate::extension::quadratic::QuadraticExtension;
+            use crate::goldilocks_field::GoldilocksField;
+            use crate::types::{Field64, Sample};
+
+            type F = QuadraticExtension<GoldilocksField>;
+
+            let x = F::rand();
+            let bytes = x.to_bytes_le();
+            assert_eq!(bytes.len(), 16);
+            assert_eq!(F::from_bytes_le(&bytes).unwrap(), x);
+
+            let mut noncanonical = bytes;
+            noncanonical[8..].copy_from_slice(&GoldilocksField::ORDER.to_le_bytes());
+            assert!(F::from_bytes_le(&noncanonical).is_err());
+            assert!(F::from_bytes_le(&noncanonical[..15]).is_err());
+        }
     }
 }
@@ -1,19 +1,34 @@
+use alloc::vec::Vec;
 use core::fmt::{self, Debug, Display, Formatter};
 use core::iter::{Product, Sum};
 use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+use anyhow::Result;
 use num::bigint::BigUint;
 use num::traits::Pow;
 use serde::{Deserialize, Serialize};
 
-use crate::extension::{Extendable, FieldExtension, Frobenius, OEF};
+use crate::extension::{self, Extendable, FieldExtension, Frobenius, OEF};
 use crate::ops::Square;
-use crate::types::{Field, Sample};
+use crate::types::{Field, PrimeField64, Sample};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct QuinticExtension<F: Extendable<5>>(pub [F; 5]);
 
+impl<F: Extendable<5> + PrimeField64> QuinticExtension<F> {
+    /// Returns the concatenated canonical little-endian encodings of the coefficients.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        extension::to_bytes_le::<_, _, 5>(self)
+    }
+
+    /// Decodes an element from the encoding produced by [`Self::to_bytes_le`], failing if its
+    /// length is wrong or if any coefficient is not canonical.
+    pub fn from_bytes_le(bytes: &[u8]) -> Result<Self> {
+        extension::from_bytes_le::<_, _, 5>(bytes)
+    }
+}
+
 impl<F: Extendable<5>> Default for QuinticExtension<F> {
     fn default() -> Self {
         Self::ZERO
@@ -1,6 +1,10 @@
+use alloc::vec;
 use alloc::vec::Vec;
+use core::borrow::Borrow;
 use core::cmp::{max, min};
 
+use num::{BigUint, Zero};
+use plonky2_maybe_rayon::*;
 use plonky2_util::{log2_strict, reverse_index_bits_in_place};
 use unroll::unroll_for_loops;
 
@@ -11,6 +15,18 @@ use crate::types::Field;
 
 pub type FftRootTable<F> = Vec<Vec<F>>;
 
+/// With the `parallel` feature, FFTs of at least `2^LG_PAR_FFT_THRESHOLD` points are split across
+/// threads by `fft_classic_par`.
+const LG_PAR_FFT_THRESHOLD: usize = 16;
+
+/// The size of the blocks `fft_classic_par` transforms independently, and the number of
+/// butterflies in each of its later tasks.
+const LG_PAR_FFT_BLOCK: usize = 12;
+
+/// In `coset_lde_batch_bit_reversed`, the butterflies of a block are split across threads once
+/// the block holds at least `2^LG_PAR_BATCH_HALF` pairs of rows.
+const LG_PAR_BATCH_HALF: usize = 6;
+
 pub fn fft_root_table<F: Field>(n: usize) -> FftRootTable<F> {
     let lg_n = log2_strict(n);
     // bases[i] = g^2^i, for i = 0, ..., lg_n - 1
@@ -32,14 +48,28 @@ pub fn fft_root_table<F: Field>(n: usize) -> FftRootTable<F> {
     root_table
 }
 
+/// The root table for FFTs of `n` points, taken from the domain cache if it is available.
+#[cfg(feature = "std")]
+fn shared_root_table<F: Field>(n: usize) -> alloc::sync::Arc<FftRootTable<F>> {
+    crate::domain_cache::fft_root_table(log2_strict(n))
+}
+
+/// The root table for FFTs of `n` points, taken from the domain cache if it is available.
+#[cfg(not(feature = "std"))]
+fn shared_root_table<F: Field>(n: usize) -> FftRootTable<F> {
+    fft_root_table(n)
+}
+
 #[inline]
 fn fft_dispatch<F: Field>(
     input: &mut [F],
     zero_factor: Option<usize>,
     root_table: Option<&FftRootTable<F>>,
 ) {
-    let computed_root_table = root_table.is_none().then(|| fft_root_table(input.len()));
-    let used_root_table = root_table.or(computed_root_table.as_ref()).unwrap();
+    let computed_root_table = root_table.is_none().then(|| shared_root_table(input.len()));
+    let used_root_table = root_table
+        .or(computed_root_table.as_ref().map(Borrow::borrow))
+        .unwrap();
 
     fft_classic(input, zero_factor.unwrap_or(0), used_root_table);
 }
@@ -90,9 +120,186 @@ pub fn ifft_with_options<F: Field>(
     PolynomialCoeffs { coeffs: buffer }
 }
 
+/// The radices supported by `fft_mixed_radix`, tried in this order.
+const MIXED_RADICES: [usize; 3] = [2, 3, 5];
+
+/// Returns the generator of the subgroup of order `n` used by `fft_mixed_radix`, or `None` if
+/// there is no such subgroup. This is `g^((|F*|) / n)` for the multiplicative group generator `g`,
+/// so for powers of two it may differ from `F::primitive_root_of_unity`.
+pub fn mixed_radix_root_of_unity<F: Field>(n: usize) -> Option<F> {
+    let group_order = F::order() - 1u32;
+    let n = BigUint::from(n);
+    (!n.is_zero() && (&group_order % &n).is_zero())
+        .then(|| F::MULTIPLICATIVE_GROUP_GENERATOR.exp_biguint(&(group_order / n)))
+}
+
+/// Evaluates the polynomial with coefficients `coeffs` over the subgroup of order
+/// `n = coeffs.len()`, i.e. at `omega^i` for `omega = mixed_radix_root_of_unity(n)`.
+///
+/// Unlike `fft`, `n` does not need to be a power of two, but can be any product of powers of 2, 3
+/// and 5 dividing the order of the multiplicative group, so that domains of such sizes need not be
+/// padded. This is meant for auxiliary computations; it is slower than `fft` on powers of two.
+pub fn fft_mixed_radix<F: Field>(coeffs: &[F]) -> Vec<F> {
+    let n = coeffs.len();
+    let omega = mixed_radix_root_of_unity(n)
+        .unwrap_or_else(|| panic!("The field has no subgroup of order {n}"));
+    fft_mixed_radix_rec(coeffs, 1, n, omega)
+}
+
+/// The inverse of `fft_mixed_radix`: interpolates `values` over the subgroup of order
+/// `values.len()`.
+pub fn ifft_mixed_radix<F: Field>(values: &[F]) -> Vec<F> {
+    let n = values.len();
+    let omega = mixed_radix_root_of_unity::<F>(n)
+        .unwrap_or_else(|| panic!("The field has no subgroup of order {n}"));
+    let n_inv = F::from_canonical_usize(n).inverse();
+    let mut coeffs = fft_mixed_radix_rec(values, 1, n, omega.inverse());
+    coeffs.iter_mut().for_each(|c| *c *= n_inv);
+    coeffs
+}
+
+/// Evaluates the polynomial with coefficients `coeffs[i * stride]`, for `i < n`, at the powers of
+/// `omega`, which has order `n`, using a decimation-in-time Cooley-Tukey step of the smallest
+/// supported radix dividing `n`.
+fn fft_mixed_radix_rec<F: Field>(coeffs: &[F], stride: usize, n: usize, omega: F) -> Vec<F> {
+    if n == 1 {
+        return vec![coeffs[0]];
+    }
+    let radix = MIXED_RADICES
+        .into_iter()
+        .find(|&r| n.is_multiple_of(r))
+        .unwrap_or_else(|| panic!("Mixed-radix FFTs only support sizes of the form 2^a 3^b 5^c"));
+    let m = n / radix;
+
+    // The evaluations of the `radix` polynomials made of every `radix`th coefficient, over the
+    // subgroup of order `m`.
+    let omega_radix = omega.exp_u64(radix as u64);
+    let sub_evals = (0..radix)
+        .map(|s| fft_mixed_radix_rec(&coeffs[s * stride..], stride * radix, m, omega_radix))
+        .collect::<Vec<_>>();
+
+    // With `Y_s` the evaluations of the `s`th one, and `w = omega^m` of order `radix`,
+    // `X[k + m u] = sum_s omega^(s k) w^(s u) Y_s[k]`.
+    let w_powers = omega
+        .exp_u64(m as u64)
+        .powers()
+        .take(radix)
+        .collect::<Vec<_>>();
+    let mut evals = vec![F::ZERO; n];
+    for (k, omega_k) in omega.powers().take(m).enumerate() {
+        let mut terms = [F::ZERO; 5];
+        for (s, (term, omega_sk)) in terms
+            .iter_mut()
+            .zip(omega_k.powers())
+            .take(radix)
+            .enumerate()
+        {
+            *term = omega_sk * sub_evals[s][k];
+        }
+        for u in 0..radix {
+            evals[k + m * u] = (0..radix).map(|s| terms[s] * w_powers[s * u % radix]).sum();
+        }
+    }
+    evals
+}
+
+/// Extends each of `polys`, which have the same length `n`, to its evaluations over the coset
+/// `shift * H`, where `H` is the subgroup of size `n << rate_bits`. The evaluations are returned
+/// row by row, in bit-reversed order: `rows[i][k]` is the evaluation of `polys[k]` at
+/// `shift * g^reverse_bits(i)`, where `g` generates `H`. Each row is followed by `extra_columns`
+/// zeros, which the caller may fill in.
+///
+/// This is the layout of Merkle leaves, so no transpose or bit reversal is needed afterwards, as it
+/// would be with one FFT per polynomial. All polynomials go through a single decimation-in-frequency
+/// FFT over the rows, which applies each twiddle factor to a whole row at once.
+pub fn coset_lde_batch_bit_reversed<F: Field>(
+    polys: &[PolynomialCoeffs<F>],
+    rate_bits: usize,
+    shift: F,
+    extra_columns: usize,
+    root_table: Option<&FftRootTable<F>>,
+) -> Vec<Vec<F>> {
+    let n = polys[0].len();
+    let lg_n = log2_strict(n);
+    assert!(
+        polys.iter().all(|p| p.len() == n),
+        "Polynomial degrees inconsistent"
+    );
+    let computed_root_table = root_table.is_none().then(|| shared_root_table(n));
+    let root_table = root_table
+        .or(computed_root_table.as_ref().map(Borrow::borrow))
+        .unwrap();
+    assert!(root_table.len() >= lg_n, "Root table too small");
+
+    // The first `rate_bits` layers would only combine coefficients with zeros. After them, the
+    // `b`th block of `n` rows holds the coefficients of `p(shift * g^reverse_bits(b) * X)`.
+    let mut block_shifts = F::primitive_root_of_unity(lg_n + rate_bits)
+        .powers()
+        .take(1 << rate_bits)
+        .map(|p| shift * p)
+        .collect::<Vec<_>>();
+    reverse_index_bits_in_place(&mut block_shifts);
+    let mut rows = vec![vec![F::ZERO; polys.len() + extra_columns]; n << rate_bits];
+    rows.par_chunks_mut(n)
+        .zip(block_shifts)
+        .for_each(|(block, block_shift)| {
+            let powers = block_shift.powers().take(n).collect::<Vec<_>>();
+            block
+                .par_iter_mut()
+                .zip(powers)
+                .enumerate()
+                .for_each(|(j, (row, power))| {
+                    for (x, p) in row.iter_mut().zip(polys) {
+                        *x = p.coeffs[j] * power;
+                    }
+                });
+        });
+
+    for lg_half_m in (0..lg_n).rev() {
+        let half_m = 1 << lg_half_m;
+        let twiddles = &root_table[lg_half_m][..half_m];
+        rows.par_chunks_mut(2 * half_m).for_each(|block| {
+            let (left, right) = block.split_at_mut(half_m);
+            if lg_half_m >= LG_PAR_BATCH_HALF {
+                left.par_iter_mut()
+                    .zip(right.par_iter_mut())
+                    .zip(twiddles.par_iter())
+                    .for_each(|((u, v), &twiddle)| dif_butterfly_rows(u, v, twiddle));
+            } else {
+                for ((u, v), &twiddle) in left.iter_mut().zip(right).zip(twiddles) {
+                    dif_butterfly_rows(u, v, twiddle);
+                }
+            }
+        });
+    }
+    rows
+}
+
+/// Replaces the rows `u` and `v` with `u + v` and `(u - v) * twiddle`.
+#[inline]
+fn dif_butterfly_rows<F: Field>(u: &mut [F], v: &mut [F], twiddle: F) {
+    let width = <F as Packable>::Packing::WIDTH;
+    let packed_len = u.len() - u.len() % width;
+    let (u_packed, u_rest) = u.split_at_mut(packed_len);
+    let (v_packed, v_rest) = v.split_at_mut(packed_len);
+    let twiddle_packed = <F as Packable>::Packing::from(twiddle);
+    for (x, y) in <F as Packable>::Packing::pack_slice_mut(u_packed)
+        .iter_mut()
+        .zip(<F as Packable>::Packing::pack_slice_mut(v_packed))
+    {
+        (*x, *y) = (*x + *y, (*x - *y) * twiddle_packed);
+    }
+    for (x, y) in u_rest.iter_mut().zip(v_rest) {
+        (*x, *y) = (*x + *y, (*x - *y) * twiddle);
+    }
+}
+
 /// Generic FFT implementation that works with both scalar and packed inputs.
+///
+/// Always inlined, so that the runtime AVX2 dispatch compiles it with AVX2 enabled.
 #[unroll_for_loops]
-fn fft_classic_simd<P: PackedField>(
+#[inline(always)]
+pub(crate) fn fft_classic_simd<P: PackedField>(
     values: &mut [P::Scalar],
     r: usize,
     lg_n: usize,
@@ -156,6 +363,52 @@ fn fft_classic_simd<P: PackedField>(
     }
 }
 
+/// Same as `fft_classic_simd`, but with the work of each layer split across threads. The first
+/// `LG_PAR_FFT_BLOCK` layers only mix elements within blocks of that size, so each block goes
+/// through them as a separate task, by calling `simd(block, r, LG_PAR_FFT_BLOCK)`. The remaining
+/// layers are split into chunks of butterflies, each done by `butterflies`.
+fn fft_classic_par<P: PackedField>(
+    values: &mut [P::Scalar],
+    r: usize,
+    lg_n: usize,
+    root_table: &FftRootTable<P::Scalar>,
+    simd: impl Fn(&mut [P::Scalar], usize, usize) + Sync,
+    butterflies: impl Fn(&mut [P], &mut [P], &[P]) + Sync,
+) {
+    let lg_packed_width = log2_strict(P::WIDTH);
+    debug_assert!(lg_n > LG_PAR_FFT_BLOCK && LG_PAR_FFT_BLOCK > lg_packed_width);
+
+    values
+        .par_chunks_mut(1 << LG_PAR_FFT_BLOCK)
+        .for_each(|block| simd(block, r, LG_PAR_FFT_BLOCK));
+
+    let packed_values = P::pack_slice_mut(values);
+    let packed_chunk = 1 << (LG_PAR_FFT_BLOCK - lg_packed_width);
+    for lg_half_m in max(r, LG_PAR_FFT_BLOCK)..lg_n {
+        let half_packed_m = 1 << (lg_half_m - lg_packed_width);
+        let omega_table = P::pack_slice(&root_table[lg_half_m][..]);
+        packed_values
+            .par_chunks_mut(2 * half_packed_m)
+            .for_each(|subarr| {
+                let (left, right) = subarr.split_at_mut(half_packed_m);
+                left.par_chunks_mut(packed_chunk)
+                    .zip(right.par_chunks_mut(packed_chunk))
+                    .zip(omega_table.par_chunks(packed_chunk))
+                    .for_each(|((left, right), omegas)| butterflies(left, right, omegas));
+            });
+    }
+}
+
+/// Replaces each `(left[i], right[i])` with `(u + t, u - t)`, where `u = left[i]` and
+/// `t = omegas[i] * right[i]`.
+#[inline(always)]
+pub(crate) fn fft_butterflies<P: PackedField>(left: &mut [P], right: &mut [P], omegas: &[P]) {
+    for ((u, v), &omega) in left.iter_mut().zip(right).zip(omegas) {
+        let t = omega * *v;
+        (*u, *v) = (*u + t, *u - t);
+    }
+}
+
 /// FFT implementation based on Section 32.3 of "Introduction to
 /// Algorithms" by Cormen et al.
 ///
@@ -191,26 +444,70 @@ pub(crate) fn fft_classic<F: Field>(values: &mut [F], r: usize, root_table: &Fft
         }
     }
 
-    let lg_packed_width = log2_strict(<F as Packable>::Packing::WIDTH);
+    #[cfg(all(target_arch = "x86_64", feature = "std", not(target_feature = "avx2")))]
+    if crate::arch::x86_64::avx2_runtime::fft_classic_layers(values, r, lg_n, root_table) {
+        return;
+    }
+    fft_classic_layers::<<F as Packable>::Packing>(values, r, lg_n, root_table);
+}
+
+/// The butterfly layers of `fft_classic`, using the packing `P`.
+pub(crate) fn fft_classic_layers<P: PackedField>(
+    values: &mut [P::Scalar],
+    r: usize,
+    lg_n: usize,
+    root_table: &FftRootTable<P::Scalar>,
+) {
+    fft_classic_layers_with::<P>(
+        values,
+        r,
+        lg_n,
+        root_table,
+        |values, r, lg_n| fft_classic_simd::<P>(values, r, lg_n, root_table),
+        fft_butterflies::<P>,
+    );
+}
+
+/// Like `fft_classic_layers`, but with the packed work done by `simd`, which must behave like
+/// `fft_classic_simd::<P>` with `root_table`, and by `butterflies`, which must behave like
+/// `fft_butterflies::<P>`. This lets callers run the hot loops from functions compiled with
+/// extra target features.
+pub(crate) fn fft_classic_layers_with<P: PackedField>(
+    values: &mut [P::Scalar],
+    r: usize,
+    lg_n: usize,
+    root_table: &FftRootTable<P::Scalar>,
+    simd: impl Fn(&mut [P::Scalar], usize, usize) + Sync,
+    butterflies: impl Fn(&mut [P], &mut [P], &[P]) + Sync,
+) {
+    let lg_packed_width = log2_strict(P::WIDTH);
     if lg_n <= lg_packed_width {
         // Need the slice to be at least the width of two packed vectors for the vectorized version
         // to work. Do this tiny problem in scalar.
-        fft_classic_simd::<F>(values, r, lg_n, root_table);
+        fft_classic_simd::<P::Scalar>(values, r, lg_n, root_table);
+    } else if cfg!(feature = "parallel") && lg_n >= LG_PAR_FFT_THRESHOLD {
+        fft_classic_par::<P>(values, r, lg_n, root_table, simd, butterflies);
     } else {
-        fft_classic_simd::<<F as Packable>::Packing>(values, r, lg_n, root_table);
+        simd(values, r, lg_n);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use alloc::vec;
     use alloc::vec::Vec;
 
-    use plonky2_util::{log2_ceil, log2_strict};
+    use plonky2_util::{log2_ceil, log2_strict, reverse_index_bits_in_place};
 
-    use crate::fft::{fft, fft_with_options, ifft};
+    use crate::fft::{
+        coset_lde_batch_bit_reversed, fft, fft_butterflies, fft_classic_par, fft_classic_simd,
+        fft_mixed_radix, fft_root_table, fft_with_options, ifft, ifft_mixed_radix,
+        mixed_radix_root_of_unity, LG_PAR_FFT_THRESHOLD,
+    };
     use crate::goldilocks_field::GoldilocksField;
+    use crate::packable::Packable;
     use crate::polynomial::{PolynomialCoeffs, PolynomialValues};
-    use crate::types::Field;
+    use crate::types::{Field, Sample};
 
     #[test]
     fn fft_and_ifft() {
@@ -222,7 +519,7 @@ mod tests {
         // "random", the last degree_padded-degree of them are zero.
         let coeffs = (0..degree)
             .map(|i| F::from_canonical_usize(i * 1337 % 100))
-            .chain(core::iter::repeat(F::ZERO).take(degree_padded - degree))
+            .chain(core::iter::repeat_n(F::ZERO, degree_padded - degree))
             .collect::<Vec<_>>();
         assert_eq!(coeffs.len(), degree_padded);
         let coefficients = PolynomialCoeffs { coeffs };
@@ -248,6 +545,86 @@ mod tests {
         }
     }
 
+    #[test]
+    fn par_fft_matches_serial() {
+        type F = GoldilocksField;
+        type P = <F as Packable>::Packing;
+        let lg_n = LG_PAR_FFT_THRESHOLD + 1;
+        let root_table = fft_root_table::<F>(1 << lg_n);
+        let values = F::rand_vec(1 << lg_n);
+        for r in [0, 1, lg_n - 2] {
+            let mut serial = values.clone();
+            fft_classic_simd::<P>(&mut serial, r, lg_n, &root_table);
+            let mut par = values.clone();
+            fft_classic_par::<P>(
+                &mut par,
+                r,
+                lg_n,
+                &root_table,
+                |values, r, lg_n| fft_classic_simd::<P>(values, r, lg_n, &root_table),
+                fft_butterflies::<P>,
+            );
+            assert_eq!(par, serial, "r = {r}");
+        }
+    }
+
+    #[test]
+    fn batch_coset_lde_matches_fft() {
+        type F = GoldilocksField;
+        let shift = F::coset_shift();
+        for (lg_n, rate_bits) in [(0, 1), (3, 0), (4, 2), (9, 3)] {
+            let polys = (0..7)
+                .map(|_| PolynomialCoeffs::new(F::rand_vec(1 << lg_n)))
+                .collect::<Vec<_>>();
+            let rows = coset_lde_batch_bit_reversed(&polys, rate_bits, shift, 2, None);
+
+            let mut expected = polys
+                .iter()
+                .map(|p| {
+                    p.lde(rate_bits)
+                        .coset_fft_with_options(shift, Some(rate_bits), None)
+                        .values
+                })
+                .collect::<Vec<_>>();
+            expected.push(vec![F::ZERO; 1 << (lg_n + rate_bits)]);
+            expected.push(vec![F::ZERO; 1 << (lg_n + rate_bits)]);
+            let mut expected_rows = (0..1 << (lg_n + rate_bits))
+                .map(|i| expected.iter().map(|column| column[i]).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            reverse_index_bits_in_place(&mut expected_rows);
+            assert_eq!(
+                rows, expected_rows,
+                "lg_n = {lg_n}, rate_bits = {rate_bits}"
+            );
+        }
+    }
+
+    #[test]
+    fn mixed_radix_fft_and_ifft() {
+        type F = GoldilocksField;
+        for n in [1, 2, 3, 5, 6, 12, 15, 60, 15 << 6] {
+            let omega = mixed_radix_root_of_unity::<F>(n).unwrap();
+            assert_eq!(omega.exp_u64(n as u64), F::ONE);
+            for p in [2, 3, 5].into_iter().filter(|&p| n.is_multiple_of(p)) {
+                assert_ne!(omega.exp_u64((n / p) as u64), F::ONE, "n = {n}");
+            }
+
+            let coeffs = PolynomialCoeffs::new(F::rand_vec(n));
+            let values = fft_mixed_radix(&coeffs.coeffs);
+            let expected = omega
+                .powers()
+                .take(n)
+                .map(|x| coeffs.eval(x))
+                .collect::<Vec<_>>();
+            assert_eq!(values, expected, "n = {n}");
+            assert_eq!(ifft_mixed_radix(&values), coeffs.coeffs, "n = {n}");
+        }
+
+        // GoldilocksField's multiplicative group has order 2^32 * 3 * 5 * 17 * 257 * 65537.
+        assert!(mixed_radix_root_of_unity::<F>(7).is_none());
+        assert!(mixed_radix_root_of_unity::<F>(9).is_none());
+    }
+
     fn evaluate_naive<F: Field>(coefficients: &PolynomialCoeffs<F>) -> PolynomialValues<F> {
         let degree = coefficients.len();
         let degree_padded = 1 << log2_ceil(degree);
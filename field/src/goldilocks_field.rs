@@ -5,7 +5,8 @@ use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAss
 
 use num::{BigUint, Integer, ToPrimitive};
 use plonky2_util::{assume, branch_hint};
-use serde::{Deserialize, Serialize};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::ops::Square;
 use crate::types::{Field, Field64, PrimeField, PrimeField64, Sample};
@@ -15,15 +16,75 @@ const EPSILON: u64 = (1 << 32) - 1;
 /// A field selected to have fast reduction.
 ///
 /// Its order is 2^64 - 2^32 + 1.
+///
+/// With the `constant-time` feature, additions, subtractions, reductions and inversions avoid
+/// branching on the values of their operands, at a small cost in speed. This is meant for provers
+/// whose witness is secret and which share hardware with untrusted code. Note that the compiler
+/// gives no formal guarantee that branch-free source code stays branch-free.
 /// ```ignore
 /// P = 2**64 - EPSILON
 ///   = 2**64 - 2**32 + 1
 ///   = 2**32 * (2**32 - 1) + 1
 /// ```
-#[derive(Copy, Clone, Serialize, Deserialize)]
+#[derive(Copy, Clone)]
 #[repr(transparent)]
 pub struct GoldilocksField(pub u64);
 
+/// Human-readable formats such as JSON get the canonical value as a number, while binary formats
+/// get the raw `u64`, as with a derived implementation.
+///
+/// Upstream plonky2 writes the raw `u64` to JSON too. As it may be non-canonical, which is rejected
+/// here, proofs from upstream should be read from its binary encodings instead.
+impl Serialize for GoldilocksField {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_u64(self.to_canonical_u64())
+        } else {
+            serializer.serialize_newtype_struct("GoldilocksField", &self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GoldilocksField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            // Decimal strings are accepted too, for JSON written by tools that would otherwise round
+            // integers above 2^53.
+            deserializer.deserialize_any(GoldilocksFieldVisitor)
+        } else {
+            deserializer.deserialize_newtype_struct("GoldilocksField", GoldilocksFieldVisitor)
+        }
+    }
+}
+
+struct GoldilocksFieldVisitor;
+
+impl<'de> Visitor<'de> for GoldilocksFieldVisitor {
+    type Value = GoldilocksField;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "a Goldilocks field element")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        if v >= GoldilocksField::ORDER {
+            return Err(E::custom("non-canonical Goldilocks field element"));
+        }
+        Ok(GoldilocksField(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        self.visit_u64(v.parse::<u64>().map_err(E::custom)?)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        u64::deserialize(deserializer).map(GoldilocksField)
+    }
+}
+
 impl Default for GoldilocksField {
     fn default() -> Self {
         Self::ZERO
@@ -106,7 +167,9 @@ impl Field for GoldilocksField {
     /// The following code has been adapted from winterfell/math/src/field/f64/mod.rs
     /// located at <https://github.com/facebook/winterfell>.
     fn try_inverse(&self) -> Option<Self> {
-        if self.is_zero() {
+        // In constant-time mode, the exponentiation is done regardless of whether `self` is zero,
+        // and only the final result depends on it.
+        if !cfg!(feature = "constant-time") && self.is_zero() {
             return None;
         }
 
@@ -143,7 +206,8 @@ impl Field for GoldilocksField {
         let t63 = exp_acc::<32>(t31, t31);
 
         // compute base^1111111111111111111111111111111011111111111111111111111111111111
-        Some(t63.square() * *self)
+        let inverse = t63.square() * *self;
+        (!self.is_zero()).then_some(inverse)
     }
 
     fn from_noncanonical_biguint(n: BigUint) -> Self {
@@ -183,8 +247,10 @@ impl Field for GoldilocksField {
 
     #[inline]
     fn multiply_accumulate(&self, x: Self, y: Self) -> Self {
+        let (lo, hi) = mul_wide(x.0, y.0);
+        let (lo, carry) = lo.overflowing_add(self.0);
         // u64 + u64 * u64 cannot overflow.
-        reduce128((self.0 as u128) + (x.0 as u128) * (y.0 as u128))
+        reduce128_parts((lo, hi + carry as u64))
     }
 }
 
@@ -217,7 +283,9 @@ impl PrimeField64 for GoldilocksField {
     fn to_canonical_u64(&self) -> u64 {
         let mut c = self.0;
         // We only need one condition subtraction, since 2 * ORDER would not fit in a u64.
-        if c >= Self::ORDER {
+        if cfg!(feature = "constant-time") {
+            c -= Self::ORDER * ((c >= Self::ORDER) as u64);
+        } else if c >= Self::ORDER {
             c -= Self::ORDER;
         }
         c
@@ -234,7 +302,10 @@ impl Neg for GoldilocksField {
 
     #[inline]
     fn neg(self) -> Self {
-        if self.is_zero() {
+        if cfg!(feature = "constant-time") {
+            // Negating zero gives `ORDER`, which is a valid (noncanonical) representation of zero.
+            Self(Self::ORDER - self.to_canonical_u64())
+        } else if self.is_zero() {
             Self::ZERO
         } else {
             Self(Self::ORDER - self.to_canonical_u64())
@@ -250,7 +321,9 @@ impl Add for GoldilocksField {
     fn add(self, rhs: Self) -> Self {
         let (sum, over) = self.0.overflowing_add(rhs.0);
         let (mut sum, over) = sum.overflowing_add((over as u64) * EPSILON);
-        if over {
+        if cfg!(feature = "constant-time") {
+            sum += (over as u64) * EPSILON; // Cannot overflow.
+        } else if over {
             // NB: self.0 > Self::ORDER && rhs.0 > Self::ORDER is necessary but not sufficient for
             // double-overflow.
             // This assume does two things:
@@ -287,7 +360,9 @@ impl Sub for GoldilocksField {
     fn sub(self, rhs: Self) -> Self {
         let (diff, under) = self.0.overflowing_sub(rhs.0);
         let (mut diff, under) = diff.overflowing_sub((under as u64) * EPSILON);
-        if under {
+        if cfg!(feature = "constant-time") {
+            diff -= (under as u64) * EPSILON; // Cannot underflow.
+        } else if under {
             // NB: self.0 < EPSILON - 1 && rhs.0 > Self::ORDER is necessary but not sufficient for
             // double-underflow.
             // This assume does two things:
@@ -315,7 +390,7 @@ impl Mul for GoldilocksField {
 
     #[inline]
     fn mul(self, rhs: Self) -> Self {
-        reduce128((self.0 as u128) * (rhs.0 as u128))
+        reduce128_parts(mul_wide(self.0, rhs.0))
     }
 }
 
@@ -400,12 +475,19 @@ fn reduce96((x_lo, x_hi): (u64, u32)) -> GoldilocksField {
 /// field order and `2^64`.
 #[inline]
 fn reduce128(x: u128) -> GoldilocksField {
-    let (x_lo, x_hi) = split(x); // This is a no-op
+    reduce128_parts(split(x)) // This is a no-op
+}
+
+/// Like `reduce128`, but takes the low and high halves of the input.
+#[inline(always)]
+fn reduce128_parts((x_lo, x_hi): (u64, u64)) -> GoldilocksField {
     let x_hi_hi = x_hi >> 32;
     let x_hi_lo = x_hi & EPSILON;
 
     let (mut t0, borrow) = x_lo.overflowing_sub(x_hi_hi);
-    if borrow {
+    if cfg!(feature = "constant-time") {
+        t0 -= (borrow as u64) * EPSILON; // Cannot underflow.
+    } else if borrow {
         branch_hint(); // A borrow is exceedingly rare. It is faster to branch.
         t0 -= EPSILON; // Cannot underflow.
     }
@@ -419,6 +501,35 @@ const fn split(x: u128) -> (u64, u64) {
     (x as u64, (x >> 64) as u64)
 }
 
+/// Returns the low and high halves of the 128-bit product `x * y`.
+#[inline(always)]
+const fn mul_wide(x: u64, y: u64) -> (u64, u64) {
+    if cfg!(target_arch = "wasm32") {
+        mul_wide_u32_limbs(x, y)
+    } else {
+        split((x as u128) * (y as u128))
+    }
+}
+
+/// Like `mul_wide`, but only multiplies 32-bit limbs into 64-bit products. On wasm32, which has no
+/// 64x64 to 128-bit multiplication, this is much faster than a `u128` multiplication, which goes
+/// through a generic software routine.
+#[inline(always)]
+const fn mul_wide_u32_limbs(x: u64, y: u64) -> (u64, u64) {
+    let (x_lo, x_hi) = (x & EPSILON, x >> 32);
+    let (y_lo, y_hi) = (y & EPSILON, y >> 32);
+    let lo_lo = x_lo * y_lo;
+    let lo_hi = x_lo * y_hi;
+    let hi_lo = x_hi * y_lo;
+    let hi_hi = x_hi * y_hi;
+
+    // Bits 32 to 63 of the product, with carries. This is less than 3 * 2^32.
+    let mid = (lo_lo >> 32) + (lo_hi & EPSILON) + (hi_lo & EPSILON);
+    let lo = (lo_lo & EPSILON) | (mid << 32);
+    let hi = hi_hi + (lo_hi >> 32) + (hi_lo >> 32) + (mid >> 32);
+    (lo, hi)
+}
+
 /// Reduce the value x_lo + x_hi * 2^128 to an element in the
 /// Goldilocks field.
 ///
@@ -433,7 +544,9 @@ pub(crate) unsafe fn reduce160(x_lo: u128, x_hi: u32) -> GoldilocksField {
 
     // sub + jc (should fuse)
     let (mut t0, borrow) = x_lo.overflowing_sub(x_hi);
-    if borrow {
+    if cfg!(feature = "constant-time") {
+        t0 -= (borrow as u64) * EPSILON; // Cannot underflow if x_hi is canonical.
+    } else if borrow {
         // The maximum possible value of x is (2^64 - 1)^2 * 4 * 7 < 2^133,
         // so x_hi < 2^37. A borrow will happen roughly one in 134 million
         // times, so it's best to branch.
@@ -456,8 +569,24 @@ fn exp_acc<const N: usize>(base: GoldilocksField, tail: GoldilocksField) -> Gold
 
 #[cfg(test)]
 mod tests {
+    use rand::rngs::OsRng;
+    use rand::Rng;
+
+    use super::{mul_wide_u32_limbs, split};
     use crate::{test_field_arithmetic, test_prime_field_arithmetic};
 
     test_prime_field_arithmetic!(crate::goldilocks_field::GoldilocksField);
     test_field_arithmetic!(crate::goldilocks_field::GoldilocksField);
+
+    #[test]
+    fn test_mul_wide_u32_limbs() {
+        let mut rng = OsRng;
+        let edge_cases = [0, 1, u32::MAX as u64, 1 << 32, u64::MAX - 1, u64::MAX];
+        let random = (0..1000).map(|_| rng.gen::<u64>()).collect::<Vec<_>>();
+        for &x in edge_cases.iter().chain(&random) {
+            for &y in edge_cases.iter().chain(&random[..10]) {
+                assert_eq!(mul_wide_u32_limbs(x, y), split(x as u128 * y as u128));
+            }
+        }
+    }
 }
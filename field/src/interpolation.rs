@@ -1,7 +1,9 @@
+use alloc::vec;
 use alloc::vec::Vec;
 
 use plonky2_util::log2_ceil;
 
+use crate::extension::{Extendable, FieldExtension};
 use crate::fft::ifft;
 use crate::polynomial::{PolynomialCoeffs, PolynomialValues};
 use crate::types::Field;
@@ -36,15 +38,17 @@ pub fn interpolate<F: Field>(points: &[(F, F)], x: F, barycentric_weights: &[F])
         }
     }
 
-    let l_x: F = points.iter().map(|&(x_i, _y_i)| x - x_i).product();
+    let differences = points
+        .iter()
+        .map(|&(x_i, _y_i)| x - x_i)
+        .collect::<Vec<_>>();
+    let l_x: F = differences.iter().copied().product();
 
-    let sum = (0..points.len())
-        .map(|i| {
-            let x_i = points[i].0;
-            let y_i = points[i].1;
-            let w_i = barycentric_weights[i];
-            w_i / (x - x_i) * y_i
-        })
+    let sum = F::batch_multiplicative_inverse(&differences)
+        .into_iter()
+        .zip(points)
+        .zip(barycentric_weights)
+        .map(|((diff_inv, &(_x_i, y_i)), &w_i)| w_i * diff_inv * y_i)
         .sum();
 
     l_x * sum
@@ -64,6 +68,70 @@ pub fn barycentric_weights<F: Field>(points: &[(F, F)]) -> Vec<F> {
     )
 }
 
+/// Evaluates at `x` the `i`th Lagrange basis polynomial of the coset `shift * H`, where `H` is the
+/// subgroup of order `n = 2^n_log` generated by `g = F::primitive_root_of_unity(n_log)`. This is
+/// the polynomial of degree less than `n` which is one at `shift * g^i`, and zero at the other
+/// points of the coset.
+pub fn eval_lagrange_basis_on_coset<F: Field>(n_log: usize, shift: F, i: usize, x: F) -> F {
+    let x_i = shift * F::primitive_root_of_unity(n_log).exp_u64(i as u64);
+    if x == x_i {
+        // The formula below would divide zero by zero.
+        return F::ONE;
+    }
+
+    // L_i(x) = Z(x) / (Z'(x_i) (x - x_i)), where Z(x) = x^n - shift^n vanishes on the coset and
+    // Z'(x_i) = n x_i^(n - 1) = n shift^n / x_i.
+    let shift_n = shift.exp_power_of_2(n_log);
+    let z_x = x.exp_power_of_2(n_log) - shift_n;
+    z_x * x_i / (F::from_canonical_usize(1 << n_log) * shift_n * (x - x_i))
+}
+
+/// Returns the evaluations at `x` of all Lagrange basis polynomials of the coset `shift * H` (see
+/// [`eval_lagrange_basis_on_coset`]), in order. These are the weights for which
+/// `p(x) = sum_i weights[i] * p(shift * g^i)` for any polynomial `p` of degree less than
+/// `2^n_log`, so that many polynomials can be evaluated at `x` from their values on the coset with
+/// [`barycentric_eval_batch`], sharing a single batch inversion.
+pub fn barycentric_weights_on_coset<F: Field>(n_log: usize, shift: F, x: F) -> Vec<F> {
+    let n = 1 << n_log;
+    let points = F::primitive_root_of_unity(n_log)
+        .powers()
+        .take(n)
+        .map(|g_i| shift * g_i)
+        .collect::<Vec<_>>();
+    if let Some(i) = points.iter().position(|&x_i| x_i == x) {
+        let mut weights = vec![F::ZERO; n];
+        weights[i] = F::ONE;
+        return weights;
+    }
+
+    let shift_n = shift.exp_power_of_2(n_log);
+    let scale = (x.exp_power_of_2(n_log) - shift_n) / (F::from_canonical_usize(n) * shift_n);
+    let denominators = points.iter().map(|&x_i| x - x_i).collect::<Vec<_>>();
+    F::batch_multiplicative_inverse(&denominators)
+        .into_iter()
+        .zip(points)
+        .map(|(d_inv, x_i)| scale * x_i * d_inv)
+        .collect()
+}
+
+/// Evaluates a batch of `num_polys` polynomials over the base field at a point of the extension
+/// field, given their values over a domain and the weights of that point for the domain (e.g. from
+/// [`barycentric_weights_on_coset`]). `rows` yields, for each point of the domain in order, the
+/// values of all polynomials there.
+pub fn barycentric_eval_batch<'a, F: Field + Extendable<D>, const D: usize>(
+    weights: &[F::Extension],
+    rows: impl IntoIterator<Item = &'a [F]>,
+    num_polys: usize,
+) -> Vec<F::Extension> {
+    let mut evals = vec![F::Extension::ZERO; num_polys];
+    for (&weight, row) in weights.iter().zip(rows) {
+        for (eval, &y) in evals.iter_mut().zip(row) {
+            *eval += weight.scalar_mul(y);
+        }
+    }
+    evals
+}
+
 /// Interpolate the linear polynomial passing through `points` on `x`.
 pub fn interpolate2<F: Field>(points: [(F, F); 2], x: F) -> F {
     // a0 -> a1
@@ -78,6 +146,7 @@ pub fn interpolate2<F: Field>(points: [(F, F); 2], x: F) -> F {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::extension::quadratic::QuadraticExtension;
     use crate::extension::quartic::QuarticExtension;
     use crate::goldilocks_field::GoldilocksField;
     use crate::polynomial::PolynomialCoeffs;
@@ -127,6 +196,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn lagrange_basis_on_coset() {
+        type F = GoldilocksField;
+        let n_log = 3;
+        let shift = F::coset_shift();
+        let coset = F::two_adic_subgroup(n_log)
+            .into_iter()
+            .map(|g_i| shift * g_i)
+            .collect::<Vec<_>>();
+        for i in 0..coset.len() {
+            for (j, &x_j) in coset.iter().enumerate() {
+                let expected = if i == j { F::ONE } else { F::ZERO };
+                assert_eq!(eval_lagrange_basis_on_coset(n_log, shift, i, x_j), expected);
+            }
+        }
+
+        let x = F::rand();
+        let weights = barycentric_weights_on_coset(n_log, shift, x);
+        for (i, &w) in weights.iter().enumerate() {
+            assert_eq!(w, eval_lagrange_basis_on_coset(n_log, shift, i, x));
+        }
+        assert_eq!(
+            barycentric_weights_on_coset(n_log, shift, coset[5]),
+            (0..coset.len())
+                .map(|i| if i == 5 { F::ONE } else { F::ZERO })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn barycentric_batch() {
+        type F = GoldilocksField;
+        type FE = QuadraticExtension<F>;
+        let n_log = 4;
+        let shift = F::coset_shift();
+        let polys = (0..5)
+            .map(|_| PolynomialCoeffs::new(F::rand_vec(1 << n_log)))
+            .collect::<Vec<_>>();
+        let values = polys
+            .iter()
+            .map(|p| p.coset_fft(shift).values)
+            .collect::<Vec<_>>();
+        let rows = (0..1 << n_log)
+            .map(|i| values.iter().map(|v| v[i]).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let x = FE::rand();
+        let weights = barycentric_weights_on_coset(n_log, shift.into(), x);
+        let evals = barycentric_eval_batch::<F, 2>(&weights, rows.iter().map(|r| &r[..]), 5);
+        for (p, eval) in polys.iter().zip(evals) {
+            assert_eq!(p.to_extension::<2>().eval(x), eval);
+        }
+    }
+
     fn eval_naive<F: Field>(coeffs: &PolynomialCoeffs<F>, domain: &[F]) -> Vec<(F, F)> {
         domain.iter().map(|&x| (x, coeffs.eval(x))).collect()
     }
@@ -4,19 +4,23 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(missing_debug_implementations)]
 #![feature(specialization)]
-#![cfg_attr(target_arch = "x86_64", feature(stdarch_x86_avx512))]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
 extern crate alloc;
 
 pub(crate) mod arch;
+#[cfg(all(target_arch = "x86_64", feature = "std", not(target_feature = "avx2")))]
+pub use arch::x86_64::avx2_runtime::set_avx2_dispatch;
 
 pub mod batch_util;
 pub mod cosets;
+#[cfg(feature = "std")]
+pub mod domain_cache;
 pub mod extension;
 pub mod fft;
 pub mod goldilocks_extensions;
 pub mod goldilocks_field;
+pub mod goldilocks_montgomery_field;
 pub mod interpolation;
 pub mod ops;
 pub mod packable;
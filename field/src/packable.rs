@@ -38,3 +38,8 @@ impl Packable for crate::goldilocks_field::GoldilocksField {
 impl Packable for crate::goldilocks_field::GoldilocksField {
     type Packing = crate::arch::x86_64::avx512_goldilocks_field::Avx512GoldilocksField;
 }
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+impl Packable for crate::goldilocks_field::GoldilocksField {
+    type Packing = crate::arch::aarch64::neon_goldilocks_field::NeonGoldilocksField;
+}
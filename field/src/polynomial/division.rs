@@ -70,6 +70,33 @@ impl<F: Field> PolynomialCoeffs<F> {
         }
     }
 
+    /// Division by the vanishing polynomial `X^n - 1` of the subgroup of size `n`.
+    /// Returns `(q, r)` such that `self = q * (X^n - 1) + r`, where `r` has degree less than `n`.
+    /// This takes linear time, unlike the general [`Self::div_rem`].
+    pub fn div_rem_by_vanishing(&self, n: usize) -> (Self, Self) {
+        assert!(
+            n > 0,
+            "The vanishing polynomial of an empty subgroup is not defined"
+        );
+        let len = self.degree_plus_one();
+        if len <= n {
+            return (Self::empty(), self.trimmed());
+        }
+
+        let mut remainder = self.coeffs[..len].to_vec();
+        let mut quotient = vec![F::ZERO; len - n];
+        // X^i = X^(i - n) (X^n - 1) + X^(i - n).
+        for i in (n..len).rev() {
+            let c = remainder[i];
+            quotient[i - n] = c;
+            remainder[i - n] += c;
+        }
+        remainder.truncate(n);
+        let mut remainder = Self::new(remainder);
+        remainder.trim();
+        (Self::new(quotient), remainder)
+    }
+
     /// Let `self=p(X)`, this returns `(p(X)-p(z))/(X-z)`.
     /// See <https://en.wikipedia.org/wiki/Horner%27s_method>
     pub fn divide_by_linear(&self, z: F) -> PolynomialCoeffs<F> {
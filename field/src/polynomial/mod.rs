@@ -1,3 +1,16 @@
+//! Univariate polynomials over a field, in coefficient form ([`PolynomialCoeffs`]) or in
+//! point-value form over a multiplicative subgroup ([`PolynomialValues`]).
+//!
+//! The two forms are converted into each other with FFTs, optionally over a coset of the subgroup
+//! or with a low-degree extension to a larger subgroup. Both support the usual ring operations:
+//! in coefficient form these are exact, while in point-value form they act pointwise, i.e. modulo
+//! the vanishing polynomial `X^n - 1` of the subgroup of size `n`. Coefficient-form polynomials
+//! can also be divided (see [`PolynomialCoeffs::div_rem`] and
+//! [`PolynomialCoeffs::div_rem_by_vanishing`]) and composed.
+//!
+//! All operations on two polynomials in point-value form expect them to have the same number of
+//! values.
+
 pub(crate) mod division;
 
 use alloc::vec;
@@ -25,20 +38,24 @@ pub struct PolynomialValues<F: Field> {
 }
 
 impl<F: Field> PolynomialValues<F> {
+    /// Creates a polynomial from its values, whose number must be a power of two.
     pub fn new(values: Vec<F>) -> Self {
         // Check that a subgroup exists of this size, which should be a power of two.
         debug_assert!(log2_strict(values.len()) <= F::TWO_ADICITY);
         PolynomialValues { values }
     }
 
+    /// The constant polynomial `value`, given by `len` values.
     pub fn constant(value: F, len: usize) -> Self {
         Self::new(vec![value; len])
     }
 
+    /// The zero polynomial, given by `len` values.
     pub fn zero(len: usize) -> Self {
         Self::constant(F::ZERO, len)
     }
 
+    /// Whether all values are zero.
     pub fn is_zero(&self) -> bool {
         self.values.iter().all(|x| x.is_zero())
     }
@@ -55,6 +72,8 @@ impl<F: Field> PolynomialValues<F> {
         self.values.len()
     }
 
+    /// Interpolates the values, returning the coefficients of the polynomial of degree less than
+    /// `self.len()` which takes them.
     pub fn ifft(self) -> PolynomialCoeffs<F> {
         ifft(self)
     }
@@ -72,10 +91,13 @@ impl<F: Field> PolynomialValues<F> {
         shifted_coeffs
     }
 
+    /// Applies [`Self::lde`] to each of `polys`.
     pub fn lde_multiple(polys: Vec<Self>, rate_bits: usize) -> Vec<Self> {
         polys.into_iter().map(|p| p.lde(rate_bits)).collect()
     }
 
+    /// Low-degree extend `Self` (seen as evaluations over the subgroup) onto the subgroup of size
+    /// `self.len() << rate_bits`.
     pub fn lde(self, rate_bits: usize) -> Self {
         let coeffs = ifft(self).lde(rate_bits);
         fft_with_options(coeffs, Some(rate_bits), None)
@@ -87,10 +109,12 @@ impl<F: Field> PolynomialValues<F> {
         coeffs.coset_fft_with_options(F::coset_shift(), Some(rate_bits), None)
     }
 
+    /// The degree of the interpolated polynomial, or 0 for the zero polynomial.
     pub fn degree(&self) -> usize {
         self.degree_plus_one().saturating_sub(1)
     }
 
+    /// The degree of the interpolated polynomial plus one, or 0 for the zero polynomial.
     pub fn degree_plus_one(&self) -> usize {
         self.clone().ifft().degree_plus_one()
     }
@@ -110,6 +134,57 @@ impl<F: Field> From<Vec<F>> for PolynomialValues<F> {
     }
 }
 
+impl<F: Field> Add for &PolynomialValues<F> {
+    type Output = PolynomialValues<F>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let values = self
+            .values
+            .iter()
+            .zip_eq(&rhs.values)
+            .map(|(&a, &b)| a + b)
+            .collect();
+        PolynomialValues::new(values)
+    }
+}
+
+impl<F: Field> Sub for &PolynomialValues<F> {
+    type Output = PolynomialValues<F>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let values = self
+            .values
+            .iter()
+            .zip_eq(&rhs.values)
+            .map(|(&a, &b)| a - b)
+            .collect();
+        PolynomialValues::new(values)
+    }
+}
+
+/// Pointwise multiplication, which gives the product of the polynomials modulo `X^n - 1`.
+impl<F: Field> Mul for &PolynomialValues<F> {
+    type Output = PolynomialValues<F>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let values = self
+            .values
+            .iter()
+            .zip_eq(&rhs.values)
+            .map(|(&a, &b)| a * b)
+            .collect();
+        PolynomialValues::new(values)
+    }
+}
+
+impl<F: Field> Mul<F> for &PolynomialValues<F> {
+    type Output = PolynomialValues<F>;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        PolynomialValues::new(self.values.iter().map(|&x| x * rhs).collect())
+    }
+}
+
 /// A polynomial in coefficient form.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
@@ -118,6 +193,7 @@ pub struct PolynomialCoeffs<F: Field> {
 }
 
 impl<F: Field> PolynomialCoeffs<F> {
+    /// Creates a polynomial from its coefficients, starting with the constant one.
     pub fn new(coeffs: Vec<F>) -> Self {
         PolynomialCoeffs { coeffs }
     }
@@ -127,10 +203,12 @@ impl<F: Field> PolynomialCoeffs<F> {
         Self::new(Vec::new())
     }
 
+    /// The zero polynomial, given by `len` coefficients.
     pub fn zero(len: usize) -> Self {
         Self::new(vec![F::ZERO; len])
     }
 
+    /// Whether all coefficients are zero.
     pub fn is_zero(&self) -> bool {
         self.coeffs.iter().all(|x| x.is_zero())
     }
@@ -141,10 +219,13 @@ impl<F: Field> PolynomialCoeffs<F> {
         self.coeffs.len()
     }
 
+    /// The base-2 logarithm of the number of coefficients, which must be a power of two.
     pub fn log_len(&self) -> usize {
         log2_strict(self.len())
     }
 
+    /// Splits the coefficients into polynomials of `chunk_size` coefficients, the last of which
+    /// may be shorter.
     pub fn chunks(&self, chunk_size: usize) -> Vec<Self> {
         self.coeffs
             .chunks(chunk_size)
@@ -152,6 +233,7 @@ impl<F: Field> PolynomialCoeffs<F> {
             .collect()
     }
 
+    /// Evaluates the polynomial at `x`.
     pub fn eval(&self, x: F) -> F {
         self.coeffs
             .iter()
@@ -169,6 +251,8 @@ impl<F: Field> PolynomialCoeffs<F> {
             .fold(acc, |acc, (&x, &c)| acc + c * x)
     }
 
+    /// Evaluates the polynomial, whose coefficients are in an extension field, at a point of the
+    /// base field.
     pub fn eval_base<const D: usize>(&self, x: F::BaseField) -> F
     where
         F: FieldExtension<D>,
@@ -192,14 +276,18 @@ impl<F: Field> PolynomialCoeffs<F> {
             .fold(acc, |acc, (&x, &c)| acc + x.scalar_mul(c))
     }
 
+    /// Applies [`Self::lde`] to each of `polys`.
     pub fn lde_multiple(polys: Vec<&Self>, rate_bits: usize) -> Vec<Self> {
         polys.into_iter().map(|p| p.lde(rate_bits)).collect()
     }
 
+    /// Pads the coefficients with zeros, to `self.len() << rate_bits` of them. Their FFT is then
+    /// the low-degree extension of the polynomial.
     pub fn lde(&self, rate_bits: usize) -> Self {
         self.padded(self.len() << rate_bits)
     }
 
+    /// Pads the coefficients with zeros, to `new_len` of them. Fails if there already are more.
     pub fn pad(&mut self, new_len: usize) -> Result<()> {
         ensure!(
             new_len >= self.len(),
@@ -211,6 +299,8 @@ impl<F: Field> PolynomialCoeffs<F> {
         Ok(())
     }
 
+    /// Like [`Self::pad`], but returns a padded copy, and panics if there are more than `new_len`
+    /// coefficients.
     pub fn padded(&self, new_len: usize) -> Self {
         let mut poly = self.clone();
         poly.pad(new_len).unwrap();
@@ -259,10 +349,14 @@ impl<F: Field> PolynomialCoeffs<F> {
         Self::new(self.trimmed().coeffs.into_iter().rev().collect())
     }
 
+    /// Evaluates the polynomial over the subgroup of size `self.len()`, which must be a power of
+    /// two.
     pub fn fft(self) -> PolynomialValues<F> {
         fft(self)
     }
 
+    /// Like [`Self::fft`], but with the knowledge that only the first `self.len() >> zero_factor`
+    /// coefficients may be non-zero, and with precomputed roots of unity.
     pub fn fft_with_options(
         self,
         zero_factor: Option<usize>,
@@ -292,6 +386,24 @@ impl<F: Field> PolynomialCoeffs<F> {
         modified_poly.fft_with_options(zero_factor, root_table)
     }
 
+    /// Returns the composition `self(inner(X))`.
+    pub fn compose(&self, inner: &Self) -> Self {
+        // Horner's method, with polynomial coefficients.
+        let mut result = Self::empty();
+        for &c in self.coeffs.iter().rev() {
+            let mut product = if result.coeffs.is_empty() {
+                Self::zero(1)
+            } else {
+                &result * inner
+            };
+            product.coeffs[0] += c;
+            product.trim();
+            result = product;
+        }
+        result
+    }
+
+    /// Maps the coefficients into the extension field.
     pub fn to_extension<const D: usize>(&self) -> PolynomialCoeffs<F::Extension>
     where
         F: Extendable<D>,
@@ -299,6 +411,7 @@ impl<F: Field> PolynomialCoeffs<F> {
         PolynomialCoeffs::new(self.coeffs.iter().map(|&c| c.into()).collect())
     }
 
+    /// Maps the coefficients into the extension field, and multiplies the result by `rhs`.
     pub fn mul_extension<const D: usize>(&self, rhs: F::Extension) -> PolynomialCoeffs<F::Extension>
     where
         F: Extendable<D>,
@@ -598,6 +711,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_division_by_vanishing() {
+        type F = GoldilocksField;
+        let mut rng = OsRng;
+        let n = 1 << rng.gen_range(0..8);
+        let a_deg = rng.gen_range(1..1_000);
+        let a = PolynomialCoeffs::new(F::rand_vec(a_deg));
+        let (q, r) = a.div_rem_by_vanishing(n);
+        assert!(r.len() <= n);
+        for _ in 0..1000 {
+            let x = F::rand();
+            assert_eq!(
+                a.eval(x),
+                (x.exp_u64(n as u64) - F::ONE) * q.eval(x) + r.eval(x)
+            );
+        }
+
+        // The quotient and remainder are recovered exactly.
+        let q = PolynomialCoeffs::new(F::rand_vec(a_deg));
+        let r = PolynomialCoeffs::new(F::rand_vec(n));
+        let mut vanishing = PolynomialCoeffs::zero(n + 1);
+        vanishing.coeffs[0] = F::NEG_ONE;
+        vanishing.coeffs[n] = F::ONE;
+        let a = &(&q * &vanishing) + &r;
+        assert_eq!(a.div_rem_by_vanishing(n), (q, r));
+    }
+
+    #[test]
+    fn test_compose() {
+        type F = GoldilocksField;
+        let mut rng = OsRng;
+        let a = PolynomialCoeffs::new(F::rand_vec(rng.gen_range(0..20)));
+        let b = PolynomialCoeffs::new(F::rand_vec(rng.gen_range(0..20)));
+        let composed = a.compose(&b);
+        for _ in 0..100 {
+            let x = F::rand();
+            assert_eq!(composed.eval(x), a.eval(b.eval(x)));
+        }
+    }
+
+    #[test]
+    fn test_values_arithmetic() {
+        type F = GoldilocksField;
+        let n = 1 << 5;
+        let a = PolynomialCoeffs::new(F::rand_vec(n / 2));
+        let b = PolynomialCoeffs::new(F::rand_vec(n / 2));
+        let (a_values, b_values) = (a.padded(n).fft(), b.padded(n).fft());
+        let c = F::rand();
+
+        assert_eq!((&a_values + &b_values).ifft(), &a.padded(n) + &b.padded(n));
+        assert_eq!((&a_values - &b_values).ifft(), &a.padded(n) - &b.padded(n));
+        assert_eq!((&a_values * &b_values).ifft(), &a * &b);
+        assert_eq!((&a_values * c).ifft(), &a.padded(n) * c);
+    }
+
     // Test to see which polynomial division method is faster for divisions of the type
     // `(X^n - 1)/(X - a)
     #[test]
@@ -2,9 +2,10 @@ use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::{Debug, Display};
 use core::hash::Hash;
-use core::iter::{Product, Sum};
+use core::iter::{self, Product, Sum};
 use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+use anyhow::{ensure, Result};
 use num::bigint::BigUint;
 use num::{Integer, One, ToPrimitive, Zero};
 use plonky2_util::bits_u64;
@@ -377,6 +378,24 @@ pub trait Field:
         product
     }
 
+    /// Computes `self^e` for each `e` in `powers`. The repeated squares of `self` are computed
+    /// once and shared, so each exponentiation only costs a multiplication per set bit.
+    fn batch_exp_u64(&self, powers: &[u64]) -> Vec<Self> {
+        let max_bits = powers.iter().map(|&p| bits_u64(p)).max().unwrap_or(0);
+        let squares = iter::successors(Some(*self), |x| Some(x.square()))
+            .take(max_bits)
+            .collect::<Vec<_>>();
+        powers
+            .iter()
+            .map(|&power| {
+                (0..bits_u64(power))
+                    .filter(|&j| (power >> j & 1) != 0)
+                    .map(|j| squares[j])
+                    .product()
+            })
+            .collect()
+    }
+
     fn exp_biguint(&self, power: &BigUint) -> Self {
         let mut result = Self::ONE;
         for &digit in power.to_u64_digits().iter().rev() {
@@ -556,6 +575,9 @@ pub trait Field64: Field {
 
 /// A finite field of prime order less than 2^64.
 pub trait PrimeField64: PrimeField + Field64 {
+    /// The length of the canonical byte encoding of an element.
+    const NUM_BYTES: usize = Self::BITS.div_ceil(8);
+
     fn to_canonical_u64(&self) -> u64;
 
     fn to_noncanonical_u64(&self) -> u64;
@@ -564,6 +586,28 @@ pub trait PrimeField64: PrimeField + Field64 {
     fn to_canonical(&self) -> Self {
         Self::from_canonical_u64(self.to_canonical_u64())
     }
+
+    /// Returns the little-endian encoding of the canonical form of this element, which is
+    /// `Self::NUM_BYTES` long.
+    fn to_bytes_le(&self) -> Vec<u8> {
+        self.to_canonical_u64().to_le_bytes()[..Self::NUM_BYTES].to_vec()
+    }
+
+    /// Decodes an element from the encoding produced by [`PrimeField64::to_bytes_le`]. Fails if
+    /// `bytes` is not `Self::NUM_BYTES` long, or if it encodes a value which is not canonical.
+    fn from_bytes_le(bytes: &[u8]) -> Result<Self> {
+        ensure!(
+            bytes.len() == Self::NUM_BYTES,
+            "expected {} bytes, got {}",
+            Self::NUM_BYTES,
+            bytes.len()
+        );
+        let mut buf = [0u8; 8];
+        buf[..Self::NUM_BYTES].copy_from_slice(bytes);
+        let n = u64::from_le_bytes(buf);
+        ensure!(n < Self::ORDER, "{n} is not a canonical field element");
+        Ok(Self::from_canonical_u64(n))
+    }
 }
 
 /// An iterator over the powers of a certain base element `b`: `b^0, b^1, b^2, ...`.
@@ -618,7 +662,7 @@ impl<F: Field> Powers<F> {
 
 #[cfg(test)]
 mod tests {
-    use super::Field;
+    use super::{Field, Sample};
     use crate::goldilocks_field::GoldilocksField;
 
     #[test]
@@ -637,4 +681,15 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_batch_exp_u64() {
+        type F = GoldilocksField;
+
+        let base = F::rand();
+        let powers = [0, 1, 2, 7, 1 << 40, u64::MAX, 0x1234_5678_9abc_def0];
+        let expected = powers.iter().map(|&p| base.exp_u64(p)).collect::<Vec<_>>();
+        assert_eq!(base.batch_exp_u64(&powers), expected);
+        assert!(base.batch_exp_u64(&[]).is_empty());
+    }
 }
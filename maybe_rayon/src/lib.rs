@@ -240,11 +240,21 @@ pub trait ParallelIteratorMock {
     where
         P: Fn(&Self::Item) -> bool + Sync + Send;
 
+    fn find_first<P>(self, predicate: P) -> Option<Self::Item>
+    where
+        P: Fn(&Self::Item) -> bool + Sync + Send;
+
     fn flat_map_iter<U, F>(self, map_op: F) -> FlatMap<Self, U, F>
     where
         Self: Sized,
         U: IntoIterator,
         F: Fn(Self::Item) -> U;
+
+    fn map_init<S, INIT, F, R>(self, init: INIT, map_op: F) -> impl Iterator<Item = R>
+    where
+        Self: Sized,
+        INIT: Fn() -> S,
+        F: Fn(&mut S, Self::Item) -> R;
 }
 
 #[cfg(not(feature = "parallel"))]
@@ -258,6 +268,13 @@ impl<T: Iterator> ParallelIteratorMock for T {
         self.find(predicate)
     }
 
+    fn find_first<P>(mut self, predicate: P) -> Option<Self::Item>
+    where
+        P: Fn(&Self::Item) -> bool + Sync + Send,
+    {
+        self.find(predicate)
+    }
+
     fn flat_map_iter<U, F>(self, map_op: F) -> FlatMap<Self, U, F>
     where
         Self: Sized,
@@ -266,6 +283,16 @@ impl<T: Iterator> ParallelIteratorMock for T {
     {
         self.flat_map(map_op)
     }
+
+    fn map_init<S, INIT, F, R>(self, init: INIT, map_op: F) -> impl Iterator<Item = R>
+    where
+        Self: Sized,
+        INIT: Fn() -> S,
+        F: Fn(&mut S, Self::Item) -> R,
+    {
+        let mut state = init();
+        self.map(move |item| map_op(&mut state, item))
+    }
 }
 
 #[cfg(feature = "parallel")]
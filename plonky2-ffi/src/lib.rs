@@ -0,0 +1,4 @@
+//! Builds the C interface of `plonky2::ffi` as `libplonky2_ffi.so` and `libplonky2_ffi.a`, to be linked
+//! against with the declarations in `plonky2/include/plonky2.h`.
+
+pub use plonky2::ffi::*;
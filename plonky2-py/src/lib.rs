@@ -0,0 +1,238 @@
+//! Python bindings for building, proving and verifying circuits, built with `maturin`:
+//!
+//! ```sh
+//! cd plonky2-py && maturin develop --release
+//! ```
+//!
+//! The bindings are fixed to `PoseidonGoldilocksConfig` with `D = 2`. Field elements are passed as
+//! canonical integers.
+
+// Triggered by the code `#[pymethods]` generates for methods returning `PyResult`.
+#![allow(clippy::useless_conversion)]
+
+use plonky2::field::types::{Field, Field64, PrimeField64};
+use plonky2::iop::target::Target;
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, CircuitData};
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+fn field_element(value: u64) -> PyResult<F> {
+    if value >= F::ORDER {
+        return Err(PyValueError::new_err(format!(
+            "{value} is not a canonical field element"
+        )));
+    }
+    Ok(F::from_canonical_u64(value))
+}
+
+/// A wire or virtual target, as returned by `CircuitBuilder` methods.
+#[pyclass(name = "Target", frozen, eq, hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct PyTarget(Target);
+
+#[pymethods]
+impl PyTarget {
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// A circuit under construction. It can only be built once.
+#[pyclass(name = "CircuitBuilder", unsendable)]
+pub struct PyCircuitBuilder(Option<CircuitBuilder<F, D>>);
+
+impl PyCircuitBuilder {
+    fn builder(&mut self) -> PyResult<&mut CircuitBuilder<F, D>> {
+        self.0
+            .as_mut()
+            .ok_or_else(|| PyRuntimeError::new_err("The circuit was already built"))
+    }
+}
+
+#[pymethods]
+impl PyCircuitBuilder {
+    /// Creates a builder with the standard recursion config, or its zero-knowledge variant.
+    #[new]
+    #[pyo3(signature = (zero_knowledge = false))]
+    fn new(zero_knowledge: bool) -> Self {
+        let config = if zero_knowledge {
+            CircuitConfig::zk_config()
+        } else {
+            CircuitConfig::standard_recursion_config()
+        };
+        Self(Some(CircuitBuilder::new(config)))
+    }
+
+    fn add_virtual_target(&mut self) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.add_virtual_target()))
+    }
+
+    fn add_virtual_public_input(&mut self) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.add_virtual_public_input()))
+    }
+
+    fn register_public_input(&mut self, target: PyTarget) -> PyResult<()> {
+        self.builder()?.register_public_input(target.0);
+        Ok(())
+    }
+
+    fn constant(&mut self, value: u64) -> PyResult<PyTarget> {
+        let value = field_element(value)?;
+        Ok(PyTarget(self.builder()?.constant(value)))
+    }
+
+    fn add(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.add(x.0, y.0)))
+    }
+
+    fn sub(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.sub(x.0, y.0)))
+    }
+
+    fn mul(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.mul(x.0, y.0)))
+    }
+
+    /// Computes `x / y`. Proving fails if `y` is zero.
+    fn div(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.div(x.0, y.0)))
+    }
+
+    /// Returns a target holding 1 if `x == y`, and 0 otherwise.
+    fn is_equal(&mut self, x: PyTarget, y: PyTarget) -> PyResult<PyTarget> {
+        Ok(PyTarget(self.builder()?.is_equal(x.0, y.0).target))
+    }
+
+    fn connect(&mut self, x: PyTarget, y: PyTarget) -> PyResult<()> {
+        self.builder()?.connect(x.0, y.0);
+        Ok(())
+    }
+
+    fn assert_zero(&mut self, x: PyTarget) -> PyResult<()> {
+        self.builder()?.assert_zero(x.0);
+        Ok(())
+    }
+
+    fn assert_bool(&mut self, x: PyTarget) -> PyResult<()> {
+        self.builder()?.constrain_bool(x.0);
+        Ok(())
+    }
+
+    /// Checks that `x` fits in `bits` bits.
+    fn range_check(&mut self, x: PyTarget, bits: usize) -> PyResult<()> {
+        self.builder()?.range_check(x.0, bits);
+        Ok(())
+    }
+
+    fn num_gates(&mut self) -> PyResult<usize> {
+        Ok(self.builder()?.num_gates())
+    }
+
+    /// Builds the circuit, consuming the builder.
+    fn build(&mut self) -> PyResult<PyCircuitData> {
+        let builder = self
+            .0
+            .take()
+            .ok_or_else(|| PyRuntimeError::new_err("The circuit was already built"))?;
+        Ok(PyCircuitData(builder.build::<C>()))
+    }
+}
+
+/// The inputs to a proof.
+#[pyclass(name = "PartialWitness")]
+#[derive(Default)]
+pub struct PyPartialWitness(PartialWitness<F>);
+
+#[pymethods]
+impl PyPartialWitness {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn set_target(&mut self, target: PyTarget, value: u64) -> PyResult<()> {
+        self.0
+            .set_target(target.0, field_element(value)?)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+/// A built circuit.
+#[pyclass(name = "CircuitData", frozen)]
+pub struct PyCircuitData(CircuitData<F, C, D>);
+
+#[pymethods]
+impl PyCircuitData {
+    #[getter]
+    fn num_public_inputs(&self) -> usize {
+        self.0.common.num_public_inputs
+    }
+
+    /// The number of rows, i.e. the padded number of gates.
+    #[getter]
+    fn degree(&self) -> usize {
+        self.0.common.degree()
+    }
+
+    /// Proves the circuit, releasing the GIL while proving.
+    fn prove(&self, py: Python<'_>, witness: &PyPartialWitness) -> PyResult<PyProof> {
+        let inputs = witness.0.clone();
+        py.allow_threads(|| self.0.prove(inputs))
+            .map(PyProof)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Raises a `ValueError` if the proof is invalid.
+    fn verify(&self, py: Python<'_>, proof: &PyProof) -> PyResult<()> {
+        let proof = proof.0.clone();
+        py.allow_threads(|| self.0.verify(proof))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Reads a proof written by `Proof.to_bytes`.
+    fn proof_from_bytes(&self, bytes: Vec<u8>) -> PyResult<PyProof> {
+        ProofWithPublicInputs::from_bytes(bytes, &self.0.common)
+            .map(PyProof)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+/// A proof with its public inputs.
+#[pyclass(name = "Proof", frozen)]
+pub struct PyProof(ProofWithPublicInputs<F, C, D>);
+
+#[pymethods]
+impl PyProof {
+    #[getter]
+    fn public_inputs(&self) -> Vec<u64> {
+        self.0
+            .public_inputs
+            .iter()
+            .map(|x| x.to_canonical_u64())
+            .collect()
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.0.to_bytes())
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "plonky2")]
+fn plonky2_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTarget>()?;
+    m.add_class::<PyCircuitBuilder>()?;
+    m.add_class::<PyPartialWitness>()?;
+    m.add_class::<PyCircuitData>()?;
+    m.add_class::<PyProof>()?;
+    Ok(())
+}
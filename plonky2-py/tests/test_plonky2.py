@@ -0,0 +1,47 @@
+import pytest
+
+import plonky2
+
+
+def build_circuit():
+    builder = plonky2.CircuitBuilder()
+    x = builder.add_virtual_public_input()
+    y = builder.add_virtual_target()
+    z = builder.add(builder.mul(x, y), builder.constant(1))
+    builder.register_public_input(z)
+    return builder.build(), x, y
+
+
+def test_prove_and_verify():
+    data, x, y = build_circuit()
+    assert data.num_public_inputs == 2
+
+    witness = plonky2.PartialWitness()
+    witness.set_target(x, 6)
+    witness.set_target(y, 7)
+    proof = data.prove(witness)
+    assert proof.public_inputs == [6, 43]
+    data.verify(proof)
+
+    proof = data.proof_from_bytes(proof.to_bytes())
+    data.verify(proof)
+
+
+def test_invalid_inputs():
+    data, x, y = build_circuit()
+    witness = plonky2.PartialWitness()
+    with pytest.raises(ValueError):
+        witness.set_target(x, 2**64 - 1)
+    witness.set_target(x, 6)
+    with pytest.raises(ValueError):
+        witness.set_target(x, 7)
+    # `y` is missing.
+    with pytest.raises(ValueError):
+        data.prove(witness)
+
+
+def test_build_twice():
+    builder = plonky2.CircuitBuilder()
+    builder.build()
+    with pytest.raises(RuntimeError):
+        builder.build()
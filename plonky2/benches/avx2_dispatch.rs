@@ -0,0 +1,56 @@
+//! Compares the runtime dispatch to AVX2 against the scalar path. Only meaningful for builds
+//! without the `avx2` target feature, on CPUs with AVX2.
+
+mod allocator;
+
+#[cfg(all(target_arch = "x86_64", not(target_feature = "avx2")))]
+use criterion::BenchmarkId;
+use criterion::{criterion_group, criterion_main, Criterion};
+#[cfg(all(target_arch = "x86_64", not(target_feature = "avx2")))]
+use plonky2::field::{
+    batch_util::{batch_add_inplace, batch_multiply_inplace},
+    goldilocks_field::GoldilocksField,
+    polynomial::PolynomialCoeffs,
+    set_avx2_dispatch,
+    types::Sample,
+};
+
+#[cfg(all(target_arch = "x86_64", not(target_feature = "avx2")))]
+fn criterion_benchmark(c: &mut Criterion) {
+    type F = GoldilocksField;
+
+    for (name, dispatch) in [("dispatched", true), ("scalar", false)] {
+        set_avx2_dispatch(dispatch);
+
+        let mut group = c.benchmark_group(format!("fft/{name}"));
+        for size_log in [13, 16] {
+            let size = 1 << size_log;
+            group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+                let coeffs = PolynomialCoeffs::new(F::rand_vec(size));
+                b.iter(|| coeffs.clone().fft_with_options(None, None));
+            });
+        }
+        group.finish();
+
+        let size = 1 << 16;
+        let x = F::rand_vec(size);
+        let y = F::rand_vec(size);
+        let mut group = c.benchmark_group(format!("batch/{name}"));
+        group.bench_function(BenchmarkId::new("multiply", size), |b| {
+            let mut out = x.clone();
+            b.iter(|| batch_multiply_inplace(&mut out, &y));
+        });
+        group.bench_function(BenchmarkId::new("add", size), |b| {
+            let mut out = x.clone();
+            b.iter(|| batch_add_inplace(&mut out, &y));
+        });
+        group.finish();
+    }
+    set_avx2_dispatch(true);
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(target_feature = "avx2"))))]
+fn criterion_benchmark(_c: &mut Criterion) {}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
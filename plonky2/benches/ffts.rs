@@ -1,6 +1,7 @@
 mod allocator;
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use plonky2::field::fft::coset_lde_batch_bit_reversed;
 use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::polynomial::PolynomialCoeffs;
 use plonky2::field::types::Field;
@@ -37,9 +38,30 @@ pub(crate) fn bench_ldes<F: Field>(c: &mut Criterion) {
     }
 }
 
+pub(crate) fn bench_batch_ldes<F: Field>(c: &mut Criterion) {
+    const RATE_BITS: usize = 3;
+    const NUM_POLYS: usize = 135;
+
+    let mut group = c.benchmark_group(format!("batch_lde<{}>", type_name::<F>()));
+    group.sample_size(10);
+
+    for size_log in [13, 14, 15, 16] {
+        let orig_size = 1 << (size_log - RATE_BITS);
+        let lde_size = 1 << size_log;
+
+        group.bench_with_input(BenchmarkId::from_parameter(lde_size), &lde_size, |b, _| {
+            let polys = (0..NUM_POLYS)
+                .map(|_| PolynomialCoeffs::new(F::rand_vec(orig_size)))
+                .collect::<Vec<_>>();
+            b.iter(|| coset_lde_batch_bit_reversed(&polys, RATE_BITS, F::coset_shift(), 0, None));
+        });
+    }
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     bench_ffts::<GoldilocksField>(c);
     bench_ldes::<GoldilocksField>(c);
+    bench_batch_ldes::<GoldilocksField>(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -5,6 +5,7 @@ use plonky2::field::extension::quadratic::QuadraticExtension;
 use plonky2::field::extension::quartic::QuarticExtension;
 use plonky2::field::extension::quintic::QuinticExtension;
 use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::goldilocks_montgomery_field::GoldilocksMontgomeryField;
 use plonky2::field::types::Field;
 use tynm::type_name;
 
@@ -176,6 +177,8 @@ fn criterion_benchmark(c: &mut Criterion) {
     bench_field::<QuadraticExtension<GoldilocksField>>(c);
     bench_field::<QuarticExtension<GoldilocksField>>(c);
     bench_field::<QuinticExtension<GoldilocksField>>(c);
+    bench_field::<GoldilocksMontgomeryField>(c);
+    bench_field::<QuadraticExtension<GoldilocksMontgomeryField>>(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -3,9 +3,11 @@ mod allocator;
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::types::Sample;
+use plonky2::hash::blake3::Blake3Hash;
 use plonky2::hash::hash_types::{BytesHash, RichField};
 use plonky2::hash::keccak::KeccakHash;
 use plonky2::hash::poseidon::{Poseidon, SPONGE_WIDTH};
+use plonky2::hash::sha256::Sha256Hash;
 use plonky2::plonk::config::Hasher;
 use tynm::type_name;
 
@@ -19,6 +21,26 @@ pub(crate) fn bench_keccak<F: RichField>(c: &mut Criterion) {
     });
 }
 
+pub(crate) fn bench_blake3<F: RichField>(c: &mut Criterion) {
+    c.bench_function("blake3", |b| {
+        b.iter_batched(
+            || (BytesHash::<32>::rand(), BytesHash::<32>::rand()),
+            |(left, right)| <Blake3Hash<32> as Hasher<F>>::two_to_one(left, right),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+pub(crate) fn bench_sha256<F: RichField>(c: &mut Criterion) {
+    c.bench_function("sha256", |b| {
+        b.iter_batched(
+            || (BytesHash::<32>::rand(), BytesHash::<32>::rand()),
+            |(left, right)| <Sha256Hash<32> as Hasher<F>>::two_to_one(left, right),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
 pub(crate) fn bench_poseidon<F: Poseidon>(c: &mut Criterion) {
     c.bench_function(
         &format!("poseidon<{}, {SPONGE_WIDTH}>", type_name::<F>()),
@@ -35,6 +57,8 @@ pub(crate) fn bench_poseidon<F: Poseidon>(c: &mut Criterion) {
 fn criterion_benchmark(c: &mut Criterion) {
     bench_poseidon::<GoldilocksField>(c);
     bench_keccak::<GoldilocksField>(c);
+    bench_blake3::<GoldilocksField>(c);
+    bench_sha256::<GoldilocksField>(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
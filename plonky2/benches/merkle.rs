@@ -2,10 +2,12 @@ mod allocator;
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::hash::blake3::Blake3Hash;
 use plonky2::hash::hash_types::RichField;
 use plonky2::hash::keccak::KeccakHash;
 use plonky2::hash::merkle_tree::MerkleTree;
 use plonky2::hash::poseidon::PoseidonHash;
+use plonky2::hash::sha256::Sha256Hash;
 use plonky2::plonk::config::Hasher;
 use tynm::type_name;
 
@@ -31,6 +33,8 @@ pub(crate) fn bench_merkle_tree<F: RichField, H: Hasher<F>>(c: &mut Criterion) {
 fn criterion_benchmark(c: &mut Criterion) {
     bench_merkle_tree::<GoldilocksField, PoseidonHash>(c);
     bench_merkle_tree::<GoldilocksField, KeccakHash<25>>(c);
+    bench_merkle_tree::<GoldilocksField, Blake3Hash<25>>(c);
+    bench_merkle_tree::<GoldilocksField, Sha256Hash<32>>(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
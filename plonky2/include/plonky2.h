@@ -0,0 +1,44 @@
+/* C declarations for the `ffi` module of plonky2. Link against `libplonky2_ffi`, built by the
+ * `plonky2-ffi` crate. */
+
+#ifndef PLONKY2_H
+#define PLONKY2_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+typedef enum {
+    PLONKY2_OK = 0,
+    PLONKY2_INVALID_ARGUMENT = 1,
+    PLONKY2_INVALID_DATA = 2,
+    PLONKY2_CONFLICTING_INPUT = 3,
+    PLONKY2_PROVING_FAILED = 4,
+    PLONKY2_VERIFICATION_FAILED = 5,
+} Plonky2Status;
+
+typedef struct Plonky2Circuit Plonky2Circuit;
+typedef struct Plonky2Witness Plonky2Witness;
+
+typedef struct {
+    uint8_t *data;
+    size_t len;
+} Plonky2Proof;
+
+Plonky2Circuit *plonky2_circuit_load(const uint8_t *bytes, size_t len);
+void plonky2_circuit_free(Plonky2Circuit *circuit);
+size_t plonky2_circuit_num_public_inputs(const Plonky2Circuit *circuit);
+
+Plonky2Witness *plonky2_witness_new(void);
+void plonky2_witness_free(Plonky2Witness *witness);
+Plonky2Status plonky2_witness_set_public_input(const Plonky2Circuit *circuit,
+                                               Plonky2Witness *witness, size_t index,
+                                               uint64_t value);
+Plonky2Status plonky2_witness_set_virtual_target(Plonky2Witness *witness, size_t index,
+                                                 uint64_t value);
+
+Plonky2Status plonky2_prove(const Plonky2Circuit *circuit, const Plonky2Witness *witness,
+                            Plonky2Proof *proof);
+void plonky2_proof_free(Plonky2Proof proof);
+Plonky2Status plonky2_verify(const Plonky2Circuit *circuit, const uint8_t *proof, size_t len);
+
+#endif /* PLONKY2_H */
@@ -0,0 +1,268 @@
+//! Pluggable implementations of the heavy, data-parallel steps of the prover.
+//!
+//! Committing to a batch of polynomials is dominated by their interpolation, their low-degree
+//! extension onto a coset, and the hashing of the resulting Merkle leaves. The quotient
+//! polynomials are computed by evaluating the constraints over the LDE domain. The FRI opening
+//! proof then combines the committed polynomials pointwise into a single one, and repeatedly folds
+//! a codeword and hashes each folded codeword into a Merkle tree. The [`ProverBackend`] trait
+//! covers these steps, so that an accelerator (e.g. a GPU) can take them over through
+//! [`crate::plonk::prover::prove_with_backend`] without changes to the rest of the prover.
+//!
+//! Every method has a default implementation running on the CPU, which [`CpuBackend`] uses as is.
+//! Backends only need to override the steps they accelerate, and must produce the same results,
+//! so each step falls back to the CPU independently of the others.
+//!
+//! With the `opencl` feature, [`opencl::OpenClBackend`] is a reference implementation running the
+//! FFTs and pointwise operations on an OpenCL device. It leaves the constraint evaluation on the
+//! CPU, since that needs a kernel for each gate type.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use plonky2_maybe_rayon::*;
+
+#[cfg(feature = "opencl")]
+pub mod opencl;
+
+use crate::field::extension::Extendable;
+use crate::field::fft::{coset_lde_batch_bit_reversed, FftRootTable};
+use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use crate::fri::oracle::PolynomialBatch;
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_tree::MerkleTree;
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::plonk_common::reduce_with_powers;
+use crate::plonk::prover::compute_quotient_polys;
+use crate::util::reducing::ReducingFactor;
+
+/// The inputs to the computation of the quotient polynomials: the committed wires, `Z`s, partial
+/// products and lookup polynomials, and the challenges drawn after committing to them.
+#[derive(Debug)]
+pub struct QuotientInputs<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub common_data: &'a CommonCircuitData<F, D>,
+    pub prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    pub public_inputs_hash: &'a <C::InnerHasher as Hasher<F>>::Hash,
+    pub wires_commitment: &'a PolynomialBatch<F, C, D>,
+    pub zs_partial_products_and_lookup_commitment: &'a PolynomialBatch<F, C, D>,
+    pub betas: &'a [F],
+    pub gammas: &'a [F],
+    pub deltas: &'a [F],
+    pub alphas: &'a [F],
+}
+
+/// The steps of a polynomial commitment which can be offloaded.
+pub trait ProverBackend<F: RichField> {
+    /// Interpolates each of `values` over the subgroup of its size.
+    fn ifft(&self, values: Vec<PolynomialValues<F>>) -> Vec<PolynomialCoeffs<F>> {
+        values.into_par_iter().map(|v| v.ifft()).collect()
+    }
+
+    /// Extends `polynomials`, which have the same length `n`, to their evaluations over the coset
+    /// `F::coset_shift() * H`, where `H` is the subgroup of size `n << rate_bits`. These are
+    /// returned as Merkle leaves: the `i`th leaf holds the evaluations of all polynomials at the
+    /// `reverse_bits(i)`th point, followed by `salt_size` zeros.
+    fn coset_lde_leaves(
+        &self,
+        polynomials: &[PolynomialCoeffs<F>],
+        rate_bits: usize,
+        salt_size: usize,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Vec<Vec<F>> {
+        coset_lde_batch_bit_reversed(
+            polynomials,
+            rate_bits,
+            F::coset_shift(),
+            salt_size,
+            fft_root_table,
+        )
+    }
+
+    /// Hashes `leaves` into a Merkle tree with a cap of height `cap_height`.
+    fn merkle_tree<H: Hasher<F>>(
+        &self,
+        leaves: Vec<Vec<F>>,
+        cap_height: usize,
+    ) -> MerkleTree<F, H> {
+        MerkleTree::new(leaves, cap_height)
+    }
+
+    /// Computes `sum_j alpha^j * polys[j]`, coefficient by coefficient. Shorter polynomials are
+    /// padded with zeros.
+    fn reduce_polys<const D: usize>(
+        &self,
+        polys: &[&PolynomialCoeffs<F>],
+        alpha: F::Extension,
+    ) -> PolynomialCoeffs<F::Extension>
+    where
+        F: Extendable<D>,
+    {
+        ReducingFactor::new(alpha).reduce_polys_base(polys.iter().copied())
+    }
+
+    /// Evaluates the vanishing polynomial, i.e. the gate constraints and the permutation and
+    /// lookup arguments combined with `alphas`, over the LDE domain, and divides it by `Z_H`.
+    /// Returns the quotient polynomial for each challenge, of degree less than
+    /// `quotient_degree_factor` times the circuit's degree.
+    fn quotient_polys<C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        inputs: &QuotientInputs<F, C, D>,
+    ) -> Vec<PolynomialCoeffs<F>>
+    where
+        F: Extendable<D>,
+    {
+        compute_quotient_polys(inputs)
+    }
+
+    /// Performs one FRI folding step: writing `coeffs` as `P(x) = sum_{i<r} x^i * P_i(x^r)`, where
+    /// `r = 1 << arity_bits`, returns the coefficients of `sum_{i<r} beta^i * P_i(x)` along with
+    /// its evaluations over the coset `shift * H`, where `H` is the subgroup of its length.
+    fn fri_fold<const D: usize>(
+        &self,
+        coeffs: &PolynomialCoeffs<F::Extension>,
+        arity_bits: usize,
+        beta: F::Extension,
+        shift: F,
+    ) -> (
+        PolynomialCoeffs<F::Extension>,
+        PolynomialValues<F::Extension>,
+    )
+    where
+        F: Extendable<D>,
+    {
+        let folded = PolynomialCoeffs::new(
+            coeffs
+                .coeffs
+                .par_chunks_exact(1 << arity_bits)
+                .map(|chunk| reduce_with_powers(chunk, beta))
+                .collect::<Vec<_>>(),
+        );
+        let values = folded.coset_fft(shift.into());
+        (folded, values)
+    }
+}
+
+/// The default backend, which runs everything on the CPU.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CpuBackend;
+
+impl<F: RichField> ProverBackend<F> for CpuBackend {}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::plonk::prover::prove_with_backend;
+    use crate::util::timing::TimingTree;
+
+    /// Runs on the CPU, but counts the Merkle trees it builds, the batches of polynomials it
+    /// reduces, the quotient computations and the FRI folding steps.
+    #[derive(Default)]
+    struct CountingBackend {
+        merkle_trees: AtomicUsize,
+        reductions: AtomicUsize,
+        quotients: AtomicUsize,
+        fri_folds: AtomicUsize,
+    }
+
+    impl<F: RichField> ProverBackend<F> for CountingBackend {
+        fn merkle_tree<H: Hasher<F>>(
+            &self,
+            leaves: Vec<Vec<F>>,
+            cap_height: usize,
+        ) -> MerkleTree<F, H> {
+            self.merkle_trees.fetch_add(1, Ordering::Relaxed);
+            MerkleTree::new(leaves, cap_height)
+        }
+
+        fn reduce_polys<const D: usize>(
+            &self,
+            polys: &[&PolynomialCoeffs<F>],
+            alpha: F::Extension,
+        ) -> PolynomialCoeffs<F::Extension>
+        where
+            F: Extendable<D>,
+        {
+            self.reductions.fetch_add(1, Ordering::Relaxed);
+            CpuBackend.reduce_polys(polys, alpha)
+        }
+
+        fn quotient_polys<C: GenericConfig<D, F = F>, const D: usize>(
+            &self,
+            inputs: &QuotientInputs<F, C, D>,
+        ) -> Vec<PolynomialCoeffs<F>>
+        where
+            F: Extendable<D>,
+        {
+            self.quotients.fetch_add(1, Ordering::Relaxed);
+            CpuBackend.quotient_polys(inputs)
+        }
+
+        fn fri_fold<const D: usize>(
+            &self,
+            coeffs: &PolynomialCoeffs<F::Extension>,
+            arity_bits: usize,
+            beta: F::Extension,
+            shift: F,
+        ) -> (
+            PolynomialCoeffs<F::Extension>,
+            PolynomialValues<F::Extension>,
+        )
+        where
+            F: Extendable<D>,
+        {
+            self.fri_folds.fetch_add(1, Ordering::Relaxed);
+            CpuBackend.fri_fold(coeffs, arity_bits, beta, shift)
+        }
+    }
+
+    #[test]
+    fn test_prove_with_backend() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 7);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO)?;
+        let backend = CountingBackend::default();
+        let proof = prove_with_backend::<F, C, _, D>(
+            &data.prover_only,
+            &data.common,
+            pw,
+            &backend,
+            &mut TimingTree::default(),
+        )?;
+
+        // The wires, the partial products and `Z`s, and the quotient chunks are committed to, then
+        // each FRI layer is folded and committed to.
+        let num_fri_layers = data.common.fri_params.reduction_arity_bits.len();
+        assert_eq!(
+            backend.merkle_trees.load(Ordering::Relaxed),
+            3 + num_fri_layers
+        );
+        // The openings at `zeta` and `g * zeta` are reduced separately.
+        assert_eq!(backend.reductions.load(Ordering::Relaxed), 2);
+        assert_eq!(backend.quotients.load(Ordering::Relaxed), 1);
+        assert_eq!(backend.fri_folds.load(Ordering::Relaxed), num_fri_layers);
+        data.verify(proof)
+    }
+}
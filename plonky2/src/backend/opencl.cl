@@ -0,0 +1,149 @@
+// Kernels for `OpenClBackend`, over the Goldilocks field p = 2^64 - 2^32 + 1.
+//
+// Elements are passed as `ulong`s, which may be non-canonical (i.e. in [p, 2^64)) when they come
+// from the host. Every kernel writes canonical elements.
+
+#define GL_ORDER 0xFFFFFFFF00000001UL
+#define GL_EPSILON 0xFFFFFFFFUL
+
+inline ulong gl_canonical(ulong x) {
+    return x >= GL_ORDER ? x - GL_ORDER : x;
+}
+
+// Both inputs must be canonical.
+inline ulong gl_add(ulong x, ulong y) {
+    ulong sum = x + y;
+    if (sum < x) {
+        // x + y = sum + 2^64 = (sum + GL_EPSILON) + p, and sum + GL_EPSILON < p.
+        return sum + GL_EPSILON;
+    }
+    return gl_canonical(sum);
+}
+
+// Both inputs must be canonical.
+inline ulong gl_sub(ulong x, ulong y) {
+    ulong diff = x - y;
+    if (x < y) {
+        // x - y = diff - 2^64 = (diff - GL_EPSILON) - p.
+        return diff - GL_EPSILON;
+    }
+    return diff;
+}
+
+// Any inputs, as in `GoldilocksField::mul`.
+inline ulong gl_mul(ulong x, ulong y) {
+    ulong lo = x * y;
+    ulong hi = mul_hi(x, y);
+    ulong hi_hi = hi >> 32;
+    ulong hi_lo = hi & GL_EPSILON;
+
+    ulong t0 = lo - hi_hi;
+    if (lo < hi_hi) {
+        t0 -= GL_EPSILON;
+    }
+    ulong t1 = hi_lo * GL_EPSILON;
+    ulong t2 = t0 + t1;
+    if (t2 < t1) {
+        t2 += GL_EPSILON;
+    }
+    return gl_canonical(t2);
+}
+
+inline ulong reverse_bits(ulong x, uint bits) {
+    ulong reversed = 0;
+    for (uint i = 0; i < bits; i++) {
+        reversed = (reversed << 1) | ((x >> i) & 1);
+    }
+    return reversed;
+}
+
+// Each of the polynomials in `values`, of `2^lg_n` elements each, is multiplied pointwise by
+// `powers`. One work item per element.
+__kernel void mul_powers(__global ulong *values, __global const ulong *powers, uint lg_n) {
+    size_t i = get_global_id(0);
+    values[i] = gl_mul(values[i], powers[i & ((1UL << lg_n) - 1)]);
+}
+
+// Copies each of the vectors in `src`, of `2^lg_n` elements each, to `dst` in bit-reversed order.
+// One work item per element.
+__kernel void bit_reverse(__global const ulong *src, __global ulong *dst, uint lg_n) {
+    size_t i = get_global_id(0);
+    size_t block = i >> lg_n;
+    ulong j = i & ((1UL << lg_n) - 1);
+    dst[(block << lg_n) | reverse_bits(j, lg_n)] = gl_canonical(src[i]);
+}
+
+// One layer of a decimation-in-time FFT over each of the vectors in `values`, of `2^lg_n` elements
+// each, combining elements `2^lg_half_m` apart. `twiddles` holds the rows of an `FftRootTable` one
+// after the other, so that row `lg_half_m` starts at `2^lg_half_m - 1`. One work item per butterfly.
+__kernel void butterflies(
+    __global ulong *values,
+    __global const ulong *twiddles,
+    uint lg_n,
+    uint lg_half_m
+) {
+    size_t i = get_global_id(0);
+    size_t block = i >> (lg_n - 1);
+    size_t b = i & ((1UL << (lg_n - 1)) - 1);
+    size_t half_m = 1UL << lg_half_m;
+    size_t j = b & (half_m - 1);
+    size_t u_index = (block << lg_n) + ((b >> lg_half_m) << (lg_half_m + 1)) + j;
+    size_t v_index = u_index + half_m;
+
+    ulong t = gl_mul(twiddles[half_m - 1 + j], values[v_index]);
+    ulong u = values[u_index];
+    values[u_index] = gl_add(u, t);
+    values[v_index] = gl_sub(u, t);
+}
+
+// Turns forward FFTs of the vectors in `src`, of `2^lg_n` elements each, into inverse FFTs, by
+// reversing all elements but the first and multiplying them by `n_inv`. One work item per element.
+__kernel void ifft_finish(
+    __global const ulong *src,
+    __global ulong *dst,
+    uint lg_n,
+    ulong n_inv
+) {
+    size_t i = get_global_id(0);
+    size_t mask = (1UL << lg_n) - 1;
+    size_t block_start = i & ~mask;
+    size_t j = (0 - (i & mask)) & mask;
+    dst[i] = gl_mul(src[block_start | j], n_inv);
+}
+
+// Writes Merkle leaves of `leaf_len` elements: leaf `i` holds element `reverse_bits(i)` of each of
+// the `num_vectors` vectors in `values`, of `2^lg_n` elements each, followed by zeros. One work
+// item per leaf element.
+__kernel void to_leaves(
+    __global const ulong *values,
+    __global ulong *leaves,
+    uint lg_n,
+    uint num_vectors,
+    uint leaf_len
+) {
+    size_t i = get_global_id(0);
+    size_t leaf = i / leaf_len;
+    size_t column = i % leaf_len;
+    leaves[i] = column < num_vectors ? values[(column << lg_n) | reverse_bits(leaf, lg_n)] : 0;
+}
+
+// Computes `sum_j powers[j] * polys[j]`, coefficient by coefficient, where `polys` holds
+// `num_polys` base field polynomials of `len` coefficients each, and `powers` holds `num_polys`
+// extension field elements of `d` limbs each. One work item per coefficient.
+__kernel void reduce_polys(
+    __global const ulong *polys,
+    __global const ulong *powers,
+    __global ulong *out,
+    uint num_polys,
+    uint len,
+    uint d
+) {
+    size_t i = get_global_id(0);
+    for (uint k = 0; k < d; k++) {
+        ulong acc = 0;
+        for (uint j = 0; j < num_polys; j++) {
+            acc = gl_add(acc, gl_mul(polys[(size_t)j * len + i], powers[j * d + k]));
+        }
+        out[i * d + k] = acc;
+    }
+}
@@ -0,0 +1,474 @@
+//! A reference [`ProverBackend`] running on an OpenCL device.
+//!
+//! It runs the interpolations, the coset LDEs (including their layout as Merkle leaves) and the
+//! pointwise combination of polynomials on the device, with the kernels in `opencl.cl`. Merkle
+//! hashing, the constraint evaluation and FRI folding stay on the CPU. It is meant as a starting
+//! point for vendor backends rather than a fast one: every call copies its inputs to the device
+//! and its results back.
+//!
+//! This is not an end-to-end GPU prover, and no speedup over [`CpuBackend`] has been measured.
+//!
+//! The OpenCL library is loaded at runtime, so this builds without an OpenCL SDK. If a step fails
+//! on the device, it is logged and the step runs on the CPU instead.
+
+use core::fmt::{self, Debug, Formatter};
+use core::ptr;
+
+use anyhow::{anyhow, ensure, Result};
+use opencl3::command_queue::CommandQueue;
+use opencl3::context::Context;
+use opencl3::device::{get_all_devices, Device, CL_DEVICE_TYPE_ALL, CL_DEVICE_TYPE_GPU};
+use opencl3::kernel::{ExecuteKernel, Kernel};
+use opencl3::memory::{Buffer, CL_MEM_READ_WRITE};
+use opencl3::program::Program;
+use opencl3::types::{cl_uint, cl_ulong, CL_BLOCKING};
+use plonky2_util::log2_strict;
+
+use crate::backend::{CpuBackend, ProverBackend};
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::fft::{fft_root_table, FftRootTable};
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
+use crate::field::types::{Field, PrimeField64};
+
+type F = GoldilocksField;
+
+const KERNELS: &str = include_str!("opencl.cl");
+
+/// Runs the FFTs and pointwise operations of the prover over `GoldilocksField` on an OpenCL device.
+pub struct OpenClBackend {
+    device_name: String,
+    context: Context,
+    queue: CommandQueue,
+    program: Program,
+}
+
+impl Debug for OpenClBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenClBackend")
+            .field("device_name", &self.device_name)
+            .finish()
+    }
+}
+
+fn cl<T>(result: Result<T, opencl3::error_codes::ClError>) -> Result<T> {
+    result.map_err(|e| anyhow!("OpenCL error: {e}"))
+}
+
+fn to_u64s(values: &[F]) -> impl Iterator<Item = cl_ulong> + '_ {
+    values.iter().map(|x| x.to_noncanonical_u64())
+}
+
+impl OpenClBackend {
+    /// Uses the first GPU found, or else the first OpenCL device of any type.
+    pub fn new() -> Result<Self> {
+        let gpus = get_all_devices(CL_DEVICE_TYPE_GPU).unwrap_or_default();
+        let device = match gpus.first() {
+            Some(&device) => device,
+            None => *cl(get_all_devices(CL_DEVICE_TYPE_ALL))?
+                .first()
+                .ok_or_else(|| anyhow!("No OpenCL device found"))?,
+        };
+        Self::with_device(Device::new(device))
+    }
+
+    /// Uses the given OpenCL device.
+    pub fn with_device(device: Device) -> Result<Self> {
+        let device_name = cl(device.name())?;
+        let context = cl(Context::from_device(&device))?;
+        let queue = cl(CommandQueue::create_default(&context, 0))?;
+        let program = Program::create_and_build_from_source(&context, KERNELS, "")
+            .map_err(|log| anyhow!("Failed to build the OpenCL kernels: {log}"))?;
+        Ok(Self {
+            device_name,
+            context,
+            queue,
+            program,
+        })
+    }
+
+    /// The name of the device in use.
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    fn buffer(&self, len: usize) -> Result<Buffer<cl_ulong>> {
+        // SAFETY: No host pointer is given.
+        cl(unsafe { Buffer::create(&self.context, CL_MEM_READ_WRITE, len, ptr::null_mut()) })
+    }
+
+    fn upload(&self, data: &[cl_ulong]) -> Result<Buffer<cl_ulong>> {
+        let mut buffer = self.buffer(data.len())?;
+        // SAFETY: The write is blocking, and `buffer` holds `data.len()` elements.
+        cl(unsafe {
+            self.queue
+                .enqueue_write_buffer(&mut buffer, CL_BLOCKING, 0, data, &[])
+        })?;
+        Ok(buffer)
+    }
+
+    fn download(&self, buffer: &Buffer<cl_ulong>, len: usize) -> Result<Vec<cl_ulong>> {
+        let mut data = vec![0; len];
+        // SAFETY: The read is blocking, and `buffer` holds at least `len` elements.
+        cl(unsafe {
+            self.queue
+                .enqueue_read_buffer(buffer, CL_BLOCKING, 0, &mut data, &[])
+        })?;
+        Ok(data)
+    }
+
+    /// Runs the kernel `name` with one work item for each of `work_size` elements. `set_args` must
+    /// set arguments of the types the kernel expects.
+    fn run(
+        &self,
+        name: &str,
+        work_size: usize,
+        set_args: impl FnOnce(&mut ExecuteKernel),
+    ) -> Result<()> {
+        let kernel = cl(Kernel::create(&self.program, name))?;
+        let mut execute = ExecuteKernel::new(&kernel);
+        set_args(&mut execute);
+        // SAFETY: The kernels index their buffers by work item, and callers size the buffers for
+        // `work_size` work items.
+        cl(unsafe {
+            execute
+                .set_global_work_size(work_size)
+                .enqueue_nd_range(&self.queue)
+        })?;
+        Ok(())
+    }
+
+    /// Runs forward FFTs over the `num_vectors` vectors in `values`, of `2^lg_n` elements each,
+    /// with the output in natural order. `root_table` must have at least `lg_n` rows.
+    fn fft_in_place(
+        &self,
+        values: &mut Buffer<cl_ulong>,
+        scratch: &mut Buffer<cl_ulong>,
+        num_vectors: usize,
+        lg_n: usize,
+        root_table: &FftRootTable<F>,
+    ) -> Result<()> {
+        let n = 1 << lg_n;
+        let twiddles = root_table[..lg_n]
+            .iter()
+            .enumerate()
+            .flat_map(|(lg_half_m, row)| to_u64s(&row[..1 << lg_half_m]))
+            .collect::<Vec<_>>();
+        let twiddles = self.upload(&twiddles)?;
+        let lg_n = lg_n as cl_uint;
+
+        // SAFETY (all kernel arguments): They match the parameters of the kernels.
+        self.run("bit_reverse", num_vectors * n, |k| unsafe {
+            k.set_arg(values).set_arg(scratch).set_arg(&lg_n);
+        })?;
+        for lg_half_m in 0..lg_n {
+            self.run("butterflies", num_vectors * n / 2, |k| unsafe {
+                k.set_arg(scratch)
+                    .set_arg(&twiddles)
+                    .set_arg(&lg_n)
+                    .set_arg(&lg_half_m);
+            })?;
+        }
+        core::mem::swap(values, scratch);
+        Ok(())
+    }
+
+    fn try_ifft(&self, values: &[PolynomialValues<F>]) -> Result<Vec<PolynomialCoeffs<F>>> {
+        let n = values[0].len();
+        ensure!(
+            values.iter().all(|v| v.len() == n),
+            "Polynomial sizes inconsistent"
+        );
+        let lg_n = log2_strict(n);
+        let len = values.len() * n;
+
+        let data = values
+            .iter()
+            .flat_map(|v| to_u64s(&v.values))
+            .collect::<Vec<_>>();
+        let mut buffer = self.upload(&data)?;
+        let mut scratch = self.buffer(len)?;
+        self.fft_in_place(
+            &mut buffer,
+            &mut scratch,
+            values.len(),
+            lg_n,
+            &fft_root_table(n),
+        )?;
+
+        let lg_n = lg_n as cl_uint;
+        let n_inv = F::inverse_2exp(lg_n as usize).to_noncanonical_u64();
+        // SAFETY: The arguments match the parameters of the kernel.
+        self.run("ifft_finish", len, |k| unsafe {
+            k.set_arg(&buffer)
+                .set_arg(&scratch)
+                .set_arg(&lg_n)
+                .set_arg(&n_inv);
+        })?;
+        let coeffs = self.download(&scratch, len)?;
+        Ok(coeffs
+            .chunks_exact(n)
+            .map(|chunk| PolynomialCoeffs::new(chunk.iter().map(|&x| GoldilocksField(x)).collect()))
+            .collect())
+    }
+
+    fn try_coset_lde_leaves(
+        &self,
+        polynomials: &[PolynomialCoeffs<F>],
+        rate_bits: usize,
+        salt_size: usize,
+        root_table: Option<&FftRootTable<F>>,
+    ) -> Result<Vec<Vec<F>>> {
+        let n = polynomials[0].len();
+        ensure!(
+            polynomials.iter().all(|p| p.len() == n),
+            "Polynomial degrees inconsistent"
+        );
+        let lg_lde_size = log2_strict(n) + rate_bits;
+        let lde_size = 1 << lg_lde_size;
+        let len = polynomials.len() * lde_size;
+
+        let data = polynomials
+            .iter()
+            .flat_map(|p| to_u64s(&p.coeffs).chain(core::iter::repeat_n(0, lde_size - n)))
+            .collect::<Vec<_>>();
+        let mut buffer = self.upload(&data)?;
+
+        // Evaluating `p(shift * X)` over `H` gives the evaluations of `p` over `shift * H`.
+        let powers = F::coset_shift()
+            .powers()
+            .take(lde_size)
+            .map(|x| x.to_noncanonical_u64())
+            .collect::<Vec<_>>();
+        let powers = self.upload(&powers)?;
+        let lg_lde_size = lg_lde_size as cl_uint;
+        // SAFETY: The arguments match the parameters of the kernel.
+        self.run("mul_powers", len, |k| unsafe {
+            k.set_arg(&buffer).set_arg(&powers).set_arg(&lg_lde_size);
+        })?;
+
+        let mut scratch = self.buffer(len)?;
+        let computed_root_table;
+        let root_table = match root_table {
+            Some(table) if table.len() >= lg_lde_size as usize => table,
+            _ => {
+                computed_root_table = fft_root_table(lde_size);
+                &computed_root_table
+            }
+        };
+        self.fft_in_place(
+            &mut buffer,
+            &mut scratch,
+            polynomials.len(),
+            lg_lde_size as usize,
+            root_table,
+        )?;
+
+        let leaf_len = polynomials.len() + salt_size;
+        let num_vectors = polynomials.len() as cl_uint;
+        let leaf_len_arg = leaf_len as cl_uint;
+        let leaves = self.buffer(lde_size * leaf_len)?;
+        // SAFETY: The arguments match the parameters of the kernel.
+        self.run("to_leaves", lde_size * leaf_len, |k| unsafe {
+            k.set_arg(&buffer)
+                .set_arg(&leaves)
+                .set_arg(&lg_lde_size)
+                .set_arg(&num_vectors)
+                .set_arg(&leaf_len_arg);
+        })?;
+        let leaves = self.download(&leaves, lde_size * leaf_len)?;
+        Ok(leaves
+            .chunks_exact(leaf_len)
+            .map(|leaf| leaf.iter().map(|&x| GoldilocksField(x)).collect())
+            .collect())
+    }
+
+    fn try_reduce_polys<const D: usize>(
+        &self,
+        polys: &[&PolynomialCoeffs<F>],
+        alpha: <F as Extendable<D>>::Extension,
+    ) -> Result<PolynomialCoeffs<<F as Extendable<D>>::Extension>>
+    where
+        F: Extendable<D>,
+    {
+        let len = polys.iter().map(|p| p.len()).max().unwrap_or(0);
+        ensure!(len > 0, "No coefficients to reduce");
+
+        let data = polys
+            .iter()
+            .flat_map(|p| to_u64s(&p.coeffs).chain(core::iter::repeat_n(0, len - p.len())))
+            .collect::<Vec<_>>();
+        let polys_buffer = self.upload(&data)?;
+        let powers = alpha
+            .powers()
+            .take(polys.len())
+            .flat_map(|power| power.to_basefield_array().map(|x| x.to_noncanonical_u64()))
+            .collect::<Vec<_>>();
+        let powers = self.upload(&powers)?;
+        let out = self.buffer(len * D)?;
+
+        let num_polys = polys.len() as cl_uint;
+        let len_arg = len as cl_uint;
+        let d = D as cl_uint;
+        // SAFETY: The arguments match the parameters of the kernel.
+        self.run("reduce_polys", len, |k| unsafe {
+            k.set_arg(&polys_buffer)
+                .set_arg(&powers)
+                .set_arg(&out)
+                .set_arg(&num_polys)
+                .set_arg(&len_arg)
+                .set_arg(&d);
+        })?;
+        let out = self.download(&out, len * D)?;
+        Ok(PolynomialCoeffs::new(
+            out.chunks_exact(D)
+                .map(|limbs| {
+                    let mut array = [F::ZERO; D];
+                    for (x, &limb) in array.iter_mut().zip(limbs) {
+                        *x = GoldilocksField(limb);
+                    }
+                    <F as Extendable<D>>::Extension::from_basefield_array(array)
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Unwraps the device's `result`, or logs its error and runs `cpu` instead.
+fn or_cpu<T>(step: &str, result: Result<T>, cpu: impl FnOnce() -> T) -> T {
+    result.unwrap_or_else(|e| {
+        log::warn!("OpenCL backend failed on {step}, falling back to the CPU: {e}");
+        cpu()
+    })
+}
+
+impl ProverBackend<F> for OpenClBackend {
+    fn ifft(&self, values: Vec<PolynomialValues<F>>) -> Vec<PolynomialCoeffs<F>> {
+        // The kernels need at least one butterfly per vector.
+        if values.is_empty() || values.iter().any(|v| v.len() < 2) {
+            return CpuBackend.ifft(values);
+        }
+        or_cpu("IFFT", self.try_ifft(&values), || CpuBackend.ifft(values))
+    }
+
+    fn coset_lde_leaves(
+        &self,
+        polynomials: &[PolynomialCoeffs<F>],
+        rate_bits: usize,
+        salt_size: usize,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Vec<Vec<F>> {
+        let cpu = || CpuBackend.coset_lde_leaves(polynomials, rate_bits, salt_size, fft_root_table);
+        if polynomials.is_empty() || polynomials[0].len() << rate_bits < 2 {
+            return cpu();
+        }
+        or_cpu(
+            "coset LDE",
+            self.try_coset_lde_leaves(polynomials, rate_bits, salt_size, fft_root_table),
+            cpu,
+        )
+    }
+
+    fn reduce_polys<const D: usize>(
+        &self,
+        polys: &[&PolynomialCoeffs<F>],
+        alpha: <F as Extendable<D>>::Extension,
+    ) -> PolynomialCoeffs<<F as Extendable<D>>::Extension>
+    where
+        F: Extendable<D>,
+    {
+        let cpu = || CpuBackend.reduce_polys(polys, alpha);
+        if polys.is_empty() {
+            return cpu();
+        }
+        or_cpu(
+            "polynomial reduction",
+            self.try_reduce_polys(polys, alpha),
+            cpu,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::plonk::prover::prove_with_backend;
+    use crate::util::timing::TimingTree;
+
+    const D: usize = 2;
+
+    /// The backend on this machine's first OpenCL device, if there is one. CI sets
+    /// `PLONKY2_TEST_OPENCL` so that a missing device fails the tests instead of skipping them.
+    fn backend() -> Option<OpenClBackend> {
+        match OpenClBackend::new() {
+            Ok(backend) => Some(backend),
+            Err(e) if std::env::var_os("PLONKY2_TEST_OPENCL").is_none() => {
+                println!("Skipping, no usable OpenCL device: {e}");
+                None
+            }
+            Err(e) => panic!("No usable OpenCL device: {e}"),
+        }
+    }
+
+    #[test]
+    fn test_matches_cpu() -> Result<()> {
+        let Some(backend) = backend() else {
+            return Ok(());
+        };
+
+        let values = (0..3)
+            .map(|_| PolynomialValues::new(F::rand_vec(1 << 5)))
+            .collect::<Vec<_>>();
+        let coeffs = backend.try_ifft(&values)?;
+        assert_eq!(coeffs, CpuBackend.ifft(values));
+
+        for (rate_bits, salt_size) in [(0, 0), (1, 4), (3, 0)] {
+            assert_eq!(
+                backend.try_coset_lde_leaves(&coeffs, rate_bits, salt_size, None)?,
+                CpuBackend.coset_lde_leaves(&coeffs, rate_bits, salt_size, None)
+            );
+        }
+
+        let mut polys = coeffs.iter().collect::<Vec<_>>();
+        let short = PolynomialCoeffs::new(F::rand_vec(7));
+        polys.push(&short);
+        let alpha = <F as Extendable<D>>::Extension::rand();
+        assert_eq!(
+            backend.try_reduce_polys::<D>(&polys, alpha)?,
+            CpuBackend.reduce_polys::<D>(&polys, alpha)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prove_with_opencl() -> Result<()> {
+        type C = PoseidonGoldilocksConfig;
+        let Some(backend) = backend() else {
+            return Ok(());
+        };
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 7);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO)?;
+        let proof = prove_with_backend::<F, C, _, D>(
+            &data.prover_only,
+            &data.common,
+            pw,
+            &backend,
+            &mut TimingTree::default(),
+        )?;
+        data.verify(proof)
+    }
+}
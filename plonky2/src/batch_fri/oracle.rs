@@ -458,7 +458,7 @@ mod test {
             &fri_instances,
             &fri_openings,
             &fri_challenges,
-            &[merkle_cap.clone()],
+            core::slice::from_ref(&merkle_cap),
             &proof,
             &fri_params,
         )?;
@@ -0,0 +1,45 @@
+//! Errors which callers of the prover, the verifier and witness generation may want to handle.
+//!
+//! Fallible functions of this crate return [`anyhow::Result`]s. When they fail because of their
+//! inputs, as opposed to a bug in a circuit or in plonky2, the [`anyhow::Error`] wraps an [`Error`],
+//! which can be recovered with `downcast_ref::<Error>()`, possibly under some context.
+
+use core::fmt;
+
+use crate::iop::target::Target;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// A target was read before its value was set.
+    TargetNotSet(Target),
+    /// A target read as a boolean holds neither zero nor one.
+    NotABool(Target),
+    /// A random access index is not smaller than the size of the accessed vector.
+    AccessIndexOutOfRange { index: u64, vec_size: usize },
+    /// A lookup input is missing from its lookup table.
+    LookupInputNotInTable { input: u64 },
+    /// A proof doesn't have as many public inputs as its circuit.
+    PublicInputCount { expected: usize, actual: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TargetNotSet(target) => write!(f, "Target {:?} is not set", target),
+            Self::NotABool(target) => write!(f, "Target {:?} is not a boolean", target),
+            Self::AccessIndexOutOfRange { index, vec_size } => write!(
+                f,
+                "Access index {} is out of range for a vector of size {}",
+                index, vec_size
+            ),
+            Self::LookupInputNotInTable { input } => {
+                write!(f, "Lookup input {} is not in the lookup table", input)
+            }
+            Self::PublicInputCount { expected, actual } => {
+                write!(f, "Expected {} public inputs, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl core::error::Error for Error {}
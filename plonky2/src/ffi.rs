@@ -0,0 +1,325 @@
+//! A C interface for proving and verifying, enabled by the `ffi` feature, so that services in
+//! other languages can embed the prover. See `include/plonky2.h` for the matching declarations, and
+//! the `plonky2-ffi` crate for a C library exporting them.
+//!
+//! Circuits are built natively and loaded from `CircuitData::to_bytes`, with the default gate and
+//! generator serializers. The interface is fixed to `PoseidonGoldilocksConfig` with `D = 2`.
+//! Field elements are passed as canonical `u64`s.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use crate::field::types::{Field, Field64};
+use crate::iop::target::Target;
+use crate::iop::witness::{PartialWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_data::CircuitData;
+use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// The result of an FFI call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Plonky2Status {
+    Ok = 0,
+    /// A pointer was null, an index was out of range or a value wasn't a canonical field element.
+    InvalidArgument = 1,
+    /// Circuit data or a proof couldn't be deserialized.
+    InvalidData = 2,
+    /// A target was set twice with different values.
+    ConflictingInput = 3,
+    /// Witness generation or proving failed, e.g. because of missing or inconsistent inputs.
+    ProvingFailed = 4,
+    /// The proof is invalid.
+    VerificationFailed = 5,
+}
+
+/// A loaded circuit. Opaque to C.
+#[derive(Debug)]
+pub struct Plonky2Circuit(CircuitData<F, C, D>);
+
+/// The inputs to a proof. Opaque to C.
+#[derive(Debug)]
+pub struct Plonky2Witness(PartialWitness<F>);
+
+/// A serialized proof, as written by `ProofWithPublicInputs::to_bytes`. Must be released with
+/// `plonky2_proof_free`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Plonky2Proof {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// Loads a circuit serialized with `CircuitData::to_bytes`. Returns null on failure. The circuit
+/// must be released with `plonky2_circuit_free`.
+///
+/// # Safety
+/// `bytes` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_circuit_load(bytes: *const u8, len: usize) -> *mut Plonky2Circuit {
+    if bytes.is_null() {
+        return ptr::null_mut();
+    }
+    let bytes = slice::from_raw_parts(bytes, len);
+    let generator_serializer = DefaultGeneratorSerializer::<C, D>::default();
+    // Malformed data may make deserialization panic, which mustn't unwind into the caller.
+    let result = catch_unwind(|| {
+        CircuitData::from_bytes(bytes, &DefaultGateSerializer, &generator_serializer)
+    });
+    match result {
+        Ok(Ok(data)) => Box::into_raw(Box::new(Plonky2Circuit(data))),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `circuit` must be null or have been returned by `plonky2_circuit_load`, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_circuit_free(circuit: *mut Plonky2Circuit) {
+    if !circuit.is_null() {
+        drop(Box::from_raw(circuit));
+    }
+}
+
+/// The number of public inputs of `circuit`.
+///
+/// # Safety
+/// `circuit` must be a live circuit.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_circuit_num_public_inputs(
+    circuit: *const Plonky2Circuit,
+) -> usize {
+    circuit
+        .as_ref()
+        .map_or(0, |circuit| circuit.0.common.num_public_inputs)
+}
+
+/// Creates an empty witness, to be released with `plonky2_witness_free`.
+#[no_mangle]
+pub extern "C" fn plonky2_witness_new() -> *mut Plonky2Witness {
+    Box::into_raw(Box::new(Plonky2Witness(PartialWitness::new())))
+}
+
+/// # Safety
+/// `witness` must be null or have been returned by `plonky2_witness_new`, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_witness_free(witness: *mut Plonky2Witness) {
+    if !witness.is_null() {
+        drop(Box::from_raw(witness));
+    }
+}
+
+/// Sets the public input with the given index, i.e. the target registered in that position.
+///
+/// # Safety
+/// `circuit` and `witness` must be live.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_witness_set_public_input(
+    circuit: *const Plonky2Circuit,
+    witness: *mut Plonky2Witness,
+    index: usize,
+    value: u64,
+) -> Plonky2Status {
+    let Some(circuit) = circuit.as_ref() else {
+        return Plonky2Status::InvalidArgument;
+    };
+    match circuit.0.prover_only.public_inputs.get(index) {
+        Some(&target) => set_target(witness, target, value),
+        None => Plonky2Status::InvalidArgument,
+    }
+}
+
+/// Sets the private input held by the virtual target with the given index.
+///
+/// # Safety
+/// `witness` must be live.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_witness_set_virtual_target(
+    witness: *mut Plonky2Witness,
+    index: usize,
+    value: u64,
+) -> Plonky2Status {
+    set_target(witness, Target::VirtualTarget { index }, value)
+}
+
+unsafe fn set_target(witness: *mut Plonky2Witness, target: Target, value: u64) -> Plonky2Status {
+    let Some(witness) = witness.as_mut() else {
+        return Plonky2Status::InvalidArgument;
+    };
+    if value >= F::ORDER {
+        return Plonky2Status::InvalidArgument;
+    }
+    let value = F::from_canonical_u64(value);
+    // Check for conflicts first, as `PartialWitness::set_target` overwrites the old value.
+    match witness.0.try_get_target(target) {
+        Some(old_value) if old_value != value => Plonky2Status::ConflictingInput,
+        Some(_) => Plonky2Status::Ok,
+        None => match witness.0.set_target(target, value) {
+            Ok(()) => Plonky2Status::Ok,
+            Err(_) => Plonky2Status::ConflictingInput,
+        },
+    }
+}
+
+/// Proves `circuit` with the inputs in `witness`, writing the proof to `proof` on success.
+///
+/// # Safety
+/// `circuit` and `witness` must be live, and `proof` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_prove(
+    circuit: *const Plonky2Circuit,
+    witness: *const Plonky2Witness,
+    proof: *mut Plonky2Proof,
+) -> Plonky2Status {
+    let (Some(circuit), Some(witness), false) =
+        (circuit.as_ref(), witness.as_ref(), proof.is_null())
+    else {
+        return Plonky2Status::InvalidArgument;
+    };
+    // Panics mustn't unwind into the caller.
+    let result = catch_unwind(AssertUnwindSafe(|| circuit.0.prove(witness.0.clone())));
+    let Ok(Ok(proof_with_pis)) = result else {
+        return Plonky2Status::ProvingFailed;
+    };
+
+    let bytes = proof_with_pis.to_bytes().into_boxed_slice();
+    let len = bytes.len();
+    proof.write(Plonky2Proof {
+        data: Box::into_raw(bytes).cast(),
+        len,
+    });
+    Plonky2Status::Ok
+}
+
+/// Releases a proof returned by `plonky2_prove`.
+///
+/// # Safety
+/// `proof` must have been written by `plonky2_prove`, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_proof_free(proof: Plonky2Proof) {
+    if !proof.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            proof.data, proof.len,
+        )));
+    }
+}
+
+/// Verifies a serialized proof of `circuit`.
+///
+/// # Safety
+/// `circuit` must be live, and `proof` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn plonky2_verify(
+    circuit: *const Plonky2Circuit,
+    proof: *const u8,
+    len: usize,
+) -> Plonky2Status {
+    let (Some(circuit), false) = (circuit.as_ref(), proof.is_null()) else {
+        return Plonky2Status::InvalidArgument;
+    };
+    let bytes = slice::from_raw_parts(proof, len).to_vec();
+    // Neither deserialization nor verification may unwind into the caller.
+    let Ok(Ok(proof_with_pis)) = catch_unwind(AssertUnwindSafe(|| {
+        ProofWithPublicInputs::from_bytes(bytes, &circuit.0.common)
+    })) else {
+        return Plonky2Status::InvalidData;
+    };
+    let result = catch_unwind(AssertUnwindSafe(|| circuit.0.verify(proof_with_pis)));
+    match result {
+        Ok(Ok(())) => Plonky2Status::Ok,
+        _ => Plonky2Status::VerificationFailed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+
+    #[test]
+    fn test_ffi_prove_and_verify() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.add_virtual_target();
+        let z = builder.mul(x, y);
+        builder.register_public_input(z);
+        let data = builder.build::<C>();
+        let Target::VirtualTarget { index: y_index } = y else {
+            unreachable!()
+        };
+
+        let bytes = data
+            .to_bytes(
+                &DefaultGateSerializer,
+                &DefaultGeneratorSerializer::<C, D>::default(),
+            )
+            .unwrap();
+
+        unsafe {
+            let circuit = plonky2_circuit_load(bytes.as_ptr(), bytes.len());
+            assert!(!circuit.is_null());
+            assert_eq!(plonky2_circuit_num_public_inputs(circuit), 2);
+
+            let witness = plonky2_witness_new();
+            assert_eq!(
+                plonky2_witness_set_public_input(circuit, witness, 0, 6),
+                Plonky2Status::Ok
+            );
+            assert_eq!(
+                plonky2_witness_set_public_input(circuit, witness, 2, 6),
+                Plonky2Status::InvalidArgument
+            );
+            assert_eq!(
+                plonky2_witness_set_virtual_target(witness, y_index, F::ORDER),
+                Plonky2Status::InvalidArgument
+            );
+            assert_eq!(
+                plonky2_witness_set_virtual_target(witness, y_index, 7),
+                Plonky2Status::Ok
+            );
+            assert_eq!(
+                plonky2_witness_set_virtual_target(witness, y_index, 8),
+                Plonky2Status::ConflictingInput
+            );
+
+            let mut proof = Plonky2Proof {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(
+                plonky2_prove(circuit, witness, &mut proof),
+                Plonky2Status::Ok
+            );
+            let proof_bytes = slice::from_raw_parts(proof.data, proof.len).to_vec();
+            let proof_with_pis =
+                ProofWithPublicInputs::<F, C, D>::from_bytes(proof_bytes, &data.common).unwrap();
+            assert_eq!(proof_with_pis.public_inputs[1], F::from_canonical_u64(42));
+            assert_eq!(
+                plonky2_verify(circuit, proof.data, proof.len),
+                Plonky2Status::Ok
+            );
+
+            assert_eq!(
+                plonky2_verify(circuit, proof.data, proof.len / 2),
+                Plonky2Status::InvalidData
+            );
+            assert!(plonky2_circuit_load(proof.data, proof.len).is_null());
+
+            *proof.data.add(proof.len / 2) ^= 1;
+            assert_ne!(
+                plonky2_verify(circuit, proof.data, proof.len),
+                Plonky2Status::Ok
+            );
+
+            plonky2_proof_free(proof);
+            plonky2_witness_free(witness);
+            plonky2_circuit_free(circuit);
+        }
+    }
+}
@@ -0,0 +1,518 @@
+//! Batched openings of arbitrary polynomial commitments at arbitrary points, proven with a single
+//! FRI instance.
+//!
+//! This lets arguments built on top of the polynomial commitment scheme open their own
+//! commitments alongside each other, rather than running FRI once per commitment.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use plonky2_maybe_rayon::*;
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::fri::oracle::PolynomialBatch;
+use crate::fri::proof::{CompressedFriProof, FriProof};
+use crate::fri::structure::{
+    FriBatchInfo, FriInstanceInfo, FriOpeningBatch, FriOpenings, FriOracleInfo, FriPolynomialInfo,
+};
+use crate::fri::verifier::verify_fri_proof;
+use crate::fri::FriParams;
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::iop::challenger::Challenger;
+use crate::plonk::config::GenericConfig;
+use crate::util::timing::TimingTree;
+
+/// The openings of a FRI instance, built by registering commitments and the points at which their
+/// polynomials are opened. Openings at the same point are grouped in a single batch.
+#[derive(Clone, Debug)]
+pub struct BatchOpeningSet<F: RichField + Extendable<D>, const D: usize> {
+    oracles: Vec<FriOracleInfo>,
+    batches: Vec<FriBatchInfo<F, D>>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Default for BatchOpeningSet<F, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> BatchOpeningSet<F, D> {
+    pub const fn new() -> Self {
+        Self {
+            oracles: Vec::new(),
+            batches: Vec::new(),
+        }
+    }
+
+    /// Registers a commitment to `num_polys` polynomials, and returns its index. Commitments must
+    /// be registered in the order in which their caps are passed to `verify`.
+    pub fn add_oracle(&mut self, num_polys: usize, blinding: bool) -> usize {
+        self.oracles.push(FriOracleInfo {
+            num_polys,
+            blinding,
+        });
+        self.oracles.len() - 1
+    }
+
+    /// Opens the polynomials at `polynomial_indices` in the `oracle_index`th commitment at `point`.
+    ///
+    /// Returns an error if the oracle or a polynomial is out of range, or if `point` lies in a
+    /// coset on which the polynomials may be low-degree extended, where the FRI quotients are
+    /// undefined. This rejects every base field point of `g H`, for `g` the coset shift and `H`
+    /// the largest two-adic subgroup, which contains the LDE domain of any degree and rate.
+    pub fn open<I: IntoIterator<Item = usize>>(
+        &mut self,
+        oracle_index: usize,
+        polynomial_indices: I,
+        point: F::Extension,
+    ) -> Result<()> {
+        ensure!(
+            oracle_index < self.oracles.len(),
+            "Oracle {} out of range, as only {} oracles are registered",
+            oracle_index,
+            self.oracles.len()
+        );
+        ensure!(
+            !in_lde_coset::<F, D>(point),
+            "Can't open at {:?}, which lies in the LDE coset",
+            point
+        );
+        let num_polys = self.oracles[oracle_index].num_polys;
+        let polynomials = polynomial_indices
+            .into_iter()
+            .map(|polynomial_index| {
+                ensure!(
+                    polynomial_index < num_polys,
+                    "Polynomial {} out of range for an oracle of {} polynomials",
+                    polynomial_index,
+                    num_polys
+                );
+                Ok(FriPolynomialInfo {
+                    oracle_index,
+                    polynomial_index,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        match self.batches.iter_mut().find(|batch| batch.point == point) {
+            Some(batch) => batch.polynomials.extend(polynomials),
+            None => self.batches.push(FriBatchInfo { point, polynomials }),
+        }
+        Ok(())
+    }
+
+    pub fn instance(&self) -> FriInstanceInfo<F, D> {
+        FriInstanceInfo {
+            oracles: self.oracles.clone(),
+            batches: self.batches.clone(),
+        }
+    }
+
+    /// Evaluates the opened polynomials of `oracles` at their points.
+    pub fn openings<C: GenericConfig<D, F = F>>(
+        &self,
+        oracles: &[&PolynomialBatch<F, C, D>],
+    ) -> FriOpenings<F, D> {
+        let batches = self
+            .batches
+            .iter()
+            .map(|batch| FriOpeningBatch {
+                values: batch
+                    .polynomials
+                    .par_iter()
+                    .map(|p| {
+                        oracles[p.oracle_index].polynomials[p.polynomial_index]
+                            .to_extension::<D>()
+                            .eval(batch.point)
+                    })
+                    .collect(),
+            })
+            .collect();
+        FriOpenings { batches }
+    }
+
+    /// Computes the openings of `oracles`, observes them and proves them with a single FRI
+    /// instance. `challenger` must already have observed the caps of `oracles`.
+    #[allow(clippy::type_complexity)]
+    pub fn prove<C: GenericConfig<D, F = F>>(
+        &self,
+        oracles: &[&PolynomialBatch<F, C, D>],
+        challenger: &mut Challenger<F, C::Hasher>,
+        fri_params: &FriParams,
+        timing: &mut TimingTree,
+    ) -> Result<(FriOpenings<F, D>, FriProof<F, C::Hasher, D>)> {
+        check_params::<F>(fri_params)?;
+        ensure!(
+            oracles.len() == self.oracles.len(),
+            "Wrong number of oracles"
+        );
+        for (oracle, info) in oracles.iter().zip(&self.oracles) {
+            ensure!(
+                oracle.polynomials.len() == info.num_polys && oracle.blinding == info.blinding,
+                "Oracle doesn't match the opening set"
+            );
+            ensure!(
+                oracle.degree_log == fri_params.degree_bits
+                    && oracle.rate_bits == fri_params.config.rate_bits,
+                "Oracle of degree 2^{} at rate 2^-{} doesn't match the FRI parameters",
+                oracle.degree_log,
+                oracle.rate_bits
+            );
+        }
+
+        let openings = self.openings(oracles);
+        challenger.observe_openings(&openings);
+        let proof = PolynomialBatch::prove_openings(
+            &self.instance(),
+            oracles,
+            challenger,
+            fri_params,
+            timing,
+        );
+        Ok((openings, proof))
+    }
+
+    /// Verifies `openings` of the commitments with the given caps. `challenger` must be in the
+    /// same state as the prover's when it called `prove`.
+    pub fn verify<C: GenericConfig<D, F = F>>(
+        &self,
+        openings: &FriOpenings<F, D>,
+        caps: &[MerkleCap<F, C::Hasher>],
+        proof: &FriProof<F, C::Hasher, D>,
+        challenger: &mut Challenger<F, C::Hasher>,
+        fri_params: &FriParams,
+    ) -> Result<()> {
+        check_params::<F>(fri_params)?;
+        ensure!(caps.len() == self.oracles.len(), "Wrong number of caps");
+        self.check_openings(openings)?;
+
+        challenger.observe_openings(openings);
+        let challenges = challenger.fri_challenges::<C, D>(
+            &proof.commit_phase_merkle_caps,
+            &proof.final_poly,
+            proof.pow_witness,
+            fri_params.degree_bits,
+            &fri_params.config,
+        );
+        verify_fri_proof::<F, C, D>(
+            &self.instance(),
+            openings,
+            &challenges,
+            caps,
+            proof,
+            fri_params,
+        )
+    }
+
+    /// Like `prove`, but prunes the Merkle paths and evaluations shared between queries from the
+    /// proof, as in `FriProof::compress`.
+    #[allow(clippy::type_complexity)]
+    pub fn prove_compressed<C: GenericConfig<D, F = F>>(
+        &self,
+        oracles: &[&PolynomialBatch<F, C, D>],
+        challenger: &mut Challenger<F, C::Hasher>,
+        fri_params: &FriParams,
+        timing: &mut TimingTree,
+    ) -> Result<(FriOpenings<F, D>, CompressedFriProof<F, C::Hasher, D>)> {
+        // Replay the prover's transcript to find the query indices.
+        let mut query_challenger = challenger.clone();
+        let (openings, proof) = self.prove(oracles, challenger, fri_params, timing)?;
+        query_challenger.observe_openings(&openings);
+        let challenges = query_challenger.fri_challenges::<C, D>(
+            &proof.commit_phase_merkle_caps,
+            &proof.final_poly,
+            proof.pow_witness,
+            fri_params.degree_bits,
+            &fri_params.config,
+        );
+        let proof = proof.compress(&challenges.fri_query_indices, fri_params);
+        Ok((openings, proof))
+    }
+
+    /// Verifies a proof made with `prove_compressed`, after reconstructing the pruned Merkle paths
+    /// and evaluations.
+    pub fn verify_compressed<C: GenericConfig<D, F = F>>(
+        &self,
+        openings: &FriOpenings<F, D>,
+        caps: &[MerkleCap<F, C::Hasher>],
+        proof: &CompressedFriProof<F, C::Hasher, D>,
+        challenger: &mut Challenger<F, C::Hasher>,
+        fri_params: &FriParams,
+    ) -> Result<()> {
+        check_params::<F>(fri_params)?;
+        self.check_openings(openings)?;
+
+        let mut query_challenger = challenger.clone();
+        query_challenger.observe_openings(openings);
+        let challenges = query_challenger.fri_challenges::<C, D>(
+            &proof.commit_phase_merkle_caps,
+            &proof.final_poly,
+            proof.pow_witness,
+            fri_params.degree_bits,
+            &fri_params.config,
+        );
+        let fri_inferred_elements =
+            proof.inferred_elements::<C>(&self.instance(), openings, &challenges, fri_params)?;
+        let proof = proof
+            .clone()
+            .decompress(&challenges, fri_inferred_elements, fri_params)?;
+        self.verify::<C>(openings, caps, &proof, challenger, fri_params)
+    }
+
+    fn check_openings(&self, openings: &FriOpenings<F, D>) -> Result<()> {
+        ensure!(
+            openings.batches.len() == self.batches.len()
+                && openings
+                    .batches
+                    .iter()
+                    .zip(&self.batches)
+                    .all(|(opening, batch)| opening.values.len() == batch.polynomials.len()),
+            "Openings don't match the opening set"
+        );
+        Ok(())
+    }
+}
+
+/// Whether `point` is in `g H`, for `g` the coset shift and `H` the largest two-adic subgroup of
+/// `F`. LDE domains are subsets of it.
+fn in_lde_coset<F: RichField + Extendable<D>, const D: usize>(point: F::Extension) -> bool {
+    point.is_in_basefield()
+        && (point.to_basefield_array()[0] * F::coset_shift().inverse())
+            .exp_power_of_2(F::TWO_ADICITY)
+            == F::ONE
+}
+
+/// Checks that `fri_params` describe a FRI instance supported by the field `F`, so that the LDE
+/// domain exists and the FRI reductions don't exceed the degree.
+fn check_params<F: RichField>(fri_params: &FriParams) -> Result<()> {
+    ensure!(
+        fri_params.lde_bits() <= F::TWO_ADICITY,
+        "An LDE of 2^{} points exceeds the field's two-adicity of {}",
+        fri_params.lde_bits(),
+        F::TWO_ADICITY
+    );
+    ensure!(
+        fri_params.total_arities() <= fri_params.degree_bits,
+        "The FRI reductions of {} bits exceed the degree of 2^{}",
+        fri_params.total_arities(),
+        fri_params.degree_bits
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+    use crate::field::polynomial::PolynomialValues;
+    use crate::field::types::{Field, Sample};
+    use crate::fri::reduction_strategies::FriReductionStrategy;
+    use crate::fri::FriConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_batch_opening_set() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FE = <F as Extendable<D>>::Extension;
+
+        let degree_bits = 8;
+        let config = FriConfig {
+            rate_bits: 2,
+            cap_height: 2,
+            proof_of_work_bits: 0,
+            reduction_strategy: FriReductionStrategy::ConstantArityBits(2, 2),
+            num_query_rounds: 10,
+        };
+        let fri_params = config.fri_params(degree_bits, false);
+        let mut timing = TimingTree::default();
+
+        // Two commitments of a custom argument, opened at two shared points and one of their own.
+        let commit = |num_polys: usize, timing: &mut TimingTree| {
+            PolynomialBatch::<F, C, D>::from_values(
+                (0..num_polys)
+                    .map(|_| PolynomialValues::new(F::rand_vec(1 << degree_bits)))
+                    .collect(),
+                config.rate_bits,
+                false,
+                config.cap_height,
+                timing,
+                None,
+            )
+        };
+        let a = commit(3, &mut timing);
+        let b = commit(2, &mut timing);
+
+        let mut challenger = Challenger::<F, <C as GenericConfig<D>>::Hasher>::new();
+        challenger.observe_cap(&a.merkle_tree.cap);
+        challenger.observe_cap(&b.merkle_tree.cap);
+        let verifier_challenger = challenger.clone();
+        let zeta = challenger.get_extension_challenge::<D>();
+        let g = <FE as FieldExtension<D>>::from_basefield(F::primitive_root_of_unity(degree_bits));
+        let other_point = FE::rand();
+
+        let mut opening_set = BatchOpeningSet::<F, D>::new();
+        let a_index = opening_set.add_oracle(3, false);
+        let b_index = opening_set.add_oracle(2, false);
+        opening_set.open(a_index, 0..3, zeta)?;
+        opening_set.open(b_index, 0..2, zeta)?;
+        opening_set.open(b_index, [1], g * zeta)?;
+        opening_set.open(a_index, [2, 0], other_point)?;
+        assert_eq!(opening_set.instance().batches.len(), 3);
+
+        // Out of range oracles and polynomials, and points of the LDE coset, are rejected without
+        // changing the opening set.
+        let mut rejected = opening_set.clone();
+        assert!(rejected.open(2, [0], zeta).is_err());
+        assert!(rejected.open(b_index, [0, 2], zeta).is_err());
+        let lde_point = F::coset_shift() * F::primitive_root_of_unity(degree_bits + 2).exp_u64(5);
+        assert!(rejected
+            .open(
+                a_index,
+                [0],
+                <FE as FieldExtension<D>>::from_basefield(lde_point)
+            )
+            .is_err());
+        let num_opened = |set: &BatchOpeningSet<F, D>| {
+            set.batches
+                .iter()
+                .map(|batch| batch.polynomials.len())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(num_opened(&rejected), num_opened(&opening_set));
+
+        let (openings, proof) =
+            opening_set.prove(&[&a, &b], &mut challenger, &fri_params, &mut timing)?;
+        assert_eq!(
+            openings.batches[1].values,
+            vec![b.polynomials[1].to_extension::<D>().eval(g * zeta)]
+        );
+
+        let verify = |openings: &FriOpenings<F, D>| {
+            let mut challenger = verifier_challenger.clone();
+            let zeta_check = challenger.get_extension_challenge::<D>();
+            assert_eq!(zeta_check, zeta);
+            opening_set.verify::<C>(
+                openings,
+                &[a.merkle_tree.cap.clone(), b.merkle_tree.cap.clone()],
+                &proof,
+                &mut challenger,
+                &fri_params,
+            )
+        };
+        verify(&openings)?;
+
+        // A degree beyond the field's two-adicity is rejected rather than panicking.
+        let too_large = FriParams {
+            degree_bits: F::TWO_ADICITY,
+            ..fri_params.clone()
+        };
+        assert!(opening_set
+            .verify::<C>(
+                &openings,
+                &[a.merkle_tree.cap.clone(), b.merkle_tree.cap.clone()],
+                &proof,
+                &mut verifier_challenger.clone(),
+                &too_large,
+            )
+            .is_err());
+        assert!(opening_set
+            .prove(&[&a, &b], &mut challenger.clone(), &too_large, &mut timing)
+            .is_err());
+
+        let mut tampered = FriOpenings {
+            batches: openings
+                .batches
+                .iter()
+                .map(|batch| FriOpeningBatch {
+                    values: batch.values.clone(),
+                })
+                .collect(),
+        };
+        tampered.batches[2].values[0] += FE::ONE;
+        assert!(verify(&tampered).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_batch_opening() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // Enough queries over a small domain for many of them to share Merkle paths.
+        let degree_bits = 6;
+        let config = FriConfig {
+            rate_bits: 1,
+            cap_height: 0,
+            proof_of_work_bits: 0,
+            reduction_strategy: FriReductionStrategy::ConstantArityBits(1, 2),
+            num_query_rounds: 40,
+        };
+        let fri_params = config.fri_params(degree_bits, false);
+        let mut timing = TimingTree::default();
+        let oracle = PolynomialBatch::<F, C, D>::from_values(
+            (0..4)
+                .map(|_| PolynomialValues::new(F::rand_vec(1 << degree_bits)))
+                .collect(),
+            config.rate_bits,
+            false,
+            config.cap_height,
+            &mut timing,
+            None,
+        );
+
+        let mut challenger = Challenger::<F, <C as GenericConfig<D>>::Hasher>::new();
+        challenger.observe_cap(&oracle.merkle_tree.cap);
+        let verifier_challenger = challenger.clone();
+        let mut opening_set = BatchOpeningSet::<F, D>::new();
+        let index = opening_set.add_oracle(4, false);
+        opening_set.open(index, 0..4, challenger.get_extension_challenge::<D>())?;
+
+        let mut prover_challenger = challenger.clone();
+        let (openings, proof) =
+            opening_set.prove(&[&oracle], &mut prover_challenger, &fri_params, &mut timing)?;
+        let (compressed_openings, compressed_proof) =
+            opening_set.prove_compressed(&[&oracle], &mut challenger, &fri_params, &mut timing)?;
+        assert_eq!(
+            compressed_openings.batches[0].values,
+            openings.batches[0].values
+        );
+
+        let verify = |proof: &CompressedFriProof<F, <C as GenericConfig<D>>::Hasher, D>| {
+            let mut challenger = verifier_challenger.clone();
+            challenger.get_extension_challenge::<D>();
+            opening_set.verify_compressed::<C>(
+                &openings,
+                core::slice::from_ref(&oracle.merkle_tree.cap),
+                proof,
+                &mut challenger,
+                &fri_params,
+            )
+        };
+        verify(&compressed_proof)?;
+
+        let mut tampered = compressed_proof.clone();
+        for step in tampered.query_round_proofs.steps[0].values_mut() {
+            step.evals[0] += <F as Extendable<D>>::Extension::ONE;
+        }
+        assert!(verify(&tampered).is_err());
+
+        // Query steps missing from an untrusted proof are reported as errors.
+        let mut truncated = compressed_proof.clone();
+        truncated.query_round_proofs.steps[0].clear();
+        assert!(verify(&truncated).is_err());
+        truncated.query_round_proofs.initial_trees_proofs.clear();
+        assert!(verify(&truncated).is_err());
+
+        let size = serde_cbor::to_vec(&proof).unwrap().len();
+        let compressed_size = serde_cbor::to_vec(&compressed_proof).unwrap().len();
+        assert!(compressed_size < size);
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
 use crate::field::extension::Extendable;
 use crate::field::polynomial::PolynomialCoeffs;
 use crate::fri::proof::{FriChallenges, FriChallengesTarget};
@@ -7,7 +10,7 @@ use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::hash::hash_types::{MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::challenger::{Challenger, RecursiveChallenger};
-use crate::iop::target::Target;
+use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
 
@@ -110,4 +113,57 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
             fri_query_indices,
         }
     }
+
+    /// Same as `fri_challenges`, except that the length of the final polynomial is only known to
+    /// be one of `final_poly_lens`, chosen by the flag paired with it. `final_poly` is padded to the
+    /// largest of these lengths, and only the prefix of the selected length is observed.
+    pub(crate) fn fri_challenges_with_final_poly_lens(
+        &mut self,
+        builder: &mut CircuitBuilder<F, D>,
+        commit_phase_merkle_caps: &[MerkleCapTarget],
+        final_poly: &PolynomialCoeffsExtTarget<D>,
+        final_poly_lens: &[(BoolTarget, usize)],
+        pow_witness: Target,
+        inner_fri_config: &FriConfig,
+    ) -> FriChallengesTarget<D> {
+        let num_fri_queries = inner_fri_config.num_query_rounds;
+        // Scaling factor to combine polynomials.
+        let fri_alpha = self.get_extension_challenge(builder);
+
+        // Recover the random betas used in the FRI reductions.
+        let fri_betas = commit_phase_merkle_caps
+            .iter()
+            .map(|cap| {
+                self.observe_cap(cap);
+                self.get_extension_challenge(builder)
+            })
+            .collect();
+
+        // Run the rest of the transcript up to the PoW response once per candidate length, then
+        // continue from the state of the selected one.
+        let (flags, candidates): (Vec<_>, Vec<_>) = final_poly_lens
+            .iter()
+            .map(|&(flag, len)| {
+                let mut challenger = self.clone();
+                challenger.observe_extension_elements(&final_poly.0[..len]);
+                challenger.observe_element(pow_witness);
+                let fri_pow_response = challenger.get_challenge(builder);
+                (flag, (challenger, fri_pow_response))
+            })
+            .unzip();
+        let (challengers, fri_pow_responses): (Vec<_>, Vec<_>) = candidates.into_iter().unzip();
+        *self = Self::select(builder, &flags, &challengers);
+        let fri_pow_response = builder.select_one_hot(&flags, &fri_pow_responses);
+
+        let fri_query_indices = (0..num_fri_queries)
+            .map(|_| self.get_challenge(builder))
+            .collect();
+
+        FriChallengesTarget {
+            fri_alpha,
+            fri_betas,
+            fri_pow_response,
+            fri_query_indices,
+        }
+    }
 }
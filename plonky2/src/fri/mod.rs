@@ -6,10 +6,12 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
+use num::BigUint;
 use serde::Serialize;
 
 use crate::fri::reduction_strategies::FriReductionStrategy;
 
+pub mod batch_opening;
 mod challenges;
 pub mod oracle;
 pub mod proof;
@@ -30,7 +32,10 @@ pub struct FriConfig {
     /// Height of Merkle tree caps.
     pub cap_height: usize,
 
-    /// Number of bits used for grinding.
+    /// Number of bits used for grinding, i.e. the number of leading zeros required of the PoW
+    /// response, on top of those implied by the field size. Grinding takes about
+    /// `2^proof_of_work_bits` permutations, which are spread across threads with the `parallel`
+    /// feature; the search stops as soon as any thread finds a witness.
     pub proof_of_work_bits: u32,
 
     /// The reduction strategy to be applied at each layer during the commit phase.
@@ -40,11 +45,60 @@ pub struct FriConfig {
     pub num_query_rounds: usize,
 }
 
+/// The soundness assumption used to account for the security of FRI queries.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub enum FriSecurity {
+    /// Only relies on the proven soundness of FRI up to the Johnson bound, where each query
+    /// contributes `rate_bits / 2` bits of security.
+    Proven,
+    /// Relies on the list decoding conjecture from the ethSTARK paper, where each query
+    /// contributes `rate_bits` bits of security. This needs about half as many queries.
+    #[default]
+    ConjecturedListDecoding,
+}
+
+impl FriSecurity {
+    /// The number of query rounds needed to get `query_security_bits` bits of security from the
+    /// queries alone.
+    pub const fn num_query_rounds(self, rate_bits: usize, query_security_bits: usize) -> usize {
+        match self {
+            FriSecurity::Proven => (2 * query_security_bits).div_ceil(rate_bits),
+            FriSecurity::ConjecturedListDecoding => query_security_bits.div_ceil(rate_bits),
+        }
+    }
+
+    /// The bits of security given by `num_query_rounds` query rounds. Under
+    /// [`FriSecurity::Proven`], this is the limit for a large proximity parameter, which the
+    /// commit phase error prevents reaching; see [`FriParams::security_bits`].
+    pub const fn query_security_bits(self, rate_bits: usize, num_query_rounds: usize) -> usize {
+        match self {
+            FriSecurity::Proven => num_query_rounds * rate_bits / 2,
+            FriSecurity::ConjecturedListDecoding => num_query_rounds * rate_bits,
+        }
+    }
+}
+
 impl FriConfig {
     pub fn rate(&self) -> f64 {
         1.0 / ((1 << self.rate_bits) as f64)
     }
 
+    /// The bits of security of the query rounds and grinding under the given assumption, for a
+    /// FRI field of `field_bits` bits.
+    ///
+    /// This is the security of FRI under [`FriSecurity::ConjecturedListDecoding`]. Under
+    /// [`FriSecurity::Proven`], it's only an upper bound, as it ignores the commit phase error,
+    /// which depends on the instance size; see [`FriParams::security_bits`].
+    pub const fn query_security_bits(&self, security: FriSecurity, field_bits: usize) -> usize {
+        let query_bits = security.query_security_bits(self.rate_bits, self.num_query_rounds)
+            + self.proof_of_work_bits as usize;
+        if query_bits < field_bits {
+            query_bits
+        } else {
+            field_bits
+        }
+    }
+
     pub fn fri_params(&self, degree_bits: usize, hiding: bool) -> FriParams {
         let reduction_arity_bits = self.reduction_strategy.reduction_arity_bits(
             degree_bits,
@@ -109,4 +163,86 @@ impl FriParams {
     pub fn final_poly_len(&self) -> usize {
         1 << self.final_poly_bits()
     }
+
+    /// The bits of security of FRI with these parameters under the given assumption, for a FRI
+    /// field of `field_bits` bits, rounded down.
+    ///
+    /// Under [`FriSecurity::ConjecturedListDecoding`], this is
+    /// [`FriConfig::query_security_bits`]. Under [`FriSecurity::Proven`], the soundness error of
+    /// FRI up to the Johnson bound is `ε_C + α^s / 2^pow`, for `s` query rounds and `pow` bits of
+    /// grinding, where for any proximity parameter `m >= 3`, `α = (1 + 1/(2m)) √ρ` and the
+    /// commit phase error is
+    /// ```text
+    /// ε_C = ((m + 1/2)^7 / (3 ρ^{3/2}) n^2 + (2m + 1)(n + 1) / √ρ Σ a_i) / |F|,
+    /// ```
+    /// with `n` the LDE size and `a_i` the reduction arities; see Theorem 8.3 of BCIKS20
+    /// (<https://eprint.iacr.org/2020/654>) and the ethSTARK documentation
+    /// (<https://eprint.iacr.org/2021/582>). A larger `m` improves the query term at the expense
+    /// of the commit phase one, so this returns the best, over all `m`, of the security of the
+    /// weaker of the two terms.
+    pub fn security_bits(&self, security: FriSecurity, field_bits: usize) -> usize {
+        match security {
+            FriSecurity::ConjecturedListDecoding => {
+                self.config.query_security_bits(security, field_bits)
+            }
+            FriSecurity::Proven => {
+                let mut best = 0;
+                for m in 3.. {
+                    let query_bits = self.proven_query_security_bits(m);
+                    let commit_bits = self.commit_phase_security_bits(m, field_bits);
+                    best = best.max(query_bits.min(commit_bits));
+                    // The query term improves with `m` while the commit phase one worsens.
+                    if commit_bits <= query_bits {
+                        break;
+                    }
+                }
+                best.min(field_bits)
+            }
+        }
+    }
+
+    /// `-log2(α^s / 2^pow)`, rounded down, for the proximity parameter `m`.
+    fn proven_query_security_bits(&self, m: u32) -> usize {
+        // We bound `α^{2s} = ((2m + 1) / 2m)^{2s} / 2^{rate_bits s}` to avoid square roots.
+        let s = self.config.num_query_rounds as u32;
+        let numerator = BigUint::from(2 * m + 1).pow(2 * s);
+        let denominator = BigUint::from(2 * m).pow(2 * s)
+            << (self.config.rate_bits * self.config.num_query_rounds);
+        neg_log2_floor(&numerator, &denominator) / 2 + self.config.proof_of_work_bits as usize
+    }
+
+    /// `-log2(ε_C)`, rounded down, for the proximity parameter `m`.
+    fn commit_phase_security_bits(&self, m: u32, field_bits: usize) -> usize {
+        // Multiplying both terms by `3 * 2^7` gives
+        // `ε_C = ((2m + 1)^7 n^2 / ρ^{3/2} + 3 * 2^7 (2m + 1)(n + 1) / √ρ Σ a_i) / (3 * 2^{7 + |F|})`,
+        // where both powers of `1 / √ρ` are `√2` times a power of two if `rate_bits` is odd, so
+        // we bound `ε_C^2` instead.
+        let rate_bits = self.config.rate_bits;
+        let lde_size = BigUint::from(1u32) << self.lde_bits();
+        let arities: usize = self
+            .reduction_arity_bits
+            .iter()
+            .map(|&bits| 1 << bits)
+            .sum();
+        let first =
+            (BigUint::from(2 * m + 1).pow(7) * &lde_size * &lde_size) << (3 * rate_bits / 2);
+        let second = (BigUint::from(3u32 << 7) * (2 * m + 1) * (lde_size + 1u32) * arities)
+            << (rate_bits / 2);
+        let numerator = (first + second).pow(2) << (rate_bits % 2);
+        let denominator = BigUint::from(9u32) << (2 * (7 + field_bits));
+        neg_log2_floor(&numerator, &denominator) / 2
+    }
+}
+
+/// The largest `k` such that `numerator / denominator <= 2^{-k}`, or zero if there is none.
+fn neg_log2_floor(numerator: &BigUint, denominator: &BigUint) -> usize {
+    if numerator >= denominator {
+        return 0;
+    }
+    let k = (denominator.bits() - numerator.bits()) as usize;
+    if numerator << k > *denominator {
+        k - 1
+    } else {
+        k
+    }
 }
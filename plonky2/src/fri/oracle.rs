@@ -5,12 +5,13 @@ use itertools::Itertools;
 use plonky2_field::types::Field;
 use plonky2_maybe_rayon::*;
 
+use crate::backend::{CpuBackend, ProverBackend};
 use crate::field::extension::Extendable;
 use crate::field::fft::FftRootTable;
 use crate::field::packed::PackedField;
 use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use crate::fri::proof::FriProof;
-use crate::fri::prover::fri_proof;
+use crate::fri::prover::fri_proof_with_backend;
 use crate::fri::structure::{FriBatchInfo, FriInstanceInfo};
 use crate::fri::FriParams;
 use crate::hash::hash_types::RichField;
@@ -18,13 +19,17 @@ use crate::hash::merkle_tree::MerkleTree;
 use crate::iop::challenger::Challenger;
 use crate::plonk::config::GenericConfig;
 use crate::timed;
-use crate::util::reducing::ReducingFactor;
 use crate::util::timing::TimingTree;
-use crate::util::{log2_strict, reverse_bits, reverse_index_bits_in_place, transpose};
+use crate::util::{log2_strict, reverse_bits};
 
 /// Four (~64 bit) field elements gives ~128 bit security.
 pub const SALT_SIZE: usize = 4;
 
+/// Maximum number of changed values for which `PolynomialBatch::from_values_incremental` updates
+/// a polynomial in place rather than recomputing it. Each update costs `O(n)`, against
+/// `O(n log n)` for the IFFT and LDE.
+const MAX_SPARSE_UPDATE_ROWS: usize = 8;
+
 /// Represents a FRI oracle, i.e. a batch of polynomials which have been Merklized.
 #[derive(Eq, PartialEq, Debug)]
 pub struct PolynomialBatch<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
@@ -62,22 +67,188 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         timing: &mut TimingTree,
         fft_root_table: Option<&FftRootTable<F>>,
     ) -> Self {
-        let coeffs = timed!(
+        Self::from_values_with_backend(
+            values,
+            rate_bits,
+            blinding,
+            cap_height,
             timing,
-            "IFFT",
-            values.into_par_iter().map(|v| v.ifft()).collect::<Vec<_>>()
-        );
+            fft_root_table,
+            &CpuBackend,
+        )
+    }
+
+    /// Like `from_values`, but runs the IFFTs, LDEs and Merkle tree construction on `backend`.
+    pub fn from_values_with_backend<B: ProverBackend<F>>(
+        values: Vec<PolynomialValues<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+        backend: &B,
+    ) -> Self {
+        let coeffs = timed!(timing, "IFFT", backend.ifft(values));
 
-        Self::from_coeffs(
+        Self::from_coeffs_with_backend(
             coeffs,
             rate_bits,
             blinding,
             cap_height,
             timing,
             fft_root_table,
+            backend,
         )
     }
 
+    /// Like `from_values`, but reuses the work done for `previous`, a commitment to
+    /// `previous_values`. Polynomials whose values did not change are copied over, and those with
+    /// only a few changed values are updated by adding the corresponding Lagrange basis
+    /// polynomials, instead of going through a full IFFT and LDE.
+    ///
+    /// Blinded commitments are salted with fresh randomness, so they are always recomputed.
+    pub fn from_values_incremental(
+        values: Vec<PolynomialValues<F>>,
+        previous_values: &[PolynomialValues<F>],
+        previous: &Self,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self {
+        let degree = values[0].len();
+        let degree_log = log2_strict(degree);
+        let compatible = !blinding
+            && !previous.blinding
+            && previous.degree_log == degree_log
+            && previous.rate_bits == rate_bits
+            && previous.merkle_tree.cap.height() == cap_height
+            && previous_values.len() == values.len()
+            && previous.polynomials.len() == values.len()
+            && previous_values.iter().all(|v| v.len() == degree);
+        if !compatible {
+            return Self::from_values(
+                values,
+                rate_bits,
+                blinding,
+                cap_height,
+                timing,
+                fft_root_table,
+            );
+        }
+
+        if values.as_slice() == previous_values {
+            return Self {
+                polynomials: previous.polynomials.clone(),
+                merkle_tree: previous.merkle_tree.clone(),
+                degree_log,
+                rate_bits,
+                blinding,
+            };
+        }
+
+        let lde_bits = degree_log + rate_bits;
+        let (polynomials, lde_values): (Vec<_>, Vec<_>) = timed!(
+            timing,
+            "update IFFTs and LDEs",
+            values
+                .into_par_iter()
+                .zip(previous_values.par_iter())
+                .enumerate()
+                .map(|(i, (v, previous_v))| {
+                    let changed_rows = (0..degree)
+                        .filter(|&r| v.values[r] != previous_v.values[r])
+                        .collect::<Vec<_>>();
+                    if changed_rows.len() > MAX_SPARSE_UPDATE_ROWS {
+                        let coeffs = v.ifft();
+                        let lde = coeffs
+                            .lde(rate_bits)
+                            .coset_fft_with_options(
+                                F::coset_shift(),
+                                Some(rate_bits),
+                                fft_root_table,
+                            )
+                            .values;
+                        return (coeffs, lde);
+                    }
+
+                    let mut coeffs = previous.polynomials[i].clone();
+                    let mut lde = (0..degree << rate_bits)
+                        .map(|j| previous.merkle_tree.leaves[reverse_bits(j, lde_bits)][i])
+                        .collect::<Vec<_>>();
+                    for r in changed_rows {
+                        let delta = v.values[r] - previous_v.values[r];
+                        Self::add_lagrange_basis(&mut coeffs, &mut lde, r, delta, rate_bits);
+                    }
+                    (coeffs, lde)
+                })
+                .unzip()
+        );
+
+        // The leaves are hashed straight from the LDEs as the tree is built, rather than
+        // transposed up front.
+        let merkle_tree = timed!(
+            timing,
+            "build Merkle tree",
+            MerkleTree::new_from_columns(&lde_values, |i| reverse_bits(i, lde_bits), cap_height)
+        );
+
+        Self {
+            polynomials,
+            merkle_tree,
+            degree_log,
+            rate_bits,
+            blinding,
+        }
+    }
+
+    /// Adds `delta` times the `r`th Lagrange basis polynomial of the subgroup of size
+    /// `coeffs.len()` to both the coefficients `coeffs` and the coset LDE values `lde`.
+    fn add_lagrange_basis(
+        coeffs: &mut PolynomialCoeffs<F>,
+        lde: &mut [F],
+        r: usize,
+        delta: F,
+        rate_bits: usize,
+    ) {
+        let degree = coeffs.len();
+        let degree_log = log2_strict(degree);
+        let omega_r = F::primitive_root_of_unity(degree_log).exp_u64(r as u64);
+        let scale = delta * F::inverse_2exp(degree_log);
+
+        // L_r(X) = (1/n) sum_i (omega^{-r} X)^i.
+        for (c, p) in coeffs
+            .coeffs
+            .iter_mut()
+            .zip(omega_r.inverse().powers().take(degree))
+        {
+            *c += scale * p;
+        }
+
+        // L_r(X) = (omega^r / n) (X^n - 1) / (X - omega^r). Over the coset shift * <Omega>,
+        // X^n = shift^n (Omega^n)^j only takes 2^rate_bits distinct values.
+        let shift = F::coset_shift();
+        let lde_generator = F::primitive_root_of_unity(degree_log + rate_bits);
+        let denominators = lde_generator
+            .powers()
+            .take(lde.len())
+            .map(|p| shift * p - omega_r)
+            .collect::<Vec<_>>();
+        let denominator_invs = F::batch_multiplicative_inverse(&denominators);
+        let shift_n = shift.exp_power_of_2(degree_log);
+        let vanishing = lde_generator
+            .exp_power_of_2(degree_log)
+            .powers()
+            .take(1 << rate_bits)
+            .map(|p| shift_n * p - F::ONE)
+            .collect::<Vec<_>>();
+        let scale = scale * omega_r;
+        for (j, (l, d)) in lde.iter_mut().zip(denominator_invs).enumerate() {
+            *l += scale * vanishing[j % vanishing.len()] * d;
+        }
+    }
+
     /// Creates a list polynomial commitment for the polynomials `polynomials`.
     pub fn from_coeffs(
         polynomials: Vec<PolynomialCoeffs<F>>,
@@ -86,20 +257,48 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         cap_height: usize,
         timing: &mut TimingTree,
         fft_root_table: Option<&FftRootTable<F>>,
+    ) -> Self {
+        Self::from_coeffs_with_backend(
+            polynomials,
+            rate_bits,
+            blinding,
+            cap_height,
+            timing,
+            fft_root_table,
+            &CpuBackend,
+        )
+    }
+
+    /// Like `from_coeffs`, but runs the LDEs and Merkle tree construction on `backend`.
+    pub fn from_coeffs_with_backend<B: ProverBackend<F>>(
+        polynomials: Vec<PolynomialCoeffs<F>>,
+        rate_bits: usize,
+        blinding: bool,
+        cap_height: usize,
+        timing: &mut TimingTree,
+        fft_root_table: Option<&FftRootTable<F>>,
+        backend: &B,
     ) -> Self {
         let degree = polynomials[0].len();
-        let lde_values = timed!(
+
+        // If blinding, salt with two random elements to each leaf vector.
+        let salt_size = if blinding { SALT_SIZE } else { 0 };
+
+        let mut leaves = timed!(
             timing,
-            "FFT + blinding",
-            Self::lde_values(&polynomials, rate_bits, blinding, fft_root_table)
+            "FFT into leaves",
+            backend.coset_lde_leaves(&polynomials, rate_bits, salt_size, fft_root_table)
         );
-
-        let mut leaves = timed!(timing, "transpose LDEs", transpose(&lde_values));
-        reverse_index_bits_in_place(&mut leaves);
+        if blinding {
+            let num_polys = polynomials.len();
+            leaves
+                .par_iter_mut()
+                .for_each(|leaf| leaf[num_polys..].copy_from_slice(&F::rand_vec(salt_size)));
+        }
         let merkle_tree = timed!(
             timing,
             "build Merkle tree",
-            MerkleTree::new(leaves, cap_height)
+            backend.merkle_tree(leaves, cap_height)
         );
 
         Self {
@@ -179,10 +378,29 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         challenger: &mut Challenger<F, C::Hasher>,
         fri_params: &FriParams,
         timing: &mut TimingTree,
+    ) -> FriProof<F, C::Hasher, D> {
+        Self::prove_openings_with_backend(
+            instance,
+            oracles,
+            challenger,
+            fri_params,
+            &CpuBackend,
+            timing,
+        )
+    }
+
+    /// Like `prove_openings`, but combines the polynomials of each batch, and runs the FRI folding
+    /// steps and their Merkle trees, on `backend`.
+    pub fn prove_openings_with_backend<B: ProverBackend<F>>(
+        instance: &FriInstanceInfo<F, D>,
+        oracles: &[&Self],
+        challenger: &mut Challenger<F, C::Hasher>,
+        fri_params: &FriParams,
+        backend: &B,
+        timing: &mut TimingTree,
     ) -> FriProof<F, C::Hasher, D> {
         assert!(D > 1, "Not implemented for D=1.");
         let alpha = challenger.get_extension_challenge::<D>();
-        let mut alpha = ReducingFactor::new(alpha);
 
         // Final low-degree polynomial that goes into FRI.
         let mut final_poly = PolynomialCoeffs::empty();
@@ -196,17 +414,20 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         // The oracles used in Plonky2 are given in `FRI_ORACLES` in `plonky2/src/plonk/plonk_common.rs`.
         for FriBatchInfo { point, polynomials } in &instance.batches {
             // Collect the coefficients of all the polynomials in `polynomials`.
-            let polys_coeff = polynomials.iter().map(|fri_poly| {
-                &oracles[fri_poly.oracle_index].polynomials[fri_poly.polynomial_index]
-            });
+            let polys_coeff = polynomials
+                .iter()
+                .map(|fri_poly| {
+                    &oracles[fri_poly.oracle_index].polynomials[fri_poly.polynomial_index]
+                })
+                .collect::<Vec<_>>();
             let composition_poly = timed!(
                 timing,
                 &format!("reduce batch of {} polynomials", polynomials.len()),
-                alpha.reduce_polys_base(polys_coeff)
+                backend.reduce_polys(&polys_coeff, alpha)
             );
             let mut quotient = composition_poly.divide_by_linear(*point);
             quotient.coeffs.push(F::Extension::ZERO); // pad back to power of two
-            alpha.shift_poly(&mut final_poly);
+            final_poly *= alpha.exp_u64(polys_coeff.len() as u64);
             final_poly += quotient;
         }
 
@@ -217,7 +438,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             lde_final_poly.coset_fft(F::coset_shift().into())
         );
 
-        let fri_proof = fri_proof::<F, C, D>(
+        let fri_proof = fri_proof_with_backend::<F, C, B, D>(
             &oracles
                 .par_iter()
                 .map(|c| &c.merkle_tree)
@@ -226,9 +447,75 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             lde_final_values,
             challenger,
             fri_params,
+            backend,
             timing,
         );
 
         fri_proof
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use plonky2_field::types::Sample;
+
+    use super::*;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_from_values_incremental() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let (degree_log, rate_bits, cap_height) = (6, 2, 1);
+        let degree = 1 << degree_log;
+        let commit = |values: Vec<PolynomialValues<F>>| {
+            PolynomialBatch::<F, C, D>::from_values(
+                values,
+                rate_bits,
+                false,
+                cap_height,
+                &mut TimingTree::default(),
+                None,
+            )
+        };
+
+        let previous_values = (0..4)
+            .map(|_| PolynomialValues::new(F::rand_vec(degree)))
+            .collect::<Vec<_>>();
+        let previous = commit(previous_values.clone());
+
+        // Column 0 is unchanged, column 1 has a single change, column 2 a few and column 3 is
+        // entirely new.
+        let mut values = previous_values.clone();
+        values[1].values[5] = F::rand();
+        for r in [0, 17, 40, 63] {
+            values[2].values[r] = F::rand();
+        }
+        values[3] = PolynomialValues::new(F::rand_vec(degree));
+
+        let incremental = PolynomialBatch::<F, C, D>::from_values_incremental(
+            values.clone(),
+            &previous_values,
+            &previous,
+            rate_bits,
+            false,
+            cap_height,
+            &mut TimingTree::default(),
+            None,
+        );
+        assert_eq!(incremental, commit(values));
+
+        let unchanged = PolynomialBatch::<F, C, D>::from_values_incremental(
+            previous_values.clone(),
+            &previous_values,
+            &previous,
+            rate_bits,
+            false,
+            cap_height,
+            &mut TimingTree::default(),
+            None,
+        );
+        assert_eq!(unchanged, previous);
+    }
+}
@@ -1,12 +1,15 @@
 #[cfg(not(feature = "std"))]
 use alloc::{vec, vec::Vec};
 
-use hashbrown::HashMap;
+use anyhow::{anyhow, ensure, Result};
+use hashbrown::{HashMap, HashSet};
 use itertools::izip;
 use serde::{Deserialize, Serialize};
 
 use crate::field::extension::{flatten, unflatten, Extendable};
 use crate::field::polynomial::PolynomialCoeffs;
+use crate::fri::structure::{FriInstanceInfo, FriOpenings};
+use crate::fri::verifier::{compute_evaluation, fri_combine_initial, PrecomputedReducedOpenings};
 use crate::fri::FriParams;
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::hash::hash_types::{MerkleCapTarget, RichField};
@@ -15,9 +18,10 @@ use crate::hash::merkle_tree::MerkleCap;
 use crate::hash::path_compression::{compress_merkle_proofs, decompress_merkle_proofs};
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
-use crate::plonk::config::Hasher;
+use crate::plonk::config::{GenericConfig, Hasher};
 use crate::plonk::plonk_common::salt_size;
-use crate::plonk::proof::{FriInferredElements, ProofChallenges};
+use crate::plonk::proof::FriInferredElements;
+use crate::util::reverse_bits;
 
 /// Evaluations and Merkle proof produced by the prover in a FRI query step.
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
@@ -236,13 +240,97 @@ impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> FriProof<F, H,
 }
 
 impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> CompressedFriProof<F, H, D> {
+    /// Computes the coset elements which `compress` removed from the query steps, by replaying
+    /// the folding of the verifier. Fails if the proof lacks the query steps of some challenge,
+    /// as the proof is untrusted.
+    pub(crate) fn inferred_elements<C: GenericConfig<D, F = F, Hasher = H>>(
+        &self,
+        instance: &FriInstanceInfo<F, D>,
+        openings: &FriOpenings<F, D>,
+        challenges: &FriChallenges<F, D>,
+        params: &FriParams,
+    ) -> Result<FriInferredElements<F, D>> {
+        self.check_shape(params)?;
+        let FriChallenges {
+            fri_alpha,
+            fri_betas,
+            fri_query_indices,
+            ..
+        } = challenges;
+        let mut fri_inferred_elements = Vec::new();
+        // Holds the indices that have already been seen at each reduction depth.
+        let mut seen_indices_by_depth = vec![HashSet::new(); params.reduction_arity_bits.len()];
+        let precomputed_reduced_evals =
+            PrecomputedReducedOpenings::from_os_and_alpha(openings, *fri_alpha);
+        let log_n = params.lde_bits();
+        // Simulate the proof verification and collect the inferred elements.
+        // The content of the loop is basically the same as the `fri_verifier_query_round` function.
+        for &(mut x_index) in fri_query_indices {
+            let mut subgroup_x = F::MULTIPLICATIVE_GROUP_GENERATOR
+                * F::primitive_root_of_unity(log_n).exp_u64(reverse_bits(x_index, log_n) as u64);
+            let initial_trees_proof = self
+                .query_round_proofs
+                .initial_trees_proofs
+                .get(&x_index)
+                .ok_or_else(|| anyhow!("Missing initial trees proof for query {}", x_index))?;
+            let mut old_eval = fri_combine_initial::<F, C, D>(
+                instance,
+                initial_trees_proof,
+                *fri_alpha,
+                subgroup_x,
+                &precomputed_reduced_evals,
+                params,
+            );
+            for (i, &arity_bits) in params.reduction_arity_bits.iter().enumerate() {
+                let coset_index = x_index >> arity_bits;
+                if !seen_indices_by_depth[i].insert(coset_index) {
+                    // If this index has already been seen, we can skip the rest of the reductions.
+                    break;
+                }
+                fri_inferred_elements.push(old_eval);
+                let arity = 1 << arity_bits;
+                let mut evals = self.query_round_proofs.steps[i]
+                    .get(&coset_index)
+                    .ok_or_else(|| anyhow!("Missing query step {} for coset {}", i, coset_index))?
+                    .evals
+                    .clone();
+                ensure!(
+                    evals.len() == arity - 1,
+                    "Wrong number of evaluations in a query step"
+                );
+                let x_index_within_coset = x_index & (arity - 1);
+                evals.insert(x_index_within_coset, old_eval);
+                old_eval = compute_evaluation(
+                    subgroup_x,
+                    x_index_within_coset,
+                    arity_bits,
+                    &evals,
+                    fri_betas[i],
+                );
+                subgroup_x = subgroup_x.exp_power_of_2(arity_bits);
+                x_index = coset_index;
+            }
+        }
+        Ok(FriInferredElements(fri_inferred_elements))
+    }
+
+    /// Checks that the query steps match the number of reductions in `params`.
+    fn check_shape(&self, params: &FriParams) -> Result<()> {
+        ensure!(
+            self.query_round_proofs.steps.len() == params.reduction_arity_bits.len(),
+            "Wrong number of FRI reduction steps"
+        );
+        Ok(())
+    }
+
     /// Decompress all the Merkle paths in the FRI proof and reinsert duplicate indices.
     pub(crate) fn decompress(
         self,
-        challenges: &ProofChallenges<F, D>,
+        challenges: &FriChallenges<F, D>,
         fri_inferred_elements: FriInferredElements<F, D>,
         params: &FriParams,
-    ) -> FriProof<F, H, D> {
+    ) -> Result<FriProof<F, H, D>> {
+        self.check_shape(params)?;
         let CompressedFriProof {
             commit_phase_merkle_caps,
             query_round_proofs,
@@ -253,7 +341,7 @@ impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> CompressedFriPr
         let FriChallenges {
             fri_query_indices: indices,
             ..
-        } = &challenges.fri_challenges;
+        } = challenges;
         let mut fri_inferred_elements = fri_inferred_elements.0.into_iter();
         let cap_height = params.config.cap_height;
         let reduction_arity_bits = &params.reduction_arity_bits;
@@ -262,7 +350,7 @@ impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> CompressedFriPr
             .initial_trees_proofs
             .values()
             .next()
-            .unwrap()
+            .ok_or_else(|| anyhow!("No initial trees proofs"))?
             .evals_proofs
             .len();
 
@@ -286,7 +374,15 @@ impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> CompressedFriPr
         let mut evals_by_depth =
             vec![HashMap::<usize, Vec<_>>::new(); params.reduction_arity_bits.len()];
         for &(mut index) in indices {
-            let initial_trees_proof = query_round_proofs.initial_trees_proofs[&index].clone();
+            let initial_trees_proof = query_round_proofs
+                .initial_trees_proofs
+                .get(&index)
+                .ok_or_else(|| anyhow!("Missing initial trees proof for query {}", index))?
+                .clone();
+            ensure!(
+                initial_trees_proof.evals_proofs.len() == num_initial_trees,
+                "Wrong number of initial trees"
+            );
             for (i, (leaves_data, proof)) in
                 initial_trees_proof.evals_proofs.into_iter().enumerate()
             {
@@ -300,14 +396,24 @@ impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> CompressedFriPr
                 let FriQueryStep {
                     mut evals,
                     merkle_proof,
-                } = query_round_proofs.steps[i][&index].clone();
+                } = query_round_proofs.steps[i]
+                    .get(&index)
+                    .ok_or_else(|| anyhow!("Missing query step {} for coset {}", i, index))?
+                    .clone();
                 steps_indices[i].push(index);
                 if let Some(v) = evals_by_depth[i].get(&index) {
                     // If this index has already been seen, get `evals` from the `HashMap`.
                     evals = v.to_vec();
                 } else {
                     // Otherwise insert the next inferred element.
-                    evals.insert(index_within_coset, fri_inferred_elements.next().unwrap());
+                    ensure!(
+                        evals.len() == (1 << reduction_arity_bits[i]) - 1,
+                        "Wrong number of evaluations in a query step"
+                    );
+                    let inferred = fri_inferred_elements
+                        .next()
+                        .ok_or_else(|| anyhow!("Missing inferred FRI element"))?;
+                    evals.insert(index_within_coset, inferred);
                     evals_by_depth[i].insert(index, evals.clone());
                 }
                 steps_evals[i].push(flatten(&evals));
@@ -351,12 +457,12 @@ impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> CompressedFriPr
             })
         }
 
-        FriProof {
+        Ok(FriProof {
             commit_phase_merkle_caps,
             query_round_proofs: decompressed_query_proofs,
             final_poly,
             pow_witness,
-        }
+        })
     }
 }
 
@@ -3,6 +3,7 @@ use alloc::vec::Vec;
 
 use plonky2_maybe_rayon::*;
 
+use crate::backend::{CpuBackend, ProverBackend};
 use crate::field::extension::{flatten, unflatten, Extendable};
 use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use crate::fri::proof::{FriInitialTreeProof, FriProof, FriQueryRound, FriQueryStep};
@@ -12,7 +13,6 @@ use crate::hash::hashing::PlonkyPermutation;
 use crate::hash::merkle_tree::MerkleTree;
 use crate::iop::challenger::Challenger;
 use crate::plonk::config::GenericConfig;
-use crate::plonk::plonk_common::reduce_with_powers;
 use crate::timed;
 use crate::util::reverse_index_bits_in_place;
 use crate::util::timing::TimingTree;
@@ -27,6 +27,32 @@ pub fn fri_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const
     challenger: &mut Challenger<F, C::Hasher>,
     fri_params: &FriParams,
     timing: &mut TimingTree,
+) -> FriProof<F, C::Hasher, D> {
+    fri_proof_with_backend::<F, C, _, D>(
+        initial_merkle_trees,
+        lde_polynomial_coeffs,
+        lde_polynomial_values,
+        challenger,
+        fri_params,
+        &CpuBackend,
+        timing,
+    )
+}
+
+/// Like `fri_proof`, but folds the codewords and builds their Merkle trees on `backend`.
+pub fn fri_proof_with_backend<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    B: ProverBackend<F>,
+    const D: usize,
+>(
+    initial_merkle_trees: &[&MerkleTree<F, C::Hasher>],
+    lde_polynomial_coeffs: PolynomialCoeffs<F::Extension>,
+    lde_polynomial_values: PolynomialValues<F::Extension>,
+    challenger: &mut Challenger<F, C::Hasher>,
+    fri_params: &FriParams,
+    backend: &B,
+    timing: &mut TimingTree,
 ) -> FriProof<F, C::Hasher, D> {
     let n = lde_polynomial_values.len();
     assert_eq!(lde_polynomial_coeffs.len(), n);
@@ -35,11 +61,12 @@ pub fn fri_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const
     let (trees, final_coeffs) = timed!(
         timing,
         "fold codewords in the commitment phase",
-        fri_committed_trees::<F, C, D>(
+        fri_committed_trees::<F, C, B, D>(
             lde_polynomial_coeffs,
             lde_polynomial_values,
             challenger,
             fri_params,
+            backend,
         )
     );
 
@@ -67,11 +94,17 @@ pub(crate) type FriCommitedTrees<F, C, const D: usize> = (
     PolynomialCoeffs<<F as Extendable<D>>::Extension>,
 );
 
-fn fri_committed_trees<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+fn fri_committed_trees<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    B: ProverBackend<F>,
+    const D: usize,
+>(
     mut coeffs: PolynomialCoeffs<F::Extension>,
     mut values: PolynomialValues<F::Extension>,
     challenger: &mut Challenger<F, C::Hasher>,
     fri_params: &FriParams,
+    backend: &B,
 ) -> FriCommitedTrees<F, C, D> {
     let mut trees = Vec::with_capacity(fri_params.reduction_arity_bits.len());
 
@@ -85,22 +118,16 @@ fn fri_committed_trees<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>,
             .par_chunks(arity)
             .map(|chunk: &[F::Extension]| flatten(chunk))
             .collect();
-        let tree = MerkleTree::<F, C::Hasher>::new(chunked_values, fri_params.config.cap_height);
+        let tree: MerkleTree<F, C::Hasher> =
+            backend.merkle_tree(chunked_values, fri_params.config.cap_height);
 
         challenger.observe_cap(&tree.cap);
         trees.push(tree);
 
         let beta = challenger.get_extension_challenge::<D>();
         // P(x) = sum_{i<r} x^i * P_i(x^r) becomes sum_{i<r} beta^i * P_i(x).
-        coeffs = PolynomialCoeffs::new(
-            coeffs
-                .coeffs
-                .par_chunks_exact(arity)
-                .map(|chunk| reduce_with_powers(chunk, beta))
-                .collect::<Vec<_>>(),
-        );
         shift = shift.exp_u64(arity as u64);
-        values = coeffs.coset_fft(shift.into())
+        (coeffs, values) = backend.fri_fold(&coeffs, *arity_bits, beta, shift);
     }
 
     // The coefficients being removed here should always be zero.
@@ -143,9 +170,11 @@ pub(crate) fn fri_proof_of_work<
     let witness_input_pos = challenger.input_buffer.len();
     duplex_intermediate_state.set_from_iter(challenger.input_buffer.clone(), 0);
 
+    // Take the smallest valid candidate rather than any, so that the proof doesn't depend on how the
+    // search was scheduled across threads.
     let pow_witness = (0..=F::NEG_ONE.to_canonical_u64())
         .into_par_iter()
-        .find_any(|&candidate| {
+        .find_first(|&candidate| {
             let mut duplex_state = duplex_intermediate_state;
             duplex_state.set_elt(F::from_canonical_u64(candidate), witness_input_pos);
             duplex_state.permute();
@@ -219,3 +248,41 @@ fn fri_prover_query_round<
         steps: query_steps,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::fri::reduction_strategies::FriReductionStrategy;
+    use crate::fri::verifier::fri_verify_proof_of_work;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_fri_proof_of_work() -> anyhow::Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        for proof_of_work_bits in [0, 8, 16] {
+            let config = FriConfig {
+                rate_bits: 3,
+                cap_height: 0,
+                proof_of_work_bits,
+                reduction_strategy: FriReductionStrategy::Fixed(vec![]),
+                num_query_rounds: 1,
+            };
+            let mut challenger = Challenger::<F, <C as GenericConfig<D>>::Hasher>::new();
+            challenger.observe_elements(&F::rand_vec(5));
+            let mut verifier_challenger = challenger.clone();
+
+            let pow_witness = fri_proof_of_work::<F, C, D>(&mut challenger, &config);
+            verifier_challenger.observe_element(pow_witness);
+            fri_verify_proof_of_work::<F, D>(verifier_challenger.get_challenge(), &config)?;
+        }
+
+        Ok(())
+    }
+}
@@ -17,9 +17,10 @@ use crate::hash::hash_types::{MerkleCapTarget, RichField};
 use crate::iop::ext_target::{flatten_target, ExtensionTarget};
 use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CircuitConfig;
 use crate::plonk::config::{AlgebraicHasher, GenericConfig};
 use crate::util::reducing::ReducingFactorTarget;
-use crate::util::{log2_strict, reverse_index_bits_in_place};
+use crate::util::reverse_index_bits_in_place;
 use crate::with_context;
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
@@ -33,26 +34,32 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         evals: &[ExtensionTarget<D>],
         beta: ExtensionTarget<D>,
     ) -> ExtensionTarget<D> {
-        let arity = 1 << arity_bits;
-        debug_assert_eq!(evals.len(), arity);
+        let step = FriStepHints::new(arity_bits, &self.config);
+        self.compute_evaluation_with_hints(&step, x, x_index_within_coset_bits, evals, beta)
+    }
 
-        let g = F::primitive_root_of_unity(arity_bits);
-        let g_inv = g.exp_u64((arity as u64) - 1);
+    /// Like `compute_evaluation`, with the constants of the reduction step already computed.
+    fn compute_evaluation_with_hints(
+        &mut self,
+        step: &FriStepHints<F, D>,
+        x: Target,
+        x_index_within_coset_bits: &[BoolTarget],
+        evals: &[ExtensionTarget<D>],
+        beta: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D> {
+        debug_assert_eq!(evals.len(), 1 << step.arity_bits);
 
         // The evaluation vector needs to be reordered first.
         let mut evals = evals.to_vec();
         reverse_index_bits_in_place(&mut evals);
         // Want `g^(arity - rev_x_index_within_coset)` as in the out-of-circuit version. Compute it
         // as `(g^-1)^rev_x_index_within_coset`.
-        let start = self.exp_from_bits_const_base(g_inv, x_index_within_coset_bits.iter().rev());
+        let start =
+            self.exp_from_bits_const_base(step.g_inv, x_index_within_coset_bits.iter().rev());
         let coset_start = self.mul(start, x);
 
         // The answer is gotten by interpolating {(x*g^i, P(x*g^i))} and evaluating at beta.
-        let interpolation_gate = <CosetInterpolationGate<F, D>>::with_max_degree(
-            arity_bits,
-            self.config.max_quotient_degree_factor,
-        );
-        self.interpolate_coset(interpolation_gate, coset_start, &evals, beta)
+        self.interpolate_coset(step.interpolation_gate.clone(), coset_start, &evals, beta)
     }
 
     /// Make sure we have enough wires and routed wires to do the FRI checks efficiently. This check
@@ -123,8 +130,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             "Final polynomial has wrong degree."
         );
 
-        // Size of the LDE domain.
-        let n = params.lde_size();
+        let hints = FriVerifierHints::new(params, &self.config);
 
         with_context!(
             self,
@@ -171,9 +177,112 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
                     initial_merkle_caps,
                     proof,
                     challenges.fri_query_indices[i],
-                    n,
                     round_proof,
                     params,
+                    &hints,
+                )
+            );
+        }
+    }
+
+    /// Same as `verify_fri_proof`, except that the degree of the committed polynomials is only
+    /// known to be that of one of `params`, chosen by the flag paired with it. The candidates must
+    /// differ only in their degree, and `proof` must be shaped for the largest one.
+    pub(crate) fn verify_fri_proof_with_degree_flags<C: GenericConfig<D, F = F>>(
+        &mut self,
+        instance: &FriInstanceInfoTarget<D>,
+        openings: &FriOpeningsTarget<D>,
+        challenges: &FriChallengesTarget<D>,
+        initial_merkle_caps: &[MerkleCapTarget],
+        proof: &FriProofTarget<D>,
+        params: &[(BoolTarget, FriParams)],
+    ) where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        let (_, max_params) = params
+            .iter()
+            .max_by_key(|(_, p)| p.degree_bits)
+            .expect("No FRI parameters given");
+        for (_, p) in params {
+            assert_eq!(p.config, max_params.config);
+            assert_eq!(p.hiding, max_params.hiding);
+            assert_eq!(
+                p.reduction_arity_bits, max_params.reduction_arity_bits,
+                "All degrees must use the same FRI reduction arities."
+            );
+        }
+
+        if let Some(max_arity_bits) = max_params.max_arity_bits() {
+            self.check_recursion_config(max_arity_bits);
+        }
+
+        debug_assert_eq!(
+            max_params.final_poly_len(),
+            proof.final_poly.len(),
+            "Final polynomial has wrong degree."
+        );
+
+        with_context!(
+            self,
+            "check PoW",
+            self.fri_verify_proof_of_work(challenges.fri_pow_response, &max_params.config)
+        );
+
+        // Coefficients beyond the final polynomial length of the selected degree must be zero, so
+        // that evaluating the padded polynomial agrees with evaluating the actual one.
+        with_context!(self, "check final polynomial padding", {
+            for (j, &coeff) in proof.final_poly.0.iter().enumerate() {
+                let padded = params
+                    .iter()
+                    .filter(|(_, p)| p.final_poly_len() <= j)
+                    .map(|(flag, _)| flag.target)
+                    .collect::<Vec<_>>();
+                if padded.is_empty() {
+                    continue;
+                }
+                let is_padding = self.add_many(padded);
+                let masked = self.scalar_mul_ext(is_padding, coeff);
+                let zero = self.zero_extension();
+                self.connect_extension(masked, zero);
+            }
+        });
+
+        // Only the reduction steps are shared by all degrees.
+        let hints = FriVerifierHints::new(max_params, &self.config);
+
+        let precomputed_reduced_evals = with_context!(
+            self,
+            "precompute reduced evaluations",
+            PrecomputedReducedOpeningsTarget::from_os_and_alpha(
+                openings,
+                challenges.fri_alpha,
+                self
+            )
+        );
+
+        for (i, round_proof) in proof.query_round_proofs.iter().enumerate() {
+            let level = if i == 1 {
+                log::Level::Debug
+            } else {
+                log::Level::Trace
+            };
+
+            let num_queries = proof.query_round_proofs.len();
+            with_context!(
+                self,
+                level,
+                &format!("verify one (of {num_queries}) query rounds"),
+                self.fri_verifier_query_round_with_degree_flags::<C>(
+                    instance,
+                    challenges,
+                    &precomputed_reduced_evals,
+                    initial_merkle_caps,
+                    proof,
+                    challenges.fri_query_indices[i],
+                    round_proof,
+                    params,
+                    max_params,
+                    &hints,
                 )
             );
         }
@@ -258,18 +367,15 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         initial_merkle_caps: &[MerkleCapTarget],
         proof: &FriProofTarget<D>,
         x_index: Target,
-        n: usize,
         round_proof: &FriQueryRoundTarget<D>,
         params: &FriParams,
+        hints: &FriVerifierHints<F, D>,
     ) where
         C::Hasher: AlgebraicHasher<F>,
     {
-        let n_log = log2_strict(n);
-
-        // Note that this `low_bits` decomposition permits non-canonical binary encodings. Here we
-        // verify that this has a negligible impact on soundness error.
-        Self::assert_noncanonical_indices_ok(&params.config);
-        let mut x_index_bits = self.low_bits(x_index, n_log, F::BITS);
+        // Note that this `low_bits` decomposition permits non-canonical binary encodings, which
+        // `FriVerifierHints::new` checks to have a negligible impact on soundness error.
+        let mut x_index_bits = self.low_bits(x_index, params.lde_bits(), F::BITS);
 
         let cap_index =
             self.le_sum(x_index_bits[x_index_bits.len() - params.config.cap_height..].iter());
@@ -287,8 +393,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         // `subgroup_x` is `subgroup[x_index]`, i.e., the actual field element in the domain.
         let mut subgroup_x = with_context!(self, "compute x from its index", {
             let g = self.constant(F::coset_shift());
-            let phi = F::primitive_root_of_unity(n_log);
-            let phi = self.exp_from_bits_const_base(phi, x_index_bits.iter().rev());
+            let phi = self.exp_from_bits_const_base(hints.phi, x_index_bits.iter().rev());
             // subgroup_x = g * phi
             self.mul(g, phi)
         });
@@ -308,7 +413,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             )
         );
 
-        for (i, &arity_bits) in params.reduction_arity_bits.iter().enumerate() {
+        for (i, step) in hints.steps.iter().enumerate() {
+            let arity_bits = step.arity_bits;
             let evals = &round_proof.steps[i].evals;
 
             // Split x_index into the index of the coset x is in, and the index of x within that coset.
@@ -324,10 +430,10 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             old_eval = with_context!(
                 self,
                 "infer evaluation using interpolation",
-                self.compute_evaluation(
+                self.compute_evaluation_with_hints(
+                    step,
                     subgroup_x,
                     x_index_within_coset_bits,
-                    arity_bits,
                     evals,
                     challenges.fri_betas[i],
                 )
@@ -364,6 +470,150 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.connect_extension(eval, old_eval);
     }
 
+    fn fri_verifier_query_round_with_degree_flags<C: GenericConfig<D, F = F>>(
+        &mut self,
+        instance: &FriInstanceInfoTarget<D>,
+        challenges: &FriChallengesTarget<D>,
+        precomputed_reduced_evals: &PrecomputedReducedOpeningsTarget<D>,
+        initial_merkle_caps: &[MerkleCapTarget],
+        proof: &FriProofTarget<D>,
+        x_index: Target,
+        round_proof: &FriQueryRoundTarget<D>,
+        params: &[(BoolTarget, FriParams)],
+        max_params: &FriParams,
+        hints: &FriVerifierHints<F, D>,
+    ) where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        let cap_height = max_params.config.cap_height;
+        let flags = params.iter().map(|&(flag, _)| flag).collect::<Vec<_>>();
+
+        // The query index is reduced modulo the LDE size of the selected degree. Since all sizes
+        // are powers of two, the bits of each reduced index are a prefix of the largest one's.
+        let mut x_index_bits = self.low_bits(x_index, max_params.lde_bits(), F::BITS);
+
+        let cap_indices = params
+            .iter()
+            .map(|(_, p)| {
+                let n_log = p.lde_bits();
+                self.le_sum(x_index_bits[n_log - cap_height..n_log].iter())
+            })
+            .collect::<Vec<_>>();
+        let cap_index = self.select_one_hot(&flags, &cap_indices);
+
+        let mut merkle_proof_lens = params
+            .iter()
+            .map(|&(flag, ref p)| (flag, p.lde_bits() - cap_height))
+            .collect::<Vec<_>>();
+        with_context!(self, "check FRI initial proof", {
+            for (i, ((evals, merkle_proof), cap)) in round_proof
+                .initial_trees_proof
+                .evals_proofs
+                .iter()
+                .zip(initial_merkle_caps)
+                .enumerate()
+            {
+                with_context!(
+                    self,
+                    &format!("verify {i}'th initial Merkle proof"),
+                    self.verify_variable_length_merkle_proof_to_cap_with_cap_index::<C::Hasher>(
+                        evals.clone(),
+                        &x_index_bits,
+                        cap_index,
+                        cap,
+                        merkle_proof,
+                        &merkle_proof_lens,
+                    )
+                );
+            }
+        });
+
+        // `subgroup_x` is `subgroup[x_index]`, i.e., the actual field element in the domain.
+        let mut subgroup_x = with_context!(self, "compute x from its index", {
+            let g = self.constant(F::coset_shift());
+            let xs = params
+                .iter()
+                .map(|(_, p)| {
+                    let n_log = p.lde_bits();
+                    let phi = F::primitive_root_of_unity(n_log);
+                    let phi =
+                        self.exp_from_bits_const_base(phi, x_index_bits[..n_log].iter().rev());
+                    // subgroup_x = g * phi
+                    self.mul(g, phi)
+                })
+                .collect::<Vec<_>>();
+            self.select_one_hot(&flags, &xs)
+        });
+
+        let mut old_eval = with_context!(
+            self,
+            "combine initial oracles",
+            self.fri_combine_initial(
+                instance,
+                &round_proof.initial_trees_proof,
+                challenges.fri_alpha,
+                subgroup_x,
+                precomputed_reduced_evals,
+                max_params,
+            )
+        );
+
+        for (i, step) in hints.steps.iter().enumerate() {
+            let arity_bits = step.arity_bits;
+            let evals = &round_proof.steps[i].evals;
+
+            let coset_index_bits = x_index_bits[arity_bits..].to_vec();
+            let x_index_within_coset_bits = &x_index_bits[..arity_bits];
+            let x_index_within_coset = self.le_sum(x_index_within_coset_bits.iter());
+
+            let new_eval = self.random_access_extension(x_index_within_coset, evals.clone());
+            self.connect_extension(new_eval, old_eval);
+
+            old_eval = with_context!(
+                self,
+                "infer evaluation using interpolation",
+                self.compute_evaluation_with_hints(
+                    step,
+                    subgroup_x,
+                    x_index_within_coset_bits,
+                    evals,
+                    challenges.fri_betas[i],
+                )
+            );
+
+            for (_, len) in &mut merkle_proof_lens {
+                *len -= arity_bits;
+            }
+            with_context!(
+                self,
+                "verify FRI round Merkle proof.",
+                self.verify_variable_length_merkle_proof_to_cap_with_cap_index::<C::Hasher>(
+                    flatten_target(evals),
+                    &coset_index_bits,
+                    cap_index,
+                    &proof.commit_phase_merkle_caps[i],
+                    &round_proof.steps[i].merkle_proof,
+                    &merkle_proof_lens,
+                )
+            );
+
+            subgroup_x = self.exp_power_of_2(subgroup_x, arity_bits);
+
+            x_index_bits = coset_index_bits;
+        }
+
+        // The final polynomial is padded with zeros, so evaluating it at full length is fine.
+        let eval = with_context!(
+            self,
+            &format!(
+                "evaluate final polynomial of length {}",
+                proof.final_poly.len()
+            ),
+            proof.final_poly.eval_scalar(self, subgroup_x)
+        );
+        self.connect_extension(eval, old_eval);
+    }
+
     /// We decompose FRI query indices into bits without verifying that the decomposition given by
     /// the prover is the canonical one. In particular, if `x_index < 2^field_bits - p`, then the
     /// prover could supply the binary encoding of either `x_index` or `x_index + p`, since the are
@@ -460,6 +710,49 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 }
 
+/// Values of the FRI verifier which only depend on the FRI parameters, computed once when building
+/// the verifier rather than in every query round.
+struct FriVerifierHints<F: RichField + Extendable<D>, const D: usize> {
+    /// The generator of the LDE domain, whose powers by the reversed query index bits give `x`.
+    phi: F,
+    steps: Vec<FriStepHints<F, D>>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> FriVerifierHints<F, D> {
+    fn new(params: &FriParams, config: &CircuitConfig) -> Self {
+        CircuitBuilder::<F, D>::assert_noncanonical_indices_ok(&params.config);
+        Self {
+            phi: F::primitive_root_of_unity(params.lde_bits()),
+            steps: params
+                .reduction_arity_bits
+                .iter()
+                .map(|&arity_bits| FriStepHints::new(arity_bits, config))
+                .collect(),
+        }
+    }
+}
+
+/// The constants of a FRI reduction step.
+struct FriStepHints<F: RichField + Extendable<D>, const D: usize> {
+    arity_bits: usize,
+    /// The inverse of the generator of the cosets which are folded.
+    g_inv: F,
+    interpolation_gate: CosetInterpolationGate<F, D>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> FriStepHints<F, D> {
+    fn new(arity_bits: usize, config: &CircuitConfig) -> Self {
+        Self {
+            arity_bits,
+            g_inv: F::primitive_root_of_unity(arity_bits).inverse(),
+            interpolation_gate: CosetInterpolationGate::with_max_degree(
+                arity_bits,
+                config.max_quotient_degree_factor,
+            ),
+        }
+    }
+}
+
 /// For each opening point, holds the reduced (by `alpha`) evaluations of each polynomial that's
 /// opened at that point.
 #[derive(Clone)]
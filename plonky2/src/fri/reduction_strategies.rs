@@ -23,6 +23,11 @@ pub enum FriReductionStrategy {
     /// optional max `arity_bits`. If this proof will have recursive proofs on top of it, a max
     /// `arity_bits` of 3 is recommended.
     MinSize(Option<usize>),
+
+    /// `MinVerifierGates(opt_max_arity_bits)` searches for the sequence of reduction arities
+    /// minimizing an estimate of the number of gates needed to verify the FRI proof in a recursive
+    /// circuit, with an optional max `arity_bits`.
+    MinVerifierGates(Option<usize>),
 }
 
 impl FriReductionStrategy {
@@ -51,6 +56,15 @@ impl FriReductionStrategy {
             FriReductionStrategy::MinSize(opt_max_arity_bits) => {
                 min_size_arity_bits(degree_bits, rate_bits, num_queries, *opt_max_arity_bits)
             }
+            FriReductionStrategy::MinVerifierGates(opt_max_arity_bits) => {
+                min_verifier_gates_arity_bits(
+                    degree_bits,
+                    rate_bits,
+                    cap_height,
+                    num_queries,
+                    *opt_max_arity_bits,
+                )
+            }
         }
     }
 }
@@ -67,8 +81,14 @@ fn min_size_arity_bits(
 
     #[cfg(feature = "timing")]
     let start = Instant::now();
-    let (mut arity_bits, fri_proof_size) =
-        min_size_arity_bits_helper(degree_bits, rate_bits, num_queries, max_arity_bits, vec![]);
+    let (mut arity_bits, fri_proof_size) = min_cost_arity_bits_helper(
+        degree_bits,
+        rate_bits,
+        rate_bits,
+        max_arity_bits,
+        vec![],
+        &|arity_bits| relative_proof_size(degree_bits, rate_bits, num_queries, arity_bits),
+    );
     arity_bits.shrink_to_fit();
 
     #[cfg(feature = "timing")]
@@ -77,6 +97,8 @@ fn min_size_arity_bits(
         start.elapsed().as_secs_f32()
     );
     debug!(
+        arity_bits:?,
+        fri_proof_size;
         "Smallest arity_bits {:?} results in estimated FRI proof size of {} elements",
         arity_bits, fri_proof_size
     );
@@ -84,20 +106,56 @@ fn min_size_arity_bits(
     arity_bits
 }
 
-/// Return `(arity_bits, fri_proof_size)`.
-fn min_size_arity_bits_helper(
+fn min_verifier_gates_arity_bits(
     degree_bits: usize,
     rate_bits: usize,
+    cap_height: usize,
     num_queries: usize,
+    opt_max_arity_bits: Option<usize>,
+) -> Vec<usize> {
+    // Larger arities need wider interpolation gates than the standard recursion config offers.
+    let max_arity_bits = opt_max_arity_bits.unwrap_or(4);
+
+    // The last Merkle tree must not be shorter than the cap.
+    let min_layer_bits = rate_bits.max(cap_height);
+    let (mut arity_bits, num_gates) = min_cost_arity_bits_helper(
+        degree_bits,
+        rate_bits,
+        min_layer_bits,
+        max_arity_bits,
+        vec![],
+        &|arity_bits| {
+            relative_verifier_gates(degree_bits, rate_bits, cap_height, num_queries, arity_bits)
+        },
+    );
+    arity_bits.shrink_to_fit();
+
+    debug!(
+        arity_bits:?,
+        num_gates;
+        "Cheapest arity_bits {:?} results in an estimated FRI verifier cost of {} gates",
+        arity_bits, num_gates
+    );
+
+    arity_bits
+}
+
+/// Return `(arity_bits, cost)` for the sequence of arities starting with `prefix` which minimizes
+/// `cost`, while keeping layers of at least `min_layer_bits` bits.
+fn min_cost_arity_bits_helper(
+    degree_bits: usize,
+    rate_bits: usize,
+    min_layer_bits: usize,
     global_max_arity_bits: usize,
     prefix: Vec<usize>,
+    cost: &dyn Fn(&[usize]) -> usize,
 ) -> (Vec<usize>, usize) {
     let sum_of_arities: usize = prefix.iter().sum();
     let current_layer_bits = degree_bits + rate_bits - sum_of_arities;
-    assert!(current_layer_bits >= rate_bits);
+    assert!(current_layer_bits >= min_layer_bits);
 
     let mut best_arity_bits = prefix.clone();
-    let mut best_size = relative_proof_size(degree_bits, rate_bits, num_queries, &prefix);
+    let mut best_cost = cost(&prefix);
 
     // The largest next_arity_bits to search. Note that any optimal arity sequence will be
     // monotonically non-increasing, as a larger arity will shrink more Merkle proofs if it occurs
@@ -106,26 +164,27 @@ fn min_size_arity_bits_helper(
         .last()
         .copied()
         .unwrap_or(global_max_arity_bits)
-        .min(current_layer_bits - rate_bits);
+        .min(current_layer_bits - min_layer_bits);
 
     for next_arity_bits in 1..=max_arity_bits {
         let mut extended_prefix = prefix.clone();
         extended_prefix.push(next_arity_bits);
 
-        let (arity_bits, size) = min_size_arity_bits_helper(
+        let (arity_bits, cost) = min_cost_arity_bits_helper(
             degree_bits,
             rate_bits,
-            num_queries,
+            min_layer_bits,
             max_arity_bits,
             extended_prefix,
+            cost,
         );
-        if size < best_size {
+        if cost < best_cost {
             best_arity_bits = arity_bits;
-            best_size = size;
+            best_cost = cost;
         }
     }
 
-    (best_arity_bits, best_size)
+    (best_arity_bits, best_cost)
 }
 
 /// Compute the approximate size of a FRI proof with the given reduction arities. Note that this
@@ -160,3 +219,70 @@ fn relative_proof_size(
 
     total_elems
 }
+
+/// Estimate the number of gates needed to verify a FRI proof with the given reduction arities in a
+/// recursive circuit, counting one gate per hash permutation, per coset interpolation and per 32
+/// coefficients of the final polynomial. This ignores the initial Merkle proofs, which aren't
+/// affected by arities.
+fn relative_verifier_gates(
+    degree_bits: usize,
+    rate_bits: usize,
+    cap_height: usize,
+    num_queries: usize,
+    arity_bits: &[usize],
+) -> usize {
+    const D: usize = 2;
+    const HASH_RATE: usize = 8;
+    const COEFFS_PER_REDUCING_GATE: usize = 32;
+
+    let mut current_layer_bits = degree_bits + rate_bits;
+
+    let mut gates_per_query = 0;
+    for arity_bits in arity_bits {
+        let arity = 1 << arity_bits;
+        current_layer_bits -= arity_bits;
+
+        // Hash the coset's evaluations, then verify its Merkle path up to the cap.
+        gates_per_query += (arity * D).div_ceil(HASH_RATE);
+        gates_per_query += current_layer_bits.saturating_sub(cap_height);
+        // Select the evaluation within the coset, then interpolate the coset.
+        gates_per_query += 2;
+    }
+
+    // Evaluate the final polynomial.
+    let final_poly_len = 1 << (current_layer_bits - rate_bits);
+    gates_per_query += (final_poly_len * D).div_ceil(COEFFS_PER_REDUCING_GATE);
+
+    gates_per_query * num_queries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_verifier_gates() {
+        let (degree_bits, rate_bits, cap_height, num_queries) = (13, 3, 4, 28);
+        let cost = |arity_bits: &[usize]| {
+            relative_verifier_gates(degree_bits, rate_bits, cap_height, num_queries, arity_bits)
+        };
+        let arity_bits_for = |strategy: FriReductionStrategy| {
+            strategy.reduction_arity_bits(degree_bits, rate_bits, cap_height, num_queries)
+        };
+
+        let arity_bits = arity_bits_for(FriReductionStrategy::MinVerifierGates(None));
+        assert!(arity_bits.iter().all(|&bits| (1..=4).contains(&bits)));
+        assert!(arity_bits.iter().sum::<usize>() <= degree_bits + rate_bits - cap_height);
+
+        for other in [
+            FriReductionStrategy::ConstantArityBits(4, 5),
+            FriReductionStrategy::MinSize(None),
+            FriReductionStrategy::MinSize(Some(3)),
+        ] {
+            assert!(cost(&arity_bits) <= cost(&arity_bits_for(other)));
+        }
+
+        let arity_bits = arity_bits_for(FriReductionStrategy::MinVerifierGates(Some(2)));
+        assert!(arity_bits.iter().all(|&bits| bits <= 2));
+    }
+}
@@ -11,7 +11,7 @@ use crate::fri::structure::{FriBatchInfo, FriInstanceInfo, FriOpenings};
 use crate::fri::validate_shape::validate_fri_proof_shape;
 use crate::fri::{FriConfig, FriParams};
 use crate::hash::hash_types::RichField;
-use crate::hash::merkle_proofs::verify_merkle_proof_to_cap;
+use crate::hash::merkle_proofs::{verify_merkle_proof_to_cap, MerkleVerificationCache};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::plonk::config::{GenericConfig, Hasher};
 use crate::util::reducing::ReducingFactor;
@@ -70,6 +70,33 @@ pub fn verify_fri_proof<
     initial_merkle_caps: &[MerkleCap<F, C::Hasher>],
     proof: &FriProof<F, C::Hasher, D>,
     params: &FriParams,
+) -> Result<()> {
+    verify_fri_proof_with_cache::<F, C, D>(
+        instance,
+        openings,
+        challenges,
+        initial_merkle_caps,
+        proof,
+        params,
+        None,
+    )
+}
+
+/// Same as `verify_fri_proof`, except that openings of the first initial oracle are verified
+/// through `first_oracle_cache`, if given. This lets proofs which share that oracle, such as the
+/// preprocessed polynomials of one circuit, share the work of verifying its Merkle paths.
+pub(crate) fn verify_fri_proof_with_cache<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    instance: &FriInstanceInfo<F, D>,
+    openings: &FriOpenings<F, D>,
+    challenges: &FriChallenges<F, D>,
+    initial_merkle_caps: &[MerkleCap<F, C::Hasher>],
+    proof: &FriProof<F, C::Hasher, D>,
+    params: &FriParams,
+    mut first_oracle_cache: Option<&mut MerkleVerificationCache<F, C::Hasher>>,
 ) -> Result<()> {
     validate_fri_proof_shape::<F, C, D>(proof, instance, params)?;
 
@@ -87,9 +114,22 @@ pub fn verify_fri_proof<
 
     let precomputed_reduced_evals =
         PrecomputedReducedOpenings::from_os_and_alpha(openings, challenges.fri_alpha);
-    for (&x_index, round_proof) in challenges
+
+    // `subgroup_xs[i]` is `subgroup[x_index]` for the `i`th query, i.e., the actual field element
+    // in the domain. The exponentiations share the same base, so we compute them all at once.
+    let log_n = log2_strict(n);
+    let subgroup_xs = F::primitive_root_of_unity(log_n).batch_exp_u64(
+        &challenges
+            .fri_query_indices
+            .iter()
+            .map(|&x_index| reverse_bits(x_index, log_n) as u64)
+            .collect::<Vec<_>>(),
+    );
+
+    for ((&x_index, &subgroup_x), round_proof) in challenges
         .fri_query_indices
         .iter()
+        .zip(&subgroup_xs)
         .zip(&proof.query_round_proofs)
     {
         fri_verifier_query_round::<F, C, D>(
@@ -99,9 +139,10 @@ pub fn verify_fri_proof<
             initial_merkle_caps,
             proof,
             x_index,
-            n,
+            F::MULTIPLICATIVE_GROUP_GENERATOR * subgroup_x,
             round_proof,
             params,
+            first_oracle_cache.as_deref_mut(),
         )?;
     }
 
@@ -112,8 +153,15 @@ fn fri_verify_initial_proof<F: RichField, H: Hasher<F>>(
     x_index: usize,
     proof: &FriInitialTreeProof<F, H>,
     initial_merkle_caps: &[MerkleCap<F, H>],
+    first_oracle_cache: Option<&mut MerkleVerificationCache<F, H>>,
 ) -> Result<()> {
-    for ((evals, merkle_proof), cap) in proof.evals_proofs.iter().zip(initial_merkle_caps) {
+    let mut evals_proofs = proof.evals_proofs.iter().zip(initial_merkle_caps);
+    if let Some(cache) = first_oracle_cache {
+        if let Some(((evals, merkle_proof), cap)) = evals_proofs.next() {
+            cache.verify_merkle_proof_to_cap(evals, x_index, cap, merkle_proof)?;
+        }
+    }
+    for ((evals, merkle_proof), cap) in evals_proofs {
         verify_merkle_proof_to_cap::<F, H>(evals.clone(), x_index, cap, merkle_proof)?;
     }
 
@@ -172,19 +220,17 @@ fn fri_verifier_query_round<
     initial_merkle_caps: &[MerkleCap<F, C::Hasher>],
     proof: &FriProof<F, C::Hasher, D>,
     mut x_index: usize,
-    n: usize,
+    mut subgroup_x: F,
     round_proof: &FriQueryRound<F, C::Hasher, D>,
     params: &FriParams,
+    first_oracle_cache: Option<&mut MerkleVerificationCache<F, C::Hasher>>,
 ) -> Result<()> {
     fri_verify_initial_proof::<F, C::Hasher>(
         x_index,
         &round_proof.initial_trees_proof,
         initial_merkle_caps,
+        first_oracle_cache,
     )?;
-    // `subgroup_x` is `subgroup[x_index]`, i.e., the actual field element in the domain.
-    let log_n = log2_strict(n);
-    let mut subgroup_x = F::MULTIPLICATIVE_GROUP_GENERATOR
-        * F::primitive_root_of_unity(log_n).exp_u64(reverse_bits(x_index, log_n) as u64);
 
     // old_eval is the last derived evaluation; it will be checked for consistency with its
     // committed "parent" value in the next iteration.
@@ -1,9 +1,11 @@
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use itertools::Itertools;
 
 use crate::field::extension::Extendable;
+use crate::field::types::Field;
 use crate::fri::proof::{FriProof, FriProofTarget};
-use crate::hash::hash_types::RichField;
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::hash::merkle_proofs::{MerkleProof, MerkleProofTarget};
 use crate::iop::witness::WitnessWrite;
 use crate::plonk::config::AlgebraicHasher;
 
@@ -73,3 +75,61 @@ where
 
     Ok(())
 }
+
+/// Same as `set_fri_proof_target`, except that `fri_proof` may come from a polynomial of smaller
+/// degree than the one `fri_proof_target` was shaped for. Missing Merkle siblings and final
+/// polynomial coefficients are set to zero.
+pub fn set_padded_fri_proof_target<F, W, H, const D: usize>(
+    witness: &mut W,
+    fri_proof_target: &FriProofTarget<D>,
+    fri_proof: &FriProof<F, H, D>,
+) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    W: WitnessWrite<F> + ?Sized,
+    H: AlgebraicHasher<F>,
+{
+    let mut fri_proof = fri_proof.clone();
+
+    let final_poly_len = fri_proof_target.final_poly.len();
+    ensure!(
+        fri_proof.final_poly.len() <= final_poly_len,
+        "Final polynomial is longer than its target."
+    );
+    fri_proof
+        .final_poly
+        .coeffs
+        .resize(final_poly_len, F::Extension::ZERO);
+
+    for (qt, q) in fri_proof_target
+        .query_round_proofs
+        .iter()
+        .zip_eq(&mut fri_proof.query_round_proofs)
+    {
+        for (at, a) in qt
+            .initial_trees_proof
+            .evals_proofs
+            .iter()
+            .zip_eq(&mut q.initial_trees_proof.evals_proofs)
+        {
+            pad_merkle_proof(&at.1, &mut a.1)?;
+        }
+        for (st, s) in qt.steps.iter().zip_eq(&mut q.steps) {
+            pad_merkle_proof(&st.merkle_proof, &mut s.merkle_proof)?;
+        }
+    }
+
+    set_fri_proof_target(witness, fri_proof_target, &fri_proof)
+}
+
+fn pad_merkle_proof<F: RichField, H: AlgebraicHasher<F>>(
+    target: &MerkleProofTarget,
+    proof: &mut MerkleProof<F, H>,
+) -> Result<()> {
+    ensure!(
+        proof.siblings.len() <= target.siblings.len(),
+        "Merkle proof is longer than its target."
+    );
+    proof.siblings.resize(target.siblings.len(), HashOut::ZERO);
+    Ok(())
+}
@@ -15,7 +15,7 @@ use crate::gates::multiplication_extension::MulExtensionGate;
 use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::{ExtensionAlgebraTarget, ExtensionTarget};
 use crate::iop::generator::{GeneratedValues, SimpleGenerator};
-use crate::iop::target::Target;
+use crate::iop::target::{BoolTarget, Target};
 use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::circuit_data::CommonCircuitData;
@@ -435,7 +435,6 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     /// Exponentiate `base` to the power of a known `exponent`.
-    // TODO: Test
     pub fn exp_u64_extension(
         &mut self,
         base: ExtensionTarget<D>,
@@ -462,6 +461,36 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         product
     }
 
+    /// Exponentiates `base` to the power of `exponent`, given by its little-endian bits.
+    pub fn exp_from_bits_extension(
+        &mut self,
+        base: ExtensionTarget<D>,
+        exponent_bits: impl IntoIterator<Item = impl Borrow<BoolTarget>>,
+    ) -> ExtensionTarget<D> {
+        let one = self.one_extension();
+        let mut current = base;
+        let mut product = one;
+        for (i, bit) in exponent_bits.into_iter().enumerate() {
+            if i != 0 {
+                current = self.square_extension(current);
+            }
+            let factor = self.select_ext(*bit.borrow(), current, one);
+            product = self.mul_extension(product, factor);
+        }
+        product
+    }
+
+    /// Exponentiates `base` to the power of `exponent`, where `exponent < 2^num_bits`.
+    pub fn exp_extension(
+        &mut self,
+        base: ExtensionTarget<D>,
+        exponent: Target,
+        num_bits: usize,
+    ) -> ExtensionTarget<D> {
+        let exponent_bits = self.split_le(exponent, num_bits);
+        self.exp_from_bits_extension(base, exponent_bits)
+    }
+
     /// Computes `x / y`. Results in an unsatisfiable instance if `y = 0`.
     pub fn div_extension(
         &mut self,
@@ -604,7 +633,8 @@ mod tests {
     use anyhow::Result;
 
     use crate::field::extension::algebra::ExtensionAlgebra;
-    use crate::field::types::Sample;
+    use crate::field::extension::FieldExtension;
+    use crate::field::types::{Field, Sample};
     use crate::iop::ext_target::ExtensionAlgebraTarget;
     use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_builder::CircuitBuilder;
@@ -648,6 +678,47 @@ mod tests {
         verify(proof, &data.verifier_only, &data.common)
     }
 
+    #[test]
+    fn test_base_and_extension_mixing() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let [a, b, s, e] = builder.add_virtual_target_arr();
+        let x = builder.ext_from_base_targets(&[a, b]);
+        let scaled = builder.scalar_mul_ext(s, x);
+        let cubed = builder.exp_u64_extension(x, 3);
+        let powered = builder.exp_extension(x, e, 8);
+        let embedded = builder.ext_from_base_targets(&[s]);
+        let projected = builder.project_to_base(embedded);
+        for t in [scaled, cubed, powered] {
+            builder.register_public_inputs(&t.0);
+        }
+        builder.register_public_input(projected);
+        let data = builder.build::<C>();
+
+        let [av, bv, sv] = F::rand_array();
+        let xv = FF::from_basefield_array([av, bv]);
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&[a, b, s], &[av, bv, sv])?;
+        pw.set_target(e, F::from_canonical_u64(200))?;
+        let proof = data.prove(pw)?;
+
+        let expected: Vec<_> = [
+            <FF as FieldExtension<D>>::from_basefield(sv) * xv,
+            xv.cube(),
+            xv.exp_u64(200),
+        ]
+        .iter()
+        .flat_map(<FF as FieldExtension<D>>::to_basefield_array)
+        .chain([sv])
+        .collect();
+        assert_eq!(proof.public_inputs, expected);
+        data.verify(proof)
+    }
+
     #[test]
     fn test_div_extension() -> Result<()> {
         const D: usize = 2;
@@ -655,7 +726,7 @@ mod tests {
         type F = <C as GenericConfig<D>>::F;
         type FF = <C as GenericConfig<D>>::FE;
 
-        let config = CircuitConfig::standard_recursion_zk_config();
+        let config = CircuitConfig::zk_config();
 
         let pw = PartialWitness::new();
         let mut builder = CircuitBuilder::<F, D>::new(config);
@@ -40,7 +40,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 #[cfg(test)]
 mod tests {
     #[cfg(not(feature = "std"))]
-    use alloc::vec::Vec;
+    use alloc::{vec, vec::Vec};
 
     use anyhow::Result;
 
@@ -86,7 +86,7 @@ mod tests {
 
         let value_targets = values
             .iter()
-            .map(|&v| (builder.constant_extension(v)))
+            .map(|&v| builder.constant_extension(v))
             .collect::<Vec<_>>();
 
         let zt = builder.constant_extension(z);
@@ -111,4 +111,64 @@ mod tests {
 
         verify(proof, &data.verifier_only, &data.common)
     }
+
+    #[test]
+    fn test_interpolate_above_quotient_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type FF = <C as GenericConfig<D>>::FE;
+        let mut config = CircuitConfig::standard_recursion_config();
+        config.fri_config.rate_bits = 4;
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        // A 16 point interpolation in a single gate has degree 16, twice the default
+        // `max_quotient_degree_factor`.
+        let subgroup_bits = 4;
+        let gate = CosetInterpolationGate::new(subgroup_bits);
+        assert_eq!(gate.degree, 16);
+
+        let coset_shift = F::rand();
+        let g = F::primitive_root_of_unity(subgroup_bits);
+        let points = F::cyclic_subgroup_coset_known_order(g, coset_shift, 1 << subgroup_bits);
+        let values = FF::rand_vec(1 << subgroup_bits);
+        let homogeneous_points = points
+            .iter()
+            .zip(values.iter())
+            .map(|(&a, &b)| (<FF as FieldExtension<D>>::from_basefield(a), b))
+            .collect::<Vec<_>>();
+        let z = FF::rand();
+        let true_eval = interpolant(&homogeneous_points).eval(z);
+
+        let coset_shift_target = builder.constant(coset_shift);
+        let value_targets = values
+            .iter()
+            .map(|&v| builder.constant_extension(v))
+            .collect::<Vec<_>>();
+        let zt = builder.constant_extension(z);
+        let eval = builder.interpolate_coset(gate, coset_shift_target, &value_targets, zt);
+        let true_eval_target = builder.constant_extension(true_eval);
+        builder.connect_extension(eval, true_eval_target);
+
+        let data = builder.build::<C>();
+        assert_eq!(data.common.quotient_degree_factor, 16);
+        let proof = data.prove(PartialWitness::new())?;
+
+        verify(proof, &data.verifier_only, &data.common)
+    }
+
+    #[test]
+    #[should_panic(expected = "requires `rate_bits` to be at least 4")]
+    fn test_interpolate_above_rate() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let coset_shift = builder.one();
+        let values = vec![builder.zero_extension(); 16];
+        let zt = builder.zero_extension();
+        builder.interpolate_coset(CosetInterpolationGate::new(4), coset_shift, &values, zt);
+    }
 }
@@ -1,13 +1,24 @@
 #[cfg(not(feature = "std"))]
-use alloc::{borrow::ToOwned, vec};
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use anyhow::Result;
 
 use crate::field::extension::Extendable;
 use crate::gates::lookup::LookupGate;
 use crate::gates::lookup_table::{LookupTable, LookupTableGate};
 use crate::gates::noop::NoopGate;
 use crate::hash::hash_types::RichField;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator};
 use crate::iop::target::Target;
+use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
 
 /// Lookup tables used in the tests and benchmarks.
 ///
@@ -75,6 +86,62 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         looking_out
     }
 
+    /// Returns the index of the identity table over all bytes, adding it if needed. Since stored
+    /// tables are deduplicated, every gadget using it shares a single table.
+    pub fn add_byte_lookup_table(&mut self) -> usize {
+        let bytes = (0..=u8::MAX as u16).collect::<Vec<_>>();
+        self.add_lookup_table_from_fn(|x| x, &bytes)
+    }
+
+    /// Checks that `x < 2^n_log` by looking up its bytes in the shared byte table. This is
+    /// cheaper than `range_check` when many values are checked in the same circuit.
+    pub fn range_check_with_lookup(&mut self, x: Target, n_log: usize) {
+        self.split_le_bytes(x, n_log);
+    }
+
+    /// Returns the little-endian bytes of `x`, checking that `x < 2^n_log` through lookups in the
+    /// shared byte table.
+    pub fn split_le_bytes(&mut self, x: Target, n_log: usize) -> Vec<Target> {
+        assert!(
+            n_log < F::BITS,
+            "Cannot range check {} bits in a {}-bit field",
+            n_log,
+            F::BITS
+        );
+        let num_bytes = n_log.div_ceil(8);
+        let bytes = self.add_virtual_targets(num_bytes);
+        self.add_simple_generator(ByteDecompositionGenerator {
+            integer: x,
+            bytes: bytes.clone(),
+        });
+
+        if num_bytes > 0 {
+            let byte_table = self.add_byte_lookup_table();
+            for &byte in &bytes {
+                self.add_lookup_from_index(byte, byte_table);
+            }
+            // The top byte must also fit in the remaining bits, i.e. still be a byte when shifted
+            // to the top.
+            let top_bits = n_log % 8;
+            if top_bits != 0 {
+                let shifted = self.mul_const(
+                    F::from_canonical_u64(1 << (8 - top_bits)),
+                    bytes[num_bytes - 1],
+                );
+                self.add_lookup_from_index(shifted, byte_table);
+            }
+        }
+
+        let base = F::from_canonical_u64(1 << 8);
+        let zero = self.zero();
+        let sum = bytes
+            .iter()
+            .rev()
+            .fold(zero, |acc, &byte| self.mul_const_add(base, acc, byte));
+        self.connect(x, sum);
+        bytes
+    }
+
     /// We call this function at the end of circuit building right before the PI gate to add all `LookupTableGate` and `LookupGate`.
     /// It also updates `self.lookup_rows` accordingly.
     pub fn add_all_lookups(&mut self) {
@@ -154,3 +221,45 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 }
+
+#[derive(Debug, Default)]
+pub struct ByteDecompositionGenerator {
+    integer: Target,
+    bytes: Vec<Target>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
+    for ByteDecompositionGenerator
+{
+    fn id(&self) -> String {
+        "ByteDecompositionGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        vec![self.integer]
+    }
+
+    fn run_once(
+        &self,
+        witness: &PartitionWitness<F>,
+        out_buffer: &mut GeneratedValues<F>,
+    ) -> Result<()> {
+        let mut integer_value = witness.get_target(self.integer).to_canonical_u64();
+        for &byte in &self.bytes {
+            out_buffer.set_target(byte, F::from_canonical_u64(integer_value & 0xff))?;
+            integer_value >>= 8;
+        }
+        Ok(())
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_target(self.integer)?;
+        dst.write_target_vec(&self.bytes)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let integer = src.read_target()?;
+        let bytes = src.read_target_vec()?;
+        Ok(Self { integer, bytes })
+    }
+}
@@ -7,6 +7,7 @@ pub mod arithmetic_extension;
 pub mod hash;
 pub mod interpolation;
 pub mod lookup;
+pub mod ops;
 pub mod polynomial;
 pub mod random_access;
 pub mod range_check;
@@ -0,0 +1,216 @@
+//! Operator overloading for base field arithmetic on targets.
+//!
+//! [`CircuitBuilder::ops`] borrows a builder, and [`Var`]s created from it can be combined with
+//! `+`, `-`, `*`, `/` and unary `-`, each operation adding the gates it needs to the builder:
+//!
+//! ```
+//! use plonky2::field::types::Field;
+//! use plonky2::plonk::circuit_builder::CircuitBuilder;
+//! use plonky2::plonk::circuit_data::CircuitConfig;
+//! use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+//!
+//! const D: usize = 2;
+//! type F = <PoseidonGoldilocksConfig as GenericConfig<D>>::F;
+//!
+//! let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+//! let [x, y] = builder.add_virtual_target_arr();
+//! let ops = builder.ops();
+//! let (x, y) = (ops.var(x), ops.var(y));
+//! // x^2 + 3xy - 1
+//! let z = (x * x + x * y * F::from_canonical_u64(3) - F::ONE).target();
+//! builder.register_public_input(z);
+//! ```
+//!
+//! Operations are built one at a time, so `a * b + c` takes two arithmetic operations; use
+//! [`Var::mul_add`] where the fused operation matters.
+
+use core::cell::RefCell;
+use core::fmt;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::Target;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// A mutable borrow of a [`CircuitBuilder`], which [`Var`]s share to add gates.
+pub struct Ops<'a, F: RichField + Extendable<D>, const D: usize> {
+    builder: RefCell<&'a mut CircuitBuilder<F, D>>,
+}
+
+impl<'a, F: RichField + Extendable<D>, const D: usize> Ops<'a, F, D> {
+    /// Wraps `target` to use it with operators.
+    pub const fn var(&'a self, target: Target) -> Var<'a, F, D> {
+        Var { ops: self, target }
+    }
+
+    /// A constant, usable with operators.
+    pub fn constant(&'a self, c: F) -> Var<'a, F, D> {
+        let target = self.with_builder(|builder| builder.constant(c));
+        self.var(target)
+    }
+
+    /// Gives access to the builder, e.g. to call gadgets without operators. Panics if called
+    /// from within another `with_builder`.
+    pub fn with_builder<R>(&self, f: impl FnOnce(&mut CircuitBuilder<F, D>) -> R) -> R {
+        f(&mut self.builder.borrow_mut())
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> fmt::Debug for Ops<'_, F, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ops").finish_non_exhaustive()
+    }
+}
+
+/// A target which can be combined with operators, adding gates to the builder of its [`Ops`].
+#[derive(Copy, Clone)]
+pub struct Var<'a, F: RichField + Extendable<D>, const D: usize> {
+    ops: &'a Ops<'a, F, D>,
+    target: Target,
+}
+
+impl<'a, F: RichField + Extendable<D>, const D: usize> Var<'a, F, D> {
+    pub const fn target(self) -> Target {
+        self.target
+    }
+
+    /// Computes `self * y + z` in a single arithmetic operation.
+    pub fn mul_add(self, y: Self, z: Self) -> Self {
+        self.apply(|builder| builder.mul_add(self.target, y.target, z.target))
+    }
+
+    pub fn square(self) -> Self {
+        self.apply(|builder| builder.square(self.target))
+    }
+
+    pub fn exp_u64(self, exponent: u64) -> Self {
+        self.apply(|builder| builder.exp_u64(self.target, exponent))
+    }
+
+    pub fn inverse(self) -> Self {
+        self.apply(|builder| builder.inverse(self.target))
+    }
+
+    fn apply(self, f: impl FnOnce(&mut CircuitBuilder<F, D>) -> Target) -> Self {
+        self.ops.var(self.ops.with_builder(f))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> fmt::Debug for Var<'_, F, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Var").field(&self.target).finish()
+    }
+}
+
+impl<'a, F: RichField + Extendable<D>, const D: usize> From<Var<'a, F, D>> for Target {
+    fn from(var: Var<'a, F, D>) -> Self {
+        var.target
+    }
+}
+
+/// Implements a binary operator between `Var`s, and between a `Var` and a constant.
+macro_rules! impl_binary_op {
+    ($trait:ident, $method:ident, $op:ident, $const_op:expr) => {
+        impl<'a, F: RichField + Extendable<D>, const D: usize> $trait for Var<'a, F, D> {
+            type Output = Self;
+
+            fn $method(self, rhs: Self) -> Self {
+                self.apply(|builder| builder.$op(self.target, rhs.target))
+            }
+        }
+
+        impl<'a, F: RichField + Extendable<D>, const D: usize> $trait<F> for Var<'a, F, D> {
+            type Output = Self;
+
+            fn $method(self, rhs: F) -> Self {
+                self.apply(|builder| ($const_op)(builder, self.target, rhs))
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add, add, |builder: &mut CircuitBuilder<F, D>, x, c| {
+    builder.add_const(x, c)
+});
+impl_binary_op!(Sub, sub, sub, |builder: &mut CircuitBuilder<F, D>,
+                                x,
+                                c: F| {
+    builder.add_const(x, -c)
+});
+impl_binary_op!(Mul, mul, mul, |builder: &mut CircuitBuilder<F, D>, x, c| {
+    builder.mul_const(c, x)
+});
+impl_binary_op!(Div, div, div, |builder: &mut CircuitBuilder<F, D>,
+                                x,
+                                c: F| {
+    builder.mul_const(c.inverse(), x)
+});
+
+impl<'a, F: RichField + Extendable<D>, const D: usize> Neg for Var<'a, F, D> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.apply(|builder| builder.neg(self.target))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Borrows this builder to combine targets with operators; see [`Var`].
+    pub fn ops(&mut self) -> Ops<'_, F, D> {
+        Ops {
+            builder: RefCell::new(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::field::ops::Square;
+    use crate::field::types::{Field, Sample};
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_ops() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let [x, y, z] = builder.add_virtual_target_arr();
+        let three = F::from_canonical_u64(3);
+        let ops = builder.ops();
+        let (xv, yv, zv) = (ops.var(x), ops.var(y), ops.var(z));
+        let results = [
+            xv + yv * zv - ops.constant(three),
+            -(xv / yv) + F::ONE,
+            xv.mul_add(yv, zv).square() * three,
+            (xv - three) / three,
+            zv.exp_u64(5) * yv.inverse(),
+        ]
+        .map(|var| var.target());
+        builder.register_public_inputs(&results);
+        let data = builder.build::<C>();
+
+        let [xs, ys, zs] = F::rand_array();
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&[x, y, z], &[xs, ys, zs])?;
+        let proof = data.prove(pw)?;
+        assert_eq!(
+            proof.public_inputs,
+            [
+                xs + ys * zs - three,
+                -(xs / ys) + F::ONE,
+                (xs * ys + zs).square() * three,
+                (xs - three) / three,
+                zs.exp_u64(5) * ys.inverse(),
+            ]
+        );
+        data.verify(proof)
+    }
+}
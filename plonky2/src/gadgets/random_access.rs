@@ -67,19 +67,32 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         selected.into()
     }
 
+    /// Like `random_access_hash`, but selects a whole list of hashes, e.g. a Merkle path, out of
+    /// lists of equal length.
+    pub fn random_access_hashes(
+        &mut self,
+        access_index: Target,
+        v: Vec<Vec<HashOutTarget>>,
+    ) -> Vec<HashOutTarget> {
+        let len = v[0].len();
+        assert!(v.iter().all(|hashes| hashes.len() == len));
+
+        (0..len)
+            .map(|i| {
+                self.random_access_hash(access_index, v.iter().map(|hashes| hashes[i]).collect())
+            })
+            .collect()
+    }
+
     /// Like `random_access`, but with `MerkleCapTarget`s rather than simple `Target`s.
     pub fn random_access_merkle_cap(
         &mut self,
         access_index: Target,
         v: Vec<MerkleCapTarget>,
     ) -> MerkleCapTarget {
-        let cap_size = v[0].0.len();
-        assert!(v.iter().all(|cap| cap.0.len() == cap_size));
-
-        let selected = (0..cap_size)
-            .map(|i| self.random_access_hash(access_index, v.iter().map(|cap| cap.0[i]).collect()))
-            .collect();
-        MerkleCapTarget(selected)
+        MerkleCapTarget(
+            self.random_access_hashes(access_index, v.into_iter().map(|cap| cap.0).collect()),
+        )
     }
 
     /// Like `random_access`, but with `VerifierCircuitTarget`s rather than simple `Target`s.
@@ -105,8 +118,9 @@ mod tests {
     use anyhow::Result;
 
     use super::*;
+    use crate::error::Error;
     use crate::field::types::{Field, Sample};
-    use crate::iop::witness::PartialWitness;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_data::CircuitConfig;
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
     use crate::plonk::verifier::verify;
@@ -143,4 +157,60 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_random_access_hashes() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let access_index = builder.add_virtual_target();
+        let lists: Vec<Vec<_>> = (0..4).map(|_| builder.add_virtual_hashes(3)).collect();
+        let selected = builder.random_access_hashes(access_index, lists.clone());
+        for &h in &selected {
+            builder.register_public_inputs(&h.elements);
+        }
+        let data = builder.build::<C>();
+
+        let values: Vec<Vec<_>> = lists
+            .iter()
+            .map(|list| F::rand_vec(4 * list.len()))
+            .collect();
+        let mut pw = PartialWitness::new();
+        pw.set_target(access_index, F::TWO)?;
+        for (list, values) in lists.iter().zip(&values) {
+            for (h, elements) in list.iter().zip(values.chunks(4)) {
+                pw.set_target_arr(&h.elements, elements)?;
+            }
+        }
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs, values[2]);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_random_access_out_of_range() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let access_index = builder.add_virtual_target();
+        let v = (0..4)
+            .map(|i| builder.constant(F::from_canonical_usize(i)))
+            .collect();
+        builder.random_access(access_index, v);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(access_index, F::from_canonical_usize(4))?;
+        let err = data.prove(pw).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::AccessIndexOutOfRange {
+                index: 4,
+                vec_size: 4
+            })
+        );
+        Ok(())
+    }
 }
@@ -57,6 +57,26 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let zero = self.zero();
         self.connect(z, zero);
     }
+
+    /// Constrains `x` to be 0 or 1, returning it as a [`BoolTarget`].
+    pub fn constrain_bool(&mut self, x: Target) -> BoolTarget {
+        let b = BoolTarget::new_unsafe(x);
+        self.assert_bool(b);
+        b
+    }
+
+    /// Checks whether `x < y`, where both are assumed to be range-checked for having `num_bits`
+    /// bits. This range checks `x - y + 2^num_bits`, whose top bit is set iff `x >= y`.
+    pub fn is_less_than(&mut self, x: Target, y: Target, num_bits: usize) -> BoolTarget {
+        assert!(
+            num_bits < F::BITS - 1,
+            "Comparing {num_bits}-bit targets would overflow the field"
+        );
+        let diff = self.sub(x, y);
+        let shifted = self.add_const(diff, F::from_canonical_u64(1 << num_bits));
+        let bits = self.split_le(shifted, num_bits + 1);
+        self.not(bits[num_bits])
+    }
 }
 
 #[derive(Debug, Default)]
@@ -109,3 +129,72 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for Low
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+
+    #[test]
+    fn test_is_less_than() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let pairs = [
+            (0, 0),
+            (3, 5),
+            (5, 3),
+            (7, 7),
+            (0, 255),
+            (255, 0),
+            (254, 255),
+        ];
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let targets = pairs.map(|_| builder.add_virtual_target_arr::<2>());
+        for [x, y] in targets {
+            let lt = builder.is_less_than(x, y, 8);
+            builder.register_public_input(lt.target);
+        }
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        for ([x, y], (xv, yv)) in targets.into_iter().zip(pairs) {
+            pw.set_target(x, F::from_canonical_u64(xv))?;
+            pw.set_target(y, F::from_canonical_u64(yv))?;
+        }
+        let proof = data.prove(pw)?;
+        let expected = pairs.map(|(x, y)| F::from_bool(x < y));
+        assert_eq!(proof.public_inputs, expected);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_conditional_assert_eq() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let [flag, x, y] = builder.add_virtual_target_arr();
+        let flag = builder.constrain_bool(flag);
+        builder.conditional_assert_eq(flag, x, y);
+        let data = builder.build::<C>();
+
+        let prove = |flag_value: bool| {
+            let mut pw = PartialWitness::new();
+            pw.set_bool_target(flag, flag_value)?;
+            pw.set_target(x, F::ONE)?;
+            pw.set_target(y, F::TWO)?;
+            data.prove(pw)
+        };
+        data.verify(prove(false)?)?;
+        assert!(prove(true).is_err());
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use crate::field::extension::Extendable;
-use crate::hash::hash_types::RichField;
+use crate::hash::hash_types::{HashOutTarget, RichField};
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
@@ -34,6 +34,43 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let tmp = self.mul_sub(b.target, y, y);
         self.mul_sub(b.target, x, tmp)
     }
+
+    /// Like `select`, but with `HashOutTarget`s rather than simple `Target`s.
+    pub fn select_hash(
+        &mut self,
+        b: BoolTarget,
+        h0: HashOutTarget,
+        h1: HashOutTarget,
+    ) -> HashOutTarget {
+        HashOutTarget {
+            elements: core::array::from_fn(|i| self.select(b, h0.elements[i], h1.elements[i])),
+        }
+    }
+
+    /// Returns the sum of `flags[i] * xs[i]`. When exactly one of `flags` is set, this selects the
+    /// corresponding element of `xs`; the caller is responsible for enforcing that.
+    pub fn select_one_hot(&mut self, flags: &[BoolTarget], xs: &[Target]) -> Target {
+        debug_assert_eq!(flags.len(), xs.len());
+        let mut acc = self.zero();
+        for (flag, &x) in flags.iter().zip(xs) {
+            acc = self.mul_add(flag.target, x, acc);
+        }
+        acc
+    }
+
+    /// Like `select_one_hot`, but with `ExtensionTarget`s rather than simple `Target`s.
+    pub fn select_one_hot_ext(
+        &mut self,
+        flags: &[BoolTarget],
+        xs: &[ExtensionTarget<D>],
+    ) -> ExtensionTarget<D> {
+        debug_assert_eq!(flags.len(), xs.len());
+        let mut acc = self.zero_extension();
+        for (flag, &x) in flags.iter().zip(xs) {
+            acc = self.scalar_mul_add_extension(flag.target, x, acc);
+        }
+        acc
+    }
 }
 
 #[cfg(test)]
@@ -41,6 +78,7 @@ mod tests {
     use anyhow::Result;
 
     use crate::field::types::Sample;
+    use crate::hash::hash_types::HashOut;
     use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_builder::CircuitBuilder;
     use crate::plonk::circuit_data::CircuitConfig;
@@ -77,4 +115,32 @@ mod tests {
 
         verify(proof, &data.verifier_only, &data.common)
     }
+
+    #[test]
+    fn test_select_hash() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let (x, y) = (HashOut::<F>::rand(), HashOut::<F>::rand());
+        let xt = builder.constant_hash(x);
+        let yt = builder.add_virtual_hash();
+        let b = builder.add_virtual_bool_target_safe();
+        let selected = builder.select_hash(b, xt, yt);
+        builder.connect_hashes(selected, xt);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_hash_target(yt, y)?;
+        pw.set_bool_target(b, true)?;
+        data.verify(data.prove(pw)?)?;
+
+        let mut pw = PartialWitness::new();
+        pw.set_hash_target(yt, y)?;
+        pw.set_bool_target(b, false)?;
+        assert!(data.prove(pw).is_err());
+        Ok(())
+    }
 }
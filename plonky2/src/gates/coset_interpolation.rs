@@ -381,6 +381,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for CosetInterpola
         self.end()
     }
 
+    fn num_routed_wires_required(&self) -> usize {
+        self.num_routed_wires()
+    }
+
     fn num_constants(&self) -> usize {
         0
     }
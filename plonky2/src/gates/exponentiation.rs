@@ -191,6 +191,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for Exponentiation
         self.wire_intermediate_value(self.num_power_bits - 1) + 1
     }
 
+    fn num_routed_wires_required(&self) -> usize {
+        self.wire_output() + 1
+    }
+
     fn num_constants(&self) -> usize {
         0
     }
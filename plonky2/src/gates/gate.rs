@@ -7,6 +7,7 @@ use core::ops::Range;
 #[cfg(feature = "std")]
 use std::sync::Arc;
 
+use anyhow::{ensure, Result};
 use hashbrown::HashMap;
 use serde::{Serialize, Serializer};
 
@@ -19,10 +20,11 @@ use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::WitnessGeneratorRef;
 use crate::plonk::circuit_builder::CircuitBuilder;
-use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
 use crate::plonk::vars::{
     EvaluationTargets, EvaluationVars, EvaluationVarsBase, EvaluationVarsBaseBatch,
 };
+use crate::util::log2_ceil;
 use crate::util::serialization::{Buffer, IoResult};
 
 /// A custom gate.
@@ -228,6 +230,16 @@ pub trait Gate<F: RichField + Extendable<D>, const D: usize>: 'static + Send + S
     /// single gate.
     fn num_wires(&self) -> usize;
 
+    /// The number of routed wires used by this gate, i.e. its leading wires which may be connected
+    /// to other gates through copy constraints. Its remaining wires are advice wires.
+    ///
+    /// Defaults to zero, which only checks the gate against the total number of wires, as before
+    /// this method existed, so that gates defined outside this crate keep working. Gates should
+    /// override it to have their routed wires checked against the config.
+    fn num_routed_wires_required(&self) -> usize {
+        0
+    }
+
     /// The number of constants used by this gate.
     fn num_constants(&self) -> usize;
 
@@ -243,6 +255,42 @@ pub trait Gate<F: RichField + Extendable<D>, const D: usize>: 'static + Send + S
             .len()
     }
 
+    /// Checks that this gate fits in the rows of circuits built with `config`.
+    fn check_compatibility(&self, config: &CircuitConfig) -> Result<()> {
+        ensure!(
+            self.num_wires() <= config.num_wires,
+            "{:?} requires {} wires, but our CircuitConfig has only {}",
+            self.id(),
+            self.num_wires(),
+            config.num_wires
+        );
+        ensure!(
+            self.num_routed_wires_required() <= config.num_routed_wires,
+            "{:?} requires {} routed wires, but our CircuitConfig has only {}",
+            self.id(),
+            self.num_routed_wires_required(),
+            config.num_routed_wires
+        );
+        ensure!(
+            self.num_constants() <= config.num_constants,
+            "{:?} requires {} constants, but our CircuitConfig has only {}",
+            self.id(),
+            self.num_constants(),
+            config.num_constants
+        );
+        // Gates of a higher degree than `max_quotient_degree_factor` get additional quotient
+        // chunks, but the quotient must still fit in the LDE.
+        ensure!(
+            log2_ceil(self.degree()) <= config.fri_config.rate_bits,
+            "{:?} has degree {}, which requires `rate_bits` to be at least {}, but our CircuitConfig has {}",
+            self.id(),
+            self.degree(),
+            log2_ceil(self.degree()),
+            config.fri_config.rate_bits
+        );
+        Ok(())
+    }
+
     /// Enables gates to store some "routed constants", if they have both unused constants and
     /// unused routed wires.
     ///
@@ -6,11 +6,12 @@ use alloc::{
     vec::Vec,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use itertools::Itertools;
 use keccak_hash::keccak;
 
 use super::lookup_table::LookupTable;
+use crate::error::Error;
 use crate::field::extension::Extendable;
 use crate::field::packed::PackedField;
 use crate::gates::gate::Gate;
@@ -197,8 +198,12 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for Loo
         let get_wire = |wire: usize| -> F { witness.get_target(Target::wire(self.row, wire)) };
 
         let input_val = get_wire(LookupGate::wire_ith_looking_inp(self.slot_nb));
-        let (input, output) = self.lut[input_val.to_canonical_u64() as usize];
-        if input_val == F::from_canonical_u16(input) {
+        // Tables are usually indexed by their inputs, so try that first. Inputs outside of the
+        // table fall through to the search below and are reported as errors.
+        let indexed = self.lut.get(input_val.to_canonical_u64() as usize);
+        if let Some(&(_, output)) =
+            indexed.filter(|(input, _)| input_val == F::from_canonical_u16(*input))
+        {
             let output_val = F::from_canonical_u16(output);
 
             let out_wire = Target::wire(self.row, LookupGate::wire_ith_looking_out(self.slot_nb));
@@ -215,7 +220,10 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for Loo
                     return Ok(());
                 }
             }
-            Err(anyhow!("Incorrect input value provided"))
+            Err(Error::LookupInputNotInTable {
+                input: input_val.to_canonical_u64(),
+            }
+            .into())
         }
     }
 
@@ -32,16 +32,20 @@ pub mod exponentiation;
 pub mod gate;
 pub mod lookup;
 pub mod lookup_table;
+pub mod monolith;
 pub mod multiplication_extension;
 pub mod noop;
 pub mod packed_util;
 pub mod poseidon;
+pub mod poseidon2;
 pub mod poseidon_mds;
 pub mod public_input;
 pub mod random_access;
 pub mod reducing;
 pub mod reducing_extension;
+pub mod rescue_prime;
 pub(crate) mod selectors;
+pub mod tip5;
 pub mod util;
 
 // Can't use #[cfg(test)] here because it needs to be visible to other crates.
@@ -403,6 +403,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for PoseidonGate<F
         Self::end()
     }
 
+    fn num_routed_wires_required(&self) -> usize {
+        Self::WIRE_SWAP + 1
+    }
+
     fn num_constants(&self) -> usize {
         0
     }
@@ -576,6 +580,27 @@ mod tests {
         assert_eq!(Gate::wire_full_sbox_1(3, 11), 134);
     }
 
+    #[test]
+    fn check_compatibility() {
+        const D: usize = 2;
+        type F = GoldilocksField;
+        let gate = PoseidonGate::<F, D>::new();
+        let config = CircuitConfig::standard_recursion_config();
+        assert_eq!(Gate::<F, D>::num_routed_wires_required(&gate), 25);
+        assert!(gate.check_compatibility(&config).is_ok());
+
+        let too_narrow = CircuitConfig {
+            num_wires: 100,
+            ..config.clone()
+        };
+        assert!(gate.check_compatibility(&too_narrow).is_err());
+        let too_few_routed = CircuitConfig {
+            num_routed_wires: 20,
+            ..config
+        };
+        assert!(gate.check_compatibility(&too_few_routed).is_err());
+    }
+
     #[test]
     fn generated_output() {
         const D: usize = 2;
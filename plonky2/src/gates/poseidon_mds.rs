@@ -207,6 +207,10 @@ impl<F: RichField + Extendable<D> + Poseidon, const D: usize> Gate<F, D> for Pos
         2 * D * SPONGE_WIDTH
     }
 
+    fn num_routed_wires_required(&self) -> usize {
+        self.num_wires()
+    }
+
     fn num_constants(&self) -> usize {
         0
     }
@@ -279,8 +283,10 @@ impl<F: RichField + Extendable<D> + Poseidon, const D: usize> SimpleGenerator<F,
 
 #[cfg(test)]
 mod tests {
+    use crate::gates::gate::Gate;
     use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
     use crate::gates::poseidon_mds::PoseidonMdsGate;
+    use crate::plonk::circuit_data::CircuitConfig;
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
 
     #[test]
@@ -300,4 +306,22 @@ mod tests {
         let gate = PoseidonMdsGate::<F, D>::new();
         test_eval_fns::<F, C, _, D>(gate)
     }
+
+    #[test]
+    fn check_compatibility() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = PoseidonMdsGate::<F, D>::new();
+        // Every wire of the gate is routed.
+        assert_eq!(Gate::<F, D>::num_routed_wires_required(&gate), 48);
+
+        let config = CircuitConfig::standard_recursion_config();
+        assert!(gate.check_compatibility(&config).is_ok());
+        let too_few_routed = CircuitConfig {
+            num_routed_wires: 40,
+            ..config
+        };
+        assert!(gate.check_compatibility(&too_few_routed).is_err());
+    }
 }
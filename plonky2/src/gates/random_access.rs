@@ -7,9 +7,10 @@ use alloc::{
 };
 use core::marker::PhantomData;
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use itertools::Itertools;
 
+use crate::error::Error;
 use crate::field::extension::Extendable;
 use crate::field::packed::PackedField;
 use crate::field::types::Field;
@@ -276,6 +277,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for RandomAccessGa
         self.wire_bit(self.bits - 1, self.num_copies - 1) + 1
     }
 
+    fn num_routed_wires_required(&self) -> usize {
+        self.num_routed_wires()
+    }
+
     fn num_constants(&self) -> usize {
         self.num_extra_constants
     }
@@ -383,14 +388,15 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D>
         let copy = self.copy;
         let vec_size = self.gate.vec_size();
 
-        let access_index_f = get_local_wire(self.gate.wire_access_index(copy));
-        let access_index = access_index_f.to_canonical_u64() as usize;
-        debug_assert!(
-            access_index < vec_size,
-            "Access index {} is larger than the vector size {}",
-            access_index,
-            vec_size
+        let access_index_u64 = get_local_wire(self.gate.wire_access_index(copy)).to_canonical_u64();
+        ensure!(
+            access_index_u64 < vec_size as u64,
+            Error::AccessIndexOutOfRange {
+                index: access_index_u64,
+                vec_size,
+            }
         );
+        let access_index = access_index_u64 as usize;
 
         set_local_wire(
             self.gate.wire_claimed_element(copy),
@@ -171,6 +171,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for ReducingGate<D
         2 * D + self.num_coeffs * (D + 1)
     }
 
+    fn num_routed_wires_required(&self) -> usize {
+        self.start_accs()
+    }
+
     fn num_constants(&self) -> usize {
         0
     }
@@ -171,6 +171,10 @@ impl<F: RichField + Extendable<D>, const D: usize> Gate<F, D> for ReducingExtens
         2 * D + 2 * D * self.num_coeffs
     }
 
+    fn num_routed_wires_required(&self) -> usize {
+        self.start_accs()
+    }
+
     fn num_constants(&self) -> usize {
         0
     }
@@ -0,0 +1,477 @@
+#[cfg(not(feature = "std"))]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::marker::PhantomData;
+
+use anyhow::Result;
+
+use crate::field::extension::Extendable;
+use crate::field::types::Field;
+use crate::gates::gate::Gate;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::hash::poseidon::SPONGE_WIDTH;
+use crate::hash::rescue_prime;
+use crate::hash::rescue_prime::RescuePrime;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// Evaluates a full Rescue-Prime permutation with 12 state elements.
+///
+/// The outputs of the inverse S-boxes are stored as wires, so that each of them is checked by
+/// raising it to the 7th power, and all constraints have degree 7. Like
+/// [`crate::gates::poseidon::PoseidonGate`], it has a flag which can be used to swap the first four
+/// inputs with the next four, for ordering sibling digests.
+#[derive(Debug, Default)]
+pub struct RescuePrimeGate<F: RichField + Extendable<D>, const D: usize>(PhantomData<F>);
+
+impl<F: RichField + Extendable<D> + RescuePrime, const D: usize> RescuePrimeGate<F, D> {
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    /// The wire index for the `i`th input to the permutation.
+    pub(crate) const fn wire_input(i: usize) -> usize {
+        i
+    }
+
+    /// The wire index for the `i`th output to the permutation.
+    pub(crate) const fn wire_output(i: usize) -> usize {
+        SPONGE_WIDTH + i
+    }
+
+    /// If this is set to 1, the first four inputs will be swapped with the next four inputs. This
+    /// is useful for ordering hashes in Merkle proofs. Otherwise, this should be set to 0.
+    pub(crate) const WIRE_SWAP: usize = 2 * SPONGE_WIDTH;
+
+    const START_DELTA: usize = 2 * SPONGE_WIDTH + 1;
+
+    /// A wire which stores `swap * (input[i + 4] - input[i])`; used to compute the swapped inputs.
+    const fn wire_delta(i: usize) -> usize {
+        assert!(i < 4);
+        Self::START_DELTA + i
+    }
+
+    const START_INV_SBOX: usize = Self::START_DELTA + 4;
+
+    /// A wire which stores the output of the `i`-th inverse S-box of the `round`-th round.
+    const fn wire_inv_sbox_output(round: usize, i: usize) -> usize {
+        debug_assert!(round < rescue_prime::N_ROUNDS);
+        debug_assert!(i < SPONGE_WIDTH);
+        Self::START_INV_SBOX + SPONGE_WIDTH * round + i
+    }
+
+    /// End of wire indices, exclusive.
+    const fn end() -> usize {
+        Self::START_INV_SBOX + SPONGE_WIDTH * rescue_prime::N_ROUNDS
+    }
+}
+
+impl<F: RichField + Extendable<D> + RescuePrime, const D: usize> Gate<F, D>
+    for RescuePrimeGate<F, D>
+{
+    fn id(&self) -> String {
+        format!("{self:?}<WIDTH={SPONGE_WIDTH}>")
+    }
+
+    fn serialize(
+        &self,
+        _dst: &mut Vec<u8>,
+        _common_data: &CommonCircuitData<F, D>,
+    ) -> IoResult<()> {
+        Ok(())
+    }
+
+    fn deserialize(_src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        Ok(RescuePrimeGate::new())
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        constraints.push(swap * (swap - F::Extension::ONE));
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            constraints.push(swap * (input_rhs - input_lhs) - delta_i);
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [F::Extension::ZERO; SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = Self::wire_input(i);
+            let input_rhs = Self::wire_input(i + 4);
+            state[i] = vars.local_wires[input_lhs] + delta_i;
+            state[i + 4] = vars.local_wires[input_rhs] - delta_i;
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        for r in 0..rescue_prime::N_ROUNDS {
+            for x in state.iter_mut() {
+                *x = <F as RescuePrime>::sbox_monomial(*x);
+            }
+            <F as RescuePrime>::linear_layer_field(&mut state);
+            <F as RescuePrime>::constant_injection_field(&mut state, &<F as RescuePrime>::ARK1[r]);
+
+            // The inverse S-box maps `x` to the `y` such that `y^7 = x`.
+            for i in 0..SPONGE_WIDTH {
+                let sbox_out = vars.local_wires[Self::wire_inv_sbox_output(r, i)];
+                constraints.push(<F as RescuePrime>::sbox_monomial(sbox_out) - state[i]);
+                state[i] = sbox_out;
+            }
+            <F as RescuePrime>::linear_layer_field(&mut state);
+            <F as RescuePrime>::constant_injection_field(&mut state, &<F as RescuePrime>::ARK2[r]);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            constraints.push(state[i] - vars.local_wires[Self::wire_output(i)]);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        vars: EvaluationVarsBase<F>,
+        mut yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        yield_constr.one(swap * swap.sub_one());
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            yield_constr.one(swap * (input_rhs - input_lhs) - delta_i);
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [F::ZERO; SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = Self::wire_input(i);
+            let input_rhs = Self::wire_input(i + 4);
+            state[i] = vars.local_wires[input_lhs] + delta_i;
+            state[i + 4] = vars.local_wires[input_rhs] - delta_i;
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        for r in 0..rescue_prime::N_ROUNDS {
+            for x in state.iter_mut() {
+                *x = <F as RescuePrime>::sbox_monomial(*x);
+            }
+            <F as RescuePrime>::linear_layer(&mut state);
+            <F as RescuePrime>::constant_injection_field(&mut state, &<F as RescuePrime>::ARK1[r]);
+
+            for i in 0..SPONGE_WIDTH {
+                let sbox_out = vars.local_wires[Self::wire_inv_sbox_output(r, i)];
+                yield_constr.one(<F as RescuePrime>::sbox_monomial(sbox_out) - state[i]);
+                state[i] = sbox_out;
+            }
+            <F as RescuePrime>::linear_layer(&mut state);
+            <F as RescuePrime>::constant_injection_field(&mut state, &<F as RescuePrime>::ARK2[r]);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            yield_constr.one(state[i] - vars.local_wires[Self::wire_output(i)]);
+        }
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        constraints.push(builder.mul_sub_extension(swap, swap, swap));
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let diff = builder.sub_extension(input_rhs, input_lhs);
+            constraints.push(builder.mul_sub_extension(swap, diff, delta_i));
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [builder.zero_extension(); SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            state[i] = builder.add_extension(input_lhs, delta_i);
+            state[i + 4] = builder.sub_extension(input_rhs, delta_i);
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        for r in 0..rescue_prime::N_ROUNDS {
+            for x in state.iter_mut() {
+                *x = <F as RescuePrime>::sbox_monomial_circuit(builder, *x);
+            }
+            <F as RescuePrime>::linear_layer_circuit(builder, &mut state);
+            <F as RescuePrime>::constant_injection_circuit(
+                builder,
+                &mut state,
+                &<F as RescuePrime>::ARK1[r],
+            );
+
+            for i in 0..SPONGE_WIDTH {
+                let sbox_out = vars.local_wires[Self::wire_inv_sbox_output(r, i)];
+                let sbox_in = <F as RescuePrime>::sbox_monomial_circuit(builder, sbox_out);
+                constraints.push(builder.sub_extension(sbox_in, state[i]));
+                state[i] = sbox_out;
+            }
+            <F as RescuePrime>::linear_layer_circuit(builder, &mut state);
+            <F as RescuePrime>::constant_injection_circuit(
+                builder,
+                &mut state,
+                &<F as RescuePrime>::ARK2[r],
+            );
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            constraints
+                .push(builder.sub_extension(state[i], vars.local_wires[Self::wire_output(i)]));
+        }
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, _local_constants: &[F]) -> Vec<WitnessGeneratorRef<F, D>> {
+        let gen = RescuePrimeGenerator::<F, D> {
+            row,
+            _phantom: PhantomData,
+        };
+        vec![WitnessGeneratorRef::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        Self::end()
+    }
+
+    fn num_routed_wires_required(&self) -> usize {
+        Self::WIRE_SWAP + 1
+    }
+
+    fn num_constants(&self) -> usize {
+        0
+    }
+
+    fn degree(&self) -> usize {
+        7
+    }
+
+    fn num_constraints(&self) -> usize {
+        SPONGE_WIDTH * rescue_prime::N_ROUNDS + SPONGE_WIDTH + 1 + 4
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RescuePrimeGenerator<F: RichField + Extendable<D>, const D: usize> {
+    row: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D> + RescuePrime, const D: usize> SimpleGenerator<F, D>
+    for RescuePrimeGenerator<F, D>
+{
+    fn id(&self) -> String {
+        "RescuePrimeGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        (0..SPONGE_WIDTH)
+            .map(|i| RescuePrimeGate::<F, D>::wire_input(i))
+            .chain(Some(RescuePrimeGate::<F, D>::WIRE_SWAP))
+            .map(|column| Target::wire(self.row, column))
+            .collect()
+    }
+
+    fn run_once(
+        &self,
+        witness: &PartitionWitness<F>,
+        out_buffer: &mut GeneratedValues<F>,
+    ) -> Result<()> {
+        let local_wire = |column| Wire {
+            row: self.row,
+            column,
+        };
+
+        let mut state = (0..SPONGE_WIDTH)
+            .map(|i| witness.get_wire(local_wire(RescuePrimeGate::<F, D>::wire_input(i))))
+            .collect::<Vec<_>>();
+
+        let swap_value = witness.get_wire(local_wire(RescuePrimeGate::<F, D>::WIRE_SWAP));
+        debug_assert!(swap_value == F::ZERO || swap_value == F::ONE);
+
+        for i in 0..4 {
+            let delta_i = swap_value * (state[i + 4] - state[i]);
+            out_buffer.set_wire(local_wire(RescuePrimeGate::<F, D>::wire_delta(i)), delta_i)?;
+        }
+
+        if swap_value == F::ONE {
+            for i in 0..4 {
+                state.swap(i, 4 + i);
+            }
+        }
+
+        let mut state: [F; SPONGE_WIDTH] = state.try_into().unwrap();
+        for r in 0..rescue_prime::N_ROUNDS {
+            for x in state.iter_mut() {
+                *x = <F as RescuePrime>::sbox_monomial(*x);
+            }
+            <F as RescuePrime>::linear_layer(&mut state);
+            <F as RescuePrime>::constant_injection_field(&mut state, &<F as RescuePrime>::ARK1[r]);
+
+            for i in 0..SPONGE_WIDTH {
+                state[i] = <F as RescuePrime>::inverse_sbox_monomial(state[i]);
+                out_buffer.set_wire(
+                    local_wire(RescuePrimeGate::<F, D>::wire_inv_sbox_output(r, i)),
+                    state[i],
+                )?;
+            }
+            <F as RescuePrime>::linear_layer(&mut state);
+            <F as RescuePrime>::constant_injection_field(&mut state, &<F as RescuePrime>::ARK2[r]);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            out_buffer.set_wire(
+                local_wire(RescuePrimeGate::<F, D>::wire_output(i)),
+                state[i],
+            )?
+        }
+
+        Ok(())
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.row)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        Ok(Self {
+            row,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::iop::generator::generate_partial_witness;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, RescuePrimeGoldilocksConfig};
+
+    #[test]
+    fn generated_output() {
+        const D: usize = 2;
+        type C = RescuePrimeGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::new(config);
+        type Gate = RescuePrimeGate<F, D>;
+        let gate = Gate::new();
+        let row = builder.add_gate(gate, vec![]);
+        let circuit = builder.build_prover::<C>();
+
+        let permutation_inputs = (0..SPONGE_WIDTH)
+            .map(F::from_canonical_usize)
+            .collect::<Vec<_>>();
+
+        for swap in [F::ZERO, F::ONE] {
+            let mut inputs = PartialWitness::new();
+            inputs
+                .set_wire(
+                    Wire {
+                        row,
+                        column: Gate::WIRE_SWAP,
+                    },
+                    swap,
+                )
+                .unwrap();
+            for i in 0..SPONGE_WIDTH {
+                inputs
+                    .set_wire(
+                        Wire {
+                            row,
+                            column: Gate::wire_input(i),
+                        },
+                        permutation_inputs[i],
+                    )
+                    .unwrap();
+            }
+
+            let witness =
+                generate_partial_witness(inputs, &circuit.prover_only, &circuit.common).unwrap();
+
+            let mut expected_inputs: [F; SPONGE_WIDTH] =
+                permutation_inputs.clone().try_into().unwrap();
+            if swap == F::ONE {
+                for i in 0..4 {
+                    expected_inputs.swap(i, 4 + i);
+                }
+            }
+            let expected_outputs = F::rescue_prime(expected_inputs);
+            for i in 0..SPONGE_WIDTH {
+                let out = witness.get_wire(Wire {
+                    row: 0,
+                    column: Gate::wire_output(i),
+                });
+                assert_eq!(out, expected_outputs[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn low_degree() {
+        type F = GoldilocksField;
+        let gate = RescuePrimeGate::<F, 4>::new();
+        test_low_degree(gate)
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = RescuePrimeGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = RescuePrimeGate::<F, 2>::new();
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+}
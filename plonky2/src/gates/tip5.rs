@@ -0,0 +1,617 @@
+#[cfg(not(feature = "std"))]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::marker::PhantomData;
+
+use anyhow::Result;
+
+use crate::field::extension::Extendable;
+use crate::field::types::Field;
+use crate::gates::gate::Gate;
+use crate::gates::util::StridedConstraintConsumer;
+use crate::hash::hash_types::RichField;
+use crate::hash::tip5::{lookup_u16, Tip5, N_ROUNDS, N_SPLIT_AND_LOOKUP, SPONGE_WIDTH};
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::generator::{GeneratedValues, SimpleGenerator, WitnessGeneratorRef};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartitionWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::vars::{EvaluationTargets, EvaluationVars, EvaluationVarsBase};
+use crate::util::serialization::{Buffer, IoResult, Read, Write};
+
+/// Evaluates one round of the Tip5 permutation with 16 state elements.
+///
+/// The lookup table of the S-box layer is applied through lookups: the Montgomery forms of the
+/// first `N_SPLIT_AND_LOOKUP` elements are decomposed into 16-bit limbs, which must be looked up in
+/// the table built by [`Tip5Gate::add_lookup_table`] to get the limbs of their image. The
+/// decomposition is checked to be canonical.
+///
+/// The gate's only constant is the index of the round. The round constants are given by the
+/// polynomials interpolating them at `0, ..., N_ROUNDS - 1`, evaluated at this index, so that all
+/// rounds use the same gate. Like [`crate::gates::poseidon::PoseidonGate`], it also has a flag
+/// which can be used to swap the first four inputs with the next four, for ordering sibling
+/// digests.
+#[derive(Debug, Default)]
+pub struct Tip5Gate<F: RichField + Extendable<D>, const D: usize>(PhantomData<F>);
+
+impl<F: RichField + Extendable<D> + Tip5, const D: usize> Tip5Gate<F, D> {
+    /// The number of bits of each limb going through the lookup table.
+    pub(crate) const LIMB_BITS: usize = 16;
+
+    /// The number of limbs of each element going through the lookup table.
+    pub(crate) const NUM_LIMBS: usize = 64 / Self::LIMB_BITS;
+
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    /// Adds the lookup table over 16-bit limbs, if needed, and returns its index.
+    pub fn add_lookup_table(builder: &mut CircuitBuilder<F, D>) -> usize {
+        let limbs = (0..=u16::MAX).collect::<Vec<_>>();
+        builder.add_lookup_table_from_fn(lookup_u16, &limbs)
+    }
+
+    /// The wire index for the `i`th input to the round.
+    pub(crate) const fn wire_input(i: usize) -> usize {
+        i
+    }
+
+    /// The wire index for the `i`th output to the round.
+    pub(crate) const fn wire_output(i: usize) -> usize {
+        SPONGE_WIDTH + i
+    }
+
+    /// If this is set to 1, the first four inputs will be swapped with the next four inputs. This
+    /// is useful for ordering hashes in Merkle proofs. Otherwise, this should be set to 0.
+    pub(crate) const WIRE_SWAP: usize = 2 * SPONGE_WIDTH;
+
+    const START_LIMBS_INPUT: usize = 2 * SPONGE_WIDTH + 1;
+
+    /// The wire index for the `j`th limb, in little-endian order, of the Montgomery form of the
+    /// `i`th input to the lookup table.
+    pub(crate) const fn wire_limb_input(i: usize, j: usize) -> usize {
+        debug_assert!(i < N_SPLIT_AND_LOOKUP);
+        debug_assert!(j < Self::NUM_LIMBS);
+        Self::START_LIMBS_INPUT + Self::NUM_LIMBS * i + j
+    }
+
+    const START_LIMBS_OUTPUT: usize =
+        Self::START_LIMBS_INPUT + Self::NUM_LIMBS * N_SPLIT_AND_LOOKUP;
+
+    /// The wire index for the `j`th limb, in little-endian order, of the Montgomery form of the
+    /// `i`th output of the lookup table.
+    pub(crate) const fn wire_limb_output(i: usize, j: usize) -> usize {
+        debug_assert!(i < N_SPLIT_AND_LOOKUP);
+        debug_assert!(j < Self::NUM_LIMBS);
+        Self::START_LIMBS_OUTPUT + Self::NUM_LIMBS * i + j
+    }
+
+    const START_DELTA: usize = Self::START_LIMBS_OUTPUT + Self::NUM_LIMBS * N_SPLIT_AND_LOOKUP;
+
+    /// A wire which stores `swap * (input[i + 4] - input[i])`; used to compute the swapped inputs.
+    const fn wire_delta(i: usize) -> usize {
+        assert!(i < 4);
+        Self::START_DELTA + i
+    }
+
+    const START_HIGH_INV: usize = Self::START_DELTA + 4;
+
+    /// A wire which stores the inverse of `hi - (2^32 - 1)`, where `hi` is the high half of the
+    /// Montgomery form of the `i`th input to the lookup table, or zero if there is none. It is used
+    /// to check that the limbs of this input are canonical.
+    const fn wire_high_inv(i: usize) -> usize {
+        debug_assert!(i < N_SPLIT_AND_LOOKUP);
+        Self::START_HIGH_INV + i
+    }
+
+    /// End of wire indices, exclusive.
+    const fn end() -> usize {
+        Self::START_HIGH_INV + N_SPLIT_AND_LOOKUP
+    }
+
+    /// Evaluates the polynomials interpolating the round constants at `round`.
+    fn round_constants_field<T: Field>(round: T) -> [T; SPONGE_WIDTH] {
+        let mut result = [T::ZERO; SPONGE_WIDTH];
+        for (j, round_constants) in <F as Tip5>::ROUND_CONSTANTS.iter().enumerate() {
+            let basis = Self::lagrange_basis_numerator(j, |k| round - T::from_canonical_usize(k))
+                * T::from_noncanonical_i64(Self::lagrange_basis_denominator(j)).inverse();
+            for (r, &c) in result.iter_mut().zip(round_constants) {
+                *r += basis * T::from_canonical_u64(c);
+            }
+        }
+        result
+    }
+
+    /// Recursive version of `round_constants_field`.
+    fn round_constants_circuit(
+        builder: &mut CircuitBuilder<F, D>,
+        round: ExtensionTarget<D>,
+    ) -> [ExtensionTarget<D>; SPONGE_WIDTH] {
+        let mut result = [builder.zero_extension(); SPONGE_WIDTH];
+        for (j, round_constants) in <F as Tip5>::ROUND_CONSTANTS.iter().enumerate() {
+            let factors = (0..N_ROUNDS)
+                .filter(|&k| k != j)
+                .map(|k| {
+                    let k = builder.constant_extension(F::Extension::from_canonical_usize(k));
+                    builder.sub_extension(round, k)
+                })
+                .collect::<Vec<_>>();
+            let numerator = builder.mul_many_extension(factors);
+            let denominator_inv =
+                F::from_noncanonical_i64(Self::lagrange_basis_denominator(j)).inverse();
+            for (r, &c) in result.iter_mut().zip(round_constants) {
+                let c = F::from_canonical_u64(c) * denominator_inv;
+                *r = builder.mul_const_add_extension(c, numerator, *r);
+            }
+        }
+        result
+    }
+
+    /// Returns `prod_{k != j} factor(k)`, for `k` in `0..N_ROUNDS`.
+    fn lagrange_basis_numerator<T: Field>(j: usize, factor: impl Fn(usize) -> T) -> T {
+        (0..N_ROUNDS)
+            .filter(|&k| k != j)
+            .map(factor)
+            .fold(T::ONE, |acc, x| acc * x)
+    }
+
+    /// Returns `prod_{k != j} (j - k)`, for `k` in `0..N_ROUNDS`.
+    fn lagrange_basis_denominator(j: usize) -> i64 {
+        (0..N_ROUNDS)
+            .filter(|&k| k != j)
+            .map(|k| j as i64 - k as i64)
+            .product()
+    }
+}
+
+impl<F: RichField + Extendable<D> + Tip5, const D: usize> Gate<F, D> for Tip5Gate<F, D> {
+    fn id(&self) -> String {
+        format!("{self:?}<WIDTH={SPONGE_WIDTH}>")
+    }
+
+    fn serialize(
+        &self,
+        _dst: &mut Vec<u8>,
+        _common_data: &CommonCircuitData<F, D>,
+    ) -> IoResult<()> {
+        Ok(())
+    }
+
+    fn deserialize(_src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        Ok(Tip5Gate::new())
+    }
+
+    fn eval_unfiltered(&self, vars: EvaluationVars<F, D>) -> Vec<F::Extension> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let limb_base = F::Extension::from_canonical_u64(1 << Self::LIMB_BITS);
+        let half_base = F::Extension::from_canonical_u64(1 << 32);
+        let high_max = F::Extension::from_canonical_u32(u32::MAX);
+        let montgomery_r = F::Extension::from_canonical_u64(<F as Tip5>::MONTGOMERY_R);
+        let montgomery_r_inv = F::Extension::from_canonical_u64(<F as Tip5>::MONTGOMERY_R_INV);
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        constraints.push(swap * (swap - F::Extension::ONE));
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            constraints.push(swap * (input_rhs - input_lhs) - delta_i);
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [F::Extension::ZERO; SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = Self::wire_input(i);
+            let input_rhs = Self::wire_input(i + 4);
+            state[i] = vars.local_wires[input_lhs] + delta_i;
+            state[i + 4] = vars.local_wires[input_rhs] - delta_i;
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        // Split and lookup.
+        let mul_add_limb = |hi, lo| hi * limb_base + lo;
+        for i in 0..N_SPLIT_AND_LOOKUP {
+            let limb = |j| vars.local_wires[Self::wire_limb_input(i, j)];
+            let lo = mul_add_limb(limb(1), limb(0));
+            let hi = mul_add_limb(limb(3), limb(2));
+            constraints.push(state[i] * montgomery_r - (hi * half_base + lo));
+            // If the high half is `2^32 - 1`, the low half must be zero.
+            let high_inv = vars.local_wires[Self::wire_high_inv(i)];
+            constraints.push(lo * (F::Extension::ONE - (hi - high_max) * high_inv));
+
+            let looked_up = (0..Self::NUM_LIMBS)
+                .rev()
+                .fold(F::Extension::ZERO, |acc, j| {
+                    mul_add_limb(acc, vars.local_wires[Self::wire_limb_output(i, j)])
+                });
+            state[i] = looked_up * montgomery_r_inv;
+        }
+
+        // Power map.
+        for x in state.iter_mut().skip(N_SPLIT_AND_LOOKUP) {
+            *x = <F as Tip5>::power_map_field(*x);
+        }
+
+        <F as Tip5>::mds_multiply_field(&mut state);
+        let round_constants = Self::round_constants_field(vars.local_constants[0]);
+        for i in 0..SPONGE_WIDTH {
+            let output = vars.local_wires[Self::wire_output(i)];
+            constraints.push(state[i] + round_constants[i] - output);
+        }
+
+        constraints
+    }
+
+    fn eval_unfiltered_base_one(
+        &self,
+        vars: EvaluationVarsBase<F>,
+        mut yield_constr: StridedConstraintConsumer<F>,
+    ) {
+        let limb_base = F::from_canonical_u64(1 << Self::LIMB_BITS);
+        let half_base = F::from_canonical_u64(1 << 32);
+        let high_max = F::from_canonical_u32(u32::MAX);
+        let montgomery_r = F::from_canonical_u64(<F as Tip5>::MONTGOMERY_R);
+        let montgomery_r_inv = F::from_canonical_u64(<F as Tip5>::MONTGOMERY_R_INV);
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        yield_constr.one(swap * swap.sub_one());
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            yield_constr.one(swap * (input_rhs - input_lhs) - delta_i);
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [F::ZERO; SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = Self::wire_input(i);
+            let input_rhs = Self::wire_input(i + 4);
+            state[i] = vars.local_wires[input_lhs] + delta_i;
+            state[i + 4] = vars.local_wires[input_rhs] - delta_i;
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        // Split and lookup.
+        let mul_add_limb = |hi, lo| hi * limb_base + lo;
+        for i in 0..N_SPLIT_AND_LOOKUP {
+            let limb = |j| vars.local_wires[Self::wire_limb_input(i, j)];
+            let lo = mul_add_limb(limb(1), limb(0));
+            let hi = mul_add_limb(limb(3), limb(2));
+            yield_constr.one(state[i] * montgomery_r - (hi * half_base + lo));
+            // If the high half is `2^32 - 1`, the low half must be zero.
+            let high_inv = vars.local_wires[Self::wire_high_inv(i)];
+            yield_constr.one(lo * (F::ONE - (hi - high_max) * high_inv));
+
+            let looked_up = (0..Self::NUM_LIMBS).rev().fold(F::ZERO, |acc, j| {
+                mul_add_limb(acc, vars.local_wires[Self::wire_limb_output(i, j)])
+            });
+            state[i] = looked_up * montgomery_r_inv;
+        }
+
+        // Power map.
+        for x in state.iter_mut().skip(N_SPLIT_AND_LOOKUP) {
+            *x = <F as Tip5>::power_map_field(*x);
+        }
+
+        <F as Tip5>::mds_multiply(&mut state);
+        let round_constants = Self::round_constants_field(vars.local_constants[0]);
+        for i in 0..SPONGE_WIDTH {
+            let output = vars.local_wires[Self::wire_output(i)];
+            yield_constr.one(state[i] + round_constants[i] - output);
+        }
+    }
+
+    fn eval_unfiltered_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: EvaluationTargets<D>,
+    ) -> Vec<ExtensionTarget<D>> {
+        let mut constraints = Vec::with_capacity(self.num_constraints());
+        let limb_base = F::from_canonical_u64(1 << Self::LIMB_BITS);
+        let half_base = F::from_canonical_u64(1 << 32);
+        let montgomery_r = F::from_canonical_u64(<F as Tip5>::MONTGOMERY_R);
+        let montgomery_r_inv = F::from_canonical_u64(<F as Tip5>::MONTGOMERY_R_INV);
+        let one = builder.one_extension();
+        let high_max = builder.constant_extension(F::Extension::from_canonical_u32(u32::MAX));
+
+        // Assert that `swap` is binary.
+        let swap = vars.local_wires[Self::WIRE_SWAP];
+        constraints.push(builder.mul_sub_extension(swap, swap, swap));
+
+        // Assert that each delta wire is set properly: `delta_i = swap * (rhs - lhs)`.
+        for i in 0..4 {
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let diff = builder.sub_extension(input_rhs, input_lhs);
+            constraints.push(builder.mul_sub_extension(swap, diff, delta_i));
+        }
+
+        // Compute the possibly-swapped input layer.
+        let mut state = [builder.zero_extension(); SPONGE_WIDTH];
+        for i in 0..4 {
+            let delta_i = vars.local_wires[Self::wire_delta(i)];
+            let input_lhs = vars.local_wires[Self::wire_input(i)];
+            let input_rhs = vars.local_wires[Self::wire_input(i + 4)];
+            state[i] = builder.add_extension(input_lhs, delta_i);
+            state[i + 4] = builder.sub_extension(input_rhs, delta_i);
+        }
+        for i in 8..SPONGE_WIDTH {
+            state[i] = vars.local_wires[Self::wire_input(i)];
+        }
+
+        // Split and lookup.
+        for i in 0..N_SPLIT_AND_LOOKUP {
+            let limb = |j| vars.local_wires[Self::wire_limb_input(i, j)];
+            let lo = builder.mul_const_add_extension(limb_base, limb(1), limb(0));
+            let hi = builder.mul_const_add_extension(limb_base, limb(3), limb(2));
+            let sum = builder.mul_const_add_extension(half_base, hi, lo);
+            let montgomery = builder.mul_const_extension(montgomery_r, state[i]);
+            constraints.push(builder.sub_extension(montgomery, sum));
+            // If the high half is `2^32 - 1`, the low half must be zero.
+            let high_inv = vars.local_wires[Self::wire_high_inv(i)];
+            let high_diff = builder.sub_extension(hi, high_max);
+            let is_not_max = builder.mul_extension(high_diff, high_inv);
+            let is_max = builder.sub_extension(one, is_not_max);
+            constraints.push(builder.mul_extension(lo, is_max));
+
+            let mut looked_up = builder.zero_extension();
+            for j in (0..Self::NUM_LIMBS).rev() {
+                let limb = vars.local_wires[Self::wire_limb_output(i, j)];
+                looked_up = builder.mul_const_add_extension(limb_base, looked_up, limb);
+            }
+            state[i] = builder.mul_const_extension(montgomery_r_inv, looked_up);
+        }
+
+        // Power map.
+        for x in state.iter_mut().skip(N_SPLIT_AND_LOOKUP) {
+            *x = <F as Tip5>::power_map_circuit(builder, *x);
+        }
+
+        <F as Tip5>::mds_multiply_circuit(builder, &mut state);
+        let round_constants = Self::round_constants_circuit(builder, vars.local_constants[0]);
+        for i in 0..SPONGE_WIDTH {
+            let output = vars.local_wires[Self::wire_output(i)];
+            let diff = builder.sub_extension(state[i], output);
+            constraints.push(builder.add_extension(diff, round_constants[i]));
+        }
+
+        constraints
+    }
+
+    fn generators(&self, row: usize, local_constants: &[F]) -> Vec<WitnessGeneratorRef<F, D>> {
+        let gen = Tip5Generator::<F, D> {
+            row,
+            round: local_constants[0].to_canonical_u64() as usize,
+            _phantom: PhantomData,
+        };
+        vec![WitnessGeneratorRef::new(gen.adapter())]
+    }
+
+    fn num_wires(&self) -> usize {
+        Self::end()
+    }
+
+    fn num_routed_wires_required(&self) -> usize {
+        Self::START_DELTA
+    }
+
+    fn num_constants(&self) -> usize {
+        1
+    }
+
+    fn degree(&self) -> usize {
+        7
+    }
+
+    fn num_constraints(&self) -> usize {
+        1 + 4 + 2 * N_SPLIT_AND_LOOKUP + SPONGE_WIDTH
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Tip5Generator<F: RichField + Extendable<D>, const D: usize> {
+    row: usize,
+    round: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: RichField + Extendable<D> + Tip5, const D: usize> SimpleGenerator<F, D>
+    for Tip5Generator<F, D>
+{
+    fn id(&self) -> String {
+        "Tip5Generator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        (0..SPONGE_WIDTH)
+            .map(|i| Tip5Gate::<F, D>::wire_input(i))
+            .chain(Some(Tip5Gate::<F, D>::WIRE_SWAP))
+            .map(|column| Target::wire(self.row, column))
+            .collect()
+    }
+
+    fn run_once(
+        &self,
+        witness: &PartitionWitness<F>,
+        out_buffer: &mut GeneratedValues<F>,
+    ) -> Result<()> {
+        let local_wire = |column| Wire {
+            row: self.row,
+            column,
+        };
+
+        let mut state = (0..SPONGE_WIDTH)
+            .map(|i| witness.get_wire(local_wire(Tip5Gate::<F, D>::wire_input(i))))
+            .collect::<Vec<_>>();
+
+        let swap_value = witness.get_wire(local_wire(Tip5Gate::<F, D>::WIRE_SWAP));
+        debug_assert!(swap_value == F::ZERO || swap_value == F::ONE);
+
+        for i in 0..4 {
+            let delta_i = swap_value * (state[i + 4] - state[i]);
+            out_buffer.set_wire(local_wire(Tip5Gate::<F, D>::wire_delta(i)), delta_i)?;
+        }
+
+        if swap_value == F::ONE {
+            for i in 0..4 {
+                state.swap(i, 4 + i);
+            }
+        }
+
+        let mut state: [F; SPONGE_WIDTH] = state.try_into().unwrap();
+        let montgomery_r = F::from_canonical_u64(<F as Tip5>::MONTGOMERY_R);
+        let limb_mask = (1 << Tip5Gate::<F, D>::LIMB_BITS) - 1;
+        for i in 0..N_SPLIT_AND_LOOKUP {
+            let x = (state[i] * montgomery_r).to_canonical_u64();
+            for j in 0..Tip5Gate::<F, D>::NUM_LIMBS {
+                let limb = (x >> (Tip5Gate::<F, D>::LIMB_BITS * j)) & limb_mask;
+                out_buffer.set_wire(
+                    local_wire(Tip5Gate::<F, D>::wire_limb_input(i, j)),
+                    F::from_canonical_u64(limb),
+                )?;
+                out_buffer.set_wire(
+                    local_wire(Tip5Gate::<F, D>::wire_limb_output(i, j)),
+                    F::from_canonical_u16(lookup_u16(limb as u16)),
+                )?;
+            }
+            let high_diff = F::from_canonical_u64(x >> 32) - F::from_canonical_u32(u32::MAX);
+            out_buffer.set_wire(
+                local_wire(Tip5Gate::<F, D>::wire_high_inv(i)),
+                high_diff.try_inverse().unwrap_or(F::ZERO),
+            )?;
+            state[i] = <F as Tip5>::split_and_lookup(state[i]);
+        }
+
+        for x in state.iter_mut().skip(N_SPLIT_AND_LOOKUP) {
+            *x = <F as Tip5>::power_map_field(*x);
+        }
+        <F as Tip5>::mds_multiply(&mut state);
+        for (x, &c) in state
+            .iter_mut()
+            .zip(&<F as Tip5>::ROUND_CONSTANTS[self.round])
+        {
+            *x += F::from_canonical_u64(c);
+        }
+
+        for i in 0..SPONGE_WIDTH {
+            out_buffer.set_wire(local_wire(Tip5Gate::<F, D>::wire_output(i)), state[i])?
+        }
+
+        Ok(())
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_usize(self.row)?;
+        dst.write_usize(self.round)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let row = src.read_usize()?;
+        let round = src.read_usize()?;
+        Ok(Self {
+            row,
+            round,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2_field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+    use crate::gates::gate_testing::{test_eval_fns, test_low_degree};
+    use crate::hash::hashing::PlonkyPermutation;
+    use crate::hash::tip5::Tip5Hash;
+    use crate::iop::witness::PartialWitness;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{AlgebraicHasher, GenericConfig, Tip5GoldilocksConfig};
+
+    #[test]
+    fn permutation() -> Result<()> {
+        const D: usize = 2;
+        type C = Tip5GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type Perm = <Tip5Hash as AlgebraicHasher<F>>::AlgebraicPermutation;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let inputs = builder.add_virtual_target_arr::<SPONGE_WIDTH>();
+        let swap = builder.add_virtual_bool_target_safe();
+        let outputs = <Tip5Hash as AlgebraicHasher<F>>::permute_swapped(
+            Perm::new(inputs),
+            swap,
+            &mut builder,
+        );
+        builder.register_public_inputs(outputs.as_ref());
+        let data = builder.build::<C>();
+
+        let input_values = core::array::from_fn(|i| F::from_canonical_usize(i * 0x1234567));
+        for swap_value in [false, true] {
+            let mut pw = PartialWitness::new();
+            pw.set_target_arr(&inputs, &input_values)?;
+            pw.set_bool_target(swap, swap_value)?;
+            let proof = data.prove(pw)?;
+
+            let mut expected_inputs = input_values;
+            if swap_value {
+                for i in 0..4 {
+                    expected_inputs.swap(i, i + 4);
+                }
+            }
+            assert_eq!(proof.public_inputs, F::tip5(expected_inputs));
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn round_constants_interpolation() {
+        type F = GoldilocksField;
+        for round in 0..N_ROUNDS {
+            let round_constants =
+                Tip5Gate::<F, 2>::round_constants_field(F::from_canonical_usize(round));
+            assert_eq!(
+                round_constants,
+                <F as Tip5>::ROUND_CONSTANTS[round].map(F::from_canonical_u64)
+            );
+        }
+    }
+
+    #[test]
+    fn low_degree() {
+        type F = GoldilocksField;
+        let gate = Tip5Gate::<F, 4>::new();
+        test_low_degree(gate)
+    }
+
+    #[test]
+    fn eval_fns() -> Result<()> {
+        const D: usize = 2;
+        type C = Tip5GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let gate = Tip5Gate::<F, 2>::new();
+        test_eval_fns::<F, C, _, D>(gate)
+    }
+}
@@ -0,0 +1,120 @@
+//! Blake3-based hashing, for Merkle trees and challengers of proofs which are only verified
+//! natively. Blake3 is much faster than Poseidon outside of a circuit, but very expensive inside
+//! one, so it should not be used for proofs which are meant to be verified recursively.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use crate::hash::hash_types::{BytesHash, RichField};
+use crate::hash::hashing::PlonkyPermutation;
+use crate::plonk::config::Hasher;
+use crate::util::serialization::Write;
+
+pub const SPONGE_RATE: usize = 8;
+pub const SPONGE_CAPACITY: usize = 4;
+pub const SPONGE_WIDTH: usize = SPONGE_RATE + SPONGE_CAPACITY;
+
+/// Blake3 pseudo-permutation (not necessarily one-to-one) used in the challenger.
+/// A state `input: [F; 12]` is sent to the field elements parsed from the extendable output of
+/// `H(input)`, where `H` is the Blake3 hash.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Blake3Permutation<F: RichField> {
+    state: [F; SPONGE_WIDTH],
+}
+
+impl<F: RichField> Eq for Blake3Permutation<F> {}
+
+impl<F: RichField> AsRef<[F]> for Blake3Permutation<F> {
+    fn as_ref(&self) -> &[F] {
+        &self.state
+    }
+}
+
+impl<F: RichField> AsMut<[F]> for Blake3Permutation<F> {
+    fn as_mut(&mut self) -> &mut [F] {
+        &mut self.state
+    }
+}
+
+impl<F: RichField> PlonkyPermutation<F> for Blake3Permutation<F> {
+    const RATE: usize = SPONGE_RATE;
+    const WIDTH: usize = SPONGE_WIDTH;
+
+    fn permute(&mut self) {
+        let mut hasher = blake3::Hasher::new();
+        for x in &self.state {
+            hasher.update(&x.to_canonical_u64().to_le_bytes());
+        }
+        let mut output = hasher.finalize_xof();
+
+        // Parse field elements from the output stream, using rejection sampling such that words
+        // that don't fit in F are ignored.
+        let mut word = [0u8; size_of::<u64>()];
+        let mut i = 0;
+        while i < SPONGE_WIDTH {
+            output.fill(&mut word);
+            let word = u64::from_le_bytes(word);
+            if word < F::ORDER {
+                self.state[i] = F::from_canonical_u64(word);
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Blake3 hash function, truncated to `N` bytes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Blake3Hash<const N: usize>;
+impl<F: RichField, const N: usize> Hasher<F> for Blake3Hash<N> {
+    const HASH_SIZE: usize = N;
+    type Hash = BytesHash<N>;
+    type Permutation = Blake3Permutation<F>;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        let mut buffer = Vec::with_capacity(input.len());
+        buffer.write_field_vec(input).unwrap();
+        let mut arr = [0; N];
+        arr.copy_from_slice(&blake3::hash(&buffer).as_bytes()[..N]);
+        BytesHash(arr)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&left.0);
+        hasher.update(&right.0);
+        let mut arr = [0; N];
+        arr.copy_from_slice(&hasher.finalize().as_bytes()[..N]);
+        BytesHash(arr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{Blake3GoldilocksConfig, GenericConfig};
+
+    #[test]
+    fn test_blake3_proof() -> Result<()> {
+        const D: usize = 2;
+        type C = Blake3GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 5);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO)?;
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs, [F::from_canonical_u64(32)]);
+        data.verify(proof)
+    }
+}
@@ -1,7 +1,7 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-use anyhow::ensure;
+use anyhow::{ensure, Result};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::field::goldilocks_field::GoldilocksField;
@@ -44,6 +44,29 @@ impl<F: Field> HashOut<F> {
     }
 }
 
+impl<F: PrimeField64> HashOut<F> {
+    /// Returns the concatenated canonical little-endian encodings of the elements.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        self.elements.iter().flat_map(|x| x.to_bytes_le()).collect()
+    }
+
+    /// Decodes a hash from the encoding produced by [`Self::to_bytes_le`], failing if its length
+    /// is wrong or if any element is not canonical.
+    pub fn from_bytes_le(bytes: &[u8]) -> Result<Self> {
+        ensure!(
+            bytes.len() == NUM_HASH_OUT_ELTS * F::NUM_BYTES,
+            "expected {} bytes, got {}",
+            NUM_HASH_OUT_ELTS * F::NUM_BYTES,
+            bytes.len()
+        );
+        let mut elements = [F::ZERO; NUM_HASH_OUT_ELTS];
+        for (x, chunk) in elements.iter_mut().zip(bytes.chunks_exact(F::NUM_BYTES)) {
+            *x = F::from_bytes_le(chunk)?;
+        }
+        Ok(Self { elements })
+    }
+}
+
 impl<F: Field> From<[F; NUM_HASH_OUT_ELTS]> for HashOut<F> {
     fn from(elements: [F; NUM_HASH_OUT_ELTS]) -> Self {
         Self { elements }
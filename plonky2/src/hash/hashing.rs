@@ -1,6 +1,6 @@
 //! Concrete instantiation of a hash function.
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 use core::fmt::Debug;
 
 use crate::field::extension::Extendable;
@@ -11,6 +11,8 @@ use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::AlgebraicHasher;
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Hashes `inputs`, unless they fit in a single hash, in which case they are padded with zeros
+    /// instead. This matches `Hasher::hash_or_noop`.
     pub fn hash_or_noop<H: AlgebraicHasher<F>>(&mut self, inputs: Vec<Target>) -> HashOutTarget {
         let zero = self.zero();
         if inputs.len() <= NUM_HASH_OUT_ELTS {
@@ -56,11 +58,84 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             state = self.permute::<H>(state);
         }
     }
+
+    /// Hashes the first `len` of `inputs` with the `pad10*1` rule, where `len` is only known to the
+    /// prover. The digest matches [`Hasher::hash_pad`] and [`Sponge`] for the same elements, and
+    /// the circuit is unsatisfiable if `len` exceeds `inputs.len()`.
+    ///
+    /// Every block the longest message could need is permuted, and the state after the last block
+    /// of the actual message is selected.
+    ///
+    /// [`Hasher::hash_pad`]: crate::plonk::config::Hasher::hash_pad
+    /// [`Sponge`]: crate::hash::sponge::Sponge
+    pub fn hash_pad_variable_len<H: AlgebraicHasher<F>>(
+        &mut self,
+        inputs: &[Target],
+        len: Target,
+    ) -> HashOutTarget {
+        let rate = H::AlgebraicPermutation::RATE;
+        let max_len = inputs.len();
+        let zero = self.zero();
+
+        // `is_len[i]` is set iff `len == i`, and exactly one of them must be set.
+        let is_len = (0..=max_len)
+            .map(|i| {
+                let i = self.constant(F::from_canonical_usize(i));
+                self.is_equal(len, i).target
+            })
+            .collect::<Vec<_>>();
+        let num_lens = self.add_many(&is_len);
+        self.assert_one(num_lens);
+
+        // A message of length `i` is padded to `(i + 1) / rate + 1` blocks.
+        let num_blocks = (max_len + 1) / rate + 1;
+        let mut is_last_block = vec![zero; num_blocks];
+        for (i, &is_len_i) in is_len.iter().enumerate() {
+            let block = (i + 1) / rate;
+            is_last_block[block] = self.add(is_last_block[block], is_len_i);
+        }
+
+        // The padded message: the inputs up to `len`, then a one at position `len`, zeros, and a
+        // one at the end of the last block.
+        let mut is_past_end = zero;
+        let padded = (0..num_blocks * rate)
+            .map(|p| {
+                let mut element = zero;
+                if p <= max_len {
+                    is_past_end = self.add(is_past_end, is_len[p]);
+                    element = is_len[p];
+                }
+                if p < max_len {
+                    let x = self.mul(inputs[p], is_past_end);
+                    let x = self.sub(inputs[p], x);
+                    element = self.add(element, x);
+                }
+                if p % rate == rate - 1 {
+                    element = self.add(element, is_last_block[p / rate]);
+                }
+                element
+            })
+            .collect::<Vec<_>>();
+
+        let mut state = H::AlgebraicPermutation::new(core::iter::repeat(zero));
+        let mut outputs = [zero; NUM_HASH_OUT_ELTS];
+        for (block, &is_last) in padded.chunks(rate).zip(&is_last_block) {
+            state.set_from_slice(block, 0);
+            state = self.permute::<H>(state);
+            for (output, &s) in outputs.iter_mut().zip(state.squeeze()) {
+                *output = self.mul_add(is_last, s, *output);
+            }
+        }
+        HashOutTarget::from_vec(outputs.to_vec())
+    }
 }
 
 /// Permutation that can be used in the sponge construction for an algebraic hash.
+///
+/// Only `permute` needs to be implemented: the sponge state is accessed through `AsRef` and
+/// `AsMut`, which must return all `WIDTH` elements, and the other methods are derived from it.
 pub trait PlonkyPermutation<T: Copy + Default>:
-    AsRef<[T]> + Copy + Debug + Default + Eq + Sync + Send
+    AsRef<[T]> + AsMut<[T]> + Copy + Debug + Default + Eq + Sync + Send
 {
     const RATE: usize;
     const WIDTH: usize;
@@ -72,25 +147,41 @@ pub trait PlonkyPermutation<T: Copy + Default>:
     /// different value, instead of your original `iter` pass
     /// `iter.chain(core::iter::repeat(F::from_canonical_u64(12345)))`
     /// or similar.
-    fn new<I: IntoIterator<Item = T>>(iter: I) -> Self;
+    fn new<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut perm = Self::default();
+        perm.set_from_iter(iter, 0);
+        perm
+    }
 
     /// Set idx-th state element to be `elt`. Panics if `idx >= WIDTH`.
-    fn set_elt(&mut self, elt: T, idx: usize);
+    fn set_elt(&mut self, elt: T, idx: usize) {
+        self.as_mut()[idx] = elt;
+    }
 
     /// Set state element `i` to be `elts[i] for i =
     /// start_idx..start_idx + n` where `n = min(elts.len(),
     /// WIDTH-start_idx)`. Panics if `start_idx > WIDTH`.
-    fn set_from_iter<I: IntoIterator<Item = T>>(&mut self, elts: I, start_idx: usize);
+    fn set_from_iter<I: IntoIterator<Item = T>>(&mut self, elts: I, start_idx: usize) {
+        for (s, e) in self.as_mut()[start_idx..].iter_mut().zip(elts) {
+            *s = e;
+        }
+    }
 
     /// Same semantics as for `set_from_iter` but probably faster than
     /// just calling `set_from_iter(elts.iter())`.
-    fn set_from_slice(&mut self, elts: &[T], start_idx: usize);
+    fn set_from_slice(&mut self, elts: &[T], start_idx: usize) {
+        let begin = start_idx;
+        let end = start_idx + elts.len();
+        self.as_mut()[begin..end].copy_from_slice(elts);
+    }
 
     /// Apply permutation to internal state
     fn permute(&mut self);
 
     /// Return a slice of `RATE` elements
-    fn squeeze(&self) -> &[T];
+    fn squeeze(&self) -> &[T] {
+        &self.as_ref()[..Self::RATE]
+    }
 }
 
 /// A one-way compression function which takes two ~256 bit inputs and returns a ~256 bit output.
@@ -141,5 +232,17 @@ pub fn hash_n_to_m_no_pad<F: RichField, P: PlonkyPermutation<F>>(
 }
 
 pub fn hash_n_to_hash_no_pad<F: RichField, P: PlonkyPermutation<F>>(inputs: &[F]) -> HashOut<F> {
-    HashOut::from_vec(hash_n_to_m_no_pad::<F, P>(inputs, NUM_HASH_OUT_ELTS))
+    debug_assert!(P::RATE >= NUM_HASH_OUT_ELTS);
+
+    // Same as `hash_n_to_m_no_pad`, but a single squeeze suffices, so no output buffer is needed.
+    // This is called for every Merkle leaf.
+    let mut perm = P::new(core::iter::repeat(F::ZERO));
+    for input_chunk in inputs.chunks(P::RATE) {
+        perm.set_from_slice(input_chunk, 0);
+        perm.permute();
+    }
+
+    HashOut {
+        elements: perm.squeeze()[..NUM_HASH_OUT_ELTS].try_into().unwrap(),
+    }
 }
@@ -30,36 +30,16 @@ impl<F: RichField> AsRef<[F]> for KeccakPermutation<F> {
     }
 }
 
-// TODO: Several implementations here are copied from
-// PoseidonPermutation; they should be refactored.
+impl<F: RichField> AsMut<[F]> for KeccakPermutation<F> {
+    fn as_mut(&mut self) -> &mut [F] {
+        &mut self.state
+    }
+}
+
 impl<F: RichField> PlonkyPermutation<F> for KeccakPermutation<F> {
     const RATE: usize = SPONGE_RATE;
     const WIDTH: usize = SPONGE_WIDTH;
 
-    fn new<I: IntoIterator<Item = F>>(elts: I) -> Self {
-        let mut perm = Self {
-            state: [F::default(); SPONGE_WIDTH],
-        };
-        perm.set_from_iter(elts, 0);
-        perm
-    }
-
-    fn set_elt(&mut self, elt: F, idx: usize) {
-        self.state[idx] = elt;
-    }
-
-    fn set_from_slice(&mut self, elts: &[F], start_idx: usize) {
-        let begin = start_idx;
-        let end = start_idx + elts.len();
-        self.state[begin..end].copy_from_slice(elts);
-    }
-
-    fn set_from_iter<I: IntoIterator<Item = F>>(&mut self, elts: I, start_idx: usize) {
-        for (s, e) in self.state[start_idx..].iter_mut().zip(elts) {
-            *s = e;
-        }
-    }
-
     fn permute(&mut self) {
         let mut state_bytes = vec![0u8; SPONGE_WIDTH * size_of::<u64>()];
         for i in 0..SPONGE_WIDTH {
@@ -92,10 +72,6 @@ impl<F: RichField> PlonkyPermutation<F> for KeccakPermutation<F> {
             .try_into()
             .unwrap();
     }
-
-    fn squeeze(&self) -> &[F] {
-        &self.state[..Self::RATE]
-    }
 }
 
 /// Keccak-256 hash function.
@@ -124,3 +100,188 @@ impl<F: RichField, const N: usize> Hasher<F> for KeccakHash<N> {
         BytesHash(arr)
     }
 }
+
+/// Canonical encoding of field elements for EVM verifiers: the concatenation of the 8-byte
+/// big-endian representations of their canonical values, as with Solidity's
+/// `abi.encodePacked(uint64, ...)`.
+fn evm_encode<F: RichField>(elements: &[F]) -> Vec<u8> {
+    elements
+        .iter()
+        .flat_map(|x| x.to_canonical_u64().to_be_bytes())
+        .collect()
+}
+
+/// Keccak-256 pseudo-permutation meant to be recomputed by EVM verifiers.
+/// A state `input: [F; 12]` is sent to the state whose `i`th element is
+/// `uint256(keccak256(abi.encodePacked(input, uint8(i)))) % p`, with `input` encoded as in
+/// `abi.encodePacked(uint64, ...)`. Unlike [`KeccakPermutation`], this needs neither rejection
+/// sampling nor little-endian conversions on the EVM.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct EvmKeccakPermutation<F: RichField> {
+    state: [F; SPONGE_WIDTH],
+}
+
+impl<F: RichField> Eq for EvmKeccakPermutation<F> {}
+
+impl<F: RichField> AsRef<[F]> for EvmKeccakPermutation<F> {
+    fn as_ref(&self) -> &[F] {
+        &self.state
+    }
+}
+
+impl<F: RichField> AsMut<[F]> for EvmKeccakPermutation<F> {
+    fn as_mut(&mut self) -> &mut [F] {
+        &mut self.state
+    }
+}
+
+impl<F: RichField> PlonkyPermutation<F> for EvmKeccakPermutation<F> {
+    const RATE: usize = SPONGE_RATE;
+    const WIDTH: usize = SPONGE_WIDTH;
+
+    fn permute(&mut self) {
+        let mut input = evm_encode(&self.state);
+        input.push(0);
+        let two_to_64 = F::from_noncanonical_u128(1 << 64);
+        for i in 0..SPONGE_WIDTH {
+            *input.last_mut().unwrap() = i as u8;
+            // Reduce the big-endian 256-bit output modulo the field order.
+            self.state[i] =
+                keccak(&input)
+                    .0
+                    .chunks_exact(size_of::<u64>())
+                    .fold(F::ZERO, |acc, word| {
+                        acc * two_to_64
+                            + F::from_noncanonical_u64(u64::from_be_bytes(word.try_into().unwrap()))
+                    });
+        }
+    }
+}
+
+/// Keccak-256 hash function over the EVM encoding of field elements, i.e.
+/// `keccak256(abi.encodePacked(uint64, ...))`, so that Merkle proofs can be checked with the EVM's
+/// native hash.
+///
+/// In a Merkle tree, a leaf of at most four elements is not hashed: its digest is its encoding,
+/// right-padded with zeros to 32 bytes. Other leaves are hashed as above, and two siblings are
+/// compressed into `keccak256(abi.encodePacked(left, right))`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EvmKeccakHash;
+impl<F: RichField> Hasher<F> for EvmKeccakHash {
+    const HASH_SIZE: usize = 32;
+    type Hash = BytesHash<32>;
+    type Permutation = EvmKeccakPermutation<F>;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        BytesHash(keccak(evm_encode(input)).0)
+    }
+
+    fn hash_or_noop(inputs: &[F]) -> Self::Hash {
+        if inputs.len() * 8 <= 32 {
+            let mut arr = [0; 32];
+            arr[..inputs.len() * 8].copy_from_slice(&evm_encode(inputs));
+            BytesHash(arr)
+        } else {
+            Self::hash_no_pad(inputs)
+        }
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        let mut v = vec![0; 64];
+        v[..32].copy_from_slice(&left.0);
+        v[32..].copy_from_slice(&right.0);
+        BytesHash(keccak(v).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::Field;
+    use crate::hash::merkle_tree::MerkleTree;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{EvmKeccakGoldilocksConfig, GenericConfig};
+
+    #[test]
+    fn test_evm_keccak_encoding() {
+        type F = GoldilocksField;
+        let inputs = [
+            F::ONE,
+            F::NEG_ONE,
+            F::from_canonical_u64(0x0102030405060708),
+        ];
+        let mut bytes = Vec::new();
+        bytes.extend([0, 0, 0, 0, 0, 0, 0, 1]);
+        bytes.extend(0xFFFF_FFFF_0000_0000u64.to_be_bytes());
+        bytes.extend([1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            <EvmKeccakHash as Hasher<F>>::hash_no_pad(&inputs).0,
+            keccak(&bytes).0
+        );
+
+        let mut perm = EvmKeccakPermutation::new(inputs);
+        perm.permute();
+        let mut padded = bytes;
+        padded.resize(SPONGE_WIDTH * 8, 0);
+        padded.push(1);
+        let expected = keccak(&padded).0.iter().fold(F::ZERO, |acc, &b| {
+            acc * F::from_canonical_u16(256) + F::from_canonical_u8(b)
+        });
+        assert_eq!(perm.as_ref()[1], expected);
+    }
+
+    #[test]
+    fn test_evm_keccak_merkle_tree() {
+        type F = GoldilocksField;
+        type H = EvmKeccakHash;
+
+        // Two leaves which are hashed, and two which are not.
+        let leaves = vec![
+            (1..=5).map(F::from_canonical_u64).collect::<Vec<_>>(),
+            (6..=10).map(F::from_canonical_u64).collect(),
+            vec![F::NEG_ONE],
+            vec![F::ZERO, F::ONE, F::TWO, F::from_canonical_u64(3)],
+        ];
+        let tree = MerkleTree::<F, H>::new(leaves.clone(), 0);
+
+        let leaf_digest = |leaf: &[F]| {
+            let bytes = evm_encode(leaf);
+            if bytes.len() <= 32 {
+                let mut digest = [0; 32];
+                digest[..bytes.len()].copy_from_slice(&bytes);
+                digest
+            } else {
+                keccak(bytes).0
+            }
+        };
+        let node = |left: [u8; 32], right: [u8; 32]| keccak([left, right].concat()).0;
+        let root = node(
+            node(leaf_digest(&leaves[0]), leaf_digest(&leaves[1])),
+            node(leaf_digest(&leaves[2]), leaf_digest(&leaves[3])),
+        );
+        assert_eq!(tree.cap.0, [BytesHash(root)]);
+    }
+
+    #[test]
+    fn test_evm_keccak_proof() -> Result<()> {
+        const D: usize = 2;
+        type C = EvmKeccakGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.mul(x, x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3))?;
+        let proof = data.prove(pw)?;
+        data.verify(proof)
+    }
+}
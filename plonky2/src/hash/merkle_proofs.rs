@@ -2,6 +2,7 @@
 use alloc::{vec, vec::Vec};
 
 use anyhow::{ensure, Result};
+use hashbrown::HashMap;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
@@ -58,7 +59,7 @@ pub fn verify_merkle_proof_to_cap<F: RichField, H: Hasher<F>>(
     proof: &MerkleProof<F, H>,
 ) -> Result<()> {
     verify_batch_merkle_proof_to_cap(
-        &[leaf_data.clone()],
+        core::slice::from_ref(&leaf_data),
         &[proof.siblings.len()],
         leaf_index,
         merkle_cap,
@@ -105,6 +106,69 @@ pub fn verify_batch_merkle_proof_to_cap<F: RichField, H: Hasher<F>>(
     Ok(())
 }
 
+/// Nodes of a single Merkle tree which are known to be consistent with its cap, keyed by their
+/// layer (counted from the leaves) and their index within that layer. Verifying many openings of
+/// the same tree through this cache stops hashing as soon as a path joins an already verified one.
+#[derive(Clone, Debug)]
+pub struct MerkleVerificationCache<F: RichField, H: Hasher<F>> {
+    nodes: HashMap<(usize, usize), H::Hash>,
+}
+
+impl<F: RichField, H: Hasher<F>> Default for MerkleVerificationCache<F, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: RichField, H: Hasher<F>> MerkleVerificationCache<F, H> {
+    pub fn new() -> Self {
+        Self {
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// Same as `verify_merkle_proof_to_cap`, reusing and extending the set of verified nodes.
+    /// All calls on one cache must be for the same tree, i.e. the same `merkle_cap`.
+    pub fn verify_merkle_proof_to_cap(
+        &mut self,
+        leaf_data: &[F],
+        mut leaf_index: usize,
+        merkle_cap: &MerkleCap<F, H>,
+        proof: &MerkleProof<F, H>,
+    ) -> Result<()> {
+        let mut current_digest = H::hash_or_noop(leaf_data);
+        let mut new_nodes = Vec::with_capacity(2 * proof.siblings.len());
+        let mut joined = false;
+        for (layer, &sibling_digest) in proof.siblings.iter().enumerate() {
+            if let Some(known_digest) = self.nodes.get(&(layer, leaf_index)) {
+                ensure!(*known_digest == current_digest, "Invalid Merkle proof.");
+                joined = true;
+                break;
+            }
+            new_nodes.push(((layer, leaf_index), current_digest));
+            new_nodes.push(((layer, leaf_index ^ 1), sibling_digest));
+
+            let bit = leaf_index & 1;
+            leaf_index >>= 1;
+            current_digest = if bit == 1 {
+                H::two_to_one(sibling_digest, current_digest)
+            } else {
+                H::two_to_one(current_digest, sibling_digest)
+            };
+        }
+        if !joined {
+            ensure!(
+                current_digest == merkle_cap.0[leaf_index],
+                "Invalid Merkle proof."
+            );
+        }
+
+        // Every node on the path, and every sibling hashed into it, is now known to be consistent.
+        self.nodes.extend(new_nodes);
+        Ok(())
+    }
+}
+
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// Verifies that the given leaf data is present at the given index in the Merkle tree with the
     /// given root. The index is given by its little-endian bits.
@@ -180,6 +244,60 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
+    /// Same as `verify_merkle_proof_to_cap_with_cap_index`, except that the tree height is only
+    /// known to be one of `proof_lens`, chosen by the flag paired with it. `proof` is padded to the
+    /// largest of these lengths; siblings beyond the selected length are ignored.
+    pub(crate) fn verify_variable_length_merkle_proof_to_cap_with_cap_index<
+        H: AlgebraicHasher<F>,
+    >(
+        &mut self,
+        leaf_data: Vec<Target>,
+        leaf_index_bits: &[BoolTarget],
+        cap_index: Target,
+        merkle_cap: &MerkleCapTarget,
+        proof: &MerkleProofTarget,
+        proof_lens: &[(BoolTarget, usize)],
+    ) {
+        debug_assert!(H::AlgebraicPermutation::RATE >= NUM_HASH_OUT_ELTS);
+        debug_assert!(proof_lens
+            .iter()
+            .all(|&(_, len)| len <= proof.siblings.len()));
+
+        let zero = self.zero();
+        let mut state: HashOutTarget = self.hash_or_noop::<H>(leaf_data);
+        let mut states = vec![state];
+
+        for (&bit, &sibling) in leaf_index_bits.iter().zip(&proof.siblings) {
+            let mut perm_inputs = H::AlgebraicPermutation::default();
+            perm_inputs.set_from_slice(&state.elements, 0);
+            perm_inputs.set_from_slice(&sibling.elements, NUM_HASH_OUT_ELTS);
+            // Ensure the rest of the state, if any, is zero:
+            perm_inputs.set_from_iter(core::iter::repeat(zero), 2 * NUM_HASH_OUT_ELTS);
+            let perm_outs = self.permute_swapped::<H>(perm_inputs, bit);
+            let hash_outs = perm_outs.squeeze()[0..NUM_HASH_OUT_ELTS]
+                .try_into()
+                .unwrap();
+            state = HashOutTarget {
+                elements: hash_outs,
+            };
+            states.push(state);
+        }
+
+        let flags = proof_lens.iter().map(|&(flag, _)| flag).collect::<Vec<_>>();
+        for i in 0..NUM_HASH_OUT_ELTS {
+            let candidates = proof_lens
+                .iter()
+                .map(|&(_, len)| states[len].elements[i])
+                .collect::<Vec<_>>();
+            let root = self.select_one_hot(&flags, &candidates);
+            let result = self.random_access(
+                cap_index,
+                merkle_cap.0.iter().map(|h| h.elements[i]).collect(),
+            );
+            self.connect(result, root);
+        }
+    }
+
     /// Same as `verify_batch_merkle_proof_to_cap`, except with the final "cap index" as separate parameter,
     /// rather than being contained in `leaf_index_bits`.
     pub(crate) fn verify_batch_merkle_proof_to_cap_with_cap_index<H: AlgebraicHasher<F>>(
@@ -236,6 +354,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
     }
 
+    /// Enforces that two [`HashOutTarget`]s are equal, element by element.
     pub fn connect_hashes(&mut self, x: HashOutTarget, y: HashOutTarget) {
         for i in 0..NUM_HASH_OUT_ELTS {
             self.connect(x.elements[i], y.elements[i]);
@@ -315,4 +434,34 @@ mod tests {
 
         verify(proof, &data.verifier_only, &data.common)
     }
+
+    #[test]
+    fn test_merkle_verification_cache() -> Result<()> {
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<2>>::F;
+        type H = <C as GenericConfig<2>>::Hasher;
+
+        let log_n = 8;
+        let n = 1 << log_n;
+        let cap_height = 2;
+        let leaves = random_data::<F>(n, 7);
+        let tree = MerkleTree::<F, H>::new(leaves, cap_height);
+
+        let mut cache = MerkleVerificationCache::<F, H>::new();
+        for _ in 0..2 {
+            for i in 0..n {
+                cache.verify_merkle_proof_to_cap(&tree.leaves[i], i, &tree.cap, &tree.prove(i))?;
+            }
+        }
+
+        // A wrong leaf must be rejected even though its whole path is already cached.
+        let i: usize = OsRng.gen_range(0..n);
+        let mut leaf = tree.leaves[i].clone();
+        leaf[0] += F::ONE;
+        assert!(cache
+            .verify_merkle_proof_to_cap(&leaf, i, &tree.cap, &tree.prove(i))
+            .is_err());
+
+        Ok(())
+    }
 }
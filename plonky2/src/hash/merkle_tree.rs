@@ -1,6 +1,7 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
-use core::mem::MaybeUninit;
+use core::mem::{self, MaybeUninit};
+use core::ops::Range;
 use core::slice;
 
 use plonky2_maybe_rayon::*;
@@ -11,6 +12,13 @@ use crate::hash::merkle_proofs::MerkleProof;
 use crate::plonk::config::{GenericHashOut, Hasher};
 use crate::util::log2_strict;
 
+/// The number of leaves the chunked constructors of [`MerkleTree`] gather before hashing them.
+const LEAF_CHUNK_LEN: usize = 1 << 12;
+
+/// The number of leaves at the bottom of a sub-tree which are hashed together, so that hashers with
+/// a vectorized permutation can hash them in parallel lanes. This is the widest packing we have.
+const LEAF_BATCH_LEN: usize = 8;
+
 /// The Merkle cap of height `h` of a Merkle tree is the `h`-th layer (from the root) of the tree.
 /// It can be used in place of the root to verify Merkle paths, which are `h` elements shorter.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
@@ -88,8 +96,9 @@ pub(crate) fn fill_subtree<F: RichField, H: Hasher<F>>(
     leaves: &[Vec<F>],
 ) -> H::Hash {
     assert_eq!(leaves.len(), digests_buf.len() / 2 + 1);
-    if digests_buf.is_empty() {
-        H::hash_or_noop(&leaves[0])
+    if leaves.len() <= LEAF_BATCH_LEN {
+        let leaf_digests = H::hash_or_noop_many(leaves);
+        fill_subtree_from_block_roots::<F, H>(digests_buf, &leaf_digests)
     } else {
         // Layout is: left recursive output || left child digest
         //             || right child digest || right recursive output.
@@ -124,10 +133,12 @@ pub(crate) fn fill_digests_buf<F: RichField, H: Hasher<F>>(
     if digests_buf.is_empty() {
         debug_assert_eq!(cap_buf.len(), leaves.len());
         cap_buf
-            .par_iter_mut()
-            .zip(leaves)
-            .for_each(|(cap_buf, leaf)| {
-                cap_buf.write(H::hash_or_noop(leaf));
+            .par_chunks_mut(LEAF_BATCH_LEN)
+            .zip(leaves.par_chunks(LEAF_BATCH_LEN))
+            .for_each(|(cap_buf, leaves)| {
+                for (cap_buf, digest) in cap_buf.iter_mut().zip(H::hash_or_noop_many(leaves)) {
+                    cap_buf.write(digest);
+                }
             });
         return;
     }
@@ -148,6 +159,86 @@ pub(crate) fn fill_digests_buf<F: RichField, H: Hasher<F>>(
     );
 }
 
+/// Hashes the leaves with indices in `range`, in parallel batches of at most `LEAF_BATCH_LEN`
+/// leaves, where `hash_batch(batch)` returns the digests of the leaves with indices in `batch`.
+fn hash_leaves<F: RichField, H: Hasher<F>, B>(range: Range<usize>, hash_batch: B) -> Vec<H::Hash>
+where
+    B: Fn(Range<usize>) -> Vec<H::Hash> + Sync,
+{
+    let mut digests = Vec::with_capacity(range.len());
+    capacity_up_to_mut(&mut digests, range.len())
+        .par_chunks_mut(LEAF_BATCH_LEN)
+        .enumerate()
+        .for_each(|(i, digests_buf)| {
+            let start = range.start + i * LEAF_BATCH_LEN;
+            let batch_digests = hash_batch(start..start + digests_buf.len());
+            assert_eq!(batch_digests.len(), digests_buf.len());
+            for (digest_mem, digest) in digests_buf.iter_mut().zip(batch_digests) {
+                digest_mem.write(digest);
+            }
+        });
+    unsafe {
+        // SAFETY: Each batch initialized its part of the spare capacity of `digests`, which has
+        // length `range.len()`.
+        digests.set_len(range.len());
+    }
+    digests
+}
+
+/// Returns the parts of `digests_buf` holding the digests internal to each of the `blocks`, i.e.
+/// aligned groups of `block_len` leaves, within sub-trees of `subtree_leaves_len` leaves each.
+fn block_digests_bufs<T>(
+    mut digests_buf: &mut [MaybeUninit<T>],
+    blocks: Range<usize>,
+    block_len: usize,
+    subtree_leaves_len: usize,
+) -> Vec<&mut [MaybeUninit<T>]> {
+    let blocks_per_subtree = subtree_leaves_len / block_len;
+    let subtree_digests_len = 2 * (subtree_leaves_len - 1);
+    let block_digests_len = 2 * (block_len - 1);
+
+    let mut consumed = 0;
+    let mut bufs = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        // Within a sub-tree, the `i`th block's digests are preceded by those of the `i` previous
+        // blocks and by one pair of siblings per block, so they start at `2 * block_len * i`.
+        let start = (block / blocks_per_subtree) * subtree_digests_len
+            + (block % blocks_per_subtree) * 2 * block_len;
+        let rest = mem::take(&mut digests_buf);
+        let (block_buf, rest) = rest[start - consumed..].split_at_mut(block_digests_len);
+        bufs.push(block_buf);
+        digests_buf = rest;
+        consumed = start + block_digests_len;
+    }
+    bufs
+}
+
+/// Like `fill_subtree`, but for a sub-tree whose leaves have already been hashed in blocks, with
+/// the digests internal to each block written to `digests_buf`. Fills in the digests above the
+/// blocks, given their roots.
+fn fill_subtree_from_block_roots<F: RichField, H: Hasher<F>>(
+    digests_buf: &mut [MaybeUninit<H::Hash>],
+    block_roots: &[H::Hash],
+) -> H::Hash {
+    if block_roots.len() == 1 {
+        block_roots[0]
+    } else {
+        let (left_digests_buf, right_digests_buf) = digests_buf.split_at_mut(digests_buf.len() / 2);
+        let (left_digest_mem, left_digests_buf) = left_digests_buf.split_last_mut().unwrap();
+        let (right_digest_mem, right_digests_buf) = right_digests_buf.split_first_mut().unwrap();
+        let (left_roots, right_roots) = block_roots.split_at(block_roots.len() / 2);
+
+        let (left_digest, right_digest) = plonky2_maybe_rayon::join(
+            || fill_subtree_from_block_roots::<F, H>(left_digests_buf, left_roots),
+            || fill_subtree_from_block_roots::<F, H>(right_digests_buf, right_roots),
+        );
+
+        left_digest_mem.write(left_digest);
+        right_digest_mem.write(right_digest);
+        H::two_to_one(left_digest, right_digest)
+    }
+}
+
 pub(crate) fn merkle_tree_prove<F: RichField, H: Hasher<F>>(
     leaf_index: usize,
     leaves_len: usize,
@@ -191,7 +282,63 @@ pub(crate) fn merkle_tree_prove<F: RichField, H: Hasher<F>>(
 
 impl<F: RichField, H: Hasher<F>> MerkleTree<F, H> {
     pub fn new(leaves: Vec<Vec<F>>, cap_height: usize) -> Self {
-        let log2_leaves_len = log2_strict(leaves.len());
+        Self::new_chunked(
+            leaves.len(),
+            leaves,
+            cap_height,
+            |_| unreachable!("All leaves are given"),
+            |leaves, range| H::hash_or_noop_many(&leaves[range]),
+        )
+    }
+
+    /// Builds a Merkle tree whose `i`th leaf is row `row(i)` of the matrix whose columns are
+    /// `columns`, such as a batch of LDEs. The leaves are hashed straight from the columns, in
+    /// parallel lanes if the hasher supports it, while the rows are copied out for openings.
+    pub fn new_from_columns<Col, R>(columns: &[Col], row: R, cap_height: usize) -> Self
+    where
+        Col: AsRef<[F]> + Sync,
+        R: Fn(usize) -> usize + Sync,
+    {
+        let leaves_len = columns.first().expect("No columns").as_ref().len();
+        Self::new_chunked(
+            leaves_len,
+            Vec::with_capacity(leaves_len),
+            cap_height,
+            |range| {
+                range
+                    .into_par_iter()
+                    .map(|i| columns.iter().map(|col| col.as_ref()[row(i)]).collect())
+                    .collect()
+            },
+            |_, range| {
+                let mut rows = [0; LEAF_BATCH_LEN];
+                let rows = &mut rows[..range.len()];
+                for (r, i) in rows.iter_mut().zip(range) {
+                    *r = row(i);
+                }
+                H::hash_or_noop_rows(columns, rows)
+            },
+        )
+    }
+
+    /// Builds a Merkle tree of `leaves_len` leaves, of which `leaves` holds the first ones, and
+    /// where `next_chunk(range)` returns the leaves with indices in `range` which are not in
+    /// `leaves`. The leaves are hashed in consecutive chunks of `LEAF_CHUNK_LEN` leaves (or fewer,
+    /// for small trees), and each chunk is hashed before the next one is requested, in batches of
+    /// at most `LEAF_BATCH_LEN` leaves: `hash_batch(leaves, range)` returns the digests of the
+    /// leaves with indices in `range`, given all leaves read so far.
+    fn new_chunked<C, B>(
+        leaves_len: usize,
+        mut leaves: Vec<Vec<F>>,
+        cap_height: usize,
+        mut next_chunk: C,
+        hash_batch: B,
+    ) -> Self
+    where
+        C: FnMut(Range<usize>) -> Vec<Vec<F>>,
+        B: Fn(&[Vec<F>], Range<usize>) -> Vec<H::Hash> + Sync,
+    {
+        let log2_leaves_len = log2_strict(leaves_len);
         assert!(
             cap_height <= log2_leaves_len,
             "cap_height={} should be at most log2(leaves.len())={}",
@@ -199,19 +346,69 @@ impl<F: RichField, H: Hasher<F>> MerkleTree<F, H> {
             log2_leaves_len
         );
 
-        let num_digests = 2 * (leaves.len() - (1 << cap_height));
+        let num_digests = 2 * (leaves_len - (1 << cap_height));
         let mut digests = Vec::with_capacity(num_digests);
 
         let len_cap = 1 << cap_height;
         let mut cap = Vec::with_capacity(len_cap);
 
+        // Each chunk of leaves is split into blocks lying within a single sub-tree, whose digests
+        // are computed as soon as the chunk is read. The digests above the blocks are filled in
+        // from the blocks' roots once all leaves have been read.
+        let subtree_leaves_len = leaves_len >> cap_height;
+        let block_len = subtree_leaves_len.min(LEAF_CHUNK_LEN);
+        let chunk_len = leaves_len.min(LEAF_CHUNK_LEN);
+        let mut block_roots = Vec::with_capacity(leaves_len / block_len);
+
         let digests_buf = capacity_up_to_mut(&mut digests, num_digests);
+        for chunk_start in (0..leaves_len).step_by(chunk_len) {
+            if leaves.len() < chunk_start + chunk_len {
+                leaves.extend(next_chunk(leaves.len()..chunk_start + chunk_len));
+            }
+            let leaf_digests =
+                hash_leaves::<F, H, _>(chunk_start..chunk_start + chunk_len, |range| {
+                    hash_batch(&leaves, range)
+                });
+
+            let first_block = chunk_start / block_len;
+            let blocks_bufs = block_digests_bufs(
+                digests_buf,
+                first_block..first_block + chunk_len / block_len,
+                block_len,
+                subtree_leaves_len,
+            );
+            let chunk_roots: Vec<_> = leaf_digests
+                .par_chunks_exact(block_len)
+                .zip(blocks_bufs)
+                .map(|(block_leaf_digests, block_buf)| {
+                    fill_subtree_from_block_roots::<F, H>(block_buf, block_leaf_digests)
+                })
+                .collect();
+            block_roots.extend(chunk_roots);
+        }
+
         let cap_buf = capacity_up_to_mut(&mut cap, len_cap);
-        fill_digests_buf::<F, H>(digests_buf, cap_buf, &leaves[..], cap_height);
+        if digests_buf.is_empty() {
+            // Each leaf is its own sub-tree, so the blocks' roots form the cap.
+            for (cap_buf, &root) in cap_buf.iter_mut().zip(&block_roots) {
+                cap_buf.write(root);
+            }
+        } else {
+            digests_buf
+                .par_chunks_exact_mut(num_digests >> cap_height)
+                .zip(cap_buf)
+                .zip(block_roots.par_chunks_exact(subtree_leaves_len / block_len))
+                .for_each(|((subtree_digests, subtree_cap), subtree_roots)| {
+                    subtree_cap.write(fill_subtree_from_block_roots::<F, H>(
+                        subtree_digests,
+                        subtree_roots,
+                    ));
+                });
+        }
 
         unsafe {
-            // SAFETY: `fill_digests_buf` and `cap` initialized the spare capacity up to
-            // `num_digests` and `len_cap`, resp.
+            // SAFETY: The blocks and `fill_subtree_from_block_roots` initialized the spare capacity
+            // of `digests` up to `num_digests`, and that of `cap` up to `len_cap`.
             digests.set_len(num_digests);
             cap.set_len(len_cap);
         }
@@ -245,6 +442,7 @@ pub(crate) mod tests {
     use crate::field::extension::Extendable;
     use crate::hash::merkle_proofs::verify_merkle_proof_to_cap;
     use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::util::reverse_bits;
 
     pub(crate) fn random_data<F: RichField>(n: usize, k: usize) -> Vec<Vec<F>> {
         (0..n).map(|_| F::rand_vec(k)).collect()
@@ -309,4 +507,56 @@ pub(crate) mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_chunked_build_matches_recursive() {
+        type H = <PoseidonGoldilocksConfig as GenericConfig<2>>::Hasher;
+        type F = <PoseidonGoldilocksConfig as GenericConfig<2>>::F;
+
+        // More leaves than `LEAF_CHUNK_LEN`, so that they are read in several chunks.
+        let log_n = 13;
+        let n = 1 << log_n;
+        let leaves = random_data::<F>(n, 5);
+
+        for cap_height in [0, 1, 4, log_n - 1, log_n] {
+            let num_digests = 2 * (n - (1 << cap_height));
+            let mut digests = Vec::with_capacity(num_digests);
+            let mut cap = Vec::with_capacity(1 << cap_height);
+            fill_digests_buf::<F, H>(
+                capacity_up_to_mut(&mut digests, num_digests),
+                capacity_up_to_mut(&mut cap, 1 << cap_height),
+                &leaves,
+                cap_height,
+            );
+            unsafe {
+                digests.set_len(num_digests);
+                cap.set_len(1 << cap_height);
+            }
+
+            let tree = MerkleTree::<F, H>::new(leaves.clone(), cap_height);
+            assert_eq!(tree.digests, digests);
+            assert_eq!(tree.cap.0, cap);
+            assert_eq!(tree.leaves, leaves);
+        }
+    }
+
+    #[test]
+    fn test_new_from_columns() {
+        type H = <PoseidonGoldilocksConfig as GenericConfig<2>>::Hasher;
+        type F = <PoseidonGoldilocksConfig as GenericConfig<2>>::F;
+
+        let log_n = 13;
+        let n = 1 << log_n;
+        let row = |i| reverse_bits(i, log_n);
+        // Short rows are not hashed, so check both kinds.
+        for num_columns in [3, 13] {
+            let columns = random_data::<F>(num_columns, n);
+            let leaves: Vec<Vec<F>> = (0..n)
+                .map(|i| columns.iter().map(|col| col[row(i)]).collect())
+                .collect();
+            let expected = MerkleTree::<F, H>::new(leaves, 2);
+            let tree = MerkleTree::<F, H>::new_from_columns(&columns, row, 2);
+            assert_eq!(tree, expected);
+        }
+    }
 }
@@ -3,11 +3,18 @@
 
 mod arch;
 pub mod batch_merkle_tree;
+pub mod blake3;
 pub mod hash_types;
 pub mod hashing;
 pub mod keccak;
 pub mod merkle_proofs;
 pub mod merkle_tree;
+pub mod monolith;
 pub mod path_compression;
 pub mod poseidon;
+pub mod poseidon2;
 pub mod poseidon_goldilocks;
+pub mod rescue_prime;
+pub mod sha256;
+pub mod sponge;
+pub mod tip5;
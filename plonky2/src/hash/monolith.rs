@@ -0,0 +1,433 @@
+//! Implementation of the Monolith permutation, as described in
+//! <https://eprint.iacr.org/2023/1025.pdf>
+//!
+//! Monolith is designed for fields whose elements fit in 64 bits. Its only non-linear layers are
+//! `Bars`, which applies a small S-box to each byte of a few state elements, and `Bricks`, a
+//! Feistel layer of squarings. This makes it much cheaper to compute natively than Poseidon, at the
+//! cost of needing lookups to be computed in a circuit.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+use core::fmt::Debug;
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::{Field, PrimeField64};
+use crate::gates::monolith::MonolithGate;
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::hash::hashing::{compress, hash_n_to_hash_no_pad, PlonkyPermutation};
+use crate::hash::poseidon::{SPONGE_RATE, SPONGE_WIDTH};
+use crate::hash::poseidon_goldilocks::poseidon12_mds::circulant_multiply_freq;
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, Hasher};
+
+/// The number of rounds, as recommended in the paper for a width of 12 over a 64-bit field.
+pub const N_ROUNDS: usize = 6;
+
+/// The number of state elements to which `Bars` is applied.
+pub const N_BARS: usize = 4;
+
+/// The first row of the circulant matrix used by `Concrete`. Rescue-Prime uses the same matrix.
+pub(crate) const MDS_CIRC: [u64; SPONGE_WIDTH] = [7, 23, 8, 26, 13, 10, 9, 7, 6, 22, 21, 8];
+
+/// The circulant matrix used by `Concrete`, whose `i`th row is `MDS_CIRC` rotated right by `i`.
+pub(crate) const MDS_MATRIX: [[u64; SPONGE_WIDTH]; SPONGE_WIDTH] = {
+    let mut matrix = [[0; SPONGE_WIDTH]; SPONGE_WIDTH];
+    let mut i = 0;
+    while i < SPONGE_WIDTH {
+        let mut j = 0;
+        while j < SPONGE_WIDTH {
+            matrix[i][j] = MDS_CIRC[(j + SPONGE_WIDTH - i) % SPONGE_WIDTH];
+            j += 1;
+        }
+        i += 1;
+    }
+    matrix
+};
+
+/// Applies the `Bars` S-box to each byte of `x`, i.e.
+/// `y |--> (y ^ ((!y <<< 1) & (y <<< 2) & (y <<< 3))) <<< 1`, where `<<<` rotates within the byte.
+///
+/// The S-box maps `0x00` and `0xff` to themselves, so that the result is canonical whenever `x` is
+/// a canonical Goldilocks element.
+#[inline(always)]
+pub const fn bar_u64(x: u64) -> u64 {
+    /// Rotates each byte of `x` left by `n` bits.
+    #[inline(always)]
+    const fn rotl_bytes(x: u64, n: u32) -> u64 {
+        let high_mask = u64::from_ne_bytes([(0xffu8 << (8 - n)); 8]);
+        ((x & high_mask) >> (8 - n)) | ((x & !high_mask) << n)
+    }
+
+    let t = x ^ (rotl_bytes(!x, 1) & rotl_bytes(x, 2) & rotl_bytes(x, 3));
+    rotl_bytes(t, 1)
+}
+
+/// The `Bars` S-box applied to each byte of a 16-bit limb, which is the lookup table used to
+/// compute `Bars` in circuits.
+pub(crate) fn bar_u16(limb: u16) -> u16 {
+    bar_u64(limb as u64) as u16
+}
+
+pub trait Monolith: PrimeField64 {
+    /// The constants added by `Concrete` in each round. The last round adds no constants, so its
+    /// row is zero.
+    const ROUND_CONSTANTS: [[u64; SPONGE_WIDTH]; N_ROUNDS];
+
+    /// Applies the `Bars` S-box to the first `N_BARS` elements.
+    #[inline(always)]
+    fn bars(state: &mut [Self; SPONGE_WIDTH]) {
+        for x in state.iter_mut().take(N_BARS) {
+            *x = Self::from_noncanonical_u64(bar_u64(x.to_canonical_u64()));
+        }
+    }
+
+    /// Adds to each element the square of the one before it, as they were before this layer.
+    #[inline(always)]
+    fn bricks_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; SPONGE_WIDTH],
+    ) {
+        for i in (1..SPONGE_WIDTH).rev() {
+            state[i] += state[i - 1].square();
+        }
+    }
+
+    /// Recursive version of `bricks_field`.
+    fn bricks_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; SPONGE_WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for i in (1..SPONGE_WIDTH).rev() {
+            state[i] = builder.mul_add_extension(state[i - 1], state[i - 1], state[i]);
+        }
+    }
+
+    /// Multiplies the state by the circulant matrix with first row `MDS_CIRC`, then adds the
+    /// constants of the given round.
+    #[inline(always)]
+    fn concrete_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; SPONGE_WIDTH],
+        round_constants: &[F; SPONGE_WIDTH],
+    ) {
+        let mut result = *round_constants;
+        for (i, r) in result.iter_mut().enumerate() {
+            for (j, &x) in state.iter().enumerate() {
+                *r += x * F::from_canonical_u64(MDS_CIRC[(j + SPONGE_WIDTH - i) % SPONGE_WIDTH]);
+            }
+        }
+        *state = result;
+    }
+
+    /// Same as `concrete_field` for the base field. As the coefficients are small, the products
+    /// by the low and high halves of each element are accumulated separately in `u64`s, and only
+    /// reduced once per element.
+    #[inline(always)]
+    fn concrete(state: &mut [Self; SPONGE_WIDTH], round_constants: &[u64; SPONGE_WIDTH]) {
+        let lo = state.map(|x| x.to_noncanonical_u64() & 0xffff_ffff);
+        let hi = state.map(|x| x.to_noncanonical_u64() >> 32);
+        for (i, r) in state.iter_mut().enumerate() {
+            // Each sum is below `12 * 2^5 * 2^32 < 2^41`.
+            let mut acc_lo = 0u64;
+            let mut acc_hi = 0u64;
+            for j in 0..SPONGE_WIDTH {
+                acc_lo += lo[j] * MDS_MATRIX[i][j];
+                acc_hi += hi[j] * MDS_MATRIX[i][j];
+            }
+            let acc = ((acc_hi as u128) << 32) + acc_lo as u128 + round_constants[i] as u128;
+            *r = Self::from_noncanonical_u128(acc);
+        }
+    }
+
+    /// Recursive version of `concrete_field`.
+    fn concrete_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; SPONGE_WIDTH],
+        round_constants: &[ExtensionTarget<D>; SPONGE_WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        let mut result = *round_constants;
+        for (i, r) in result.iter_mut().enumerate() {
+            for (j, &x) in state.iter().enumerate() {
+                let c = Self::from_canonical_u64(MDS_CIRC[(j + SPONGE_WIDTH - i) % SPONGE_WIDTH]);
+                *r = builder.mul_const_add_extension(c, x, *r);
+            }
+        }
+        *state = result;
+    }
+
+    fn monolith(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        let mut state = input;
+        Self::concrete(&mut state, &[0; SPONGE_WIDTH]);
+        for round_constants in &Self::ROUND_CONSTANTS {
+            Self::bars(&mut state);
+            Self::bricks_field(&mut state);
+            Self::concrete(&mut state, round_constants);
+        }
+        state
+    }
+}
+
+/// `MDS_MATRIX` in the frequency domain, for `poseidon12_mds::circulant_multiply_freq`.
+const MDS_FREQ_BLOCK_ONE: [i64; 3] = [16, 8, 16];
+const MDS_FREQ_BLOCK_TWO: [(i64, i64); 3] = [(-1, 2), (-1, 1), (4, 8)];
+const MDS_FREQ_BLOCK_THREE: [i64; 3] = [-8, 1, 1];
+
+/// Multiplies `state`, whose entries must fit in 32 bits, by `MDS_MATRIX`.
+#[inline(always)]
+pub(crate) const fn mds_multiply_freq(state: [u64; SPONGE_WIDTH]) -> [u64; SPONGE_WIDTH] {
+    circulant_multiply_freq(
+        state,
+        MDS_FREQ_BLOCK_ONE,
+        MDS_FREQ_BLOCK_TWO,
+        MDS_FREQ_BLOCK_THREE,
+    )
+}
+
+/// The round constants were generated as in the reference implementation: each is the first
+/// little-endian `u64` below the field order read from a SHAKE128 stream, seeded with `"Monolith"`,
+/// the width and number of rounds as bytes, the field order in little-endian and the bit size of
+/// each of the eight limbs.
+#[rustfmt::skip]
+impl Monolith for GoldilocksField {
+    const ROUND_CONSTANTS: [[u64; SPONGE_WIDTH]; N_ROUNDS] = [
+        [
+            0xbcaf2516e5926dcf, 0x4ec5a76bce1e7676, 0x9d804725bebb56ab, 0x2ec05fca215a5be3,
+            0xe16274e4acab86a0, 0x80b0fddcc3c4380f, 0xc87c769ad77ffece, 0x37f85ec9117d287c,
+            0x3b8d825b014c458d, 0xb7a01d0cb850d75e, 0x1333b751bac704bd, 0x7b7ef14183d47b6f,
+        ],
+        [
+            0x2114517643e3b286, 0x542d15ea3cd12ade, 0xe847d363f17a93e9, 0x24f0421c6ff41c56,
+            0x66e3eda93e2ca216, 0xfb88d475279cb568, 0x7f421c6269938a22, 0xdbb973acce857401,
+            0xe172409cb1563a6a, 0x996f729f6340447d, 0x925c579738b6fa4a, 0x752e9ec9e0b34686,
+        ],
+        [
+            0xdb419e0bd38469bd, 0xba41cee828bd26d8, 0xd6630f8f0969db39, 0x2340e955ae2f0d94,
+            0x282f553d35872e2e, 0x77f7c3ff1ae496b3, 0xf5f2efab64bc5eef, 0x47b23a00830284f4,
+            0x0e18a2d2242486fa, 0x3d101838a773dab0, 0x47d686fd16856524, 0x3eb2d254189b3534,
+        ],
+        [
+            0xfe886e291ca8c5bd, 0xb97ec74df1e4b0b6, 0x574fdef3a600e370, 0x8ad61c6f132d4feb,
+            0x41e69ca4ecc7e8c7, 0x151ad562e1f90ca4, 0x747c051439a5603c, 0x990151d3e52d502c,
+            0x532c7f258282ea12, 0x065e62cb34275dd5, 0x5288008954f5d0b2, 0xee7c3407cf3d6e02,
+        ],
+        [
+            0xda07029808bad5de, 0x7bebdf38dcc7a673, 0x20a3f252688c312d, 0x9c5248f7bbf8d188,
+            0xcf1cf778994382d4, 0x8c434b1738b8338c, 0xfe504398813b67a8, 0xe879562fdef813b9,
+            0xd4666793b2a2f191, 0xd9096b87de22de01, 0xcaf4cea5f22abf34, 0x3128d1e75d0204fa,
+        ],
+        [0; SPONGE_WIDTH],
+    ];
+
+    #[inline(always)]
+    fn concrete(state: &mut [Self; SPONGE_WIDTH], round_constants: &[u64; SPONGE_WIDTH]) {
+        let lo = state.map(|x| x.to_noncanonical_u64() & 0xffff_ffff);
+        let hi = state.map(|x| x.to_noncanonical_u64() >> 32);
+        // As in Poseidon's MDS layer, the low and high halves are multiplied separately so that
+        // the results fit in 64 bits.
+        let lo = mds_multiply_freq(lo);
+        let hi = mds_multiply_freq(hi);
+        for i in 0..SPONGE_WIDTH {
+            let s = ((hi[i] as u128) << 32) + lo[i] as u128 + round_constants[i] as u128;
+            state[i] = Self::from_noncanonical_u96((s as u64, (s >> 64) as u32));
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct MonolithPermutation<T> {
+    state: [T; SPONGE_WIDTH],
+}
+
+impl<T: Eq> Eq for MonolithPermutation<T> {}
+
+impl<T> AsRef<[T]> for MonolithPermutation<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.state
+    }
+}
+
+impl<T> AsMut<[T]> for MonolithPermutation<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        &mut self.state
+    }
+}
+
+trait MonolithPermuter: Sized {
+    fn permute(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH];
+}
+
+impl<F: Monolith> MonolithPermuter for F {
+    fn permute(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        <F as Monolith>::monolith(input)
+    }
+}
+
+impl MonolithPermuter for Target {
+    fn permute(_input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        panic!("Call `permute_swapped()` instead of `permute()`");
+    }
+}
+
+impl<T: Copy + Debug + Default + Eq + MonolithPermuter + Send + Sync> PlonkyPermutation<T>
+    for MonolithPermutation<T>
+{
+    const RATE: usize = SPONGE_RATE;
+    const WIDTH: usize = SPONGE_WIDTH;
+
+    fn permute(&mut self) {
+        self.state = T::permute(self.state);
+    }
+}
+
+/// Monolith hash function, with the same sponge parameters as [`crate::hash::poseidon::PoseidonHash`].
+///
+/// In circuits, each permutation takes one [`MonolithGate`] per round, and a lookup in a table of
+/// size `2^16` for each 16-bit limb going through `Bars`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MonolithHash;
+impl<F: RichField + Monolith> Hasher<F> for MonolithHash {
+    const HASH_SIZE: usize = 4 * 8;
+    type Hash = HashOut<F>;
+    type Permutation = MonolithPermutation<F>;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        hash_n_to_hash_no_pad::<F, Self::Permutation>(input)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        compress::<F, Self::Permutation>(left, right)
+    }
+}
+
+impl<F: RichField + Monolith> AlgebraicHasher<F> for MonolithHash {
+    type AlgebraicPermutation = MonolithPermutation<Target>;
+
+    fn permute_swapped<const D: usize>(
+        inputs: Self::AlgebraicPermutation,
+        swap: BoolTarget,
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> Self::AlgebraicPermutation
+    where
+        F: RichField + Extendable<D>,
+    {
+        let lut_index = MonolithGate::<F, D>::add_lookup_table(builder);
+        let zero = builder.zero();
+
+        let mut state: [Target; SPONGE_WIDTH] = inputs.as_ref().try_into().unwrap();
+        for round in 0..N_ROUNDS {
+            let gate_type = MonolithGate::<F, D>::new();
+            let gate = builder.add_gate(gate_type, vec![F::from_bool(round == 0)]);
+
+            // Only the first round swaps its inputs.
+            let swap_wire = Target::wire(gate, MonolithGate::<F, D>::WIRE_SWAP);
+            builder.connect(if round == 0 { swap.target } else { zero }, swap_wire);
+
+            // Route input wires and round constants.
+            for i in 0..SPONGE_WIDTH {
+                let in_wire = Target::wire(gate, MonolithGate::<F, D>::wire_input(i));
+                builder.connect(state[i], in_wire);
+                let c = F::from_canonical_u64(<F as Monolith>::ROUND_CONSTANTS[round][i]);
+                let c = builder.constant(c);
+                let c_wire = Target::wire(gate, MonolithGate::<F, D>::wire_round_constant(i));
+                builder.connect(c, c_wire);
+            }
+
+            // Look up the image of each limb through `Bars`.
+            for i in 0..N_BARS {
+                for j in 0..MonolithGate::<F, D>::NUM_LIMBS {
+                    let limb_in = Target::wire(gate, MonolithGate::<F, D>::wire_limb_input(i, j));
+                    let limb_out = builder.add_lookup_from_index(limb_in, lut_index);
+                    let out_wire = MonolithGate::<F, D>::wire_limb_output(i, j);
+                    builder.connect(limb_out, Target::wire(gate, out_wire));
+                }
+            }
+
+            state =
+                core::array::from_fn(|i| Target::wire(gate, MonolithGate::<F, D>::wire_output(i)));
+        }
+
+        Self::AlgebraicPermutation::new(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::{Field64, Sample};
+
+    #[test]
+    fn test_vectors() {
+        // Computed with an independent implementation of the permutation, from the same constants.
+        #[rustfmt::skip]
+        let test_vectors: [([u64; SPONGE_WIDTH], [u64; SPONGE_WIDTH]); 4] = [
+            ([0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+              0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+              0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, ],
+             [0xfa60f4367e102330, 0x08375c01f5e0d586, 0x781e934217fe1177, 0x4eb6804e8456d65b,
+              0x6b6ccd3734374568, 0x9ac4b9617f037daf, 0x156d4a26f73014b1, 0x5a0c06634e08d10c,
+              0x88905ab9b32a4e13, 0xc8b0a5c90b512b59, 0x22b4b2c0f553f7ab, 0x9bcdba399e4956d3, ]),
+            ([0x0000000000000000, 0x0000000000000001, 0x0000000000000002, 0x0000000000000003,
+              0x0000000000000004, 0x0000000000000005, 0x0000000000000006, 0x0000000000000007,
+              0x0000000000000008, 0x0000000000000009, 0x000000000000000a, 0x000000000000000b, ],
+             [0x516dd661e959f541, 0x082c137169707901, 0x53dff3fd9f0a5beb, 0x0b2ebaa261590650,
+              0x89aadb57e2969cb6, 0x5d3d6905970259bd, 0x6e5ac1a4c0cfa0fe, 0xd674b7736abfc5ce,
+              0x0d8697e1cd9a235f, 0x85fc4017c247136e, 0x572bafd76e511424, 0xbec1638e28eae57f, ]),
+            ([0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000,
+              0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000,
+              0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, ],
+             [0xed0d96c28e7956a0, 0x62c20713a305a058, 0x5d553a1c32833bc9, 0x3144f548acc4f1b7,
+              0x3075d2898057991c, 0xe38d8e9c63381e7d, 0xf684e898b9a38583, 0x6f6131f33e84cf71,
+              0x061f4aafe32ef3cb, 0xc79453384194d313, 0xff8b63863ab919c7, 0x557fc31a78ac943c, ]),
+            ([0x91b7584a2265b1f5, 0xcd613e30d8f16adf, 0x1027c4d1c386bbc4, 0x1e2feb89414c343c,
+              0xc2ce6f447ed4d57b, 0x78e510617311d8a3, 0x612e7696a6cecc1b, 0x35bf992dc9e9c616,
+              0x7ce42c8218072e8c, 0xe4b06ce60741c7a8, 0x63ca828dd5f4b3b2, 0x9b810e766ec9d286, ],
+             [0xee51e8f41b6d36fc, 0xd2e8191cd83de568, 0x4175cd82587cafb3, 0xc6a92e23f42876b1,
+              0xb78fe5d98a07afa4, 0x6f0611fa27135d17, 0x6c871b64faa07b36, 0x55c71434f6a6f8c5,
+              0x57e191007b9205ff, 0x894ce01179f1d8c4, 0x240ec1769d32e76a, 0xf3a9ce3d023ac814, ]),
+        ];
+
+        for (input, expected) in test_vectors {
+            let input = input.map(GoldilocksField::from_canonical_u64);
+            let output = GoldilocksField::monolith(input);
+            assert_eq!(output, expected.map(GoldilocksField::from_canonical_u64));
+        }
+    }
+
+    #[test]
+    fn test_concrete_matches_generic() {
+        let round_constants = GoldilocksField::ROUND_CONSTANTS[0];
+        let mut state = GoldilocksField::rand_array::<SPONGE_WIDTH>();
+        let mut expected = state;
+        GoldilocksField::concrete(&mut state, &round_constants);
+        GoldilocksField::concrete_field(
+            &mut expected,
+            &round_constants.map(GoldilocksField::from_canonical_u64),
+        );
+        assert_eq!(state, expected);
+    }
+
+    #[test]
+    fn test_bars_preserves_canonical_form() {
+        let order = GoldilocksField::ORDER;
+        let edge_cases = [
+            0,
+            1,
+            order - 1,
+            order - 2,
+            0xffff_fffe_ffff_ffff,
+            0xffff_ffff,
+        ];
+        for x in edge_cases {
+            assert!(bar_u64(x) < order);
+        }
+        for _ in 0..1000 {
+            let x = GoldilocksField::rand().to_canonical_u64();
+            assert!(bar_u64(x) < order);
+        }
+    }
+}
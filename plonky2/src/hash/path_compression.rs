@@ -151,12 +151,12 @@ mod tests {
         #[cfg(feature = "std")]
         {
             let compressed_proof_bytes = serde_cbor::to_vec(&compressed_proofs).unwrap();
-            println!(
+            log::info!(
                 "Compressed proof length: {} bytes",
                 compressed_proof_bytes.len()
             );
             let proof_bytes = serde_cbor::to_vec(&proofs).unwrap();
-            println!("Proof length: {} bytes", proof_bytes.len());
+            log::info!("Proof length: {} bytes", proof_bytes.len());
         }
     }
 }
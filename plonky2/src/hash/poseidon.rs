@@ -5,6 +5,7 @@
 use alloc::{vec, vec::Vec};
 use core::fmt::Debug;
 
+use plonky2_field::packable::Packable;
 use plonky2_field::packed::PackedField;
 use unroll::unroll_for_loops;
 
@@ -13,7 +14,7 @@ use crate::field::types::{Field, PrimeField64};
 use crate::gates::gate::Gate;
 use crate::gates::poseidon::PoseidonGate;
 use crate::gates::poseidon_mds::PoseidonMdsGate;
-use crate::hash::hash_types::{HashOut, RichField};
+use crate::hash::hash_types::{HashOut, RichField, NUM_HASH_OUT_ELTS};
 use crate::hash::hashing::{compress, hash_n_to_hash_no_pad, PlonkyPermutation};
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::{BoolTarget, Target};
@@ -776,6 +777,23 @@ pub trait Poseidon: PrimeField64 {
         state
     }
 
+    /// Same as `poseidon`, but permutes `P::WIDTH` independent states at once, one in each lane.
+    #[inline]
+    fn poseidon_packed<P: PackedField<Scalar = Self>>(
+        input: [P; SPONGE_WIDTH],
+    ) -> [P; SPONGE_WIDTH] {
+        let mut state = input;
+        let mut round_ctr = 0;
+
+        full_rounds_packed(&mut state, &mut round_ctr);
+        partial_rounds_packed(&mut state);
+        round_ctr += N_PARTIAL_ROUNDS;
+        full_rounds_packed(&mut state, &mut round_ctr);
+        debug_assert_eq!(round_ctr, N_ROUNDS);
+
+        state
+    }
+
     // For testing only, to ensure that various tricks are correct.
     #[inline]
     fn partial_rounds_naive(state: &mut [Self; SPONGE_WIDTH], round_ctr: &mut usize) {
@@ -801,6 +819,79 @@ pub trait Poseidon: PrimeField64 {
     }
 }
 
+#[inline(always)]
+fn sbox_monomial_packed<P: PackedField>(x: P) -> P {
+    // x |--> x^7
+    let x2 = x.square();
+    let x4 = x2.square();
+    let x3 = x * x2;
+    x3 * x4
+}
+
+#[inline]
+fn mds_layer_packed<F: Poseidon, P: PackedField<Scalar = F>>(
+    state: &[P; SPONGE_WIDTH],
+) -> [P; SPONGE_WIDTH] {
+    let mut result = [P::ZEROS; SPONGE_WIDTH];
+    for (r, res) in result.iter_mut().enumerate() {
+        for i in 0..SPONGE_WIDTH {
+            *res += state[(i + r) % SPONGE_WIDTH] * F::from_canonical_u64(F::MDS_MATRIX_CIRC[i]);
+        }
+        *res += state[r] * F::from_canonical_u64(F::MDS_MATRIX_DIAG[r]);
+    }
+    result
+}
+
+#[inline]
+fn full_rounds_packed<F: Poseidon, P: PackedField<Scalar = F>>(
+    state: &mut [P; SPONGE_WIDTH],
+    round_ctr: &mut usize,
+) {
+    for _ in 0..HALF_N_FULL_ROUNDS {
+        for (i, s) in state.iter_mut().enumerate() {
+            *s += F::from_canonical_u64(ALL_ROUND_CONSTANTS[i + SPONGE_WIDTH * *round_ctr]);
+            *s = sbox_monomial_packed(*s);
+        }
+        *state = mds_layer_packed(state);
+        *round_ctr += 1;
+    }
+}
+
+/// Same as `Poseidon::partial_rounds` for `PackedField`, using the same sparse matrices.
+#[inline]
+fn partial_rounds_packed<F: Poseidon, P: PackedField<Scalar = F>>(state: &mut [P; SPONGE_WIDTH]) {
+    for (s, &c) in state.iter_mut().zip(&F::FAST_PARTIAL_FIRST_ROUND_CONSTANT) {
+        *s += F::from_canonical_u64(c);
+    }
+
+    // The initial matrix has first row and column [1, 0, ..., 0].
+    let mut result = [P::ZEROS; SPONGE_WIDTH];
+    result[0] = state[0];
+    for r in 1..SPONGE_WIDTH {
+        for c in 1..SPONGE_WIDTH {
+            let t = F::from_canonical_u64(F::FAST_PARTIAL_ROUND_INITIAL_MATRIX[r - 1][c - 1]);
+            result[c] += state[r] * t;
+        }
+    }
+    *state = result;
+
+    for r in 0..N_PARTIAL_ROUNDS {
+        state[0] = sbox_monomial_packed(state[0])
+            + F::from_canonical_u64(F::FAST_PARTIAL_ROUND_CONSTANTS[r]);
+
+        let mds0to0 = F::MDS_MATRIX_CIRC[0] + F::MDS_MATRIX_DIAG[0];
+        let mut d = state[0] * F::from_canonical_u64(mds0to0);
+        for i in 1..SPONGE_WIDTH {
+            d += state[i] * F::from_canonical_u64(F::FAST_PARTIAL_ROUND_W_HATS[r][i - 1]);
+        }
+        for i in 1..SPONGE_WIDTH {
+            let t = F::from_canonical_u64(F::FAST_PARTIAL_ROUND_VS[r][i - 1]);
+            state[i] += state[0] * t;
+        }
+        state[0] = d;
+    }
+}
+
 #[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct PoseidonPermutation<T> {
     state: [T; SPONGE_WIDTH],
@@ -814,6 +905,12 @@ impl<T> AsRef<[T]> for PoseidonPermutation<T> {
     }
 }
 
+impl<T> AsMut<[T]> for PoseidonPermutation<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        &mut self.state
+    }
+}
+
 trait Permuter: Sized {
     fn permute(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH];
 }
@@ -836,37 +933,41 @@ impl<T: Copy + Debug + Default + Eq + Permuter + Send + Sync> PlonkyPermutation<
     const RATE: usize = SPONGE_RATE;
     const WIDTH: usize = SPONGE_WIDTH;
 
-    fn new<I: IntoIterator<Item = T>>(elts: I) -> Self {
-        let mut perm = Self {
-            state: [T::default(); SPONGE_WIDTH],
-        };
-        perm.set_from_iter(elts, 0);
-        perm
-    }
-
-    fn set_elt(&mut self, elt: T, idx: usize) {
-        self.state[idx] = elt;
-    }
-
-    fn set_from_slice(&mut self, elts: &[T], start_idx: usize) {
-        let begin = start_idx;
-        let end = start_idx + elts.len();
-        self.state[begin..end].copy_from_slice(elts);
-    }
-
-    fn set_from_iter<I: IntoIterator<Item = T>>(&mut self, elts: I, start_idx: usize) {
-        for (s, e) in self.state[start_idx..].iter_mut().zip(elts) {
-            *s = e;
-        }
-    }
-
     fn permute(&mut self) {
         self.state = T::permute(self.state);
     }
+}
 
-    fn squeeze(&self) -> &[T] {
-        &self.state[..Self::RATE]
+/// Same as `hash_n_to_hash_no_pad` applied to `num_inputs` inputs of `input_len` elements each,
+/// where `input(i, j)` is the `j`th element of the `i`th input. The inputs are absorbed `P::WIDTH`
+/// at a time, one in each lane of a packed permutation, and any remainder one at a time.
+fn hash_no_pad_packed<F: RichField, P: PackedField<Scalar = F>>(
+    num_inputs: usize,
+    input_len: usize,
+    input: impl Fn(usize, usize) -> F,
+) -> Vec<HashOut<F>> {
+    let num_packed = num_inputs - num_inputs % P::WIDTH;
+    let mut digests = Vec::with_capacity(num_inputs);
+    for start in (0..num_packed).step_by(P::WIDTH) {
+        let mut state = [P::ZEROS; SPONGE_WIDTH];
+        for chunk_start in (0..input_len).step_by(SPONGE_RATE) {
+            let chunk_end = (chunk_start + SPONGE_RATE).min(input_len);
+            for (s, j) in state.iter_mut().zip(chunk_start..chunk_end) {
+                for (lane, x) in s.as_slice_mut().iter_mut().enumerate() {
+                    *x = input(start + lane, j);
+                }
+            }
+            state = F::poseidon_packed(state);
+        }
+        digests.extend((0..P::WIDTH).map(|lane| HashOut {
+            elements: core::array::from_fn(|k| state[k].as_slice()[lane]),
+        }));
     }
+    digests.extend((num_packed..num_inputs).map(|i| {
+        let input: Vec<F> = (0..input_len).map(|j| input(i, j)).collect();
+        hash_n_to_hash_no_pad::<F, PoseidonPermutation<F>>(&input)
+    }));
+    digests
 }
 
 /// Poseidon hash function.
@@ -881,6 +982,37 @@ impl<F: RichField> Hasher<F> for PoseidonHash {
         hash_n_to_hash_no_pad::<F, Self::Permutation>(input)
     }
 
+    fn hash_or_noop_many<L: AsRef<[F]>>(inputs: &[L]) -> Vec<Self::Hash> {
+        let input_len = inputs.first().map_or(0, |input| input.as_ref().len());
+        if <F as Packable>::Packing::WIDTH == 1
+            || input_len <= NUM_HASH_OUT_ELTS
+            || inputs.iter().any(|input| input.as_ref().len() != input_len)
+        {
+            return inputs
+                .iter()
+                .map(|input| Self::hash_or_noop(input.as_ref()))
+                .collect();
+        }
+        hash_no_pad_packed::<F, <F as Packable>::Packing>(inputs.len(), input_len, |i, j| {
+            inputs[i].as_ref()[j]
+        })
+    }
+
+    fn hash_or_noop_rows<Col: AsRef<[F]>>(columns: &[Col], rows: &[usize]) -> Vec<Self::Hash> {
+        if <F as Packable>::Packing::WIDTH == 1 || columns.len() <= NUM_HASH_OUT_ELTS {
+            return rows
+                .iter()
+                .map(|&row| {
+                    let input: Vec<F> = columns.iter().map(|col| col.as_ref()[row]).collect();
+                    Self::hash_or_noop(&input)
+                })
+                .collect();
+        }
+        hash_no_pad_packed::<F, <F as Packable>::Packing>(rows.len(), columns.len(), |i, j| {
+            columns[j].as_ref()[rows[i]]
+        })
+    }
+
     fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
         compress::<F, Self::Permutation>(left, right)
     }
@@ -951,8 +1083,28 @@ pub(crate) mod test_helpers {
         }
         let output = F::poseidon(input);
         let output_naive = F::poseidon_naive(input);
+        let output_packed = F::poseidon_packed(input);
         for i in 0..SPONGE_WIDTH {
             assert_eq!(output[i], output_naive[i]);
+            assert_eq!(output[i], output_packed[i]);
+        }
+    }
+
+    pub(crate) fn check_packed_hash<F: RichField>() {
+        // Inputs spanning a partial absorption, a full one, and several.
+        for input_len in [5, SPONGE_RATE, 3 * SPONGE_RATE + 1] {
+            let inputs: Vec<Vec<F>> = (0..7).map(|_| F::rand_vec(input_len)).collect();
+            let expected: Vec<_> = inputs
+                .iter()
+                .map(|input| PoseidonHash::hash_no_pad(input))
+                .collect();
+            let packed = hash_no_pad_packed::<F, <F as Packable>::Packing>(7, input_len, |i, j| {
+                inputs[i][j]
+            });
+            assert_eq!(packed, expected);
+            let unpacked = hash_no_pad_packed::<F, F>(7, input_len, |i, j| inputs[i][j]);
+            assert_eq!(unpacked, expected);
+            assert_eq!(PoseidonHash::hash_or_noop_many(&inputs), expected);
         }
     }
 }
@@ -0,0 +1,442 @@
+//! Implementation of the Poseidon2 permutation, as described in
+//! <https://eprint.iacr.org/2023/323.pdf>
+//!
+//! Poseidon2 has the same structure and S-box as Poseidon, but replaces the dense MDS matrix with
+//! two cheap linear layers: an "external" one, used in full rounds, built from a fixed 4x4 matrix,
+//! and an "internal" one, used in partial rounds, which is the all-ones matrix plus a diagonal.
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::fmt::Debug;
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::{Field, PrimeField64};
+use crate::gates::poseidon2::Poseidon2Gate;
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::hash::hashing::{compress, hash_n_to_hash_no_pad, PlonkyPermutation};
+use crate::hash::poseidon::{SPONGE_RATE, SPONGE_WIDTH};
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, Hasher};
+
+// As for Poseidon, with the S-box x^7 and a width of 12, 8 full rounds and 22 partial rounds are
+// used.
+pub const HALF_N_FULL_ROUNDS: usize = 4;
+pub(crate) const N_FULL_ROUNDS_TOTAL: usize = 2 * HALF_N_FULL_ROUNDS;
+pub const N_PARTIAL_ROUNDS: usize = 22;
+
+/// The 4x4 matrix from which the external linear layer is built, as in section 5.1 of the paper.
+const M4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+pub trait Poseidon2: PrimeField64 {
+    /// The round constants of the full rounds, the first `HALF_N_FULL_ROUNDS` of which are used
+    /// before the partial rounds, and the others after them.
+    const EXTERNAL_ROUND_CONSTANTS: [[u64; SPONGE_WIDTH]; N_FULL_ROUNDS_TOTAL];
+
+    /// The round constants of the partial rounds, which are only added to the first element.
+    const INTERNAL_ROUND_CONSTANTS: [u64; N_PARTIAL_ROUNDS];
+
+    /// The internal matrix is `J + diag(INTERNAL_MATRIX_DIAG_M_1)`, where `J` is the all-ones
+    /// matrix.
+    const INTERNAL_MATRIX_DIAG_M_1: [u64; SPONGE_WIDTH];
+
+    /// Multiplies each chunk of four elements by `M4`, then adds to each element the sum of the
+    /// elements at the same position in all chunks. This is the circulant matrix
+    /// `circ(2 M4, M4, M4)`.
+    #[inline(always)]
+    fn external_linear_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; SPONGE_WIDTH],
+    ) {
+        for chunk in state.chunks_exact_mut(4) {
+            // The additions below compute the product by `M4` in 8 additions and 4 doublings.
+            let t0 = chunk[0] + chunk[1];
+            let t1 = chunk[2] + chunk[3];
+            let t2 = chunk[1].double() + t1;
+            let t3 = chunk[3].double() + t0;
+            let t4 = t1.double().double() + t3;
+            let t5 = t0.double().double() + t2;
+            let t6 = t3 + t5;
+            let t7 = t2 + t4;
+            chunk.copy_from_slice(&[t6, t5, t7, t4]);
+        }
+
+        let mut sums = [F::ZERO; 4];
+        for chunk in state.chunks_exact(4) {
+            for i in 0..4 {
+                sums[i] += chunk[i];
+            }
+        }
+        for (i, x) in state.iter_mut().enumerate() {
+            *x += sums[i % 4];
+        }
+    }
+
+    /// Recursive version of `external_linear_layer_field`.
+    fn external_linear_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; SPONGE_WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for chunk in state.chunks_exact_mut(4) {
+            let input = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            for (row, out) in M4.iter().zip(chunk.iter_mut()) {
+                let mut res = builder.zero_extension();
+                for (&c, x) in row.iter().zip(input) {
+                    res = builder.mul_const_add_extension(Self::from_canonical_u64(c), x, res);
+                }
+                *out = res;
+            }
+        }
+
+        let sums: [ExtensionTarget<D>; 4] = core::array::from_fn(|i| {
+            let column = state.iter().skip(i).step_by(4).copied().collect::<Vec<_>>();
+            builder.add_many_extension(column)
+        });
+        for (i, x) in state.iter_mut().enumerate() {
+            *x = builder.add_extension(*x, sums[i % 4]);
+        }
+    }
+
+    /// Same as `external_linear_layer_field`, but with the reductions delayed until the end. Each
+    /// output is a combination of the inputs whose coefficients sum to 64, so it fits in 70 bits.
+    #[inline(always)]
+    fn external_linear_layer(state: &mut [Self; SPONGE_WIDTH]) {
+        let x = state.map(|x| x.to_noncanonical_u64() as u128);
+        let mut products = [0u128; SPONGE_WIDTH];
+        for (chunk, out) in x.chunks_exact(4).zip(products.chunks_exact_mut(4)) {
+            let t0 = chunk[0] + chunk[1];
+            let t1 = chunk[2] + chunk[3];
+            let t2 = 2 * chunk[1] + t1;
+            let t3 = 2 * chunk[3] + t0;
+            let t4 = 4 * t1 + t3;
+            let t5 = 4 * t0 + t2;
+            out.copy_from_slice(&[t3 + t5, t5, t2 + t4, t4]);
+        }
+
+        let sums: [u128; 4] =
+            core::array::from_fn(|i| products[i] + products[4 + i] + products[8 + i]);
+        for (i, x) in state.iter_mut().enumerate() {
+            *x = Self::from_noncanonical_u128(products[i] + sums[i % 4]);
+        }
+    }
+
+    /// Multiplies the state by `J + diag(INTERNAL_MATRIX_DIAG_M_1)`, i.e. maps each element `x_i`
+    /// to `d_i x_i + sum_j x_j`.
+    #[inline(always)]
+    fn internal_linear_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; SPONGE_WIDTH],
+    ) {
+        let sum: F = state.iter().copied().sum();
+        for (x, &d) in state.iter_mut().zip(&Self::INTERNAL_MATRIX_DIAG_M_1) {
+            *x = x.scalar_mul(Self::from_canonical_u64(d)) + sum;
+        }
+    }
+
+    /// Same as `internal_linear_layer_field`, but with a single reduction per element.
+    #[inline(always)]
+    fn internal_linear_layer(state: &mut [Self; SPONGE_WIDTH]) {
+        let sum = state
+            .iter()
+            .map(|x| x.to_noncanonical_u64() as u128)
+            .sum::<u128>();
+        // Once reduced to 64 bits, the sum can be added to a product of two 64-bit values without
+        // overflowing.
+        let sum = Self::from_noncanonical_u128(sum).to_noncanonical_u64() as u128;
+        for (x, &d) in state.iter_mut().zip(&Self::INTERNAL_MATRIX_DIAG_M_1) {
+            *x = Self::from_noncanonical_u128(x.to_noncanonical_u64() as u128 * d as u128 + sum);
+        }
+    }
+
+    /// Recursive version of `internal_linear_layer_field`.
+    fn internal_linear_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; SPONGE_WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        let sum = builder.add_many_extension(*state);
+        for (x, &d) in state.iter_mut().zip(&Self::INTERNAL_MATRIX_DIAG_M_1) {
+            *x = builder.mul_const_add_extension(Self::from_canonical_u64(d), *x, sum);
+        }
+    }
+
+    /// Adds the round constants of the `round`th full round.
+    #[inline(always)]
+    fn external_constant_layer_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; SPONGE_WIDTH],
+        round: usize,
+    ) {
+        for (x, &c) in state.iter_mut().zip(&Self::EXTERNAL_ROUND_CONSTANTS[round]) {
+            *x += F::from_canonical_u64(c);
+        }
+    }
+
+    /// Recursive version of `external_constant_layer_field`.
+    fn external_constant_layer_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; SPONGE_WIDTH],
+        round: usize,
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        for (x, &c) in state.iter_mut().zip(&Self::EXTERNAL_ROUND_CONSTANTS[round]) {
+            let c = builder.constant_extension(Self::Extension::from_canonical_u64(c));
+            *x = builder.add_extension(*x, c);
+        }
+    }
+
+    #[inline(always)]
+    fn sbox_monomial<F: FieldExtension<D, BaseField = Self>, const D: usize>(x: F) -> F {
+        // x |--> x^7
+        let x2 = x.square();
+        let x4 = x2.square();
+        let x3 = x * x2;
+        x3 * x4
+    }
+
+    /// Recursive version of `sbox_monomial`.
+    fn sbox_monomial_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        x: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D>
+    where
+        Self: RichField + Extendable<D>,
+    {
+        // x |--> x^7
+        builder.exp_u64_extension(x, 7)
+    }
+
+    fn poseidon2(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        let mut state = input;
+        Self::external_linear_layer(&mut state);
+
+        for r in 0..HALF_N_FULL_ROUNDS {
+            Self::external_constant_layer_field(&mut state, r);
+            for x in state.iter_mut() {
+                *x = Self::sbox_monomial(*x);
+            }
+            Self::external_linear_layer(&mut state);
+        }
+
+        for &c in &Self::INTERNAL_ROUND_CONSTANTS {
+            state[0] = Self::sbox_monomial(state[0] + Self::from_canonical_u64(c));
+            Self::internal_linear_layer(&mut state);
+        }
+
+        for r in HALF_N_FULL_ROUNDS..N_FULL_ROUNDS_TOTAL {
+            Self::external_constant_layer_field(&mut state, r);
+            for x in state.iter_mut() {
+                *x = Self::sbox_monomial(*x);
+            }
+            Self::external_linear_layer(&mut state);
+        }
+
+        state
+    }
+}
+
+/// The round constants were generated with the Grain LFSR of the reference implementation, with
+/// the parameters `field = 1, sbox = 0, n = 64, t = 12, R_F = 8, R_P = 22`. The diagonal of the
+/// internal matrix is that of the reference implementation for this field and width.
+#[rustfmt::skip]
+impl Poseidon2 for GoldilocksField {
+    const EXTERNAL_ROUND_CONSTANTS: [[u64; SPONGE_WIDTH]; N_FULL_ROUNDS_TOTAL] = [
+        [
+            0x13dcf33aba214f46, 0x30b3b654a1da6d83, 0x1fc634ada6159b56, 0x937459964dc03466,
+            0xedd2ef2ca7949924, 0xede9affde0e22f68, 0x8515b9d6bac9282d, 0x6b5c07b4e9e900d8,
+            0x1ec66368838c8a08, 0x9042367d80d1fbab, 0x400283564a3c3799, 0x4a00be0466bca75e,
+        ],
+        [
+            0x7913beee58e3817f, 0xf545e88532237d90, 0x22f8cb8736042005, 0x6f04990e247a2623,
+            0xfe22e87ba37c38cd, 0xd20e32c85ffe2815, 0x117227674048fe73, 0x4e9fb7ea98a6b145,
+            0xe0866c232b8af08b, 0x00bbc77916884964, 0x7031c0fb990d7116, 0x240a9e87cf35108f,
+        ],
+        [
+            0x2e6363a5a12244b3, 0x5e1c3787d1b5011c, 0x4132660e2a196e8b, 0x3a013b648d3d4327,
+            0xf79839f49888ea43, 0xfe85658ebafe1439, 0xb6889825a14240bd, 0x578453605541382b,
+            0x4508cda8f6b63ce9, 0x9c3ef35848684c91, 0x0812bde23c87178c, 0xfe49638f7f722c14,
+        ],
+        [
+            0x8e3f688ce885cbf5, 0xb8e110acf746a87d, 0xb4b2e8973a6dabef, 0x9e714c5da3d462ec,
+            0x6438f9033d3d0c15, 0x24312f7cf1a27199, 0x23f843bb47acbf71, 0x9183f11a34be9f01,
+            0x839062fbb9d45dbf, 0x24b56e7e6c2e43fa, 0xe1683da61c962a72, 0xa95c63971a19bfa7,
+        ],
+        [
+            0x9271d450fc9b4117, 0xcffeea06b6e3aac1, 0xfa4a44c748d1cd8e, 0xe64db01ba569b469,
+            0xd31005160e4045fe, 0x39e0fa013e025f79, 0xe243be574196a956, 0x205b2a681e3d2642,
+            0x79cae5ad93486bab, 0xfdf567844e32c295, 0x331679589bfb7189, 0xaf06ee32297b89c2,
+        ],
+        [
+            0xa6bcae311e498491, 0x9d16f52c96ac8b3e, 0x48a674b59393fa35, 0x0f9e65da3fde3796,
+            0x1e098310fc84578c, 0x559ae5fab1ae8dad, 0x56bd4d624078881d, 0xfd8bbbf8fbe817b5,
+            0x82d30695c44df534, 0x3ec0a97bc41127c5, 0x1eb8b64adaa22078, 0x82c45e418d60c983,
+        ],
+        [
+            0xb092280f484d55bf, 0xcd317c9537697939, 0xd3be2e352feb79f3, 0xca6d866539a390e5,
+            0xb5efb1a494e55ee6, 0xfa9013ac89756e9e, 0xaeb88efd1e981242, 0x13ee477cdab6e0dc,
+            0xce7df902c40da2d3, 0xf3fbaf0d4e6f5f34, 0xf96354ada6785f38, 0x13b5692812406886,
+        ],
+        [
+            0xf03cae030a0f4418, 0x7d3172887aa98e1a, 0x8a2c2644f2faf7b9, 0x80d721abee696d00,
+            0x27c8b903a4d68267, 0xaf0b7b12f90291b8, 0x00acd08cfdff3817, 0x4659ee496c634328,
+            0xf5b25c10730dbff1, 0xdde3a153297329c2, 0x50c0b70d6910a44b, 0x23c7426af725a6a0,
+        ],
+    ];
+
+    const INTERNAL_ROUND_CONSTANTS: [u64; N_PARTIAL_ROUNDS] = [
+        0x4adf842aa75d4316, 0x3f36b9fe72ad4e5f, 0x9717f025e7daf6a5, 0xac4bb7c627cf7c13,
+        0x047d766678f13875, 0xbfce13201f3f7e6b, 0x70971fc4e6f85305, 0xe2a6e06e61fcec9c,
+        0xdf58134c134491c2, 0x1c4bd1e816050a7e, 0xf8a6cd02e92cdb0b, 0x4c0f5fc6c0dda3d1,
+        0x0a4a11d794be40a2, 0x6d3fbd3b4a9f1de6, 0x0d0c371c5b35b850, 0x2cff3000be1fcd0a,
+        0xd5ef60d6f76a42fa, 0x942069f5d6eece7e, 0x8b62a5551e9a9797, 0x4f88cdcdfb791921,
+        0xab21b42e0f642307, 0x587fa39990b62800,
+    ];
+
+    const INTERNAL_MATRIX_DIAG_M_1: [u64; SPONGE_WIDTH] = [
+        0xc3b6c08e23ba9300, 0xd84b5de94a324fb6, 0x0d0c371c5b35b84f, 0x7964f570e7188037,
+        0x5daf18bbd996604b, 0x6743bc47b9595257, 0x5528b9362c59bb70, 0xac45e25b7127b68b,
+        0xa2077d7dfbb606b5, 0xf3faac6faee378ae, 0x0c6388b51545e883, 0xd27dbb6944917b60,
+    ];
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Poseidon2Permutation<T> {
+    state: [T; SPONGE_WIDTH],
+}
+
+impl<T: Eq> Eq for Poseidon2Permutation<T> {}
+
+impl<T> AsRef<[T]> for Poseidon2Permutation<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.state
+    }
+}
+
+impl<T> AsMut<[T]> for Poseidon2Permutation<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        &mut self.state
+    }
+}
+
+trait Permuter2: Sized {
+    fn permute(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH];
+}
+
+impl<F: Poseidon2> Permuter2 for F {
+    fn permute(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        <F as Poseidon2>::poseidon2(input)
+    }
+}
+
+impl Permuter2 for Target {
+    fn permute(_input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        panic!("Call `permute_swapped()` instead of `permute()`");
+    }
+}
+
+impl<T: Copy + Debug + Default + Eq + Permuter2 + Send + Sync> PlonkyPermutation<T>
+    for Poseidon2Permutation<T>
+{
+    const RATE: usize = SPONGE_RATE;
+    const WIDTH: usize = SPONGE_WIDTH;
+
+    fn permute(&mut self) {
+        self.state = T::permute(self.state);
+    }
+}
+
+/// Poseidon2 hash function, with the same sponge parameters as [`crate::hash::poseidon::PoseidonHash`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Poseidon2Hash;
+impl<F: RichField + Poseidon2> Hasher<F> for Poseidon2Hash {
+    const HASH_SIZE: usize = 4 * 8;
+    type Hash = HashOut<F>;
+    type Permutation = Poseidon2Permutation<F>;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        hash_n_to_hash_no_pad::<F, Self::Permutation>(input)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        compress::<F, Self::Permutation>(left, right)
+    }
+}
+
+impl<F: RichField + Poseidon2> AlgebraicHasher<F> for Poseidon2Hash {
+    type AlgebraicPermutation = Poseidon2Permutation<Target>;
+
+    fn permute_swapped<const D: usize>(
+        inputs: Self::AlgebraicPermutation,
+        swap: BoolTarget,
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> Self::AlgebraicPermutation
+    where
+        F: RichField + Extendable<D>,
+    {
+        let gate_type = Poseidon2Gate::<F, D>::new();
+        let gate = builder.add_gate(gate_type, vec![]);
+
+        let swap_wire = Poseidon2Gate::<F, D>::WIRE_SWAP;
+        let swap_wire = Target::wire(gate, swap_wire);
+        builder.connect(swap.target, swap_wire);
+
+        // Route input wires.
+        let inputs = inputs.as_ref();
+        for i in 0..SPONGE_WIDTH {
+            let in_wire = Poseidon2Gate::<F, D>::wire_input(i);
+            let in_wire = Target::wire(gate, in_wire);
+            builder.connect(inputs[i], in_wire);
+        }
+
+        // Collect output wires.
+        Self::AlgebraicPermutation::new(
+            (0..SPONGE_WIDTH).map(|i| Target::wire(gate, Poseidon2Gate::<F, D>::wire_output(i))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        // Computed with an independent implementation of the permutation, from the same constants.
+        #[rustfmt::skip]
+        let test_vectors: [([u64; SPONGE_WIDTH], [u64; SPONGE_WIDTH]); 4] = [
+            ([0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+              0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+              0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, ],
+             [0x9082fefdb7d0e906, 0xcca894d4e0fade75, 0x144c2109b8ae9466, 0x9a7c1e7947386ca4,
+              0x18a34487b04ea9fa, 0xafa608f4798a455b, 0x5481c70415d0b240, 0x866957cf2556f1cc,
+              0x00bc1fe70daac703, 0xc89d6d913d3035af, 0x451bcf82570cf5bc, 0x9695d080b8dceea6, ]),
+            ([0x0000000000000000, 0x0000000000000001, 0x0000000000000002, 0x0000000000000003,
+              0x0000000000000004, 0x0000000000000005, 0x0000000000000006, 0x0000000000000007,
+              0x0000000000000008, 0x0000000000000009, 0x000000000000000a, 0x000000000000000b, ],
+             [0x7dad28df76f00a0c, 0x7eeee15a719b2c83, 0x9cbeeaab2a21779f, 0x55d01e3390da190f,
+              0xc1b07fdd2b1f52d5, 0x0eddb45a904bb791, 0x5d4a5a83ec7044eb, 0xea94a554cd4cb8da,
+              0x05b264ec4aed4aa6, 0x3f142c01fb5938d9, 0x5b7ce4cb9689b006, 0x5a899329ab43d2bb, ]),
+            ([0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000,
+              0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000,
+              0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, ],
+             [0x6e49279cea5bcb8f, 0x7fca539385517a1d, 0x941fc5ca1c98f9a4, 0x9935ce75c26172d9,
+              0x417d12da82489e20, 0xe22f95baa49e3dfe, 0xe633ca5533c2c284, 0xf97e6b8df8373153,
+              0xf419ae8956cc080a, 0x3f0cedd5768f3094, 0x1b3d406cd5c9dc78, 0xf42209e1ccbd21c6, ]),
+            ([0x8ccbbbea4fe5d2b7, 0xc2af59ee9ec49970, 0x90f7e1a9e658446a, 0xdcc0630a3ab8b1b8,
+              0x7ff8256bca20588c, 0x5d99a7ca0c44ecfb, 0x48452b17a70fbee3, 0xeb09d654690b6c88,
+              0x4a55d3a39c676a88, 0xc0407a38d2285139, 0xa234bac9356386d1, 0xe1633f2bad98a52f, ],
+             [0x5585dee532e57824, 0x6de2e5cf6ec228a6, 0xd7bb5c9e44ade939, 0x919d8b3f2a0c06a1,
+              0x6ee393ae079482e9, 0xa7ba3b183dc6b620, 0x84319575c1a3addf, 0xc26a9ac2b57ed1be,
+              0x58c92382eade5ed2, 0x2ede985ba2a76221, 0x2b3ac73a435b95be, 0x6797c2d21e3416d6, ]),
+        ];
+
+        for (input, expected) in test_vectors {
+            let input = input.map(GoldilocksField::from_canonical_u64);
+            let output = GoldilocksField::poseidon2(input);
+            assert_eq!(output, expected.map(GoldilocksField::from_canonical_u64));
+        }
+    }
+}
@@ -307,15 +307,37 @@ impl Poseidon for GoldilocksField {
 // MDS layer helper methods
 // The following code has been adapted from winterfell/crypto/src/hash/mds/mds_f64_12x12.rs
 // located at https://github.com/facebook/winterfell.
-#[cfg(not(all(target_arch = "aarch64", target_feature = "neon")))]
-mod poseidon12_mds {
+pub(crate) mod poseidon12_mds {
+    #[cfg(not(all(target_arch = "aarch64", target_feature = "neon")))]
     const MDS_FREQ_BLOCK_ONE: [i64; 3] = [16, 32, 16];
+    #[cfg(not(all(target_arch = "aarch64", target_feature = "neon")))]
     const MDS_FREQ_BLOCK_TWO: [(i64, i64); 3] = [(2, -1), (-4, 1), (16, 1)];
+    #[cfg(not(all(target_arch = "aarch64", target_feature = "neon")))]
     const MDS_FREQ_BLOCK_THREE: [i64; 3] = [-1, -8, 2];
 
     /// Split 3 x 4 FFT-based MDS vector-multiplication with the Poseidon circulant MDS matrix.
+    #[cfg(not(all(target_arch = "aarch64", target_feature = "neon")))]
     #[inline(always)]
     pub(crate) const fn mds_multiply_freq(state: [u64; 12]) -> [u64; 12] {
+        circulant_multiply_freq(
+            state,
+            MDS_FREQ_BLOCK_ONE,
+            MDS_FREQ_BLOCK_TWO,
+            MDS_FREQ_BLOCK_THREE,
+        )
+    }
+
+    /// Split 3 x 4 FFT-based vector-multiplication with a 12x12 circulant matrix, given by its
+    /// blocks in the frequency domain, which include the scaling of the inverse FFTs. The entries
+    /// of `state` must fit in 32 bits, and those of the matrix must be small enough for the results
+    /// to fit in 64 bits.
+    #[inline(always)]
+    pub(crate) const fn circulant_multiply_freq(
+        state: [u64; 12],
+        block_one: [i64; 3],
+        block_two: [(i64, i64); 3],
+        block_three: [i64; 3],
+    ) -> [u64; 12] {
         let [s0, s1, s2, s3, s4, s5, s6, s7, s8, s9, s10, s11] = state;
 
         let (u0, u1, u2) = fft4_real([s0, s3, s6, s9]);
@@ -330,9 +352,9 @@ mod poseidon12_mds {
         // The expressions in the aforementioned functions are the result of explicit computations
         // combined with the Karatsuba trick for the multiplication of complex numbers.
 
-        let [v0, v4, v8] = block1([u0, u4, u8], MDS_FREQ_BLOCK_ONE);
-        let [v1, v5, v9] = block2([u1, u5, u9], MDS_FREQ_BLOCK_TWO);
-        let [v2, v6, v10] = block3([u2, u6, u10], MDS_FREQ_BLOCK_THREE);
+        let [v0, v4, v8] = block1([u0, u4, u8], block_one);
+        let [v1, v5, v9] = block2([u1, u5, u9], block_two);
+        let [v2, v6, v10] = block3([u2, u6, u10], block_three);
         // The 4th block is not computed as it is similar to the 2nd one, up to complex conjugation.
 
         let [s0, s3, s6, s9] = ifft4_real_unreduced((v0, v1, v2));
@@ -449,7 +471,9 @@ mod tests {
 
     use crate::field::goldilocks_field::GoldilocksField as F;
     use crate::field::types::{Field, PrimeField64};
-    use crate::hash::poseidon::test_helpers::{check_consistency, check_test_vectors};
+    use crate::hash::poseidon::test_helpers::{
+        check_consistency, check_packed_hash, check_test_vectors,
+    };
 
     #[test]
     fn test_vectors() {
@@ -493,4 +517,9 @@ mod tests {
     fn consistency() {
         check_consistency::<F>();
     }
+
+    #[test]
+    fn packed_hash() {
+        check_packed_hash::<F>();
+    }
 }
@@ -0,0 +1,156 @@
+//! SHA-256-based hashing, for Merkle trees and challengers of proofs which are only verified
+//! natively, in deployments which require FIPS-approved primitives for commitments. Like Keccak and
+//! Blake3, SHA-256 is very expensive to evaluate inside a circuit, so it should not be used for
+//! proofs which are meant to be verified recursively.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use sha2::{Digest, Sha256};
+
+use crate::hash::hash_types::{BytesHash, RichField};
+use crate::hash::hashing::PlonkyPermutation;
+use crate::plonk::config::Hasher;
+use crate::util::serialization::Write;
+
+pub const SPONGE_RATE: usize = 8;
+pub const SPONGE_CAPACITY: usize = 4;
+pub const SPONGE_WIDTH: usize = SPONGE_RATE + SPONGE_CAPACITY;
+
+/// SHA-256 pseudo-permutation (not necessarily one-to-one) used in the challenger.
+/// A state `input: [F; 12]` is sent to the field elements parsed from
+/// `H(input || 0) || H(input || 1) || ...`, where `H` is SHA-256 and the counters are encoded as
+/// little-endian `u32`s.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Sha256Permutation<F: RichField> {
+    state: [F; SPONGE_WIDTH],
+}
+
+impl<F: RichField> Eq for Sha256Permutation<F> {}
+
+impl<F: RichField> AsRef<[F]> for Sha256Permutation<F> {
+    fn as_ref(&self) -> &[F] {
+        &self.state
+    }
+}
+
+impl<F: RichField> AsMut<[F]> for Sha256Permutation<F> {
+    fn as_mut(&mut self) -> &mut [F] {
+        &mut self.state
+    }
+}
+
+impl<F: RichField> PlonkyPermutation<F> for Sha256Permutation<F> {
+    const RATE: usize = SPONGE_RATE;
+    const WIDTH: usize = SPONGE_WIDTH;
+
+    fn permute(&mut self) {
+        let mut hasher = Sha256::new();
+        for x in &self.state {
+            hasher.update(x.to_canonical_u64().to_le_bytes());
+        }
+
+        // Parse field elements from the output stream, using rejection sampling such that words
+        // that don't fit in F are ignored.
+        let mut i = 0;
+        let mut counter = 0u32;
+        while i < SPONGE_WIDTH {
+            let output = hasher
+                .clone()
+                .chain_update(counter.to_le_bytes())
+                .finalize();
+            counter += 1;
+            for word in output.chunks_exact(size_of::<u64>()) {
+                let word = u64::from_le_bytes(word.try_into().unwrap());
+                if i < SPONGE_WIDTH && word < F::ORDER {
+                    self.state[i] = F::from_canonical_u64(word);
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+/// SHA-256 hash function, truncated to `N` bytes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Sha256Hash<const N: usize>;
+impl<F: RichField, const N: usize> Hasher<F> for Sha256Hash<N> {
+    const HASH_SIZE: usize = N;
+    type Hash = BytesHash<N>;
+    type Permutation = Sha256Permutation<F>;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        let mut buffer = Vec::with_capacity(input.len());
+        buffer.write_field_vec(input).unwrap();
+        let mut arr = [0; N];
+        arr.copy_from_slice(&Sha256::digest(&buffer)[..N]);
+        BytesHash(arr)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        let mut hasher = Sha256::new();
+        hasher.update(left.0);
+        hasher.update(right.0);
+        let mut arr = [0; N];
+        arr.copy_from_slice(&hasher.finalize()[..N]);
+        BytesHash(arr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::{format, string::String};
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, Sha256GoldilocksConfig};
+
+    #[test]
+    fn test_sha256_digests() {
+        type F = GoldilocksField;
+
+        // SHA-256 of the empty message.
+        let empty = <Sha256Hash<32> as Hasher<F>>::hash_no_pad(&[]);
+        let expected = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(hex(&empty.0), expected);
+
+        // `two_to_one` hashes the concatenation of both digests.
+        let left = BytesHash::<32>([0x61; 32]);
+        let right = BytesHash::<32>([0x62; 32]);
+        let mut message = [0x61; 64];
+        message[32..].fill(0x62);
+        let digest = <Sha256Hash<32> as Hasher<F>>::two_to_one(left, right);
+        assert_eq!(digest.0[..], Sha256::digest(message)[..]);
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_sha256_proof() -> Result<()> {
+        const D: usize = 2;
+        type C = Sha256GoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 5);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO)?;
+        let proof = data.prove(pw)?;
+        assert_eq!(proof.public_inputs, [F::from_canonical_u64(32)]);
+        data.verify(proof)
+    }
+}
@@ -0,0 +1,184 @@
+//! Incremental native sponge, for computing commitments off-circuit which are opened in-circuit
+//! with [`CircuitBuilder::hash_pad_variable_len`].
+//!
+//! Messages are padded with the `pad10*1` rule, so the digest of the absorbed elements is the same
+//! as [`Hasher::hash_pad`] gives, and as the in-circuit gadget gives for any length.
+//!
+//! [`CircuitBuilder::hash_pad_variable_len`]: crate::plonk::circuit_builder::CircuitBuilder::hash_pad_variable_len
+//! [`Hasher::hash_pad`]: crate::plonk::config::Hasher::hash_pad
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use crate::hash::hash_types::{HashOut, RichField, NUM_HASH_OUT_ELTS};
+use crate::hash::hashing::PlonkyPermutation;
+use crate::hash::poseidon::PoseidonPermutation;
+
+/// The number of bytes packed into each field element by [`Sponge::absorb_bytes`]. Seven bytes
+/// always fit in a 64-bit field element.
+pub const BYTES_PER_ELEMENT: usize = 7;
+
+/// A sponge in overwrite mode over the permutation `P`, whose input is padded with the `pad10*1`
+/// rule when it is finalized.
+#[derive(Clone, Debug)]
+pub struct Sponge<F: RichField, P: PlonkyPermutation<F>> {
+    perm: P,
+    /// Absorbed elements which don't fill a block yet.
+    pending: Vec<F>,
+    _phantom: PhantomData<F>,
+}
+
+/// A [`Sponge`] over Poseidon, matching [`crate::hash::poseidon::PoseidonHash`].
+pub type PoseidonSponge<F> = Sponge<F, PoseidonPermutation<F>>;
+
+impl<F: RichField, P: PlonkyPermutation<F>> Default for Sponge<F, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: RichField, P: PlonkyPermutation<F>> Sponge<F, P> {
+    pub fn new() -> Self {
+        Self {
+            perm: P::new(core::iter::repeat(F::ZERO)),
+            pending: Vec::with_capacity(P::RATE),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Absorbs a single field element.
+    pub fn absorb(&mut self, element: F) {
+        self.pending.push(element);
+        if self.pending.len() == P::RATE {
+            self.perm.set_from_slice(&self.pending, 0);
+            self.perm.permute();
+            self.pending.clear();
+        }
+    }
+
+    /// Absorbs field elements.
+    pub fn absorb_elements(&mut self, elements: &[F]) {
+        for &element in elements {
+            self.absorb(element);
+        }
+    }
+
+    /// Absorbs bytes, packed into field elements of `BYTES_PER_ELEMENT` little-endian bytes each,
+    /// the last of which is zero-extended. Bytes absorbed by different calls are packed separately,
+    /// and byte strings which only differ by trailing zeros may be packed identically, so callers
+    /// absorbing byte strings of varying length should also absorb their lengths.
+    pub fn absorb_bytes(&mut self, bytes: &[u8]) {
+        self.absorb_elements(&pack_bytes(bytes));
+    }
+
+    /// Pads the absorbed elements with the `pad10*1` rule and squeezes `num_outputs` elements.
+    pub fn squeeze(mut self, num_outputs: usize) -> Vec<F> {
+        self.absorb(F::ONE);
+        while self.pending.len() != P::RATE - 1 {
+            self.absorb(F::ZERO);
+        }
+        self.absorb(F::ONE);
+
+        let mut outputs = Vec::with_capacity(num_outputs);
+        loop {
+            for &item in self.perm.squeeze() {
+                outputs.push(item);
+                if outputs.len() == num_outputs {
+                    return outputs;
+                }
+            }
+            self.perm.permute();
+        }
+    }
+
+    /// Pads the absorbed elements with the `pad10*1` rule and returns their digest.
+    pub fn finalize(self) -> HashOut<F> {
+        HashOut::from_vec(self.squeeze(NUM_HASH_OUT_ELTS))
+    }
+}
+
+/// Packs bytes into field elements of `BYTES_PER_ELEMENT` little-endian bytes each, the last of
+/// which is zero-extended.
+pub(crate) fn pack_bytes<F: RichField>(bytes: &[u8]) -> Vec<F> {
+    bytes
+        .chunks(BYTES_PER_ELEMENT)
+        .map(|chunk| {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            F::from_canonical_u64(u64::from_le_bytes(word))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::{Field, Sample};
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, Hasher, PoseidonGoldilocksConfig};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_sponge_matches_hash_pad() {
+        let elements = F::rand_vec(30);
+        for len in 0..elements.len() {
+            let mut sponge = PoseidonSponge::<F>::new();
+            let (first, second) = elements[..len].split_at(len / 3);
+            sponge.absorb_elements(first);
+            sponge.absorb_elements(second);
+            assert_eq!(sponge.finalize(), PoseidonHash::hash_pad(&elements[..len]));
+        }
+    }
+
+    #[test]
+    fn test_sponge_bytes() {
+        let bytes = (1..=10).collect::<Vec<u8>>();
+        let mut sponge = PoseidonSponge::<F>::new();
+        sponge.absorb_bytes(&bytes);
+        let expected = [0x07060504030201, 0x0a0908].map(F::from_canonical_u64);
+        assert_eq!(sponge.finalize(), PoseidonHash::hash_pad(&expected));
+    }
+
+    #[test]
+    fn test_hash_pad_variable_len() -> Result<()> {
+        const MAX_LEN: usize = 17;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let inputs = builder.add_virtual_targets(MAX_LEN);
+        let len = builder.add_virtual_target();
+        let hash = builder.hash_pad_variable_len::<PoseidonHash>(&inputs, len);
+        builder.register_public_inputs(&hash.elements);
+        let data = builder.build::<C>();
+
+        let elements = F::rand_vec(MAX_LEN);
+        // Lengths at which the padding takes one or two elements, or a whole block.
+        for n in [0, 1, 6, 7, 8, 15, 16, MAX_LEN] {
+            let mut sponge = PoseidonSponge::<F>::new();
+            sponge.absorb_elements(&elements[..n]);
+
+            let mut pw = PartialWitness::new();
+            pw.set_target_arr(&inputs, &elements)?;
+            pw.set_target(len, F::from_canonical_usize(n))?;
+            let proof = data.prove(pw)?;
+            assert_eq!(proof.public_inputs, sponge.finalize().elements);
+            data.verify(proof)?;
+        }
+
+        // A length above the number of inputs can't be proven.
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&inputs, &elements)?;
+        pw.set_target(len, F::from_canonical_usize(MAX_LEN + 1))?;
+        assert!(data.prove(pw).is_err());
+
+        Ok(())
+    }
+}
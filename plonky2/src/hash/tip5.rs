@@ -0,0 +1,438 @@
+//! Implementation of the Tip5 permutation, as described in <https://eprint.iacr.org/2023/107.pdf>
+//!
+//! Tip5 has a state of 16 elements, 10 of which are the rate. Each round applies an S-box layer,
+//! which maps the first four elements through a byte-wise lookup table and raises the others to
+//! the 7th power, then multiplies the state by a circulant MDS matrix and adds round constants.
+//!
+//! The permutation is the one of Triton VM and Neptune. The sponge follows the other hashers of this
+//! crate, so that digests are four elements long, while Triton VM uses five; digests computed here
+//! are thus not the same as theirs.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+use core::fmt::Debug;
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::PrimeField64;
+use crate::gates::tip5::Tip5Gate;
+use crate::hash::hash_types::{HashOut, RichField};
+use crate::hash::hashing::{compress, hash_n_to_hash_no_pad, PlonkyPermutation};
+use crate::iop::ext_target::ExtensionTarget;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::config::{AlgebraicHasher, Hasher};
+
+pub const SPONGE_RATE: usize = 10;
+pub const SPONGE_CAPACITY: usize = 6;
+pub const SPONGE_WIDTH: usize = SPONGE_RATE + SPONGE_CAPACITY;
+
+pub const N_ROUNDS: usize = 5;
+
+/// The number of state elements going through the lookup table in each round.
+pub const N_SPLIT_AND_LOOKUP: usize = 4;
+
+/// The first column of the circulant MDS matrix.
+const MDS_FIRST_COLUMN: [u64; SPONGE_WIDTH] = [
+    61402, 1108, 28750, 33823, 7454, 43244, 53865, 12034, 56951, 27521, 41351, 40901, 12021, 59689,
+    26798, 17845,
+];
+
+/// The MDS matrix, whose `i`th column is `MDS_FIRST_COLUMN` rotated down by `i`.
+const MDS_MATRIX: [[u64; SPONGE_WIDTH]; SPONGE_WIDTH] = {
+    let mut matrix = [[0; SPONGE_WIDTH]; SPONGE_WIDTH];
+    let mut i = 0;
+    while i < SPONGE_WIDTH {
+        let mut j = 0;
+        while j < SPONGE_WIDTH {
+            matrix[i][j] = MDS_FIRST_COLUMN[(i + SPONGE_WIDTH - j) % SPONGE_WIDTH];
+            j += 1;
+        }
+        i += 1;
+    }
+    matrix
+};
+
+/// The lookup table applied to each byte, which is the offset Fermat cube map
+/// `x |--> (x + 1)^3 - 1 mod 257`. It maps `0x00` and `0xff` to themselves.
+const LOOKUP_TABLE: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let x = i as u64 + 1;
+        table[i] = ((x * x * x + 256) % 257) as u8;
+        i += 1;
+    }
+    table
+};
+
+/// Applies `LOOKUP_TABLE` to each byte of `x`. The result is canonical whenever `x` is a canonical
+/// Goldilocks element.
+#[inline(always)]
+pub const fn lookup_u64(x: u64) -> u64 {
+    let mut bytes = x.to_le_bytes();
+    let mut i = 0;
+    while i < 8 {
+        bytes[i] = LOOKUP_TABLE[bytes[i] as usize];
+        i += 1;
+    }
+    u64::from_le_bytes(bytes)
+}
+
+/// `LOOKUP_TABLE` applied to each byte of a 16-bit limb, which is the lookup table used to compute
+/// the S-box layer in circuits.
+pub(crate) fn lookup_u16(limb: u16) -> u16 {
+    lookup_u64(limb as u64) as u16
+}
+
+pub trait Tip5: PrimeField64 {
+    const ROUND_CONSTANTS: [[u64; SPONGE_WIDTH]; N_ROUNDS];
+
+    /// `2^64` modulo the field order. The lookup table is applied to the bytes of the Montgomery
+    /// form `x * 2^64` of each element, as in the reference implementation.
+    const MONTGOMERY_R: u64;
+
+    /// The inverse of `MONTGOMERY_R`.
+    const MONTGOMERY_R_INV: u64;
+
+    /// Applies the lookup table to each byte of the Montgomery form of `x`.
+    #[inline(always)]
+    fn split_and_lookup(x: Self) -> Self {
+        let montgomery = x * Self::from_canonical_u64(Self::MONTGOMERY_R);
+        let looked_up = lookup_u64(montgomery.to_canonical_u64());
+        Self::from_canonical_u64(looked_up) * Self::from_canonical_u64(Self::MONTGOMERY_R_INV)
+    }
+
+    #[inline(always)]
+    fn power_map_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(x: F) -> F {
+        // x |--> x^7
+        let x2 = x.square();
+        let x4 = x2.square();
+        let x3 = x * x2;
+        x3 * x4
+    }
+
+    /// Recursive version of `power_map_field`.
+    fn power_map_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        x: ExtensionTarget<D>,
+    ) -> ExtensionTarget<D>
+    where
+        Self: RichField + Extendable<D>,
+    {
+        // x |--> x^7
+        builder.exp_u64_extension(x, 7)
+    }
+
+    /// Multiplies the state by the circulant MDS matrix.
+    #[inline(always)]
+    fn mds_multiply_field<F: FieldExtension<D, BaseField = Self>, const D: usize>(
+        state: &mut [F; SPONGE_WIDTH],
+    ) {
+        let mut result = [F::ZERO; SPONGE_WIDTH];
+        for (r, row) in result.iter_mut().zip(&MDS_MATRIX) {
+            for (&x, &c) in state.iter().zip(row) {
+                *r += x * F::from_canonical_u64(c);
+            }
+        }
+        *state = result;
+    }
+
+    /// Same as `mds_multiply_field` for the base field. As the coefficients fit in 16 bits, the
+    /// products by the low and high halves of each element are accumulated separately in `u64`s,
+    /// and only reduced once per element.
+    #[inline(always)]
+    fn mds_multiply(state: &mut [Self; SPONGE_WIDTH]) {
+        let lo = state.map(|x| x.to_noncanonical_u64() & 0xffff_ffff);
+        let hi = state.map(|x| x.to_noncanonical_u64() >> 32);
+        for (r, row) in state.iter_mut().zip(&MDS_MATRIX) {
+            // Each sum is below `16 * 2^16 * 2^32 = 2^52`.
+            let mut acc_lo = 0u64;
+            let mut acc_hi = 0u64;
+            for j in 0..SPONGE_WIDTH {
+                acc_lo += lo[j] * row[j];
+                acc_hi += hi[j] * row[j];
+            }
+            let acc = ((acc_hi as u128) << 32) + acc_lo as u128;
+            *r = Self::from_noncanonical_u96((acc as u64, (acc >> 64) as u32));
+        }
+    }
+
+    /// Recursive version of `mds_multiply_field`.
+    fn mds_multiply_circuit<const D: usize>(
+        builder: &mut CircuitBuilder<Self, D>,
+        state: &mut [ExtensionTarget<D>; SPONGE_WIDTH],
+    ) where
+        Self: RichField + Extendable<D>,
+    {
+        let mut result = [builder.zero_extension(); SPONGE_WIDTH];
+        for (r, row) in result.iter_mut().zip(&MDS_MATRIX) {
+            for (&x, &c) in state.iter().zip(row) {
+                *r = builder.mul_const_add_extension(Self::from_canonical_u64(c), x, *r);
+            }
+        }
+        *state = result;
+    }
+
+    fn tip5(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        let mut state = input;
+        for round_constants in &Self::ROUND_CONSTANTS {
+            for x in state.iter_mut().take(N_SPLIT_AND_LOOKUP) {
+                *x = Self::split_and_lookup(*x);
+            }
+            for x in state.iter_mut().skip(N_SPLIT_AND_LOOKUP) {
+                *x = Self::power_map_field(*x);
+            }
+            Self::mds_multiply(&mut state);
+            for (x, &c) in state.iter_mut().zip(round_constants) {
+                *x += Self::from_canonical_u64(c);
+            }
+        }
+        state
+    }
+}
+
+/// The round constants are those of the reference implementation, which derives them with BLAKE3.
+#[rustfmt::skip]
+impl Tip5 for GoldilocksField {
+    const ROUND_CONSTANTS: [[u64; SPONGE_WIDTH]; N_ROUNDS] = [
+        [
+            0xbd2a3deb61ab60de, 0xea7df21ad9547ed2, 0x900b3677a1de063f, 0x1b46887e876c8677,
+            0xd364d977889cfb97, 0xdc8dfac843699f02, 0x375c405d7190db58, 0x27924006d2b0d4b1,
+            0x78dd1172d483cd38, 0x3346c66244882a56, 0xb0249b279f498aa5, 0x94cd51be79338d4d,
+            0xb0e0dc7052c5b218, 0xf8dcc4d248adad95, 0x68e3c635fec868b7, 0xd7d06b3ffb6b0d8c,
+        ],
+        [
+            0xf3500dea20ef032a, 0x4865bf175bba5803, 0xd5f7fe3027287a27, 0xa57333f44e193412,
+            0x8726e153a977eae2, 0x3014a98463fc191b, 0xba145461af39b212, 0x03ab70105933202f,
+            0x3d90b7eebfcf71e5, 0x386322b1cc520bfd, 0x27c2c8daf774f675, 0x4fcb83f50309bc6a,
+            0x5e6d5ce8275f3cb3, 0xecc2f6592c8f905c, 0x837f532461e609b4, 0xb2b1f6b95c92c93c,
+        ],
+        [
+            0xc0027af556411dc1, 0x16e18c885fc2a26c, 0x8880ef183d9f2bf3, 0xb2930bdb5ca88c45,
+            0x9c2ec8322e1c1553, 0xe5b05eaf3220a674, 0xa49cc6ae4b861c4e, 0x11708e0aeb86ebd7,
+            0xc09de92bbc3902e0, 0x929b3c79516bcbc1, 0xe006e5bf738f27d1, 0x2d9e1ec0eac8ea38,
+            0x0984d8d94bf937c5, 0x4959273c220e6747, 0xfe1d934207e796fa, 0x2b9b9298f2f6dd73,
+        ],
+        [
+            0x07a1f5a67d6e3a41, 0x4407593ee73743d9, 0x9f054720ef802e59, 0x78d4b711336e6aa6,
+            0xadc638aef3c8b228, 0xa4d6d3e86afb2114, 0x9d4808e725531968, 0x369804df3866d0ef,
+            0xe6dbd9a9d2215024, 0x8ed22ca212ee85b2, 0x397bb882fcd23eb6, 0xeb8f8786d7277531,
+            0x9999d4cdaff543b5, 0xf382a61217f192d6, 0x49c37260b026adc1, 0x3ff8918ce35c1019,
+        ],
+        [
+            0x2e7df8b76080bd07, 0xf5dbac250b8a28b9, 0x853c3727ae9da4cc, 0xb2f1f5f3d9e5a26d,
+            0x3fce22012d337847, 0x6b5a3e6db7eee347, 0x171582cd59dde50d, 0xc0c0b3095ee62a8a,
+            0x665b25c6f6a203d2, 0x3099aed93b6ae69f, 0x801df6092be69c38, 0x8066ad0cdfff43cd,
+            0x8af9d44a5f4fdc6b, 0xd80219cd97c0d762, 0x10c9ceba14148ebb, 0x539bd4c3f2f24474,
+        ],
+    ];
+
+    const MONTGOMERY_R: u64 = 0xffffffff;
+    const MONTGOMERY_R_INV: u64 = 0xfffffffe00000001;
+}
+
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Tip5Permutation<T> {
+    state: [T; SPONGE_WIDTH],
+}
+
+impl<T: Eq> Eq for Tip5Permutation<T> {}
+
+impl<T> AsRef<[T]> for Tip5Permutation<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.state
+    }
+}
+
+impl<T> AsMut<[T]> for Tip5Permutation<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        &mut self.state
+    }
+}
+
+trait Tip5Permuter: Sized {
+    fn permute(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH];
+}
+
+impl<F: Tip5> Tip5Permuter for F {
+    fn permute(input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        <F as Tip5>::tip5(input)
+    }
+}
+
+impl Tip5Permuter for Target {
+    fn permute(_input: [Self; SPONGE_WIDTH]) -> [Self; SPONGE_WIDTH] {
+        panic!("Call `permute_swapped()` instead of `permute()`");
+    }
+}
+
+impl<T: Copy + Debug + Default + Eq + Tip5Permuter + Send + Sync> PlonkyPermutation<T>
+    for Tip5Permutation<T>
+{
+    const RATE: usize = SPONGE_RATE;
+    const WIDTH: usize = SPONGE_WIDTH;
+
+    fn permute(&mut self) {
+        self.state = T::permute(self.state);
+    }
+}
+
+/// Tip5 hash function, used as a sponge in overwrite mode with a rate of 10 elements and four-element
+/// digests.
+///
+/// In circuits, each permutation takes one [`Tip5Gate`] per round, and a lookup in a table of size
+/// `2^16` for each 16-bit limb going through the lookup table.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Tip5Hash;
+impl<F: RichField + Tip5> Hasher<F> for Tip5Hash {
+    const HASH_SIZE: usize = 4 * 8;
+    type Hash = HashOut<F>;
+    type Permutation = Tip5Permutation<F>;
+
+    fn hash_no_pad(input: &[F]) -> Self::Hash {
+        hash_n_to_hash_no_pad::<F, Self::Permutation>(input)
+    }
+
+    fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash {
+        compress::<F, Self::Permutation>(left, right)
+    }
+}
+
+impl<F: RichField + Tip5> AlgebraicHasher<F> for Tip5Hash {
+    type AlgebraicPermutation = Tip5Permutation<Target>;
+
+    fn permute_swapped<const D: usize>(
+        inputs: Self::AlgebraicPermutation,
+        swap: BoolTarget,
+        builder: &mut CircuitBuilder<F, D>,
+    ) -> Self::AlgebraicPermutation
+    where
+        F: RichField + Extendable<D>,
+    {
+        let lut_index = Tip5Gate::<F, D>::add_lookup_table(builder);
+        let zero = builder.zero();
+
+        let mut state: [Target; SPONGE_WIDTH] = inputs.as_ref().try_into().unwrap();
+        for round in 0..N_ROUNDS {
+            let gate_type = Tip5Gate::<F, D>::new();
+            let gate = builder.add_gate(gate_type, vec![F::from_canonical_usize(round)]);
+
+            // Only the first round swaps its inputs.
+            let swap_wire = Target::wire(gate, Tip5Gate::<F, D>::WIRE_SWAP);
+            builder.connect(if round == 0 { swap.target } else { zero }, swap_wire);
+
+            // Route input wires.
+            for i in 0..SPONGE_WIDTH {
+                let in_wire = Target::wire(gate, Tip5Gate::<F, D>::wire_input(i));
+                builder.connect(state[i], in_wire);
+            }
+
+            // Look up the image of each limb.
+            for i in 0..N_SPLIT_AND_LOOKUP {
+                for j in 0..Tip5Gate::<F, D>::NUM_LIMBS {
+                    let limb_in = Target::wire(gate, Tip5Gate::<F, D>::wire_limb_input(i, j));
+                    let limb_out = builder.add_lookup_from_index(limb_in, lut_index);
+                    let out_wire = Tip5Gate::<F, D>::wire_limb_output(i, j);
+                    builder.connect(limb_out, Target::wire(gate, out_wire));
+                }
+            }
+
+            state = core::array::from_fn(|i| Target::wire(gate, Tip5Gate::<F, D>::wire_output(i)));
+        }
+
+        Self::AlgebraicPermutation::new(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::{Field, Field64, Sample};
+
+    #[test]
+    fn test_vectors() {
+        // Computed with `Tip5::permutation` from twenty-first 0.41.
+        #[rustfmt::skip]
+        let test_vectors: [([u64; SPONGE_WIDTH], [u64; SPONGE_WIDTH]); 4] = [
+            ([0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+              0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+              0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+              0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, ],
+             [0x84054f79ab2ad21c, 0x328e294774b8c16b, 0xa525eb61d000ea21, 0x2753ec3326ce6498,
+              0xb6a8ecea7a31b066, 0x654a9e8456dc5585, 0x657232c52a7e8fc9, 0x501a20c8a2cd71b1,
+              0x7d5f2b71cf14fdc0, 0xed2fcfa368f9965f, 0xc91a99b6ef0c69cc, 0x0cc91b85f59bc59a,
+              0xccda6075ab653f60, 0x40a6cd2ccfe86a32, 0xe6c66e56f7325ee4, 0xb56ed5ee84ad50b0, ]),
+            ([0x0000000000000000, 0x0000000000000001, 0x0000000000000002, 0x0000000000000003,
+              0x0000000000000004, 0x0000000000000005, 0x0000000000000006, 0x0000000000000007,
+              0x0000000000000008, 0x0000000000000009, 0x000000000000000a, 0x000000000000000b,
+              0x000000000000000c, 0x000000000000000d, 0x000000000000000e, 0x000000000000000f, ],
+             [0xc613f392f8b302ca, 0xa9a92f49df848725, 0xfce783ebe6e88ff8, 0x43b68593bf4c37d7,
+              0x02c04d498facf46b, 0x5cf28e0b2508ec74, 0x369f1993348f55d0, 0x26993e1b70ea9316,
+              0x67bfda304d2acf8a, 0x65cb07f00526b55e, 0x11b3434d072d766e, 0x21efb8205f634d31,
+              0xebfdeb32ffcf2c1d, 0x845d32fd1d3e7bbb, 0xb04c435f33c05b2a, 0x4b55fa913190b76a, ]),
+            ([0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000,
+              0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000,
+              0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000,
+              0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, 0xffffffff00000000, ],
+             [0xb5702a3ed9f90878, 0xaed9e312334aea2a, 0xfe03763f16a915da, 0xf39ca8c6e12f6f6a,
+              0x73e2c2677a8cdb0b, 0xccd974c377edc22a, 0xe989a50eeb9aa648, 0x308d4f190fc617a0,
+              0x716435a78c74276d, 0x5b65f920b540671d, 0x6c112b3667df400b, 0x6fe4f02c076ccbe6,
+              0x8ef3fbad1e60bca8, 0x1c08eada7b7aceb6, 0x43a10f391aa2e8df, 0xad572fb2add7eff6, ]),
+            ([0x8ccbbbea4fe5d2b7, 0xc2af59ee9ec49970, 0x90f7e1a9e658446a, 0xdcc0630a3ab8b1b8,
+              0x7ff8256bca20588c, 0x5d99a7ca0c44ecfb, 0x48452b17a70fbee3, 0xeb09d654690b6c88,
+              0x4a55d3a39c676a88, 0xc0407a38d2285139, 0xa234bac9356386d1, 0xe1633f2bad98a52f,
+              0x1f62b2bf6a3e2f8c, 0x3b0a3e8c9c1c6a5d, 0x0000000100000000, 0xfffffffeffffffff, ],
+             [0x738d87b5082806b6, 0x4866afed4ea14eb7, 0x02592f5d84da164f, 0x2e3c2276f2442092,
+              0x70147b96bda94bb4, 0x22f7bcb40addc316, 0x4f6be94de6080f16, 0xc3593dcece1415f0,
+              0x4dd42a76736bc846, 0x1b893f5497ae15b5, 0xa7ca11a1cbffcf9e, 0x408339a16308c57e,
+              0x11f5da72f5c2120a, 0xa174a6d43f4238d6, 0x304222f327fdcfb0, 0x68d7cb4ef2f94595, ]),
+        ];
+
+        for (input, expected) in test_vectors {
+            let input = input.map(GoldilocksField::from_canonical_u64);
+            let output = GoldilocksField::tip5(input);
+            assert_eq!(output, expected.map(GoldilocksField::from_canonical_u64));
+        }
+    }
+
+    #[test]
+    fn test_lookup_table_is_permutation() {
+        let mut seen = [false; 256];
+        for &y in &LOOKUP_TABLE {
+            assert!(!seen[y as usize]);
+            seen[y as usize] = true;
+        }
+        assert_eq!(LOOKUP_TABLE[0], 0);
+        assert_eq!(LOOKUP_TABLE[255], 255);
+    }
+
+    #[test]
+    fn test_mds_multiply_matches_generic() {
+        let mut state = GoldilocksField::rand_array::<SPONGE_WIDTH>();
+        let mut expected = state;
+        GoldilocksField::mds_multiply(&mut state);
+        GoldilocksField::mds_multiply_field(&mut expected);
+        assert_eq!(state, expected);
+    }
+
+    #[test]
+    fn test_lookup_preserves_canonical_form() {
+        let order = GoldilocksField::ORDER;
+        let edge_cases = [
+            0,
+            1,
+            order - 1,
+            order - 2,
+            0xffff_fffe_ffff_ffff,
+            0xffff_ffff,
+        ];
+        for x in edge_cases {
+            assert!(lookup_u64(x) < order);
+        }
+        for _ in 0..1000 {
+            let x = GoldilocksField::rand().to_canonical_u64();
+            assert!(lookup_u64(x) < order);
+        }
+    }
+}
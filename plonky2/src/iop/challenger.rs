@@ -6,8 +6,9 @@ use crate::field::extension::{Extendable, FieldExtension};
 use crate::hash::hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::hashing::PlonkyPermutation;
 use crate::hash::merkle_tree::MerkleCap;
+use crate::hash::sponge::pack_bytes;
 use crate::iop::ext_target::ExtensionTarget;
-use crate::iop::target::Target;
+use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{AlgebraicHasher, GenericHashOut, Hasher};
 
@@ -79,6 +80,18 @@ impl<F: RichField, H: Hasher<F>> Challenger<F, H> {
         }
     }
 
+    /// Observes a label, so that sub-protocols sharing this transcript can be domain-separated.
+    /// The label's length is observed first, followed by its bytes packed into field elements, so
+    /// distinct labels are never observed as the same elements.
+    pub fn observe_label(&mut self, label: &[u8]) {
+        self.observe_elements(&label_to_elements(label));
+    }
+
+    /// Observes a domain tag naming the sub-protocol whose messages follow. See `observe_label`.
+    pub fn observe_domain_tag(&mut self, tag: &str) {
+        self.observe_label(tag.as_bytes());
+    }
+
     pub fn get_challenge(&mut self) -> F {
         // If we have buffered inputs, we must perform a duplexing so that the challenge will
         // reflect them. Or if we've run out of outputs, we must perform a duplexing to get more.
@@ -161,7 +174,7 @@ impl<F: RichField, H: AlgebraicHasher<F>> Default for Challenger<F, H> {
 /// A recursive version of `Challenger`. The main difference is that `RecursiveChallenger`'s input
 /// buffer can grow beyond `H::Permutation::RATE`. This is so that `observe_element` etc do not need access
 /// to the `CircuitBuilder`.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct RecursiveChallenger<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
 {
     sponge_state: H::AlgebraicPermutation,
@@ -215,6 +228,17 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
         }
     }
 
+    /// Observes a label, matching `Challenger::observe_label`.
+    pub fn observe_label(&mut self, builder: &mut CircuitBuilder<F, D>, label: &[u8]) {
+        let elements = builder.constants(&label_to_elements::<F>(label));
+        self.observe_elements(&elements);
+    }
+
+    /// Observes a domain tag, matching `Challenger::observe_domain_tag`.
+    pub fn observe_domain_tag(&mut self, builder: &mut CircuitBuilder<F, D>, tag: &str) {
+        self.observe_label(builder, tag.as_bytes());
+    }
+
     pub fn observe_extension_element(&mut self, element: ExtensionTarget<D>) {
         self.observe_elements(&element.0);
     }
@@ -285,6 +309,48 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
         self.input_buffer.clear();
     }
 
+    /// Selects one of `challengers` according to the matching entry of `flags`, exactly one of
+    /// which must be set. This is used to continue a transcript which took one of several shapes,
+    /// e.g. a variable number of observed elements. All challengers must have absorbed their inputs
+    /// and have the same number of buffered outputs.
+    pub(crate) fn select(
+        builder: &mut CircuitBuilder<F, D>,
+        flags: &[BoolTarget],
+        challengers: &[Self],
+    ) -> Self {
+        assert_eq!(flags.len(), challengers.len());
+        assert!(challengers.iter().all(|c| c.input_buffer.is_empty()));
+        let output_len = challengers[0].output_buffer.len();
+        assert!(challengers
+            .iter()
+            .all(|c| c.output_buffer.len() == output_len));
+
+        let sponge_state =
+            H::AlgebraicPermutation::new((0..H::AlgebraicPermutation::WIDTH).map(|i| {
+                let elements = challengers
+                    .iter()
+                    .map(|c| c.sponge_state.as_ref()[i])
+                    .collect::<Vec<_>>();
+                builder.select_one_hot(flags, &elements)
+            }));
+        let output_buffer = (0..output_len)
+            .map(|i| {
+                let elements = challengers
+                    .iter()
+                    .map(|c| c.output_buffer[i])
+                    .collect::<Vec<_>>();
+                builder.select_one_hot(flags, &elements)
+            })
+            .collect();
+
+        Self {
+            sponge_state,
+            input_buffer: vec![],
+            output_buffer,
+            __: PhantomData,
+        }
+    }
+
     pub fn compact(&mut self, builder: &mut CircuitBuilder<F, D>) -> H::AlgebraicPermutation {
         self.absorb_buffered_inputs(builder);
         self.output_buffer.clear();
@@ -292,6 +358,13 @@ impl<F: RichField + Extendable<D>, H: AlgebraicHasher<F>, const D: usize>
     }
 }
 
+/// Encodes a label as its length followed by its packed bytes.
+fn label_to_elements<F: RichField>(label: &[u8]) -> Vec<F> {
+    let mut elements = vec![F::from_canonical_usize(label.len())];
+    elements.extend(pack_bytes::<F>(label));
+    elements
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(not(feature = "std"))]
@@ -374,4 +447,40 @@ mod tests {
 
         assert_eq!(outputs_per_round, recursive_output_values_per_round);
     }
+
+    #[test]
+    fn test_domain_tags() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type H = <C as GenericConfig<D>>::InnerHasher;
+
+        let inputs = F::rand_vec(3);
+        let challenges_with_tag = |tag: Option<&str>| {
+            let mut challenger = Challenger::<F, H>::new();
+            if let Some(tag) = tag {
+                challenger.observe_domain_tag(tag);
+            }
+            challenger.observe_elements(&inputs);
+            challenger.get_n_challenges(2)
+        };
+        let untagged = challenges_with_tag(None);
+        let tagged = challenges_with_tag(Some("sumcheck"));
+        assert_ne!(untagged, tagged);
+        assert_ne!(tagged, challenges_with_tag(Some("lookup")));
+        // The empty tag still observes its length.
+        assert_ne!(untagged, challenges_with_tag(Some("")));
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let mut recursive_challenger = RecursiveChallenger::<F, H, D>::new(&mut builder);
+        recursive_challenger.observe_domain_tag(&mut builder, "sumcheck");
+        recursive_challenger.observe_elements(&builder.constants(&inputs));
+        let recursive_challenges = recursive_challenger.get_n_challenges(&mut builder, 2);
+        let circuit = builder.build::<C>();
+        let witness =
+            generate_partial_witness(PartialWitness::new(), &circuit.prover_only, &circuit.common)
+                .unwrap();
+        assert_eq!(witness.get_targets(&recursive_challenges), tagged);
+    }
 }
@@ -69,6 +69,12 @@ impl<const D: usize> ExtensionTarget<D> {
     }
 }
 
+impl<const D: usize> From<[Target; D]> for ExtensionTarget<D> {
+    fn from(targets: [Target; D]) -> Self {
+        Self(targets)
+    }
+}
+
 impl<const D: usize> TryFrom<Vec<Target>> for ExtensionTarget<D> {
     type Error = Vec<Target>;
 
@@ -109,10 +115,13 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         ExtensionAlgebraTarget(parts)
     }
 
+    /// Returns a routable target with a value of 0. Like other constants, its targets are
+    /// memoized, so calling this repeatedly doesn't add wires.
     pub fn zero_extension(&mut self) -> ExtensionTarget<D> {
         self.constant_extension(F::Extension::ZERO)
     }
 
+    /// Returns a routable target with a value of 1. See `zero_extension`.
     pub fn one_extension(&mut self) -> ExtensionTarget<D> {
         self.constant_extension(F::Extension::ONE)
     }
@@ -129,11 +138,34 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.constant_ext_algebra(ExtensionAlgebra::ZERO)
     }
 
+    /// Embeds a base field target into the extension field.
     pub fn convert_to_ext(&mut self, t: Target) -> ExtensionTarget<D> {
         let zero = self.zero();
         t.to_ext_target(zero)
     }
 
+    /// Returns the extension element whose coefficients, in the basis `1, X, ..., X^(D-1)`, are
+    /// `coeffs`, padded with zeros.
+    pub fn ext_from_base_targets(&mut self, coeffs: &[Target]) -> ExtensionTarget<D> {
+        assert!(
+            coeffs.len() <= D,
+            "{} coefficients don't fit in an extension of degree {D}",
+            coeffs.len()
+        );
+        let zero = self.zero();
+        ExtensionTarget(core::array::from_fn(|i| {
+            coeffs.get(i).copied().unwrap_or(zero)
+        }))
+    }
+
+    /// Returns `x` as a base field target, enforcing that it lies in the base field.
+    pub fn project_to_base(&mut self, x: ExtensionTarget<D>) -> Target {
+        for &t in &x.0[1..] {
+            self.assert_zero(t);
+        }
+        x.0[0]
+    }
+
     pub fn convert_to_ext_algebra(&mut self, et: ExtensionTarget<D>) -> ExtensionAlgebraTarget<D> {
         let zero = self.zero_extension();
         let mut arr = [zero; D];
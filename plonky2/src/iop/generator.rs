@@ -1,25 +1,36 @@
 #[cfg(not(feature = "std"))]
 use alloc::{
     boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    format,
     string::{String, ToString},
     vec,
     vec::Vec,
 };
 use core::fmt::Debug;
 use core::marker::PhantomData;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, BTreeSet};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, ensure, Context, Result};
+use plonky2_maybe_rayon::*;
+use serde::Serialize;
+#[cfg(feature = "timing")]
+use web_time::Instant;
 
 use crate::field::extension::Extendable;
 use crate::field::types::Field;
 use crate::hash::hash_types::RichField;
+use crate::hash::poseidon::PoseidonHash;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
 use crate::iop::wire::Wire;
 use crate::iop::witness::{PartialWitness, PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
-use crate::plonk::config::GenericConfig;
-use crate::util::serialization::{Buffer, IoResult, Read, Write};
+use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use crate::util::serialization::{Buffer, IoError, IoResult, Read, Write};
+use crate::util::timing::TimingTree;
 
 /// Given a `PartitionWitness` that has only inputs set, populates the rest of the witness using the
 /// given set of generators.
@@ -33,74 +44,520 @@ pub fn generate_partial_witness<
     prover_data: &'a ProverOnlyCircuitData<F, C, D>,
     common_data: &'a CommonCircuitData<F, D>,
 ) -> Result<PartitionWitness<'a, F>> {
-    let config = &common_data.config;
-    let generators = &prover_data.generators;
-    let generator_indices_by_watches = &prover_data.generator_indices_by_watches;
+    let mut witness = initial_witness(inputs, prover_data, common_data)?;
+    let generator_is_expired = vec![false; prover_data.generators.len()];
+    let unfinished_generators =
+        run_generators(&mut witness, prover_data, generator_is_expired, None, None)?;
+
+    if !unfinished_generators.is_empty() {
+        return Err(stall_error(&witness, prover_data, &unfinished_generators));
+    }
+
+    Ok(witness)
+}
+
+/// Like `generate_partial_witness`, but also adds to the deepest open scope of `timing` the time
+/// spent running the generators of each builder context, summed across threads, slowest first.
+pub fn generate_partial_witness_with_timing<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inputs: PartialWitness<F>,
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+    timing: &mut TimingTree,
+) -> Result<PartitionWitness<'a, F>> {
+    let mut witness = initial_witness(inputs, prover_data, common_data)?;
+    let generator_is_expired = vec![false; prover_data.generators.len()];
+    let mut times = vec![Duration::ZERO; prover_data.generators.len()];
+    let unfinished_generators = run_generators(
+        &mut witness,
+        prover_data,
+        generator_is_expired,
+        None,
+        Some(&mut times),
+    )?;
+
+    let mut times_by_context = BTreeMap::<&str, Duration>::new();
+    for (generator_idx, time) in times.into_iter().enumerate() {
+        let context = prover_data
+            .generator_context(generator_idx)
+            .unwrap_or_default();
+        *times_by_context.entry(context).or_default() += time;
+    }
+    let mut times_by_context = times_by_context.into_iter().collect::<Vec<_>>();
+    times_by_context.sort_by(|(_, t0), (_, t1)| t1.cmp(t0));
+    for (context, time) in times_by_context {
+        timing.add_closed(context, time);
+    }
+
+    if !unfinished_generators.is_empty() {
+        return Err(stall_error(&witness, prover_data, &unfinished_generators));
+    }
+
+    Ok(witness)
+}
+
+/// The part of a circuit's witness which doesn't depend on its inputs, i.e. the outputs of
+/// generators which can run before any input is set, such as constant generators and anything
+/// computed from constants. See `witness_template`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WitnessTemplate<F: Field> {
+    /// The digest of the circuit this template was created for.
+    circuit_digest: Vec<F>,
+    values: Vec<Option<F>>,
+    generator_is_expired: Vec<bool>,
+}
+
+impl<F: Field> WitnessTemplate<F> {
+    /// The number of generators which were run to completion in this template, and so are skipped
+    /// when generating a witness from it.
+    pub fn num_finished_generators(&self) -> usize {
+        self.generator_is_expired
+            .iter()
+            .filter(|&&expired| expired)
+            .count()
+    }
+
+    /// The value generated for the targets with the given representative, if any.
+    pub(crate) fn value(&self, representative: usize) -> Option<F> {
+        self.values[representative]
+    }
+}
+
+/// Runs every generator which can run without inputs, so that their outputs can be reused by
+/// `generate_partial_witness_with_template` when proving the same circuit repeatedly.
+///
+/// Generators which aren't deterministic (see `WitnessGenerator::is_deterministic`), such as those
+/// of blinding values, are left out so that their values are drawn afresh for each witness.
+pub fn witness_template<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<WitnessTemplate<F>> {
+    let mut witness = initial_witness(PartialWitness::new(), prover_data, common_data)?;
+    let is_nondeterministic = prover_data
+        .generators
+        .iter()
+        .map(|generator| !generator.0.is_deterministic())
+        .collect::<Vec<_>>();
+
+    // Non-deterministic generators are marked as expired so that they aren't run, and neither is
+    // anything depending on their outputs, since those outputs are never set.
+    let unfinished_generators = run_generators(
+        &mut witness,
+        prover_data,
+        is_nondeterministic.clone(),
+        None,
+        None,
+    )?;
+    let mut generator_is_expired = vec![true; prover_data.generators.len()];
+    for generator_idx in unfinished_generators {
+        generator_is_expired[generator_idx] = false;
+    }
+    for (expired, nondeterministic) in generator_is_expired.iter_mut().zip(is_nondeterministic) {
+        *expired &= !nondeterministic;
+    }
+
+    Ok(WitnessTemplate {
+        circuit_digest: prover_data.circuit_digest.to_vec(),
+        values: witness.values,
+        generator_is_expired,
+    })
+}
+
+/// Like `generate_partial_witness`, but starts from a `template` of the same circuit, so that only
+/// the generators which depend on the inputs are run.
+pub fn generate_partial_witness_with_template<
+    'a,
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inputs: PartialWitness<F>,
+    template: &WitnessTemplate<F>,
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &'a CommonCircuitData<F, D>,
+) -> Result<PartitionWitness<'a, F>> {
+    ensure!(
+        template.circuit_digest == prover_data.circuit_digest.to_vec()
+            && template.values.len() == prover_data.representative_map.len()
+            && template.generator_is_expired.len() == prover_data.generators.len(),
+        "Witness template was created for a different circuit"
+    );
+
+    let mut witness = PartitionWitness {
+        values: template.values.clone(),
+        ..PartitionWitness::new(
+            common_data.config.num_wires,
+            common_data.degree(),
+            &prover_data.representative_map,
+        )
+    };
+    set_inputs(&mut witness, inputs)?;
+
+    let unfinished_generators = run_generators(
+        &mut witness,
+        prover_data,
+        template.generator_is_expired.clone(),
+        None,
+        None,
+    )?;
+
+    if !unfinished_generators.is_empty() {
+        return Err(stall_error(&witness, prover_data, &unfinished_generators));
+    }
+
+    Ok(witness)
+}
+
+/// Describes why generation stalled: which unset targets each unfinished generator is waiting for,
+/// and where in the circuit those targets are.
+fn stall_error<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    witness: &PartitionWitness<F>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    unfinished_generators: &[usize],
+) -> anyhow::Error {
+    const MAX_REPORTED_GENERATORS: usize = 10;
+
+    let blocked_generators = unfinished_generators
+        .iter()
+        .map(|&generator_idx| {
+            let generator = &prover_data.generators[generator_idx].0;
+            let mut unset_reps = generator
+                .watch_list()
+                .into_iter()
+                .map(|t| witness.representative_map[witness.target_index(t)])
+                .filter(|&rep| witness.values[rep].is_none())
+                .collect::<Vec<_>>();
+            unset_reps.sort_unstable();
+            unset_reps.dedup();
+            (generator.id(), unset_reps)
+        })
+        .collect::<Vec<_>>();
+    let unset_reps = blocked_generators
+        .iter()
+        .flat_map(|(_, reps)| reps.iter().copied())
+        .collect::<BTreeSet<_>>();
+
+    // Gate contexts are known for wires, so describe each target by a wire copied from it if any.
+    let num_wire_targets = witness.num_wires * witness.degree;
+    let mut wire_by_rep = BTreeMap::new();
+    for (index, rep) in witness.representative_map[..num_wire_targets]
+        .iter()
+        .enumerate()
+    {
+        if unset_reps.contains(rep) {
+            wire_by_rep.entry(*rep).or_insert(index);
+        }
+    }
+    let describe = |rep: usize| match wire_by_rep.get(&rep) {
+        Some(&index) => {
+            let (row, column) = (index / witness.num_wires, index % witness.num_wires);
+            let context = prover_data.gate_context(row).unwrap_or("unknown context");
+            format!("wire ({row}, {column}) in {context}")
+        }
+        None => format!(
+            "{:?}, which isn't copied to any wire",
+            Target::from_index(rep, witness.num_wires, witness.degree)
+        ),
+    };
+
+    let mut message = format!(
+        "{} generators weren't run, because witness generation stalled with {} watched targets unset",
+        unfinished_generators.len(),
+        unset_reps.len()
+    );
+    for (id, reps) in blocked_generators.iter().take(MAX_REPORTED_GENERATORS) {
+        if reps.is_empty() {
+            message += &format!("\n- {id} didn't finish, though every target it watches is set");
+        } else {
+            let targets = reps.iter().map(|&rep| describe(rep)).collect::<Vec<_>>();
+            message += &format!("\n- {id} is waiting for {}", targets.join("; "));
+        }
+    }
+    if blocked_generators.len() > MAX_REPORTED_GENERATORS {
+        message += &format!(
+            "\n- and {} more generators",
+            blocked_generators.len() - MAX_REPORTED_GENERATORS
+        );
+    }
 
+    anyhow!(message)
+}
+
+/// Runs witness generation from the given inputs, and records which targets each generator
+/// watched and populated, and in which round it finished. Generators which never finish are
+/// included rather than reported as an error, so the graph can also show where generation stalls.
+pub fn generator_dependency_graph<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    inputs: PartialWitness<F>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<GeneratorGraph> {
+    let mut witness = initial_witness(inputs, prover_data, common_data)?;
+    let generators = prover_data
+        .generators
+        .iter()
+        .map(|generator| {
+            let mut watches = generator
+                .0
+                .watch_list()
+                .into_iter()
+                .map(|t| witness.representative_map[witness.target_index(t)])
+                .collect::<Vec<_>>();
+            watches.sort_unstable();
+            watches.dedup();
+            GeneratorNode {
+                id: generator.0.id(),
+                watches,
+                outputs: Vec::new(),
+                finished_round: None,
+            }
+        })
+        .collect();
+    let mut graph = GeneratorGraph {
+        num_wires: witness.num_wires,
+        degree: witness.degree,
+        generators,
+    };
+    let generator_is_expired = vec![false; prover_data.generators.len()];
+    run_generators(
+        &mut witness,
+        prover_data,
+        generator_is_expired,
+        Some(&mut graph),
+        None,
+    )?;
+
+    Ok(graph)
+}
+
+fn initial_witness<'a, F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    inputs: PartialWitness<F>,
+    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<PartitionWitness<'a, F>> {
     let mut witness = PartitionWitness::new(
-        config.num_wires,
+        common_data.config.num_wires,
         common_data.degree(),
         &prover_data.representative_map,
     );
 
-    for (t, v) in inputs.target_values.into_iter() {
+    set_inputs(&mut witness, inputs)?;
+
+    Ok(witness)
+}
+
+fn set_inputs<F: RichField>(
+    witness: &mut PartitionWitness<F>,
+    inputs: PartialWitness<F>,
+) -> Result<()> {
+    // Set inputs in target order, so that e.g. which of two conflicting inputs gets reported doesn't
+    // depend on hash map iteration order.
+    let mut inputs = inputs.target_values.into_iter().collect::<Vec<_>>();
+    inputs.sort_unstable_by_key(|&(t, _)| witness.target_index(t));
+    for (t, v) in inputs {
         witness.set_target(t, v)?;
     }
 
-    // Build a list of "pending" generators which are queued to be run. Initially, all generators
-    // are queued.
-    let mut pending_generator_indices: Vec<_> = (0..generators.len()).collect();
+    Ok(())
+}
+
+/// Runs generators until none can make progress, returning the indices of those which haven't
+/// finished. Generators initially marked in `generator_is_expired` are never run. If `times` is
+/// given and the `timing` feature is enabled, the time spent running each generator is added to it.
+fn run_generators<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    witness: &mut PartitionWitness<F>,
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    mut generator_is_expired: Vec<bool>,
+    mut graph: Option<&mut GeneratorGraph>,
+    mut times: Option<&mut [Duration]>,
+) -> Result<Vec<usize>> {
+    let generators = &prover_data.generators;
+    let generator_indices_by_watches = &prover_data.generator_indices_by_watches;
 
-    // We also track a list of "expired" generators which have already returned false.
-    let mut generator_is_expired = vec![false; generators.len()];
-    let mut remaining_generators = generators.len();
+    // Generators are run in rounds. Each round runs the queued generators in parallel against the
+    // witness as it stood at the start of the round, then merges their outputs in queue order, so
+    // the result doesn't depend on scheduling. Initially, all unexpired generators are queued.
+    let mut pending_generator_indices: Vec<_> = (0..generators.len())
+        .filter(|&generator_idx| !generator_is_expired[generator_idx])
+        .collect();
 
-    let mut buffer = GeneratedValues::empty();
+    // We also track a list of "expired" generators which have already returned true, and which
+    // generators are queued for the next round, so that none is run twice in a round.
+    let mut generator_is_queued = vec![false; generators.len()];
 
     // Keep running generators until we fail to make progress.
+    #[cfg(feature = "timing")]
+    let measure_time = times.is_some();
+    let mut round = 0;
     while !pending_generator_indices.is_empty() {
-        let mut next_pending_generator_indices = Vec::new();
+        let results: Vec<_> = pending_generator_indices
+            .par_iter()
+            .map(|&generator_idx| {
+                let mut buffer = GeneratedValues::empty();
+                #[cfg(feature = "timing")]
+                let start = measure_time.then(Instant::now);
+                let finished = generators[generator_idx].0.run(witness, &mut buffer);
+                #[cfg(feature = "timing")]
+                let time = start.map_or(Duration::ZERO, |start| start.elapsed());
+                #[cfg(not(feature = "timing"))]
+                let time = Duration::ZERO;
+                (finished, buffer, time)
+            })
+            .collect();
 
-        for &generator_idx in &pending_generator_indices {
-            if generator_is_expired[generator_idx] {
-                continue;
+        let mut next_pending_generator_indices = Vec::new();
+        for (&generator_idx, (finished, buffer, time)) in
+            pending_generator_indices.iter().zip(results)
+        {
+            if let Some(times) = times.as_deref_mut() {
+                times[generator_idx] += time;
             }
-
-            let finished = generators[generator_idx].0.run(&witness, &mut buffer);
-            if finished {
+            let describe = || describe_generator(prover_data, generator_idx);
+            if finished.with_context(|| format!("{} failed", describe()))? {
                 generator_is_expired[generator_idx] = true;
-                remaining_generators -= 1;
-            }
-
-            // Merge any generated values into our witness, and get a list of newly-populated
-            // targets' representatives.
-            let mut new_target_reps = Vec::with_capacity(buffer.target_values.len());
-            for (t, v) in buffer.target_values.drain(..) {
-                let reps = witness.set_target_returning_rep(t, v)?;
-                new_target_reps.extend(reps);
+                if let Some(graph) = graph.as_deref_mut() {
+                    graph.generators[generator_idx].finished_round = Some(round);
+                }
             }
 
-            // Enqueue unfinished generators that were watching one of the newly populated targets.
-            for watch in new_target_reps {
-                let opt_watchers = generator_indices_by_watches.get(&watch);
-                if let Some(watchers) = opt_watchers {
-                    for &watching_generator_idx in watchers {
-                        if !generator_is_expired[watching_generator_idx] {
-                            next_pending_generator_indices.push(watching_generator_idx);
-                        }
+            // Merge any generated values into our witness, and enqueue unfinished generators that
+            // were watching one of the newly populated targets' representatives.
+            for (t, v) in buffer.target_values {
+                let Some(watch) = witness
+                    .set_target_returning_rep(t, v)
+                    .with_context(|| format!("Setting an output of {}", describe()))?
+                else {
+                    continue;
+                };
+                if let Some(graph) = graph.as_deref_mut() {
+                    graph.generators[generator_idx].outputs.push(watch);
+                }
+                let Some(watchers) = generator_indices_by_watches.get(&watch) else {
+                    continue;
+                };
+                for &watching_generator_idx in watchers {
+                    if !generator_is_expired[watching_generator_idx]
+                        && !generator_is_queued[watching_generator_idx]
+                    {
+                        generator_is_queued[watching_generator_idx] = true;
+                        next_pending_generator_indices.push(watching_generator_idx);
                     }
                 }
             }
         }
 
+        // A generator may have finished after being queued by an earlier generator's outputs.
+        next_pending_generator_indices.retain(|&generator_idx| {
+            generator_is_queued[generator_idx] = false;
+            !generator_is_expired[generator_idx]
+        });
         pending_generator_indices = next_pending_generator_indices;
+        round += 1;
     }
 
-    if remaining_generators != 0 {
-        return Err(anyhow!("{} generators weren't run", remaining_generators));
+    Ok((0..generators.len())
+        .filter(|&generator_idx| !generator_is_expired[generator_idx])
+        .collect())
+}
+
+/// Describes a generator by its ID and the context it was added in, e.g.
+/// `"EqualityGenerator added in root > verify signature"`.
+fn describe_generator<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    generator_idx: usize,
+) -> String {
+    let id = prover_data.generators[generator_idx].0.id();
+    match prover_data.generator_context(generator_idx) {
+        Some(context) => format!("{id} added in {context}"),
+        None => id,
     }
+}
 
-    Ok(witness)
+/// The dependency graph between a circuit's generators and targets, as recorded by
+/// `generator_dependency_graph`. Targets are identified by the index of their copy-constraint
+/// representative, so all copies of a value are one node.
+///
+/// The graph can be serialized, e.g. to JSON, or rendered with Graphviz through `to_dot`.
+#[derive(Serialize, Clone, Debug, Eq, PartialEq)]
+pub struct GeneratorGraph {
+    pub num_wires: usize,
+    pub degree: usize,
+    pub generators: Vec<GeneratorNode>,
+}
+
+#[derive(Serialize, Clone, Debug, Eq, PartialEq)]
+pub struct GeneratorNode {
+    pub id: String,
+    /// The targets which queue this generator when populated.
+    pub watches: Vec<usize>,
+    /// The targets which this generator populated first.
+    pub outputs: Vec<usize>,
+    /// The round in which this generator finished, or `None` if it never did.
+    pub finished_round: Option<usize>,
+}
+
+impl GeneratorGraph {
+    /// The target with the given representative index.
+    pub fn target(&self, index: usize) -> Target {
+        Target::from_index(index, self.num_wires, self.degree)
+    }
+
+    /// The number of rounds of generators needed to populate the witness, i.e. the length of its
+    /// critical path.
+    pub fn num_rounds(&self) -> usize {
+        self.generators
+            .iter()
+            .filter_map(|generator| generator.finished_round)
+            .max()
+            .map_or(0, |round| round + 1)
+    }
+
+    /// Renders the graph in Graphviz's DOT format, with generators as boxes labelled with their
+    /// IDs and the rounds they finished in, and targets as ellipses.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph generators {\n");
+        let mut targets = Vec::new();
+        for (i, generator) in self.generators.iter().enumerate() {
+            let round = generator.finished_round.map_or_else(
+                || "unfinished".to_string(),
+                |round| format!("round {round}"),
+            );
+            dot += &format!(
+                "  g{i} [shape=box, label=\"{}\\n{round}\"];\n",
+                generator.id.replace('\\', "\\\\").replace('"', "\\\"")
+            );
+            for &watch in &generator.watches {
+                dot += &format!("  t{watch} -> g{i};\n");
+            }
+            for &output in &generator.outputs {
+                dot += &format!("  g{i} -> t{output};\n");
+            }
+            targets.extend(&generator.watches);
+            targets.extend(&generator.outputs);
+        }
+        targets.sort_unstable();
+        targets.dedup();
+        for t in targets {
+            let label = match self.target(t) {
+                Target::Wire(Wire { row, column }) => format!("wire {row}, {column}"),
+                Target::VirtualTarget { index } => format!("virtual {index}"),
+            };
+            dot += &format!("  t{t} [label=\"{label}\"];\n");
+        }
+        dot += "}\n";
+        dot
+    }
 }
 
 /// A generator participates in the generation of the witness.
@@ -115,8 +572,19 @@ pub trait WitnessGenerator<F: RichField + Extendable<D>, const D: usize>:
 
     /// Run this generator, returning a flag indicating whether the generator is finished. If the
     /// flag is true, the generator will never be run again, otherwise it will be queued for another
-    /// run next time a target in its watch list is populated.
-    fn run(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> bool;
+    /// run next time a target in its watch list is populated. Errors abort witness generation.
+    fn run(
+        &self,
+        witness: &PartitionWitness<F>,
+        out_buffer: &mut GeneratedValues<F>,
+    ) -> Result<bool>;
+
+    /// Whether this generator always produces the same outputs from the same inputs. Outputs of
+    /// non-deterministic generators, such as blinding values, are never reused across witnesses;
+    /// see `witness_template`.
+    fn is_deterministic(&self) -> bool {
+        true
+    }
 
     fn serialize(&self, dst: &mut Vec<u8>, common_data: &CommonCircuitData<F, D>) -> IoResult<()>;
 
@@ -216,6 +684,11 @@ pub trait SimpleGenerator<F: RichField + Extendable<D>, const D: usize>:
         out_buffer: &mut GeneratedValues<F>,
     ) -> Result<()>;
 
+    /// See `WitnessGenerator::is_deterministic`.
+    fn is_deterministic(&self) -> bool {
+        true
+    }
+
     fn adapter(self) -> SimpleGeneratorAdapter<F, Self, D>
     where
         Self: Sized,
@@ -254,12 +727,20 @@ impl<F: RichField + Extendable<D>, SG: SimpleGenerator<F, D>, const D: usize> Wi
         self.inner.dependencies()
     }
 
-    fn run(&self, witness: &PartitionWitness<F>, out_buffer: &mut GeneratedValues<F>) -> bool {
-        if witness.contains_all(&self.inner.dependencies()) {
-            self.inner.run_once(witness, out_buffer).is_ok()
-        } else {
-            false
+    fn run(
+        &self,
+        witness: &PartitionWitness<F>,
+        out_buffer: &mut GeneratedValues<F>,
+    ) -> Result<bool> {
+        if !witness.contains_all(&self.inner.dependencies()) {
+            return Ok(false);
         }
+        self.inner.run_once(witness, out_buffer)?;
+        Ok(true)
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.inner.is_deterministic()
     }
 
     fn serialize(&self, dst: &mut Vec<u8>, common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
@@ -274,6 +755,67 @@ impl<F: RichField + Extendable<D>, SG: SimpleGenerator<F, D>, const D: usize> Wi
     }
 }
 
+/// A generator which runs a closure once all of its dependencies are set. See
+/// `CircuitBuilder::add_generator_fn`.
+///
+/// Closures can't be serialized, so neither can circuits using this generator.
+pub struct FnGenerator<F, G> {
+    deps: Vec<Target>,
+    f: G,
+    _phantom: PhantomData<F>,
+}
+
+impl<F, G> FnGenerator<F, G> {
+    pub const fn new(deps: Vec<Target>, f: G) -> Self {
+        Self {
+            deps,
+            f,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, G> Debug for FnGenerator<F, G> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FnGenerator")
+            .field("deps", &self.deps)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: RichField + Extendable<D>, G, const D: usize> SimpleGenerator<F, D> for FnGenerator<F, G>
+where
+    G: Fn(&PartitionWitness<F>, &mut GeneratedValues<F>) -> Result<()> + 'static + Send + Sync,
+{
+    fn id(&self) -> String {
+        "FnGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        self.deps.clone()
+    }
+
+    fn run_once(
+        &self,
+        witness: &PartitionWitness<F>,
+        out_buffer: &mut GeneratedValues<F>,
+    ) -> Result<()> {
+        (self.f)(witness, out_buffer)
+    }
+
+    fn serialize(
+        &self,
+        _dst: &mut Vec<u8>,
+        _common_data: &CommonCircuitData<F, D>,
+    ) -> IoResult<()> {
+        Err(IoError)
+    }
+
+    fn deserialize(_src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        Err(IoError)
+    }
+}
+
 /// A generator which copies one wire to another.
 #[derive(Debug, Default)]
 pub struct CopyGenerator {
@@ -335,6 +877,10 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for Ran
         out_buffer.set_target(self.target, random_value)
     }
 
+    fn is_deterministic(&self) -> bool {
+        false
+    }
+
     fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
         dst.write_target(self.target)
     }
@@ -345,6 +891,49 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for Ran
     }
 }
 
+/// A generator for values which look random but are derived from the values of some seed targets,
+/// so that the same seed always gives the same values.
+#[derive(Debug, Default)]
+pub struct SeededValueGenerator {
+    pub(crate) seed: Vec<Target>,
+    pub(crate) targets: Vec<Target>,
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for SeededValueGenerator {
+    fn id(&self) -> String {
+        "SeededValueGenerator".to_string()
+    }
+
+    fn dependencies(&self) -> Vec<Target> {
+        self.seed.clone()
+    }
+
+    fn run_once(
+        &self,
+        witness: &PartitionWitness<F>,
+        out_buffer: &mut GeneratedValues<F>,
+    ) -> Result<()> {
+        let mut inputs = witness.get_targets(&self.seed);
+        for (i, &target) in self.targets.iter().enumerate() {
+            inputs.push(F::from_canonical_usize(i));
+            out_buffer.set_target(target, PoseidonHash::hash_no_pad(&inputs).elements[0])?;
+            inputs.pop();
+        }
+        Ok(())
+    }
+
+    fn serialize(&self, dst: &mut Vec<u8>, _common_data: &CommonCircuitData<F, D>) -> IoResult<()> {
+        dst.write_target_vec(&self.seed)?;
+        dst.write_target_vec(&self.targets)
+    }
+
+    fn deserialize(src: &mut Buffer, _common_data: &CommonCircuitData<F, D>) -> IoResult<Self> {
+        let seed = src.read_target_vec()?;
+        let targets = src.read_target_vec()?;
+        Ok(Self { seed, targets })
+    }
+}
+
 /// A generator for testing if a value equals zero
 #[derive(Debug, Default)]
 pub struct NonzeroTestGenerator {
@@ -441,3 +1030,252 @@ impl<F: RichField + Extendable<D>, const D: usize> SimpleGenerator<F, D> for Con
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::iop::witness::WitnessWrite;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_generator_dependency_graph() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x2 = builder.mul(x, x);
+        let x3 = builder.mul(x2, x);
+        let x4 = builder.mul(x3, x);
+        builder.register_public_input(x4);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO)?;
+        let graph = generator_dependency_graph(pw.clone(), &data.prover_only, &data.common)?;
+        assert!(graph
+            .generators
+            .iter()
+            .all(|generator| generator.finished_round.is_some()));
+        // Each multiplication waits for the previous one.
+        assert!(graph.num_rounds() >= 3);
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph generators {"));
+        assert!(dot.contains("ArithmeticBaseGenerator"));
+        serde_json::to_string(&graph)?;
+
+        // Without the input, generation stalls, and the graph shows which generators never ran.
+        let graph =
+            generator_dependency_graph(PartialWitness::new(), &data.prover_only, &data.common)?;
+        assert!(graph
+            .generators
+            .iter()
+            .any(|generator| generator.finished_round.is_none()));
+        assert!(
+            generate_partial_witness(PartialWitness::new(), &data.prover_only, &data.common)
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_generator_fn() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x_inv = builder.add_virtual_target();
+        builder.add_generator_fn(&[x], move |witness, out| {
+            out.set_target(x_inv, witness.get_target(x).inverse())
+        });
+        let product = builder.mul(x, x_inv);
+        builder.assert_one(product);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(7))?;
+        let proof = data.prove(pw)?;
+        data.verify(proof)?;
+
+        // Closures can't be serialized.
+        let generator_serializer = DefaultGeneratorSerializer::<C, D>::default();
+        assert!(data
+            .to_bytes(&DefaultGateSerializer, &generator_serializer)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stall_error() {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        builder.push_context(log::Level::Debug, "squaring");
+        let x2 = builder.mul(x, x);
+        builder.pop_context();
+        builder.register_public_input(x2);
+        let data = builder.build::<C>();
+
+        let err = generate_partial_witness(PartialWitness::new(), &data.prover_only, &data.common)
+            .unwrap_err()
+            .to_string();
+        // `x` is unset, so the multiplication is blocked, and so are hashing its public output and
+        // filling the unused public input wires from that hash.
+        assert!(err.starts_with("3 generators weren't run"), "{err}");
+        assert!(
+            err.contains("ArithmeticBaseGenerator is waiting for wire (0, 0) in root > squaring"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_generator_error_context() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let access_index = builder.add_virtual_target();
+        builder.push_context(log::Level::Debug, "lookup");
+        let v = (0..4)
+            .map(|i| builder.constant(F::from_canonical_u64(i)))
+            .collect();
+        builder.random_access(access_index, v);
+        builder.pop_context();
+        let [x, y] = builder.add_virtual_target_arr();
+        builder.push_context(log::Level::Debug, "inverting");
+        builder.add_generator_fn(&[x], move |witness, out_buffer| {
+            let x_value = witness.get_target(x);
+            ensure!(x_value.is_nonzero(), "Zero has no inverse");
+            out_buffer.set_target(y, x_value.inverse())
+        });
+        builder.pop_context();
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(access_index, F::from_canonical_u64(4))?;
+        pw.set_target(x, F::TWO)?;
+        let err = generate_partial_witness(pw, &data.prover_only, &data.common).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "RandomAccessGenerator added in root > lookup failed"
+        );
+        assert!(err.downcast_ref::<crate::error::Error>().is_some());
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(access_index, F::ONE)?;
+        pw.set_target(x, F::ZERO)?;
+        let err = generate_partial_witness(pw, &data.prover_only, &data.common).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "FnGenerator added in root > inverting failed: Zero has no inverse"
+        );
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(access_index, F::ONE)?;
+        pw.set_target(x, F::TWO)?;
+        pw.set_target(y, F::TWO)?;
+        let err = generate_partial_witness(pw, &data.prover_only, &data.common).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Setting an output of FnGenerator added in root > inverting"
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "timing")]
+    #[test]
+    fn test_generator_timing_by_context() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        builder.push_context(log::Level::Debug, "hashing");
+        let h = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x]);
+        builder.pop_context();
+        builder.register_public_inputs(&h.elements);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE)?;
+        let mut timing = TimingTree::default();
+        generate_partial_witness_with_timing(pw, &data.prover_only, &data.common, &mut timing)?;
+        assert!(timing.find("root > hashing").is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_witness_template() -> Result<()> {
+        let config = CircuitConfig::standard_recursion_config();
+        // Each FRI query round needs its own blinding rows, so use only a few at a lower security.
+        let zk_config = CircuitConfig {
+            zero_knowledge: true,
+            ..CircuitConfig::fast_config()
+        };
+        for config in [config, zk_config] {
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            // This hash only depends on constants, so it can be computed once in the template.
+            let constants = builder.constants(&[F::ONE, F::TWO, F::from_canonical_u64(3)]);
+            let hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(constants);
+            let x = builder.add_virtual_target();
+            let y = builder.add(x, hash.elements[0]);
+            builder.register_public_input(y);
+            let data = builder.build::<C>();
+
+            let template = data.witness_template()?;
+            assert!(template.num_finished_generators() > 0);
+            assert!(template.num_finished_generators() < data.prover_only.generators.len());
+
+            for i in 0..2 {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_u64(i))?;
+                let witness = generate_partial_witness_with_template(
+                    pw.clone(),
+                    &template,
+                    &data.prover_only,
+                    &data.common,
+                )?;
+                let expected =
+                    generate_partial_witness(pw.clone(), &data.prover_only, &data.common)?;
+                assert_eq!(witness.get_target(y), expected.get_target(y));
+                if !data.common.config.zero_knowledge {
+                    assert_eq!(witness.values, expected.values);
+                }
+
+                data.verify(data.prove_with_template(pw, &template)?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_witness_template_rejects_other_circuit() -> Result<()> {
+        // Two circuits of the same shape, differing only in a constant.
+        let build = |c: u64| {
+            let mut builder =
+                CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+            let x = builder.add_virtual_target();
+            let c = builder.constant(F::from_canonical_u64(c));
+            let y = builder.mul(x, c);
+            builder.register_public_input(y);
+            (builder.build::<C>(), x)
+        };
+        let (data, x) = build(2);
+        let (other_data, _) = build(3);
+        assert_eq!(
+            data.prover_only.generators.len(),
+            other_data.prover_only.generators.len()
+        );
+
+        let template = other_data.witness_template()?;
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::ONE)?;
+        assert!(generate_partial_witness_with_template(
+            pw,
+            &template,
+            &data.prover_only,
+            &data.common
+        )
+        .is_err());
+        Ok(())
+    }
+}
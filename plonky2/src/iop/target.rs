@@ -59,6 +59,18 @@ impl Target {
         }
     }
 
+    /// The inverse of `index`.
+    pub(crate) const fn from_index(index: usize, num_wires: usize, degree: usize) -> Self {
+        let num_wire_targets = num_wires * degree;
+        if index < num_wire_targets {
+            Self::wire(index / num_wires, index % num_wires)
+        } else {
+            Target::VirtualTarget {
+                index: index - num_wire_targets,
+            }
+        }
+    }
+
     /// Conversion to an `ExtensionTarget`.
     pub const fn to_ext_target<const D: usize>(self, zero: Self) -> ExtensionTarget<D> {
         let mut arr = [zero; D];
@@ -68,6 +80,10 @@ impl Target {
 }
 
 /// A `Target` which has already been constrained such that it can only be 0 or 1.
+///
+/// Flags taken by `select`, `_if`, `conditional_assert_eq` and the like are `BoolTarget`s, so that
+/// an unconstrained `Target` can't be used as one by mistake. Safe ways of getting one include
+/// `add_virtual_bool_target_safe`, `constrain_bool`, `is_equal`, `is_less_than` and `split_le`.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 #[allow(clippy::manual_non_exhaustive)]
 pub struct BoolTarget {
@@ -77,6 +93,8 @@ pub struct BoolTarget {
 }
 
 impl BoolTarget {
+    /// Wraps `target` without constraining it. The caller must ensure that `target` is
+    /// constrained to be 0 or 1 elsewhere in the circuit.
     pub const fn new_unsafe(target: Target) -> BoolTarget {
         BoolTarget {
             target,
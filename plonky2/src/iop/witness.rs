@@ -2,22 +2,27 @@
 use alloc::{vec, vec::Vec};
 use core::iter::zip;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, ensure, Result};
 use hashbrown::HashMap;
 use itertools::{zip_eq, Itertools};
+use num::{BigUint, ToPrimitive};
 
+use crate::error::Error;
 use crate::field::extension::{Extendable, FieldExtension};
 use crate::field::types::Field;
 use crate::fri::structure::{FriOpenings, FriOpeningsTarget};
-use crate::fri::witness_util::set_fri_proof_target;
+use crate::fri::witness_util::{set_fri_proof_target, set_padded_fri_proof_target};
 use crate::hash::hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::wire::Wire;
-use crate::plonk::circuit_data::{VerifierCircuitTarget, VerifierOnlyCircuitData};
+use crate::plonk::circuit_data::{
+    CommonCircuitData, ProverOnlyCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
+};
 use crate::plonk::config::{AlgebraicHasher, GenericConfig};
 use crate::plonk::proof::{Proof, ProofTarget, ProofWithPublicInputs, ProofWithPublicInputsTarget};
+use crate::util::serialization::{Buffer, Read, Write};
 
 pub trait WitnessWrite<F: Field> {
     fn set_target(&mut self, target: Target, value: F) -> Result<()>;
@@ -84,6 +89,79 @@ pub trait WitnessWrite<F: Field> {
         self.set_target(target.target, F::from_bool(value))
     }
 
+    fn set_bool_targets(&mut self, targets: &[BoolTarget], values: &[bool]) -> Result<()> {
+        ensure_same_len(targets.len(), values.len())?;
+        for (&target, &value) in zip(targets, values) {
+            self.set_bool_target(target, value)?;
+        }
+
+        Ok(())
+    }
+
+    fn set_hash_targets(&mut self, targets: &[HashOutTarget], values: &[HashOut<F>]) -> Result<()> {
+        ensure_same_len(targets.len(), values.len())?;
+        for (&target, &value) in zip(targets, values) {
+            self.set_hash_target(target, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets a target to a `u64`, failing if it isn't below the field order.
+    fn set_u64_target(&mut self, target: Target, value: u64) -> Result<()> {
+        self.set_target(target, canonical_u64(u64_order::<F>(), value)?)
+    }
+
+    fn set_u64_targets(&mut self, targets: &[Target], values: &[u64]) -> Result<()> {
+        ensure_same_len(targets.len(), values.len())?;
+        let order = u64_order::<F>();
+        for (&target, &value) in zip(targets, values) {
+            self.set_target(target, canonical_u64(order, value)?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets a target to a `u32`, failing if it isn't below the field order.
+    fn set_u32_target(&mut self, target: Target, value: u32) -> Result<()> {
+        self.set_u64_target(target, value.into())
+    }
+
+    fn set_u32_targets(&mut self, targets: &[Target], values: &[u32]) -> Result<()> {
+        ensure_same_len(targets.len(), values.len())?;
+        let order = u64_order::<F>();
+        for (&target, &value) in zip(targets, values) {
+            self.set_target(target, canonical_u64(order, value.into())?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets targets to the bytes of `values`, one byte per target.
+    fn set_bytes_target(&mut self, targets: &[Target], values: &[u8]) -> Result<()> {
+        ensure_same_len(targets.len(), values.len())?;
+        let order = u64_order::<F>();
+        for (&target, &value) in zip(targets, values) {
+            self.set_target(target, canonical_u64(order, value.into())?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets `limbs` to the little-endian 32-bit limbs of `value`, failing if `value` doesn't fit
+    /// in that many limbs.
+    fn set_biguint_target(&mut self, limbs: &[Target], value: &BigUint) -> Result<()> {
+        let mut digits = value.to_u32_digits();
+        ensure!(
+            digits.len() <= limbs.len(),
+            "{} doesn't fit in {} 32-bit limbs",
+            value,
+            limbs.len()
+        );
+        digits.resize(limbs.len(), 0);
+        self.set_u32_targets(limbs, &digits)
+    }
+
     /// Set the targets in a `ProofWithPublicInputsTarget` to their corresponding values in a
     /// `ProofWithPublicInputs`.
     fn set_proof_with_pis_target<C: GenericConfig<D, F = F>, const D: usize>(
@@ -137,6 +215,47 @@ pub trait WitnessWrite<F: Field> {
         set_fri_proof_target(self, &proof_target.opening_proof, &proof.opening_proof)
     }
 
+    /// Same as `set_proof_with_pis_target`, except that the proof may come from a circuit of smaller
+    /// degree than the one `proof_with_pis_target` was shaped for, as in
+    /// `CircuitBuilder::verify_proof_with_variable_degree`. The unused parts of the target are
+    /// padded with zeros.
+    fn set_padded_proof_with_pis_target<C: GenericConfig<D, F = F>, const D: usize>(
+        &mut self,
+        proof_with_pis_target: &ProofWithPublicInputsTarget<D>,
+        proof_with_pis: &ProofWithPublicInputs<F, C, D>,
+    ) -> Result<()>
+    where
+        F: RichField + Extendable<D>,
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        let ProofWithPublicInputs {
+            proof,
+            public_inputs,
+        } = proof_with_pis;
+        let ProofWithPublicInputsTarget {
+            proof: pt,
+            public_inputs: pi_targets,
+        } = proof_with_pis_target;
+
+        for (&pi_t, &pi) in pi_targets.iter().zip_eq(public_inputs) {
+            self.set_target(pi_t, pi)?;
+        }
+
+        self.set_cap_target(&pt.wires_cap, &proof.wires_cap)?;
+        self.set_cap_target(
+            &pt.plonk_zs_partial_products_cap,
+            &proof.plonk_zs_partial_products_cap,
+        )?;
+        self.set_cap_target(&pt.quotient_polys_cap, &proof.quotient_polys_cap)?;
+
+        self.set_fri_openings(
+            &pt.openings.to_fri_openings(),
+            &proof.openings.to_fri_openings(),
+        )?;
+
+        set_padded_fri_proof_target(self, &pt.opening_proof, &proof.opening_proof)
+    }
+
     fn set_fri_openings<const D: usize>(
         &mut self,
         fri_openings_target: &FriOpeningsTarget<D>,
@@ -207,13 +326,28 @@ pub trait Witness<F: Field>: WitnessWrite<F> {
     fn try_get_target(&self, target: Target) -> Option<F>;
 
     fn get_target(&self, target: Target) -> F {
-        self.try_get_target(target).unwrap()
+        self.try_get_target(target)
+            .unwrap_or_else(|| panic!("{}", Error::TargetNotSet(target)))
+    }
+
+    /// Like `get_target`, but fails with [`Error::TargetNotSet`] rather than panicking.
+    fn get_target_checked(&self, target: Target) -> Result<F> {
+        self.try_get_target(target)
+            .ok_or_else(|| Error::TargetNotSet(target).into())
     }
 
     fn get_targets(&self, targets: &[Target]) -> Vec<F> {
         targets.iter().map(|&t| self.get_target(t)).collect()
     }
 
+    /// Like `get_targets`, but fails with [`Error::TargetNotSet`] rather than panicking.
+    fn get_targets_checked(&self, targets: &[Target]) -> Result<Vec<F>> {
+        targets
+            .iter()
+            .map(|&t| self.get_target_checked(t))
+            .collect()
+    }
+
     fn get_extension_target<const D: usize>(&self, et: ExtensionTarget<D>) -> F::Extension
     where
         F: RichField + Extendable<D>,
@@ -233,14 +367,20 @@ pub trait Witness<F: Field>: WitnessWrite<F> {
     }
 
     fn get_bool_target(&self, target: BoolTarget) -> bool {
-        let value = self.get_target(target.target);
+        self.get_bool_target_checked(target)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `get_bool_target`, but fails with an [`Error`] rather than panicking.
+    fn get_bool_target_checked(&self, target: BoolTarget) -> Result<bool> {
+        let value = self.get_target_checked(target.target)?;
         if value.is_zero() {
-            return false;
+            return Ok(false);
         }
         if value.is_one() {
-            return true;
+            return Ok(true);
         }
-        panic!("not a bool")
+        Err(Error::NotABool(target.target).into())
     }
 
     fn get_hash_target(&self, ht: HashOutTarget) -> HashOut<F> {
@@ -279,7 +419,7 @@ pub trait Witness<F: Field>: WitnessWrite<F> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MatrixWitness<F: Field> {
     pub(crate) wire_values: Vec<Vec<F>>,
 }
@@ -327,6 +467,34 @@ impl<F: Field> Witness<F> for PartialWitness<F> {
     }
 }
 
+fn ensure_same_len(num_targets: usize, num_values: usize) -> Result<()> {
+    ensure!(
+        num_targets == num_values,
+        "Got {} values for {} targets",
+        num_values,
+        num_targets
+    );
+    Ok(())
+}
+
+/// Returns the field order if it fits in a `u64`, in which case it bounds the `u64`s which can be
+/// set without wrapping around.
+fn u64_order<F: Field>() -> Option<u64> {
+    F::order().to_u64()
+}
+
+fn canonical_u64<F: Field>(order: Option<u64>, value: u64) -> Result<F> {
+    if let Some(order) = order {
+        ensure!(
+            value < order,
+            "{} is out of range for a field of order {}",
+            value,
+            order
+        );
+    }
+    Ok(F::from_canonical_u64(value))
+}
+
 /// `PartitionWitness` holds a disjoint-set forest of the targets respecting a circuit's copy constraints.
 /// The value of a target is defined to be the value of its root in the forest.
 #[derive(Clone, Debug)]
@@ -388,6 +556,55 @@ impl<'a, F: Field> PartitionWitness<'a, F> {
     }
 }
 
+impl<'a, F: RichField> PartitionWitness<'a, F> {
+    /// Serializes this witness along with the digest of its circuit, so that proving can be
+    /// decoupled from witness generation, or a failing proof replayed from the same witness with
+    /// `prove_with_partition_witness`.
+    pub fn to_bytes<C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+    ) -> Vec<u8>
+    where
+        F: Extendable<D>,
+    {
+        let mut buffer = Vec::new();
+        buffer
+            .write_hash::<F, C::Hasher>(prover_data.circuit_digest)
+            .and_then(|()| buffer.write_partition_witness(self))
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    /// Deserializes a witness written by `to_bytes`, failing if it was generated for a different
+    /// circuit.
+    pub fn from_bytes<C: GenericConfig<D, F = F>, const D: usize>(
+        bytes: &[u8],
+        prover_data: &'a ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<Self>
+    where
+        F: Extendable<D>,
+    {
+        let mut buffer = Buffer::new(bytes);
+        let circuit_digest = buffer
+            .read_hash::<F, C::Hasher>()
+            .map_err(anyhow::Error::msg)?;
+        ensure!(
+            circuit_digest == prover_data.circuit_digest,
+            "The witness was generated for a different circuit."
+        );
+        let witness = buffer
+            .read_partition_witness(&prover_data.representative_map)
+            .map_err(anyhow::Error::msg)?;
+        ensure!(
+            witness.num_wires == common_data.config.num_wires
+                && witness.degree == common_data.degree(),
+            "The witness doesn't match the circuit's shape."
+        );
+        Ok(witness)
+    }
+}
+
 impl<'a, F: Field> WitnessWrite<F> for PartitionWitness<'a, F> {
     fn set_target(&mut self, target: Target, value: F) -> Result<()> {
         self.set_target_returning_rep(target, value).map(|_| ())
@@ -400,3 +617,112 @@ impl<'a, F: Field> Witness<F> for PartitionWitness<'a, F> {
         self.values[rep_index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use num::BigUint;
+
+    use super::*;
+    use crate::field::goldilocks_field::GoldilocksField;
+    use crate::field::types::{Field64, Sample};
+    use crate::iop::generator::generate_partial_witness;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::plonk::prover::prove_with_partition_witness;
+    use crate::util::timing::TimingTree;
+
+    type F = GoldilocksField;
+
+    #[test]
+    fn test_typed_setters() -> Result<()> {
+        let targets = (0..4)
+            .map(|index| Target::VirtualTarget { index })
+            .collect::<Vec<_>>();
+
+        let mut pw = PartialWitness::<F>::new();
+        pw.set_u64_targets(&targets[..2], &[0, F::ORDER - 1])?;
+        assert_eq!(pw.get_target(targets[1]), F::NEG_ONE);
+        assert!(pw.set_u64_target(targets[2], F::ORDER).is_err());
+        assert!(pw.set_u32_targets(&targets, &[1, 2]).is_err());
+
+        let mut pw = PartialWitness::<F>::new();
+        pw.set_bytes_target(&targets[..3], &[1, 2, 255])?;
+        assert_eq!(
+            pw.get_targets(&targets[..3]),
+            [1, 2, 255].map(F::from_canonical_u64)
+        );
+
+        let mut pw = PartialWitness::<F>::new();
+        let value = (BigUint::from(3u32) << 64) + 5u32;
+        pw.set_biguint_target(&targets, &value)?;
+        assert_eq!(
+            pw.get_targets(&targets),
+            [5, 0, 3, 0].map(F::from_canonical_u64)
+        );
+        assert!(pw.set_biguint_target(&targets[..2], &value).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_checked_getters() -> Result<()> {
+        let x = Target::VirtualTarget { index: 0 };
+        let y = Target::VirtualTarget { index: 1 };
+        let mut pw = PartialWitness::<F>::new();
+        pw.set_target(x, F::TWO)?;
+
+        assert_eq!(pw.get_targets_checked(&[x])?, [F::TWO]);
+        let err = pw.get_targets_checked(&[x, y]).unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::TargetNotSet(y)));
+        let err = pw
+            .get_bool_target_checked(BoolTarget::new_unsafe(x))
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::NotABool(x)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_partition_witness_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+
+        let build = |num_squarings| {
+            let mut builder =
+                CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+            let x = builder.add_virtual_public_input();
+            let mut y = x;
+            for _ in 0..num_squarings {
+                y = builder.square(y);
+            }
+            builder.register_public_input(y);
+            (x, builder.build::<C>())
+        };
+        let (x, data) = build(3);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand())?;
+        let witness = generate_partial_witness(pw, &data.prover_only, &data.common)?;
+        let bytes = witness.to_bytes(&data.prover_only);
+        let witness_from_bytes =
+            PartitionWitness::from_bytes(&bytes, &data.prover_only, &data.common)?;
+        assert_eq!(witness_from_bytes.values, witness.values);
+
+        let proof = prove_with_partition_witness(
+            &data.prover_only,
+            &data.common,
+            witness_from_bytes,
+            &mut TimingTree::default(),
+        )?;
+        data.verify(proof)?;
+
+        let (_, other_data) = build(4);
+        assert!(
+            PartitionWitness::from_bytes(&bytes, &other_data.prover_only, &other_data.common)
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
@@ -11,7 +11,15 @@ pub extern crate alloc;
 #[doc(inline)]
 pub use plonky2_field as field;
 
+// Lets tests use derive macros, which refer to this crate as `::plonky2`.
+#[cfg(test)]
+extern crate self as plonky2;
+
+pub mod backend;
 pub mod batch_fri;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod fri;
 pub mod gadgets;
 pub mod gates;
@@ -19,7 +27,11 @@ pub mod hash;
 pub mod iop;
 pub mod plonk;
 pub mod recursion;
+#[cfg(feature = "scale")]
+pub mod scale;
 pub mod util;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 #[cfg(test)]
 mod lookup_test;
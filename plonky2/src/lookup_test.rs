@@ -6,6 +6,7 @@ use std::sync::{Arc, Once};
 use itertools::Itertools;
 use log::Level;
 
+use crate::error::Error;
 use crate::field::types::Field;
 use crate::gadgets::lookup::{OTHER_TABLE, SMALLER_TABLE, TIP5_TABLE};
 use crate::gates::lookup_table::LookupTable;
@@ -509,6 +510,45 @@ fn test_many_lookups_on_big_lut() -> anyhow::Result<()> {
     data.verify(proof)
 }
 
+// Range checks values through lookups in the shared byte table.
+#[test]
+fn test_range_check_with_lookup() -> anyhow::Result<()> {
+    init_logger();
+
+    let config = CircuitConfig::standard_recursion_config();
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+
+    let x = builder.add_virtual_target();
+    let y = builder.add_virtual_target();
+    builder.range_check_with_lookup(x, 12);
+    let y_bytes = builder.split_le_bytes(y, 32);
+    builder.register_public_inputs(&y_bytes);
+    assert_eq!(builder.num_luts(), 1);
+
+    let data = builder.build::<C>();
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64((1 << 12) - 1))?;
+    pw.set_target(y, F::from_canonical_u64(0x12345678))?;
+    let proof = data.prove(pw)?;
+    assert_eq!(
+        proof.public_inputs,
+        [0x78, 0x56, 0x34, 0x12].map(F::from_canonical_u64)
+    );
+    data.verify(proof)?;
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(x, F::from_canonical_u64(1 << 12))?;
+    pw.set_target(y, F::ZERO)?;
+    let err = data.prove(pw).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::LookupInputNotInTable { .. })
+    ));
+
+    Ok(())
+}
+
 fn init_logger() {
     #[cfg(feature = "std")]
     {
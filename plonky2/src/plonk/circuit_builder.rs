@@ -1,14 +1,23 @@
 //! Logic for building plonky2 circuits.
 
 #[cfg(not(feature = "std"))]
-use alloc::{collections::BTreeMap, sync::Arc, vec, vec::Vec};
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
 use core::cmp::max;
+use core::iter;
 #[cfg(feature = "std")]
 use std::{collections::BTreeMap, sync::Arc};
 
+use anyhow::{ensure, Context, Result};
 use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
-use log::{debug, info, warn, Level};
+use log::{debug, warn, Level};
 #[cfg(feature = "timing")]
 use web_time::Instant;
 
@@ -30,24 +39,30 @@ use crate::gates::lookup::{Lookup, LookupGate};
 use crate::gates::lookup_table::LookupTable;
 use crate::gates::noop::NoopGate;
 use crate::gates::public_input::PublicInputGate;
+use crate::gates::random_access::RandomAccessGate;
 use crate::gates::selectors::{selector_ends_lookups, selector_polynomials, selectors_lookup};
-use crate::hash::hash_types::{HashOut, HashOutTarget, MerkleCapTarget, RichField};
+use crate::hash::hash_types::{
+    HashOut, HashOutTarget, MerkleCapTarget, RichField, NUM_HASH_OUT_ELTS,
+};
 use crate::hash::merkle_proofs::MerkleProofTarget;
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::{
-    ConstantGenerator, CopyGenerator, RandomValueGenerator, SimpleGenerator, WitnessGeneratorRef,
+    ConstantGenerator, CopyGenerator, FnGenerator, GeneratedValues, RandomValueGenerator,
+    SeededValueGenerator, SimpleGenerator, WitnessGeneratorRef,
 };
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::wire::Wire;
+use crate::iop::witness::PartitionWitness;
 use crate::plonk::circuit_data::{
-    CircuitConfig, CircuitData, CommonCircuitData, MockCircuitData, ProverCircuitData,
+    context_at, CircuitConfig, CircuitData, CommonCircuitData, MockCircuitData, ProverCircuitData,
     ProverOnlyCircuitData, VerifierCircuitData, VerifierCircuitTarget, VerifierOnlyCircuitData,
 };
 use crate::plonk::config::{AlgebraicHasher, GenericConfig, GenericHashOut, Hasher};
 use crate::plonk::copy_constraint::CopyConstraint;
 use crate::plonk::permutation_argument::Forest;
 use crate::plonk::plonk_common::PlonkOracle;
+use crate::plonk::public_inputs::PublicInputNames;
 use crate::timed;
 use crate::util::context_tree::ContextTree;
 use crate::util::partial_products::num_partial_products;
@@ -156,6 +171,9 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     /// Targets to be made public.
     public_inputs: Vec<Target>,
 
+    /// Names given to groups of public inputs.
+    public_input_names: PublicInputNames,
+
     /// The next available index for a `VirtualTarget`.
     virtual_target_index: usize,
 
@@ -167,6 +185,10 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     /// Generators used to generate the witness.
     generators: Vec<WitnessGeneratorRef<F, D>>,
 
+    /// The stack of contexts in effect when each listed generator onwards was added, as
+    /// `(generator index, stack)` pairs sorted by generator index.
+    generator_contexts: Vec<(usize, String)>,
+
     constants_to_targets: HashMap<F, Target>,
     targets_to_constants: HashMap<Target, F>,
 
@@ -200,24 +222,36 @@ pub struct CircuitBuilder<F: RichField + Extendable<D>, const D: usize> {
     /// Optional verifier data that is registered as public inputs.
     /// This is used in cyclic recursion to hold the circuit's own verifier key.
     pub(crate) verifier_data_public_input: Option<VerifierCircuitTarget>,
+
+    /// Whether `build` replaces the configured Merkle cap height with the one given by
+    /// `optimal_cap_height` for the circuit's degree.
+    auto_cap_height: bool,
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// Given a [`CircuitConfig`], generate a new [`CircuitBuilder`] instance.
     /// It will also check that the configuration provided is consistent, i.e.
     /// that the different parameters provided can achieve the targeted security
-    /// level.
+    /// level, and panic otherwise; see [`CircuitBuilder::try_new`].
     pub fn new(config: CircuitConfig) -> Self {
+        Self::try_new(config).expect("Invalid circuit config")
+    }
+
+    /// Same as [`CircuitBuilder::new`], but returns an error if the configuration is
+    /// inconsistent.
+    pub fn try_new(config: CircuitConfig) -> Result<Self> {
         let builder = CircuitBuilder {
             config,
             domain_separator: None,
             gates: HashSet::new(),
             gate_instances: Vec::new(),
             public_inputs: Vec::new(),
+            public_input_names: PublicInputNames::default(),
             virtual_target_index: 0,
             copy_constraints: Vec::new(),
             context_log: ContextTree::new(),
             generators: Vec::new(),
+            generator_contexts: Vec::new(),
             constants_to_targets: HashMap::new(),
             targets_to_constants: HashMap::new(),
             base_arithmetic_results: HashMap::new(),
@@ -229,34 +263,45 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             luts: Vec::new(),
             goal_common_data: None,
             verifier_data_public_input: None,
+            auto_cap_height: false,
         };
-        builder.check_config();
-        builder
+        builder.check_config()?;
+        Ok(builder)
     }
 
-    /// Assert that the configuration used to create this `CircuitBuilder` is consistent,
+    /// Checks that the configuration used to create this `CircuitBuilder` is consistent,
     /// i.e. that the different parameters meet the targeted security level.
-    fn check_config(&self) {
+    fn check_config(&self) -> Result<()> {
         let &CircuitConfig {
             security_bits,
-            fri_config:
-                FriConfig {
-                    rate_bits,
-                    proof_of_work_bits,
-                    num_query_rounds,
-                    ..
-                },
+            fri_config: FriConfig {
+                proof_of_work_bits, ..
+            },
             ..
         } = &self.config;
 
-        // Conjectured FRI security; see the ethSTARK paper.
+        self.config.validate()?;
+
+        // Grinding could never succeed if it required more leading zeros than the field has bits.
+        ensure!(
+            (proof_of_work_bits as u64) < F::order().bits(),
+            "{} proof-of-work bits are too many for a {}-bit field",
+            proof_of_work_bits,
+            F::order().bits()
+        );
+
+        // FRI security under the config's soundness assumption. Under `FriSecurity::Proven`, this
+        // is an upper bound which ignores the commit phase error, checked once the degree is known.
         let fri_field_bits = F::Extension::order().bits() as usize;
-        let fri_query_security_bits = num_query_rounds * rate_bits + proof_of_work_bits as usize;
-        let fri_security_bits = fri_field_bits.min(fri_query_security_bits);
-        assert!(
+        let fri_security_bits = self
+            .config
+            .fri_config
+            .query_security_bits(self.config.fri_security, fri_field_bits);
+        ensure!(
             fri_security_bits >= security_bits,
             "FRI params fall short of target security"
         );
+        Ok(())
     }
 
     pub fn set_domain_separator(&mut self, separator: Vec<F>) {
@@ -264,6 +309,75 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.domain_separator = Some(separator);
     }
 
+    /// Lets `build` choose the Merkle cap height once the circuit's degree is known, rather than
+    /// using the one in `config`; see `optimal_cap_height`.
+    pub fn set_auto_cap_height(&mut self) {
+        assert!(
+            self.verifier_data_public_input.is_none(),
+            "Verifier data public inputs depend on the cap height"
+        );
+        self.auto_cap_height = true;
+    }
+
+    /// The Merkle cap height minimizing the number of hashes in proofs of a circuit with
+    /// `2^degree_bits` rows, plus the number of gates needed to verify them recursively. A higher
+    /// cap shortens the Merkle paths of every query, but adds to every commitment's cap and makes
+    /// picking a cap entry in-circuit more expensive.
+    ///
+    /// All commitments share the one cap height, so this optimizes their total rather than each
+    /// tree separately.
+    pub fn optimal_cap_height(&self, degree_bits: usize) -> usize {
+        let lde_bits = degree_bits + self.config.fri_config.rate_bits;
+        let num_query_rounds = self.config.fri_config.num_query_rounds;
+        (0..=lde_bits)
+            .filter_map(|cap_height| {
+                let fri_params = FriConfig {
+                    cap_height,
+                    ..self.config.fri_config.clone()
+                }
+                .fri_params(degree_bits, self.config.zero_knowledge);
+                // The cap can't be higher than the smallest tree.
+                if fri_params.total_arities() > lde_bits - cap_height {
+                    return None;
+                }
+
+                // Picking a cap entry takes a random access per hash element.
+                let cap_access_gates = if cap_height == 0 {
+                    0
+                } else {
+                    let num_copies =
+                        RandomAccessGate::<F, D>::new_from_config(&self.config, cap_height)
+                            .num_copies;
+                    if num_copies == 0 {
+                        return None;
+                    }
+                    NUM_HASH_OUT_ELTS.div_ceil(num_copies)
+                };
+
+                // The Plonk oracles, followed by the trees of the FRI commit phase.
+                let tree_heights = iter::repeat_n(lde_bits, PlonkOracle::COUNT).chain(
+                    fri_params
+                        .reduction_arity_bits
+                        .iter()
+                        .scan(lde_bits, |height, &arity_bits| {
+                            *height -= arity_bits;
+                            Some(*height)
+                        }),
+                );
+                // Each sibling is both a hash in the proof and a hashing gate in the verifier.
+                let cost = tree_heights
+                    .map(|height| {
+                        (1 << cap_height)
+                            + num_query_rounds * (2 * (height - cap_height) + cap_access_gates)
+                    })
+                    .sum::<usize>();
+                Some((cost, cap_height))
+            })
+            .min()
+            .expect("No valid cap height")
+            .1
+    }
+
     /// Outputs the number of gates in this circuit.
     pub fn num_gates(&self) -> usize {
         self.gate_instances.len()
@@ -279,6 +393,20 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         targets.iter().for_each(|&t| self.register_public_input(t));
     }
 
+    /// Registers the given target as a public input named `name`, whose value can then be read
+    /// from proofs with `ProofWithPublicInputs::named_public_inputs`. Panics if `name` is taken.
+    pub fn register_public_input_named(&mut self, target: Target, name: &str) {
+        self.register_public_inputs_named(&[target], name);
+    }
+
+    /// Registers the given targets as a group of public inputs named `name`.
+    pub fn register_public_inputs_named(&mut self, targets: &[Target], name: &str) {
+        let start = self.public_inputs.len();
+        self.register_public_inputs(targets);
+        self.public_input_names
+            .push(name, start..self.public_inputs.len());
+    }
+
     /// Outputs the number of public inputs in this circuit.
     pub fn num_public_inputs(&self) -> usize {
         self.public_inputs.len()
@@ -392,10 +520,13 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         PolynomialCoeffsExtTarget(coeffs)
     }
 
+    /// Adds a virtual [`BoolTarget`] without constraining it to be boolean. Only use this when
+    /// the caller adds such a constraint itself; otherwise see [`Self::add_virtual_bool_target_safe`].
     pub fn add_virtual_bool_target_unsafe(&mut self) -> BoolTarget {
         BoolTarget::new_unsafe(self.add_virtual_target())
     }
 
+    /// Adds a virtual [`BoolTarget`], constrained to be 0 or 1.
     pub fn add_virtual_bool_target_safe(&mut self) -> BoolTarget {
         let b = BoolTarget::new_unsafe(self.add_virtual_target());
         self.assert_bool(b);
@@ -431,6 +562,10 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self.verifier_data_public_input.is_none(),
             "add_verifier_data_public_inputs only needs to be called once"
         );
+        assert!(
+            !self.auto_cap_height,
+            "Verifier data public inputs depend on the cap height"
+        );
 
         let verifier_data = self.add_virtual_verifier_data(self.config.fri_config.cap_height);
         // The verifier data are public inputs.
@@ -443,11 +578,24 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         verifier_data
     }
 
-    /// Adds a gate to the circuit, and returns its index.
-    pub fn add_gate<G: Gate<F, D>>(&mut self, gate_type: G, mut constants: Vec<F>) -> usize {
-        self.check_gate_compatibility(&gate_type);
+    /// Adds a gate to the circuit, and returns its index. Panics if the gate doesn't fit in the
+    /// circuit's rows; see [`CircuitBuilder::try_add_gate`].
+    pub fn add_gate<G: Gate<F, D>>(&mut self, gate_type: G, constants: Vec<F>) -> usize {
+        self.try_add_gate(gate_type, constants)
+            .expect("Incompatible gate")
+    }
 
-        assert!(
+    /// Same as [`CircuitBuilder::add_gate`], but returns an error if the gate doesn't fit in the
+    /// circuit's rows or is given too many constants.
+    pub fn try_add_gate<G: Gate<F, D>>(
+        &mut self,
+        gate_type: G,
+        mut constants: Vec<F>,
+    ) -> Result<usize> {
+        gate_type
+            .check_compatibility(&self.config)
+            .with_context(|| format!("Adding a gate in {}", self.context_log.open_stack()))?;
+        ensure!(
             constants.len() <= gate_type.num_constants(),
             "Too many constants."
         );
@@ -478,24 +626,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             constants,
         });
 
-        row
-    }
-
-    fn check_gate_compatibility<G: Gate<F, D>>(&self, gate: &G) {
-        assert!(
-            gate.num_wires() <= self.config.num_wires,
-            "{:?} requires {} wires, but our CircuitConfig has only {}",
-            gate.id(),
-            gate.num_wires(),
-            self.config.num_wires
-        );
-        assert!(
-            gate.num_constants() <= self.config.num_constants,
-            "{:?} requires {} constants, but our CircuitConfig has only {}",
-            gate.id(),
-            gate.num_constants(),
-            self.config.num_constants
-        );
+        Ok(row)
     }
 
     /// Adds a gate type to the set of gates to be used in this circuit. This can be useful
@@ -541,10 +672,10 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     /// If `condition`, enforces that two routable `Target` values are equal, using Plonk's permutation argument.
-    pub fn conditional_assert_eq(&mut self, condition: Target, x: Target, y: Target) {
+    pub fn conditional_assert_eq(&mut self, condition: BoolTarget, x: Target, y: Target) {
         let zero = self.zero();
         let diff = self.sub(x, y);
-        let constr = self.mul(condition, diff);
+        let constr = self.mul(condition.target, diff);
         self.connect(constr, zero);
     }
 
@@ -575,14 +706,38 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     }
 
     pub fn add_generators(&mut self, generators: Vec<WitnessGeneratorRef<F, D>>) {
+        self.enter_generator_context(self.context_log.open_stack());
         self.generators.extend(generators);
     }
 
     pub fn add_simple_generator<G: SimpleGenerator<F, D>>(&mut self, generator: G) {
+        self.enter_generator_context(self.context_log.open_stack());
         self.generators
             .push(WitnessGeneratorRef::new(generator.adapter()));
     }
 
+    /// Records that generators added from now on belong to `context`.
+    fn enter_generator_context(&mut self, context: String) {
+        let next = self.generators.len();
+        match self.generator_contexts.last_mut() {
+            Some((_, last_context)) if *last_context == context => {}
+            // The previous context contributed no generators, so it is replaced.
+            Some((last_generator, last_context)) if *last_generator == next => {
+                *last_context = context
+            }
+            _ => self.generator_contexts.push((next, context)),
+        }
+    }
+
+    /// Adds a generator which runs `f` once all of `deps` are set, for hints too simple to warrant
+    /// their own `SimpleGenerator`. Circuits using such generators can't be serialized.
+    pub fn add_generator_fn<G>(&mut self, deps: &[Target], f: G)
+    where
+        G: Fn(&PartitionWitness<F>, &mut GeneratedValues<F>) -> Result<()> + 'static + Send + Sync,
+    {
+        self.add_simple_generator(FnGenerator::new(deps.to_vec(), f));
+    }
+
     /// Returns a routable target with a value of 0.
     pub fn zero(&mut self) -> Target {
         self.constant(F::ZERO)
@@ -701,6 +856,20 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self.context_log.pop(self.num_gates());
     }
 
+    /// Makes `build` fail if the circuit has more than `max_rows` gates, not counting the
+    /// blinding and padding gates, with a breakdown of the gate count by context. Useful to catch
+    /// circuit size regressions in tests.
+    pub fn assert_gate_budget(&mut self, max_rows: usize) {
+        self.context_log.set_root_gate_budget(max_rows);
+    }
+
+    /// Makes `build` fail if the current context, i.e. the innermost one opened with
+    /// `with_context!`, adds more than `max_rows` gates; see
+    /// [`CircuitBuilder::assert_gate_budget`].
+    pub fn assert_context_gate_budget(&mut self, max_rows: usize) {
+        self.context_log.set_gate_budget(max_rows);
+    }
+
     /// Returns the total number of LUTs.
     pub fn get_luts_length(&self) -> usize {
         self.luts.len()
@@ -908,7 +1077,9 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
     fn blind(&mut self) {
         let (regular_poly_openings, z_openings) = self.blinding_counts();
-        info!(
+        debug!(
+            regular_poly_openings,
+            z_openings;
             "Adding {} blinding terms for witness polynomials, and {}*2 for Z polynomials",
             regular_poly_openings, z_openings
         );
@@ -962,56 +1133,17 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             .map(|g| g.0.num_constants())
             .max()
             .unwrap();
-        transpose(
-            &self
-                .gate_instances
-                .iter()
-                .map(|g| {
-                    let mut consts = g.constants.clone();
-                    consts.resize(max_constants, F::ZERO);
-                    consts
-                })
-                .collect::<Vec<_>>(),
-        )
-        .into_iter()
-        .map(PolynomialValues::new)
-        .collect()
+        gate_constant_polys(&self.gate_instances, max_constants)
     }
 
     fn sigma_vecs(&self, k_is: &[F], subgroup: &[F]) -> (Vec<PolynomialValues<F>>, Forest) {
-        let degree = self.gate_instances.len();
-        let degree_log = log2_strict(degree);
-        let config = &self.config;
-        let mut forest = Forest::new(
-            config.num_wires,
-            config.num_routed_wires,
-            degree,
+        sigma_polys(
+            &self.config,
+            self.gate_instances.len(),
             self.virtual_target_index,
-        );
-
-        for gate in 0..degree {
-            for input in 0..config.num_wires {
-                forest.add(Target::Wire(Wire {
-                    row: gate,
-                    column: input,
-                }));
-            }
-        }
-
-        for index in 0..self.virtual_target_index {
-            forest.add(Target::VirtualTarget { index });
-        }
-
-        for &CopyConstraint { pair: (a, b), .. } in &self.copy_constraints {
-            forest.merge(a, b);
-        }
-
-        forest.compress_paths();
-
-        let wire_partition = forest.wire_partition();
-        (
-            wire_partition.get_sigma_polys(degree_log, k_is, subgroup),
-            forest,
+            self.copy_constraints.iter().map(|c| c.pair),
+            k_is,
+            subgroup,
         )
     }
 
@@ -1023,13 +1155,13 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
         // Print total count of each gate type.
         debug!("Total gate counts:");
-        for gate in self.gates.iter().cloned() {
+        for gate in self.gates.iter().cloned().sorted_by_key(|g| g.0.id()) {
             let count = self
                 .gate_instances
                 .iter()
                 .filter(|inst| inst.gate_ref == gate)
                 .count();
-            debug!("- {} instances of {}", count, gate.0.id());
+            debug!(gate = gate.0.id().as_str(), count; "- {} instances of {}", count, gate.0.id());
         }
     }
 
@@ -1037,10 +1169,22 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
     /// mitigate this by randomizing some unused witness elements, so if proving fails with
     /// division by zero, the next attempt will have an (almost) independent chance of success.
     /// See <https://github.com/0xPolygonZero/plonky2/issues/456>.
-    fn randomize_unused_pi_wires(&mut self, pi_gate: usize) {
-        for wire in PublicInputGate::wires_public_inputs_hash().end..self.config.num_wires {
-            self.add_simple_generator(RandomValueGenerator {
-                target: Target::wire(pi_gate, wire),
+    ///
+    /// Zero-knowledge circuits use fresh random values, as their proofs are randomized anyway.
+    /// Other circuits derive the values from the public inputs hash, so that identical inputs
+    /// produce identical proofs; a statement that hits a division by zero will hit it again, but
+    /// any other statement still has an independent chance of success.
+    fn randomize_unused_pi_wires(&mut self, pi_gate: usize, public_inputs_hash: HashOutTarget) {
+        let targets = (PublicInputGate::wires_public_inputs_hash().end..self.config.num_wires)
+            .map(|wire| Target::wire(pi_gate, wire));
+        if self.config.zero_knowledge {
+            for target in targets {
+                self.add_simple_generator(RandomValueGenerator { target });
+            }
+        } else {
+            self.add_simple_generator(SeededValueGenerator {
+                seed: public_inputs_hash.elements.to_vec(),
+                targets: targets.collect(),
             });
         }
     }
@@ -1050,24 +1194,58 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         self,
         commit_to_sigma: bool,
     ) -> CircuitData<F, C, D> {
-        let (circuit_data, success) = self.try_build_with_options(commit_to_sigma);
+        let (circuit_data, success) = self
+            .try_build_with_options(commit_to_sigma)
+            .expect("Failed to build circuit");
         if !success {
             panic!("Failed to build circuit");
         }
         circuit_data
     }
 
+    /// Builds the circuit, returning it along with whether its common data matches the goal
+    /// common data, if any. Returns an error if the circuit can't be built; see
+    /// [`CircuitBuilder::try_build`].
     pub fn try_build_with_options<C: GenericConfig<D, F = F>>(
-        mut self,
+        self,
         commit_to_sigma: bool,
-    ) -> (CircuitData<F, C, D>, bool) {
+    ) -> Result<(CircuitData<F, C, D>, bool)> {
         let mut timing = TimingTree::new("preprocess", Level::Trace);
+        let res = self.try_build_with_timing(commit_to_sigma, &mut timing);
+        timing.print();
+        res
+    }
+
+    /// Like `build`, but records the time spent in each phase of the preprocessing in `timing`.
+    pub fn build_with_timing<C: GenericConfig<D, F = F>>(
+        self,
+        timing: &mut TimingTree,
+    ) -> CircuitData<F, C, D> {
+        let (circuit_data, success) = self
+            .try_build_with_timing(true, timing)
+            .expect("Failed to build circuit");
+        if !success {
+            panic!("Failed to build circuit");
+        }
+        circuit_data
+    }
 
+    fn try_build_with_timing<C: GenericConfig<D, F = F>>(
+        mut self,
+        commit_to_sigma: bool,
+        timing: &mut TimingTree,
+    ) -> Result<(CircuitData<F, C, D>, bool)> {
         #[cfg(feature = "std")]
         let start = Instant::now();
 
+        // Gates registered through `add_gate_to_gate_set` haven't been checked yet.
+        for gate in &self.gates {
+            gate.0
+                .check_compatibility(&self.config)
+                .with_context(|| self.describe_gate(gate))?;
+        }
+
         let rate_bits = self.config.fri_config.rate_bits;
-        let cap_height = self.config.fri_config.cap_height;
         // Total number of LUTs.
         let num_luts = self.get_luts_length();
         // Hash the public inputs, and route them to a `PublicInputGate` which will enforce that
@@ -1075,7 +1253,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let num_public_inputs = self.public_inputs.len();
         let public_inputs_hash =
             self.hash_n_to_hash_no_pad::<C::InnerHasher>(self.public_inputs.clone());
-        let pi_gate = self.add_gate(PublicInputGate, vec![]);
+        let pi_gate = self.try_add_gate(PublicInputGate, vec![])?;
         for (&hash_part, wire) in public_inputs_hash
             .elements
             .iter()
@@ -1083,19 +1261,19 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         {
             self.connect(hash_part, Target::wire(pi_gate, wire))
         }
-        self.randomize_unused_pi_wires(pi_gate);
+        self.randomize_unused_pi_wires(pi_gate, public_inputs_hash);
 
         // Place LUT-related gates.
-        self.add_all_lookups();
+        timed!(timing, "place lookup gates", self.add_all_lookups());
 
         // Make sure we have enough constant generators. If not, add a `ConstantGate`.
         while self.constants_to_targets.len() > self.constant_generators.len() {
-            self.add_gate(
+            self.try_add_gate(
                 ConstantGate {
                     num_consts: self.config.num_constants,
                 },
                 vec![],
-            );
+            )?;
         }
 
         // For each constant-target pair used in the circuit, use a constant generator to fill this target.
@@ -1117,26 +1295,71 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             self.add_simple_generator(const_gen);
         }
 
-        debug!(
-            "Degree before blinding & padding: {}",
-            self.gate_instances.len()
-        );
-        self.blind_and_pad();
+        let num_gates = self.gate_instances.len();
+        debug!(num_gates; "Degree before blinding & padding: {}", num_gates);
+        self.context_log.check_gate_budgets(self.num_gates())?;
+        timed!(timing, "blind and pad", self.blind_and_pad());
         let degree = self.gate_instances.len();
-        debug!("Degree after blinding & padding: {}", degree);
+        debug!(degree; "Degree after blinding & padding: {}", degree);
         let degree_bits = log2_strict(degree);
+        if self.auto_cap_height {
+            self.config.fri_config.cap_height = self.optimal_cap_height(degree_bits);
+            let cap_height = self.config.fri_config.cap_height;
+            debug!(cap_height; "Cap height: {}", cap_height);
+        }
+        let cap_height = self.config.fri_config.cap_height;
+        ensure!(
+            cap_height <= degree_bits + rate_bits,
+            "The cap height {} is larger than the height {} of the Merkle trees of a circuit of \
+            degree 2^{} with rate bits {}",
+            cap_height,
+            degree_bits + rate_bits,
+            degree_bits,
+            rate_bits
+        );
         let fri_params = self.fri_params(degree_bits);
-        assert!(
+        ensure!(
             fri_params.total_arities() <= degree_bits + rate_bits - cap_height,
-            "FRI total reduction arity is too large.",
+            "FRI total reduction arity {} is too large for a circuit of degree 2^{} with rate bits {} and cap height {}",
+            fri_params.total_arities(),
+            degree_bits,
+            rate_bits,
+            cap_height
+        );
+        let fri_field_bits = F::Extension::order().bits() as usize;
+        let fri_security_bits = fri_params.security_bits(self.config.fri_security, fri_field_bits);
+        ensure!(
+            fri_security_bits >= self.config.security_bits,
+            "FRI params give {} bits of security under {:?} for a circuit of degree 2^{}, short of \
+            the target of {} bits",
+            fri_security_bits,
+            self.config.fri_security,
+            degree_bits,
+            self.config.security_bits
         );
 
-        let quotient_degree_factor = self.config.max_quotient_degree_factor;
         let mut gates = self.gates.iter().cloned().collect::<Vec<_>>();
         // Gates need to be sorted by their degrees (and ID to make the ordering deterministic) to compute the selector polynomials.
         gates.sort_unstable_by_key(|g| (g.0.degree(), g.0.id()));
-        let (mut constant_vecs, selectors_info) =
-            selector_polynomials(&gates, &self.gate_instances, quotient_degree_factor + 1);
+        let quotient_degree_factor =
+            self.quotient_degree_factor(gates.last().expect("No gates?"))?;
+
+        // Both the LDE and the quotient's evaluation domain must be subgroups of the field.
+        let lde_bits = degree_bits + max(rate_bits, log2_ceil(quotient_degree_factor));
+        ensure!(
+            lde_bits <= F::TWO_ADICITY,
+            "A circuit of degree 2^{} with rate bits {} has an LDE of size 2^{}, but the field's \
+            two-adicity is only {}",
+            degree_bits,
+            rate_bits,
+            lde_bits,
+            F::TWO_ADICITY
+        );
+        let (mut constant_vecs, selectors_info) = timed!(
+            timing,
+            "compute selector polynomials",
+            selector_polynomials(&gates, &self.gate_instances, quotient_degree_factor + 1)
+        );
 
         // Get the lookup selectors.
         let num_lookup_selectors = if num_luts != 0 {
@@ -1164,18 +1387,26 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         );
 
         // Precompute FFT roots.
-        let max_fft_points = 1 << (degree_bits + max(rate_bits, log2_ceil(quotient_degree_factor)));
-        let fft_root_table = fft_root_table(max_fft_points);
+        let max_fft_points = 1 << lde_bits;
+        let fft_root_table = timed!(
+            timing,
+            "precompute FFT roots",
+            fft_root_table(max_fft_points)
+        );
 
         let constants_sigmas_commitment = if commit_to_sigma {
             let constants_sigmas_vecs = [constant_vecs, sigma_vecs.clone()].concat();
-            PolynomialBatch::<F, C, D>::from_values(
-                constants_sigmas_vecs,
-                rate_bits,
-                PlonkOracle::CONSTANTS_SIGMAS.blinding,
-                cap_height,
-                &mut timing,
-                Some(&fft_root_table),
+            timed!(
+                timing,
+                "commit to constants and sigmas",
+                PolynomialBatch::<F, C, D>::from_values(
+                    constants_sigmas_vecs,
+                    rate_bits,
+                    PlonkOracle::CONSTANTS_SIGMAS.blinding,
+                    cap_height,
+                    timing,
+                    Some(&fft_root_table),
+                )
             )
         } else {
             PolynomialBatch::<F, C, D>::default()
@@ -1188,38 +1419,30 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             .flat_map(|current_slot| current_slot.current_slot.values().copied())
             .collect::<HashMap<_, _>>();
 
-        // Add gate generators.
-        self.add_generators(
-            self.gate_instances
-                .iter()
-                .enumerate()
-                .flat_map(|(index, gate)| {
-                    let mut gens = gate.gate_ref.0.generators(index, &gate.constants);
-                    // Remove unused generators, if any.
-                    if let Some(&op) = incomplete_gates.get(&index) {
-                        gens.drain(op..);
-                    }
-                    gens
-                })
-                .collect(),
-        );
-
-        // Index generator indices by their watched targets.
-        let mut generator_indices_by_watches = BTreeMap::new();
-        for (i, generator) in self.generators.iter().enumerate() {
-            for watch in generator.0.watch_list() {
-                let watch_index = forest.target_index(watch);
-                let watch_rep_index = forest.parents[watch_index];
-                generator_indices_by_watches
-                    .entry(watch_rep_index)
-                    .or_insert_with(Vec::new)
-                    .push(i);
+        // Add gate generators, attributed to the contexts their gates were added in.
+        let gate_contexts = self.context_log.gate_contexts();
+        timed!(timing, "create gate generators", {
+            for index in 0..self.gate_instances.len() {
+                let gate = &self.gate_instances[index];
+                let mut gens = gate.gate_ref.0.generators(index, &gate.constants);
+                // Remove unused generators, if any.
+                if let Some(&op) = incomplete_gates.get(&index) {
+                    gens.drain(op..);
+                }
+                if gens.is_empty() {
+                    continue;
+                }
+                let context = context_at(&gate_contexts, index).unwrap_or_default();
+                self.enter_generator_context(context.to_string());
+                self.generators.extend(gens);
             }
-        }
-        for indices in generator_indices_by_watches.values_mut() {
-            indices.dedup();
-            indices.shrink_to_fit();
-        }
+        });
+
+        let generator_indices_by_watches = timed!(
+            timing,
+            "index generators by watched targets",
+            generator_indices_by_watches(&self.generators, &forest)
+        );
 
         let num_gate_constraints = gates
             .iter()
@@ -1230,7 +1453,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         let num_partial_products =
             num_partial_products(self.config.num_routed_wires, quotient_degree_factor);
 
-        let lookup_degree = self.config.max_quotient_degree_factor - 1;
+        let lookup_degree = quotient_degree_factor - 1;
         let num_lookup_polys = if num_luts == 0 {
             0
         } else {
@@ -1239,17 +1462,8 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         };
         let constants_sigmas_cap = constants_sigmas_commitment.merkle_tree.cap.clone();
         let domain_separator = self.domain_separator.unwrap_or_default();
-        let domain_separator_digest = C::Hasher::hash_pad(&domain_separator);
-        // TODO: This should also include an encoding of gate constraints.
-        let circuit_digest_parts = [
-            constants_sigmas_cap.flatten(),
-            domain_separator_digest.to_vec(),
-            vec![
-                F::from_canonical_usize(degree_bits),
-                /* Add other circuit data here */
-            ],
-        ];
-        let circuit_digest = C::Hasher::hash_no_pad(&circuit_digest_parts.concat());
+        let circuit_digest =
+            circuit_digest::<F, C, D>(&constants_sigmas_cap, &domain_separator, degree_bits);
 
         let common = CommonCircuitData {
             config: self.config,
@@ -1277,17 +1491,20 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         }
 
         let prover_only = ProverOnlyCircuitData::<F, C, D> {
-            generators: self.generators,
-            generator_indices_by_watches,
-            constants_sigmas_commitment,
-            sigmas: transpose_poly_values(sigma_vecs),
-            subgroup,
+            generators: Arc::new(self.generators),
+            generator_indices_by_watches: Arc::new(generator_indices_by_watches),
+            constants_sigmas_commitment: Arc::new(constants_sigmas_commitment),
+            sigmas: Arc::new(transpose_poly_values(sigma_vecs)),
+            subgroup: Arc::new(subgroup),
             public_inputs: self.public_inputs,
-            representative_map: forest.parents,
-            fft_root_table: Some(fft_root_table),
+            public_input_names: self.public_input_names,
+            representative_map: Arc::new(forest.parents),
+            fft_root_table: Some(Arc::new(fft_root_table)),
             circuit_digest,
             lookup_rows: self.lookup_rows.clone(),
             lut_to_lookups: self.lut_to_lookups.clone(),
+            gate_contexts,
+            generator_contexts: self.generator_contexts,
         };
 
         let verifier_only = VerifierOnlyCircuitData::<C, D> {
@@ -1295,24 +1512,78 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             circuit_digest,
         };
 
-        timing.print();
         #[cfg(feature = "std")]
-        debug!("Building circuit took {}s", start.elapsed().as_secs_f32());
-        (
+        {
+            let secs = start.elapsed().as_secs_f32();
+            debug!(secs, degree_bits; "Building circuit took {}s", secs);
+        }
+        Ok((
             CircuitData {
                 prover_only,
                 verifier_only,
                 common,
             },
             success,
-        )
+        ))
     }
 
-    /// Builds a "full circuit", with both prover and verifier data.
+    /// Returns the number of quotient chunks per challenge. This is `max_quotient_degree_factor`,
+    /// unless `max_degree_gate` has a higher degree, in which case its constraints are split
+    /// across additional chunks. The quotient is computed on the LDE of the committed
+    /// polynomials, so the degree factor is limited by the rate.
+    fn quotient_degree_factor(&self, max_degree_gate: &GateRef<F, D>) -> Result<usize> {
+        let gate_degree = max_degree_gate.0.degree();
+        let quotient_degree_factor = self.config.max_quotient_degree_factor.max(gate_degree);
+        let rate_bits = self.config.fri_config.rate_bits;
+        ensure!(
+            log2_ceil(quotient_degree_factor) <= rate_bits,
+            "A quotient degree factor of {} requires `rate_bits` to be at least {}, but it is {}; \
+            the highest-degree gate is {}, with degree {}",
+            quotient_degree_factor,
+            log2_ceil(quotient_degree_factor),
+            rate_bits,
+            self.describe_gate(max_degree_gate),
+            gate_degree
+        );
+        Ok(quotient_degree_factor)
+    }
+
+    /// Describes `gate` for error messages, along with the context of its first instance.
+    fn describe_gate(&self, gate: &GateRef<F, D>) -> String {
+        let id = gate.0.id();
+        let Some(row) = self
+            .gate_instances
+            .iter()
+            .position(|instance| &instance.gate_ref == gate)
+        else {
+            return format!("{} (in the gate set, but never instantiated)", id);
+        };
+        let contexts = self.context_log.gate_contexts();
+        let i = contexts.partition_point(|&(start, _)| start <= row);
+        match i.checked_sub(1) {
+            Some(i) => format!("{} (first used in row {}, in {})", id, row, contexts[i].1),
+            None => format!("{} (first used in row {})", id, row),
+        }
+    }
+
+    /// Builds a "full circuit", with both prover and verifier data. Panics if the circuit can't
+    /// be built; see [`CircuitBuilder::try_build`].
     pub fn build<C: GenericConfig<D, F = F>>(self) -> CircuitData<F, C, D> {
         self.build_with_options(true)
     }
 
+    /// Same as [`CircuitBuilder::build`], but returns an error describing the problem if the
+    /// circuit can't be built, e.g. if one of its gates doesn't fit in the configured rows, if
+    /// its constraint degree is too high for the rate, or if its LDE is too large for the field.
+    pub fn try_build<C: GenericConfig<D, F = F>>(self) -> Result<CircuitData<F, C, D>> {
+        let (circuit_data, success) = self.try_build_with_options(true)?;
+        ensure!(
+            success,
+            "The circuit's common data doesn't match the expected common data"
+        );
+        Ok(circuit_data)
+    }
+
     pub fn mock_build<C: GenericConfig<D, F = F>>(self) -> MockCircuitData<F, C, D> {
         let circuit_data = self.build_with_options(false);
         MockCircuitData {
@@ -1334,3 +1605,316 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         circuit_data.verifier_data()
     }
 }
+
+/// The values of the constant polynomials holding the gates' own constants, i.e. not counting
+/// selectors, padded to `max_constants` per row.
+pub(crate) fn gate_constant_polys<F: RichField + Extendable<D>, const D: usize>(
+    gate_instances: &[GateInstance<F, D>],
+    max_constants: usize,
+) -> Vec<PolynomialValues<F>> {
+    transpose(
+        &gate_instances
+            .iter()
+            .map(|g| {
+                let mut consts = g.constants.clone();
+                consts.resize(max_constants, F::ZERO);
+                consts
+            })
+            .collect::<Vec<_>>(),
+    )
+    .into_iter()
+    .map(PolynomialValues::new)
+    .collect()
+}
+
+/// Computes the sigma polynomials of a circuit with `degree` rows and the given copy constraints,
+/// along with the disjoint-set forest of its targets.
+pub(crate) fn sigma_polys<F: Field>(
+    config: &CircuitConfig,
+    degree: usize,
+    num_virtual_targets: usize,
+    copy_constraints: impl IntoIterator<Item = (Target, Target)>,
+    k_is: &[F],
+    subgroup: &[F],
+) -> (Vec<PolynomialValues<F>>, Forest) {
+    let degree_log = log2_strict(degree);
+    let mut forest = Forest::new(
+        config.num_wires,
+        config.num_routed_wires,
+        degree,
+        num_virtual_targets,
+    );
+
+    for gate in 0..degree {
+        for input in 0..config.num_wires {
+            forest.add(Target::Wire(Wire {
+                row: gate,
+                column: input,
+            }));
+        }
+    }
+
+    for index in 0..num_virtual_targets {
+        forest.add(Target::VirtualTarget { index });
+    }
+
+    for (a, b) in copy_constraints {
+        forest.merge(a, b);
+    }
+
+    forest.compress_paths();
+
+    let wire_partition = forest.wire_partition();
+    (
+        wire_partition.get_sigma_polys(degree_log, k_is, subgroup),
+        forest,
+    )
+}
+
+/// Indexes generators by the representatives of the targets they watch.
+pub(crate) fn generator_indices_by_watches<F: RichField + Extendable<D>, const D: usize>(
+    generators: &[WitnessGeneratorRef<F, D>],
+    forest: &Forest,
+) -> BTreeMap<usize, Vec<usize>> {
+    let mut generator_indices_by_watches = BTreeMap::new();
+    for (i, generator) in generators.iter().enumerate() {
+        for watch in generator.0.watch_list() {
+            let watch_index = forest.target_index(watch);
+            let watch_rep_index = forest.parents[watch_index];
+            generator_indices_by_watches
+                .entry(watch_rep_index)
+                .or_insert_with(Vec::new)
+                .push(i);
+        }
+    }
+    for indices in generator_indices_by_watches.values_mut() {
+        indices.dedup();
+        indices.shrink_to_fit();
+    }
+    generator_indices_by_watches
+}
+
+/// The digest of a circuit, which seeds Fiat-Shamir: a hash of its constants and sigmas cap, its
+/// domain separator and its degree.
+pub(crate) fn circuit_digest<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    constants_sigmas_cap: &MerkleCap<F, C::Hasher>,
+    domain_separator: &[F],
+    degree_bits: usize,
+) -> <C::Hasher as Hasher<F>>::Hash {
+    let domain_separator_digest = C::Hasher::hash_pad(domain_separator);
+    // TODO: This should also include an encoding of gate constraints.
+    let circuit_digest_parts = [
+        constants_sigmas_cap.flatten(),
+        domain_separator_digest.to_vec(),
+        vec![
+            F::from_canonical_usize(degree_bits),
+            /* Add other circuit data here */
+        ],
+    ];
+    C::Hasher::hash_no_pad(&circuit_digest_parts.concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::fri::FriSecurity;
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::with_context;
+
+    #[test]
+    fn test_try_new() {
+        const D: usize = 2;
+        type F = <PoseidonGoldilocksConfig as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        assert!(CircuitBuilder::<F, D>::try_new(config.clone()).is_ok());
+        let too_many_routed_wires = CircuitConfig {
+            num_routed_wires: config.num_wires + 1,
+            ..config.clone()
+        };
+        assert!(CircuitBuilder::<F, D>::try_new(too_many_routed_wires).is_err());
+        let too_secure = CircuitConfig {
+            security_bits: 128,
+            ..config.clone()
+        };
+        assert!(CircuitBuilder::<F, D>::try_new(too_secure).is_err());
+        // The standard config only reaches 100 bits under the list decoding conjecture.
+        let proven = CircuitConfig {
+            fri_security: FriSecurity::Proven,
+            ..config
+        };
+        assert!(CircuitBuilder::<F, D>::try_new(proven).is_err());
+    }
+
+    #[test]
+    fn test_proven_security_checked_at_build() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // Enough query rounds for 100 provable bits, were it not for the commit phase error, which
+        // is only known once the degree is.
+        let config = CircuitConfig::standard_recursion_config();
+        let config = CircuitConfig {
+            fri_security: FriSecurity::Proven,
+            fri_config: FriConfig {
+                num_query_rounds: 56,
+                ..config.fri_config.clone()
+            },
+            ..config
+        };
+        let mut builder = CircuitBuilder::<F, D>::try_new(config).unwrap();
+        builder.add_virtual_public_input();
+        let err = builder.try_build::<C>().map(|_| ()).unwrap_err();
+        assert!(format!("{:#}", err).contains("under Proven"));
+    }
+
+    #[test]
+    fn test_try_build() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+
+        // A registered gate needing more routed wires than the config has.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig {
+            num_routed_wires: 40,
+            ..config.clone()
+        });
+        builder.add_gate_to_gate_set(GateRef::new(RandomAccessGate::new_from_config(&config, 5)));
+        let err = builder.try_build::<C>().map(|_| ()).unwrap_err();
+        assert!(format!("{:#}", err).contains("routed wires, but our CircuitConfig has only 40"));
+        assert!(format!("{:#}", err).contains("never instantiated"));
+
+        // A gate added in a context, whose degree is too high for the rate.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig {
+            max_quotient_degree_factor: 2,
+            fri_config: FriConfig {
+                rate_bits: 1,
+                ..config.fri_config.clone()
+            },
+            security_bits: 40,
+            ..config.clone()
+        });
+        let err = with_context!(
+            builder,
+            "arithmetic",
+            builder.try_add_gate(ArithmeticGate::new_from_config(&config), vec![])
+        )
+        .expect_err("A degree 3 gate doesn't fit with a rate of 2");
+        assert!(format!("{:#}", err).contains("rate_bits"));
+        assert!(format!("{:#}", err).contains("root > arithmetic"));
+
+        // An LDE larger than the field's two-adic subgroup.
+        let builder = CircuitBuilder::<F, D>::new(CircuitConfig {
+            fri_config: FriConfig {
+                rate_bits: 32,
+                num_query_rounds: 3,
+                ..config.fri_config.clone()
+            },
+            ..config
+        });
+        let err = builder.try_build::<C>().map(|_| ()).unwrap_err();
+        assert!(format!("{:#}", err).contains("two-adicity"));
+    }
+
+    #[test]
+    fn test_gate_budget() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let build = |total_budget, hash_budget| {
+            let mut builder =
+                CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+            builder.assert_gate_budget(total_budget);
+            let x = builder.add_virtual_public_input();
+            with_context!(builder, "hash", {
+                builder.assert_context_gate_budget(hash_budget);
+                // Two permutations.
+                builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x; 10]);
+            });
+            builder.try_build::<C>().map(|_| ())
+        };
+
+        assert!(build(100, 2).is_ok());
+        let err = build(100, 1).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "Gate budget exceeded:\nroot > hash uses 2 gates, over its budget of 1"
+        );
+        let err = build(2, 2).unwrap_err();
+        assert!(format!("{:#}", err).starts_with("Gate budget exceeded:\nroot uses"));
+        assert!(format!("{:#}", err).contains("\n| 2 gates to hash"));
+    }
+
+    #[test]
+    fn test_auto_cap_height() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        builder.set_auto_cap_height();
+        let x = builder.add_virtual_target();
+        let y = builder.exp_u64(x, 3);
+        builder.register_public_input(y);
+        let cap_height = builder.optimal_cap_height(2);
+        let data = builder.build::<C>();
+
+        // With 28 queries, a cap of 32 hashes saves more than it costs.
+        assert_eq!(data.common.degree_bits(), 2);
+        assert_eq!(cap_height, 5);
+        assert_eq!(data.common.config.fri_config.cap_height, cap_height);
+        assert_eq!(data.common.fri_oracles().len(), PlonkOracle::COUNT);
+        assert_eq!(
+            data.verifier_only.constants_sigmas_cap.0.len(),
+            1 << cap_height
+        );
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO)?;
+        data.verify(data.prove(pw)?)
+    }
+
+    #[test]
+    fn test_deterministic_proving() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let prove = || -> Result<_> {
+            let config = CircuitConfig::standard_recursion_config();
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let x = builder.add_virtual_target();
+            let mut acc = x;
+            for i in 0..20 {
+                let c = builder.constant(F::from_canonical_u64(i * i + 3));
+                acc = builder.mul_add(acc, x, c);
+            }
+            let hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![x, acc]);
+            builder.register_public_inputs(&hash.elements);
+            let data = builder.build::<C>();
+
+            let mut pw = PartialWitness::new();
+            pw.set_target(x, F::from_canonical_u64(7))?;
+            let proof = data.prove(pw)?;
+            data.verify(proof.clone())?;
+            Ok((data.verifier_only.circuit_digest, proof.to_bytes()))
+        };
+
+        // Each build uses fresh hash maps, so this would catch any dependence on their ordering.
+        assert_eq!(prove()?, prove()?);
+        Ok(())
+    }
+}
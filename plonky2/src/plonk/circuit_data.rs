@@ -13,25 +13,27 @@
 //! This is useful to allow even small devices to verify plonky2 proofs.
 
 #[cfg(not(feature = "std"))]
-use alloc::{collections::BTreeMap, vec, vec::Vec};
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec, vec::Vec};
 use core::ops::{Range, RangeFrom};
 #[cfg(feature = "std")]
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, sync::Arc};
 
-use anyhow::Result;
-use serde::Serialize;
+use anyhow::{ensure, Result};
+#[cfg(feature = "parallel")]
+use plonky2_maybe_rayon::rayon;
+use serde::{Deserialize, Serialize};
 
 use super::circuit_builder::LookupWire;
 use crate::field::extension::Extendable;
 use crate::field::fft::FftRootTable;
 use crate::field::types::Field;
-use crate::fri::oracle::PolynomialBatch;
+use crate::fri::oracle::{PolynomialBatch, SALT_SIZE};
 use crate::fri::reduction_strategies::FriReductionStrategy;
 use crate::fri::structure::{
     FriBatchInfo, FriBatchInfoTarget, FriInstanceInfo, FriInstanceInfoTarget, FriOracleInfo,
     FriPolynomialInfo,
 };
-use crate::fri::{FriConfig, FriParams};
+use crate::fri::{FriConfig, FriParams, FriSecurity};
 use crate::gates::gate::GateRef;
 use crate::gates::lookup::Lookup;
 use crate::gates::lookup_table::LookupTable;
@@ -39,17 +41,26 @@ use crate::gates::selectors::SelectorsInfo;
 use crate::hash::hash_types::{HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::ext_target::ExtensionTarget;
-use crate::iop::generator::{generate_partial_witness, WitnessGeneratorRef};
+use crate::iop::generator::{
+    generate_partial_witness, witness_template, WitnessGeneratorRef, WitnessTemplate,
+};
 use crate::iop::target::Target;
 use crate::iop::witness::{PartialWitness, PartitionWitness};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::config::{GenericConfig, Hasher};
 use crate::plonk::plonk_common::PlonkOracle;
 use crate::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
-use crate::plonk::prover::prove;
-use crate::plonk::verifier::verify;
+#[cfg(feature = "parallel")]
+use crate::plonk::prover::prove_in_thread_pool;
+use crate::plonk::prover::{
+    prove, prove_batch, prove_incremental, prove_with_template, IncrementalProverCache,
+};
+use crate::plonk::public_inputs::PublicInputNames;
+use crate::plonk::verifier::{verify, verify_batch};
+use crate::plonk::witness_check::check_witness;
+use crate::util::log2_ceil;
 use crate::util::serialization::{
-    Buffer, GateSerializer, IoResult, Read, WitnessGeneratorSerializer, Write,
+    Buffer, GateSerializer, IoResult, Read, Remaining, WitnessGeneratorSerializer, Write,
 };
 use crate::util::timing::TimingTree;
 
@@ -73,15 +84,29 @@ pub struct CircuitConfig {
     /// Whether to use a dedicated gate for base field arithmetic, rather than using a single gate
     /// for both base field and extension field arithmetic.
     pub use_base_arithmetic_gate: bool,
+    /// The targeted security level, in bits. The FRI parameters are checked against it when
+    /// building a circuit; see [`CircuitConfig::with_security_bits`] to derive them.
     pub security_bits: usize,
+    /// The soundness assumption under which FRI must reach `security_bits`.
+    ///
+    /// It is recorded by [`CommonCircuitData::to_versioned_bytes`]. The unversioned encoding of
+    /// [`CommonCircuitData::to_bytes`] stays compatible with upstream plonky2, which has no such
+    /// field, so it is left out there and configs read from it use the default,
+    /// [`FriSecurity::ConjecturedListDecoding`].
+    pub fri_security: FriSecurity,
     /// The number of challenge points to generate, for IOPs that have soundness errors of (roughly)
     /// `degree / |F|`.
     pub num_challenges: usize,
     /// A boolean to activate the zero-knowledge property. When this is set to `false`, proofs *may*
     /// leak additional information.
+    ///
+    /// When set, the builder adds rows of random values to blind the wire and partial product
+    /// polynomials, growing the circuit's degree if they don't fit, and the prover salts the leaves
+    /// of the Merkle trees it commits to.
     pub zero_knowledge: bool,
-    /// A cap on the quotient polynomial's degree factor. The actual degree factor is derived
-    /// systematically, but will never exceed this value.
+    /// The quotient polynomial's degree factor, i.e. the number of quotient chunks per challenge.
+    /// Circuits with gates of a higher degree use as many chunks as their largest gate degree,
+    /// which is only possible while it doesn't exceed `2^rate_bits`.
     pub max_quotient_degree_factor: usize,
     pub fri_config: FriConfig,
 }
@@ -98,6 +123,10 @@ impl CircuitConfig {
     }
 
     /// A typical recursion config, without zero-knowledge, targeting ~100 bit security.
+    ///
+    /// The 100 bits hold under the conjectured FRI list-decoding soundness with a quadratic
+    /// extension (`D = 2`). Provable security is lower; use [`CircuitConfig::with_security_bits`]
+    /// with [`FriSecurity::Proven`] for parameters meeting the target provably.
     pub const fn standard_recursion_config() -> Self {
         Self {
             num_wires: 135,
@@ -105,6 +134,7 @@ impl CircuitConfig {
             num_constants: 2,
             use_base_arithmetic_gate: true,
             security_bits: 100,
+            fri_security: FriSecurity::ConjecturedListDecoding,
             num_challenges: 2,
             zero_knowledge: false,
             max_quotient_degree_factor: 8,
@@ -132,16 +162,208 @@ impl CircuitConfig {
         }
     }
 
-    pub fn standard_recursion_zk_config() -> Self {
+    /// [`CircuitConfig::standard_recursion_config`], with zero-knowledge.
+    pub fn zk_config() -> Self {
         CircuitConfig {
             zero_knowledge: true,
             ..Self::standard_recursion_config()
         }
     }
+
+    #[deprecated(note = "Use `CircuitConfig::zk_config`")]
+    pub fn standard_recursion_zk_config() -> Self {
+        Self::zk_config()
+    }
+
+    /// [`CircuitConfig::standard_recursion_config`] with only ~20 bits of security, which needs
+    /// just two FRI query rounds. Proofs are much faster to generate and verify recursively, and
+    /// zero-knowledge circuits need fewer blinding rows, but they are **not secure**; this is
+    /// meant for tests and prototyping.
+    pub fn fast_config() -> Self {
+        let config = Self::standard_recursion_config();
+        Self {
+            security_bits: 20,
+            fri_config: FriConfig {
+                num_query_rounds: 2,
+                ..config.fri_config
+            },
+            ..config
+        }
+    }
+
+    /// Returns this config with the FRI parameters and number of challenges derived to reach
+    /// `security_bits` bits of security under the given FRI soundness assumption, for circuits
+    /// over `F` of degree up to `2^degree_bits`. The assumption is recorded in the returned
+    /// config, so that circuits built with it are checked against it.
+    ///
+    /// The rate is the highest one supporting `max_quotient_degree_factor`, the grinding bits of
+    /// this config are kept, up to `security_bits`, and the query rounds make up the rest. Each
+    /// Plonk challenge, drawn from `F`, gives about `log2(|F|) - degree_bits` bits of security.
+    ///
+    /// Under [`FriSecurity::Proven`], the query rounds are sized with
+    /// [`FriParams::security_bits`] for the given degree. Its commit phase error doesn't shrink
+    /// with more queries, so this returns an error if it keeps FRI below `security_bits`, as it
+    /// does for 100 bits over the quadratic Goldilocks extension.
+    pub fn with_security_bits<F: RichField + Extendable<D>, const D: usize>(
+        self,
+        security_bits: usize,
+        security: FriSecurity,
+        degree_bits: usize,
+    ) -> Result<Self> {
+        let field_bits = F::order().bits() as usize - 1;
+        ensure!(
+            degree_bits < field_bits,
+            "Plonk challenges give no security for circuits of degree 2^{} over a {}-bit field",
+            degree_bits,
+            field_bits + 1
+        );
+        let bits_per_challenge = field_bits - degree_bits;
+
+        let rate_bits = log2_ceil(self.max_quotient_degree_factor).max(1);
+        let proof_of_work_bits = (self.fri_config.proof_of_work_bits as usize).min(security_bits);
+        let query_bits = security_bits - proof_of_work_bits;
+        let mut fri_config = FriConfig {
+            rate_bits,
+            proof_of_work_bits: proof_of_work_bits as u32,
+            num_query_rounds: security.num_query_rounds(rate_bits, query_bits),
+            ..self.fri_config
+        };
+
+        if security == FriSecurity::Proven {
+            let fri_field_bits = F::Extension::order().bits() as usize;
+            let achieved = |fri_config: &FriConfig| {
+                fri_config
+                    .fri_params(degree_bits, self.zero_knowledge)
+                    .security_bits(FriSecurity::Proven, fri_field_bits)
+            };
+            // The commit phase error caps the security reachable with any number of queries,
+            // which is approached long before the query rounds double.
+            let max_query_rounds = 2 * fri_config.num_query_rounds.max(1);
+            while achieved(&fri_config) < security_bits {
+                ensure!(
+                    fri_config.num_query_rounds < max_query_rounds,
+                    "FRI can't provably reach {} bits of security for circuits of degree 2^{} over \
+                    a {}-bit field with rate bits {}; the commit phase error limits it to {} bits",
+                    security_bits,
+                    degree_bits,
+                    fri_field_bits,
+                    rate_bits,
+                    achieved(&fri_config)
+                );
+                fri_config.num_query_rounds += 1;
+            }
+        }
+
+        Ok(Self {
+            security_bits,
+            fri_security: security,
+            num_challenges: security_bits.div_ceil(bits_per_challenge),
+            fri_config,
+            ..self
+        })
+    }
+
+    /// Returns this config with the wire counts of the given profile.
+    pub fn with_wire_profile(self, profile: WireProfile) -> Self {
+        Self {
+            num_wires: profile.num_wires(),
+            num_routed_wires: profile.num_routed_wires(),
+            ..self
+        }
+    }
+
+    /// Checks that the wire counts of this config are consistent.
+    pub fn check_wires(&self) -> Result<()> {
+        ensure!(
+            self.num_routed_wires <= self.num_wires,
+            "The number of routed wires ({}) exceeds the number of wires ({})",
+            self.num_routed_wires,
+            self.num_wires
+        );
+        // The permutation argument needs at least one routed wire per quotient chunk to compute
+        // partial products.
+        ensure!(
+            self.num_routed_wires > self.max_quotient_degree_factor,
+            "The number of routed wires ({}) must exceed the max quotient degree factor ({})",
+            self.num_routed_wires,
+            self.max_quotient_degree_factor
+        );
+        Ok(())
+    }
+
+    /// Checks that this config is consistent. Checks depending on the field, such as whether the
+    /// FRI parameters reach `security_bits`, or on the circuit's degree, such as whether the cap
+    /// height fits in the LDE, are done by the [`CircuitBuilder`].
+    pub fn validate(&self) -> Result<()> {
+        self.check_wires()?;
+        ensure!(self.num_challenges > 0, "At least one challenge is needed");
+        let fri_config = &self.fri_config;
+        ensure!(
+            fri_config.rate_bits > 0,
+            "`rate_bits` must be positive, as FRI needs a rate below 1"
+        );
+        ensure!(
+            fri_config.num_query_rounds > 0,
+            "FRI needs at least one query round"
+        );
+        ensure!(
+            log2_ceil(self.max_quotient_degree_factor) <= fri_config.rate_bits,
+            "A max quotient degree factor of {} requires `rate_bits` to be at least {}, but it is {}",
+            self.max_quotient_degree_factor,
+            log2_ceil(self.max_quotient_degree_factor),
+            fri_config.rate_bits
+        );
+        let positive_arities = match &fri_config.reduction_strategy {
+            FriReductionStrategy::Fixed(arity_bits) => arity_bits.iter().all(|&bits| bits > 0),
+            &FriReductionStrategy::ConstantArityBits(arity_bits, _) => arity_bits > 0,
+            &FriReductionStrategy::MinSize(max_arity_bits)
+            | &FriReductionStrategy::MinVerifierGates(max_arity_bits) => max_arity_bits != Some(0),
+        };
+        ensure!(
+            positive_arities,
+            "FRI reduction arities must be at least 2, but the reduction strategy is {:?}",
+            fri_config.reduction_strategy
+        );
+        Ok(())
+    }
+}
+
+/// Preset wire counts for [`CircuitConfig`], trading the width of a circuit against its number
+/// of rows.
+///
+/// Every profile has enough wires for the Poseidon gate, which is needed to hash the public
+/// inputs, and enough routed wires to recursively verify proofs.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WireProfile {
+    /// Few routed wires, for circuits dominated by gates with many advice wires such as
+    /// Poseidon. Fewer routed wires make the permutation argument cheaper.
+    Narrow,
+    /// The wire counts of [`CircuitConfig::standard_recursion_config`].
+    Standard,
+    /// Many wires, for circuits dominated by gates packing several operations per row, such as
+    /// arithmetic gates, which then need fewer rows.
+    Wide,
+}
+
+impl WireProfile {
+    pub const fn num_wires(self) -> usize {
+        match self {
+            WireProfile::Narrow | WireProfile::Standard => 135,
+            WireProfile::Wide => 234,
+        }
+    }
+
+    pub const fn num_routed_wires(self) -> usize {
+        match self {
+            WireProfile::Narrow => 40,
+            WireProfile::Standard => 80,
+            WireProfile::Wide => 160,
+        }
+    }
 }
 
 /// Mock circuit data to only do witness generation without generating a proof.
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct MockCircuitData<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
 {
     pub prover_only: ProverOnlyCircuitData<F, C, D>,
@@ -151,13 +373,20 @@ pub struct MockCircuitData<F: RichField + Extendable<D>, C: GenericConfig<D, F =
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
     MockCircuitData<F, C, D>
 {
-    pub fn generate_witness(&self, inputs: PartialWitness<F>) -> PartitionWitness<F> {
-        generate_partial_witness::<F, C, D>(inputs, &self.prover_only, &self.common).unwrap()
+    pub fn generate_witness(&self, inputs: PartialWitness<F>) -> Result<PartitionWitness<'_, F>> {
+        generate_partial_witness::<F, C, D>(inputs, &self.prover_only, &self.common)
+    }
+
+    /// Generates the witness and checks it against every gate constraint, reporting the first
+    /// unsatisfied constraint in detail.
+    pub fn check_witness(&self, inputs: &PartialWitness<F>) -> Result<()> {
+        check_witness::<F, C, D>(&self.prover_only, &self.common, inputs)
     }
 }
 
-/// Circuit data required by the prover or the verifier.
-#[derive(Eq, PartialEq, Debug)]
+/// Circuit data required by the prover or the verifier. Cloning it is cheap; see
+/// [`ProverOnlyCircuitData`].
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct CircuitData<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
     pub prover_only: ProverOnlyCircuitData<F, C, D>,
     pub verifier_only: VerifierOnlyCircuitData<C, D>,
@@ -195,10 +424,97 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         )
     }
 
+    /// Like `prove`, but runs on `pool` instead of rayon's global thread pool. See
+    /// `prove_in_thread_pool`.
+    #[cfg(feature = "parallel")]
+    pub fn prove_in_thread_pool(
+        &self,
+        inputs: PartialWitness<F>,
+        pool: &rayon::ThreadPool,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        prove_in_thread_pool::<F, C, D>(
+            pool,
+            &self.prover_only,
+            &self.common,
+            inputs,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Proves each of `inputs`, with at most `max_concurrent_proofs` proofs in flight at a time.
+    /// See `prove_batch`.
+    pub fn prove_batch(
+        &self,
+        inputs: Vec<PartialWitness<F>>,
+        max_concurrent_proofs: usize,
+    ) -> Result<Vec<ProofWithPublicInputs<F, C, D>>> {
+        prove_batch::<F, C, D>(
+            &self.prover_only,
+            &self.common,
+            inputs,
+            max_concurrent_proofs,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Runs witness generation on `inputs`, without proving. The returned witness can be queried
+    /// for the value of any target, e.g. intermediate gadget outputs, with `try_get_target`.
+    pub fn generate_witness(&self, inputs: PartialWitness<F>) -> Result<PartitionWitness<'_, F>> {
+        generate_partial_witness::<F, C, D>(inputs, &self.prover_only, &self.common)
+    }
+
+    /// Captures the part of the witness which doesn't depend on the inputs, to be reused across
+    /// proofs with `prove_with_template`.
+    pub fn witness_template(&self) -> Result<WitnessTemplate<F>> {
+        witness_template::<F, C, D>(&self.prover_only, &self.common)
+    }
+
+    /// Like `prove`, but skips the generators whose outputs were captured in `template`.
+    pub fn prove_with_template(
+        &self,
+        inputs: PartialWitness<F>,
+        template: &WitnessTemplate<F>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        prove_with_template::<F, C, D>(
+            &self.prover_only,
+            &self.common,
+            inputs,
+            template,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Generates the witness and checks it against every gate constraint, reporting the first
+    /// unsatisfied constraint in detail. Useful to debug a circuit whose proofs don't verify.
+    pub fn check_witness(&self, inputs: &PartialWitness<F>) -> Result<()> {
+        check_witness::<F, C, D>(&self.prover_only, &self.common, inputs)
+    }
+
+    /// Like `prove`, but reuses the wire polynomial work of the previous proof recorded in
+    /// `cache`. See `prove_incremental`.
+    pub fn prove_incremental(
+        &self,
+        inputs: PartialWitness<F>,
+        cache: &mut IncrementalProverCache<F, C, D>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        prove_incremental::<F, C, D>(
+            &self.prover_only,
+            &self.common,
+            inputs,
+            cache,
+            &mut TimingTree::default(),
+        )
+    }
+
     pub fn verify(&self, proof_with_pis: ProofWithPublicInputs<F, C, D>) -> Result<()> {
         verify::<F, C, D>(proof_with_pis, &self.verifier_only, &self.common)
     }
 
+    /// Verifies several proofs of this circuit, sharing some of the work between them.
+    pub fn verify_batch(&self, proofs_with_pis: &[ProofWithPublicInputs<F, C, D>]) -> Result<()> {
+        verify_batch::<F, C, D>(proofs_with_pis, &self.verifier_only, &self.common)
+    }
+
     pub fn verify_compressed(
         &self,
         compressed_proof_with_pis: CompressedProofWithPublicInputs<F, C, D>,
@@ -252,7 +568,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
 /// structure as succinct as we can. Thus we include various precomputed data which isn't strictly
 /// required, like LDEs of preprocessed polynomials. If more succinctness was desired, we could
 /// construct a more minimal prover structure and convert back and forth.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ProverCircuitData<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -292,6 +608,88 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             &mut TimingTree::default(),
         )
     }
+
+    /// Like `prove`, but runs on `pool` instead of rayon's global thread pool. See
+    /// `prove_in_thread_pool`.
+    #[cfg(feature = "parallel")]
+    pub fn prove_in_thread_pool(
+        &self,
+        inputs: PartialWitness<F>,
+        pool: &rayon::ThreadPool,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        prove_in_thread_pool::<F, C, D>(
+            pool,
+            &self.prover_only,
+            &self.common,
+            inputs,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Proves each of `inputs`, with at most `max_concurrent_proofs` proofs in flight at a time.
+    /// See `prove_batch`.
+    pub fn prove_batch(
+        &self,
+        inputs: Vec<PartialWitness<F>>,
+        max_concurrent_proofs: usize,
+    ) -> Result<Vec<ProofWithPublicInputs<F, C, D>>> {
+        prove_batch::<F, C, D>(
+            &self.prover_only,
+            &self.common,
+            inputs,
+            max_concurrent_proofs,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Runs witness generation on `inputs`, without proving. The returned witness can be queried
+    /// for the value of any target, e.g. intermediate gadget outputs, with `try_get_target`.
+    pub fn generate_witness(&self, inputs: PartialWitness<F>) -> Result<PartitionWitness<'_, F>> {
+        generate_partial_witness::<F, C, D>(inputs, &self.prover_only, &self.common)
+    }
+
+    /// Captures the part of the witness which doesn't depend on the inputs, to be reused across
+    /// proofs with `prove_with_template`.
+    pub fn witness_template(&self) -> Result<WitnessTemplate<F>> {
+        witness_template::<F, C, D>(&self.prover_only, &self.common)
+    }
+
+    /// Like `prove`, but skips the generators whose outputs were captured in `template`.
+    pub fn prove_with_template(
+        &self,
+        inputs: PartialWitness<F>,
+        template: &WitnessTemplate<F>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        prove_with_template::<F, C, D>(
+            &self.prover_only,
+            &self.common,
+            inputs,
+            template,
+            &mut TimingTree::default(),
+        )
+    }
+
+    /// Generates the witness and checks it against every gate constraint, reporting the first
+    /// unsatisfied constraint in detail. Useful to debug a circuit whose proofs don't verify.
+    pub fn check_witness(&self, inputs: &PartialWitness<F>) -> Result<()> {
+        check_witness::<F, C, D>(&self.prover_only, &self.common, inputs)
+    }
+
+    /// Like `prove`, but reuses the wire polynomial work of the previous proof recorded in
+    /// `cache`. See `prove_incremental`.
+    pub fn prove_incremental(
+        &self,
+        inputs: PartialWitness<F>,
+        cache: &mut IncrementalProverCache<F, C, D>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        prove_incremental::<F, C, D>(
+            &self.prover_only,
+            &self.common,
+            inputs,
+            cache,
+            &mut TimingTree::default(),
+        )
+    }
 }
 
 /// Circuit data required by the prover.
@@ -326,38 +724,69 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         verify::<F, C, D>(proof_with_pis, &self.verifier_only, &self.common)
     }
 
+    /// Verifies several proofs of this circuit, sharing some of the work between them.
+    pub fn verify_batch(&self, proofs_with_pis: &[ProofWithPublicInputs<F, C, D>]) -> Result<()> {
+        verify_batch::<F, C, D>(proofs_with_pis, &self.verifier_only, &self.common)
+    }
+
     pub fn verify_compressed(
         &self,
         compressed_proof_with_pis: CompressedProofWithPublicInputs<F, C, D>,
     ) -> Result<()> {
         compressed_proof_with_pis.verify(&self.verifier_only, &self.common)
     }
+
+    /// Compresses a proof of this circuit, so that e.g. a relay which only holds verifier data can
+    /// shrink proofs before forwarding them. Repeated FRI queries are only kept once, and Merkle
+    /// path nodes the verifier can recompute from other openings are dropped. The result can be
+    /// checked as is with [`VerifierCircuitData::verify_compressed`].
+    pub fn compress(
+        &self,
+        proof: ProofWithPublicInputs<F, C, D>,
+    ) -> Result<CompressedProofWithPublicInputs<F, C, D>> {
+        proof.compress(&self.verifier_only.circuit_digest, &self.common)
+    }
+
+    /// Restores the full proof from a compressed one, recomputing the dropped Merkle path nodes and
+    /// FRI evaluations. Fails if the compressed proof doesn't have the shape of a proof of this
+    /// circuit, but doesn't verify it.
+    pub fn decompress(
+        &self,
+        proof: CompressedProofWithPublicInputs<F, C, D>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        proof.decompress(&self.verifier_only.circuit_digest, &self.common)
+    }
 }
 
 /// Circuit data required by the prover, but not the verifier.
-#[derive(Eq, PartialEq, Debug)]
+///
+/// The large, immutable parts are behind `Arc`s, so that cloning this data is cheap and the clones
+/// share them. Proving only borrows it, so many threads can prove against one loaded circuit.
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct ProverOnlyCircuitData<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
 > {
-    pub generators: Vec<WitnessGeneratorRef<F, D>>,
+    pub generators: Arc<Vec<WitnessGeneratorRef<F, D>>>,
     /// Generator indices (within the `Vec` above), indexed by the representative of each target
     /// they watch.
-    pub generator_indices_by_watches: BTreeMap<usize, Vec<usize>>,
+    pub generator_indices_by_watches: Arc<BTreeMap<usize, Vec<usize>>>,
     /// Commitments to the constants polynomials and sigma polynomials.
-    pub constants_sigmas_commitment: PolynomialBatch<F, C, D>,
+    pub constants_sigmas_commitment: Arc<PolynomialBatch<F, C, D>>,
     /// The transpose of the list of sigma polynomials.
-    pub sigmas: Vec<Vec<F>>,
+    pub sigmas: Arc<Vec<Vec<F>>>,
     /// Subgroup of order `degree`.
-    pub subgroup: Vec<F>,
+    pub subgroup: Arc<Vec<F>>,
     /// Targets to be made public.
     pub public_inputs: Vec<Target>,
+    /// Names given to groups of public inputs, for reading them from proofs.
+    pub public_input_names: PublicInputNames,
     /// A map from each `Target`'s index to the index of its representative in the disjoint-set
     /// forest.
-    pub representative_map: Vec<usize>,
+    pub representative_map: Arc<Vec<usize>>,
     /// Pre-computed roots for faster FFT.
-    pub fft_root_table: Option<FftRootTable<F>>,
+    pub fft_root_table: Option<Arc<FftRootTable<F>>>,
     /// A digest of the "circuit" (i.e. the instance, minus public inputs), which can be used to
     /// seed Fiat-Shamir.
     pub circuit_digest: <<C as GenericConfig<D>>::Hasher as Hasher<F>>::Hash,
@@ -365,11 +794,19 @@ pub struct ProverOnlyCircuitData<
     pub lookup_rows: Vec<LookupWire>,
     /// A vector of (looking_in, looking_out) pairs for each lookup table index.
     pub lut_to_lookups: Vec<Lookup>,
+    /// The stack of contexts in effect from each listed gate onwards, as `(gate index, stack)`
+    /// pairs sorted by gate index. Used to describe targets in witness generation errors.
+    pub gate_contexts: Vec<(usize, String)>,
+    /// The stack of contexts in effect when each listed generator onwards was added, in the same
+    /// format as `gate_contexts`. Used to attribute generator failures and witness generation time.
+    pub generator_contexts: Vec<(usize, String)>,
 }
 
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
     ProverOnlyCircuitData<F, C, D>
 {
+    /// Serializes the prover data, including the LDEs of the constants and sigma polynomials, so
+    /// that it can be loaded with `from_bytes` instead of rebuilding the circuit.
     pub fn to_bytes(
         &self,
         generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
@@ -380,6 +817,8 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         Ok(buffer)
     }
 
+    /// Deserializes prover data written by `to_bytes`. Fails if it doesn't match the shape of
+    /// `common_data`.
     pub fn from_bytes(
         bytes: &[u8],
         generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
@@ -388,10 +827,27 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         let mut buffer = Buffer::new(bytes);
         buffer.read_prover_only_circuit_data(generator_serializer, common_data)
     }
+
+    /// The stack of contexts in effect when the given gate was added, e.g. `"root > verify proof"`.
+    pub fn gate_context(&self, gate: usize) -> Option<&str> {
+        context_at(&self.gate_contexts, gate)
+    }
+
+    /// The stack of contexts in effect when the given generator was added.
+    pub fn generator_context(&self, generator_idx: usize) -> Option<&str> {
+        context_at(&self.generator_contexts, generator_idx)
+    }
+}
+
+/// Looks up the context of `index` in a list of `(first index, context)` pairs sorted by index.
+pub(crate) fn context_at(contexts: &[(usize, String)], index: usize) -> Option<&str> {
+    let i = contexts.partition_point(|&(start, _)| start <= index);
+    i.checked_sub(1).map(|i| contexts[i].1.as_str())
 }
 
 /// Circuit data required by the verifier, but not the prover.
-#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VerifierOnlyCircuitData<C: GenericConfig<D>, const D: usize> {
     /// A commitment to each constant polynomial and each permutation polynomial.
     pub constants_sigmas_cap: MerkleCap<C::F, C::Hasher>,
@@ -453,6 +909,13 @@ pub struct CommonCircuitData<F: RichField + Extendable<D>, const D: usize> {
     pub luts: Vec<LookupTable>,
 }
 
+/// Prefix identifying the output of `CommonCircuitData::to_versioned_bytes`.
+const COMMON_CIRCUIT_DATA_MAGIC: [u8; 4] = *b"PCCD";
+
+/// The version of the encoding written by `CommonCircuitData::to_versioned_bytes`. It must be
+/// bumped whenever that encoding changes, so that older data is rejected rather than misread.
+pub const COMMON_CIRCUIT_DATA_FORMAT_VERSION: u16 = 2;
+
 impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
     pub fn to_bytes(&self, gate_serializer: &dyn GateSerializer<F, D>) -> IoResult<Vec<u8>> {
         let mut buffer = Vec::new();
@@ -468,6 +931,126 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         buffer.read_common_circuit_data(gate_serializer)
     }
 
+    /// Like `to_bytes`, but prefixed with a header recording the format version, the field and the
+    /// extension degree, so that `from_versioned_bytes` can reject incompatible data. Meant as the
+    /// interchange format between services which build circuits and those which prove them.
+    ///
+    /// Unlike `to_bytes`, this also records the config's `fri_security`.
+    pub fn to_versioned_bytes(
+        &self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+    ) -> IoResult<Vec<u8>> {
+        let mut buffer = Vec::new();
+        buffer.write_all(&COMMON_CIRCUIT_DATA_MAGIC)?;
+        buffer.write_u16(COMMON_CIRCUIT_DATA_FORMAT_VERSION)?;
+        buffer.write_u64(F::ORDER)?;
+        buffer.write_usize(D)?;
+        buffer.write_fri_security(self.config.fri_security)?;
+        buffer.write_common_circuit_data(self, gate_serializer)?;
+        Ok(buffer)
+    }
+
+    /// Reads common data written by `to_versioned_bytes`, checking that it was written with the
+    /// current format version for the same field and extension degree, that no bytes are left
+    /// over, and that the data is internally consistent.
+    pub fn from_versioned_bytes(
+        bytes: &[u8],
+        gate_serializer: &dyn GateSerializer<F, D>,
+    ) -> Result<Self> {
+        let mut buffer = Buffer::new(bytes);
+        let mut magic = [0; COMMON_CIRCUIT_DATA_MAGIC.len()];
+        buffer.read_exact(&mut magic).map_err(anyhow::Error::msg)?;
+        ensure!(
+            magic == COMMON_CIRCUIT_DATA_MAGIC,
+            "Not a versioned encoding of common circuit data"
+        );
+        let version = buffer.read_u16().map_err(anyhow::Error::msg)?;
+        ensure!(
+            version == COMMON_CIRCUIT_DATA_FORMAT_VERSION,
+            "Unsupported common circuit data format version {version}, expected {COMMON_CIRCUIT_DATA_FORMAT_VERSION}"
+        );
+        let order = buffer.read_u64().map_err(anyhow::Error::msg)?;
+        ensure!(
+            order == F::ORDER,
+            "Common circuit data is for a field of order {order}, expected {}",
+            F::ORDER
+        );
+        let extension_degree = buffer.read_usize().map_err(anyhow::Error::msg)?;
+        ensure!(
+            extension_degree == D,
+            "Common circuit data is for extension degree {extension_degree}, expected {D}"
+        );
+
+        let fri_security = buffer.read_fri_security().map_err(anyhow::Error::msg)?;
+
+        let mut common_data = buffer
+            .read_common_circuit_data(gate_serializer)
+            .map_err(anyhow::Error::msg)?;
+        common_data.config.fri_security = fri_security;
+        ensure!(
+            buffer.is_empty(),
+            "{} unexpected bytes after common circuit data",
+            buffer.remaining()
+        );
+        common_data.check_consistency()?;
+
+        Ok(common_data)
+    }
+
+    /// Checks the relations between fields which `CircuitBuilder::build` guarantees, to catch
+    /// corrupted or hand-edited data.
+    fn check_consistency(&self) -> Result<()> {
+        let config = &self.config;
+        ensure!(
+            self.fri_params.config == config.fri_config,
+            "FRI parameters don't match the circuit config"
+        );
+        ensure!(
+            self.fri_params.hiding == config.zero_knowledge,
+            "FRI hiding doesn't match the circuit's zero-knowledge setting"
+        );
+        let fri_field_bits = F::Extension::order().bits() as usize;
+        ensure!(
+            self.fri_params
+                .security_bits(config.fri_security, fri_field_bits)
+                >= config.security_bits,
+            "FRI parameters don't reach the circuit's security level under {:?}",
+            config.fri_security
+        );
+        ensure!(
+            config.num_routed_wires <= config.num_wires,
+            "More routed wires than wires"
+        );
+        ensure!(
+            self.k_is.len() == config.num_routed_wires,
+            "Expected one coset shift per routed wire"
+        );
+        ensure!(!self.gates.is_empty(), "Circuit has no gates");
+        ensure!(
+            self.selectors_info.selector_indices.len() == self.gates.len(),
+            "Expected one selector index per gate"
+        );
+        ensure!(
+            self.selectors_info
+                .selector_indices
+                .iter()
+                .all(|&i| i < self.selectors_info.num_selectors()),
+            "Gate selector index out of range"
+        );
+        ensure!(
+            self.selectors_info
+                .groups
+                .iter()
+                .all(|group| group.start < group.end && group.end <= self.gates.len()),
+            "Selector group out of range"
+        );
+        ensure!(
+            self.num_constants >= self.selectors_info.num_selectors(),
+            "Fewer constants than selectors"
+        );
+        Ok(())
+    }
+
     pub const fn degree_bits(&self) -> usize {
         self.fri_params.degree_bits
     }
@@ -496,6 +1079,56 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         self.quotient_degree_factor * self.degree()
     }
 
+    /// The length in bytes of a serialized [`ProofWithPublicInputs`] for this circuit, when
+    /// hashing with `H`.
+    pub fn proof_size<H: Hasher<F>>(&self) -> usize {
+        const FIELD_SIZE: usize = 8;
+        let ext_size = D * FIELD_SIZE;
+        let fri_params = &self.fri_params;
+        let cap_height = fri_params.config.cap_height;
+        let cap_size = (1 << cap_height) * H::HASH_SIZE;
+        // A Merkle proof is prefixed with its length, as a byte.
+        let merkle_proof_size = |tree_bits: usize| 1 + (tree_bits - cap_height) * H::HASH_SIZE;
+
+        let caps = 3 * cap_size;
+        let num_openings = self.num_preprocessed_polys()
+            + self.config.num_wires
+            + 2 * self.config.num_challenges
+            + 2 * self.num_all_lookup_polys()
+            + self.config.num_challenges * self.num_partial_products
+            + self.num_quotient_polys();
+
+        let lde_bits = fri_params.lde_bits();
+        let initial_trees = self
+            .fri_oracles()
+            .iter()
+            .map(|oracle| {
+                let salt_size = if oracle.blinding && fri_params.hiding {
+                    SALT_SIZE
+                } else {
+                    0
+                };
+                (oracle.num_polys + salt_size) * FIELD_SIZE + merkle_proof_size(lde_bits)
+            })
+            .sum::<usize>();
+        let mut tree_bits = lde_bits;
+        let steps = fri_params
+            .reduction_arity_bits
+            .iter()
+            .map(|&arity_bits| {
+                tree_bits -= arity_bits;
+                (1 << arity_bits) * ext_size + merkle_proof_size(tree_bits)
+            })
+            .sum::<usize>();
+        let fri = fri_params.reduction_arity_bits.len() * cap_size
+            + fri_params.config.num_query_rounds * (initial_trees + steps)
+            + fri_params.final_poly_len() * ext_size
+            + FIELD_SIZE;
+
+        let public_inputs = FIELD_SIZE + self.num_public_inputs * FIELD_SIZE;
+        caps + num_openings * ext_size + fri + public_inputs
+    }
+
     /// Range of the constants polynomials in the `constants_sigmas_commitment`.
     pub const fn constants_range(&self) -> Range<usize> {
         0..self.num_constants
@@ -553,6 +1186,19 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         &self,
         builder: &mut CircuitBuilder<F, D>,
         zeta: ExtensionTarget<D>,
+    ) -> FriInstanceInfoTarget<D> {
+        // The Z polynomials are also opened at g * zeta.
+        let g = F::primitive_root_of_unity(self.degree_bits());
+        let zeta_next = builder.mul_const_extension(g, zeta);
+        self.get_fri_instance_target_with_zeta_next(zeta, zeta_next)
+    }
+
+    /// Same as `get_fri_instance_target`, except that `g * zeta` is supplied by the caller, so that
+    /// `g` need not be fixed when the circuit is built.
+    pub(crate) fn get_fri_instance_target_with_zeta_next(
+        &self,
+        zeta: ExtensionTarget<D>,
+        zeta_next: ExtensionTarget<D>,
     ) -> FriInstanceInfoTarget<D> {
         // All polynomials are opened at zeta.
         let zeta_batch = FriBatchInfoTarget {
@@ -561,8 +1207,6 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         };
 
         // The Z polynomials are also opened at g * zeta.
-        let g = F::primitive_root_of_unity(self.degree_bits());
-        let zeta_next = builder.mul_const_extension(g, zeta);
         let zeta_next_batch = FriBatchInfoTarget {
             point: zeta_next,
             polynomials: self.fri_next_batch_polys(),
@@ -575,7 +1219,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CommonCircuitData<F, D> {
         }
     }
 
-    fn fri_oracles(&self) -> Vec<FriOracleInfo> {
+    pub(crate) fn fri_oracles(&self) -> Vec<FriOracleInfo> {
         vec![
             FriOracleInfo {
                 num_polys: self.num_preprocessed_polys(),
@@ -676,3 +1320,495 @@ pub struct VerifierCircuitTarget {
     /// seed Fiat-Shamir.
     pub circuit_digest: HashOutTarget,
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    use anyhow::{anyhow, Result};
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::iop::witness::{Witness, WitnessWrite};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+    #[test]
+    fn test_config_validation() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        for config in [
+            CircuitConfig::standard_recursion_config(),
+            CircuitConfig::fast_config(),
+            CircuitConfig::zk_config(),
+        ] {
+            config.validate()?;
+            CircuitBuilder::<F, D>::try_new(config)?;
+        }
+
+        let standard = CircuitConfig::standard_recursion_config();
+        let with_fri_config = |fri_config| CircuitConfig {
+            fri_config,
+            ..standard.clone()
+        };
+        let invalid = [
+            CircuitConfig {
+                num_challenges: 0,
+                ..standard.clone()
+            },
+            CircuitConfig {
+                max_quotient_degree_factor: 9,
+                ..standard.clone()
+            },
+            with_fri_config(FriConfig {
+                num_query_rounds: 0,
+                ..standard.fri_config.clone()
+            }),
+            with_fri_config(FriConfig {
+                rate_bits: 0,
+                ..standard.fri_config.clone()
+            }),
+            with_fri_config(FriConfig {
+                reduction_strategy: FriReductionStrategy::ConstantArityBits(0, 5),
+                ..standard.fri_config.clone()
+            }),
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{:?} is invalid", config);
+        }
+
+        // The cap height is checked against the degree when building.
+        let mut builder = CircuitBuilder::<F, D>::new(with_fri_config(FriConfig {
+            cap_height: 8,
+            ..standard.fri_config.clone()
+        }));
+        builder.add_virtual_public_input();
+        assert!(builder.try_build::<C>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_security_bits() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // The standard config is exactly what the list decoding conjecture gives for 100 bits.
+        let standard = CircuitConfig::standard_recursion_config();
+        assert_eq!(
+            standard.clone().with_security_bits::<F, D>(
+                100,
+                FriSecurity::ConjecturedListDecoding,
+                12
+            )?,
+            standard
+        );
+
+        // Over the quadratic Goldilocks extension, for a circuit of degree 2^12, the standard config
+        // only achieves 57 bits of FRI security without the conjecture, where the query rounds
+        // limit it. More query rounds make the commit phase error the limit instead, at 82 bits.
+        let field_bits = 128;
+        let fri_security_bits = |config: &CircuitConfig, security| {
+            config
+                .fri_config
+                .fri_params(12, false)
+                .security_bits(security, field_bits)
+        };
+        assert_eq!(fri_security_bits(&standard, FriSecurity::Proven), 57);
+        assert!(standard
+            .clone()
+            .with_security_bits::<F, D>(100, FriSecurity::Proven, 12)
+            .is_err());
+
+        let proven = standard
+            .clone()
+            .with_security_bits::<F, D>(80, FriSecurity::Proven, 12)?;
+        assert_eq!(proven.fri_security, FriSecurity::Proven);
+        assert!(fri_security_bits(&proven, FriSecurity::Proven) >= 80);
+        // The commit phase error grows with the degree.
+        assert!(standard
+            .clone()
+            .with_security_bits::<F, D>(80, FriSecurity::Proven, 20)
+            .is_err());
+
+        let config =
+            standard.with_security_bits::<F, D>(128, FriSecurity::ConjecturedListDecoding, 12)?;
+        assert_eq!(config.num_challenges, 3);
+        for config in [config, proven] {
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let x = builder.add_virtual_public_input();
+            let y = builder.square(x);
+            builder.register_public_input(y);
+            let data = builder.build::<C>();
+
+            let mut pw = PartialWitness::new();
+            pw.set_target(x, F::from_canonical_u64(3))?;
+            data.verify(data.prove(pw)?)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_witness() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x2 = builder.square(x);
+        let x3 = builder.mul(x2, x);
+        builder.register_public_input(x3);
+        let unused = builder.add_virtual_target();
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3))?;
+        let witness = data.generate_witness(pw)?;
+        assert_eq!(witness.try_get_target(x2), Some(F::from_canonical_u64(9)));
+        assert_eq!(witness.get_target(x3), F::from_canonical_u64(27));
+        assert_eq!(witness.try_get_target(unused), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prover_only_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let build = |num_squarings| {
+            let mut builder =
+                CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+            let x = builder.add_virtual_public_input();
+            let mut y = x;
+            for _ in 0..num_squarings {
+                y = builder.square(y);
+            }
+            builder.register_public_input(y);
+            (x, builder.build::<C>())
+        };
+        let (x, data) = build(1);
+
+        let generator_serializer = DefaultGeneratorSerializer::<C, D>::default();
+        let bytes = data
+            .prover_only
+            .to_bytes(&generator_serializer, &data.common)
+            .map_err(|_| anyhow!("serialization failed"))?;
+        let prover_only = ProverOnlyCircuitData::<F, C, D>::from_bytes(
+            &bytes,
+            &generator_serializer,
+            &data.common,
+        )
+        .map_err(|_| anyhow!("deserialization failed"))?;
+        assert_eq!(prover_only, data.prover_only);
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3))?;
+        let proof = prove::<F, C, D>(&prover_only, &data.common, pw, &mut TimingTree::default())?;
+        data.verify(proof)?;
+
+        // Prover data can't be loaded against the common data of a different circuit.
+        let (_, other_data) = build(1 << 12);
+        assert!(ProverOnlyCircuitData::<F, C, D>::from_bytes(
+            &bytes,
+            &generator_serializer,
+            &other_data.common,
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_common_data_versioned_serialization() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let bytes = data
+            .common
+            .to_versioned_bytes(&DefaultGateSerializer)
+            .map_err(|_| anyhow!("serialization failed"))?;
+        let common =
+            CommonCircuitData::<F, D>::from_versioned_bytes(&bytes, &DefaultGateSerializer)?;
+        assert_eq!(common, data.common);
+
+        let load = |bytes: &[u8]| {
+            CommonCircuitData::<F, D>::from_versioned_bytes(bytes, &DefaultGateSerializer)
+                .unwrap_err()
+                .to_string()
+        };
+
+        // The unversioned encoding has no header.
+        let unversioned = data
+            .common
+            .to_bytes(&DefaultGateSerializer)
+            .map_err(|_| anyhow!("serialization failed"))?;
+        assert!(load(&unversioned).contains("Not a versioned encoding"));
+
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(COMMON_CIRCUIT_DATA_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(load(&newer).contains("Unsupported common circuit data format version"));
+
+        let mut other_field = bytes.clone();
+        other_field[6..14].copy_from_slice(&7u64.to_le_bytes());
+        assert!(load(&other_field).contains("field of order 7"));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(load(&trailing).contains("1 unexpected bytes"));
+
+        assert!(load(&bytes[..bytes.len() - 1]).contains("IoError"));
+
+        // The FRI soundness assumption follows the header. The standard config's FRI parameters
+        // don't reach its security level without the conjecture.
+        let mut proven = bytes.clone();
+        proven[22] = 0;
+        assert!(load(&proven).contains("don't reach the circuit's security level under Proven"));
+
+        let config = CircuitConfig::standard_recursion_config().with_security_bits::<F, D>(
+            80,
+            FriSecurity::Proven,
+            12,
+        )?;
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+        let bytes = data
+            .common
+            .to_versioned_bytes(&DefaultGateSerializer)
+            .map_err(|_| anyhow!("serialization failed"))?;
+        let common =
+            CommonCircuitData::<F, D>::from_versioned_bytes(&bytes, &DefaultGateSerializer)?;
+        assert_eq!(common.config.fri_security, FriSecurity::Proven);
+        assert_eq!(common, data.common);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_prove_in_thread_pool() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let mut y = x;
+        for _ in 0..100 {
+            y = builder.mul_add(y, x, x);
+        }
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let prove = |num_threads| -> Result<_> {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()?;
+            let mut pw = PartialWitness::new();
+            pw.set_target(x, F::from_canonical_u64(3))?;
+            data.prove_in_thread_pool(pw, &pool)
+        };
+
+        // Proving is deterministic, so the proof doesn't depend on how work is split.
+        let proof = prove(1)?;
+        assert_eq!(prove(3)?, proof);
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_prove_batch() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.exp_u64(x, 5);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let inputs = (0..5)
+            .map(|i| -> Result<_> {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_u64(i))?;
+                Ok(pw)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        assert!(data.prove_batch(inputs.clone(), 0).is_err());
+        let proofs = data.prove_batch(inputs.clone(), 1)?;
+        // The proofs are returned in the order of their inputs, however many run concurrently.
+        assert_eq!(data.prove_batch(inputs, 2)?, proofs);
+        for (i, proof) in proofs.into_iter().enumerate() {
+            assert_eq!(
+                proof.public_inputs[1],
+                F::from_canonical_u64(i as u64).exp_u64(5)
+            );
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_shared_prover_data() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.exp_u64(x, 3);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        // Clones share the preprocessed polynomials rather than copying them.
+        let prover_data = data.clone().prover_data();
+        assert!(Arc::ptr_eq(
+            &prover_data.prover_only.constants_sigmas_commitment,
+            &data.prover_only.constants_sigmas_commitment
+        ));
+        assert!(Arc::ptr_eq(
+            &prover_data.prover_only.generators,
+            &data.prover_only.generators
+        ));
+
+        let proofs = std::thread::scope(|scope| {
+            let handles = (0..3)
+                .map(|i| {
+                    let prover_data = &prover_data;
+                    scope.spawn(move || -> Result<_> {
+                        let mut pw = PartialWitness::new();
+                        pw.set_target(x, F::from_canonical_u64(i))?;
+                        prover_data.prove(pw)
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Result<Vec<_>>>()
+        })?;
+        for (i, proof) in proofs.into_iter().enumerate() {
+            assert_eq!(proof.public_inputs[1], F::from_canonical_usize(i).cube());
+            data.verify(proof)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_upstream_proof() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        // Written by upstream plonky2 1.0.0 with its default gate serializer, for a circuit with
+        // the standard recursion config, which hashes two inputs with Poseidon. See
+        // `testdata/upstream/README.md` for how to regenerate them.
+        let common_bytes = include_bytes!("../../testdata/upstream/common_circuit_data.bin");
+        let common =
+            CommonCircuitData::<F, D>::from_bytes(common_bytes.to_vec(), &DefaultGateSerializer)
+                .map_err(|_| anyhow!("failed to read upstream common data"))?;
+        assert_eq!(common.config, CircuitConfig::standard_recursion_config());
+        assert_eq!(
+            common.to_bytes(&DefaultGateSerializer).unwrap(),
+            common_bytes
+        );
+        let verifier_only = VerifierOnlyCircuitData::<C, D>::from_bytes(
+            include_bytes!("../../testdata/upstream/verifier_only_circuit_data.bin").to_vec(),
+        )
+        .map_err(|_| anyhow!("failed to read upstream verifier data"))?;
+        let verifier_data = VerifierCircuitData {
+            verifier_only,
+            common,
+        };
+
+        let proof = ProofWithPublicInputs::<F, C, D>::from_bytes(
+            include_bytes!("../../testdata/upstream/proof_with_public_inputs.bin").to_vec(),
+            &verifier_data.common,
+        )?;
+        let compressed_proof = CompressedProofWithPublicInputs::<F, C, D>::from_bytes(
+            include_bytes!("../../testdata/upstream/compressed_proof_with_public_inputs.bin")
+                .to_vec(),
+            &verifier_data.common,
+        )?;
+        verifier_data.verify(proof.clone())?;
+        verifier_data.verify_compressed(compressed_proof.clone())?;
+        assert_eq!(verifier_data.decompress(compressed_proof)?, proof);
+
+        // The proof can be verified recursively too.
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let proof_target = builder.add_virtual_proof_with_pis(&verifier_data.common);
+        let verifier_data_target =
+            builder.add_virtual_verifier_data(verifier_data.common.fri_params.config.cap_height);
+        builder.verify_proof::<C>(&proof_target, &verifier_data_target, &verifier_data.common);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&proof_target, &proof)?;
+        pw.set_verifier_data_target(&verifier_data_target, &verifier_data.verifier_only)?;
+        data.verify(data.prove(pw)?)
+    }
+
+    #[test]
+    fn test_verifier_data_compression() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3))?;
+        let proof = data.prove(pw)?;
+
+        let verifier_data = data.verifier_data();
+        let compressed = verifier_data.compress(proof.clone())?;
+        assert!(compressed.to_bytes().len() < proof.to_bytes().len());
+
+        // Each distinct query's initial Merkle paths are kept once, with shared nodes removed.
+        let query_rounds = &compressed.proof.opening_proof.query_round_proofs;
+        assert_eq!(
+            query_rounds.initial_trees_proofs.len(),
+            query_rounds.indices.iter().unique().count()
+        );
+        let compressed_siblings: usize = query_rounds
+            .initial_trees_proofs
+            .values()
+            .flat_map(|p| &p.evals_proofs)
+            .map(|(_, merkle_proof)| merkle_proof.siblings.len())
+            .sum();
+        let siblings: usize = proof
+            .proof
+            .opening_proof
+            .query_round_proofs
+            .iter()
+            .flat_map(|q| &q.initial_trees_proof.evals_proofs)
+            .map(|(_, merkle_proof)| merkle_proof.siblings.len())
+            .sum();
+        assert!(compressed_siblings < siblings);
+
+        verifier_data.verify_compressed(compressed.clone())?;
+        assert_eq!(verifier_data.decompress(compressed)?, proof);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,519 @@
+//! A structured description of a built circuit, which can be exported to e.g. JSON for audits,
+//! diffing and non-Rust tooling, and loaded back into `CircuitData`.
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
+use core::cmp::max;
+use core::fmt::Write as _;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use anyhow::{anyhow, ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::field::extension::Extendable;
+use crate::field::fft::fft_root_table;
+use crate::fri::oracle::PolynomialBatch;
+use crate::gates::gate::GateInstance;
+use crate::gates::selectors::{selector_polynomials, UNUSED_SELECTOR};
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::witness_template;
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::plonk::circuit_builder::{
+    circuit_digest, gate_constant_polys, generator_indices_by_watches, sigma_polys,
+};
+use crate::plonk::circuit_data::{
+    CircuitData, CommonCircuitData, ProverOnlyCircuitData, VerifierOnlyCircuitData,
+};
+use crate::plonk::config::{GenericConfig, GenericHashOut};
+use crate::plonk::plonk_common::PlonkOracle;
+use crate::plonk::public_inputs::PublicInputNames;
+use crate::util::serialization::{
+    Buffer, GateSerializer, Read, Remaining, WitnessGeneratorSerializer, Write,
+};
+use crate::util::timing::TimingTree;
+use crate::util::{log2_ceil, transpose_poly_values};
+
+/// A description of a built circuit: the gate and constants in each row, the copy constraints and
+/// the public inputs. It derives `Serialize`, so it can be written as JSON with e.g. `serde_json`.
+///
+/// The gate types and generators are also included in binary form, so that `to_circuit_data` can
+/// reconstruct the circuit exactly. Circuits with lookup tables aren't supported.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CircuitDescription<F: RichField> {
+    /// The ID of each gate type, in the order of `CommonCircuitData::gates`.
+    pub gates: Vec<String>,
+    /// The gate placed in each row, padding and blinding rows included.
+    pub rows: Vec<RowDescription<F>>,
+    /// Each set of two or more targets which are constrained to be equal, in index order.
+    pub copy_constraints: Vec<Vec<Target>>,
+    /// The targets which are made public, in order.
+    pub public_inputs: Vec<Target>,
+    /// The number of virtual targets, i.e. targets which aren't wires.
+    pub num_virtual_targets: usize,
+    /// The circuit's `CommonCircuitData`, in its versioned binary encoding, as hex.
+    pub common_data: String,
+    /// The witness generators, serialized with a `WitnessGeneratorSerializer`, as hex.
+    pub generators: String,
+    /// The domain separator the circuit was built with, which is hashed into its digest.
+    pub domain_separator: Vec<F>,
+    /// The circuit digest, as hex. `to_circuit_data` recomputes the digest and checks it against
+    /// this one if present, so it should be cleared after editing the description.
+    #[serde(default)]
+    pub circuit_digest: Option<String>,
+}
+
+/// A gate placed in a row of a circuit.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RowDescription<F: RichField> {
+    /// The index of the gate type in `CircuitDescription::gates`.
+    pub gate: usize,
+    /// The constants configuring this instance of the gate.
+    pub constants: Vec<F>,
+}
+
+impl<F: RichField> CircuitDescription<F> {
+    /// Describes a circuit, built with the given domain separator (empty unless set with
+    /// `CircuitBuilder::set_domain_separator`). Its constants and sigma polynomials must have been
+    /// committed to, i.e. it must have been built with `commit_to_sigma`.
+    pub fn from_circuit_data<C: GenericConfig<D, F = F>, const D: usize>(
+        data: &CircuitData<F, C, D>,
+        domain_separator: &[F],
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<Self>
+    where
+        F: Extendable<D>,
+    {
+        let common = &data.common;
+        let prover_only = &data.prover_only;
+        ensure!(
+            common.luts.is_empty(),
+            "Circuits with lookup tables can't be described"
+        );
+        let num_routed_wires = common.config.num_routed_wires;
+        let constants_sigmas = &prover_only.constants_sigmas_commitment.polynomials;
+        ensure!(
+            constants_sigmas.len() == common.num_constants + num_routed_wires,
+            "The circuit's constants and sigma polynomials weren't committed to"
+        );
+        ensure!(
+            circuit_digest::<F, C, D>(
+                &data.verifier_only.constants_sigmas_cap,
+                domain_separator,
+                common.degree_bits(),
+            ) == prover_only.circuit_digest,
+            "The domain separator doesn't match the circuit digest"
+        );
+
+        // Recover each row's gate from the selector polynomials, and its constants from the
+        // constant polynomials which follow them.
+        let num_selectors = common.selectors_info.num_selectors();
+        let constant_values = constants_sigmas[..common.num_constants]
+            .iter()
+            .map(|poly| poly.clone().fft().values)
+            .collect::<Vec<_>>();
+        let rows = (0..common.degree())
+            .map(|row| {
+                let gate = (0..num_selectors)
+                    .map(|selector| constant_values[selector][row].to_canonical_u64() as usize)
+                    .find(|&gate| gate != UNUSED_SELECTOR)
+                    .filter(|&gate| gate < common.gates.len())
+                    .ok_or_else(|| anyhow!("No gate is selected in row {row}"))?;
+                let constants = constant_values[num_selectors..]
+                    .iter()
+                    .take(common.gates[gate].0.num_constants())
+                    .map(|values| values[row])
+                    .collect();
+                Ok(RowDescription { gate, constants })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Group targets by their representative in the copy constraint partition.
+        let num_wires = common.config.num_wires;
+        let degree = common.degree();
+        let mut classes = vec![vec![]; prover_only.representative_map.len()];
+        for (index, &representative) in prover_only.representative_map.iter().enumerate() {
+            classes[representative].push(Target::from_index(index, num_wires, degree));
+        }
+        let mut copy_constraints = classes
+            .into_iter()
+            .filter(|class| class.len() > 1)
+            .collect::<Vec<_>>();
+        copy_constraints.sort_by_key(|class| class[0].index(num_wires, degree));
+
+        let mut generators = Vec::new();
+        generators
+            .write_usize(prover_only.generators.len())
+            .map_err(anyhow::Error::msg)?;
+        for generator in prover_only.generators.iter() {
+            generators
+                .write_generator(generator, generator_serializer, common)
+                .map_err(anyhow::Error::msg)?;
+        }
+
+        let common_data = common
+            .to_versioned_bytes(gate_serializer)
+            .map_err(anyhow::Error::msg)?;
+
+        Ok(Self {
+            gates: common.gates.iter().map(|gate| gate.0.id()).collect(),
+            rows,
+            copy_constraints,
+            public_inputs: prover_only.public_inputs.clone(),
+            num_virtual_targets: prover_only.representative_map.len() - num_wires * degree,
+            common_data: to_hex(&common_data),
+            generators: to_hex(&generators),
+            domain_separator: domain_separator.to_vec(),
+            circuit_digest: Some(to_hex(&prover_only.circuit_digest.to_bytes())),
+        })
+    }
+
+    /// Reconstructs the described circuit. The rows, copy constraints and public inputs are taken
+    /// from this description, so edits to them carry over to the circuit, and its digest is
+    /// recomputed from them.
+    ///
+    /// The generators aren't regenerated, so the constant wires they set must still match the
+    /// constants in each row.
+    pub fn to_circuit_data<C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        gate_serializer: &dyn GateSerializer<F, D>,
+        generator_serializer: &dyn WitnessGeneratorSerializer<F, D>,
+    ) -> Result<CircuitData<F, C, D>>
+    where
+        F: Extendable<D>,
+    {
+        let common = CommonCircuitData::<F, D>::from_versioned_bytes(
+            &from_hex(&self.common_data)?,
+            gate_serializer,
+        )?;
+        ensure!(
+            common.luts.is_empty(),
+            "Circuits with lookup tables can't be described"
+        );
+        ensure!(
+            self.gates.len() == common.gates.len()
+                && self
+                    .gates
+                    .iter()
+                    .zip(&common.gates)
+                    .all(|(id, gate)| *id == gate.0.id()),
+            "Gate IDs don't match the common circuit data"
+        );
+        let degree = common.degree();
+        ensure!(
+            self.rows.len() == degree,
+            "Expected {degree} rows, found {}",
+            self.rows.len()
+        );
+        ensure!(
+            self.public_inputs.len() == common.num_public_inputs,
+            "Expected {} public inputs, found {}",
+            common.num_public_inputs,
+            self.public_inputs.len()
+        );
+
+        let gate_instances = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(row, RowDescription { gate, constants })| {
+                let gate_ref = common
+                    .gates
+                    .get(*gate)
+                    .ok_or_else(|| anyhow!("Unknown gate {gate} in row {row}"))?;
+                ensure!(
+                    constants.len() == gate_ref.0.num_constants(),
+                    "Wrong number of constants in row {row}"
+                );
+                Ok(GateInstance {
+                    gate_ref: gate_ref.clone(),
+                    constants: constants.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let config = &common.config;
+        let is_valid_target = |t: &Target| match *t {
+            Target::Wire(Wire { row, column }) => row < degree && column < config.num_wires,
+            Target::VirtualTarget { index } => index < self.num_virtual_targets,
+        };
+        ensure!(
+            self.copy_constraints
+                .iter()
+                .flatten()
+                .chain(&self.public_inputs)
+                .all(is_valid_target),
+            "Target out of range"
+        );
+
+        // As in `CircuitBuilder::build`.
+        let (mut constant_vecs, _) = selector_polynomials(
+            &common.gates,
+            &gate_instances,
+            common.quotient_degree_factor + 1,
+        );
+        let max_constants = common
+            .gates
+            .iter()
+            .map(|gate| gate.0.num_constants())
+            .max()
+            .unwrap_or(0);
+        constant_vecs.extend(gate_constant_polys(&gate_instances, max_constants));
+        ensure!(
+            constant_vecs.len() == common.num_constants,
+            "Number of constants doesn't match the common circuit data"
+        );
+
+        let degree_bits = common.degree_bits();
+        let rate_bits = config.fri_config.rate_bits;
+        let subgroup = F::two_adic_subgroup(degree_bits);
+        let (sigma_vecs, forest) = sigma_polys(
+            config,
+            degree,
+            self.num_virtual_targets,
+            self.copy_constraints
+                .iter()
+                .filter_map(|class| class.split_first())
+                .flat_map(|(&first, rest)| rest.iter().map(move |&target| (first, target))),
+            &common.k_is,
+            &subgroup,
+        );
+
+        let max_fft_points =
+            1 << (degree_bits + max(rate_bits, log2_ceil(common.quotient_degree_factor)));
+        let fft_root_table = fft_root_table(max_fft_points);
+        let constants_sigmas_commitment = PolynomialBatch::<F, C, D>::from_values(
+            [constant_vecs, sigma_vecs.clone()].concat(),
+            rate_bits,
+            PlonkOracle::CONSTANTS_SIGMAS.blinding,
+            config.fri_config.cap_height,
+            &mut TimingTree::default(),
+            Some(&fft_root_table),
+        );
+
+        let generator_bytes = from_hex(&self.generators)?;
+        let mut buffer = Buffer::new(&generator_bytes);
+        let num_generators = buffer.read_usize().map_err(anyhow::Error::msg)?;
+        let generators = (0..num_generators)
+            .map(|_| {
+                buffer
+                    .read_generator(generator_serializer, &common)
+                    .map_err(anyhow::Error::msg)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(buffer.is_empty(), "Unexpected bytes after generators");
+
+        let constants_sigmas_cap = constants_sigmas_commitment.merkle_tree.cap.clone();
+        let circuit_digest =
+            circuit_digest::<F, C, D>(&constants_sigmas_cap, &self.domain_separator, degree_bits);
+        if let Some(stored_digest) = &self.circuit_digest {
+            ensure!(
+                from_hex(stored_digest)? == circuit_digest.to_bytes(),
+                "The circuit digest doesn't match the described circuit"
+            );
+        }
+        let prover_only = ProverOnlyCircuitData {
+            generator_indices_by_watches: Arc::new(generator_indices_by_watches(
+                &generators,
+                &forest,
+            )),
+            generators: Arc::new(generators),
+            constants_sigmas_commitment: Arc::new(constants_sigmas_commitment),
+            sigmas: Arc::new(transpose_poly_values(sigma_vecs)),
+            subgroup: Arc::new(subgroup),
+            public_inputs: self.public_inputs.clone(),
+            public_input_names: PublicInputNames::default(),
+            representative_map: Arc::new(forest.parents),
+            fft_root_table: Some(Arc::new(fft_root_table)),
+            circuit_digest,
+            lookup_rows: vec![],
+            lut_to_lookups: vec![],
+            gate_contexts: vec![],
+            generator_contexts: vec![],
+        };
+        let verifier_only = VerifierOnlyCircuitData {
+            constants_sigmas_cap,
+            circuit_digest,
+        };
+
+        // Constant wires are set by generators, which an edit to a row's constants doesn't update.
+        let template = witness_template(&prover_only, &common)?;
+        for (row, RowDescription { gate, constants }) in self.rows.iter().enumerate() {
+            for (constant_index, wire_index) in common.gates[*gate].0.extra_constant_wires() {
+                let target = Target::wire(row, wire_index);
+                let representative =
+                    prover_only.representative_map[target.index(config.num_wires, degree)];
+                if let Some(value) = template.value(representative) {
+                    ensure!(
+                        value == constants[constant_index],
+                        "The generators set wire {wire_index} of row {row} to {value}, not to its \
+                         constant {}",
+                        constants[constant_index]
+                    );
+                }
+            }
+        }
+
+        Ok(CircuitData {
+            prover_only,
+            verifier_only,
+            common,
+        })
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        write!(hex, "{byte:02x}").expect("Writing to a string cannot fail.");
+    }
+    hex
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    ensure!(hex.len().is_multiple_of(2), "Odd-length hex string");
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid hex string"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_circuit_description_round_trip() -> Result<()> {
+        for config in [
+            CircuitConfig::standard_recursion_config(),
+            CircuitConfig::zk_config(),
+        ] {
+            let domain_separator = vec![F::from_canonical_u64(42)];
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            builder.set_domain_separator(domain_separator.clone());
+            let x = builder.add_virtual_public_input();
+            let y = builder.add_virtual_target();
+            let three = builder.constant(F::from_canonical_u64(3));
+            let z = builder.mul_add(x, y, three);
+            let w = builder.div(z, x);
+            builder.register_public_input(w);
+            let data = builder.build::<C>();
+
+            let generator_serializer = DefaultGeneratorSerializer::<C, D>::default();
+            assert!(CircuitDescription::from_circuit_data(
+                &data,
+                &[],
+                &DefaultGateSerializer,
+                &generator_serializer,
+            )
+            .is_err());
+            let description = CircuitDescription::from_circuit_data(
+                &data,
+                &domain_separator,
+                &DefaultGateSerializer,
+                &generator_serializer,
+            )?;
+            assert_eq!(description.rows.len(), data.common.degree());
+            assert!(description
+                .copy_constraints
+                .iter()
+                .any(|class| class.contains(&x)));
+
+            let json = serde_json::to_string(&description)?;
+            let description: CircuitDescription<F> = serde_json::from_str(&json)?;
+            let loaded = description
+                .to_circuit_data::<C, D>(&DefaultGateSerializer, &generator_serializer)?;
+            assert_eq!(loaded.verifier_only, data.verifier_only);
+            assert_eq!(loaded.common, data.common);
+
+            // Proofs from either side verify on the other.
+            let mut pw = PartialWitness::new();
+            pw.set_target(x, F::from_canonical_u64(5))?;
+            pw.set_target(y, F::from_canonical_u64(7))?;
+            data.verify(loaded.prove(pw.clone())?)?;
+            loaded.verify(data.prove(pw)?)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_edited_circuit_description() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        let three = builder.constant(F::from_canonical_u64(3));
+        let z = builder.add(y, three);
+        builder.register_public_input(z);
+        let data = builder.build::<C>();
+
+        let generator_serializer = DefaultGeneratorSerializer::<C, D>::default();
+        let mut description = CircuitDescription::from_circuit_data(
+            &data,
+            &[],
+            &DefaultGateSerializer,
+            &generator_serializer,
+        )?;
+
+        // Dropping a copy constraint between routed wires changes the circuit.
+        let num_routed_wires = data.common.config.num_routed_wires;
+        let is_routed_wire =
+            |t: &&Target| matches!(t, Target::Wire(wire) if wire.column < num_routed_wires);
+        let i = description
+            .copy_constraints
+            .iter()
+            .position(|class| class.iter().filter(is_routed_wire).count() > 1)
+            .expect("No copy constraint between routed wires");
+        description.copy_constraints.remove(i);
+        // The stored digest is now stale, so it's rejected until cleared.
+        assert!(description
+            .to_circuit_data::<C, D>(&DefaultGateSerializer, &generator_serializer)
+            .is_err());
+        description.circuit_digest = None;
+        let loaded =
+            description.to_circuit_data::<C, D>(&DefaultGateSerializer, &generator_serializer)?;
+        assert_ne!(loaded.verifier_only, data.verifier_only);
+
+        // Editing a constant without its generator is rejected.
+        let constant_gate = description
+            .gates
+            .iter()
+            .position(|id| id.starts_with("ConstantGate"))
+            .expect("No constant gate");
+        let mut edited = description.clone();
+        edited
+            .rows
+            .iter_mut()
+            .find(|row| row.gate == constant_gate)
+            .expect("No constant gate row")
+            .constants[0] += F::ONE;
+        assert!(edited
+            .to_circuit_data::<C, D>(&DefaultGateSerializer, &generator_serializer)
+            .is_err());
+
+        description.rows.pop();
+        assert!(description
+            .to_circuit_data::<C, D>(&DefaultGateSerializer, &generator_serializer)
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -2,9 +2,9 @@
 //!
 //! This module defines a [`Hasher`] trait as well as its recursive
 //! counterpart [`AlgebraicHasher`] for in-circuit hashing. It also
-//! provides concrete configurations, one fully recursive leveraging
-//! the Poseidon hash function both internally and natively, and one
-//! mixing Poseidon internally and truncated Keccak externally.
+//! provides concrete configurations, five fully recursive leveraging
+//! the Poseidon, Poseidon2, Monolith, Rescue-Prime or Tip5 hash functions both internally and natively,
+//! and others mixing Poseidon internally and Keccak, Blake3 or SHA-256 externally.
 
 #[cfg(not(feature = "std"))]
 use alloc::{vec, vec::Vec};
@@ -16,10 +16,16 @@ use serde::Serialize;
 use crate::field::extension::quadratic::QuadraticExtension;
 use crate::field::extension::{Extendable, FieldExtension};
 use crate::field::goldilocks_field::GoldilocksField;
+use crate::hash::blake3::Blake3Hash;
 use crate::hash::hash_types::{HashOut, RichField};
 use crate::hash::hashing::PlonkyPermutation;
-use crate::hash::keccak::KeccakHash;
+use crate::hash::keccak::{EvmKeccakHash, KeccakHash};
+use crate::hash::monolith::MonolithHash;
 use crate::hash::poseidon::PoseidonHash;
+use crate::hash::poseidon2::Poseidon2Hash;
+use crate::hash::rescue_prime::RescuePrimeHash;
+use crate::hash::sha256::Sha256Hash;
+use crate::hash::tip5::Tip5Hash;
 use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
 
@@ -51,7 +57,7 @@ pub trait Hasher<F: RichField>: Sized + Copy + Debug + Eq + PartialEq {
     fn hash_pad(input: &[F]) -> Self::Hash {
         let mut padded_input = input.to_vec();
         padded_input.push(F::ONE);
-        while (padded_input.len() + 1) % Self::Permutation::RATE != 0 {
+        while !(padded_input.len() + 1).is_multiple_of(Self::Permutation::RATE) {
             padded_input.push(F::ZERO);
         }
         padded_input.push(F::ONE);
@@ -73,6 +79,27 @@ pub trait Hasher<F: RichField>: Sized + Copy + Debug + Eq + PartialEq {
         }
     }
 
+    /// Applies `hash_or_noop` to each of `inputs`. Hashers with a vectorized permutation override
+    /// this to hash several inputs at once, in parallel lanes.
+    fn hash_or_noop_many<L: AsRef<[F]>>(inputs: &[L]) -> Vec<Self::Hash> {
+        inputs
+            .iter()
+            .map(|input| Self::hash_or_noop(input.as_ref()))
+            .collect()
+    }
+
+    /// Applies `hash_or_noop` to the rows with indices `rows` of the matrix whose columns are
+    /// `columns`. Hashers with a vectorized permutation override this to read the rows straight
+    /// from the columns into parallel lanes, rather than gathering each row first.
+    fn hash_or_noop_rows<Col: AsRef<[F]>>(columns: &[Col], rows: &[usize]) -> Vec<Self::Hash> {
+        rows.iter()
+            .map(|&row| {
+                let input: Vec<F> = columns.iter().map(|col| col.as_ref()[row]).collect();
+                Self::hash_or_noop(&input)
+            })
+            .collect()
+    }
+
     fn two_to_one(left: Self::Hash, right: Self::Hash) -> Self::Hash;
 }
 
@@ -106,6 +133,16 @@ pub trait GenericConfig<const D: usize>:
 }
 
 /// Configuration using Poseidon over the Goldilocks field.
+///
+/// Like the other Goldilocks configurations, this uses the quadratic extension (`D = 2`) for
+/// challenges, which gives smaller proofs and faster proving than a higher degree extension at a
+/// lower soundness level, as the soundness error of FRI grows with the circuit's degree relative
+/// to the 128-bit extension field. With [`CircuitConfig::standard_recursion_config`], this amounts to
+/// ~100 bits under the conjectured list-decoding assumption, but only 57 provable bits; see
+/// [`FriParams::security_bits`].
+///
+/// [`CircuitConfig::standard_recursion_config`]: crate::plonk::circuit_data::CircuitConfig::standard_recursion_config
+/// [`FriParams::security_bits`]: crate::fri::FriParams::security_bits
 #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize)]
 pub struct PoseidonGoldilocksConfig;
 impl GenericConfig<2> for PoseidonGoldilocksConfig {
@@ -115,6 +152,49 @@ impl GenericConfig<2> for PoseidonGoldilocksConfig {
     type InnerHasher = PoseidonHash;
 }
 
+/// Configuration using Poseidon2 over the Goldilocks field, both natively and in circuits.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Poseidon2GoldilocksConfig;
+impl GenericConfig<2> for Poseidon2GoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = Poseidon2Hash;
+    type InnerHasher = Poseidon2Hash;
+}
+
+/// Configuration using Monolith over the Goldilocks field, both natively and in circuits. Monolith
+/// is much faster than Poseidon natively, but circuits hashing with it need lookups.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct MonolithGoldilocksConfig;
+impl GenericConfig<2> for MonolithGoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = MonolithHash;
+    type InnerHasher = MonolithHash;
+}
+
+/// Configuration using Rescue-Prime over the Goldilocks field, both natively and in circuits. The
+/// permutation is the same as Winterfell's `Rp64_256`.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct RescuePrimeGoldilocksConfig;
+impl GenericConfig<2> for RescuePrimeGoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = RescuePrimeHash;
+    type InnerHasher = RescuePrimeHash;
+}
+
+/// Configuration using Tip5 over the Goldilocks field, both natively and in circuits. The
+/// permutation is the same as Triton VM's, but digests have four elements instead of five.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Tip5GoldilocksConfig;
+impl GenericConfig<2> for Tip5GoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = Tip5Hash;
+    type InnerHasher = Tip5Hash;
+}
+
 /// Configuration using truncated Keccak over the Goldilocks field.
 #[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
 pub struct KeccakGoldilocksConfig;
@@ -124,3 +204,38 @@ impl GenericConfig<2> for KeccakGoldilocksConfig {
     type Hasher = KeccakHash<25>;
     type InnerHasher = PoseidonHash;
 }
+
+/// Configuration using Keccak over the Goldilocks field, with an encoding of field elements which
+/// lets EVM verifiers recompute Merkle roots and challenges with the native `keccak256`. See
+/// [`EvmKeccakHash`] and [`crate::hash::keccak::EvmKeccakPermutation`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct EvmKeccakGoldilocksConfig;
+impl GenericConfig<2> for EvmKeccakGoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = EvmKeccakHash;
+    type InnerHasher = PoseidonHash;
+}
+
+/// Configuration using truncated Blake3 over the Goldilocks field. This makes native proving and
+/// verification faster, but proofs are expensive to verify recursively.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Blake3GoldilocksConfig;
+impl GenericConfig<2> for Blake3GoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = Blake3Hash<25>;
+    type InnerHasher = PoseidonHash;
+}
+
+/// Configuration using SHA-256 over the Goldilocks field, for Merkle trees and the challenger. This
+/// suits deployments which require FIPS-approved primitives for commitments, but proofs are
+/// expensive to verify recursively.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Sha256GoldilocksConfig;
+impl GenericConfig<2> for Sha256GoldilocksConfig {
+    type F = GoldilocksField;
+    type FE = QuadraticExtension<Self::F>;
+    type Hasher = Sha256Hash<32>;
+    type InnerHasher = PoseidonHash;
+}
@@ -1,18 +1,15 @@
 #[cfg(not(feature = "std"))]
 use alloc::{vec, vec::Vec};
 
-use hashbrown::HashSet;
-
 use super::circuit_builder::NUM_COINS_LOOKUP;
 use crate::field::extension::Extendable;
 use crate::field::polynomial::PolynomialCoeffs;
-use crate::fri::proof::{CompressedFriProof, FriChallenges, FriProof, FriProofTarget};
-use crate::fri::verifier::{compute_evaluation, fri_combine_initial, PrecomputedReducedOpenings};
+use crate::fri::proof::{CompressedFriProof, FriProof, FriProofTarget};
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::hash::hash_types::{HashOutTarget, MerkleCapTarget, RichField};
 use crate::hash::merkle_tree::MerkleCap;
 use crate::iop::challenger::{Challenger, RecursiveChallenger};
-use crate::iop::target::Target;
+use crate::iop::target::{BoolTarget, Target};
 use crate::plonk::circuit_builder::CircuitBuilder;
 use crate::plonk::circuit_data::CommonCircuitData;
 use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
@@ -21,7 +18,6 @@ use crate::plonk::proof::{
     OpeningSetTarget, Proof, ProofChallenges, ProofChallengesTarget, ProofTarget,
     ProofWithPublicInputs, ProofWithPublicInputsTarget,
 };
-use crate::util::reverse_bits;
 
 fn get_challenges<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     public_inputs_hash: <<C as GenericConfig<D>>::InnerHasher as Hasher<F>>::Hash,
@@ -182,74 +178,13 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         &self,
         challenges: &ProofChallenges<F, D>,
         common_data: &CommonCircuitData<F, D>,
-    ) -> FriInferredElements<F, D> {
-        let ProofChallenges {
-            plonk_zeta,
-            fri_challenges:
-                FriChallenges {
-                    fri_alpha,
-                    fri_betas,
-                    fri_query_indices,
-                    ..
-                },
-            ..
-        } = challenges;
-        let mut fri_inferred_elements = Vec::new();
-        // Holds the indices that have already been seen at each reduction depth.
-        let mut seen_indices_by_depth =
-            vec![HashSet::new(); common_data.fri_params.reduction_arity_bits.len()];
-        let precomputed_reduced_evals = PrecomputedReducedOpenings::from_os_and_alpha(
+    ) -> anyhow::Result<FriInferredElements<F, D>> {
+        self.proof.opening_proof.inferred_elements::<C>(
+            &common_data.get_fri_instance(challenges.plonk_zeta),
             &self.proof.openings.to_fri_openings(),
-            *fri_alpha,
-        );
-        let log_n = common_data.degree_bits() + common_data.config.fri_config.rate_bits;
-        // Simulate the proof verification and collect the inferred elements.
-        // The content of the loop is basically the same as the `fri_verifier_query_round` function.
-        for &(mut x_index) in fri_query_indices {
-            let mut subgroup_x = F::MULTIPLICATIVE_GROUP_GENERATOR
-                * F::primitive_root_of_unity(log_n).exp_u64(reverse_bits(x_index, log_n) as u64);
-            let mut old_eval = fri_combine_initial::<F, C, D>(
-                &common_data.get_fri_instance(*plonk_zeta),
-                &self
-                    .proof
-                    .opening_proof
-                    .query_round_proofs
-                    .initial_trees_proofs[&x_index],
-                *fri_alpha,
-                subgroup_x,
-                &precomputed_reduced_evals,
-                &common_data.fri_params,
-            );
-            for (i, &arity_bits) in common_data
-                .fri_params
-                .reduction_arity_bits
-                .iter()
-                .enumerate()
-            {
-                let coset_index = x_index >> arity_bits;
-                if !seen_indices_by_depth[i].insert(coset_index) {
-                    // If this index has already been seen, we can skip the rest of the reductions.
-                    break;
-                }
-                fri_inferred_elements.push(old_eval);
-                let arity = 1 << arity_bits;
-                let mut evals = self.proof.opening_proof.query_round_proofs.steps[i][&coset_index]
-                    .evals
-                    .clone();
-                let x_index_within_coset = x_index & (arity - 1);
-                evals.insert(x_index_within_coset, old_eval);
-                old_eval = compute_evaluation(
-                    subgroup_x,
-                    x_index_within_coset,
-                    arity_bits,
-                    &evals,
-                    fri_betas[i],
-                );
-                subgroup_x = subgroup_x.exp_power_of_2(arity_bits);
-                x_index = coset_index;
-            }
-        }
-        FriInferredElements(fri_inferred_elements)
+            &challenges.fri_challenges,
+            &common_data.fri_params,
+        )
     }
 }
 
@@ -266,6 +201,7 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
         pow_witness: Target,
         inner_circuit_digest: HashOutTarget,
         inner_common_data: &CommonCircuitData<F, D>,
+        final_poly_lens: Option<&[(BoolTarget, usize)]>,
     ) -> ProofChallengesTarget<D>
     where
         C::Hasher: AlgebraicHasher<F>,
@@ -308,19 +244,31 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
 
         challenger.observe_openings(&openings.to_fri_openings());
 
-        ProofChallengesTarget {
-            plonk_betas,
-            plonk_gammas,
-            plonk_alphas,
-            plonk_deltas,
-            plonk_zeta,
-            fri_challenges: challenger.fri_challenges(
+        let fri_challenges = match final_poly_lens {
+            Some(final_poly_lens) => challenger.fri_challenges_with_final_poly_lens(
+                self,
+                commit_phase_merkle_caps,
+                final_poly,
+                final_poly_lens,
+                pow_witness,
+                &inner_common_data.config.fri_config,
+            ),
+            None => challenger.fri_challenges(
                 self,
                 commit_phase_merkle_caps,
                 final_poly,
                 pow_witness,
                 &inner_common_data.config.fri_config,
             ),
+        };
+
+        ProofChallengesTarget {
+            plonk_betas,
+            plonk_gammas,
+            plonk_alphas,
+            plonk_deltas,
+            plonk_zeta,
+            fri_challenges,
         }
     }
 }
@@ -333,6 +281,51 @@ impl<const D: usize> ProofWithPublicInputsTarget<D> {
         inner_circuit_digest: HashOutTarget,
         inner_common_data: &CommonCircuitData<F, D>,
     ) -> ProofChallengesTarget<D>
+    where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        self.get_challenges_inner::<F, C>(
+            builder,
+            public_inputs_hash,
+            inner_circuit_digest,
+            inner_common_data,
+            None,
+        )
+    }
+
+    /// Same as `get_challenges`, except that the final FRI polynomial is padded to the largest of
+    /// `final_poly_lens`, and its actual length is the one whose flag is set.
+    pub(crate) fn get_challenges_with_final_poly_lens<
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    >(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        public_inputs_hash: HashOutTarget,
+        inner_circuit_digest: HashOutTarget,
+        inner_common_data: &CommonCircuitData<F, D>,
+        final_poly_lens: &[(BoolTarget, usize)],
+    ) -> ProofChallengesTarget<D>
+    where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        self.get_challenges_inner::<F, C>(
+            builder,
+            public_inputs_hash,
+            inner_circuit_digest,
+            inner_common_data,
+            Some(final_poly_lens),
+        )
+    }
+
+    fn get_challenges_inner<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        public_inputs_hash: HashOutTarget,
+        inner_circuit_digest: HashOutTarget,
+        inner_common_data: &CommonCircuitData<F, D>,
+        final_poly_lens: Option<&[(BoolTarget, usize)]>,
+    ) -> ProofChallengesTarget<D>
     where
         C::Hasher: AlgebraicHasher<F>,
     {
@@ -361,6 +354,7 @@ impl<const D: usize> ProofWithPublicInputsTarget<D> {
             *pow_witness,
             inner_circuit_digest,
             inner_common_data,
+            final_poly_lens,
         )
     }
 }
@@ -5,6 +5,7 @@
 
 pub mod circuit_builder;
 pub mod circuit_data;
+pub mod circuit_description;
 pub mod config;
 pub(crate) mod copy_constraint;
 mod get_challenges;
@@ -12,7 +13,12 @@ pub(crate) mod permutation_argument;
 pub mod plonk_common;
 pub mod proof;
 pub mod prover;
+#[cfg(feature = "std")]
+pub mod prover_service;
+pub mod public_inputs;
+pub mod solidity;
 mod validate_shape;
 pub(crate) mod vanishing_poly;
 pub mod vars;
 pub mod verifier;
+mod witness_check;
@@ -4,6 +4,7 @@
 use alloc::{vec, vec::Vec};
 
 use crate::field::extension::Extendable;
+use crate::field::interpolation::eval_lagrange_basis_on_coset;
 use crate::field::packed::PackedField;
 use crate::field::types::Field;
 use crate::fri::oracle::SALT_SIZE;
@@ -12,6 +13,7 @@ use crate::hash::hash_types::RichField;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
 use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::util::log2_strict;
 use crate::util::reducing::ReducingFactorTarget;
 
 /// Holds the Merkle tree index and blinding flag of a set of polynomials used in FRI.
@@ -38,6 +40,9 @@ impl PlonkOracle {
         index: 3,
         blinding: true,
     };
+
+    /// The number of Plonk oracles; lookup polynomials share the `ZS_PARTIAL_PRODUCTS` oracle.
+    pub(crate) const COUNT: usize = Self::QUOTIENT.index + 1;
 }
 
 pub const fn salt_size(salted: bool) -> usize {
@@ -48,24 +53,10 @@ pub const fn salt_size(salted: bool) -> usize {
     }
 }
 
-/// Evaluate the polynomial which vanishes on any multiplicative subgroup of a given order `n`.
-pub(crate) fn eval_zero_poly<F: Field>(n: usize, x: F) -> F {
-    // Z(x) = x^n - 1
-    x.exp_u64(n as u64) - F::ONE
-}
-
 /// Evaluate the Lagrange basis `L_0` with `L_0(1) = 1`, and `L_0(x) = 0` for other members of the
 /// order `n` multiplicative subgroup.
 pub(crate) fn eval_l_0<F: Field>(n: usize, x: F) -> F {
-    if x.is_one() {
-        // The code below would divide by zero, since we have (x - 1) in both the numerator and
-        // denominator.
-        return F::ONE;
-    }
-
-    // L_0(x) = (x^n - 1) / (n * (x - 1))
-    //        = Z(x) / (n * (x - 1))
-    eval_zero_poly(n, x) / (F::from_canonical_usize(n) * (x - F::ONE))
+    eval_lagrange_basis_on_coset(log2_strict(n), F::ONE, 0, x)
 }
 
 /// Evaluates the Lagrange basis L_0(x), which has L_0(1) = 1 and vanishes at all other points in
@@ -12,6 +12,7 @@ use plonky2_maybe_rayon::*;
 use serde::{Deserialize, Serialize};
 
 use crate::field::extension::Extendable;
+use crate::field::interpolation::{barycentric_eval_batch, barycentric_weights_on_coset};
 use crate::fri::oracle::PolynomialBatch;
 use crate::fri::proof::{
     CompressedFriProof, FriChallenges, FriChallengesTarget, FriProof, FriProofTarget,
@@ -26,9 +27,13 @@ use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::Target;
 use crate::plonk::circuit_data::{CommonCircuitData, VerifierOnlyCircuitData};
 use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::public_inputs::{PublicInputNames, PublicInputs};
 use crate::plonk::verifier::verify_with_challenges;
 use crate::util::serialization::{Buffer, Read, Write};
 
+/// The number of points per task when evaluating committed polynomials in `OpeningSet::new`.
+const BARYCENTRIC_CHUNK_SIZE: usize = 1 << 10;
+
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
 #[serde(bound = "")]
 pub struct Proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
@@ -107,6 +112,12 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         C::InnerHasher::hash_no_pad(&self.public_inputs)
     }
 
+    /// The public inputs, read by the names given to them in the circuit, which can be found in
+    /// `ProverOnlyCircuitData::public_input_names`.
+    pub fn named_public_inputs<'a>(&'a self, names: &'a PublicInputNames) -> PublicInputs<'a, F> {
+        PublicInputs::new(&self.public_inputs, names)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buffer = Vec::new();
         buffer
@@ -152,7 +163,7 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
         challenges: &ProofChallenges<F, D>,
         fri_inferred_elements: FriInferredElements<F, D>,
         params: &FriParams,
-    ) -> Proof<F, C, D> {
+    ) -> anyhow::Result<Proof<F, C, D>> {
         let CompressedProof {
             wires_cap,
             plonk_zs_partial_products_cap,
@@ -161,13 +172,17 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             opening_proof,
         } = self;
 
-        Proof {
+        Ok(Proof {
             wires_cap,
             plonk_zs_partial_products_cap,
             quotient_polys_cap,
             openings,
-            opening_proof: opening_proof.decompress(challenges, fri_inferred_elements, params),
-        }
+            opening_proof: opening_proof.decompress(
+                &challenges.fri_challenges,
+                fri_inferred_elements,
+                params,
+            )?,
+        })
     }
 }
 
@@ -192,10 +207,10 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
     ) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
         let challenges =
             self.get_challenges(self.get_public_inputs_hash(), circuit_digest, common_data)?;
-        let fri_inferred_elements = self.get_inferred_elements(&challenges, common_data);
+        let fri_inferred_elements = self.get_inferred_elements(&challenges, common_data)?;
         let decompressed_proof =
             self.proof
-                .decompress(&challenges, fri_inferred_elements, &common_data.fri_params);
+                .decompress(&challenges, fri_inferred_elements, &common_data.fri_params)?;
         Ok(ProofWithPublicInputs {
             public_inputs: self.public_inputs,
             proof: decompressed_proof,
@@ -217,10 +232,10 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
             &verifier_data.circuit_digest,
             common_data,
         )?;
-        let fri_inferred_elements = self.get_inferred_elements(&challenges, common_data);
+        let fri_inferred_elements = self.get_inferred_elements(&challenges, common_data)?;
         let decompressed_proof =
             self.proof
-                .decompress(&challenges, fri_inferred_elements, &common_data.fri_params);
+                .decompress(&challenges, fri_inferred_elements, &common_data.fri_params)?;
         verify_with_challenges::<F, C, D>(
             decompressed_proof,
             public_inputs_hash,
@@ -320,25 +335,51 @@ impl<F: RichField + Extendable<D>, const D: usize> OpeningSet<F, D> {
         quotient_polys_commitment: &PolynomialBatch<F, C, D>,
         common_data: &CommonCircuitData<F, D>,
     ) -> Self {
-        let eval_commitment = |z: F::Extension, c: &PolynomialBatch<F, C, D>| {
-            c.polynomials
-                .par_iter()
-                .map(|p| p.to_extension().eval(z))
+        // Each polynomial is evaluated from its values on the coset `F::coset_shift() * H`, which
+        // are every `2^rate_bits`th value of its LDE, rather than from its coefficients.
+        let degree_bits = common_data.degree_bits();
+        let shift = F::coset_shift().into();
+        let zeta_weights = barycentric_weights_on_coset(degree_bits, shift, zeta);
+        let zeta_next_weights = barycentric_weights_on_coset(degree_bits, shift, g * zeta);
+        let eval_commitment = |weights: &[F::Extension], c: &PolynomialBatch<F, C, D>| {
+            debug_assert_eq!(c.degree_log, degree_bits);
+            let step = 1 << c.rate_bits;
+            let num_polys = c.polynomials.len();
+            weights
+                .par_chunks(BARYCENTRIC_CHUNK_SIZE)
+                .enumerate()
+                .map(|(j, weights)| {
+                    let rows = (j * BARYCENTRIC_CHUNK_SIZE..).map(|i| c.get_lde_values(i, step));
+                    barycentric_eval_batch::<F, D>(weights, rows, num_polys)
+                })
                 .collect::<Vec<_>>()
+                .into_iter()
+                .reduce(|mut evals, chunk_evals| {
+                    evals.iter_mut().zip(chunk_evals).for_each(|(e, c)| *e += c);
+                    evals
+                })
+                .expect("Empty domain")
         };
-        let constants_sigmas_eval = eval_commitment(zeta, constants_sigmas_commitment);
-
+        // The commitments are evaluated concurrently, as each one may be too small to keep all
+        // threads busy on its own.
         // `zs_partial_products_lookup_eval` contains the permutation argument polynomials as well as lookup polynomials.
-        let zs_partial_products_lookup_eval =
-            eval_commitment(zeta, zs_partial_products_lookup_commitment);
-        let zs_partial_products_lookup_next_eval =
-            eval_commitment(g * zeta, zs_partial_products_lookup_commitment);
-        let quotient_polys = eval_commitment(zeta, quotient_polys_commitment);
+        let evals = [
+            (&zeta_weights, constants_sigmas_commitment),
+            (&zeta_weights, wires_commitment),
+            (&zeta_weights, zs_partial_products_lookup_commitment),
+            (&zeta_next_weights, zs_partial_products_lookup_commitment),
+            (&zeta_weights, quotient_polys_commitment),
+        ]
+        .par_iter()
+        .map(|&(weights, c)| eval_commitment(weights, c))
+        .collect::<Vec<_>>();
+        let [constants_sigmas_eval, wires, zs_partial_products_lookup_eval, zs_partial_products_lookup_next_eval, quotient_polys] =
+            evals.try_into().unwrap();
 
         Self {
             constants: constants_sigmas_eval[common_data.constants_range()].to_vec(),
             plonk_sigmas: constants_sigmas_eval[common_data.sigmas_range()].to_vec(),
-            wires: eval_commitment(zeta, wires_commitment),
+            wires,
             plonk_zs: zs_partial_products_lookup_eval[common_data.zs_range()].to_vec(),
             plonk_zs_next: zs_partial_products_lookup_next_eval[common_data.zs_range()].to_vec(),
             partial_products: zs_partial_products_lookup_eval[common_data.partial_products_range()]
@@ -453,23 +494,27 @@ impl<const D: usize> OpeningSetTarget<D> {
 #[cfg(test)]
 mod tests {
     #[cfg(not(feature = "std"))]
-    use alloc::sync::Arc;
+    use alloc::{format, sync::Arc};
     #[cfg(feature = "std")]
     use std::sync::Arc;
 
     use anyhow::Result;
     use itertools::Itertools;
-    use plonky2_field::types::Sample;
+    use plonky2_field::types::{Field, Field64, Sample};
 
     use super::*;
+    use crate::error::Error;
     use crate::fri::reduction_strategies::FriReductionStrategy;
     use crate::gates::lookup_table::LookupTable;
     use crate::gates::noop::NoopGate;
-    use crate::iop::witness::PartialWitness;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_builder::CircuitBuilder;
     use crate::plonk::circuit_data::CircuitConfig;
     use crate::plonk::config::PoseidonGoldilocksConfig;
+    use crate::plonk::plonk_common::{salt_size, PlonkOracle};
+    use crate::plonk::prover::{IncrementalProverCache, ProverCheckpoint, ProverStage};
     use crate::plonk::verifier::verify;
+    use crate::util::timing::TimingTree;
 
     #[test]
     fn test_proof_compression() -> Result<()> {
@@ -509,6 +554,232 @@ mod tests {
         data.verify_compressed(compressed_proof)
     }
 
+    #[test]
+    fn test_proof_serde() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::NEG_ONE)?;
+        let proof = data.prove(pw)?;
+        let compressed_proof = data.compress(proof.clone())?;
+
+        let json = serde_json::to_string(&proof)?;
+        assert!(json.contains(&F::NEG_ONE.to_string()));
+        let proof_from_json: ProofWithPublicInputs<F, C, D> = serde_json::from_str(&json)?;
+        assert_eq!(proof_from_json, proof);
+
+        let proof_from_cbor: ProofWithPublicInputs<F, C, D> =
+            serde_cbor::from_slice(&serde_cbor::to_vec(&proof)?)?;
+        assert_eq!(proof_from_cbor, proof);
+
+        let compressed_proof_from_json: CompressedProofWithPublicInputs<F, C, D> =
+            serde_json::from_str(&serde_json::to_string(&compressed_proof)?)?;
+        assert_eq!(compressed_proof_from_json, compressed_proof);
+
+        let verifier_only_from_json: VerifierOnlyCircuitData<C, D> =
+            serde_json::from_str(&serde_json::to_string(&data.verifier_only)?)?;
+        assert_eq!(verifier_only_from_json, data.verifier_only);
+
+        // Decimal strings are accepted too, but non-canonical values aren't.
+        assert_eq!(
+            serde_json::from_str::<F>("\"7\"")?,
+            F::from_canonical_u64(7)
+        );
+        assert!(serde_json::from_str::<F>(&F::ORDER.to_string()).is_err());
+        assert!(serde_json::from_str::<F>(&format!("\"{}\"", F::ORDER)).is_err());
+
+        verify(proof_from_json, &verifier_only_from_json, &data.common)
+    }
+
+    #[test]
+    fn test_verify_batch() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        for _ in 0..100 {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        let data = builder.build::<C>();
+
+        let mut proofs = (0..4)
+            .map(|_| {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::rand())?;
+                data.prove(pw)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        data.verify_batch(&proofs)?;
+
+        // Tampering with any single proof invalidates the batch.
+        proofs[2].public_inputs[1] = F::rand();
+        assert!(data.verify_batch(&proofs).is_err());
+
+        proofs[2].public_inputs.pop();
+        let err = data.verify(proofs[2].clone()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::PublicInputCount {
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prove_incremental() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let xs = builder.add_virtual_targets(4);
+        for &x in &xs {
+            let y = builder.cube(x);
+            builder.register_public_input(y);
+        }
+        for _ in 0..100 {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        let data = builder.build::<C>();
+
+        let mut cache = IncrementalProverCache::new();
+        let mut inputs = F::rand_vec(xs.len());
+        for i in 0..3 {
+            // Change a single input between consecutive proofs.
+            inputs[i] = F::rand();
+            let mut pw = PartialWitness::new();
+            pw.set_target_arr(&xs, &inputs)?;
+            let proof = data.prove_incremental(pw, &mut cache)?;
+            assert_eq!(
+                proof.public_inputs,
+                inputs.iter().map(|&x| x.cube()).collect::<Vec<_>>()
+            );
+            data.verify(proof)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prove_in_stages() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let table: LookupTable = Arc::new((0..16).map(|i| (i, i * i)).collect());
+        let lut_index = builder.add_lookup_table_from_pairs(table);
+        let x = builder.add_virtual_public_input();
+        let y = builder.add_lookup_from_index(x, lut_index);
+        builder.register_public_input(y);
+        for _ in 0..100 {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        let data = builder.build::<C>();
+        let (prover_data, common_data) = (&data.prover_only, &data.common);
+        let timing = &mut TimingTree::default();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u16(7))?;
+        let mut checkpoint =
+            ProverCheckpoint::generate_witness(prover_data, common_data, pw, timing)?;
+        assert!(checkpoint
+            .prove_openings(prover_data, common_data, timing)
+            .is_err());
+        while checkpoint.stage() != ProverStage::Opened {
+            // Resume each stage from a serialized checkpoint.
+            let bytes = checkpoint.to_bytes();
+            // A checkpoint claiming a later stage than its contents is rejected.
+            let mut tampered = bytes.clone();
+            tampered[0] += 1;
+            assert!(ProverCheckpoint::<F, C, D>::from_bytes(tampered, common_data).is_err());
+            checkpoint = ProverCheckpoint::from_bytes(bytes, common_data)?;
+            checkpoint.advance(prover_data, common_data, timing)?;
+        }
+        assert!(checkpoint
+            .advance(prover_data, common_data, timing)
+            .is_err());
+        // The witness isn't needed after the partial products are committed to.
+        assert!(checkpoint.witness.wire_values.is_empty());
+
+        let proof = checkpoint.prove_openings(prover_data, common_data, timing)?;
+        assert_eq!(proof.public_inputs[1], F::from_canonical_u16(49));
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_zero_knowledge_proofs_are_randomized() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let prove_twice = |config: CircuitConfig| -> Result<_> {
+            let mut builder = CircuitBuilder::<F, D>::new(config);
+            let x = builder.add_virtual_target();
+            let y = builder.square(x);
+            builder.register_public_input(y);
+            let data = builder.build::<C>();
+
+            let mut proofs = Vec::new();
+            for _ in 0..2 {
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_u64(7))?;
+                let proof = data.prove(pw)?;
+                data.verify(proof.clone())?;
+                proofs.push(proof.proof);
+            }
+            Ok((proofs, data.common))
+        };
+
+        let wires_leaf_len = |proof: &Proof<F, C, D>| {
+            proof.opening_proof.query_round_proofs[0]
+                .initial_trees_proof
+                .evals_proofs[PlonkOracle::WIRES.index]
+                .0
+                .len()
+        };
+
+        // Without zero knowledge, Merkle leaves hold only the polynomial evaluations.
+        let (proofs, common_data) = prove_twice(CircuitConfig::standard_recursion_config())?;
+        assert!(!common_data.fri_params.hiding);
+        assert_eq!(wires_leaf_len(&proofs[0]), common_data.config.num_wires);
+
+        // With zero knowledge, witness polynomials are blinded and Merkle leaves are salted, so
+        // that proving the same witness twice yields unrelated commitments.
+        let (proofs, common_data) = prove_twice(CircuitConfig::zk_config())?;
+        assert!(common_data.fri_params.hiding);
+        assert_eq!(
+            wires_leaf_len(&proofs[0]),
+            common_data.config.num_wires + salt_size(true)
+        );
+        assert_ne!(proofs[0].wires_cap, proofs[1].wires_cap);
+        assert_ne!(
+            proofs[0].plonk_zs_partial_products_cap,
+            proofs[1].plonk_zs_partial_products_cap
+        );
+        assert_ne!(proofs[0].quotient_polys_cap, proofs[1].quotient_polys_cap);
+
+        Ok(())
+    }
+
     #[test]
     fn test_proof_compression_lookup() -> Result<()> {
         const D: usize = 2;
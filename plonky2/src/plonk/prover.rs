@@ -3,13 +3,16 @@
 #[cfg(not(feature = "std"))]
 use alloc::{format, vec, vec::Vec};
 use core::cmp::min;
-use core::mem::swap;
 
-use anyhow::{ensure, Result};
+use anyhow::{anyhow, ensure, Result};
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
 use hashbrown::HashMap;
 use plonky2_maybe_rayon::*;
 
 use super::circuit_builder::{LookupChallenges, LookupWire};
+use crate::backend::{CpuBackend, ProverBackend, QuotientInputs};
+use crate::error::Error;
 use crate::field::extension::Extendable;
 use crate::field::polynomial::{PolynomialCoeffs, PolynomialValues};
 use crate::field::types::Field;
@@ -20,7 +23,10 @@ use crate::gates::lookup_table::LookupTableGate;
 use crate::gates::selectors::LookupSelectors;
 use crate::hash::hash_types::RichField;
 use crate::iop::challenger::Challenger;
-use crate::iop::generator::generate_partial_witness;
+use crate::iop::generator::{
+    generate_partial_witness_with_template, generate_partial_witness_with_timing, witness_template,
+    WitnessTemplate,
+};
 use crate::iop::target::Target;
 use crate::iop::witness::{MatrixWitness, PartialWitness, PartitionWitness, Witness, WitnessWrite};
 use crate::plonk::circuit_builder::NUM_COINS_LOOKUP;
@@ -32,6 +38,7 @@ use crate::plonk::vanishing_poly::{eval_vanishing_poly_base_batch, get_lut_poly}
 use crate::plonk::vars::EvaluationVarsBaseBatch;
 use crate::timed;
 use crate::util::partial_products::{partial_products_and_z_gx, quotient_chunk_products};
+use crate::util::serialization::{Buffer, Read, Write};
 use crate::util::timing::TimingTree;
 use crate::util::{log2_ceil, transpose};
 
@@ -70,10 +77,11 @@ pub fn set_lookup_wires<
             .collect();
 
         for (inp_target, _) in prover_data.lut_to_lookups[lut_index].iter() {
-            let inp_value = pw.get_target(*inp_target);
-            let idx = table_value_to_idx
-                .get(&u16::try_from(inp_value.to_canonical_u64()).unwrap())
-                .unwrap();
+            let inp_value = pw.get_target_checked(*inp_target)?.to_canonical_u64();
+            let idx = u16::try_from(inp_value)
+                .ok()
+                .and_then(|inp| table_value_to_idx.get(&inp))
+                .ok_or(Error::LookupInputNotInTable { input: inp_value })?;
 
             multiplicities[*idx] += 1;
         }
@@ -124,237 +132,865 @@ where
     let partition_witness = timed!(
         timing,
         &format!("run {} generators", prover_data.generators.len()),
-        generate_partial_witness(inputs, prover_data, common_data)?
+        generate_partial_witness_with_timing(inputs, prover_data, common_data, timing)?
     );
 
     prove_with_partition_witness(prover_data, common_data, partition_witness, timing)
 }
 
-pub fn prove_with_partition_witness<
+/// Like `prove`, but runs on `pool` instead of rayon's global thread pool, which can be configured
+/// with `ThreadPoolBuilder::build_global`. A pool built with `ThreadPoolBuilder::num_threads` caps
+/// the number of threads used by a proof, e.g. so that concurrent proofs on a server don't each
+/// compete for every core.
+#[cfg(feature = "parallel")]
+pub fn prove_in_thread_pool<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
 >(
+    pool: &rayon::ThreadPool,
     prover_data: &ProverOnlyCircuitData<F, C, D>,
     common_data: &CommonCircuitData<F, D>,
-    mut partition_witness: PartitionWitness<F>,
+    inputs: PartialWitness<F>,
     timing: &mut TimingTree,
 ) -> Result<ProofWithPublicInputs<F, C, D>>
 where
     C::Hasher: Hasher<F>,
     C::InnerHasher: Hasher<F>,
 {
-    let has_lookup = !common_data.luts.is_empty();
-    let config = &common_data.config;
-    let num_challenges = config.num_challenges;
-    let quotient_degree = common_data.quotient_degree();
-    let degree = common_data.degree();
+    pool.install(|| prove(prover_data, common_data, inputs, timing))
+}
 
-    set_lookup_wires(prover_data, common_data, &mut partition_witness)?;
+/// Like `prove`, but starts witness generation from a `template` of the circuit's input-independent
+/// witness values. See `witness_template`.
+pub fn prove_with_template<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    template: &WitnessTemplate<F>,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    let partition_witness = timed!(
+        timing,
+        "run generators from template",
+        generate_partial_witness_with_template(inputs, template, prover_data, common_data)?
+    );
 
-    let public_inputs = partition_witness.get_targets(&prover_data.public_inputs);
-    let public_inputs_hash = C::InnerHasher::hash_no_pad(&public_inputs);
+    prove_with_partition_witness(prover_data, common_data, partition_witness, timing)
+}
 
-    let witness = timed!(
+/// Proves each of `inputs`, instances of the same circuit, with at most `max_concurrent_proofs`
+/// proofs in flight at a time. Besides the FFT root table and the constants and sigmas
+/// commitment, which every proof of a circuit shares, the input-independent part of the witness is
+/// generated once for the whole batch. See `witness_template`.
+///
+/// Concurrent proofs share rayon's thread pool, which suits batches of small proofs that can't
+/// keep every thread busy on their own. Since each proof in flight holds its own polynomials and
+/// Merkle trees, `max_concurrent_proofs` also bounds the memory used by the batch.
+pub fn prove_batch<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: Vec<PartialWitness<F>>,
+    max_concurrent_proofs: usize,
+    timing: &mut TimingTree,
+) -> Result<Vec<ProofWithPublicInputs<F, C, D>>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    ensure!(
+        max_concurrent_proofs > 0,
+        "At least one proof must be computed at a time."
+    );
+    let template = timed!(
         timing,
-        "compute full witness",
-        partition_witness.full_witness()
+        "generate witness template",
+        witness_template(prover_data, common_data)?
     );
 
-    let wires_values: Vec<PolynomialValues<F>> = timed!(
+    let mut proofs = Vec::with_capacity(inputs.len());
+    let mut inputs = inputs.into_iter().peekable();
+    while inputs.peek().is_some() {
+        let chunk = inputs
+            .by_ref()
+            .take(max_concurrent_proofs)
+            .collect::<Vec<_>>();
+        let chunk_proofs: Result<Vec<_>> = timed!(
+            timing,
+            &format!("prove {} instances", chunk.len()),
+            chunk
+                .into_par_iter()
+                .map(|inputs| {
+                    prove_with_template(
+                        prover_data,
+                        common_data,
+                        inputs,
+                        &template,
+                        &mut TimingTree::default(),
+                    )
+                })
+                .collect()
+        );
+        proofs.extend(chunk_proofs?);
+    }
+    Ok(proofs)
+}
+
+/// Like `prove`, but commits to the prover's polynomials and folds the FRI codewords on `backend`,
+/// which may e.g. offload the FFTs and Merkle tree hashing to an accelerator.
+pub fn prove_with_backend<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    B: ProverBackend<F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    backend: &B,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    let partition_witness = timed!(
         timing,
-        "compute wire polynomials",
-        witness
-            .wire_values
-            .par_iter()
-            .map(|column| PolynomialValues::new(column.clone()))
-            .collect()
+        &format!("run {} generators", prover_data.generators.len()),
+        generate_partial_witness_with_timing(inputs, prover_data, common_data, timing)?
     );
 
-    let wires_commitment = timed!(
+    prove_with_partition_witness_and_cache(
+        prover_data,
+        common_data,
+        partition_witness,
+        None,
+        backend,
         timing,
-        "compute wires commitment",
-        PolynomialBatch::<F, C, D>::from_values(
-            wires_values,
-            config.fri_config.rate_bits,
-            config.zero_knowledge && PlonkOracle::WIRES.blinding,
-            config.fri_config.cap_height,
-            timing,
-            prover_data.fft_root_table.as_ref(),
-        )
-    );
+    )
+}
 
-    let mut challenger = Challenger::<F, C::Hasher>::new();
+/// State kept between calls to `prove_incremental`, so that re-proving a circuit after a partial
+/// change of its witness skips the work for wire polynomials which did not change.
+#[derive(Debug)]
+pub struct IncrementalProverCache<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    /// Digest of the circuit the cached values belong to.
+    circuit_digest: Option<<<C as GenericConfig<D>>::Hasher as Hasher<F>>::Hash>,
+    wires_values: Vec<PolynomialValues<F>>,
+    wires_commitment: PolynomialBatch<F, C, D>,
+}
 
-    // Observe the instance.
-    challenger.observe_hash::<C::Hasher>(prover_data.circuit_digest);
-    challenger.observe_hash::<C::InnerHasher>(public_inputs_hash);
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Default
+    for IncrementalProverCache<F, C, D>
+{
+    fn default() -> Self {
+        Self {
+            circuit_digest: None,
+            wires_values: Vec::new(),
+            wires_commitment: PolynomialBatch::default(),
+        }
+    }
+}
 
-    challenger.observe_cap::<C::Hasher>(&wires_commitment.merkle_tree.cap);
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    IncrementalProverCache<F, C, D>
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    // We need 4 values per challenge: 2 for the combos, 1 for (X-combo) in the accumulators and 1 to prove that the lookup table was computed correctly.
-    // We can reuse betas and gammas for two of them.
-    let num_lookup_challenges = NUM_COINS_LOOKUP * num_challenges;
+    /// Forgets the cached values, so that the next proof is computed from scratch.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
 
-    let betas = challenger.get_n_challenges(num_challenges);
-    let gammas = challenger.get_n_challenges(num_challenges);
+/// Like `prove`, but reuses the wires commitment of the previous proof recorded in `cache` for
+/// the wire polynomials which are unchanged or only changed in a few rows. `cache` is then
+/// updated with this proof's wires. Note that when zero-knowledge is enabled the wires are
+/// salted with fresh randomness, so nothing can be reused.
+pub fn prove_incremental<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: PartialWitness<F>,
+    cache: &mut IncrementalProverCache<F, C, D>,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    let partition_witness = timed!(
+        timing,
+        &format!("run {} generators", prover_data.generators.len()),
+        generate_partial_witness_with_timing(inputs, prover_data, common_data, timing)?
+    );
 
-    let deltas = if has_lookup {
-        let mut delts = Vec::with_capacity(2 * num_challenges);
-        let num_additional_challenges = num_lookup_challenges - 2 * num_challenges;
-        let additional = challenger.get_n_challenges(num_additional_challenges);
-        delts.extend(&betas);
-        delts.extend(&gammas);
-        delts.extend(additional);
-        delts
-    } else {
-        vec![]
-    };
+    prove_with_partition_witness_and_cache(
+        prover_data,
+        common_data,
+        partition_witness,
+        Some(cache),
+        &CpuBackend,
+        timing,
+    )
+}
 
-    assert!(
-        common_data.quotient_degree_factor < common_data.config.num_routed_wires,
-        "When the number of routed wires is smaller that the degree, we should change the logic to avoid computing partial products."
-    );
-    let mut partial_products_and_zs = timed!(
+pub fn prove_with_partition_witness<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    partition_witness: PartitionWitness<F>,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    prove_with_partition_witness_and_cache(
+        prover_data,
+        common_data,
+        partition_witness,
+        None,
+        &CpuBackend,
         timing,
-        "compute partial products",
-        all_wires_permutation_partial_products(&witness, &betas, &gammas, prover_data, common_data)
-    );
+    )
+}
 
-    // Z is expected at the front of our batch; see `zs_range` and `partial_products_range`.
-    let plonk_z_vecs = partial_products_and_zs
-        .iter_mut()
-        .map(|partial_products_and_z| partial_products_and_z.pop().unwrap())
-        .collect();
-    let zs_partial_products = [plonk_z_vecs, partial_products_and_zs.concat()].concat();
+fn prove_with_partition_witness_and_cache<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    B: ProverBackend<F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    partition_witness: PartitionWitness<F>,
+    cache: Option<&mut IncrementalProverCache<F, C, D>>,
+    backend: &B,
+    timing: &mut TimingTree,
+) -> Result<ProofWithPublicInputs<F, C, D>>
+where
+    C::Hasher: Hasher<F>,
+    C::InnerHasher: Hasher<F>,
+{
+    let mut checkpoint = ProverCheckpoint::from_partition_witness(
+        prover_data,
+        common_data,
+        partition_witness,
+        timing,
+    )?;
+    let cached_wires_values = cache
+        .is_some()
+        .then(|| checkpoint.wires_values().collect::<Vec<_>>());
+    checkpoint.commit_wires_with_cache(
+        prover_data,
+        common_data,
+        cache.as_deref(),
+        backend,
+        timing,
+    )?;
+    checkpoint.commit_partial_products_and_zs_with_backend(
+        prover_data,
+        common_data,
+        backend,
+        timing,
+    )?;
+    checkpoint.commit_quotient_polys_with_backend(prover_data, common_data, backend, timing)?;
+    checkpoint.compute_openings(prover_data, common_data, timing)?;
+    let proof_with_pis =
+        checkpoint.prove_openings_with_backend(prover_data, common_data, backend, timing)?;
+
+    if let (Some(cache), Some(wires_values)) = (cache, cached_wires_values) {
+        *cache = IncrementalProverCache {
+            circuit_digest: Some(prover_data.circuit_digest),
+            wires_values,
+            wires_commitment: checkpoint
+                .wires_commitment
+                .expect("The wires are committed to by now"),
+        };
+    }
+    Ok(proof_with_pis)
+}
 
-    // All lookup polys: RE and partial SLDCs.
-    let lookup_polys =
-        compute_all_lookup_polys(&witness, &deltas, prover_data, common_data, has_lookup);
+/// The stages of the prover, in order. Each stage is followed by a commitment or opening which
+/// is observed by the Fiat-Shamir challenger before the next stage starts.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ProverStage {
+    /// The witness is fully generated.
+    WitnessGenerated,
+    /// The wire polynomials are committed to.
+    WiresCommitted,
+    /// The permutation argument's `Z` and partial product polynomials, and the lookup
+    /// polynomials if any, are committed to.
+    PartialProductsCommitted,
+    /// The quotient polynomials are committed to.
+    QuotientCommitted,
+    /// All polynomials are opened at `zeta` and `g * zeta`. Only the FRI opening proof is left.
+    Opened,
+}
 
-    let zs_partial_products_lookups = if has_lookup {
-        [zs_partial_products, lookup_polys].concat()
-    } else {
-        zs_partial_products
-    };
+/// The intermediate state of the prover between two stages.
+///
+/// A proof can be generated stage by stage, with the checkpoint serialized with `to_bytes` in
+/// between, so that stages can run on different machines or resume after an interruption. The
+/// Fiat-Shamir transcript only depends on the commitments and openings stored here, so it is
+/// replayed at each stage rather than stored.
+///
+/// The witness is only kept until the partial products are committed to, as the later stages
+/// don't need it.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ProverCheckpoint<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+> {
+    pub(crate) public_inputs: Vec<F>,
+    pub(crate) witness: MatrixWitness<F>,
+    pub(crate) wires_commitment: Option<PolynomialBatch<F, C, D>>,
+    pub(crate) partial_products_zs_and_lookup_commitment: Option<PolynomialBatch<F, C, D>>,
+    pub(crate) quotient_polys_commitment: Option<PolynomialBatch<F, C, D>>,
+    pub(crate) openings: Option<OpeningSet<F, D>>,
+}
 
-    let partial_products_zs_and_lookup_commitment = timed!(
-        timing,
-        "commit to partial products, Z's and, if any, lookup polynomials",
-        PolynomialBatch::from_values(
-            zs_partial_products_lookups,
-            config.fri_config.rate_bits,
-            config.zero_knowledge && PlonkOracle::ZS_PARTIAL_PRODUCTS.blinding,
-            config.fri_config.cap_height,
+/// The Fiat-Shamir challenges drawn so far, along with the challenger state after drawing them.
+struct StageChallenges<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> {
+    challenger: Challenger<F, H>,
+    betas: Vec<F>,
+    gammas: Vec<F>,
+    deltas: Vec<F>,
+    alphas: Vec<F>,
+    zeta: Option<F::Extension>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProverCheckpoint<F, C, D>
+{
+    /// Runs the witness generators on `inputs`, the first stage of the prover.
+    pub fn generate_witness(
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        inputs: PartialWitness<F>,
+        timing: &mut TimingTree,
+    ) -> Result<Self> {
+        let partition_witness = timed!(
             timing,
-            prover_data.fft_root_table.as_ref(),
-        )
-    );
+            &format!("run {} generators", prover_data.generators.len()),
+            generate_partial_witness_with_timing(inputs, prover_data, common_data, timing)?
+        );
 
-    challenger.observe_cap::<C::Hasher>(&partial_products_zs_and_lookup_commitment.merkle_tree.cap);
+        Self::from_partition_witness(prover_data, common_data, partition_witness, timing)
+    }
 
-    let alphas = challenger.get_n_challenges(num_challenges);
+    /// Completes the first stage of the prover from an already generated `partition_witness`.
+    pub fn from_partition_witness(
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        mut partition_witness: PartitionWitness<F>,
+        timing: &mut TimingTree,
+    ) -> Result<Self> {
+        timed!(
+            timing,
+            "set lookup wires",
+            set_lookup_wires(prover_data, common_data, &mut partition_witness)?
+        );
 
-    let quotient_polys = timed!(
-        timing,
-        "compute quotient polys",
-        compute_quotient_polys::<F, C, D>(
-            common_data,
+        let public_inputs = partition_witness.get_targets_checked(&prover_data.public_inputs)?;
+        let witness = timed!(
+            timing,
+            "compute full witness",
+            partition_witness.full_witness()
+        );
+
+        Ok(Self {
+            public_inputs,
+            witness,
+            wires_commitment: None,
+            partial_products_zs_and_lookup_commitment: None,
+            quotient_polys_commitment: None,
+            openings: None,
+        })
+    }
+
+    /// The last completed stage.
+    pub fn stage(&self) -> ProverStage {
+        if self.openings.is_some() {
+            ProverStage::Opened
+        } else if self.quotient_polys_commitment.is_some() {
+            ProverStage::QuotientCommitted
+        } else if self.partial_products_zs_and_lookup_commitment.is_some() {
+            ProverStage::PartialProductsCommitted
+        } else if self.wires_commitment.is_some() {
+            ProverStage::WiresCommitted
+        } else {
+            ProverStage::WitnessGenerated
+        }
+    }
+
+    /// Runs the stage following the current one. The FRI opening proof, which completes the proof,
+    /// is computed by `prove_openings` instead.
+    pub fn advance(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        timing: &mut TimingTree,
+    ) -> Result<()> {
+        match self.stage() {
+            ProverStage::WitnessGenerated => self.commit_wires(prover_data, common_data, timing),
+            ProverStage::WiresCommitted => {
+                self.commit_partial_products_and_zs(prover_data, common_data, timing)
+            }
+            ProverStage::PartialProductsCommitted => {
+                self.commit_quotient_polys(prover_data, common_data, timing)
+            }
+            ProverStage::QuotientCommitted => {
+                self.compute_openings(prover_data, common_data, timing)
+            }
+            ProverStage::Opened => Err(anyhow!(
+                "All stages are done; call `prove_openings` to finish the proof."
+            )),
+        }
+    }
+
+    /// Commits to the wire polynomials.
+    pub fn commit_wires(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        timing: &mut TimingTree,
+    ) -> Result<()> {
+        self.commit_wires_with_cache(prover_data, common_data, None, &CpuBackend, timing)
+    }
+
+    fn commit_wires_with_cache<B: ProverBackend<F>>(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        cache: Option<&IncrementalProverCache<F, C, D>>,
+        backend: &B,
+        timing: &mut TimingTree,
+    ) -> Result<()> {
+        self.ensure_stage(ProverStage::WitnessGenerated)?;
+        let config = &common_data.config;
+
+        let wires_values: Vec<PolynomialValues<F>> = timed!(
+            timing,
+            "compute wire polynomials",
+            self.wires_values().collect()
+        );
+
+        let blinding = config.zero_knowledge && PlonkOracle::WIRES.blinding;
+        let wires_commitment = timed!(
+            timing,
+            "compute wires commitment",
+            match cache {
+                Some(cache) if cache.circuit_digest == Some(prover_data.circuit_digest) => {
+                    PolynomialBatch::<F, C, D>::from_values_incremental(
+                        wires_values,
+                        &cache.wires_values,
+                        &cache.wires_commitment,
+                        config.fri_config.rate_bits,
+                        blinding,
+                        config.fri_config.cap_height,
+                        timing,
+                        prover_data.fft_root_table.as_deref(),
+                    )
+                }
+                _ => PolynomialBatch::<F, C, D>::from_values_with_backend(
+                    wires_values,
+                    config.fri_config.rate_bits,
+                    blinding,
+                    config.fri_config.cap_height,
+                    timing,
+                    prover_data.fft_root_table.as_deref(),
+                    backend,
+                ),
+            }
+        );
+        self.wires_commitment = Some(wires_commitment);
+        Ok(())
+    }
+
+    /// Computes and commits to the permutation argument's `Z` and partial product polynomials,
+    /// and the lookup polynomials if any.
+    pub fn commit_partial_products_and_zs(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        timing: &mut TimingTree,
+    ) -> Result<()> {
+        self.commit_partial_products_and_zs_with_backend(
             prover_data,
-            &public_inputs_hash,
-            &wires_commitment,
-            &partial_products_zs_and_lookup_commitment,
-            &betas,
-            &gammas,
-            &deltas,
-            &alphas,
+            common_data,
+            &CpuBackend,
+            timing,
         )
-    );
+    }
 
-    let all_quotient_poly_chunks: Vec<PolynomialCoeffs<F>> = timed!(
-        timing,
-        "split up quotient polys",
-        quotient_polys
-            .into_par_iter()
-            .flat_map(|mut quotient_poly| {
-                quotient_poly.trim_to_len(quotient_degree).expect(
-                    "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
-                );
-                // Split quotient into degree-n chunks.
-                quotient_poly.chunks(degree)
-            })
-            .collect()
-    );
+    fn commit_partial_products_and_zs_with_backend<B: ProverBackend<F>>(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        backend: &B,
+        timing: &mut TimingTree,
+    ) -> Result<()> {
+        self.ensure_stage(ProverStage::WiresCommitted)?;
+        let has_lookup = !common_data.luts.is_empty();
+        let config = &common_data.config;
+        let StageChallenges {
+            betas,
+            gammas,
+            deltas,
+            ..
+        } = self.challenges(prover_data, common_data);
+
+        assert!(
+            common_data.quotient_degree_factor < common_data.config.num_routed_wires,
+            "When the number of routed wires is smaller that the degree, we should change the logic to avoid computing partial products."
+        );
+        let mut partial_products_and_zs = timed!(
+            timing,
+            "compute partial products",
+            all_wires_permutation_partial_products(
+                &self.witness,
+                &betas,
+                &gammas,
+                prover_data,
+                common_data
+            )
+        );
 
-    let quotient_polys_commitment = timed!(
-        timing,
-        "commit to quotient polys",
-        PolynomialBatch::<F, C, D>::from_coeffs(
-            all_quotient_poly_chunks,
-            config.fri_config.rate_bits,
-            config.zero_knowledge && PlonkOracle::QUOTIENT.blinding,
-            config.fri_config.cap_height,
+        // All lookup polys: RE and partial SLDCs.
+        let lookup_polys = timed!(
             timing,
-            prover_data.fft_root_table.as_ref(),
-        )
-    );
+            "compute lookup polys",
+            compute_all_lookup_polys(&self.witness, &deltas, prover_data, common_data, has_lookup)
+        );
+
+        // The remaining stages only need the commitments, so the witness is released here rather
+        // than at the end of the proof.
+        self.witness.wire_values = Vec::new();
+
+        // Z is expected at the front of our batch; see `zs_range` and `partial_products_range`.
+        // The polynomials are moved rather than copied into the batch.
+        let mut zs_partial_products_lookups = partial_products_and_zs
+            .iter_mut()
+            .map(|partial_products_and_z| partial_products_and_z.pop().unwrap())
+            .collect::<Vec<_>>();
+        zs_partial_products_lookups.extend(partial_products_and_zs.into_iter().flatten());
+        if has_lookup {
+            zs_partial_products_lookups.extend(lookup_polys);
+        }
 
-    challenger.observe_cap::<C::Hasher>(&quotient_polys_commitment.merkle_tree.cap);
+        let partial_products_zs_and_lookup_commitment = timed!(
+            timing,
+            "commit to partial products, Z's and, if any, lookup polynomials",
+            PolynomialBatch::from_values_with_backend(
+                zs_partial_products_lookups,
+                config.fri_config.rate_bits,
+                config.zero_knowledge && PlonkOracle::ZS_PARTIAL_PRODUCTS.blinding,
+                config.fri_config.cap_height,
+                timing,
+                prover_data.fft_root_table.as_deref(),
+                backend,
+            )
+        );
+        self.partial_products_zs_and_lookup_commitment =
+            Some(partial_products_zs_and_lookup_commitment);
+        Ok(())
+    }
 
-    let zeta = challenger.get_extension_challenge::<D>();
-    // To avoid leaking witness data, we want to ensure that our opening locations, `zeta` and
-    // `g * zeta`, are not in our subgroup `H`. It suffices to check `zeta` only, since
-    // `(g * zeta)^n = zeta^n`, where `n` is the order of `g`.
-    let g = F::Extension::primitive_root_of_unity(common_data.degree_bits());
-    ensure!(
-        zeta.exp_power_of_2(common_data.degree_bits()) != F::Extension::ONE,
-        "Opening point is in the subgroup."
-    );
+    /// Computes and commits to the quotient polynomials.
+    pub fn commit_quotient_polys(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        timing: &mut TimingTree,
+    ) -> Result<()> {
+        self.commit_quotient_polys_with_backend(prover_data, common_data, &CpuBackend, timing)
+    }
 
-    let openings = timed!(
-        timing,
-        "construct the opening set, including lookups",
-        OpeningSet::new(
-            zeta,
-            g,
-            &prover_data.constants_sigmas_commitment,
-            &wires_commitment,
-            &partial_products_zs_and_lookup_commitment,
-            &quotient_polys_commitment,
-            common_data
-        )
-    );
-    challenger.observe_openings(&openings.to_fri_openings());
-    let instance = common_data.get_fri_instance(zeta);
+    fn commit_quotient_polys_with_backend<B: ProverBackend<F>>(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        backend: &B,
+        timing: &mut TimingTree,
+    ) -> Result<()> {
+        self.ensure_stage(ProverStage::PartialProductsCommitted)?;
+        let config = &common_data.config;
+        let quotient_degree = common_data.quotient_degree();
+        let degree = common_data.degree();
+        let StageChallenges {
+            betas,
+            gammas,
+            deltas,
+            alphas,
+            ..
+        } = self.challenges(prover_data, common_data);
+        let public_inputs_hash = C::InnerHasher::hash_no_pad(&self.public_inputs);
+
+        let quotient_polys = timed!(
+            timing,
+            "compute quotient polys",
+            backend.quotient_polys(&QuotientInputs {
+                common_data,
+                prover_data,
+                public_inputs_hash: &public_inputs_hash,
+                wires_commitment: self.wires_commitment.as_ref().unwrap(),
+                zs_partial_products_and_lookup_commitment: self
+                    .partial_products_zs_and_lookup_commitment
+                    .as_ref()
+                    .unwrap(),
+                betas: &betas,
+                gammas: &gammas,
+                deltas: &deltas,
+                alphas: &alphas,
+            })
+        );
 
-    let opening_proof = timed!(
-        timing,
-        "compute opening proofs",
-        PolynomialBatch::<F, C, D>::prove_openings(
-            &instance,
-            &[
+        let all_quotient_poly_chunks: Vec<PolynomialCoeffs<F>> = timed!(
+            timing,
+            "split up quotient polys",
+            quotient_polys
+                .into_par_iter()
+                .flat_map(|mut quotient_poly| {
+                    quotient_poly.trim_to_len(quotient_degree).expect(
+                        "Quotient has failed, the vanishing polynomial is not divisible by Z_H",
+                    );
+                    // Split quotient into degree-n chunks.
+                    quotient_poly.chunks(degree)
+                })
+                .collect()
+        );
+
+        let quotient_polys_commitment = timed!(
+            timing,
+            "commit to quotient polys",
+            PolynomialBatch::<F, C, D>::from_coeffs_with_backend(
+                all_quotient_poly_chunks,
+                config.fri_config.rate_bits,
+                config.zero_knowledge && PlonkOracle::QUOTIENT.blinding,
+                config.fri_config.cap_height,
+                timing,
+                prover_data.fft_root_table.as_deref(),
+                backend,
+            )
+        );
+        self.quotient_polys_commitment = Some(quotient_polys_commitment);
+        Ok(())
+    }
+
+    /// Opens all polynomials at the challenge point `zeta` and at `g * zeta`.
+    pub fn compute_openings(
+        &mut self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        timing: &mut TimingTree,
+    ) -> Result<()> {
+        self.ensure_stage(ProverStage::QuotientCommitted)?;
+        let zeta = self
+            .challenges(prover_data, common_data)
+            .zeta
+            .expect("All commitments are done by now");
+
+        // To avoid leaking witness data, we want to ensure that our opening locations, `zeta` and
+        // `g * zeta`, are not in our subgroup `H`. It suffices to check `zeta` only, since
+        // `(g * zeta)^n = zeta^n`, where `n` is the order of `g`.
+        let g = F::Extension::primitive_root_of_unity(common_data.degree_bits());
+        ensure!(
+            zeta.exp_power_of_2(common_data.degree_bits()) != F::Extension::ONE,
+            "Opening point is in the subgroup."
+        );
+
+        let openings = timed!(
+            timing,
+            "construct the opening set, including lookups",
+            OpeningSet::new(
+                zeta,
+                g,
                 &prover_data.constants_sigmas_commitment,
-                &wires_commitment,
-                &partial_products_zs_and_lookup_commitment,
-                &quotient_polys_commitment,
-            ],
-            &mut challenger,
-            &common_data.fri_params,
+                self.wires_commitment.as_ref().unwrap(),
+                self.partial_products_zs_and_lookup_commitment
+                    .as_ref()
+                    .unwrap(),
+                self.quotient_polys_commitment.as_ref().unwrap(),
+                common_data
+            )
+        );
+        self.openings = Some(openings);
+        Ok(())
+    }
+
+    /// Computes the FRI opening proof, the last stage of the prover, and returns the proof.
+    pub fn prove_openings(
+        &self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        timing: &mut TimingTree,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        self.prove_openings_with_backend(prover_data, common_data, &CpuBackend, timing)
+    }
+
+    fn prove_openings_with_backend<B: ProverBackend<F>>(
+        &self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+        backend: &B,
+        timing: &mut TimingTree,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        self.ensure_stage(ProverStage::Opened)?;
+        let StageChallenges {
+            mut challenger,
+            zeta,
+            ..
+        } = self.challenges(prover_data, common_data);
+        let zeta = zeta.expect("All commitments are done by now");
+        let wires_commitment = self.wires_commitment.as_ref().unwrap();
+        let partial_products_zs_and_lookup_commitment = self
+            .partial_products_zs_and_lookup_commitment
+            .as_ref()
+            .unwrap();
+        let quotient_polys_commitment = self.quotient_polys_commitment.as_ref().unwrap();
+        let openings = self.openings.clone().unwrap();
+
+        challenger.observe_openings(&openings.to_fri_openings());
+        let instance = common_data.get_fri_instance(zeta);
+
+        let opening_proof = timed!(
             timing,
-        )
-    );
+            "compute opening proofs",
+            PolynomialBatch::<F, C, D>::prove_openings_with_backend(
+                &instance,
+                &[
+                    &prover_data.constants_sigmas_commitment,
+                    wires_commitment,
+                    partial_products_zs_and_lookup_commitment,
+                    quotient_polys_commitment,
+                ],
+                &mut challenger,
+                &common_data.fri_params,
+                backend,
+                timing,
+            )
+        );
+
+        let proof = Proof::<F, C, D> {
+            wires_cap: wires_commitment.merkle_tree.cap.clone(),
+            plonk_zs_partial_products_cap: partial_products_zs_and_lookup_commitment
+                .merkle_tree
+                .cap
+                .clone(),
+            quotient_polys_cap: quotient_polys_commitment.merkle_tree.cap.clone(),
+            openings,
+            opening_proof,
+        };
+        Ok(ProofWithPublicInputs::<F, C, D> {
+            proof,
+            public_inputs: self.public_inputs.clone(),
+        })
+    }
 
-    let proof = Proof::<F, C, D> {
-        wires_cap: wires_commitment.merkle_tree.cap,
-        plonk_zs_partial_products_cap: partial_products_zs_and_lookup_commitment.merkle_tree.cap,
-        quotient_polys_cap: quotient_polys_commitment.merkle_tree.cap,
-        openings,
-        opening_proof,
-    };
-    Ok(ProofWithPublicInputs::<F, C, D> {
-        proof,
-        public_inputs,
-    })
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        buffer
+            .write_prover_checkpoint(self)
+            .expect("Writing to a byte-vector cannot fail.");
+        buffer
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>, common_data: &CommonCircuitData<F, D>) -> Result<Self> {
+        let mut buffer = Buffer::new(&bytes);
+        let checkpoint = buffer
+            .read_prover_checkpoint(common_data)
+            .map_err(anyhow::Error::msg)?;
+        Ok(checkpoint)
+    }
+
+    fn ensure_stage(&self, stage: ProverStage) -> Result<()> {
+        ensure!(
+            self.stage() == stage,
+            "Expected the prover to be at stage {:?}, but it is at stage {:?}.",
+            stage,
+            self.stage()
+        );
+        Ok(())
+    }
+
+    fn wires_values(&self) -> impl Iterator<Item = PolynomialValues<F>> + '_ {
+        self.witness
+            .wire_values
+            .iter()
+            .map(|column| PolynomialValues::new(column.clone()))
+    }
+
+    /// Replays the Fiat-Shamir transcript of the stages completed so far.
+    fn challenges(
+        &self,
+        prover_data: &ProverOnlyCircuitData<F, C, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> StageChallenges<F, C::Hasher, D> {
+        let num_challenges = common_data.config.num_challenges;
+        let public_inputs_hash = C::InnerHasher::hash_no_pad(&self.public_inputs);
+        let mut challenger = Challenger::<F, C::Hasher>::new();
+
+        // Observe the instance.
+        challenger.observe_hash::<C::Hasher>(prover_data.circuit_digest);
+        challenger.observe_hash::<C::InnerHasher>(public_inputs_hash);
+
+        let (mut betas, mut gammas, mut deltas) = (vec![], vec![], vec![]);
+        if let Some(wires_commitment) = &self.wires_commitment {
+            challenger.observe_cap::<C::Hasher>(&wires_commitment.merkle_tree.cap);
+            betas = challenger.get_n_challenges(num_challenges);
+            gammas = challenger.get_n_challenges(num_challenges);
+
+            // We need 4 values per challenge: 2 for the combos, 1 for (X-combo) in the accumulators and 1 to prove that the lookup table was computed correctly.
+            // We can reuse betas and gammas for two of them.
+            if !common_data.luts.is_empty() {
+                let num_lookup_challenges = NUM_COINS_LOOKUP * num_challenges;
+                let num_additional_challenges = num_lookup_challenges - 2 * num_challenges;
+                let additional = challenger.get_n_challenges(num_additional_challenges);
+                deltas.extend(&betas);
+                deltas.extend(&gammas);
+                deltas.extend(additional);
+            }
+        }
+
+        let mut alphas = vec![];
+        if let Some(commitment) = &self.partial_products_zs_and_lookup_commitment {
+            challenger.observe_cap::<C::Hasher>(&commitment.merkle_tree.cap);
+            alphas = challenger.get_n_challenges(num_challenges);
+        }
+
+        let zeta = self.quotient_polys_commitment.as_ref().map(|commitment| {
+            challenger.observe_cap::<C::Hasher>(&commitment.merkle_tree.cap);
+            challenger.get_extension_challenge::<D>()
+        });
+
+        StageChallenges {
+            challenger,
+            betas,
+            gammas,
+            deltas,
+            alphas,
+            zeta,
+        }
+    }
 }
 
 /// Compute the partial products used in the `Z` polynomials.
@@ -370,6 +1006,7 @@ fn all_wires_permutation_partial_products<
     common_data: &CommonCircuitData<F, D>,
 ) -> Vec<Vec<PolynomialValues<F>>> {
     (0..common_data.config.num_challenges)
+        .into_par_iter()
         .map(|i| {
             wires_permutation_partial_products_and_zs(
                 witness,
@@ -424,19 +1061,33 @@ fn wires_permutation_partial_products_and_zs<
                 .map(|(num, den_inv)| num * den_inv)
                 .collect::<Vec<_>>();
 
-            quotient_chunk_products(&quotient_values, degree)
+            let quotient_chunk_products = quotient_chunk_products(&quotient_values, degree);
+            let quotient_product = quotient_chunk_products.iter().copied().product::<F>();
+            (quotient_chunk_products, quotient_product)
         })
         .collect::<Vec<_>>();
 
-    let mut z_x = F::ONE;
-    let mut all_partial_products_and_zs = Vec::with_capacity(all_quotient_chunk_products.len());
-    for quotient_chunk_products in all_quotient_chunk_products {
-        let mut partial_products_and_z_gx =
-            partial_products_and_z_gx(z_x, &quotient_chunk_products);
-        // The last term is Z(gx), but we replace it with Z(x), otherwise Z would end up shifted.
-        swap(&mut z_x, &mut partial_products_and_z_gx[num_prods]);
-        all_partial_products_and_zs.push(partial_products_and_z_gx);
-    }
+    // `Z(g^i)` is the product of the quotients of all previous rows. Only this running product is
+    // computed serially, after which the partial products of each row are independent.
+    let zs = all_quotient_chunk_products
+        .iter()
+        .scan(F::ONE, |z, &(_, quotient_product)| {
+            let z_x = *z;
+            *z *= quotient_product;
+            Some(z_x)
+        })
+        .collect::<Vec<_>>();
+    let all_partial_products_and_zs = all_quotient_chunk_products
+        .par_iter()
+        .zip(zs.par_iter())
+        .map(|((quotient_chunk_products, _), &z_x)| {
+            let mut partial_products_and_z_gx =
+                partial_products_and_z_gx(z_x, quotient_chunk_products);
+            // The last term is Z(gx), but we replace it with Z(x), otherwise Z would end up shifted.
+            partial_products_and_z_gx[num_prods] = z_x;
+            partial_products_and_z_gx
+        })
+        .collect::<Vec<_>>();
 
     transpose(&all_partial_products_and_zs)
         .into_par_iter()
@@ -463,7 +1114,7 @@ fn compute_lookup_polys<
 ) -> Vec<PolynomialValues<F>> {
     let degree = common_data.degree();
     let num_lu_slots = LookupGate::num_slots(&common_data.config);
-    let max_lookup_degree = common_data.config.max_quotient_degree_factor - 1;
+    let max_lookup_degree = common_data.quotient_degree_factor - 1;
     let num_partial_lookups = num_lu_slots.div_ceil(max_lookup_degree);
     let num_lut_slots = LookupTableGate::num_slots(&common_data.config);
     let max_lookup_table_degree = num_lut_slots.div_ceil(num_partial_lookups);
@@ -583,6 +1234,7 @@ fn compute_all_lookup_polys<
 ) -> Vec<PolynomialValues<F>> {
     if lookup {
         let polys: Vec<Vec<PolynomialValues<F>>> = (0..common_data.config.num_challenges)
+            .into_par_iter()
             .map(|c| {
                 compute_lookup_polys(
                     witness,
@@ -602,22 +1254,24 @@ fn compute_all_lookup_polys<
 
 const BATCH_SIZE: usize = 32;
 
-fn compute_quotient_polys<
-    'a,
+pub(crate) fn compute_quotient_polys<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
     const D: usize,
 >(
-    common_data: &CommonCircuitData<F, D>,
-    prover_data: &'a ProverOnlyCircuitData<F, C, D>,
-    public_inputs_hash: &<<C as GenericConfig<D>>::InnerHasher as Hasher<F>>::Hash,
-    wires_commitment: &'a PolynomialBatch<F, C, D>,
-    zs_partial_products_and_lookup_commitment: &'a PolynomialBatch<F, C, D>,
-    betas: &[F],
-    gammas: &[F],
-    deltas: &[F],
-    alphas: &[F],
+    inputs: &QuotientInputs<F, C, D>,
 ) -> Vec<PolynomialCoeffs<F>> {
+    let &QuotientInputs {
+        common_data,
+        prover_data,
+        public_inputs_hash,
+        wires_commitment,
+        zs_partial_products_and_lookup_commitment,
+        betas,
+        gammas,
+        deltas,
+        alphas,
+    } = inputs;
     let num_challenges = common_data.config.num_challenges;
 
     let has_lookup = common_data.num_lookup_polys != 0;
@@ -636,6 +1290,11 @@ fn compute_quotient_polys<
     // steps away since we work on an LDE of degree `max_filtered_constraint_degree`.
     let next_step = 1 << quotient_degree_bits;
 
+    #[cfg(feature = "std")]
+    let points = plonky2_field::domain_cache::two_adic_subgroup::<F>(
+        common_data.degree_bits() + quotient_degree_bits,
+    );
+    #[cfg(not(feature = "std"))]
     let points = F::two_adic_subgroup(common_data.degree_bits() + quotient_degree_bits);
     let lde_size = points.len();
 
@@ -648,6 +1307,7 @@ fn compute_quotient_polys<
     let lut_re_poly_evals: Vec<Vec<F>> = if has_lookup {
         let num_lut_slots = LookupTableGate::num_slots(&common_data.config);
         (0..num_challenges)
+            .into_par_iter()
             .map(move |i| {
                 let cur_deltas = &deltas[NUM_COINS_LOOKUP * i..NUM_COINS_LOOKUP * (i + 1)];
                 let cur_challenge_delta = cur_deltas[LookupChallenges::ChallengeDelta as usize];
@@ -680,30 +1340,35 @@ fn compute_quotient_polys<
     let points_batches = points.par_chunks(BATCH_SIZE);
     let num_batches = points.len().div_ceil(BATCH_SIZE);
 
+    // The temporary buffers of a batch live in an arena, which is reset before the next batch
+    // handled by the same worker, instead of going through the global allocator.
     let quotient_values: Vec<Vec<F>> = points_batches
         .enumerate()
-        .flat_map(|(batch_i, xs_batch)| {
+        .map_init(Bump::new, |arena, (batch_i, xs_batch)| {
+            arena.reset();
+            let arena = &*arena;
+
             // Each batch must be the same size, except the last one, which may be smaller.
             debug_assert!(
                 xs_batch.len() == BATCH_SIZE
                     || (batch_i == num_batches - 1 && xs_batch.len() <= BATCH_SIZE)
             );
 
-            let indices_batch: Vec<usize> =
-                (BATCH_SIZE * batch_i..BATCH_SIZE * batch_i + xs_batch.len()).collect();
+            let indices_batch: &[usize] = arena
+                .alloc_slice_fill_iter(BATCH_SIZE * batch_i..BATCH_SIZE * batch_i + xs_batch.len());
 
-            let mut shifted_xs_batch = Vec::with_capacity(xs_batch.len());
-            let mut local_zs_batch = Vec::with_capacity(xs_batch.len());
-            let mut next_zs_batch = Vec::with_capacity(xs_batch.len());
+            let mut shifted_xs_batch = BumpVec::with_capacity_in(xs_batch.len(), arena);
+            let mut local_zs_batch = BumpVec::with_capacity_in(xs_batch.len(), arena);
+            let mut next_zs_batch = BumpVec::with_capacity_in(xs_batch.len(), arena);
 
-            let mut local_lookup_batch = Vec::with_capacity(xs_batch.len());
-            let mut next_lookup_batch = Vec::with_capacity(xs_batch.len());
+            let mut local_lookup_batch = BumpVec::with_capacity_in(xs_batch.len(), arena);
+            let mut next_lookup_batch = BumpVec::with_capacity_in(xs_batch.len(), arena);
 
-            let mut partial_products_batch = Vec::with_capacity(xs_batch.len());
-            let mut s_sigmas_batch = Vec::with_capacity(xs_batch.len());
+            let mut partial_products_batch = BumpVec::with_capacity_in(xs_batch.len(), arena);
+            let mut s_sigmas_batch = BumpVec::with_capacity_in(xs_batch.len(), arena);
 
-            let mut local_constants_batch_refs = Vec::with_capacity(xs_batch.len());
-            let mut local_wires_batch_refs = Vec::with_capacity(xs_batch.len());
+            let mut local_constants_batch_refs = BumpVec::with_capacity_in(xs_batch.len(), arena);
+            let mut local_wires_batch_refs = BumpVec::with_capacity_in(xs_batch.len(), arena);
 
             for (&i, &x) in indices_batch.iter().zip(xs_batch) {
                 let shifted_x = F::coset_shift() * x;
@@ -750,16 +1415,18 @@ fn compute_quotient_polys<
             }
 
             // NB (JN): I'm not sure how (in)efficient the below is. It needs measuring.
-            let mut local_constants_batch =
-                vec![F::ZERO; xs_batch.len() * local_constants_batch_refs[0].len()];
+            let local_constants_batch = arena.alloc_slice_fill_copy(
+                xs_batch.len() * local_constants_batch_refs[0].len(),
+                F::ZERO,
+            );
             for i in 0..local_constants_batch_refs[0].len() {
                 for (j, constants) in local_constants_batch_refs.iter().enumerate() {
                     local_constants_batch[i * xs_batch.len() + j] = constants[i];
                 }
             }
 
-            let mut local_wires_batch =
-                vec![F::ZERO; xs_batch.len() * local_wires_batch_refs[0].len()];
+            let local_wires_batch = arena
+                .alloc_slice_fill_copy(xs_batch.len() * local_wires_batch_refs[0].len(), F::ZERO);
             for i in 0..local_wires_batch_refs[0].len() {
                 for (j, wires) in local_wires_batch_refs.iter().enumerate() {
                     local_wires_batch[i * xs_batch.len() + j] = wires[i];
@@ -768,14 +1435,15 @@ fn compute_quotient_polys<
 
             let vars_batch = EvaluationVarsBaseBatch::new(
                 xs_batch.len(),
-                &local_constants_batch,
-                &local_wires_batch,
+                local_constants_batch,
+                local_wires_batch,
                 public_inputs_hash,
             );
 
             let mut quotient_values_batch = eval_vanishing_poly_base_batch::<F, D>(
                 common_data,
-                &indices_batch,
+                arena,
+                indices_batch,
                 &shifted_xs_batch,
                 vars_batch,
                 &local_zs_batch,
@@ -801,9 +1469,11 @@ fn compute_quotient_polys<
             }
             quotient_values_batch
         })
+        .flatten()
         .collect();
 
-    transpose(&quotient_values)
+    let quotient_values = transpose(&quotient_values);
+    quotient_values
         .into_par_iter()
         .map(PolynomialValues::new)
         .map(|values| values.coset_ifft(F::coset_shift()))
@@ -0,0 +1,345 @@
+//! A proving service for server deployments, which keeps a set of circuits loaded and proves
+//! jobs submitted for any of them on a fixed number of worker threads.
+//!
+//! Circuits are registered once and keyed by their digest. Jobs go through a bounded queue:
+//! `submit` blocks while the queue is full, and `try_submit` fails instead, so that callers can
+//! shed load. Dropping the service finishes the queued jobs, then stops the workers.
+
+use core::fmt::{self, Debug};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+
+use anyhow::{anyhow, ensure, Result};
+use hashbrown::HashMap;
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::witness::PartialWitness;
+use crate::plonk::circuit_data::ProverCircuitData;
+use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use crate::plonk::proof::ProofWithPublicInputs;
+
+type Digest<F, C, const D: usize> = <<C as GenericConfig<D>>::Hasher as Hasher<F>>::Hash;
+
+type Circuits<F, C, const D: usize> = RwLock<HashMap<Vec<u8>, Arc<ProverCircuitData<F, C, D>>>>;
+
+struct Job<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    circuit: Arc<ProverCircuitData<F, C, D>>,
+    inputs: PartialWitness<F>,
+    result: Sender<Result<ProofWithPublicInputs<F, C, D>>>,
+}
+
+/// A pending proof, returned when a job is submitted.
+#[derive(Debug)]
+pub struct ProofHandle<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    result: Receiver<Result<ProofWithPublicInputs<F, C, D>>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    ProofHandle<F, C, D>
+{
+    /// Blocks until the proof is done.
+    pub fn wait(self) -> Result<ProofWithPublicInputs<F, C, D>> {
+        self.result
+            .recv()
+            .map_err(|_| anyhow!("The worker proving this job panicked."))?
+    }
+
+    /// Returns the proof if it is done, or `None` if it is still queued or being proved.
+    pub fn try_wait(&self) -> Option<Result<ProofWithPublicInputs<F, C, D>>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Some(Err(anyhow!("The worker proving this job panicked.")))
+            }
+        }
+    }
+}
+
+/// Proves jobs for a set of loaded circuits on a pool of worker threads.
+pub struct ProverService<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    const D: usize,
+> {
+    circuits: Circuits<F, C, D>,
+    jobs: Option<SyncSender<Job<F, C, D>>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F> + 'static, const D: usize>
+    ProverService<F, C, D>
+{
+    /// Starts `num_workers` worker threads, with room for `queue_capacity` jobs waiting for a
+    /// worker. Each worker proves one job at a time, using rayon's global thread pool.
+    pub fn new(num_workers: usize, queue_capacity: usize) -> Result<Self> {
+        ensure!(num_workers > 0, "At least one worker is needed.");
+        let (sender, receiver) = mpsc::sync_channel::<Job<F, C, D>>(queue_capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..num_workers)
+            .map(|i| {
+                let receiver = receiver.clone();
+                thread::Builder::new()
+                    .name(format!("plonky2-prover-{i}"))
+                    .spawn(move || Self::work(&receiver))
+            })
+            .collect::<std::io::Result<_>>()?;
+
+        Ok(Self {
+            circuits: RwLock::new(HashMap::new()),
+            jobs: Some(sender),
+            workers,
+        })
+    }
+
+    fn work(receiver: &Mutex<Receiver<Job<F, C, D>>>) {
+        loop {
+            // The lock is released before proving, so that other workers can take jobs.
+            let job = receiver.lock().unwrap().recv();
+            let Ok(Job {
+                circuit,
+                inputs,
+                result,
+            }) = job
+            else {
+                // The service was dropped and the queue is drained.
+                return;
+            };
+            // A panic while proving fails this job only, and the worker goes on with the next.
+            let proof = panic::catch_unwind(AssertUnwindSafe(|| circuit.prove(inputs)))
+                .unwrap_or_else(|payload| {
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .copied()
+                        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                        .unwrap_or("unknown cause");
+                    Err(anyhow!("Proving panicked: {}", message))
+                });
+            // The submitter may have dropped its handle; the proof is discarded then.
+            let _ = result.send(proof);
+        }
+    }
+
+    /// Loads `circuit`, replacing any circuit with the same digest, and returns its digest.
+    pub fn register(&self, circuit: ProverCircuitData<F, C, D>) -> Digest<F, C, D> {
+        let digest = circuit.prover_only.circuit_digest;
+        self.circuits
+            .write()
+            .unwrap()
+            .insert(digest.to_bytes(), Arc::new(circuit));
+        digest
+    }
+
+    /// Unloads the circuit with digest `digest`. Jobs already submitted for it are still proved.
+    pub fn unregister(&self, digest: &Digest<F, C, D>) -> bool {
+        self.circuits
+            .write()
+            .unwrap()
+            .remove(&digest.to_bytes())
+            .is_some()
+    }
+
+    /// Whether a circuit with digest `digest` is loaded.
+    pub fn is_registered(&self, digest: &Digest<F, C, D>) -> bool {
+        self.circuits
+            .read()
+            .unwrap()
+            .contains_key(&digest.to_bytes())
+    }
+
+    /// Queues a proof of the circuit with digest `digest` on `inputs`, blocking while the queue is
+    /// full.
+    pub fn submit(
+        &self,
+        digest: &Digest<F, C, D>,
+        inputs: PartialWitness<F>,
+    ) -> Result<ProofHandle<F, C, D>> {
+        let (job, handle) = self.job(digest, inputs)?;
+        self.jobs
+            .as_ref()
+            .unwrap()
+            .send(job)
+            .map_err(|_| anyhow!("All workers have stopped."))?;
+        Ok(handle)
+    }
+
+    /// Like `submit`, but fails rather than blocking if the queue is full.
+    pub fn try_submit(
+        &self,
+        digest: &Digest<F, C, D>,
+        inputs: PartialWitness<F>,
+    ) -> Result<ProofHandle<F, C, D>> {
+        let (job, handle) = self.job(digest, inputs)?;
+        match self.jobs.as_ref().unwrap().try_send(job) {
+            Ok(()) => Ok(handle),
+            Err(TrySendError::Full(_)) => Err(anyhow!("The job queue is full.")),
+            Err(TrySendError::Disconnected(_)) => Err(anyhow!("All workers have stopped.")),
+        }
+    }
+
+    fn job(
+        &self,
+        digest: &Digest<F, C, D>,
+        inputs: PartialWitness<F>,
+    ) -> Result<(Job<F, C, D>, ProofHandle<F, C, D>)> {
+        let circuit = self
+            .circuits
+            .read()
+            .unwrap()
+            .get(&digest.to_bytes())
+            .cloned()
+            .ok_or_else(|| anyhow!("No circuit with digest {:?} is registered.", digest))?;
+        let (sender, receiver) = mpsc::channel();
+        Ok((
+            Job {
+                circuit,
+                inputs,
+                result: sender,
+            },
+            ProofHandle { result: receiver },
+        ))
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F> + 'static, const D: usize> Debug
+    for ProverService<F, C, D>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProverService")
+            .field("num_circuits", &self.circuits.read().unwrap().len())
+            .field("num_workers", &self.workers.len())
+            .finish()
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F> + 'static, const D: usize> Drop
+    for ProverService<F, C, D>
+{
+    fn drop(&mut self) {
+        // Closing the queue lets the workers stop once the queued jobs are done.
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::target::Target;
+    use crate::iop::witness::WitnessWrite;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    fn circuit(exponent: u64) -> (CircuitData<F, C, D>, Target) {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.exp_u64(x, exponent);
+        builder.register_public_input(y);
+        (builder.build::<C>(), x)
+    }
+
+    #[test]
+    fn test_prover_service() -> Result<()> {
+        let service = ProverService::<F, C, D>::new(2, 4)?;
+        let (cube, cube_x) = circuit(3);
+        let (square, square_x) = circuit(2);
+        let cube_verifier = cube.verifier_data();
+        let square_verifier = square.verifier_data();
+        let cube_digest = service.register(cube.prover_data());
+        let square_digest = service.register(square.prover_data());
+        assert!(service.is_registered(&cube_digest));
+
+        let handles = (0..6)
+            .map(|i| -> Result<_> {
+                let (digest, x) = if i % 2 == 0 {
+                    (&cube_digest, cube_x)
+                } else {
+                    (&square_digest, square_x)
+                };
+                let mut pw = PartialWitness::new();
+                pw.set_target(x, F::from_canonical_u64(i))?;
+                service.submit(digest, pw)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (i, handle) in handles.into_iter().enumerate() {
+            let proof = handle.wait()?;
+            let x = F::from_canonical_usize(i);
+            if i % 2 == 0 {
+                assert_eq!(proof.public_inputs[1], x.cube());
+                cube_verifier.verify(proof)?;
+            } else {
+                assert_eq!(proof.public_inputs[1], x * x);
+                square_verifier.verify(proof)?;
+            }
+        }
+
+        assert!(service.unregister(&cube_digest));
+        assert!(!service.is_registered(&cube_digest));
+        assert!(service.submit(&cube_digest, PartialWitness::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prover_service_survives_panics() -> Result<()> {
+        let service = ProverService::<F, C, D>::new(1, 2)?;
+        let (data, x) = circuit(3);
+        let verifier = data.verifier_data();
+        let digest = service.register(data.prover_data());
+
+        // Setting a target the circuit doesn't have panics in witness generation.
+        let mut pw = PartialWitness::new();
+        pw.set_target(Target::VirtualTarget { index: 1 << 30 }, F::ONE)?;
+        let panicking = service.submit(&digest, pw)?;
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO)?;
+        let normal = service.submit(&digest, pw)?;
+
+        let error = panicking.wait().unwrap_err();
+        assert!(error.to_string().starts_with("Proving panicked"));
+        let proof = normal.wait()?;
+        assert_eq!(proof.public_inputs[1], F::from_canonical_u64(8));
+        verifier.verify(proof)
+    }
+
+    #[test]
+    fn test_prover_service_backpressure() -> Result<()> {
+        let service = ProverService::<F, C, D>::new(1, 1)?;
+        let (data, x) = circuit(3);
+        let digest = service.register(data.prover_data());
+        let inputs = |value| -> Result<_> {
+            let mut pw = PartialWitness::new();
+            pw.set_target(x, F::from_canonical_u64(value))?;
+            Ok(pw)
+        };
+
+        // With one worker and room for one queued job, submitting without waiting eventually
+        // finds the queue full.
+        let mut handles = Vec::new();
+        let mut rejected = false;
+        for i in 0..16 {
+            match service.try_submit(&digest, inputs(i)?) {
+                Ok(handle) => handles.push(handle),
+                Err(_) => {
+                    rejected = true;
+                    break;
+                }
+            }
+        }
+        assert!(rejected);
+        for handle in handles {
+            handle.wait()?;
+        }
+        Ok(())
+    }
+}
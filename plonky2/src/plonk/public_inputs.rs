@@ -0,0 +1,361 @@
+//! Names for groups of public inputs, so that their values can be read from proofs without
+//! hard-coding their indices.
+//!
+//! A circuit's public inputs can also be described by a struct deriving [`CircuitPublicInputs`],
+//! which registers each of its fields under the field's name and reads them back as typed values:
+//!
+//! ```
+//! use plonky2::field::types::Field;
+//! use plonky2::hash::hash_types::HashOutTarget;
+//! use plonky2::hash::poseidon::PoseidonHash;
+//! use plonky2::iop::target::Target;
+//! use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+//! use plonky2::plonk::circuit_builder::CircuitBuilder;
+//! use plonky2::plonk::circuit_data::CircuitConfig;
+//! use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+//! use plonky2::plonk::public_inputs::CircuitPublicInputs;
+//!
+//! #[derive(CircuitPublicInputs)]
+//! struct Commitment {
+//!     count: Target,
+//!     digest: HashOutTarget,
+//! }
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! const D: usize = 2;
+//! type C = PoseidonGoldilocksConfig;
+//! type F = <C as GenericConfig<D>>::F;
+//!
+//! let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+//! let count = builder.add_virtual_target();
+//! let digest = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![count]);
+//! Commitment { count, digest }.register(&mut builder);
+//! let data = builder.build::<C>();
+//!
+//! let mut pw = PartialWitness::new();
+//! pw.set_target(count, F::from_canonical_u64(7))?;
+//! let proof = data.prove(pw)?;
+//! let values =
+//!     Commitment::read(&proof.named_public_inputs(&data.prover_only.public_input_names))?;
+//! assert_eq!(values.count, F::from_canonical_u64(7));
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::any::type_name;
+use core::fmt::Debug;
+use core::ops::Range;
+
+use anyhow::{anyhow, ensure, Result};
+pub use plonky2_derive::CircuitPublicInputs;
+use serde::{Deserialize, Serialize};
+
+use crate::field::extension::Extendable;
+use crate::field::types::Field;
+use crate::hash::hash_types::{HashOut, HashOutTarget, RichField};
+use crate::iop::target::Target;
+use crate::plonk::circuit_builder::CircuitBuilder;
+
+/// The named groups of public inputs of a circuit, in registration order. Each group is a range of
+/// indices into the circuit's public inputs.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PublicInputNames {
+    groups: Vec<(String, Range<usize>)>,
+}
+
+impl PublicInputNames {
+    /// Names the public inputs in `range`. Panics if `name` is already taken.
+    pub(crate) fn push(&mut self, name: &str, range: Range<usize>) {
+        assert!(
+            self.range(name).is_none(),
+            "Public input name {name} is already registered"
+        );
+        self.groups.push((name.to_string(), range));
+    }
+
+    /// The indices of the public inputs named `name`, if any.
+    pub fn range(&self, name: &str) -> Option<Range<usize>> {
+        self.groups
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, range)| range.clone())
+    }
+
+    /// The named groups, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Range<usize>)> {
+        self.groups
+            .iter()
+            .map(|(name, range)| (name.as_str(), range.clone()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+/// The public inputs of a proof, read through the names given to them when building the circuit.
+#[derive(Clone, Copy, Debug)]
+pub struct PublicInputs<'a, F> {
+    values: &'a [F],
+    names: &'a PublicInputNames,
+}
+
+impl<'a, F: Copy> PublicInputs<'a, F> {
+    pub const fn new(values: &'a [F], names: &'a PublicInputNames) -> Self {
+        Self { values, names }
+    }
+
+    /// The values of the group of public inputs named `name`.
+    pub fn group(&self, name: &str) -> Result<&'a [F]> {
+        let range = self
+            .names
+            .range(name)
+            .ok_or_else(|| anyhow!("No public input is named {name}"))?;
+        self.values.get(range.clone()).ok_or_else(|| {
+            anyhow!(
+                "Public inputs {name} are at {range:?}, but there are only {} public inputs",
+                self.values.len()
+            )
+        })
+    }
+
+    /// The value of the single public input named `name`.
+    pub fn get(&self, name: &str) -> Result<F> {
+        let group = self.group(name)?;
+        ensure!(
+            group.len() == 1,
+            "{name} names {} public inputs rather than one",
+            group.len()
+        );
+        Ok(group[0])
+    }
+
+    /// The group of public inputs named `name`, converted to a `T`, e.g. a `HashOut` or an array.
+    pub fn get_as<T: TryFrom<&'a [F]>>(&self, name: &str) -> Result<T> {
+        let group = self.group(name)?;
+        T::try_from(group).map_err(|_| {
+            anyhow!(
+                "The {} public inputs named {name} don't form a {}",
+                group.len(),
+                type_name::<T>()
+            )
+        })
+    }
+}
+
+/// A struct of targets to be registered as public inputs, each under its field's name. Derive it
+/// with `#[derive(CircuitPublicInputs)]`, which also defines a `<Struct>Values<F>` struct holding
+/// the fields' values.
+pub trait CircuitPublicInputs {
+    /// The values of these public inputs in a proof.
+    type Values<F: Field>;
+
+    /// Registers the fields as public inputs.
+    fn register<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+    );
+
+    /// Reads the values of the fields from a proof's public inputs.
+    fn read<F: Field>(public_inputs: &PublicInputs<F>) -> Result<Self::Values<F>>;
+}
+
+/// A target, or a group of targets, which can be a field of a struct deriving
+/// [`CircuitPublicInputs`].
+pub trait PublicInputTarget {
+    /// The value of this target in a proof.
+    type Value<F: Field>: Clone + Debug + Eq;
+
+    /// Registers this target as a group of public inputs named `name`.
+    fn register<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        name: &str,
+    );
+
+    /// Reads the value of the group of public inputs named `name`.
+    fn read<F: Field>(public_inputs: &PublicInputs<F>, name: &str) -> Result<Self::Value<F>>;
+}
+
+impl PublicInputTarget for Target {
+    type Value<F: Field> = F;
+
+    fn register<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        name: &str,
+    ) {
+        builder.register_public_input_named(*self, name);
+    }
+
+    fn read<F: Field>(public_inputs: &PublicInputs<F>, name: &str) -> Result<F> {
+        public_inputs.get(name)
+    }
+}
+
+impl PublicInputTarget for HashOutTarget {
+    type Value<F: Field> = HashOut<F>;
+
+    fn register<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        name: &str,
+    ) {
+        builder.register_public_inputs_named(&self.elements, name);
+    }
+
+    fn read<F: Field>(public_inputs: &PublicInputs<F>, name: &str) -> Result<HashOut<F>> {
+        public_inputs.get_as(name)
+    }
+}
+
+impl<const N: usize> PublicInputTarget for [Target; N] {
+    type Value<F: Field> = [F; N];
+
+    fn register<F: RichField + Extendable<D>, const D: usize>(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        name: &str,
+    ) {
+        builder.register_public_inputs_named(self, name);
+    }
+
+    fn read<F: Field>(public_inputs: &PublicInputs<F>, name: &str) -> Result<[F; N]> {
+        public_inputs.get_as(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Result};
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::hash::poseidon::PoseidonHash;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_data::{CircuitConfig, ProverOnlyCircuitData};
+    use crate::plonk::config::{GenericConfig, Hasher, PoseidonGoldilocksConfig};
+    use crate::util::serialization::DefaultGeneratorSerializer;
+
+    #[test]
+    fn test_named_public_inputs() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let leaves = builder.add_virtual_targets(4);
+        let root = builder.hash_n_to_hash_no_pad::<PoseidonHash>(leaves.clone());
+        let sum = builder.add_many(&leaves);
+        // An unnamed public input shifts the named ones.
+        builder.register_public_input(sum);
+        builder.register_public_inputs_named(&root.elements, "root");
+        builder.register_public_input_named(sum, "sum");
+        let data = builder.build::<C>();
+
+        let names = &data.prover_only.public_input_names;
+        assert_eq!(
+            names.iter().collect::<Vec<_>>(),
+            vec![("root", 1..5), ("sum", 5..6)]
+        );
+        let generator_serializer = DefaultGeneratorSerializer::<C, D>::default();
+        let bytes = data
+            .prover_only
+            .to_bytes(&generator_serializer, &data.common)
+            .map_err(|_| anyhow!("serialization failed"))?;
+        let prover_only = ProverOnlyCircuitData::<F, C, D>::from_bytes(
+            &bytes,
+            &generator_serializer,
+            &data.common,
+        )
+        .map_err(|_| anyhow!("deserialization failed"))?;
+        assert_eq!(&prover_only.public_input_names, names);
+
+        let values = F::rand_vec(4);
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&leaves, &values)?;
+        let proof = data.prove(pw)?;
+
+        let public_inputs = proof.named_public_inputs(names);
+        let expected_root = PoseidonHash::hash_no_pad(&values);
+        assert_eq!(public_inputs.get_as::<HashOut<F>>("root")?, expected_root);
+        assert_eq!(
+            public_inputs.get_as::<[F; 4]>("root")?,
+            expected_root.elements
+        );
+        assert_eq!(public_inputs.get("sum")?, values.iter().copied().sum::<F>());
+        assert!(public_inputs.get("root").is_err());
+        assert!(public_inputs.get_as::<[F; 2]>("root").is_err());
+        assert!(public_inputs.group("missing").is_err());
+
+        data.verify(proof)
+    }
+
+    #[test]
+    fn test_derive_circuit_public_inputs() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        #[derive(CircuitPublicInputs)]
+        struct Transition {
+            old_root: HashOutTarget,
+            new_root: HashOutTarget,
+            deltas: [Target; 2],
+        }
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let old_root = builder.add_virtual_hash();
+        let deltas = builder.add_virtual_target_arr::<2>();
+        let new_root = builder.hash_n_to_hash_no_pad::<PoseidonHash>(
+            old_root.elements.iter().chain(&deltas).copied().collect(),
+        );
+        Transition {
+            old_root,
+            new_root,
+            deltas,
+        }
+        .register(&mut builder);
+        let data = builder.build::<C>();
+
+        let old_root_value = HashOut::rand();
+        let deltas_value = [F::ONE, F::TWO];
+        let mut pw = PartialWitness::new();
+        pw.set_hash_target(old_root, old_root_value)?;
+        pw.set_target_arr(&deltas, &deltas_value)?;
+        let proof = data.prove(pw)?;
+
+        let values =
+            Transition::read(&proof.named_public_inputs(&data.prover_only.public_input_names))?;
+        let new_root_value = PoseidonHash::hash_no_pad(
+            &[old_root_value.elements.as_slice(), &deltas_value].concat(),
+        );
+        assert_eq!(
+            values,
+            TransitionValues {
+                old_root: old_root_value,
+                new_root: new_root_value,
+                deltas: deltas_value,
+            }
+        );
+
+        data.verify(proof)
+    }
+
+    #[test]
+    #[should_panic(expected = "already registered")]
+    fn test_duplicate_public_input_name() {
+        const D: usize = 2;
+        type F = <PoseidonGoldilocksConfig as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        builder.register_public_input_named(x, "x");
+        builder.register_public_input_named(x, "x");
+    }
+}
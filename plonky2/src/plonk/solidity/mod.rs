@@ -0,0 +1,322 @@
+//! Encoding of proofs as calldata for verifier contracts on the EVM.
+//!
+//! Only [`EvmKeccakGoldilocksConfig`] is supported, since its transcript and Merkle trees can be
+//! recomputed with the EVM's `keccak256`. Circuits with lookups are rejected. Proofs of such
+//! circuits, or under other configs, can be wrapped in a recursive proof of a circuit built with
+//! [`EvmKeccakGoldilocksConfig`], which is supported.
+//!
+//! No verifier contract is generated yet: one has to be written against the layout documented on
+//! [`encode_proof`].
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use anyhow::{ensure, Result};
+use keccak_hash::keccak;
+
+use crate::field::extension::quadratic::QuadraticExtension;
+use crate::field::extension::FieldExtension;
+use crate::field::goldilocks_field::GoldilocksField;
+use crate::field::types::PrimeField64;
+use crate::hash::hash_types::BytesHash;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::plonk::circuit_data::CommonCircuitData;
+use crate::plonk::config::{EvmKeccakGoldilocksConfig, GenericConfig};
+use crate::plonk::plonk_common::{salt_size, PlonkOracle};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::plonk::validate_shape::validate_proof_with_pis_shape;
+
+const D: usize = 2;
+type C = EvmKeccakGoldilocksConfig;
+type F = GoldilocksField;
+type FE = QuadraticExtension<F>;
+
+/// The signature of the verifier contract's entry point.
+pub const VERIFY_SIGNATURE: &str = "verify(uint64[],bytes)";
+
+/// Encodes the proof in `proof_with_pis`, without its public inputs, as the `bytes` argument of
+/// a verifier contract.
+///
+/// Field elements are written as 8-byte big-endian integers, extension elements as their two
+/// components, and hashes as their 32 bytes, in the following order: the wires, Z and quotient
+/// caps, the openings at zeta and at g * zeta, the FRI commit phase caps, final polynomial and
+/// proof of work witness, then each query round.
+pub fn encode_proof(
+    proof_with_pis: &ProofWithPublicInputs<F, C, D>,
+    common: &CommonCircuitData<F, D>,
+) -> Result<Vec<u8>> {
+    ensure!(
+        common.num_lookup_polys == 0,
+        "Lookups aren't supported by the EVM encoding"
+    );
+    validate_proof_with_pis_shape(proof_with_pis, common)?;
+    let proof = &proof_with_pis.proof;
+    let openings = &proof.openings;
+    let fri_proof = &proof.opening_proof;
+    let layout = ProofLayout::new(common);
+
+    let mut bytes = Vec::new();
+    write_cap(&mut bytes, &proof.wires_cap);
+    write_cap(&mut bytes, &proof.plonk_zs_partial_products_cap);
+    write_cap(&mut bytes, &proof.quotient_polys_cap);
+    for values in [
+        &openings.constants,
+        &openings.plonk_sigmas,
+        &openings.wires,
+        &openings.plonk_zs,
+        &openings.partial_products,
+        &openings.quotient_polys,
+    ] {
+        write_exts(&mut bytes, values);
+    }
+    debug_assert_eq!(bytes.len(), layout.next_openings);
+    write_exts(&mut bytes, &openings.plonk_zs_next);
+    for cap in &fri_proof.commit_phase_merkle_caps {
+        write_cap(&mut bytes, cap);
+    }
+    debug_assert_eq!(bytes.len(), layout.final_poly);
+    write_exts(&mut bytes, &fri_proof.final_poly.coeffs);
+    debug_assert_eq!(bytes.len(), layout.pow_witness);
+    write_elements(&mut bytes, &[fri_proof.pow_witness]);
+
+    for (i, round) in fri_proof.query_round_proofs.iter().enumerate() {
+        let round_offset = layout.query_rounds + i * layout.query_round_len;
+        for ((leaf, merkle_proof), &offset) in round
+            .initial_trees_proof
+            .evals_proofs
+            .iter()
+            .zip(&layout.oracles)
+        {
+            debug_assert_eq!(bytes.len(), round_offset + offset);
+            write_elements(&mut bytes, leaf);
+            write_hashes(&mut bytes, &merkle_proof.siblings);
+        }
+        for step in &round.steps {
+            write_exts(&mut bytes, &step.evals);
+            write_hashes(&mut bytes, &step.merkle_proof.siblings);
+        }
+    }
+
+    ensure!(
+        bytes.len() == layout.len,
+        "Encoded proof has {} bytes, expected {}",
+        bytes.len(),
+        layout.len
+    );
+    Ok(bytes)
+}
+
+/// Encodes a call to the `verify(uint64[],bytes)` function of a verifier contract, with the
+/// public inputs and proof in `proof_with_pis`.
+pub fn encode_verify_calldata(
+    proof_with_pis: &ProofWithPublicInputs<F, C, D>,
+    common: &CommonCircuitData<F, D>,
+) -> Result<Vec<u8>> {
+    let proof = encode_proof(proof_with_pis, common)?;
+    let public_inputs = &proof_with_pis.public_inputs;
+
+    let mut calldata = keccak(VERIFY_SIGNATURE.as_bytes()).0[..4].to_vec();
+    // The head holds the offsets of the two dynamic arguments, relative to its start.
+    let public_inputs_offset = 2 * 32;
+    let proof_offset = public_inputs_offset + 32 * (1 + public_inputs.len());
+    calldata.extend(abi_word(public_inputs_offset as u64));
+    calldata.extend(abi_word(proof_offset as u64));
+    calldata.extend(abi_word(public_inputs.len() as u64));
+    for x in public_inputs {
+        calldata.extend(abi_word(x.to_canonical_u64()));
+    }
+    calldata.extend(abi_word(proof.len() as u64));
+    calldata.extend(&proof);
+    // Pad the arguments, which follow the 4-byte selector, to a whole number of words.
+    calldata.resize(4 + (calldata.len() - 4).next_multiple_of(32), 0);
+    Ok(calldata)
+}
+
+/// Byte offsets of the parts of a proof encoded by [`encode_proof`].
+#[derive(Debug)]
+struct ProofLayout {
+    next_openings: usize,
+    final_poly: usize,
+    pow_witness: usize,
+    query_rounds: usize,
+    query_round_len: usize,
+    /// The offsets of the leaves opened in each initial tree, relative to the start of a query
+    /// round.
+    oracles: Vec<usize>,
+    len: usize,
+}
+
+const ELEMENT_LEN: usize = 8;
+const EXT_LEN: usize = D * ELEMENT_LEN;
+const HASH_LEN: usize = 32;
+
+impl ProofLayout {
+    fn new(common: &CommonCircuitData<F, D>) -> Self {
+        let config = &common.config;
+        let fri_params = &common.fri_params;
+        let cap_height = fri_params.config.cap_height;
+        let cap_len = 1 << cap_height;
+
+        let openings = 3 * cap_len * HASH_LEN;
+        let num_openings = common.num_preprocessed_polys()
+            + config.num_wires
+            + common.num_zs_partial_products_polys()
+            + common.num_quotient_polys();
+        let next_openings = openings + num_openings * EXT_LEN;
+        let commit_phase_caps = next_openings + config.num_challenges * EXT_LEN;
+        let final_poly =
+            commit_phase_caps + fri_params.reduction_arity_bits.len() * cap_len * HASH_LEN;
+        let pow_witness = final_poly + fri_params.final_poly_len() * EXT_LEN;
+        let query_rounds = pow_witness + ELEMENT_LEN;
+
+        let initial_siblings = fri_params.lde_bits() - cap_height;
+        let mut offset = 0;
+        let oracles = [
+            (
+                common.num_preprocessed_polys(),
+                PlonkOracle::CONSTANTS_SIGMAS,
+            ),
+            (config.num_wires, PlonkOracle::WIRES),
+            (
+                common.num_zs_partial_products_polys(),
+                PlonkOracle::ZS_PARTIAL_PRODUCTS,
+            ),
+            (common.num_quotient_polys(), PlonkOracle::QUOTIENT),
+        ]
+        .into_iter()
+        .map(|(num_polys, oracle)| {
+            let leaf_len = num_polys + salt_size(fri_params.hiding && oracle.blinding);
+            let oracle_offset = offset;
+            offset += leaf_len * ELEMENT_LEN + initial_siblings * HASH_LEN;
+            oracle_offset
+        })
+        .collect();
+
+        let mut bits = fri_params.lde_bits();
+        for &arity_bits in &fri_params.reduction_arity_bits {
+            bits -= arity_bits;
+            offset += (1 << arity_bits) * EXT_LEN + (bits - cap_height) * HASH_LEN;
+        }
+        let query_round_len = offset;
+
+        Self {
+            next_openings,
+            final_poly,
+            pow_witness,
+            query_rounds,
+            query_round_len,
+            oracles,
+            len: query_rounds + fri_params.config.num_query_rounds * query_round_len,
+        }
+    }
+}
+
+fn write_elements(bytes: &mut Vec<u8>, elements: &[F]) {
+    for x in elements {
+        bytes.extend(x.to_canonical_u64().to_be_bytes());
+    }
+}
+
+fn write_exts(bytes: &mut Vec<u8>, elements: &[FE]) {
+    for x in elements {
+        write_elements(bytes, &FieldExtension::<D>::to_basefield_array(x));
+    }
+}
+
+fn write_hashes(bytes: &mut Vec<u8>, hashes: &[BytesHash<32>]) {
+    for hash in hashes {
+        bytes.extend(hash.0);
+    }
+}
+
+fn write_cap(bytes: &mut Vec<u8>, cap: &MerkleCap<F, <C as GenericConfig<D>>::Hasher>) {
+    write_hashes(bytes, &cap.0);
+}
+
+fn abi_word(x: u64) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[24..].copy_from_slice(&x.to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+
+    fn square_circuit() -> Result<(CircuitData<F, C, D>, ProofWithPublicInputs<F, C, D>)> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.mul(x, x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3))?;
+        let proof = data.prove(pw)?;
+        Ok((data, proof))
+    }
+
+    #[test]
+    fn test_encode_verify_calldata() -> Result<()> {
+        let (data, proof) = square_circuit()?;
+        let layout = ProofLayout::new(&data.common);
+        let bytes = encode_proof(&proof, &data.common)?;
+        assert_eq!(bytes.len(), layout.len);
+        let fri_proof = &proof.proof.opening_proof;
+        let element_at = |offset: usize| {
+            u64::from_be_bytes(bytes[offset..offset + ELEMENT_LEN].try_into().unwrap())
+        };
+        assert_eq!(
+            element_at(layout.pow_witness),
+            fri_proof.pow_witness.to_canonical_u64()
+        );
+        assert_eq!(
+            element_at(layout.final_poly),
+            FieldExtension::<D>::to_basefield_array(&fri_proof.final_poly.coeffs[0])[0].0
+        );
+        assert_eq!(
+            element_at(layout.next_openings),
+            FieldExtension::<D>::to_basefield_array(&proof.proof.openings.plonk_zs_next[0])[0].0
+        );
+        let last_round = fri_proof.query_round_proofs.last().unwrap();
+        let quotient_leaf = &last_round.initial_trees_proof.evals_proofs[3].0;
+        let last_round_offset = layout.query_rounds + (layout.query_round_len * 27);
+        assert_eq!(
+            element_at(last_round_offset + layout.oracles[3]),
+            quotient_leaf[0].0
+        );
+
+        let calldata = encode_verify_calldata(&proof, &data.common)?;
+        assert_eq!(calldata[..4], keccak(VERIFY_SIGNATURE.as_bytes()).0[..4]);
+        assert_eq!(calldata.len() % 32, 4);
+        // The public inputs, 3 and 9, follow the head and the array length.
+        assert_eq!(calldata[4 + 3 * 32..4 + 4 * 32], abi_word(3));
+        assert_eq!(calldata[4 + 4 * 32..4 + 5 * 32], abi_word(9));
+        assert_eq!(
+            calldata[4 + 5 * 32..4 + 6 * 32],
+            abi_word(layout.len as u64)
+        );
+        assert_eq!(calldata[4 + 6 * 32..4 + 6 * 32 + layout.len], bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_proof_rejects_lookups() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let table = builder.add_lookup_table_from_fn(|x| x * x, &[0, 1, 2, 3]);
+        let y = builder.add_lookup_from_index(x, table);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::TWO)?;
+        let proof = data.prove(pw)?;
+        assert!(encode_proof(&proof, &data.common).is_err());
+        Ok(())
+    }
+}
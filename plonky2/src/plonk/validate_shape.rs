@@ -1,5 +1,6 @@
 use anyhow::ensure;
 
+use crate::error::Error;
 use crate::field::extension::Extendable;
 use crate::hash::hash_types::RichField;
 use crate::plonk::circuit_data::CommonCircuitData;
@@ -21,7 +22,10 @@ where
     validate_proof_shape(proof, common_data)?;
     ensure!(
         public_inputs.len() == common_data.num_public_inputs,
-        "Number of public inputs doesn't match circuit data."
+        Error::PublicInputCount {
+            expected: common_data.num_public_inputs,
+            actual: public_inputs.len(),
+        }
     );
     Ok(())
 }
@@ -2,6 +2,8 @@
 use alloc::{format, vec, vec::Vec};
 use core::cmp::min;
 
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
 use plonky2_field::polynomial::PolynomialCoeffs;
 
 use super::circuit_builder::{LookupChallenges, NUM_COINS_LOOKUP};
@@ -159,6 +161,7 @@ pub(crate) fn eval_vanishing_poly<F: RichField + Extendable<D>, const D: usize>(
 /// Like `eval_vanishing_poly`, but specialized for base field points. Batched.
 pub(crate) fn eval_vanishing_poly_base_batch<F: RichField + Extendable<D>, const D: usize>(
     common_data: &CommonCircuitData<F, D>,
+    arena: &Bump,
     indices_batch: &[usize],
     xs_batch: &[F],
     vars_batch: EvaluationVarsBaseBatch<F>,
@@ -197,40 +200,44 @@ pub(crate) fn eval_vanishing_poly_base_batch<F: RichField + Extendable<D>, const
 
     let num_gate_constraints = common_data.num_gate_constraints;
 
-    let constraint_terms_batch =
-        evaluate_gate_constraints_base_batch::<F, D>(common_data, vars_batch);
-    debug_assert!(constraint_terms_batch.len() == n * num_gate_constraints);
+    let constraint_terms_batch = arena.alloc_slice_fill_copy(n * num_gate_constraints, F::ZERO);
+    evaluate_gate_constraints_base_batch::<F, D>(common_data, vars_batch, constraint_terms_batch);
 
     let num_challenges = common_data.config.num_challenges;
     let num_routed_wires = common_data.config.num_routed_wires;
 
-    let mut numerator_values = Vec::with_capacity(num_routed_wires);
-    let mut denominator_values = Vec::with_capacity(num_routed_wires);
+    let mut numerator_values = BumpVec::with_capacity_in(num_routed_wires, arena);
+    let mut denominator_values = BumpVec::with_capacity_in(num_routed_wires, arena);
 
     // The L_0(x) (Z(x) - 1) vanishing terms.
-    let mut vanishing_z_1_terms = Vec::with_capacity(num_challenges);
+    let mut vanishing_z_1_terms = BumpVec::with_capacity_in(num_challenges, arena);
     // The terms checking the partial products.
-    let mut vanishing_partial_products_terms = Vec::new();
+    let mut vanishing_partial_products_terms = BumpVec::new_in(arena);
 
     // The terms checking the lookup constraints.
     let mut vanishing_all_lookup_terms = if has_lookup {
         let num_sldc_polys = common_data.num_lookup_polys - 1;
-        Vec::with_capacity(
+        BumpVec::with_capacity_in(
             common_data.config.num_challenges * (4 + common_data.luts.len() + 2 * num_sldc_polys),
+            arena,
         )
     } else {
-        Vec::new()
+        BumpVec::new_in(arena)
     };
 
+    let mut lookup_selectors = BumpVec::with_capacity_in(common_data.num_lookup_selectors, arena);
+
     let mut res_batch: Vec<Vec<F>> = Vec::with_capacity(n);
     for k in 0..n {
         let index = indices_batch[k];
         let x = xs_batch[k];
         let vars = vars_batch.view(k);
 
-        let lookup_selectors: Vec<F> = (0..common_data.num_lookup_selectors)
-            .map(|i| vars.local_constants[common_data.selectors_info.num_selectors() + i])
-            .collect();
+        lookup_selectors.clear();
+        lookup_selectors.extend(
+            (0..common_data.num_lookup_selectors)
+                .map(|i| vars.local_constants[common_data.selectors_info.num_selectors() + i]),
+        );
 
         let local_zs = local_zs_batch[k];
         let next_zs = next_zs_batch[k];
@@ -249,7 +256,7 @@ pub(crate) fn eval_vanishing_poly_base_batch<F: RichField + Extendable<D>, const
         let partial_products = partial_products_batch[k];
         let s_sigmas = s_sigmas_batch[k];
 
-        let constraint_terms = PackedStridedView::new(&constraint_terms_batch, n, k);
+        let constraint_terms = PackedStridedView::new(constraint_terms_batch, n, k);
 
         let l_0_x = z_h_on_coset.eval_l_0(index, x);
         for i in 0..num_challenges {
@@ -689,14 +696,19 @@ pub fn evaluate_gate_constraints<F: RichField + Extendable<D>, const D: usize>(
 
 /// Evaluate all gate constraints in the base field.
 ///
-/// Returns a vector of `num_gate_constraints * vars_batch.len()` field elements. The constraints
-/// corresponding to `vars_batch[i]` are found in `result[i], result[vars_batch.len() + i],
-/// result[2 * vars_batch.len() + i], ...`.
+/// Accumulates into `constraints_batch`, which must be zero-initialized and hold
+/// `num_gate_constraints * vars_batch.len()` field elements. The constraints corresponding to
+/// `vars_batch[i]` are found in `constraints_batch[i], constraints_batch[vars_batch.len() + i],
+/// constraints_batch[2 * vars_batch.len() + i], ...`.
 pub fn evaluate_gate_constraints_base_batch<F: RichField + Extendable<D>, const D: usize>(
     common_data: &CommonCircuitData<F, D>,
     vars_batch: EvaluationVarsBaseBatch<F>,
-) -> Vec<F> {
-    let mut constraints_batch = vec![F::ZERO; common_data.num_gate_constraints * vars_batch.len()];
+    constraints_batch: &mut [F],
+) {
+    debug_assert_eq!(
+        constraints_batch.len(),
+        common_data.num_gate_constraints * vars_batch.len()
+    );
     for (i, gate) in common_data.gates.iter().enumerate() {
         let selector_index = common_data.selectors_info.selector_indices[i];
         let gate_constraints_batch = gate.0.eval_filtered_base_batch(
@@ -717,7 +729,6 @@ pub fn evaluate_gate_constraints_base_batch<F: RichField + Extendable<D>, const
             &gate_constraints_batch,
         );
     }
-    constraints_batch
 }
 
 pub fn evaluate_gate_constraints_circuit<F: RichField + Extendable<D>, const D: usize>(
@@ -798,6 +809,46 @@ pub(crate) fn eval_vanishing_poly_circuit<F: RichField + Extendable<D>, const D:
     gammas: &[Target],
     alphas: &[Target],
     deltas: &[Target],
+) -> Vec<ExtensionTarget<D>> {
+    let l_0_x = eval_l_0_circuit(builder, common_data.degree(), x, x_pow_deg);
+    eval_vanishing_poly_with_l_0_circuit(
+        builder,
+        common_data,
+        x,
+        l_0_x,
+        vars,
+        local_zs,
+        next_zs,
+        local_lookup_zs,
+        next_lookup_zs,
+        partial_products,
+        s_sigmas,
+        betas,
+        gammas,
+        alphas,
+        deltas,
+    )
+}
+
+/// Same as `eval_vanishing_poly_circuit`, except that `L_0(x)` is supplied by the caller. This is
+/// the only term which depends on the degree of the circuit, so it lets callers handle a degree
+/// which isn't known when the circuit is built.
+pub(crate) fn eval_vanishing_poly_with_l_0_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    common_data: &CommonCircuitData<F, D>,
+    x: ExtensionTarget<D>,
+    l_0_x: ExtensionTarget<D>,
+    vars: EvaluationTargets<D>,
+    local_zs: &[ExtensionTarget<D>],
+    next_zs: &[ExtensionTarget<D>],
+    local_lookup_zs: &[ExtensionTarget<D>],
+    next_lookup_zs: &[ExtensionTarget<D>],
+    partial_products: &[ExtensionTarget<D>],
+    s_sigmas: &[ExtensionTarget<D>],
+    betas: &[Target],
+    gammas: &[Target],
+    alphas: &[Target],
+    deltas: &[Target],
 ) -> Vec<ExtensionTarget<D>> {
     let has_lookup = common_data.num_lookup_polys != 0;
     let max_degree = common_data.quotient_degree_factor;
@@ -828,8 +879,6 @@ pub(crate) fn eval_vanishing_poly_circuit<F: RichField + Extendable<D>, const D:
     // The terms checking the partial products.
     let mut vanishing_partial_products_terms = Vec::new();
 
-    let l_0_x = eval_l_0_circuit(builder, common_data.degree(), x, x_pow_deg);
-
     // Holds `k[i] * x`.
     let mut s_ids = Vec::with_capacity(common_data.config.num_routed_wires);
     for j in 0..common_data.config.num_routed_wires {
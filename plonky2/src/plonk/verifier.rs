@@ -1,11 +1,16 @@
 //! plonky2 verifier implementation.
 
+#[cfg(not(feature = "std"))]
+use alloc::{format, vec::Vec};
+
 use anyhow::{ensure, Result};
+use plonky2_maybe_rayon::*;
 
 use crate::field::extension::Extendable;
 use crate::field::types::Field;
-use crate::fri::verifier::verify_fri_proof;
+use crate::fri::verifier::verify_fri_proof_with_cache;
 use crate::hash::hash_types::RichField;
+use crate::hash::merkle_proofs::MerkleVerificationCache;
 use crate::plonk::circuit_data::{CommonCircuitData, VerifierOnlyCircuitData};
 use crate::plonk::config::{GenericConfig, Hasher};
 use crate::plonk::plonk_common::reduce_with_powers;
@@ -37,6 +42,52 @@ pub(crate) fn verify<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, c
     )
 }
 
+/// Verifies several proofs of the same circuit. This is equivalent to verifying each of them, but
+/// the Merkle paths opening the preprocessed polynomials, which all proofs share, are only hashed
+/// up to the point where they join a path which was already verified.
+pub(crate) fn verify_batch<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    proofs_with_pis: &[ProofWithPublicInputs<F, C, D>],
+    verifier_data: &VerifierOnlyCircuitData<C, D>,
+    common_data: &CommonCircuitData<F, D>,
+) -> Result<()> {
+    let public_inputs_hashes_and_challenges = proofs_with_pis
+        .par_iter()
+        .map(|proof_with_pis| {
+            validate_proof_with_pis_shape(proof_with_pis, common_data)?;
+            let public_inputs_hash = proof_with_pis.get_public_inputs_hash();
+            let challenges = proof_with_pis.get_challenges(
+                public_inputs_hash,
+                &verifier_data.circuit_digest,
+                common_data,
+            )?;
+            Ok((public_inputs_hash, challenges))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut constants_sigmas_cache = MerkleVerificationCache::new();
+    for (i, (proof_with_pis, (public_inputs_hash, challenges))) in proofs_with_pis
+        .iter()
+        .zip(public_inputs_hashes_and_challenges)
+        .enumerate()
+    {
+        verify_with_challenges_and_cache::<F, C, D>(
+            &proof_with_pis.proof,
+            public_inputs_hash,
+            challenges,
+            verifier_data,
+            common_data,
+            Some(&mut constants_sigmas_cache),
+        )
+        .map_err(|e| e.context(format!("Proof {i} of the batch is invalid.")))?;
+    }
+
+    Ok(())
+}
+
 pub(crate) fn verify_with_challenges<
     F: RichField + Extendable<D>,
     C: GenericConfig<D, F = F>,
@@ -47,6 +98,28 @@ pub(crate) fn verify_with_challenges<
     challenges: ProofChallenges<F, D>,
     verifier_data: &VerifierOnlyCircuitData<C, D>,
     common_data: &CommonCircuitData<F, D>,
+) -> Result<()> {
+    verify_with_challenges_and_cache::<F, C, D>(
+        &proof,
+        public_inputs_hash,
+        challenges,
+        verifier_data,
+        common_data,
+        None,
+    )
+}
+
+fn verify_with_challenges_and_cache<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    proof: &Proof<F, C, D>,
+    public_inputs_hash: <<C as GenericConfig<D>>::InnerHasher as Hasher<F>>::Hash,
+    challenges: ProofChallenges<F, D>,
+    verifier_data: &VerifierOnlyCircuitData<C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    constants_sigmas_cache: Option<&mut MerkleVerificationCache<F, C::Hasher>>,
 ) -> Result<()> {
     let local_constants = &proof.openings.constants;
     let local_wires = &proof.openings.wires;
@@ -99,19 +172,20 @@ pub(crate) fn verify_with_challenges<
 
     let merkle_caps = &[
         verifier_data.constants_sigmas_cap.clone(),
-        proof.wires_cap,
+        proof.wires_cap.clone(),
         // In the lookup case, `plonk_zs_partial_products_cap` should also include the lookup commitment.
-        proof.plonk_zs_partial_products_cap,
-        proof.quotient_polys_cap,
+        proof.plonk_zs_partial_products_cap.clone(),
+        proof.quotient_polys_cap.clone(),
     ];
 
-    verify_fri_proof::<F, C, D>(
+    verify_fri_proof_with_cache::<F, C, D>(
         &common_data.get_fri_instance(challenges.plonk_zeta),
         &proof.openings.to_fri_openings(),
         &challenges.fri_challenges,
         merkle_caps,
         &proof.opening_proof,
         &common_data.fri_params,
+        constants_sigmas_cache,
     )?;
 
     Ok(())
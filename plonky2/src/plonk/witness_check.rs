@@ -0,0 +1,172 @@
+//! Checking a generated witness against the circuit's gate constraints before proving, to pinpoint
+//! unsatisfied constraints instead of producing a proof which fails to verify.
+
+#[cfg(not(feature = "std"))]
+use alloc::{format, string::String, vec::Vec};
+
+use anyhow::{anyhow, Result};
+
+use crate::field::extension::Extendable;
+use crate::gates::selectors::UNUSED_SELECTOR;
+use crate::hash::hash_types::RichField;
+use crate::iop::generator::generate_partial_witness;
+use crate::iop::witness::{PartialWitness, PartitionWitness, Witness};
+use crate::plonk::circuit_data::{CommonCircuitData, ProverOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, Hasher};
+use crate::plonk::prover::set_lookup_wires;
+use crate::plonk::vars::EvaluationVarsBaseBatch;
+
+/// Generates the witness for `inputs` and checks that it satisfies every gate constraint. See
+/// `check_partition_witness`.
+pub(crate) fn check_witness<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    inputs: &PartialWitness<F>,
+) -> Result<()> {
+    let partition_witness = generate_partial_witness(inputs.clone(), prover_data, common_data)?;
+    check_partition_witness(prover_data, common_data, partition_witness)
+}
+
+/// Evaluates each row's gate constraints on a generated witness. On failure, the error names the
+/// first unsatisfied constraint's gate, row and index, and the wires it involves along with their
+/// values.
+///
+/// Copy constraints hold by construction of the witness. The lookup argument isn't checked.
+pub(crate) fn check_partition_witness<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    prover_data: &ProverOnlyCircuitData<F, C, D>,
+    common_data: &CommonCircuitData<F, D>,
+    mut partition_witness: PartitionWitness<F>,
+) -> Result<()> {
+    set_lookup_wires(prover_data, common_data, &mut partition_witness)?;
+    let public_inputs = partition_witness.get_targets_checked(&prover_data.public_inputs)?;
+    let public_inputs_hash = C::InnerHasher::hash_no_pad(&public_inputs);
+    let wire_values = partition_witness.full_witness().wire_values;
+
+    let constant_values = prover_data.constants_sigmas_commitment.polynomials
+        [common_data.constants_range()]
+    .iter()
+    .map(|poly| poly.clone().fft().values)
+    .collect::<Vec<_>>();
+    let num_selectors = common_data.selectors_info.num_selectors();
+    let num_prefix_constants = num_selectors + common_data.num_lookup_selectors;
+
+    let mut first_failure = None;
+    let mut num_failures = 0;
+    for row in 0..common_data.degree() {
+        let Some(gate_index) = (0..num_selectors)
+            .map(|s| constant_values[s][row].to_canonical_u64() as usize)
+            .find(|&selector| selector != UNUSED_SELECTOR)
+        else {
+            continue;
+        };
+        let gate = &common_data.gates[gate_index].0;
+
+        let local_constants = constant_values[num_prefix_constants..]
+            .iter()
+            .map(|values| values[row])
+            .collect::<Vec<_>>();
+        let mut local_wires = wire_values
+            .iter()
+            .map(|values| values[row])
+            .collect::<Vec<_>>();
+        let eval = |local_wires: &[F]| {
+            gate.eval_unfiltered_base_batch(EvaluationVarsBaseBatch::new(
+                1,
+                &local_constants,
+                local_wires,
+                &public_inputs_hash,
+            ))
+        };
+
+        let constraints = eval(&local_wires);
+        for (i, &constraint) in constraints.iter().enumerate() {
+            if constraint.is_zero() {
+                continue;
+            }
+            num_failures += 1;
+            if first_failure.is_some() {
+                continue;
+            }
+
+            // A wire is involved in the constraint if changing it changes the constraint's value.
+            let mut involved_wires = Vec::new();
+            for column in 0..local_wires.len() {
+                let value = local_wires[column];
+                local_wires[column] = value + F::ONE;
+                if eval(&local_wires)[i] != constraint {
+                    involved_wires.push(format!("{column}: {value}"));
+                }
+                local_wires[column] = value;
+            }
+
+            let context = prover_data
+                .gate_context(row)
+                .map_or_else(String::new, |context| format!(" in {context}"));
+            first_failure = Some(format!(
+                "Constraint {i} of {} at row {row}{context} evaluates to {constraint} instead of \
+                 zero. Involved wires: [{}]",
+                gate.id(),
+                involved_wires.join(", ")
+            ));
+        }
+    }
+
+    match first_failure {
+        None => Ok(()),
+        Some(failure) => Err(anyhow!(
+            "{failure}. {num_failures} constraints are unsatisfied in total."
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::field::types::Sample;
+    use crate::iop::witness::WitnessWrite;
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_check_witness() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let x2 = builder.mul(x, x);
+        let x3 = builder.mul(x2, x);
+        builder.register_public_input(x3);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::rand())?;
+        data.check_witness(&pw)?;
+
+        // Tamper with the generated square, so that the first multiplication is unsatisfied.
+        let mut partition_witness = generate_partial_witness(pw, &data.prover_only, &data.common)?;
+        let x2_rep = partition_witness.representative_map[partition_witness.target_index(x2)];
+        partition_witness.values[x2_rep] = Some(F::rand());
+        let err = check_partition_witness(&data.prover_only, &data.common, partition_witness)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Constraint 0 of ArithmeticGate"), "{err}");
+        // The constraint involves the multiplicands, addend and output of the first operation.
+        assert!(err.contains("Involved wires: [0: "), "{err}");
+
+        Ok(())
+    }
+}
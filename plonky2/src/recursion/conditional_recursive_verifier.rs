@@ -8,7 +8,7 @@ use crate::fri::proof::{
     FriInitialTreeProofTarget, FriProofTarget, FriQueryRoundTarget, FriQueryStepTarget,
 };
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
-use crate::hash::hash_types::{HashOutTarget, MerkleCapTarget, RichField};
+use crate::hash::hash_types::{MerkleCapTarget, RichField};
 use crate::hash::merkle_proofs::MerkleProofTarget;
 use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::target::{BoolTarget, Target};
@@ -127,18 +127,6 @@ impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
             .collect()
     }
 
-    /// Computes `if b { h0 } else { h1 }`.
-    pub(crate) fn select_hash(
-        &mut self,
-        b: BoolTarget,
-        h0: HashOutTarget,
-        h1: HashOutTarget,
-    ) -> HashOutTarget {
-        HashOutTarget {
-            elements: core::array::from_fn(|i| self.select(b, h0.elements[i], h1.elements[i])),
-        }
-    }
-
     /// Computes `if b { cap0 } else { cap1 }`.
     fn select_cap(
         &mut self,
@@ -207,13 +207,11 @@ mod tests {
     use crate::field::extension::Extendable;
     use crate::field::types::{Field, PrimeField64};
     use crate::gates::noop::NoopGate;
-    use crate::hash::hash_types::{HashOutTarget, RichField};
-    use crate::hash::hashing::hash_n_to_hash_no_pad;
-    use crate::hash::poseidon::{PoseidonHash, PoseidonPermutation};
+    use crate::hash::hash_types::{HashOut, HashOutTarget, RichField};
     use crate::iop::witness::{PartialWitness, WitnessWrite};
     use crate::plonk::circuit_builder::CircuitBuilder;
     use crate::plonk::circuit_data::{CircuitConfig, CommonCircuitData};
-    use crate::plonk::config::{AlgebraicHasher, GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher, PoseidonGoldilocksConfig};
     use crate::recursion::cyclic_recursion::check_cyclic_proof_verifier_data;
     use crate::recursion::dummy_circuit::cyclic_base_proof;
 
@@ -269,8 +267,10 @@ mod tests {
         let initial_hash_target = builder.add_virtual_hash();
         builder.register_public_inputs(&initial_hash_target.elements);
         let current_hash_in = builder.add_virtual_hash();
-        let current_hash_out =
-            builder.hash_n_to_hash_no_pad::<PoseidonHash>(current_hash_in.elements.to_vec());
+        let current_hash_out = builder
+            .hash_n_to_hash_no_pad::<<C as GenericConfig<D>>::InnerHasher>(
+                current_hash_in.elements.to_vec(),
+            );
         builder.register_public_inputs(&current_hash_out.elements);
         let counter = builder.add_virtual_public_input();
 
@@ -359,7 +359,7 @@ mod tests {
         let initial_hash = &proof.public_inputs[..4];
         let hash = &proof.public_inputs[4..8];
         let counter = proof.public_inputs[8];
-        let expected_hash: [F; 4] = iterate_poseidon(
+        let expected_hash: [F; 4] = iterate_hash::<F, <C as GenericConfig<D>>::InnerHasher>(
             initial_hash.try_into().unwrap(),
             counter.to_canonical_u64() as usize,
         );
@@ -368,10 +368,13 @@ mod tests {
         cyclic_circuit_data.verify(proof)
     }
 
-    fn iterate_poseidon<F: RichField>(initial_state: [F; 4], n: usize) -> [F; 4] {
+    fn iterate_hash<F: RichField, H: Hasher<F, Hash = HashOut<F>>>(
+        initial_state: [F; 4],
+        n: usize,
+    ) -> [F; 4] {
         let mut current = initial_state;
         for _ in 0..n {
-            current = hash_n_to_hash_no_pad::<F, PoseidonPermutation<F>>(&current).elements;
+            current = H::hash_no_pad(&current).elements;
         }
         current
     }
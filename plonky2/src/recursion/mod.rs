@@ -3,8 +3,15 @@
 //! This module also provides ways to perform conditional recursive verification
 //! (between two different circuits, depending on a condition), and cyclic
 //! recursion where a circuit implements its own verification logic.
+//! Proofs of circuits differing only in their degree can also be verified by
+//! a single circuit, and [`pcd`] offers a proof-carrying data API on top of
+//! cyclic recursion. [`shrink`] plans chains of recursive steps making proofs
+//! smaller.
 
 pub mod conditional_recursive_verifier;
 pub mod cyclic_recursion;
 pub mod dummy_circuit;
+pub mod pcd;
 pub mod recursive_verifier;
+pub mod shrink;
+pub mod variable_degree_verifier;
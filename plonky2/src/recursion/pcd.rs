@@ -0,0 +1,397 @@
+//! Proof-carrying data (PCD) built on top of cyclic recursion.
+//!
+//! A [`PcdStep`] describes how a state is updated, given the states of up to `arity` predecessors.
+//! [`PcdCircuit`] wraps it into a cyclic circuit, so that a single proof attests to the whole
+//! history of a state, be it a chain (arity one) or a DAG of steps.
+//!
+//! Proofs only expose the hash of their state as public inputs, followed by the cyclic verifier
+//! data. The state itself travels next to the proof in a [`PcdProof`].
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use anyhow::{bail, ensure, Result};
+use hashbrown::HashMap;
+
+use crate::field::extension::Extendable;
+use crate::gates::constant::ConstantGate;
+use crate::gates::gate::GateRef;
+use crate::hash::hash_types::{HashOutTarget, RichField, NUM_HASH_OUT_ELTS};
+use crate::iop::generator::generate_partial_witness;
+use crate::iop::target::{BoolTarget, Target};
+use crate::iop::witness::{PartialWitness, Witness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{
+    CircuitConfig, CircuitData, CommonCircuitData, VerifierCircuitTarget,
+};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
+use crate::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+use crate::plonk::prover::prove_with_partition_witness;
+use crate::recursion::cyclic_recursion::check_cyclic_proof_verifier_data;
+use crate::recursion::dummy_circuit::cyclic_base_proof;
+use crate::util::timing::TimingTree;
+
+/// The number of attempts at finding common data which the cyclic circuit reproduces.
+const MAX_SHAPE_ITERATIONS: usize = 8;
+
+/// The logic of a single PCD step.
+pub trait PcdStep<F: RichField + Extendable<D>, const D: usize> {
+    /// Targets needed to fill in the step's private witness.
+    type Targets;
+    /// The step's private witness.
+    type Witness;
+
+    /// The number of field elements in a state.
+    fn state_len(&self) -> usize;
+
+    /// The number of predecessors of each step: one for a chain, more for a DAG.
+    fn arity(&self) -> usize {
+        1
+    }
+
+    /// The state standing in for absent predecessors, e.g. at the start of a chain.
+    fn initial_state(&self) -> Vec<F>;
+
+    /// Computes the new state from the states of the predecessors.
+    fn build(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        prev_states: &[Vec<Target>],
+    ) -> (Vec<Target>, Self::Targets);
+
+    fn set_witness(
+        &self,
+        pw: &mut PartialWitness<F>,
+        targets: &Self::Targets,
+        witness: &Self::Witness,
+    ) -> Result<()>;
+}
+
+/// A proof of a PCD step, along with the state it attests to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PcdProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub proof: ProofWithPublicInputs<F, C, D>,
+    pub state: Vec<F>,
+}
+
+#[derive(Debug)]
+struct PredecessorTargets<const D: usize> {
+    proof: ProofWithPublicInputsTarget<D>,
+    state: Vec<Target>,
+    is_present: BoolTarget,
+}
+
+#[derive(Debug)]
+struct PcdTargets<T, const D: usize> {
+    step: T,
+    state: Vec<Target>,
+    predecessors: Vec<PredecessorTargets<D>>,
+    verifier_data: VerifierCircuitTarget,
+}
+
+/// A cyclic circuit proving steps of `S`.
+#[derive(Debug)]
+pub struct PcdCircuit<F, C, S, const D: usize>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: PcdStep<F, D>,
+{
+    step: S,
+    data: CircuitData<F, C, D>,
+    targets: PcdTargets<S::Targets, D>,
+    /// Stands in for absent predecessors.
+    base_proof: ProofWithPublicInputs<F, C, D>,
+}
+
+impl<F, C, S, const D: usize> PcdCircuit<F, C, S, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F> + 'static,
+    C::Hasher: AlgebraicHasher<F>,
+    S: PcdStep<F, D>,
+{
+    /// Builds the cyclic circuit for `step`. Since the circuit verifies proofs of itself, its
+    /// common data is found by rebuilding it until it matches the common data it expects.
+    pub fn new(step: S, config: CircuitConfig) -> Result<Self> {
+        ensure!(step.arity() > 0, "PCD steps need at least one predecessor");
+        ensure!(
+            step.initial_state().len() == step.state_len(),
+            "Initial state length mismatch"
+        );
+
+        // Start from the shape of a circuit verifying a trivial proof, which is much closer to the
+        // fixed point than the shape of an empty circuit.
+        let trivial_common_data = CircuitBuilder::<F, D>::new(config.clone())
+            .build::<C>()
+            .common;
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let proof = builder.add_virtual_proof_with_pis(&trivial_common_data);
+        let verifier_data = builder.add_virtual_verifier_data(config.fri_config.cap_height);
+        builder.verify_proof::<C>(&proof, &verifier_data, &trivial_common_data);
+        // Dummy circuits for absent predecessors always contain a `ConstantGate`.
+        builder.add_gate_to_gate_set(GateRef::new(ConstantGate::new(config.num_constants)));
+        let mut common_data = builder.build::<C>().common;
+        common_data.num_public_inputs =
+            2 * NUM_HASH_OUT_ELTS + NUM_HASH_OUT_ELTS * config.fri_config.num_cap_elements();
+
+        for _ in 0..MAX_SHAPE_ITERATIONS {
+            let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+            let targets = Self::add_targets(&step, &mut builder, &common_data)?;
+            let (data, success) = builder.try_build_with_options::<C>(true)?;
+            if success {
+                let base_proof =
+                    cyclic_base_proof(&data.common, &data.verifier_only, HashMap::new());
+                return Ok(Self {
+                    step,
+                    data,
+                    targets,
+                    base_proof,
+                });
+            }
+            common_data = data.common;
+        }
+        bail!("The PCD circuit's common data did not converge")
+    }
+
+    fn add_targets(
+        step: &S,
+        builder: &mut CircuitBuilder<F, D>,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> Result<PcdTargets<S::Targets, D>> {
+        let initial_hash = C::InnerHasher::hash_no_pad(&step.initial_state());
+        let initial_hash = builder.constant_hash(initial_hash);
+
+        let prev_states = (0..step.arity())
+            .map(|_| builder.add_virtual_targets(step.state_len()))
+            .collect::<Vec<_>>();
+        let (state, step_targets) = step.build(builder, &prev_states);
+        ensure!(state.len() == step.state_len(), "State length mismatch");
+        let state_hash = builder.hash_n_to_hash_no_pad::<C::InnerHasher>(state.clone());
+        builder.register_public_inputs(&state_hash.elements);
+        let verifier_data = builder.add_verifier_data_public_inputs();
+
+        let mut predecessors = Vec::with_capacity(step.arity());
+        for prev_state in prev_states {
+            let proof = builder.add_virtual_proof_with_pis(common_data);
+            let is_present = builder.add_virtual_bool_target_safe();
+
+            // An absent predecessor contributes the initial state.
+            let proof_state_hash =
+                HashOutTarget::try_from(&proof.public_inputs[..NUM_HASH_OUT_ELTS]).unwrap();
+            let expected_hash = builder.select_hash(is_present, proof_state_hash, initial_hash);
+            let prev_state_hash =
+                builder.hash_n_to_hash_no_pad::<C::InnerHasher>(prev_state.clone());
+            builder.connect_hashes(prev_state_hash, expected_hash);
+
+            builder.conditionally_verify_cyclic_proof_or_dummy::<C>(
+                is_present,
+                &proof,
+                common_data,
+            )?;
+            predecessors.push(PredecessorTargets {
+                proof,
+                state: prev_state,
+                is_present,
+            });
+        }
+
+        Ok(PcdTargets {
+            step: step_targets,
+            state,
+            predecessors,
+            verifier_data,
+        })
+    }
+
+    pub fn step(&self) -> &S {
+        &self.step
+    }
+
+    pub fn circuit_data(&self) -> &CircuitData<F, C, D> {
+        &self.data
+    }
+
+    /// Proves a step on top of `prev_proofs`, which must hold `arity` entries. `None` entries
+    /// stand for absent predecessors, whose state is the initial state.
+    pub fn prove_step(
+        &self,
+        prev_proofs: &[Option<&PcdProof<F, C, D>>],
+        witness: &S::Witness,
+    ) -> Result<PcdProof<F, C, D>> {
+        ensure!(
+            prev_proofs.len() == self.step.arity(),
+            "Expected {} predecessors, got {}",
+            self.step.arity(),
+            prev_proofs.len()
+        );
+
+        let mut pw = PartialWitness::new();
+        let initial_state = self.step.initial_state();
+        for (targets, prev) in self.targets.predecessors.iter().zip(prev_proofs) {
+            let (proof, state) = match prev {
+                Some(prev) => (&prev.proof, &prev.state),
+                None => (&self.base_proof, &initial_state),
+            };
+            pw.set_bool_target(targets.is_present, prev.is_some())?;
+            pw.set_proof_with_pis_target(&targets.proof, proof)?;
+            pw.set_target_arr(&targets.state, state)?;
+        }
+        pw.set_verifier_data_target(&self.targets.verifier_data, &self.data.verifier_only)?;
+        self.step
+            .set_witness(&mut pw, &self.targets.step, witness)?;
+
+        let partition_witness =
+            generate_partial_witness(pw, &self.data.prover_only, &self.data.common)?;
+        let state = partition_witness.get_targets(&self.targets.state);
+        let proof = prove_with_partition_witness(
+            &self.data.prover_only,
+            &self.data.common,
+            partition_witness,
+            &mut TimingTree::default(),
+        )?;
+        Ok(PcdProof { proof, state })
+    }
+
+    /// Verifies a PCD proof, and thereby every step leading to its state.
+    pub fn verify_chain(&self, pcd_proof: &PcdProof<F, C, D>) -> Result<()> {
+        let public_inputs = &pcd_proof.proof.public_inputs;
+        ensure!(
+            public_inputs.len() == self.data.common.num_public_inputs,
+            "Wrong number of public inputs"
+        );
+        ensure!(
+            public_inputs[..NUM_HASH_OUT_ELTS]
+                == C::InnerHasher::hash_no_pad(&pcd_proof.state).elements,
+            "The state does not match the proof"
+        );
+        check_cyclic_proof_verifier_data(
+            &pcd_proof.proof,
+            &self.data.verifier_only,
+            &self.data.common,
+        )?;
+        self.data.verify(pcd_proof.proof.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    /// Counts the steps of a chain and sums the squares of their inputs.
+    #[derive(Debug)]
+    struct SumOfSquares;
+
+    impl PcdStep<F, D> for SumOfSquares {
+        type Targets = Target;
+        type Witness = F;
+
+        fn state_len(&self) -> usize {
+            2
+        }
+
+        fn initial_state(&self) -> Vec<F> {
+            vec![F::ZERO; 2]
+        }
+
+        fn build(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            prev_states: &[Vec<Target>],
+        ) -> (Vec<Target>, Target) {
+            let x = builder.add_virtual_target();
+            let count = builder.add_const(prev_states[0][0], F::ONE);
+            let acc = builder.mul_add(x, x, prev_states[0][1]);
+            (vec![count, acc], x)
+        }
+
+        fn set_witness(&self, pw: &mut PartialWitness<F>, x: &Target, value: &F) -> Result<()> {
+            pw.set_target(*x, *value)
+        }
+    }
+
+    /// Sums the inputs of every step in a binary tree.
+    #[derive(Debug)]
+    struct TreeSum;
+
+    impl PcdStep<F, D> for TreeSum {
+        type Targets = Target;
+        type Witness = F;
+
+        fn state_len(&self) -> usize {
+            1
+        }
+
+        fn arity(&self) -> usize {
+            2
+        }
+
+        fn initial_state(&self) -> Vec<F> {
+            vec![F::ZERO]
+        }
+
+        fn build(
+            &self,
+            builder: &mut CircuitBuilder<F, D>,
+            prev_states: &[Vec<Target>],
+        ) -> (Vec<Target>, Target) {
+            let x = builder.add_virtual_target();
+            let sum = builder.add_many([prev_states[0][0], prev_states[1][0], x]);
+            (vec![sum], x)
+        }
+
+        fn set_witness(&self, pw: &mut PartialWitness<F>, x: &Target, value: &F) -> Result<()> {
+            pw.set_target(*x, *value)
+        }
+    }
+
+    #[test]
+    fn test_pcd_chain() -> Result<()> {
+        let pcd = PcdCircuit::<F, C, _, D>::new(
+            SumOfSquares,
+            CircuitConfig::standard_recursion_config(),
+        )?;
+
+        let mut proof = pcd.prove_step(&[None], &F::ONE)?;
+        pcd.verify_chain(&proof)?;
+        for x in [2, 3] {
+            proof = pcd.prove_step(&[Some(&proof)], &F::from_canonical_u64(x))?;
+            pcd.verify_chain(&proof)?;
+        }
+        assert_eq!(
+            proof.state,
+            vec![F::from_canonical_u64(3), F::from_canonical_u64(14)]
+        );
+
+        let mut forged = proof;
+        forged.state[1] += F::ONE;
+        assert!(pcd.verify_chain(&forged).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcd_dag() -> Result<()> {
+        let pcd =
+            PcdCircuit::<F, C, _, D>::new(TreeSum, CircuitConfig::standard_recursion_config())?;
+
+        let left = pcd.prove_step(&[None, None], &F::ONE)?;
+        let right = pcd.prove_step(&[None, None], &F::TWO)?;
+        let root = pcd.prove_step(&[Some(&left), Some(&right)], &F::from_canonical_u64(3))?;
+        pcd.verify_chain(&root)?;
+        assert_eq!(root.state, vec![F::from_canonical_u64(6)]);
+
+        let unbalanced = pcd.prove_step(&[None, Some(&root)], &F::from_canonical_u64(4))?;
+        pcd.verify_chain(&unbalanced)?;
+        assert_eq!(unbalanced.state, vec![F::from_canonical_u64(10)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,344 @@
+//! Chains of recursive "shrink" steps, each verifying the previous proof in a smaller circuit, until
+//! proofs meet a target size or verification cost.
+//!
+//! Rather than tuning the configuration of each step by hand, a [`ShrinkPlanner`] tries a list of
+//! candidate configurations at each step, ordered from cheapest to prove to most compact, and keeps
+//! the first one meeting the target, or else the one producing the smallest circuit.
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use anyhow::{ensure, Result};
+use log::info;
+
+use crate::field::extension::Extendable;
+use crate::fri::reduction_strategies::FriReductionStrategy;
+use crate::fri::{FriConfig, FriSecurity};
+use crate::hash::hash_types::RichField;
+use crate::iop::witness::{PartialWitness, WitnessWrite};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{
+    CircuitConfig, CircuitData, CommonCircuitData, VerifierOnlyCircuitData,
+};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig};
+use crate::plonk::proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget};
+
+/// The number of shrink steps after which [`ShrinkPlanner::plan`] gives up by default.
+const DEFAULT_MAX_STEPS: usize = 4;
+
+/// The limits which the last proof of a shrink chain must meet. Unset limits are ignored.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShrinkTarget {
+    /// The maximum length of a serialized proof, in bytes.
+    pub max_proof_bytes: Option<usize>,
+    /// The maximum degree of the last circuit, in bits. This bounds the cost of verifying its
+    /// proofs, natively or in another circuit.
+    pub max_degree_bits: Option<usize>,
+}
+
+impl ShrinkTarget {
+    fn is_met_by<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+        &self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> bool {
+        self.max_proof_bytes
+            .is_none_or(|max| common_data.proof_size::<C::Hasher>() <= max)
+            && self
+                .max_degree_bits
+                .is_none_or(|max| common_data.degree_bits() <= max)
+    }
+}
+
+/// Selects the configurations of a chain of shrink steps reaching a [`ShrinkTarget`], and builds
+/// their circuits.
+#[derive(Clone, Debug)]
+pub struct ShrinkPlanner {
+    target: ShrinkTarget,
+    candidates: Vec<CircuitConfig>,
+    max_steps: usize,
+}
+
+impl ShrinkPlanner {
+    pub fn new(target: ShrinkTarget) -> Self {
+        Self {
+            target,
+            candidates: Self::default_candidates(),
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+
+    /// Variants of [`CircuitConfig::standard_recursion_config`] with rates of `1/8`, `1/32` and
+    /// `1/128`, each with as many FRI queries as needed to keep its security level. Higher rates
+    /// give shorter proofs, which are cheaper to verify, but take longer to generate.
+    pub fn default_candidates() -> Vec<CircuitConfig> {
+        const PROOF_OF_WORK_BITS: usize = 16;
+
+        let standard_config = CircuitConfig::standard_recursion_config();
+        let query_bits = standard_config.security_bits - PROOF_OF_WORK_BITS;
+        let high_rate_config = |rate_bits| CircuitConfig {
+            fri_config: FriConfig {
+                rate_bits,
+                proof_of_work_bits: PROOF_OF_WORK_BITS as u32,
+                reduction_strategy: FriReductionStrategy::MinSize(None),
+                num_query_rounds: FriSecurity::ConjecturedListDecoding
+                    .num_query_rounds(rate_bits, query_bits),
+                ..standard_config.fri_config.clone()
+            },
+            ..standard_config.clone()
+        };
+        vec![
+            standard_config.clone(),
+            high_rate_config(5),
+            high_rate_config(7),
+        ]
+    }
+
+    /// Replaces the candidate configurations, which should be ordered from cheapest to prove to
+    /// most compact. Their cap heights are ignored, as each step picks the optimal one.
+    pub fn with_candidates(self, candidates: Vec<CircuitConfig>) -> Self {
+        Self { candidates, ..self }
+    }
+
+    pub fn with_max_steps(self, max_steps: usize) -> Self {
+        Self { max_steps, ..self }
+    }
+
+    /// Plans and builds a chain of shrink steps for proofs of the circuit described by
+    /// `inner_common_data` and `inner_verifier_data`. The chain is empty if these proofs already
+    /// meet the target.
+    ///
+    /// Each step verifies proofs of the previous circuit, so it is built once per candidate. Fails
+    /// if the steps stop getting smaller, or if the target isn't met within the maximum number of
+    /// steps.
+    pub fn plan<F, C, const D: usize>(
+        &self,
+        inner_common_data: &CommonCircuitData<F, D>,
+        inner_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Result<ShrinkChain<F, C, D>>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        ensure!(!self.candidates.is_empty(), "No candidate configurations");
+
+        // Steps are ranked by the degree of their circuit, then by the size of their proofs.
+        let rank = |common_data: &CommonCircuitData<F, D>| {
+            (
+                common_data.degree_bits(),
+                common_data.proof_size::<C::Hasher>(),
+            )
+        };
+
+        let mut steps: Vec<ShrinkStep<F, C, D>> = Vec::new();
+        loop {
+            let (common_data, verifier_data) = match steps.last() {
+                Some(step) => (&step.data.common, &step.data.verifier_only),
+                None => (inner_common_data, inner_verifier_data),
+            };
+            let (degree_bits, proof_size) = rank(common_data);
+            if self.target.is_met_by::<F, C, D>(common_data) {
+                return Ok(ShrinkChain { steps });
+            }
+            ensure!(
+                steps.len() < self.max_steps,
+                "The target was not met within {} shrink steps; the last proofs are {} bytes long, \
+                 for a circuit of degree 2^{}",
+                self.max_steps,
+                proof_size,
+                degree_bits
+            );
+
+            let mut best: Option<ShrinkStep<F, C, D>> = None;
+            for config in &self.candidates {
+                let step = ShrinkStep::new(config, common_data, verifier_data);
+                if self.target.is_met_by::<F, C, D>(&step.data.common) {
+                    best = Some(step);
+                    break;
+                }
+                if best
+                    .as_ref()
+                    .is_none_or(|best| rank(&step.data.common) < rank(&best.data.common))
+                {
+                    best = Some(step);
+                }
+            }
+            let step = best.expect("No candidates");
+            let (step_degree_bits, step_proof_size) = rank(&step.data.common);
+            ensure!(
+                (step_degree_bits, step_proof_size) < (degree_bits, proof_size),
+                "Shrink step {} does not make proofs smaller than {} bytes, for a circuit of \
+                 degree 2^{}",
+                steps.len(),
+                proof_size,
+                degree_bits
+            );
+            let fri_config = &step.data.common.config.fri_config;
+            info!(
+                step = steps.len(),
+                rate_bits = fri_config.rate_bits,
+                cap_height = fri_config.cap_height,
+                num_query_rounds = fri_config.num_query_rounds,
+                degree_bits = step_degree_bits,
+                proof_size = step_proof_size;
+                "Shrink step {}: rate bits {}, cap height {}, {} queries, giving a circuit of \
+                 degree 2^{} with {}-byte proofs",
+                steps.len(),
+                fri_config.rate_bits,
+                fri_config.cap_height,
+                fri_config.num_query_rounds,
+                step_degree_bits,
+                step_proof_size
+            );
+            steps.push(step);
+        }
+    }
+}
+
+/// A circuit verifying proofs of a fixed inner circuit, and forwarding their public inputs.
+#[derive(Debug)]
+pub struct ShrinkStep<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    pub data: CircuitData<F, C, D>,
+    inner_proof: ProofWithPublicInputsTarget<D>,
+}
+
+impl<F, C, const D: usize> ShrinkStep<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    fn new(
+        config: &CircuitConfig,
+        inner_common_data: &CommonCircuitData<F, D>,
+        inner_verifier_data: &VerifierOnlyCircuitData<C, D>,
+    ) -> Self {
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        builder.set_auto_cap_height();
+        let inner_proof = builder.add_virtual_proof_with_pis(inner_common_data);
+        let verifier_data = builder.constant_verifier_data(inner_verifier_data);
+        builder.verify_proof::<C>(&inner_proof, &verifier_data, inner_common_data);
+        builder.register_public_inputs(&inner_proof.public_inputs);
+        Self {
+            data: builder.build::<C>(),
+            inner_proof,
+        }
+    }
+
+    /// Proves that `inner_proof` is valid.
+    pub fn prove(
+        &self,
+        inner_proof: &ProofWithPublicInputs<F, C, D>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&self.inner_proof, inner_proof)?;
+        self.data.prove(pw)
+    }
+}
+
+/// A chain of shrink steps, planned by a [`ShrinkPlanner`].
+#[derive(Debug)]
+pub struct ShrinkChain<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    steps: Vec<ShrinkStep<F, C, D>>,
+}
+
+impl<F, C, const D: usize> ShrinkChain<F, C, D>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    C::Hasher: AlgebraicHasher<F>,
+{
+    pub fn steps(&self) -> &[ShrinkStep<F, C, D>] {
+        &self.steps
+    }
+
+    /// The circuit of the last step, whose proofs meet the target, or `None` if the chain is
+    /// empty.
+    pub fn last_circuit(&self) -> Option<&CircuitData<F, C, D>> {
+        self.steps.last().map(|step| &step.data)
+    }
+
+    /// Runs `proof`, a proof of the inner circuit, through every step of the chain. The resulting
+    /// proof has the same public inputs.
+    pub fn prove(
+        &self,
+        proof: ProofWithPublicInputs<F, C, D>,
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
+        self.steps
+            .iter()
+            .try_fold(proof, |proof, step| step.prove(&proof))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::types::Field;
+    use crate::gates::noop::NoopGate;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_shrink_chain() -> Result<()> {
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        // Pad the circuit to 2^13 rows.
+        for _ in 0..5_000 {
+            builder.add_gate(NoopGate, vec![]);
+        }
+        let inner = builder.build::<C>();
+        assert_eq!(inner.common.degree_bits(), 13);
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3))?;
+        let proof = inner.prove(pw)?;
+        assert_eq!(
+            proof.to_bytes().len(),
+            inner.common.proof_size::<<C as GenericConfig<D>>::Hasher>()
+        );
+
+        // Both candidates shrink the circuit to 2^12 rows, but proofs of the standard one are too
+        // large, so the planner has to pick the high-rate one.
+        let candidates = ShrinkPlanner::default_candidates();
+        let target = ShrinkTarget {
+            max_proof_bytes: Some(100_000),
+            max_degree_bits: Some(12),
+        };
+        let chain = ShrinkPlanner::new(target.clone())
+            .with_candidates(vec![candidates[0].clone(), candidates[2].clone()])
+            .with_max_steps(1)
+            .plan(&inner.common, &inner.verifier_only)?;
+        assert_eq!(chain.steps().len(), 1);
+
+        let last_circuit = chain.last_circuit().unwrap();
+        assert_eq!(last_circuit.common.config.fri_config.rate_bits, 7);
+        let shrunk_proof = chain.prove(proof)?;
+        assert_eq!(
+            shrunk_proof.public_inputs,
+            [F::from_canonical_u64(3), F::from_canonical_u64(9)]
+        );
+        assert_eq!(
+            shrunk_proof.to_bytes().len(),
+            last_circuit
+                .common
+                .proof_size::<<C as GenericConfig<D>>::Hasher>()
+        );
+        assert!(target.is_met_by::<F, C, D>(&last_circuit.common));
+        last_circuit.verify(shrunk_proof)
+    }
+
+    #[test]
+    fn test_shrink_target_already_met() -> Result<()> {
+        let builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let inner = builder.build::<C>();
+        let chain = ShrinkPlanner::new(ShrinkTarget::default())
+            .plan(&inner.common, &inner.verifier_only)?;
+        assert!(chain.steps().is_empty());
+        assert!(chain.last_circuit().is_none());
+        Ok(())
+    }
+}
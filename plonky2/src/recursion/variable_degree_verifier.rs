@@ -0,0 +1,253 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::field::extension::Extendable;
+use crate::hash::hash_types::RichField;
+use crate::iop::target::{BoolTarget, Target};
+use crate::plonk::circuit_builder::CircuitBuilder;
+use crate::plonk::circuit_data::{CommonCircuitData, VerifierCircuitTarget};
+use crate::plonk::config::{AlgebraicHasher, GenericConfig};
+use crate::plonk::plonk_common::eval_l_0_circuit;
+use crate::plonk::proof::ProofWithPublicInputsTarget;
+use crate::plonk::vanishing_poly::eval_vanishing_poly_with_l_0_circuit;
+use crate::plonk::vars::EvaluationTargets;
+use crate::util::reducing::ReducingFactorTarget;
+use crate::with_context;
+
+impl<F: RichField + Extendable<D>, const D: usize> CircuitBuilder<F, D> {
+    /// Recursively verifies an inner proof generated by one of several circuits which only differ
+    /// in their degree, such as the same logic padded to different sizes. `inner_degree_bits`
+    /// holds the `degree_bits` of the circuit which generated the proof, and `inner_common_data`
+    /// holds the common data of every allowed circuit.
+    ///
+    /// `proof_with_pis` must be shaped for the largest circuit, i.e. created with
+    /// `add_virtual_proof_with_pis` on its common data, and proofs of smaller circuits should be
+    /// set with `set_padded_proof_with_pis_target`. All circuits must use the same FRI reduction
+    /// arities, e.g. through `FriReductionStrategy::Fixed`.
+    pub fn verify_proof_with_variable_degree<C: GenericConfig<D, F = F>>(
+        &mut self,
+        proof_with_pis: &ProofWithPublicInputsTarget<D>,
+        inner_degree_bits: Target,
+        inner_verifier_data: &VerifierCircuitTarget,
+        inner_common_data: &[CommonCircuitData<F, D>],
+    ) where
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        let max_common_data = inner_common_data
+            .iter()
+            .max_by_key(|cd| cd.degree_bits())
+            .expect("No inner common data given");
+        for (i, cd) in inner_common_data.iter().enumerate() {
+            let mut normalized = cd.clone();
+            normalized.fri_params.degree_bits = max_common_data.degree_bits();
+            assert_eq!(
+                &normalized, max_common_data,
+                "Inner circuits must only differ in their degree."
+            );
+            assert!(
+                inner_common_data[..i]
+                    .iter()
+                    .all(|other| other.degree_bits() != cd.degree_bits()),
+                "Inner circuits must have distinct degrees."
+            );
+        }
+        assert_eq!(
+            proof_with_pis.public_inputs.len(),
+            max_common_data.num_public_inputs
+        );
+
+        // Exactly one of the allowed degrees is selected.
+        let flags = inner_common_data
+            .iter()
+            .map(|cd| {
+                let degree_bits = self.constant(F::from_canonical_usize(cd.degree_bits()));
+                self.is_equal(inner_degree_bits, degree_bits)
+            })
+            .collect::<Vec<_>>();
+        let num_selected = self.add_many(flags.iter().map(|b| b.target));
+        self.assert_one(num_selected);
+
+        let public_inputs_hash =
+            self.hash_n_to_hash_no_pad::<C::InnerHasher>(proof_with_pis.public_inputs.clone());
+        let final_poly_lens = flags
+            .iter()
+            .zip(inner_common_data)
+            .map(|(&flag, cd)| (flag, cd.fri_params.final_poly_len()))
+            .collect::<Vec<_>>();
+        let challenges = proof_with_pis.get_challenges_with_final_poly_lens::<F, C>(
+            self,
+            public_inputs_hash,
+            inner_verifier_data.circuit_digest,
+            max_common_data,
+            &final_poly_lens,
+        );
+
+        let proof = &proof_with_pis.proof;
+        let one = self.one_extension();
+        let zeta = challenges.plonk_zeta;
+
+        let local_constants = &proof.openings.constants;
+        let local_wires = &proof.openings.wires;
+        let vars = EvaluationTargets {
+            local_constants,
+            local_wires,
+            public_inputs_hash: &public_inputs_hash,
+        };
+        let local_zs = &proof.openings.plonk_zs;
+        let next_zs = &proof.openings.plonk_zs_next;
+        let local_lookup_zs = &proof.openings.lookup_zs;
+        let next_lookup_zs = &proof.openings.next_lookup_zs;
+        let s_sigmas = &proof.openings.plonk_sigmas;
+        let partial_products = &proof.openings.partial_products;
+
+        // Compute the degree dependent values for each candidate degree, then select ours.
+        let (zeta_pow_deg, l_0_zeta, zeta_next) =
+            with_context!(self, "compute degree dependent values", {
+                let mut zeta_pow_degs = Vec::with_capacity(inner_common_data.len());
+                let mut l_0_zetas = Vec::with_capacity(inner_common_data.len());
+                let mut zeta_nexts = Vec::with_capacity(inner_common_data.len());
+                for cd in inner_common_data {
+                    let zeta_pow_deg = self.exp_power_of_2_extension(zeta, cd.degree_bits());
+                    l_0_zetas.push(eval_l_0_circuit(self, cd.degree(), zeta, zeta_pow_deg));
+                    zeta_pow_degs.push(zeta_pow_deg);
+                    let g = F::primitive_root_of_unity(cd.degree_bits());
+                    zeta_nexts.push(self.mul_const_extension(g, zeta));
+                }
+                (
+                    self.select_one_hot_ext(&flags, &zeta_pow_degs),
+                    self.select_one_hot_ext(&flags, &l_0_zetas),
+                    self.select_one_hot_ext(&flags, &zeta_nexts),
+                )
+            });
+
+        let vanishing_polys_zeta = with_context!(
+            self,
+            "evaluate the vanishing polynomial at our challenge point, zeta.",
+            eval_vanishing_poly_with_l_0_circuit::<F, D>(
+                self,
+                max_common_data,
+                zeta,
+                l_0_zeta,
+                vars,
+                local_zs,
+                next_zs,
+                local_lookup_zs,
+                next_lookup_zs,
+                partial_products,
+                s_sigmas,
+                &challenges.plonk_betas,
+                &challenges.plonk_gammas,
+                &challenges.plonk_alphas,
+                &challenges.plonk_deltas,
+            )
+        );
+
+        with_context!(self, "check vanishing and quotient polynomials.", {
+            let quotient_polys_zeta = &proof.openings.quotient_polys;
+            let mut scale = ReducingFactorTarget::new(zeta_pow_deg);
+            let z_h_zeta = self.sub_extension(zeta_pow_deg, one);
+            for (i, chunk) in quotient_polys_zeta
+                .chunks(max_common_data.quotient_degree_factor)
+                .enumerate()
+            {
+                let recombined_quotient = scale.reduce(chunk, self);
+                let computed_vanishing_poly = self.mul_extension(z_h_zeta, recombined_quotient);
+                self.connect_extension(vanishing_polys_zeta[i], computed_vanishing_poly);
+            }
+        });
+
+        let merkle_caps = &[
+            inner_verifier_data.constants_sigmas_cap.clone(),
+            proof.wires_cap.clone(),
+            proof.plonk_zs_partial_products_cap.clone(),
+            proof.quotient_polys_cap.clone(),
+        ];
+
+        let fri_instance = max_common_data.get_fri_instance_target_with_zeta_next(zeta, zeta_next);
+        let fri_params = flags
+            .iter()
+            .zip(inner_common_data)
+            .map(|(&flag, cd)| (flag, cd.fri_params.clone()))
+            .collect::<Vec<(BoolTarget, _)>>();
+        with_context!(
+            self,
+            "verify FRI proof",
+            self.verify_fri_proof_with_degree_flags::<C>(
+                &fri_instance,
+                &proof.openings.to_fri_openings(),
+                &challenges.fri_challenges,
+                merkle_caps,
+                &proof.opening_proof,
+                &fri_params,
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::gates::noop::NoopGate;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_data::{CircuitConfig, CircuitData};
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_verify_proof_with_variable_degree() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        let config = CircuitConfig::standard_recursion_config();
+
+        // The same logic, padded to 2^12 and 2^13 rows.
+        let inner_circuit = |num_dummy_gates: usize| {
+            let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+            let x = builder.add_virtual_public_input();
+            let y = builder.square(x);
+            builder.register_public_input(y);
+            for _ in 0..num_dummy_gates {
+                builder.add_gate(NoopGate, vec![]);
+            }
+            (builder.build::<C>(), x)
+        };
+        let inner: Vec<(CircuitData<F, C, D>, Target)> =
+            vec![inner_circuit(3_000), inner_circuit(6_000)];
+        assert_eq!(inner[0].0.common.degree_bits(), 12);
+        assert_eq!(inner[1].0.common.degree_bits(), 13);
+        let inner_common_data = inner
+            .iter()
+            .map(|(data, _)| data.common.clone())
+            .collect::<Vec<_>>();
+
+        let mut builder = CircuitBuilder::<F, D>::new(config.clone());
+        let pt = builder.add_virtual_proof_with_pis(&inner_common_data[1]);
+        let degree_bits = builder.add_virtual_target();
+        let vd = builder.add_virtual_verifier_data(config.fri_config.cap_height);
+        builder.verify_proof_with_variable_degree::<C>(&pt, degree_bits, &vd, &inner_common_data);
+        let outer = builder.build::<C>();
+
+        for (data, x) in &inner {
+            let mut pw = PartialWitness::new();
+            pw.set_target(*x, F::from_canonical_u64(3))?;
+            let proof = data.prove(pw)?;
+
+            let mut pw = PartialWitness::new();
+            pw.set_padded_proof_with_pis_target(&pt, &proof)?;
+            pw.set_target(
+                degree_bits,
+                F::from_canonical_usize(data.common.degree_bits()),
+            )?;
+            pw.set_verifier_data_target(&vd, &data.verifier_only)?;
+            let outer_proof = outer.prove(pw)?;
+            outer.verify(outer_proof)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,457 @@
+//! SCALE (`parity-scale-codec`) encodings of proofs and verifier data, enabled by the `scale`
+//! feature, so that Substrate pallets and Gear programs can pass them around natively.
+//!
+//! Proofs are encoded field by field: field elements as little-endian `u64`s, hashes as their
+//! bytes, and vectors with a compact length prefix. The common circuit data is encoded as its
+//! `CommonCircuitData::to_bytes` form, with the default gate serializer, so circuits with custom
+//! gates aren't supported.
+//!
+//! Decoding never allocates more than the input can hold: a vector's length prefix is checked
+//! against the remaining input before its elements are read.
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use parity_scale_codec::{Compact, Decode, DecodeAll, Encode, Error, Input, Output};
+
+use crate::field::extension::{Extendable, FieldExtension};
+use crate::field::polynomial::PolynomialCoeffs;
+use crate::field::types::{Field64, PrimeField64};
+use crate::fri::proof::{FriInitialTreeProof, FriProof, FriQueryRound, FriQueryStep};
+use crate::hash::hash_types::RichField;
+use crate::hash::merkle_proofs::MerkleProof;
+use crate::hash::merkle_tree::MerkleCap;
+use crate::plonk::circuit_data::{CommonCircuitData, VerifierCircuitData, VerifierOnlyCircuitData};
+use crate::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use crate::plonk::proof::{OpeningSet, Proof, ProofWithPublicInputs};
+use crate::util::serialization::DefaultGateSerializer;
+
+/// The reason [`verify_for_runtime`] rejected its input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RuntimeVerifyError {
+    /// The verifier data couldn't be decoded.
+    InvalidVerifierData,
+    /// The proof couldn't be decoded.
+    InvalidProof,
+    /// The proof is invalid.
+    VerificationFailed,
+}
+
+impl fmt::Display for RuntimeVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidVerifierData => write!(f, "Invalid verifier data"),
+            Self::InvalidProof => write!(f, "Invalid proof encoding"),
+            Self::VerificationFailed => write!(f, "Proof verification failed"),
+        }
+    }
+}
+
+/// Verifies a SCALE-encoded proof against SCALE-encoded [`VerifierCircuitData`], returning the
+/// proof's public inputs.
+///
+/// Both inputs must be consumed entirely. The proof's shape is checked against the circuit before
+/// it's verified, so malformed proofs are rejected without panicking.
+pub fn verify_for_runtime<F, C, const D: usize>(
+    verifier_data: &[u8],
+    proof: &[u8],
+) -> Result<Vec<F>, RuntimeVerifyError>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+{
+    let verifier_data = VerifierCircuitData::<F, C, D>::decode_all(&mut &*verifier_data)
+        .map_err(|_| RuntimeVerifyError::InvalidVerifierData)?;
+    let proof = ProofWithPublicInputs::<F, C, D>::decode_all(&mut &*proof)
+        .map_err(|_| RuntimeVerifyError::InvalidProof)?;
+    let public_inputs = proof.public_inputs.clone();
+    verifier_data
+        .verify(proof)
+        .map_err(|_| RuntimeVerifyError::VerificationFailed)?;
+    Ok(public_inputs)
+}
+
+fn encode_field<F: PrimeField64, T: Output + ?Sized>(x: &F, dest: &mut T) {
+    x.to_canonical_u64().encode_to(dest);
+}
+
+fn decode_field<F: Field64, I: Input>(input: &mut I) -> Result<F, Error> {
+    let x = u64::decode(input)?;
+    if x >= F::ORDER {
+        return Err("Non-canonical field element".into());
+    }
+    Ok(F::from_canonical_u64(x))
+}
+
+fn encode_ext<F: RichField + Extendable<D>, const D: usize, T: Output + ?Sized>(
+    x: &F::Extension,
+    dest: &mut T,
+) {
+    for c in x.to_basefield_array() {
+        encode_field(&c, dest);
+    }
+}
+
+fn decode_ext<F: RichField + Extendable<D>, const D: usize, I: Input>(
+    input: &mut I,
+) -> Result<F::Extension, Error> {
+    let mut arr = [F::ZERO; D];
+    for c in &mut arr {
+        *c = decode_field(input)?;
+    }
+    Ok(F::Extension::from_basefield_array(arr))
+}
+
+fn encode_hash<F: RichField, H: Hasher<F>, T: Output + ?Sized>(hash: &H::Hash, dest: &mut T) {
+    dest.write(&hash.to_bytes());
+}
+
+fn decode_hash<F: RichField, H: Hasher<F>, I: Input>(input: &mut I) -> Result<H::Hash, Error> {
+    let mut bytes = vec![0; H::HASH_SIZE];
+    input.read(&mut bytes)?;
+    Ok(H::Hash::from_bytes(&bytes))
+}
+
+fn encode_vec<X, T: Output + ?Sized>(xs: &[X], dest: &mut T, mut encode: impl FnMut(&X, &mut T)) {
+    Compact(xs.len() as u32).encode_to(dest);
+    for x in xs {
+        encode(x, dest);
+    }
+}
+
+/// Decodes a vector whose elements are encoded in at least `min_item_len` bytes each.
+fn decode_vec<X, I: Input>(
+    input: &mut I,
+    min_item_len: usize,
+    mut decode: impl FnMut(&mut I) -> Result<X, Error>,
+) -> Result<Vec<X>, Error> {
+    let len = <Compact<u32>>::decode(input)?.0 as usize;
+    if let Some(remaining) = input.remaining_len()? {
+        if len.saturating_mul(min_item_len) > remaining {
+            return Err("Vector length exceeds the remaining input".into());
+        }
+    }
+    let mut xs = Vec::with_capacity(len);
+    for _ in 0..len {
+        xs.push(decode(input)?);
+    }
+    Ok(xs)
+}
+
+fn encode_exts<F: RichField + Extendable<D>, const D: usize, T: Output + ?Sized>(
+    xs: &[F::Extension],
+    dest: &mut T,
+) {
+    encode_vec(xs, dest, encode_ext::<F, D, T>);
+}
+
+fn decode_exts<F: RichField + Extendable<D>, const D: usize, I: Input>(
+    input: &mut I,
+) -> Result<Vec<F::Extension>, Error> {
+    decode_vec(input, 8 * D, decode_ext::<F, D, I>)
+}
+
+impl<F: RichField, H: Hasher<F>> Encode for MerkleCap<F, H> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        encode_vec(&self.0, dest, encode_hash::<F, H, T>);
+    }
+}
+
+impl<F: RichField, H: Hasher<F>> Decode for MerkleCap<F, H> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        decode_vec(input, H::HASH_SIZE, decode_hash::<F, H, I>).map(MerkleCap)
+    }
+}
+
+impl<F: RichField, H: Hasher<F>> Encode for MerkleProof<F, H> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        encode_vec(&self.siblings, dest, encode_hash::<F, H, T>);
+    }
+}
+
+impl<F: RichField, H: Hasher<F>> Decode for MerkleProof<F, H> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let siblings = decode_vec(input, H::HASH_SIZE, decode_hash::<F, H, I>)?;
+        Ok(Self { siblings })
+    }
+}
+
+impl<F: RichField, H: Hasher<F>> Encode for FriInitialTreeProof<F, H> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        encode_vec(&self.evals_proofs, dest, |(evals, proof), dest| {
+            encode_vec(evals, dest, encode_field);
+            proof.encode_to(dest);
+        });
+    }
+}
+
+impl<F: RichField, H: Hasher<F>> Decode for FriInitialTreeProof<F, H> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let evals_proofs = decode_vec(input, 2, |input| {
+            let evals = decode_vec(input, 8, decode_field)?;
+            Ok((evals, MerkleProof::decode(input)?))
+        })?;
+        Ok(Self { evals_proofs })
+    }
+}
+
+impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> Encode for FriQueryStep<F, H, D> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        encode_exts::<F, D, T>(&self.evals, dest);
+        self.merkle_proof.encode_to(dest);
+    }
+}
+
+impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> Decode for FriQueryStep<F, H, D> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            evals: decode_exts::<F, D, I>(input)?,
+            merkle_proof: MerkleProof::decode(input)?,
+        })
+    }
+}
+
+impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> Encode for FriQueryRound<F, H, D> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.initial_trees_proof.encode_to(dest);
+        encode_vec(&self.steps, dest, |step, dest| step.encode_to(dest));
+    }
+}
+
+impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> Decode for FriQueryRound<F, H, D> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            initial_trees_proof: FriInitialTreeProof::decode(input)?,
+            steps: decode_vec(input, 2, FriQueryStep::decode)?,
+        })
+    }
+}
+
+impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> Encode for FriProof<F, H, D> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        encode_vec(&self.commit_phase_merkle_caps, dest, |cap, dest| {
+            cap.encode_to(dest)
+        });
+        encode_vec(&self.query_round_proofs, dest, |round, dest| {
+            round.encode_to(dest)
+        });
+        encode_exts::<F, D, T>(&self.final_poly.coeffs, dest);
+        encode_field(&self.pow_witness, dest);
+    }
+}
+
+impl<F: RichField + Extendable<D>, H: Hasher<F>, const D: usize> Decode for FriProof<F, H, D> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            commit_phase_merkle_caps: decode_vec(input, 1, MerkleCap::decode)?,
+            query_round_proofs: decode_vec(input, 2, FriQueryRound::decode)?,
+            final_poly: PolynomialCoeffs::new(decode_exts::<F, D, I>(input)?),
+            pow_witness: decode_field(input)?,
+        })
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Encode for OpeningSet<F, D> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        for values in [
+            &self.constants,
+            &self.plonk_sigmas,
+            &self.wires,
+            &self.plonk_zs,
+            &self.plonk_zs_next,
+            &self.partial_products,
+            &self.quotient_polys,
+            &self.lookup_zs,
+            &self.lookup_zs_next,
+        ] {
+            encode_exts::<F, D, T>(values, dest);
+        }
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> Decode for OpeningSet<F, D> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            constants: decode_exts::<F, D, I>(input)?,
+            plonk_sigmas: decode_exts::<F, D, I>(input)?,
+            wires: decode_exts::<F, D, I>(input)?,
+            plonk_zs: decode_exts::<F, D, I>(input)?,
+            plonk_zs_next: decode_exts::<F, D, I>(input)?,
+            partial_products: decode_exts::<F, D, I>(input)?,
+            quotient_polys: decode_exts::<F, D, I>(input)?,
+            lookup_zs: decode_exts::<F, D, I>(input)?,
+            lookup_zs_next: decode_exts::<F, D, I>(input)?,
+        })
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Encode
+    for Proof<F, C, D>
+{
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.wires_cap.encode_to(dest);
+        self.plonk_zs_partial_products_cap.encode_to(dest);
+        self.quotient_polys_cap.encode_to(dest);
+        self.openings.encode_to(dest);
+        self.opening_proof.encode_to(dest);
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Decode
+    for Proof<F, C, D>
+{
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            wires_cap: MerkleCap::decode(input)?,
+            plonk_zs_partial_products_cap: MerkleCap::decode(input)?,
+            quotient_polys_cap: MerkleCap::decode(input)?,
+            openings: OpeningSet::decode(input)?,
+            opening_proof: FriProof::decode(input)?,
+        })
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Encode
+    for ProofWithPublicInputs<F, C, D>
+{
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.proof.encode_to(dest);
+        encode_vec(&self.public_inputs, dest, encode_field);
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Decode
+    for ProofWithPublicInputs<F, C, D>
+{
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            proof: Proof::decode(input)?,
+            public_inputs: decode_vec(input, 8, decode_field)?,
+        })
+    }
+}
+
+impl<C: GenericConfig<D>, const D: usize> Encode for VerifierOnlyCircuitData<C, D> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.constants_sigmas_cap.encode_to(dest);
+        encode_hash::<C::F, C::Hasher, T>(&self.circuit_digest, dest);
+    }
+}
+
+impl<C: GenericConfig<D>, const D: usize> Decode for VerifierOnlyCircuitData<C, D> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            constants_sigmas_cap: MerkleCap::decode(input)?,
+            circuit_digest: decode_hash::<C::F, C::Hasher, I>(input)?,
+        })
+    }
+}
+
+impl<F, const D: usize> Encode for CommonCircuitData<F, D>
+where
+    F: RichField + Extendable<D>,
+{
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.to_bytes(&DefaultGateSerializer)
+            .expect("Gates must be supported by the default gate serializer")
+            .encode_to(dest);
+    }
+}
+
+impl<F, const D: usize> Decode for CommonCircuitData<F, D>
+where
+    F: RichField + Extendable<D>,
+{
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let bytes = decode_vec(input, 1, u8::decode)?;
+        Self::from_bytes(bytes, &DefaultGateSerializer)
+            .map_err(|_| "Invalid common circuit data".into())
+    }
+}
+
+impl<F, C: GenericConfig<D, F = F>, const D: usize> Encode for VerifierCircuitData<F, C, D>
+where
+    F: RichField + Extendable<D>,
+{
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.verifier_only.encode_to(dest);
+        self.common.encode_to(dest);
+    }
+}
+
+impl<F, C: GenericConfig<D, F = F>, const D: usize> Decode for VerifierCircuitData<F, C, D>
+where
+    F: RichField + Extendable<D>,
+{
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        Ok(Self {
+            verifier_only: VerifierOnlyCircuitData::decode(input)?,
+            common: CommonCircuitData::decode(input)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::PoseidonGoldilocksConfig;
+
+    #[test]
+    fn test_scale_roundtrip_and_verify() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_public_input();
+        let y = builder.mul(x, x);
+        builder.register_public_input(y);
+        let data = builder.build::<C>();
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3))?;
+        let proof = data.prove(pw)?;
+
+        let proof_bytes = proof.encode();
+        assert_eq!(
+            ProofWithPublicInputs::<F, C, D>::decode_all(&mut &proof_bytes[..]).unwrap(),
+            proof
+        );
+        let verifier_data = data.verifier_data();
+        let verifier_bytes = verifier_data.encode();
+        let decoded = VerifierCircuitData::<F, C, D>::decode_all(&mut &verifier_bytes[..]).unwrap();
+        assert_eq!(decoded.verifier_only, verifier_data.verifier_only);
+        assert_eq!(decoded.common, verifier_data.common);
+
+        assert_eq!(
+            verify_for_runtime::<F, C, D>(&verifier_bytes, &proof_bytes),
+            Ok(vec![F::from_canonical_u64(3), F::from_canonical_u64(9)])
+        );
+
+        let mut tampered = proof.clone();
+        tampered.public_inputs[1] = F::from_canonical_u64(10);
+        assert_eq!(
+            verify_for_runtime::<F, C, D>(&verifier_bytes, &tampered.encode()),
+            Err(RuntimeVerifyError::VerificationFailed)
+        );
+        assert_eq!(
+            verify_for_runtime::<F, C, D>(&verifier_bytes, &proof_bytes[1..]),
+            Err(RuntimeVerifyError::InvalidProof)
+        );
+        // A huge length prefix is rejected before anything is allocated.
+        let mut huge = Compact(u32::MAX).encode();
+        huge.extend(&proof_bytes);
+        assert_eq!(
+            verify_for_runtime::<F, C, D>(&verifier_bytes, &huge),
+            Err(RuntimeVerifyError::InvalidProof)
+        );
+        Ok(())
+    }
+}
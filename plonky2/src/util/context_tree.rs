@@ -4,7 +4,9 @@ use alloc::{
     vec,
     vec::Vec,
 };
+use core::fmt::Write;
 
+use anyhow::{bail, Result};
 use log::{log, Level};
 
 /// The hierarchy of contexts, and the gate count contributed by each one. Useful for debugging.
@@ -20,6 +22,8 @@ pub(crate) struct ContextTree {
     exit_gate_count: Option<usize>,
     /// Any child contexts.
     children: Vec<ContextTree>,
+    /// The maximum number of gates this scope may contribute, if any.
+    gate_budget: Option<usize>,
 }
 
 impl ContextTree {
@@ -30,6 +34,7 @@ impl ContextTree {
             enter_gate_count: 0,
             exit_gate_count: None,
             children: vec![],
+            gate_budget: None,
         }
     }
 
@@ -73,9 +78,87 @@ impl ContextTree {
             enter_gate_count: current_gate_count,
             exit_gate_count: None,
             children: vec![],
+            gate_budget: None,
         })
     }
 
+    /// Limits the number of gates contributed by the deepest open scope to `max_gates`.
+    pub fn set_gate_budget(&mut self, max_gates: usize) {
+        assert!(self.is_open());
+
+        if let Some(last_child) = self.children.last_mut() {
+            if last_child.is_open() {
+                last_child.set_gate_budget(max_gates);
+                return;
+            }
+        }
+
+        self.set_root_gate_budget(max_gates);
+    }
+
+    /// Limits the number of gates contributed by this scope, including all of its sub-scopes.
+    pub fn set_root_gate_budget(&mut self, max_gates: usize) {
+        self.gate_budget = Some(self.gate_budget.map_or(max_gates, |b| b.min(max_gates)));
+    }
+
+    /// Returns an error listing each scope which contributed more gates than its budget, along
+    /// with a breakdown of its gate count by sub-scope.
+    pub fn check_gate_budgets(&self, current_gate_count: usize) -> Result<()> {
+        let mut report = String::new();
+        self.check_gate_budgets_helper(current_gate_count, &mut Vec::new(), &mut report);
+        if !report.is_empty() {
+            bail!("Gate budget exceeded:\n{}", report.trim_end());
+        }
+        Ok(())
+    }
+
+    fn check_gate_budgets_helper(
+        &self,
+        current_gate_count: usize,
+        stack: &mut Vec<String>,
+        report: &mut String,
+    ) {
+        stack.push(self.name.clone());
+        let count = self.gate_count_delta(current_gate_count);
+        if let Some(budget) = self.gate_budget.filter(|&budget| count > budget) {
+            let _ = writeln!(
+                report,
+                "{} uses {} gates, over its budget of {}",
+                stack.join(" > "),
+                count,
+                budget
+            );
+            // Two levels of sub-scopes are usually enough to find the culprit.
+            self.write_breakdown(current_gate_count, 1, 2, report);
+        }
+        for child in &self.children {
+            child.check_gate_budgets_helper(current_gate_count, stack, report);
+        }
+        stack.pop();
+    }
+
+    fn write_breakdown(
+        &self,
+        current_gate_count: usize,
+        depth: usize,
+        max_depth: usize,
+        report: &mut String,
+    ) {
+        if depth > max_depth {
+            return;
+        }
+        for child in &self.children {
+            let _ = writeln!(
+                report,
+                "{}{} gates to {}",
+                "| ".repeat(depth),
+                child.gate_count_delta(current_gate_count),
+                child.name
+            );
+            child.write_breakdown(current_gate_count, depth + 1, max_depth, report);
+        }
+    }
+
     /// Close the deepest open context from this tree.
     pub fn pop(&mut self, current_gate_count: usize) {
         assert!(self.is_open());
@@ -101,6 +184,7 @@ impl ContextTree {
             level: self.level,
             enter_gate_count: self.enter_gate_count,
             exit_gate_count: self.exit_gate_count,
+            gate_budget: self.gate_budget,
             children: self
                 .children
                 .iter()
@@ -110,17 +194,50 @@ impl ContextTree {
         }
     }
 
+    /// The stack of contexts in effect from each listed gate onwards, as `(gate index, stack)`
+    /// pairs sorted by gate index.
+    pub fn gate_contexts(&self) -> Vec<(usize, String)> {
+        let mut contexts = Vec::new();
+        self.gate_contexts_helper(&mut Vec::new(), &mut contexts);
+        contexts
+    }
+
+    fn gate_contexts_helper(&self, stack: &mut Vec<String>, contexts: &mut Vec<(usize, String)>) {
+        fn enter(contexts: &mut Vec<(usize, String)>, gate: usize, context: String) {
+            match contexts.last_mut() {
+                // The previous context contributed no gates, so it is replaced.
+                Some((last_gate, last_context)) if *last_gate == gate => *last_context = context,
+                _ => contexts.push((gate, context)),
+            }
+        }
+
+        stack.push(self.name.clone());
+        let context = stack.join(" > ");
+        enter(contexts, self.enter_gate_count, context.clone());
+        for child in &self.children {
+            child.gate_contexts_helper(stack, contexts);
+            if let Some(exit_gate_count) = child.exit_gate_count {
+                enter(contexts, exit_gate_count, context.clone());
+            }
+        }
+        stack.pop();
+    }
+
     pub fn print(&self, current_gate_count: usize) {
         self.print_helper(current_gate_count, 0);
     }
 
     fn print_helper(&self, current_gate_count: usize, depth: usize) {
         let prefix = "| ".repeat(depth);
+        let gates = self.gate_count_delta(current_gate_count);
         log!(
             self.level,
+            context = self.name.as_str(),
+            depth,
+            gates;
             "{}{} gates to {}",
             prefix,
-            self.gate_count_delta(current_gate_count),
+            gates,
             self.name
         );
         for child in &self.children {
@@ -49,30 +49,29 @@ pub(crate) fn num_partial_products(n: usize, max_degree: usize) -> usize {
 /// Checks the relationship between each pair of partial product accumulators. In particular, this
 /// sequence of accumulators starts with `Z(x)`, then contains each partial product polynomials
 /// `p_i(x)`, and finally `Z(g x)`. See the partial products section of the Plonky2 paper.
-pub(crate) fn check_partial_products<F: Field>(
-    numerators: &[F],
-    denominators: &[F],
-    partials: &[F],
+pub(crate) fn check_partial_products<'a, F: Field>(
+    numerators: &'a [F],
+    denominators: &'a [F],
+    partials: &'a [F],
     z_x: F,
     z_gx: F,
     max_degree: usize,
-) -> Vec<F> {
+) -> impl Iterator<Item = F> + 'a {
     debug_assert!(max_degree > 1);
-    let product_accs = iter::once(&z_x)
-        .chain(partials.iter())
-        .chain(iter::once(&z_gx));
+    let product_accs = iter::once(z_x)
+        .chain(partials.iter().copied())
+        .chain(iter::once(z_gx));
     let chunk_size = max_degree;
     numerators
         .chunks(chunk_size)
         .zip_eq(denominators.chunks(chunk_size))
         .zip_eq(product_accs.tuple_windows())
-        .map(|((nume_chunk, deno_chunk), (&prev_acc, &next_acc))| {
+        .map(|((nume_chunk, deno_chunk), (prev_acc, next_acc))| {
             let num_chunk_product = nume_chunk.iter().copied().product();
             let den_chunk_product = deno_chunk.iter().copied().product();
             // Assert that next_acc * deno_product = prev_acc * nume_product.
             prev_acc * num_chunk_product - next_acc * den_chunk_product
         })
-        .collect()
 }
 
 /// Checks the relationship between each pair of partial product accumulators. In particular, this
@@ -88,15 +87,15 @@ pub(crate) fn check_partial_products_circuit<F: RichField + Extendable<D>, const
     max_degree: usize,
 ) -> Vec<ExtensionTarget<D>> {
     debug_assert!(max_degree > 1);
-    let product_accs = iter::once(&z_x)
-        .chain(partials.iter())
-        .chain(iter::once(&z_gx));
+    let product_accs = iter::once(z_x)
+        .chain(partials.iter().copied())
+        .chain(iter::once(z_gx));
     let chunk_size = max_degree;
     numerators
         .chunks(chunk_size)
         .zip_eq(denominators.chunks(chunk_size))
         .zip_eq(product_accs.tuple_windows())
-        .map(|((nume_chunk, deno_chunk), (&prev_acc, &next_acc))| {
+        .map(|((nume_chunk, deno_chunk), (prev_acc, next_acc))| {
             let nume_product = builder.mul_many_extension(nume_chunk);
             let deno_product = builder.mul_many_extension(deno_chunk);
             let next_acc_deno = builder.mul_extension(next_acc, deno_product);
@@ -129,9 +128,7 @@ mod tests {
 
         let nums = num_partial_products(v.len(), 2);
         assert_eq!(pps.len(), nums);
-        assert!(check_partial_products(&v, &denominators, pps, z_x, z_gx, 2)
-            .iter()
-            .all(|x| x.is_zero()));
+        assert!(check_partial_products(&v, &denominators, pps, z_x, z_gx, 2).all(|x| x.is_zero()));
 
         let quotient_chunks_prods = quotient_chunk_products(&v, 3);
         assert_eq!(quotient_chunks_prods, field_vec(&[6, 120]));
@@ -140,9 +137,7 @@ mod tests {
         assert_eq!(pps_and_z_gx, field_vec(&[6, 720]));
         let nums = num_partial_products(v.len(), 3);
         assert_eq!(pps.len(), nums);
-        assert!(check_partial_products(&v, &denominators, pps, z_x, z_gx, 3)
-            .iter()
-            .all(|x| x.is_zero()));
+        assert!(check_partial_products(&v, &denominators, pps, z_x, z_gx, 3).all(|x| x.is_zero()));
     }
 
     fn field_vec<F: Field>(xs: &[usize]) -> Vec<F> {
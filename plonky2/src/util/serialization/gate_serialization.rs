@@ -103,15 +103,23 @@ pub mod default {
     use crate::gates::exponentiation::ExponentiationGate;
     use crate::gates::lookup::LookupGate;
     use crate::gates::lookup_table::LookupTableGate;
+    use crate::gates::monolith::MonolithGate;
     use crate::gates::multiplication_extension::MulExtensionGate;
     use crate::gates::noop::NoopGate;
     use crate::gates::poseidon::PoseidonGate;
+    use crate::gates::poseidon2::Poseidon2Gate;
     use crate::gates::poseidon_mds::PoseidonMdsGate;
     use crate::gates::public_input::PublicInputGate;
     use crate::gates::random_access::RandomAccessGate;
     use crate::gates::reducing::ReducingGate;
     use crate::gates::reducing_extension::ReducingExtensionGate;
+    use crate::gates::rescue_prime::RescuePrimeGate;
+    use crate::gates::tip5::Tip5Gate;
     use crate::hash::hash_types::RichField;
+    use crate::hash::monolith::Monolith;
+    use crate::hash::poseidon2::Poseidon2;
+    use crate::hash::rescue_prime::RescuePrime;
+    use crate::hash::tip5::Tip5;
     use crate::util::serialization::GateSerializer;
     /// A gate serializer that can be used to serialize all default gates supported
     /// by the `plonky2` library.
@@ -122,6 +130,12 @@ pub mod default {
     /// ```
     /// Applications using custom gates should define their own serializer implementing
     /// the `GateSerializer` trait. This can be easily done through the `impl_gate_serializer` macro.
+    ///
+    /// The gates of the Poseidon2, Monolith, Rescue-Prime and Tip5 permutations aren't included,
+    /// since they need fields implementing those permutations; see [`ExtendedGateSerializer`].
+    ///
+    /// The gates keep the tags of upstream plonky2's `DefaultGateSerializer`, so that common
+    /// circuit data written by upstream can be read with this one, and the other way around.
     #[derive(Debug)]
     pub struct DefaultGateSerializer;
     impl<F: RichField + Extendable<D>, const D: usize> GateSerializer<F, D> for DefaultGateSerializer {
@@ -145,4 +159,40 @@ pub mod default {
             ReducingGate<D>
         }
     }
+
+    /// Like [`DefaultGateSerializer`], but also serializes the gates of the Poseidon2, Monolith,
+    /// Rescue-Prime and Tip5 permutations, for fields which implement all of them.
+    ///
+    /// Those gates are tagged after the default ones, so data written by
+    /// [`DefaultGateSerializer`] can be read with this one.
+    #[derive(Debug)]
+    pub struct ExtendedGateSerializer;
+    impl<F, const D: usize> GateSerializer<F, D> for ExtendedGateSerializer
+    where
+        F: RichField + Extendable<D> + Monolith + Poseidon2 + RescuePrime + Tip5,
+    {
+        impl_gate_serializer! {
+            ExtendedGateSerializer,
+            ArithmeticGate,
+            ArithmeticExtensionGate<D>,
+            BaseSumGate<2>,
+            ConstantGate,
+            CosetInterpolationGate<F, D>,
+            ExponentiationGate<F, D>,
+            LookupGate,
+            LookupTableGate,
+            MulExtensionGate<D>,
+            NoopGate,
+            PoseidonMdsGate<F, D>,
+            PoseidonGate<F, D>,
+            PublicInputGate,
+            RandomAccessGate<F, D>,
+            ReducingExtensionGate<D>,
+            ReducingGate<D>,
+            Poseidon2Gate<F, D>,
+            MonolithGate<F, D>,
+            RescuePrimeGate<F, D>,
+            Tip5Gate<F, D>
+        }
+    }
 }
@@ -103,6 +103,7 @@ pub mod default {
 
     use crate::gadgets::arithmetic::EqualityGenerator;
     use crate::gadgets::arithmetic_extension::QuotientGeneratorExtension;
+    use crate::gadgets::lookup::ByteDecompositionGenerator;
     use crate::gadgets::range_check::LowHighGenerator;
     use crate::gadgets::split_base::BaseSumGenerator;
     use crate::gadgets::split_join::{SplitGenerator, WireSplitGenerator};
@@ -113,15 +114,24 @@ pub mod default {
     use crate::gates::exponentiation::ExponentiationGenerator;
     use crate::gates::lookup::LookupGenerator;
     use crate::gates::lookup_table::LookupTableGenerator;
+    use crate::gates::monolith::MonolithGenerator;
     use crate::gates::multiplication_extension::MulExtensionGenerator;
     use crate::gates::poseidon::PoseidonGenerator;
+    use crate::gates::poseidon2::Poseidon2Generator;
     use crate::gates::poseidon_mds::PoseidonMdsGenerator;
     use crate::gates::random_access::RandomAccessGenerator;
     use crate::gates::reducing::ReducingGenerator;
     use crate::gates::reducing_extension::ReducingGenerator as ReducingExtensionGenerator;
+    use crate::gates::rescue_prime::RescuePrimeGenerator;
+    use crate::gates::tip5::Tip5Generator;
     use crate::hash::hash_types::RichField;
+    use crate::hash::monolith::Monolith;
+    use crate::hash::poseidon2::Poseidon2;
+    use crate::hash::rescue_prime::RescuePrime;
+    use crate::hash::tip5::Tip5;
     use crate::iop::generator::{
         ConstantGenerator, CopyGenerator, NonzeroTestGenerator, RandomValueGenerator,
+        SeededValueGenerator,
     };
     use crate::plonk::config::{AlgebraicHasher, GenericConfig};
     use crate::recursion::dummy_circuit::DummyProofGenerator;
@@ -140,6 +150,13 @@ pub mod default {
     /// Applications using custom generators should define their own serializer implementing
     /// the `WitnessGeneratorSerializer` trait. This can be easily done through the
     /// `impl_generator_serializer` macro.
+    ///
+    /// The generators of the Poseidon2, Monolith, Rescue-Prime and Tip5 gates aren't included,
+    /// since they need fields implementing those permutations; see
+    /// [`ExtendedGeneratorSerializer`].
+    ///
+    /// The generators which upstream plonky2 also has keep the tags of its
+    /// `DefaultGeneratorSerializer`.
     #[derive(Debug, Default)]
     pub struct DefaultGeneratorSerializer<C: GenericConfig<D>, const D: usize> {
         pub _phantom: PhantomData<C>,
@@ -176,7 +193,65 @@ pub mod default {
             ReducingGenerator<D>,
             ReducingExtensionGenerator<D>,
             SplitGenerator,
-            WireSplitGenerator
+            WireSplitGenerator,
+            // Generators are tagged by their position, so generators which upstream plonky2
+            // doesn't have are appended to keep the tags of the others compatible with it.
+            ByteDecompositionGenerator,
+            SeededValueGenerator
+        }
+    }
+
+    /// Like [`DefaultGeneratorSerializer`], but also serializes the generators of the Poseidon2,
+    /// Monolith, Rescue-Prime and Tip5 gates, for fields which implement all of those
+    /// permutations.
+    ///
+    /// Those generators are tagged after the default ones, so data written by
+    /// [`DefaultGeneratorSerializer`] can be read with this one.
+    #[derive(Debug, Default)]
+    pub struct ExtendedGeneratorSerializer<C: GenericConfig<D>, const D: usize> {
+        pub _phantom: PhantomData<C>,
+    }
+
+    impl<F, C, const D: usize> WitnessGeneratorSerializer<F, D> for ExtendedGeneratorSerializer<C, D>
+    where
+        F: RichField + Extendable<D> + Monolith + Poseidon2 + RescuePrime + Tip5,
+        C: GenericConfig<D, F = F> + 'static,
+        C::Hasher: AlgebraicHasher<F>,
+    {
+        impl_generator_serializer! {
+            ExtendedGeneratorSerializer,
+            ArithmeticBaseGenerator<F, D>,
+            ArithmeticExtensionGenerator<F, D>,
+            BaseSplitGenerator<2>,
+            BaseSumGenerator<2>,
+            ConstantGenerator<F>,
+            CopyGenerator,
+            DummyProofGenerator<F, C, D>,
+            EqualityGenerator,
+            ExponentiationGenerator<F, D>,
+            InterpolationGenerator<F, D>,
+            LookupGenerator,
+            LookupTableGenerator,
+            LowHighGenerator,
+            MulExtensionGenerator<F, D>,
+            NonzeroTestGenerator,
+            PoseidonGenerator<F, D>,
+            PoseidonMdsGenerator<D>,
+            QuotientGeneratorExtension<D>,
+            RandomAccessGenerator<F, D>,
+            RandomValueGenerator,
+            ReducingGenerator<D>,
+            ReducingExtensionGenerator<D>,
+            SplitGenerator,
+            WireSplitGenerator,
+            // Generators are tagged by their position, so generators which upstream plonky2
+            // doesn't have are appended to keep the tags of the others compatible with it.
+            ByteDecompositionGenerator,
+            SeededValueGenerator,
+            Poseidon2Generator<F, D>,
+            MonolithGenerator<F, D>,
+            RescuePrimeGenerator<F, D>,
+            Tip5Generator<F, D>
         }
     }
 }
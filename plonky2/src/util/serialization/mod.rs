@@ -5,7 +5,7 @@ pub mod generator_serialization;
 pub mod gate_serialization;
 
 #[cfg(not(feature = "std"))]
-use alloc::{collections::BTreeMap, sync::Arc, vec, vec::Vec};
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec, vec::Vec};
 use core::convert::Infallible;
 use core::fmt::{Debug, Display, Formatter};
 use core::mem::size_of;
@@ -13,9 +13,11 @@ use core::ops::Range;
 #[cfg(feature = "std")]
 use std::{collections::BTreeMap, sync::Arc};
 
-pub use gate_serialization::default::DefaultGateSerializer;
+pub use gate_serialization::default::{DefaultGateSerializer, ExtendedGateSerializer};
 pub use gate_serialization::GateSerializer;
-pub use generator_serialization::default::DefaultGeneratorSerializer;
+pub use generator_serialization::default::{
+    DefaultGeneratorSerializer, ExtendedGeneratorSerializer,
+};
 pub use generator_serialization::WitnessGeneratorSerializer;
 use hashbrown::HashMap;
 
@@ -28,7 +30,7 @@ use crate::fri::proof::{
     FriProof, FriProofTarget, FriQueryRound, FriQueryRoundTarget, FriQueryStep, FriQueryStepTarget,
 };
 use crate::fri::reduction_strategies::FriReductionStrategy;
-use crate::fri::{FriConfig, FriParams};
+use crate::fri::{FriConfig, FriParams, FriSecurity};
 use crate::gadgets::polynomial::PolynomialCoeffsExtTarget;
 use crate::gates::gate::GateRef;
 use crate::gates::lookup::Lookup;
@@ -40,6 +42,7 @@ use crate::iop::ext_target::ExtensionTarget;
 use crate::iop::generator::WitnessGeneratorRef;
 use crate::iop::target::{BoolTarget, Target};
 use crate::iop::wire::Wire;
+use crate::iop::witness::{MatrixWitness, PartitionWitness};
 use crate::plonk::circuit_builder::LookupWire;
 use crate::plonk::circuit_data::{
     CircuitConfig, CircuitData, CommonCircuitData, ProverCircuitData, ProverOnlyCircuitData,
@@ -51,6 +54,8 @@ use crate::plonk::proof::{
     CompressedProof, CompressedProofWithPublicInputs, OpeningSet, OpeningSetTarget, Proof,
     ProofTarget, ProofWithPublicInputs, ProofWithPublicInputsTarget,
 };
+use crate::plonk::prover::{ProverCheckpoint, ProverStage};
+use crate::plonk::public_inputs::PublicInputNames;
 
 /// A no_std compatible variant of `std::io::Error`
 #[derive(Debug)]
@@ -131,6 +136,14 @@ pub trait Read {
         Ok(u32::from_le_bytes(buf))
     }
 
+    /// Reads a `u64` value from `self`.
+    #[inline]
+    fn read_u64(&mut self) -> IoResult<u64> {
+        let mut buf = [0; size_of::<u64>()];
+        self.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
     /// Reads a `usize` value from `self`.
     #[inline]
     fn read_usize(&mut self) -> IoResult<usize> {
@@ -151,6 +164,15 @@ pub trait Read {
         Ok(res)
     }
 
+    /// Reads a length-prefixed UTF-8 `String` from `self`.
+    #[inline]
+    fn read_string(&mut self) -> IoResult<String> {
+        let len = self.read_usize()?;
+        let mut bytes = vec![0; len];
+        self.read_exact(&mut bytes)?;
+        String::from_utf8(bytes).map_err(|_| IoError)
+    }
+
     /// Reads a element from the field `F` with size less than `2^64` from `self.`
     #[inline]
     fn read_field<F>(&mut self) -> IoResult<F>
@@ -632,6 +654,17 @@ pub trait Read {
                     _ => Err(IoError),
                 }
             }
+            3 => {
+                let is_some = self.read_u8()?;
+                match is_some {
+                    0 => Ok(FriReductionStrategy::MinVerifierGates(None)),
+                    1 => {
+                        let max = self.read_usize()?;
+                        Ok(FriReductionStrategy::MinVerifierGates(Some(max)))
+                    }
+                    _ => Err(IoError),
+                }
+            }
             _ => Err(IoError),
         }
     }
@@ -652,6 +685,14 @@ pub trait Read {
         })
     }
 
+    fn read_fri_security(&mut self) -> IoResult<FriSecurity> {
+        match self.read_u8()? {
+            0 => Ok(FriSecurity::Proven),
+            1 => Ok(FriSecurity::ConjecturedListDecoding),
+            _ => Err(IoError),
+        }
+    }
+
     fn read_circuit_config(&mut self) -> IoResult<CircuitConfig> {
         let num_wires = self.read_usize()?;
         let num_routed_wires = self.read_usize()?;
@@ -668,6 +709,7 @@ pub trait Read {
             num_routed_wires,
             num_constants,
             security_bits,
+            fri_security: FriSecurity::default(),
             num_challenges,
             max_quotient_degree_factor,
             use_base_arithmetic_gate,
@@ -891,18 +933,66 @@ pub trait Read {
             lut_to_lookups.push(self.read_target_lut()?);
         }
 
+        let length = self.read_usize()?;
+        let mut gate_contexts = Vec::with_capacity(length);
+        for _ in 0..length {
+            gate_contexts.push((self.read_usize()?, self.read_string()?));
+        }
+
+        let length = self.read_usize()?;
+        let mut generator_contexts = Vec::with_capacity(length);
+        for _ in 0..length {
+            generator_contexts.push((self.read_usize()?, self.read_string()?));
+        }
+
+        let length = self.read_usize()?;
+        let mut public_input_names = PublicInputNames::default();
+        for _ in 0..length {
+            let name = self.read_string()?;
+            let (start, end) = (self.read_usize()?, self.read_usize()?);
+            if start > end || end > public_inputs.len() || public_input_names.range(&name).is_some()
+            {
+                return Err(IoError);
+            }
+            public_input_names.push(&name, start..end);
+        }
+
+        // Reject prover data which doesn't match `common_data`, e.g. because it was saved for a
+        // different build of the circuit, rather than producing proofs which fail to verify.
+        let degree = common_data.degree();
+        let num_routed_wires = common_data.config.num_routed_wires;
+        let consistent = constants_sigmas_commitment.degree_log == common_data.degree_bits()
+            && constants_sigmas_commitment.rate_bits == common_data.config.fri_config.rate_bits
+            && constants_sigmas_commitment.polynomials.len()
+                == common_data.num_constants + num_routed_wires
+            && sigmas.len() == degree
+            && sigmas.iter().all(|sigma| sigma.len() == num_routed_wires)
+            && subgroup.len() == degree
+            && public_inputs.len() == common_data.num_public_inputs
+            && representative_map.len() >= common_data.config.num_wires * degree
+            && generator_indices_by_watches
+                .values()
+                .flatten()
+                .all(|&generator_idx| generator_idx < generators.len());
+        if !consistent {
+            return Err(IoError);
+        }
+
         Ok(ProverOnlyCircuitData {
-            generators,
-            generator_indices_by_watches,
-            constants_sigmas_commitment,
-            sigmas,
-            subgroup,
+            generators: Arc::new(generators),
+            generator_indices_by_watches: Arc::new(generator_indices_by_watches),
+            constants_sigmas_commitment: Arc::new(constants_sigmas_commitment),
+            sigmas: Arc::new(sigmas),
+            subgroup: Arc::new(subgroup),
             public_inputs,
-            representative_map,
-            fft_root_table,
+            public_input_names,
+            representative_map: Arc::new(representative_map),
+            fft_root_table: fft_root_table.map(Arc::new),
             circuit_digest,
             lookup_rows,
             lut_to_lookups,
+            gate_contexts,
+            generator_contexts,
         })
     }
 
@@ -1158,6 +1248,149 @@ pub trait Read {
         })
     }
 
+    /// Reads a value of type [`PartitionWitness`] from `self`, for a circuit with the given
+    /// `representative_map`.
+    #[inline]
+    fn read_partition_witness<'a, F: Field64>(
+        &mut self,
+        representative_map: &'a [usize],
+    ) -> IoResult<PartitionWitness<'a, F>> {
+        let num_wires = self.read_usize()?;
+        let degree = self.read_usize()?;
+        let len = self.read_usize()?;
+        if len != representative_map.len() {
+            return Err(IoError);
+        }
+        let mut witness = PartitionWitness::new(num_wires, degree, representative_map);
+        for value in witness.values.iter_mut() {
+            if self.read_bool()? {
+                *value = Some(self.read_field()?);
+            }
+        }
+
+        Ok(witness)
+    }
+
+    /// Reads a value of type [`ProverCheckpoint`] from `self` with `common_data`.
+    #[inline]
+    fn read_prover_checkpoint<F, C, const D: usize>(
+        &mut self,
+        common_data: &CommonCircuitData<F, D>,
+    ) -> IoResult<ProverCheckpoint<F, C, D>>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    {
+        // The stage tag is the number of the commitments and openings which follow.
+        let stage = self.read_u8()? as usize;
+        if stage > ProverStage::Opened as usize {
+            return Err(IoError);
+        }
+
+        let public_inputs_len = self.read_usize()?;
+        if public_inputs_len != common_data.num_public_inputs {
+            return Err(IoError);
+        }
+        let public_inputs = self.read_field_vec(public_inputs_len)?;
+        // The witness is released once the partial products are committed to.
+        let expected_num_wires = if stage < ProverStage::PartialProductsCommitted as usize {
+            common_data.config.num_wires
+        } else {
+            0
+        };
+        let num_wires = self.read_usize()?;
+        if num_wires != expected_num_wires {
+            return Err(IoError);
+        }
+        let degree = common_data.degree();
+        let mut wire_values = Vec::with_capacity(num_wires);
+        for _ in 0..num_wires {
+            let len = self.read_usize()?;
+            if len != degree {
+                return Err(IoError);
+            }
+            wire_values.push(self.read_field_vec(len)?);
+        }
+
+        let lde_size = common_data.lde_size();
+        let cap_height = common_data.fri_params.config.cap_height;
+        let oracles = common_data.fri_oracles();
+        let read_commitment = |buffer: &mut Self, index: usize| -> IoResult<_> {
+            let present = buffer.read_bool()?;
+            if present != (index < stage) {
+                return Err(IoError);
+            }
+            if !present {
+                return Ok(None);
+            }
+
+            // The committed oracles are the ones after the preprocessed one.
+            let oracle = &oracles[index + 1];
+            let blinding = oracle.blinding && common_data.config.zero_knowledge;
+            let num_polys = buffer.read_usize()?;
+            if num_polys != oracle.num_polys {
+                return Err(IoError);
+            }
+            let mut polynomials = Vec::with_capacity(num_polys);
+            for _ in 0..num_polys {
+                let len = buffer.read_usize()?;
+                if len != degree {
+                    return Err(IoError);
+                }
+                polynomials.push(PolynomialCoeffs::new(buffer.read_field_vec(len)?));
+            }
+
+            let merkle_tree = buffer.read_merkle_tree::<F, C::Hasher>()?;
+            let leaf_len = num_polys + salt_size(blinding);
+            if merkle_tree.leaves.len() != lde_size
+                || merkle_tree.leaves.iter().any(|leaf| leaf.len() != leaf_len)
+                || merkle_tree.cap.height() != cap_height
+                || merkle_tree.digests.len() != 2 * (lde_size - (1 << cap_height))
+            {
+                return Err(IoError);
+            }
+            let degree_log = buffer.read_usize()?;
+            let rate_bits = buffer.read_usize()?;
+            if degree_log != common_data.degree_bits()
+                || rate_bits != common_data.config.fri_config.rate_bits
+                || buffer.read_bool()? != blinding
+            {
+                return Err(IoError);
+            }
+
+            Ok(Some(PolynomialBatch {
+                polynomials,
+                merkle_tree,
+                degree_log,
+                rate_bits,
+                blinding,
+            }))
+        };
+        let wires_commitment = read_commitment(self, 0)?;
+        let partial_products_zs_and_lookup_commitment = read_commitment(self, 1)?;
+        let quotient_polys_commitment = read_commitment(self, 2)?;
+        let openings = if self.read_bool()? {
+            if stage != ProverStage::Opened as usize {
+                return Err(IoError);
+            }
+            Some(self.read_opening_set::<F, C, D>(common_data)?)
+        } else {
+            if stage == ProverStage::Opened as usize {
+                return Err(IoError);
+            }
+            None
+        };
+
+        Ok(ProverCheckpoint {
+            public_inputs,
+            witness: MatrixWitness { wire_values },
+            wires_commitment,
+            partial_products_zs_and_lookup_commitment,
+            quotient_polys_commitment,
+            openings,
+        })
+    }
+
     /// Reads a lookup table stored as `Vec<(u16, u16)>` from `self`.
     #[inline]
     fn read_lut(&mut self) -> IoResult<Vec<(u16, u16)>> {
@@ -1232,6 +1465,12 @@ pub trait Write {
         self.write_all(&x.to_le_bytes())
     }
 
+    /// Writes a word `x` to `self.`
+    #[inline]
+    fn write_u64(&mut self, x: u64) -> IoResult<()> {
+        self.write_all(&x.to_le_bytes())
+    }
+
     /// Writes a word `x` to `self.`
     #[inline]
     fn write_usize(&mut self, x: usize) -> IoResult<()> {
@@ -1249,6 +1488,13 @@ pub trait Write {
         Ok(())
     }
 
+    /// Writes a length-prefixed UTF-8 string `s` to `self`.
+    #[inline]
+    fn write_string(&mut self, s: &str) -> IoResult<()> {
+        self.write_usize(s.len())?;
+        self.write_all(s.as_bytes())
+    }
+
     /// Writes an element `x` from the field `F` to `self`.
     #[inline]
     fn write_field<F>(&mut self, x: F) -> IoResult<()>
@@ -1647,6 +1893,17 @@ pub trait Write {
                     self.write_u8(0)?;
                 }
 
+                Ok(())
+            }
+            FriReductionStrategy::MinVerifierGates(max) => {
+                self.write_u8(3)?;
+                if let Some(max) = max {
+                    self.write_u8(1)?;
+                    self.write_usize(*max)?;
+                } else {
+                    self.write_u8(0)?;
+                }
+
                 Ok(())
             }
         }
@@ -1670,6 +1927,13 @@ pub trait Write {
         Ok(())
     }
 
+    fn write_fri_security(&mut self, security: FriSecurity) -> IoResult<()> {
+        self.write_u8(match security {
+            FriSecurity::Proven => 0,
+            FriSecurity::ConjecturedListDecoding => 1,
+        })
+    }
+
     fn write_fri_params(&mut self, fri_params: &FriParams) -> IoResult<()> {
         let FriParams {
             config,
@@ -1692,6 +1956,8 @@ pub trait Write {
             num_routed_wires,
             num_constants,
             security_bits,
+            // Only in the versioned encoding; see `CircuitConfig::fri_security`.
+            fri_security: _,
             num_challenges,
             max_quotient_degree_factor,
             use_base_arithmetic_gate,
@@ -1848,11 +2114,14 @@ pub trait Write {
             sigmas,
             subgroup,
             public_inputs,
+            public_input_names,
             representative_map,
             fft_root_table,
             circuit_digest,
             lookup_rows,
             lut_to_lookups,
+            gate_contexts,
+            generator_contexts,
         } = prover_only_circuit_data;
 
         self.write_usize(generators.len())?;
@@ -1861,7 +2130,7 @@ pub trait Write {
         }
 
         self.write_usize(generator_indices_by_watches.len())?;
-        for (k, v) in generator_indices_by_watches {
+        for (k, v) in generator_indices_by_watches.iter() {
             self.write_usize(*k)?;
             self.write_usize_vec(v)?;
         }
@@ -1903,6 +2172,25 @@ pub trait Write {
             self.write_target_lut(tlut)?;
         }
 
+        self.write_usize(gate_contexts.len())?;
+        for (gate, context) in gate_contexts {
+            self.write_usize(*gate)?;
+            self.write_string(context)?;
+        }
+
+        self.write_usize(generator_contexts.len())?;
+        for (generator_idx, context) in generator_contexts {
+            self.write_usize(*generator_idx)?;
+            self.write_string(context)?;
+        }
+
+        self.write_usize(public_input_names.iter().count())?;
+        for (name, range) in public_input_names.iter() {
+            self.write_string(name)?;
+            self.write_usize(range.start)?;
+            self.write_usize(range.end)?;
+        }
+
         Ok(())
     }
 
@@ -2110,6 +2398,70 @@ pub trait Write {
         self.write_field_vec(public_inputs)
     }
 
+    /// Writes a value `witness` of type [`PartitionWitness`] to `self`.
+    #[inline]
+    fn write_partition_witness<F: PrimeField64>(
+        &mut self,
+        witness: &PartitionWitness<F>,
+    ) -> IoResult<()> {
+        self.write_usize(witness.num_wires)?;
+        self.write_usize(witness.degree)?;
+        self.write_usize(witness.values.len())?;
+        for value in &witness.values {
+            self.write_bool(value.is_some())?;
+            if let Some(value) = value {
+                self.write_field(*value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a value `checkpoint` of type [`ProverCheckpoint`] to `self`.
+    #[inline]
+    fn write_prover_checkpoint<F, C, const D: usize>(
+        &mut self,
+        checkpoint: &ProverCheckpoint<F, C, D>,
+    ) -> IoResult<()>
+    where
+        F: RichField + Extendable<D>,
+        C: GenericConfig<D, F = F>,
+    {
+        let ProverCheckpoint {
+            public_inputs,
+            witness,
+            wires_commitment,
+            partial_products_zs_and_lookup_commitment,
+            quotient_polys_commitment,
+            openings,
+        } = checkpoint;
+        self.write_u8(checkpoint.stage() as u8)?;
+        self.write_usize(public_inputs.len())?;
+        self.write_field_vec(public_inputs)?;
+        self.write_usize(witness.wire_values.len())?;
+        for column in &witness.wire_values {
+            self.write_usize(column.len())?;
+            self.write_field_vec(column)?;
+        }
+
+        for commitment in [
+            wires_commitment,
+            partial_products_zs_and_lookup_commitment,
+            quotient_polys_commitment,
+        ] {
+            self.write_bool(commitment.is_some())?;
+            if let Some(commitment) = commitment {
+                self.write_polynomial_batch(commitment)?;
+            }
+        }
+        self.write_bool(openings.is_some())?;
+        if let Some(openings) = openings {
+            self.write_opening_set(openings)?;
+        }
+
+        Ok(())
+    }
+
     /// Writes a lookup table to `self`.
     #[inline]
     fn write_lut(&mut self, lut: &[(u16, u16)]) -> IoResult<()> {
@@ -121,13 +121,83 @@ impl TimingTree {
     #[cfg(not(feature = "timing"))]
     pub fn pop(&mut self) {}
 
+    /// Adds a scope which has already been closed, and which took `duration`, to the deepest open
+    /// scope. Useful for time measured separately, e.g. summed across threads.
     #[cfg(feature = "timing")]
-    fn duration(&self) -> Duration {
+    pub fn add_closed(&mut self, ctx: &str, duration: Duration) {
+        assert!(self.is_open());
+
+        if let Some(last_child) = self.children.last_mut() {
+            if last_child.is_open() {
+                last_child.add_closed(ctx, duration);
+                return;
+            }
+        }
+
+        let exit_time = Instant::now();
+        self.children.push(TimingTree {
+            name: ctx.to_string(),
+            level: self.level,
+            enter_time: exit_time.checked_sub(duration).unwrap_or(exit_time),
+            exit_time: Some(exit_time),
+            children: vec![],
+        })
+    }
+
+    #[cfg(not(feature = "timing"))]
+    pub fn add_closed(&mut self, _ctx: &str, _duration: core::time::Duration) {}
+
+    /// The name of this scope.
+    #[cfg(feature = "timing")]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The time spent in this scope, so far if it is still open.
+    #[cfg(feature = "timing")]
+    pub fn duration(&self) -> Duration {
         self.exit_time
             .unwrap_or_else(Instant::now)
             .duration_since(self.enter_time)
     }
 
+    /// The scopes nested directly in this one, in the order they were entered.
+    #[cfg(feature = "timing")]
+    pub fn children(&self) -> &[TimingTree] {
+        &self.children
+    }
+
+    /// The first scope named `name`, searching this scope and its descendants depth-first.
+    #[cfg(feature = "timing")]
+    pub fn find(&self, name: &str) -> Option<&TimingTree> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(name))
+    }
+
+    /// The total time spent in the scopes named `name` among this scope's descendants, as a
+    /// fraction of the time spent in this scope. E.g. `fraction("compute quotient polys")` on the
+    /// tree passed to `prove` gives the share of the proving time spent in the quotient phase.
+    #[cfg(feature = "timing")]
+    pub fn fraction(&self, name: &str) -> f64 {
+        self.children
+            .iter()
+            .map(|child| child.time_in(name))
+            .sum::<Duration>()
+            .as_secs_f64()
+            / self.duration().as_secs_f64()
+    }
+
+    #[cfg(feature = "timing")]
+    fn time_in(&self, name: &str) -> Duration {
+        if self.name == name {
+            self.duration()
+        } else {
+            self.children.iter().map(|child| child.time_in(name)).sum()
+        }
+    }
+
     /// Filter out children with a low duration.
     #[cfg(feature = "timing")]
     pub fn filter(&self, min_delta: Duration) -> Self {
@@ -161,11 +231,15 @@ impl TimingTree {
     #[cfg(feature = "timing")]
     fn print_helper(&self, depth: usize) {
         let prefix = "| ".repeat(depth);
+        let secs = self.duration().as_secs_f64();
         log!(
             self.level,
+            scope = self.name.as_str(),
+            depth,
+            secs;
             "{}{:.4}s to {}",
             prefix,
-            self.duration().as_secs_f64(),
+            secs,
             self.name
         );
         for child in &self.children {
@@ -191,3 +265,54 @@ macro_rules! timed {
         res
     }};
 }
+
+#[cfg(all(test, feature = "timing"))]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::field::types::Field;
+    use crate::iop::witness::{PartialWitness, WitnessWrite};
+    use crate::plonk::circuit_builder::CircuitBuilder;
+    use crate::plonk::circuit_data::CircuitConfig;
+    use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use crate::plonk::prover::prove;
+
+    #[test]
+    fn test_query_phases() -> Result<()> {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let x = builder.add_virtual_target();
+        let y = builder.square(x);
+        builder.register_public_input(y);
+        let mut build_timing = TimingTree::new("build", Level::Debug);
+        let data = builder.build_with_timing::<C>(&mut build_timing);
+        build_timing.pop();
+        assert!(build_timing.find("generate sigma polynomials").is_some());
+        assert!(build_timing
+            .find("commit to constants and sigmas")
+            .is_some());
+
+        let mut pw = PartialWitness::new();
+        pw.set_target(x, F::from_canonical_u64(3))?;
+        let mut timing = TimingTree::new("prove", Level::Debug);
+        let proof = prove::<F, C, D>(&data.prover_only, &data.common, pw, &mut timing)?;
+        timing.pop();
+
+        let quotient = timing.find("compute quotient polys").unwrap();
+        assert_eq!(quotient.name(), "compute quotient polys");
+        assert!(quotient.duration() <= timing.duration());
+        let fraction = timing.fraction("compute quotient polys");
+        assert!(fraction > 0.0 && fraction <= 1.0);
+        assert_eq!(timing.fraction("no such scope"), 0.0);
+        assert!(timing
+            .children()
+            .iter()
+            .any(|child| child.name() == "compute quotient polys"));
+
+        data.verify(proof)
+    }
+}
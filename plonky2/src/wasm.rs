@@ -0,0 +1,151 @@
+//! `wasm-bindgen` bindings for proving and verifying from JavaScript, enabled by the `wasm`
+//! feature.
+//!
+//! Circuits are built natively and loaded from their serialized form, with the default gate and
+//! generator serializers. The bindings are fixed to `PoseidonGoldilocksConfig` with `D = 2`. Build
+//! without the `parallel` feature for a single-threaded prover, which doesn't need Web Workers.
+
+#[cfg(not(feature = "std"))]
+use alloc::{format, vec::Vec};
+
+use wasm_bindgen::prelude::*;
+
+use crate::field::types::{Field, Field64, PrimeField64};
+use crate::iop::target::Target;
+use crate::iop::wire::Wire;
+use crate::iop::witness::{PartialWitness, WitnessWrite};
+use crate::plonk::circuit_data::{ProverCircuitData, VerifierCircuitData};
+use crate::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use crate::plonk::proof::ProofWithPublicInputs;
+use crate::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+fn field_element(value: u64) -> Result<F, JsError> {
+    if value >= F::ORDER {
+        return Err(JsError::new(&format!(
+            "{value} is not a canonical field element"
+        )));
+    }
+    Ok(F::from_canonical_u64(value))
+}
+
+/// The inputs to a proof, i.e. a `PartialWitness`.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct WasmWitness {
+    inner: PartialWitness<F>,
+}
+
+#[wasm_bindgen]
+impl WasmWitness {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the wire at the given row and column.
+    #[wasm_bindgen(js_name = setWire)]
+    pub fn set_wire(&mut self, row: usize, column: usize, value: u64) -> Result<(), JsError> {
+        self.set_target(Target::Wire(Wire { row, column }), value)
+    }
+
+    /// Sets the virtual target with the given index.
+    #[wasm_bindgen(js_name = setVirtualTarget)]
+    pub fn set_virtual_target(&mut self, index: usize, value: u64) -> Result<(), JsError> {
+        self.set_target(Target::VirtualTarget { index }, value)
+    }
+
+    fn set_target(&mut self, target: Target, value: u64) -> Result<(), JsError> {
+        self.inner
+            .set_target(target, field_element(value)?)
+            .map_err(|e| JsError::new(&format!("{e}")))
+    }
+}
+
+/// A circuit loaded from `ProverCircuitData::to_bytes`.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct WasmProver {
+    data: ProverCircuitData<F, C, D>,
+}
+
+#[wasm_bindgen]
+impl WasmProver {
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmProver, JsError> {
+        let data = ProverCircuitData::from_bytes(
+            bytes,
+            &DefaultGateSerializer,
+            &DefaultGeneratorSerializer::<C, D>::default(),
+        )
+        .map_err(|_| JsError::new("Invalid prover circuit data"))?;
+        Ok(Self { data })
+    }
+
+    /// Sets the public input with the given index, i.e. the target registered in that position.
+    #[wasm_bindgen(js_name = setPublicInput)]
+    pub fn set_public_input(
+        &self,
+        witness: &mut WasmWitness,
+        index: usize,
+        value: u64,
+    ) -> Result<(), JsError> {
+        let target = *self
+            .data
+            .prover_only
+            .public_inputs
+            .get(index)
+            .ok_or_else(|| JsError::new(&format!("No public input with index {index}")))?;
+        witness.set_target(target, value)
+    }
+
+    /// Proves the circuit, returning the proof as written by `ProofWithPublicInputs::to_bytes`.
+    pub fn prove(&self, witness: &WasmWitness) -> Result<Vec<u8>, JsError> {
+        let proof = self
+            .data
+            .prove(witness.inner.clone())
+            .map_err(|e| JsError::new(&format!("{e}")))?;
+        Ok(proof.to_bytes())
+    }
+}
+
+/// A circuit loaded from `VerifierCircuitData::to_bytes`.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct WasmVerifier {
+    data: VerifierCircuitData<F, C, D>,
+}
+
+#[wasm_bindgen]
+impl WasmVerifier {
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmVerifier, JsError> {
+        let data = VerifierCircuitData::from_bytes(bytes.to_vec(), &DefaultGateSerializer)
+            .map_err(|_| JsError::new("Invalid verifier circuit data"))?;
+        Ok(Self { data })
+    }
+
+    /// Verifies a proof written by `ProofWithPublicInputs::to_bytes`.
+    pub fn verify(&self, proof: &[u8]) -> Result<(), JsError> {
+        let proof = ProofWithPublicInputs::from_bytes(proof.to_vec(), &self.data.common)
+            .map_err(|e| JsError::new(&format!("{e}")))?;
+        self.data
+            .verify(proof)
+            .map_err(|e| JsError::new(&format!("{e}")))
+    }
+
+    /// The public inputs of a proof, as canonical `u64`s.
+    #[wasm_bindgen(js_name = publicInputs)]
+    pub fn public_inputs(&self, proof: &[u8]) -> Result<Vec<u64>, JsError> {
+        let proof = ProofWithPublicInputs::<F, C, D>::from_bytes(proof.to_vec(), &self.data.common)
+            .map_err(|e| JsError::new(&format!("{e}")))?;
+        Ok(proof
+            .public_inputs
+            .iter()
+            .map(|x| x.to_canonical_u64())
+            .collect())
+    }
+}
@@ -0,0 +1,67 @@
+//! Writes the test data in `plonky2/testdata/upstream` with upstream plonky2 1.0.0: a proof, in
+//! full and compressed, of a circuit with the standard recursion config which hashes its two
+//! public inputs, 3 and 15, with Poseidon, and that circuit's verifier and common data.
+//!
+//! Run with `cargo run --release` from this directory. The files are written to its parent, or to
+//! the directory given as an argument.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use plonky2::field::types::Field;
+use plonky2::hash::poseidon::PoseidonHash;
+use plonky2::iop::witness::{PartialWitness, WitnessWrite};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::CircuitConfig;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::util::serialization::DefaultGateSerializer;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+fn main() -> Result<()> {
+    let out_dir = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(".."));
+
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let inputs = builder.add_virtual_targets(2);
+    builder.register_public_inputs(&inputs);
+    let hash = builder.hash_n_to_hash_no_pad::<PoseidonHash>(inputs.clone());
+    builder.register_public_inputs(&hash.elements);
+    let data = builder.build::<C>();
+
+    let mut pw = PartialWitness::new();
+    pw.set_target(inputs[0], F::from_canonical_u64(3))?;
+    pw.set_target(inputs[1], F::from_canonical_u64(15))?;
+    let proof = data.prove(pw)?;
+    data.verify(proof.clone())?;
+    let compressed_proof = data.compress(proof.clone())?;
+
+    let files = [
+        (
+            "common_circuit_data.bin",
+            data.common
+                .to_bytes(&DefaultGateSerializer)
+                .map_err(anyhow::Error::msg)?,
+        ),
+        (
+            "verifier_only_circuit_data.bin",
+            data.verifier_only.to_bytes().map_err(anyhow::Error::msg)?,
+        ),
+        ("proof_with_public_inputs.bin", proof.to_bytes()),
+        (
+            "compressed_proof_with_public_inputs.bin",
+            compressed_proof.to_bytes(),
+        ),
+    ];
+    for (name, bytes) in files {
+        let path = out_dir.join(name);
+        fs::write(&path, bytes)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Proves and verifies through the `wasm-bindgen` bindings. Run with
+//! `cargo test --target wasm32-unknown-unknown --no-default-features --features wasm --test wasm`,
+//! with `wasm-bindgen-test-runner` set as the runner for that target.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::CircuitConfig;
+use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+use plonky2::util::serialization::{DefaultGateSerializer, DefaultGeneratorSerializer};
+use plonky2::wasm::{WasmProver, WasmVerifier, WasmWitness};
+use wasm_bindgen::JsError;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const D: usize = 2;
+type C = PoseidonGoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// `JsError` doesn't implement `Debug`, so results holding one can't be unwrapped.
+fn ok<T>(result: Result<T, JsError>) -> T {
+    result.unwrap_or_else(|_| panic!("Unexpected JsError"))
+}
+
+/// Builds a circuit proving knowledge of `x` such that `x^3 = y`, with `y` public, and returns
+/// its serialized prover and verifier data.
+fn cube_circuit() -> (Vec<u8>, Vec<u8>) {
+    let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+    let x = builder.add_virtual_public_input();
+    let y = builder.cube(x);
+    builder.register_public_input(y);
+    let data = builder.build::<C>();
+
+    let verifier_bytes = data
+        .verifier_data()
+        .to_bytes(&DefaultGateSerializer)
+        .unwrap();
+    let prover_bytes = data
+        .prover_data()
+        .to_bytes(
+            &DefaultGateSerializer,
+            &DefaultGeneratorSerializer::<C, D>::default(),
+        )
+        .unwrap();
+    (prover_bytes, verifier_bytes)
+}
+
+#[wasm_bindgen_test]
+fn prove_and_verify() {
+    let (prover_bytes, verifier_bytes) = cube_circuit();
+    let prover = ok(WasmProver::new(&prover_bytes));
+    let verifier = ok(WasmVerifier::new(&verifier_bytes));
+
+    let mut witness = WasmWitness::new();
+    ok(prover.set_public_input(&mut witness, 0, 3));
+    let proof = ok(prover.prove(&witness));
+    assert!(verifier.verify(&proof).is_ok());
+    assert_eq!(ok(verifier.public_inputs(&proof)), vec![3, 27]);
+
+    // A proof with a tampered public input is rejected.
+    let mut tampered = proof.clone();
+    let position = tampered.len() - 8;
+    tampered[position] ^= 1;
+    assert!(verifier.verify(&tampered).is_err());
+}
+
+#[wasm_bindgen_test]
+fn invalid_inputs_are_rejected() {
+    let (prover_bytes, verifier_bytes) = cube_circuit();
+    assert!(WasmProver::new(&prover_bytes[1..]).is_err());
+    assert!(WasmVerifier::new(&verifier_bytes[1..]).is_err());
+
+    let prover = ok(WasmProver::new(&prover_bytes));
+    let mut witness = WasmWitness::new();
+    assert!(prover
+        .set_public_input(&mut witness, 0, F::NEG_ONE.to_canonical_u64() + 1)
+        .is_err());
+    assert!(prover.set_public_input(&mut witness, 2, 1).is_err());
+
+    // The output is a public input too, so it can't be set inconsistently with the input.
+    ok(prover.set_public_input(&mut witness, 0, 3));
+    ok(prover.set_public_input(&mut witness, 1, 28));
+    assert!(prover.prove(&witness).is_err());
+}
@@ -11,7 +11,7 @@ use anyhow::{anyhow, Result};
 use plonky2::field::extension::Extendable;
 use plonky2::field::types::Field;
 use plonky2::fri::reduction_strategies::FriReductionStrategy;
-use plonky2::fri::{FriConfig, FriParams};
+use plonky2::fri::{FriConfig, FriParams, FriSecurity};
 use plonky2::hash::hash_types::RichField;
 
 /// A configuration containing the different parameters used by the STARK prover.
@@ -70,20 +70,14 @@ impl StarkConfig {
     pub fn check_config<F: RichField + Extendable<D>, const D: usize>(&self) -> Result<()> {
         let StarkConfig {
             security_bits,
-            fri_config:
-                FriConfig {
-                    rate_bits,
-                    proof_of_work_bits,
-                    num_query_rounds,
-                    ..
-                },
+            fri_config,
             ..
         } = &self;
 
         // Conjectured FRI security; see the ethSTARK paper.
         let fri_field_bits = F::Extension::order().bits() as usize;
-        let fri_query_security_bits = num_query_rounds * rate_bits + *proof_of_work_bits as usize;
-        let fri_security_bits = fri_field_bits.min(fri_query_security_bits);
+        let fri_security_bits =
+            fri_config.query_security_bits(FriSecurity::ConjecturedListDecoding, fri_field_bits);
 
         if fri_security_bits < *security_bits {
             Err(anyhow!(format!(
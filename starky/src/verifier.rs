@@ -68,7 +68,7 @@ where
     C: GenericConfig<D, F = F>,
     S: Stark<F, D>,
 {
-    log::debug!("Checking proof: {}", type_name::<S>());
+    log::debug!(stark = type_name::<S>(); "Checking proof: {}", type_name::<S>());
 
     let (num_ctl_z_polys, num_ctl_polys) = ctl_vars
         .map(|ctls| {